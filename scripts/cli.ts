@@ -1,9 +1,23 @@
 #!/usr/bin/env bun
 
 import { existsSync } from 'fs';
+import { join } from 'node:path';
 import { fileURLToPath } from 'node:url';
+import { ConfigManager } from '../server/config/manager';
+import { IMPORT_SOURCES, importConfigs, type ImportSource } from '../server/config/importers';
+import { createBackup, restoreBackup } from '../server/config/backup';
+import { runBench, type BenchPercentiles } from '../server/bench/bench';
+import { ensureServiceConfigs, SERVICE_HEALTH_PATH, SERVICE_NAMES, type ServiceName } from '../server/core';
+import { stopInstance, waitForPortsReleased } from '../server/monitoring/pidFile';
+import {
+  compareVersions,
+  CURRENT_VERSION,
+  fetchLatestRelease,
+  installRelease,
+  isStandaloneBinary,
+} from '../server/update/selfUpdate';
 
-const [, , rawArg] = process.argv;
+const [, , rawArg, ...commandArgs] = process.argv;
 
 const helpMessage = `Proxy AI Fusion
 
@@ -11,8 +25,40 @@ Usage:
   bunx proxy-ai-fusion [command]
 
 Commands:
-  start   Launch the proxy server (default)
-  help    Show this help message
+  start [--read-only] [--lite] Launch the proxy server (default); --read-only disables config changes,
+                               --lite runs without dashboard, logs to requests.jsonl and caps body buffers
+  stop                         Stop the running server and every process it spawned, then check its ports are free
+  status                       Show whether the server is running and its most recent crash; exits 1 when
+                               a service has no usable config, 3 when the server is not running
+  health [--service <name>]    Probe the local proxy listener of one service (default: all); exits 0 when
+                               it can serve, 1 when it has no usable config, 3 when it does not answer
+  init                         Interactively write the first upstream config for a service
+  add <service>                Interactively add a config; it is saved only if its connectivity test passes
+  lb mode <service> <mode>     Switch load balancer strategy (weighted, round-robin)
+  import --from <tool> <file> [--dry-run]
+                               Add configs from claude-code-router (config.json) or litellm (config.yaml)
+  sync <push|pull> --remote <url> [--dry-run] [--prune]
+                               Sync configs, encrypted with [sync] passphrase, with another paf instance
+                               (http(s)://), WebDAV (dav(s)://) or S3 (s3://bucket/key); --prune also
+                               removes configs the source does not have
+  backup <file> [--include-db] Write configs (and with --include-db the request log database) to a tar.zst
+  restore <file> [--skip-db] [--force]
+                               Restore a backup; stop the server first (--force skips that check)
+  loglevel [<filter>] [--for <seconds>]
+                               Show or change the running server's log filter, e.g. "info,proxy=debug";
+                               --for returns to system.toml's log_level afterwards
+  bench <service> [config] [--requests M] [--concurrency N] [--streaming] [--model <name>]
+                               Measure proxy throughput, latency and memory against a built-in mock
+                               upstream, or against a configured upstream (real, billed requests)
+  hook                         Forward a Claude Code hook's input (stdin) to the server, which tags that
+                               session's requests and answers with provider switch / low balance notices
+  self-update [--check]        Replace the standalone binary with the latest verified release;
+                               --check only reports whether an update is available
+  help                         Show this help message
+
+Management commands talk to the running server; set PAF_API_URL to override
+the address read from ~/.paf/system.toml. sync and loglevel authenticate with
+PAF_ADMIN_TOKEN locally; sync uses PAF_REMOTE_ADMIN_TOKEN on a remote paf instance.
 `;
 
 const startServer = async (): Promise<void> => {
@@ -29,12 +75,704 @@ const startServer = async (): Promise<void> => {
   await import(sourceEntry.href);
 };
 
+const resolveApiBase = async (): Promise<string> => {
+  if (process.env.PAF_API_URL) {
+    return process.env.PAF_API_URL.replace(/\/+$/, '');
+  }
+
+  const configManager = new ConfigManager();
+  await configManager.initialize();
+  return `http://localhost:${configManager.getSystemConfig().webPort}`;
+};
+
+// Exit codes of status and health, following the LSB status convention for "not running"
+const EXIT_DEGRADED = 1;
+const EXIT_NOT_RUNNING = 3;
+
+const callApi = async (path: string, init?: RequestInit, unreachableExitCode = 1): Promise<any> => {
+  const base = await resolveApiBase();
+
+  let response: Response;
+  try {
+    response = await fetch(`${base}${path}`, {
+      ...init,
+      headers: {
+        'Content-Type': 'application/json',
+        ...init?.headers,
+      },
+    });
+  } catch {
+    console.error(`Could not reach Proxy AI Fusion at ${base}. Is the server running?`);
+    process.exit(unreachableExitCode);
+  }
+
+  const payload = await response.json().catch(() => ({}));
+  if (!response.ok) {
+    console.error(payload?.error || `Request failed with HTTP ${response.status}`);
+    process.exit(1);
+  }
+
+  return payload;
+};
+
+const runStatusCommand = async (): Promise<void> => {
+  const status = await callApi('/api/status', undefined, EXIT_NOT_RUNNING);
+  const minutes = Math.floor(status.uptime / 60);
+  console.log(`Running for ${minutes >= 60 ? `${Math.floor(minutes / 60)}h ${minutes % 60}m` : `${minutes}m`}${status.readOnly ? ' (read-only)' : ''}`);
+  for (const [service, health] of Object.entries<any>(status.services ?? {})) {
+    console.log(
+      health.state === 'ok'
+        ? `${service}: ok (${health.usable.length} of ${health.configs} configs usable)`
+        : `${service}: degraded, ${health.configs === 0 ? 'no configs' : `none of ${health.configs} configs usable`}`
+    );
+  }
+  if (status.status === 'degraded') {
+    process.exitCode = EXIT_DEGRADED;
+  }
+
+  const crash = status.lastCrash;
+  if (!crash) {
+    console.log('No crashes recorded');
+    return;
+  }
+  const kind = crash.kind === 'uncaught_exception' ? 'Uncaught exception' : 'Unhandled rejection';
+  console.log(`\nLast crash: ${kind} at ${new Date(crash.timestamp).toLocaleString()}${crash.fatal ? ' (server exited)' : ''}`);
+  if (crash.context) {
+    console.log(`  while handling ${crash.context}`);
+  }
+  console.log(`  ${crash.message}`);
+  if (crash.stack) {
+    console.log(crash.stack.split('\n').slice(1, 6).map((line: string) => `  ${line.trim()}`).join('\n'));
+  }
+};
+
+const runStopCommand = async (): Promise<void> => {
+  const configManager = new ConfigManager();
+  await configManager.initialize();
+  const systemConfig = configManager.getSystemConfig();
+
+  const result = await stopInstance(join(configManager.getConfigDir(), 'paf.pid'));
+  if (result.state === 'not_running') {
+    console.log('Proxy AI Fusion is not running');
+    return;
+  }
+
+  const ports = [systemConfig.webPort];
+  if (!systemConfig.singlePort) {
+    ports.push(systemConfig.proxyPorts.claude, systemConfig.proxyPorts.codex);
+  }
+  for (const tenant of systemConfig.tenants) {
+    ports.push(...Object.values(tenant.proxyPorts ?? {}).filter((port): port is number => !!port));
+  }
+  const busy = await waitForPortsReleased(ports);
+  if (busy.length > 0) {
+    console.error(`Stopped pid ${result.pid}, but port${busy.length === 1 ? '' : 's'} ${busy.join(', ')} ${busy.length === 1 ? 'is' : 'are'} still in use`);
+    process.exit(1);
+  }
+  console.log(`Stopped Proxy AI Fusion (pid ${result.pid}${result.group ? ' and its process group' : ''}); ports ${ports.join(', ')} are free`);
+};
+
+const runHealthCommand = async (args: string[]): Promise<void> => {
+  const serviceIndex = args.indexOf('--service');
+  const service = serviceIndex >= 0 ? args[serviceIndex + 1] : undefined;
+  if (serviceIndex >= 0 && !SERVICE_NAMES.includes(service as ServiceName)) {
+    console.error(`Usage: bunx proxy-ai-fusion health [--service <${SERVICE_NAMES.join('|')}>]`);
+    process.exit(1);
+  }
+
+  const configManager = new ConfigManager();
+  await configManager.initialize();
+  const systemConfig = configManager.getSystemConfig();
+
+  let exitCode = 0;
+  for (const name of service ? [service as ServiceName] : SERVICE_NAMES) {
+    const url = systemConfig.singlePort
+      ? `http://localhost:${systemConfig.webPort}/${name}${SERVICE_HEALTH_PATH}`
+      : `${systemConfig.proxyTls ? 'https' : 'http'}://localhost:${systemConfig.proxyPorts[name]}${SERVICE_HEALTH_PATH}`;
+
+    let response: Response;
+    try {
+      // The listener's certificate is usually issued for a public name, not localhost
+      response = await fetch(url, { signal: AbortSignal.timeout(5000), tls: { rejectUnauthorized: false } });
+    } catch {
+      console.error(`${name}: no answer at ${url}`);
+      exitCode = EXIT_NOT_RUNNING;
+      continue;
+    }
+
+    const health = await response.json().catch(() => null);
+    if (!health?.state) {
+      console.error(`${name}: unexpected HTTP ${response.status} from ${url}`);
+      exitCode = EXIT_NOT_RUNNING;
+    } else if (health.state === 'ok') {
+      console.log(`${name}: ok (${health.usable.join(', ')})`);
+    } else {
+      console.error(`${name}: degraded, ${health.configs === 0 ? 'no configs' : `none of ${health.configs} configs usable`}`);
+      exitCode = Math.max(exitCode, EXIT_DEGRADED);
+    }
+  }
+  process.exit(exitCode);
+};
+
+const runLoadBalancerCommand = async (args: string[]): Promise<void> => {
+  const [subcommand, service, mode] = args;
+
+  if (subcommand !== 'mode' || !service || !mode) {
+    console.error('Usage: bunx proxy-ai-fusion lb mode <service> <mode>');
+    process.exit(1);
+  }
+
+  await callApi(`/api/loadbalancer/${encodeURIComponent(service)}/mode`, {
+    method: 'POST',
+    body: JSON.stringify({ mode }),
+  });
+  console.log(`Load balancer strategy for ${service} set to ${mode}`);
+};
+
+const runImportCommand = async (args: string[]): Promise<void> => {
+  const fromIndex = args.indexOf('--from');
+  const source = fromIndex >= 0 ? args[fromIndex + 1] : undefined;
+  const file = args.find((arg, index) => !arg.startsWith('--') && index !== fromIndex + 1);
+  const dryRun = args.includes('--dry-run');
+
+  if (!source || !IMPORT_SOURCES.includes(source as ImportSource) || !file) {
+    console.error(`Usage: bunx proxy-ai-fusion import --from <${IMPORT_SOURCES.join('|')}> <file> [--dry-run]`);
+    process.exit(1);
+  }
+  if (!existsSync(file)) {
+    console.error(`File not found: ${file}`);
+    process.exit(1);
+  }
+
+  let result;
+  try {
+    result = importConfigs(source as ImportSource, await Bun.file(file).text());
+  } catch (error) {
+    console.error(`Could not parse ${file}: ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  }
+
+  for (const warning of result.warnings) {
+    console.warn(`warning: ${warning}`);
+  }
+
+  let imported = 0;
+  for (const { service, config } of result.configs) {
+    const summary = `${service}/${config.name} -> ${config.baseUrl}${config.apiKey ? '' : ' (no key)'}`;
+    if (dryRun) {
+      console.log(`would add ${summary}`);
+      continue;
+    }
+
+    const existing = await callApi(`/api/configs?service=${service}`);
+    if ((existing.configs ?? []).some((c: { name: string }) => c.name === config.name)) {
+      console.log(`skip ${summary} (name already exists)`);
+      continue;
+    }
+
+    await callApi(`/api/configs?service=${service}`, {
+      method: 'POST',
+      body: JSON.stringify({
+        name: config.name,
+        base_url: config.baseUrl,
+        api_key: config.apiKey,
+        weight: config.weight,
+        enabled: config.enabled,
+      }),
+    });
+    console.log(`added ${summary}`);
+    imported++;
+  }
+
+  if (!dryRun) {
+    console.log(`Imported ${imported} of ${result.configs.length} configs from ${source}`);
+  }
+};
+
+const OFFICIAL_BASE_URLS: Record<string, string> = {
+  claude: 'https://api.anthropic.com',
+  codex: 'https://api.openai.com',
+};
+
+const ask = (question: string, fallback = ''): string => {
+  const answer = prompt(fallback ? `${question} [${fallback}]:` : `${question}:`);
+  return answer?.trim() || fallback;
+};
+
+const runInitCommand = async (): Promise<void> => {
+  const service = ask(`Service (${SERVICE_NAMES.join('/')})`, 'claude');
+  if (!SERVICE_NAMES.includes(service as ServiceName)) {
+    console.error(`Unknown service: ${service}`);
+    process.exit(1);
+  }
+
+  // Works offline: configs are written straight to ~/.paf, creating the commented templates first
+  const configManager = new ConfigManager(undefined, false);
+  await configManager.initialize();
+  await ensureServiceConfigs(configManager);
+  const serviceConfig = configManager.getServiceConfig(service)!;
+
+  const name = ask('Config name', serviceConfig.configs.length === 0 ? 'official' : '');
+  if (!name) {
+    console.error('A config name is required');
+    process.exit(1);
+  }
+  if (serviceConfig.configs.some(c => c.name === name)) {
+    console.error(`${service} already has a config named "${name}"`);
+    process.exit(1);
+  }
+
+  const baseUrl = ask('Base URL', OFFICIAL_BASE_URLS[service]).replace(/\/+$/, '');
+  try {
+    new URL(baseUrl);
+  } catch {
+    console.error(`Invalid base URL: ${baseUrl}`);
+    process.exit(1);
+  }
+  const apiKey = ask('API key (leave empty for a local backend without auth)');
+
+  serviceConfig.configs.push({ name, baseUrl, apiKey: apiKey || undefined, weight: 1, enabled: true });
+  if (!serviceConfig.active) {
+    serviceConfig.active = name;
+  }
+  await configManager.saveServiceConfig(service, serviceConfig);
+
+  console.log(`Added ${service}/${name} -> ${baseUrl}${apiKey ? '' : ' (no key)'}`);
+  console.log('Start the proxy with `bunx proxy-ai-fusion start` (restart it if it is already running).');
+};
+
+// OAuth access tokens (e.g. from `claude setup-token`) are sent as bearer tokens; paf does not refresh them
+const CREDENTIAL_TYPES = ['api_key', 'token', 'oauth'] as const;
+
+const runAddCommand = async (args: string[]): Promise<void> => {
+  const [service] = args;
+  if (!service || !SERVICE_NAMES.includes(service as ServiceName)) {
+    console.error(`Usage: bunx proxy-ai-fusion add <${SERVICE_NAMES.join('|')}>`);
+    process.exit(1);
+  }
+
+  const existing = await callApi(`/api/configs?service=${service}`);
+  const name = ask('Config name');
+  if (!name) {
+    console.error('A config name is required');
+    process.exit(1);
+  }
+  if ((existing.configs ?? []).some((c: { name: string }) => c.name === name)) {
+    console.error(`${service} already has a config named "${name}"`);
+    process.exit(1);
+  }
+
+  const baseUrl = ask('Base URL', OFFICIAL_BASE_URLS[service]).replace(/\/+$/, '');
+  try {
+    new URL(baseUrl);
+  } catch {
+    console.error(`Invalid base URL: ${baseUrl}`);
+    process.exit(1);
+  }
+
+  const credentialTypes = service === 'claude' ? CREDENTIAL_TYPES : CREDENTIAL_TYPES.filter(type => type !== 'oauth');
+  const credentialType = ask(`Credential type (${credentialTypes.join('/')})`, 'api_key');
+  if (!credentialTypes.includes(credentialType as (typeof CREDENTIAL_TYPES)[number])) {
+    console.error(`Unknown credential type: ${credentialType}`);
+    process.exit(1);
+  }
+  const secret = ask(credentialType === 'oauth' ? 'OAuth access token' : credentialType === 'token' ? 'Auth token' : 'API key');
+
+  const weight = Number(ask('Weight', '1'));
+  if (!Number.isFinite(weight) || weight <= 0) {
+    console.error('Weight must be a positive number');
+    process.exit(1);
+  }
+
+  console.log(`Testing ${baseUrl}...`);
+  const result = await callApi(`/api/configs?service=${service}&verify=true`, {
+    method: 'POST',
+    body: JSON.stringify({
+      name,
+      base_url: baseUrl,
+      api_key: credentialType === 'api_key' ? secret || undefined : undefined,
+      auth_token: credentialType === 'api_key' ? undefined : secret || undefined,
+      weight,
+    }),
+  });
+
+  const test = result.test;
+  console.log(`Connectivity test passed (HTTP ${test?.status_code}, ${test?.duration_ms} ms)`);
+  console.log(`Added ${service}/${name} -> ${baseUrl}`);
+};
+
+const bearer = (token: string | undefined): Record<string, string> =>
+  token ? { Authorization: `Bearer ${token}` } : {};
+
+const davUrl = (remote: URL): string =>
+  `${remote.protocol === 'davs:' ? 'https' : 'http'}://${remote.host}${remote.pathname}`;
+
+const davAuth = (remote: URL): Record<string, string> =>
+  remote.username
+    ? { Authorization: `Basic ${btoa(`${decodeURIComponent(remote.username)}:${decodeURIComponent(remote.password)}`)}` }
+    : {};
+
+// Remotes hold the encrypted bundle as is; only paf servers (which know the passphrase) read it
+const readRemoteBundle = async (remote: URL): Promise<string> => {
+  if (remote.protocol === 's3:') {
+    return Bun.s3.file(remote.pathname.replace(/^\//, ''), { bucket: remote.hostname }).text();
+  }
+
+  const isPaf = remote.protocol === 'http:' || remote.protocol === 'https:';
+  const response = await fetch(isPaf ? `${remote.origin}/api/sync/bundle` : davUrl(remote), {
+    headers: isPaf ? bearer(process.env.PAF_REMOTE_ADMIN_TOKEN) : davAuth(remote),
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status} from ${remote.host}`);
+  }
+  return response.text();
+};
+
+const writeRemoteBundle = async (remote: URL, bundle: string): Promise<void> => {
+  if (remote.protocol === 's3:') {
+    await Bun.s3.file(remote.pathname.replace(/^\//, ''), { bucket: remote.hostname }).write(bundle);
+    return;
+  }
+
+  const response = await fetch(davUrl(remote), { method: 'PUT', headers: davAuth(remote), body: bundle });
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status} from ${remote.host}`);
+  }
+};
+
+const printSyncChanges = (changes: Array<{ service: string; config: string; kind: string; fields?: string[] }>, dryRun: boolean) => {
+  for (const change of changes) {
+    const fields = change.fields ? ` (${change.fields.join(', ')})` : '';
+    console.log(`${dryRun ? 'would be ' : ''}${change.kind} ${change.service}/${change.config}${fields}`);
+  }
+  console.log(changes.length === 0 ? 'Already in sync' : `${changes.length} change(s)${dryRun ? ' (dry run)' : ''}`);
+};
+
+const runSyncCommand = async (args: string[]): Promise<void> => {
+  const [direction] = args;
+  const remoteIndex = args.indexOf('--remote');
+  const remoteArg = remoteIndex >= 0 ? args[remoteIndex + 1] : undefined;
+  const dryRun = args.includes('--dry-run');
+  const prune = args.includes('--prune');
+
+  let remote: URL | undefined;
+  try {
+    remote = remoteArg ? new URL(remoteArg) : undefined;
+  } catch {
+    remote = undefined;
+  }
+  if (
+    (direction !== 'push' && direction !== 'pull') ||
+    !remote ||
+    !['http:', 'https:', 'dav:', 'davs:', 's3:'].includes(remote.protocol)
+  ) {
+    console.error('Usage: bunx proxy-ai-fusion sync <push|pull> --remote <url> [--dry-run] [--prune]');
+    console.error('  <url>: http(s)://<paf web ui>, dav(s)://[user:pass@]host/path/file, s3://bucket/key');
+    process.exit(1);
+  }
+
+  const localAuth = bearer(process.env.PAF_ADMIN_TOKEN);
+  const query = `dry_run=${dryRun}&prune=${prune}`;
+
+  try {
+    if (direction === 'pull') {
+      const bundle = await readRemoteBundle(remote);
+      const result = await callApi(`/api/sync/bundle?${query}`, { method: 'POST', headers: localAuth, body: bundle });
+      printSyncChanges(result.changes ?? [], dryRun);
+      return;
+    }
+
+    const bundle = JSON.stringify(await callApi('/api/sync/bundle', { headers: localAuth }));
+    if (remote.protocol === 'http:' || remote.protocol === 'https:') {
+      const response = await fetch(`${remote.origin}/api/sync/bundle?${query}`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json', ...bearer(process.env.PAF_REMOTE_ADMIN_TOKEN) },
+        body: bundle,
+      });
+      const result: any = await response.json().catch(() => ({}));
+      if (!response.ok) {
+        throw new Error(result?.error || `HTTP ${response.status} from ${remote.host}`);
+      }
+      printSyncChanges(result.changes ?? [], dryRun);
+      return;
+    }
+
+    // Storage remotes cannot diff; the machine pulling from them sees the changes
+    if (dryRun) {
+      console.log(`Would upload the config bundle to ${remoteArg}`);
+      return;
+    }
+    await writeRemoteBundle(remote, bundle);
+    console.log(`Uploaded the config bundle to ${remoteArg}`);
+  } catch (error) {
+    console.error(`Sync failed: ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  }
+};
+
+const runBackupCommand = async (args: string[]): Promise<void> => {
+  const file = args.find(arg => !arg.startsWith('--'));
+  if (!file) {
+    console.error('Usage: bunx proxy-ai-fusion backup <file.tar.zst> [--include-db]');
+    process.exit(1);
+  }
+
+  const configManager = new ConfigManager();
+  await configManager.initialize();
+  const { archive, manifest } = await createBackup(configManager, { includeDatabase: args.includes('--include-db') });
+  await Bun.write(file, archive);
+
+  for (const name of manifest.files) {
+    console.log(`  ${name}`);
+  }
+  console.log(`Wrote ${manifest.files.length} files to ${file} (${archive.length} bytes)`);
+};
+
+const runRestoreCommand = async (args: string[]): Promise<void> => {
+  const file = args.find(arg => !arg.startsWith('--'));
+  if (!file) {
+    console.error('Usage: bunx proxy-ai-fusion restore <file.tar.zst> [--skip-db] [--force]');
+    process.exit(1);
+  }
+  if (!existsSync(file)) {
+    console.error(`File not found: ${file}`);
+    process.exit(1);
+  }
+
+  const configManager = new ConfigManager();
+  await configManager.initialize();
+
+  // A running server would keep serving (and later save) its in-memory configs over the restored ones
+  if (!args.includes('--force')) {
+    const base = await resolveApiBase();
+    const running = await fetch(`${base}/api/status`, { signal: AbortSignal.timeout(2000) }).then(
+      () => true,
+      () => false
+    );
+    if (running) {
+      console.error(`Proxy AI Fusion is running at ${base}; stop it before restoring (or pass --force)`);
+      process.exit(1);
+    }
+  }
+
+  try {
+    const { manifest, restored } = await restoreBackup(
+      configManager.getConfigDir(),
+      await Bun.file(file).bytes(),
+      { includeDatabase: !args.includes('--skip-db') }
+    );
+    for (const name of restored) {
+      console.log(`  ${name}`);
+    }
+    console.log(`Restored ${restored.length} files from a backup taken ${new Date(manifest.createdAt).toISOString()}`);
+  } catch (error) {
+    console.error(`Restore failed: ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  }
+};
+
+const runLogLevelCommand = async (args: string[]): Promise<void> => {
+  const forIndex = args.indexOf('--for');
+  const ttlSeconds = forIndex >= 0 ? Number(args[forIndex + 1]) : undefined;
+  const filter = args.find((arg, index) => !arg.startsWith('--') && index !== forIndex + 1);
+
+  if (ttlSeconds !== undefined && (!Number.isFinite(ttlSeconds) || ttlSeconds <= 0 || !filter)) {
+    console.error('Usage: bunx proxy-ai-fusion loglevel [<filter>] [--for <seconds>]');
+    process.exit(1);
+  }
+
+  const result = await callApi('/api/admin/loglevel', {
+    method: filter ? 'PUT' : 'GET',
+    headers: bearer(process.env.PAF_ADMIN_TOKEN),
+    body: filter ? JSON.stringify({ filter, ttl_seconds: ttlSeconds }) : undefined,
+  });
+  const until = result.temporary ? ` (temporary; returns to ${result.configured})` : '';
+  console.log(`Log filter: ${result.filter}${until}`);
+};
+
+// Never fails the hook: a stopped or slow server must not get in Claude Code's way
+const HOOK_TIMEOUT_MS = 2000;
+
+/**
+ * For Claude Code's settings.json, e.g.
+ *   "hooks": { "UserPromptSubmit": [{ "hooks": [{ "type": "command", "command": "bunx proxy-ai-fusion hook" }] }] }
+ * (likewise SessionStart and SessionEnd)
+ */
+const runHookCommand = async (): Promise<void> => {
+  try {
+    const input = await Bun.stdin.text();
+    const response = await fetch(`${await resolveApiBase()}/api/hooks/claude-code`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: input || '{}',
+      signal: AbortSignal.timeout(HOOK_TIMEOUT_MS),
+    });
+    if (response.ok) {
+      console.log(JSON.stringify(await response.json()));
+    }
+  } catch {
+    // Server not running or not answering: nothing to report
+  }
+};
+
+const optionValue = (args: string[], flag: string): string | undefined => {
+  const index = args.indexOf(flag);
+  return index >= 0 ? args[index + 1] : undefined;
+};
+
+const formatPercentiles = (values: BenchPercentiles): string =>
+  (['p50', 'p90', 'p99', 'max'] as const)
+    .map(key => `${key} ${values[key] === null ? '-' : `${values[key]!.toFixed(1)}ms`}`)
+    .join('  ');
+
+const runBenchCommand = async (args: string[]): Promise<void> => {
+  const valueFlags = ['--requests', '--concurrency', '--model'];
+  const positional = args.filter((arg, index) => !arg.startsWith('--') && !valueFlags.includes(args[index - 1]));
+  const [service, configName] = positional;
+  const requests = Number(optionValue(args, '--requests') ?? 200);
+  const concurrency = Number(optionValue(args, '--concurrency') ?? 10);
+
+  if (
+    !SERVICE_NAMES.includes(service as ServiceName) ||
+    !Number.isInteger(requests) || requests <= 0 ||
+    !Number.isInteger(concurrency) || concurrency <= 0
+  ) {
+    console.error(
+      'Usage: bunx proxy-ai-fusion bench <claude|codex> [config] [--requests M] [--concurrency N] [--streaming] [--model <name>]'
+    );
+    process.exit(1);
+  }
+
+  let upstream: Parameters<typeof runBench>[0]['upstream'];
+  if (configName) {
+    const configManager = new ConfigManager();
+    await configManager.initialize();
+    await ensureServiceConfigs(configManager);
+    const serviceConfig = configManager.getServiceConfig(service)!;
+    const config = serviceConfig.configs.find(candidate => candidate.name === configName);
+    if (!config) {
+      console.error(`No ${service} config named ${configName}`);
+      process.exit(1);
+    }
+    const answer = ask(`This sends ${requests} real requests to ${config.baseUrl}. Continue? [y/N] `);
+    if (answer.trim().toLowerCase() !== 'y') {
+      return;
+    }
+    upstream = { config, service: serviceConfig };
+  }
+
+  const streaming = args.includes('--streaming');
+  console.log(`Running ${requests} ${streaming ? 'streaming ' : ''}${service} requests, ${concurrency} at a time...`);
+  const report = await runBench({
+    service: service as ServiceName,
+    requests,
+    concurrency,
+    streaming,
+    upstream,
+    model: optionValue(args, '--model'),
+  });
+
+  const mb = (bytes: number) => `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+  console.log(`\nUpstream:    ${report.upstream}`);
+  console.log(`Requests:    ${report.succeeded} ok, ${report.failed} failed (${Object.entries(report.statuses).map(([status, count]) => `${status}: ${count}`).join(', ')})`);
+  console.log(`Duration:    ${(report.durationMs / 1000).toFixed(2)}s`);
+  console.log(`Throughput:  ${report.throughputRps.toFixed(1)} req/s`);
+  console.log(`Latency:     ${formatPercentiles(report.latencyMs)}`);
+  if (report.firstByteMs) {
+    console.log(`First byte:  ${formatPercentiles(report.firstByteMs)}`);
+  }
+  console.log(`Memory:      rss ${mb(report.memory.rssStartBytes)} -> peak ${mb(report.memory.rssPeakBytes)}, heap peak ${mb(report.memory.heapUsedPeakBytes)}`);
+};
+
+const runSelfUpdateCommand = async (args: string[]): Promise<void> => {
+  let release;
+  try {
+    release = await fetchLatestRelease();
+  } catch (error) {
+    console.error(`Could not check for updates: ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  }
+
+  if (compareVersions(release.version, CURRENT_VERSION) <= 0) {
+    console.log(`proxy-ai-fusion ${CURRENT_VERSION} is up to date`);
+    return;
+  }
+  console.log(`Update available: ${CURRENT_VERSION} -> ${release.version} (${release.url})`);
+  if (args.includes('--check')) {
+    return;
+  }
+
+  // Package installs are updated by their package manager; bunx already runs the latest release
+  if (!isStandaloneBinary()) {
+    console.log('This copy runs from the npm package; update it with `bun add -g proxy-ai-fusion@latest`.');
+    return;
+  }
+
+  try {
+    await installRelease(release);
+  } catch (error) {
+    console.error(`Update failed, nothing was changed: ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  }
+  console.log(`Updated ${process.execPath} to ${release.version} (the previous binary is kept as ${process.execPath}.old); restart running servers to use it.`);
+};
+
 const normalized = (rawArg ?? 'start').toLowerCase();
 
 switch (normalized) {
   case 'start':
+  case '--read-only':
+  case '--lite':
+    if (normalized === '--read-only' || commandArgs.includes('--read-only')) {
+      process.env.PAF_READ_ONLY = 'true';
+    }
+    if (normalized === '--lite' || commandArgs.includes('--lite')) {
+      process.env.PAF_LITE = 'true';
+    }
     await startServer();
     break;
+  case 'stop':
+    await runStopCommand();
+    break;
+  case 'status':
+    await runStatusCommand();
+    break;
+  case 'health':
+    await runHealthCommand(commandArgs);
+    break;
+  case 'init':
+    await runInitCommand();
+    break;
+  case 'add':
+    await runAddCommand(commandArgs);
+    break;
+  case 'lb':
+    await runLoadBalancerCommand(commandArgs);
+    break;
+  case 'import':
+    await runImportCommand(commandArgs);
+    break;
+  case 'sync':
+    await runSyncCommand(commandArgs);
+    break;
+  case 'backup':
+    await runBackupCommand(commandArgs);
+    break;
+  case 'restore':
+    await runRestoreCommand(commandArgs);
+    break;
+  case 'loglevel':
+    await runLogLevelCommand(commandArgs);
+    break;
+  case 'bench':
+    await runBenchCommand(commandArgs);
+    break;
+  case 'hook':
+    await runHookCommand();
+    break;
+  case 'self-update':
+    await runSelfUpdateCommand(commandArgs);
+    break;
   case 'help':
   case '--help':
   case '-h':
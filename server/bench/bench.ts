@@ -0,0 +1,247 @@
+// Benchmark - drives synthetic load through a throwaway proxy core to measure the proxy hot path
+
+import { mkdtempSync, rmSync } from 'fs';
+import { tmpdir } from 'os';
+import { join } from 'path';
+import { ConfigManager } from '../config/manager';
+import { createDefaultServiceConfig } from '../config/defaults';
+import type { ProxyConfig, ServiceConfig } from '../config/types';
+import { createProxyCore, type ServiceName } from '../core';
+import { percentile } from '../experiments/stats';
+import { RequestLogger } from '../logging/logger';
+
+export const MOCK_UPSTREAM_CONFIG = 'bench-mock';
+
+// Text deltas per mock response; enough events to exercise SSE parsing without measuring the mock
+const MOCK_CHUNKS = 24;
+
+export interface BenchOptions {
+  service: ServiceName;
+  requests: number;
+  concurrency: number;
+  streaming: boolean;
+  upstream?: { config: ProxyConfig; service: ServiceConfig }; // Real upstream; the built-in mock when unset
+  model?: string;
+}
+
+export interface BenchReport {
+  upstream: string;
+  requests: number;
+  succeeded: number;
+  failed: number;
+  statuses: Record<string, number>; // HTTP status (or "error") -> count
+  durationMs: number;
+  throughputRps: number;
+  latencyMs: BenchPercentiles;      // Request start until the response body ended
+  firstByteMs?: BenchPercentiles;   // Request start until the first body chunk; streaming only
+  memory: {
+    rssStartBytes: number;
+    rssPeakBytes: number;
+    heapUsedPeakBytes: number;
+  };
+}
+
+export interface BenchPercentiles {
+  p50: number | null;
+  p90: number | null;
+  p99: number | null;
+  max: number | null;
+}
+
+function summarize(values: number[]): BenchPercentiles {
+  return {
+    p50: percentile(values, 0.5),
+    p90: percentile(values, 0.9),
+    p99: percentile(values, 0.99),
+    max: values.length > 0 ? Math.max(...values) : null,
+  };
+}
+
+function requestFor(service: ServiceName, streaming: boolean, model: string): Request {
+  const body =
+    service === 'claude'
+      ? { model, max_tokens: 64, stream: streaming, messages: [{ role: 'user', content: 'Say hello.' }] }
+      : { model, stream: streaming, input: 'Say hello.' };
+  return new Request(`http://paf.bench${service === 'claude' ? '/v1/messages' : '/v1/responses'}`, {
+    method: 'POST',
+    headers: {
+      'content-type': 'application/json',
+      accept: streaming ? 'text/event-stream' : 'application/json',
+    },
+    body: JSON.stringify(body),
+  });
+}
+
+const sse = (event: string, data: unknown) => `event: ${event}\ndata: ${JSON.stringify(data)}\n\n`;
+
+function mockResponse(path: string, body: any): Response {
+  const model = body?.model ?? 'bench';
+  const words = Array.from({ length: MOCK_CHUNKS }, (_, index) => `word${index} `);
+
+  if (path.endsWith('/messages')) {
+    const usage = { input_tokens: 12, output_tokens: MOCK_CHUNKS };
+    if (!body?.stream) {
+      return Response.json({
+        id: 'msg_bench',
+        type: 'message',
+        role: 'assistant',
+        model,
+        content: [{ type: 'text', text: words.join('') }],
+        stop_reason: 'end_turn',
+        usage,
+      });
+    }
+    const events = [
+      sse('message_start', { type: 'message_start', message: { id: 'msg_bench', type: 'message', role: 'assistant', model, content: [], usage: { ...usage, output_tokens: 0 } } }),
+      sse('content_block_start', { type: 'content_block_start', index: 0, content_block: { type: 'text', text: '' } }),
+      ...words.map(text => sse('content_block_delta', { type: 'content_block_delta', index: 0, delta: { type: 'text_delta', text } })),
+      sse('content_block_stop', { type: 'content_block_stop', index: 0 }),
+      sse('message_delta', { type: 'message_delta', delta: { stop_reason: 'end_turn' }, usage }),
+      sse('message_stop', { type: 'message_stop' }),
+    ];
+    return new Response(events.join(''), { headers: { 'content-type': 'text/event-stream' } });
+  }
+
+  const usage = { input_tokens: 12, output_tokens: MOCK_CHUNKS, total_tokens: 12 + MOCK_CHUNKS };
+  const response = {
+    id: 'resp_bench',
+    object: 'response',
+    model,
+    status: 'completed',
+    output: [{ type: 'message', role: 'assistant', content: [{ type: 'output_text', text: words.join('') }] }],
+    usage,
+  };
+  if (!body?.stream) {
+    return Response.json(response);
+  }
+  const events = [
+    sse('response.created', { type: 'response.created', response: { ...response, status: 'in_progress', output: [], usage: null } }),
+    ...words.map(delta => sse('response.output_text.delta', { type: 'response.output_text.delta', output_index: 0, content_index: 0, delta })),
+    sse('response.completed', { type: 'response.completed', response }),
+  ];
+  return new Response(events.join(''), { headers: { 'content-type': 'text/event-stream' } });
+}
+
+/**
+ * A local upstream answering Messages and Responses API calls instantly, so the numbers are paf's own
+ */
+export function startMockUpstream(): { url: string; stop: () => void } {
+  const server = Bun.serve({
+    port: 0,
+    hostname: '127.0.0.1',
+    async fetch(request) {
+      const body = await request.json().catch(() => null);
+      return mockResponse(new URL(request.url).pathname, body);
+    },
+  });
+  return { url: `http://127.0.0.1:${server.port}`, stop: () => server.stop(true) };
+}
+
+async function timeRequest(
+  send: () => Promise<Response>
+): Promise<{ status: string; ok: boolean; latencyMs: number; firstByteMs?: number }> {
+  const startedAt = performance.now();
+  try {
+    const response = await send();
+    let firstByteMs: number | undefined;
+    const reader = response.body?.getReader();
+    while (reader) {
+      const { done } = await reader.read();
+      firstByteMs ??= performance.now() - startedAt;
+      if (done) {
+        break;
+      }
+    }
+    return { status: String(response.status), ok: response.ok, latencyMs: performance.now() - startedAt, firstByteMs };
+  } catch {
+    return { status: 'error', ok: false, latencyMs: performance.now() - startedAt };
+  }
+}
+
+/**
+ * Run `requests` calls, `concurrency` at a time, through a proxy core built in a temporary directory
+ * (own config, request log database and load balancer), so the running server and its logs are untouched
+ */
+export async function runBench(options: BenchOptions): Promise<BenchReport> {
+  const dir = mkdtempSync(join(tmpdir(), 'paf-bench-'));
+  const mock = options.upstream ? null : startMockUpstream();
+  const configManager = new ConfigManager(dir, false);
+  let logger: RequestLogger | null = null;
+
+  try {
+    await configManager.initialize();
+    const config: ProxyConfig = options.upstream?.config ?? {
+      name: MOCK_UPSTREAM_CONFIG,
+      baseUrl: mock!.url,
+      apiKey: 'bench',
+      weight: 1,
+      enabled: true,
+    };
+    await configManager.saveServiceConfig(options.service, {
+      ...(options.upstream?.service ?? createDefaultServiceConfig()),
+      configs: [config],
+      active: config.name,
+    });
+
+    logger = new RequestLogger(dir);
+    const proxy = createProxyCore(configManager, logger).proxies[options.service];
+    const servers = configManager.getAllConfigs(options.service);
+    const model = options.model ?? (options.service === 'claude' ? 'claude-sonnet-4-5' : 'gpt-5');
+
+    const latencies: number[] = [];
+    const firstBytes: number[] = [];
+    const statuses: Record<string, number> = {};
+    let succeeded = 0;
+
+    const rssStartBytes = process.memoryUsage().rss;
+    let rssPeakBytes = rssStartBytes;
+    let heapUsedPeakBytes = process.memoryUsage().heapUsed;
+    const sampler = setInterval(() => {
+      const usage = process.memoryUsage();
+      rssPeakBytes = Math.max(rssPeakBytes, usage.rss);
+      heapUsedPeakBytes = Math.max(heapUsedPeakBytes, usage.heapUsed);
+    }, 50);
+
+    let next = 0;
+    const worker = async () => {
+      while (next < options.requests) {
+        next++;
+        const result = await timeRequest(() =>
+          proxy.handleRequest(requestFor(options.service, options.streaming, model), servers)
+        );
+        latencies.push(result.latencyMs);
+        if (options.streaming && result.firstByteMs !== undefined) {
+          firstBytes.push(result.firstByteMs);
+        }
+        statuses[result.status] = (statuses[result.status] ?? 0) + 1;
+        if (result.ok) {
+          succeeded++;
+        }
+      }
+    };
+
+    const startedAt = performance.now();
+    await Promise.all(Array.from({ length: Math.min(options.concurrency, options.requests) }, worker));
+    const durationMs = performance.now() - startedAt;
+    clearInterval(sampler);
+    // Request logs are written from microtasks and stream completions; let them land before the database closes
+    await Bun.sleep(100);
+
+    return {
+      upstream: options.upstream ? config.name : `mock (${mock!.url})`,
+      requests: options.requests,
+      succeeded,
+      failed: options.requests - succeeded,
+      statuses,
+      durationMs,
+      throughputRps: durationMs > 0 ? (options.requests / durationMs) * 1000 : 0,
+      latencyMs: summarize(latencies),
+      firstByteMs: options.streaming ? summarize(firstBytes) : undefined,
+      memory: { rssStartBytes, rssPeakBytes, heapUsedPeakBytes },
+    };
+  } finally {
+    mock?.stop();
+    logger?.close();
+    rmSync(dir, { recursive: true, force: true });
+  }
+}
@@ -0,0 +1,77 @@
+// Cluster sync - shares load balancer state between paf instances by polling their peers
+
+import type { SharedServerState } from '../routing/loadbalancer';
+
+export interface ClusterServiceState {
+  servers: Record<string, SharedServerState>;
+  freezes: Record<string, number>; // config name -> freezeUntil (ms)
+}
+
+export interface ClusterStateSnapshot {
+  node: string;
+  tenants: Record<string, Record<string, ClusterServiceState>>; // tenant -> service -> state
+}
+
+export interface ClusterSyncOptions {
+  nodeId: string;
+  peers: string[];
+  secret?: string;
+  intervalMs: number;
+  applySnapshot: (snapshot: ClusterStateSnapshot) => Promise<void>;
+}
+
+/**
+ * Poll every peer's /api/cluster/state on an interval and merge what comes back.
+ * Returns a function that stops syncing.
+ */
+export function startClusterSync(options: ClusterSyncOptions): () => void {
+  const peerErrors = new Map<string, string>();
+  let running = false;
+
+  const pollPeer = async (peer: string): Promise<void> => {
+    const controller = new AbortController();
+    const timeout = setTimeout(() => controller.abort(), options.intervalMs);
+
+    try {
+      const response = await fetch(`${peer.replace(/\/+$/, '')}/api/cluster/state`, {
+        headers: options.secret ? { Authorization: `Bearer ${options.secret}` } : {},
+        signal: controller.signal,
+      });
+      if (!response.ok) {
+        throw new Error(`HTTP ${response.status}`);
+      }
+
+      const snapshot = (await response.json()) as ClusterStateSnapshot;
+      if (snapshot?.node && snapshot.node !== options.nodeId && snapshot.tenants) {
+        await options.applySnapshot(snapshot);
+      }
+
+      if (peerErrors.delete(peer)) {
+        console.log(`[cluster] peer ${peer} reachable again`);
+      }
+    } catch (error) {
+      // Only log transitions so an offline peer doesn't flood the console
+      const message = error instanceof Error ? error.message : String(error);
+      if (peerErrors.get(peer) !== message) {
+        console.warn(`[cluster] failed to sync with peer ${peer}: ${message}`);
+        peerErrors.set(peer, message);
+      }
+    } finally {
+      clearTimeout(timeout);
+    }
+  };
+
+  const interval = setInterval(async () => {
+    if (running) {
+      return;
+    }
+    running = true;
+    try {
+      await Promise.all(options.peers.map(pollPeer));
+    } finally {
+      running = false;
+    }
+  }, options.intervalMs);
+
+  return () => clearInterval(interval);
+}
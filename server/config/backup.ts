@@ -0,0 +1,260 @@
+// Backup/restore - one tar.zst holding the TOML configs and, optionally, the request log databases
+
+import { Database } from 'bun:sqlite';
+import { existsSync, mkdirSync, readdirSync, renameSync, rmSync } from 'fs';
+import { dirname, join } from 'path';
+import { tmpdir } from 'os';
+import { ConfigManager } from './manager';
+import { SCHEMA_VERSION } from '../logging/database';
+
+// Bump when the archive layout changes in a way older releases cannot restore
+export const BACKUP_FORMAT = 1;
+
+const MANIFEST = 'manifest.json';
+const DATABASE_FILE = 'requests.db';
+const BLOCK = 512;
+
+export interface BackupManifest {
+  format: number;
+  createdAt: number;
+  schemaVersion: number;     // Request log schema the databases were written with
+  includesDatabase: boolean;
+  files: string[];
+}
+
+interface TarEntry {
+  name: string;
+  data: Uint8Array;
+}
+
+/**
+ * Archive layout: `config/` mirrors the config directory (system.toml and the service files,
+ * where load balancer settings live too), `data/` mirrors data_dir (tenant configs and databases)
+ */
+export async function createBackup(
+  configManager: ConfigManager,
+  options: { includeDatabase?: boolean } = {}
+): Promise<{ archive: Uint8Array; manifest: BackupManifest }> {
+  const configDir = configManager.getConfigDir();
+  const dataDir = configManager.getSystemConfig().dataDir;
+  const entries: TarEntry[] = [];
+
+  for (const file of tomlFiles(configDir)) {
+    entries.push({ name: `config/${file}`, data: await Bun.file(join(configDir, file)).bytes() });
+  }
+
+  const tenantsDir = join(dataDir, 'tenants');
+  const tenants = existsSync(tenantsDir) ? readdirSync(tenantsDir, { withFileTypes: true }).filter(d => d.isDirectory()) : [];
+  for (const tenant of tenants) {
+    for (const file of tomlFiles(join(tenantsDir, tenant.name))) {
+      entries.push({
+        name: `data/tenants/${tenant.name}/${file}`,
+        data: await Bun.file(join(tenantsDir, tenant.name, file)).bytes(),
+      });
+    }
+  }
+
+  if (options.includeDatabase) {
+    const databases = [
+      { name: `data/${DATABASE_FILE}`, path: join(dataDir, DATABASE_FILE) },
+      ...tenants.map(tenant => ({
+        name: `data/tenants/${tenant.name}/${DATABASE_FILE}`,
+        path: join(tenantsDir, tenant.name, DATABASE_FILE),
+      })),
+    ];
+    for (const database of databases) {
+      if (existsSync(database.path)) {
+        entries.push({ name: database.name, data: await snapshotDatabase(database.path) });
+      }
+    }
+  }
+
+  const manifest: BackupManifest = {
+    format: BACKUP_FORMAT,
+    createdAt: Date.now(),
+    schemaVersion: SCHEMA_VERSION,
+    includesDatabase: options.includeDatabase === true,
+    files: entries.map(entry => entry.name),
+  };
+  entries.unshift({ name: MANIFEST, data: new TextEncoder().encode(JSON.stringify(manifest, null, 2)) });
+
+  return { archive: Bun.zstdCompressSync(writeTar(entries)), manifest };
+}
+
+/**
+ * Write a backup over the current state. The server must be stopped: it keeps configs in memory and
+ * holds the databases open. Databases being replaced are kept next to the new ones as *.pre-restore-*.
+ */
+export async function restoreBackup(
+  configDir: string,
+  archive: Uint8Array,
+  options: { includeDatabase?: boolean } = {}
+): Promise<{ manifest: BackupManifest; restored: string[] }> {
+  const entries = readTar(Bun.zstdDecompressSync(archive));
+  const manifestEntry = entries.find(entry => entry.name === MANIFEST);
+  if (!manifestEntry) {
+    throw new Error('Not a paf backup (manifest.json missing)');
+  }
+
+  const manifest = JSON.parse(new TextDecoder().decode(manifestEntry.data)) as BackupManifest;
+  if (typeof manifest.format !== 'number' || manifest.format > BACKUP_FORMAT) {
+    throw new Error(`Backup format ${manifest.format} is newer than this release supports (${BACKUP_FORMAT}); upgrade paf first`);
+  }
+  const restoreDatabase = options.includeDatabase !== false && manifest.includesDatabase;
+  if (restoreDatabase && manifest.schemaVersion > SCHEMA_VERSION) {
+    throw new Error(
+      `Backup databases use schema v${manifest.schemaVersion}, this release supports up to v${SCHEMA_VERSION}; ` +
+        'upgrade paf first or restore without the database'
+    );
+  }
+
+  for (const entry of entries) {
+    if (entry.name !== MANIFEST && (!/^(config|data)\//.test(entry.name) || entry.name.split('/').includes('..'))) {
+      throw new Error(`Unexpected path in backup: ${entry.name}`);
+    }
+  }
+
+  const restored: string[] = [];
+
+  // Configs first: the restored system.toml decides where data_dir is
+  for (const entry of entries.filter(entry => entry.name.startsWith('config/'))) {
+    await Bun.write(join(configDir, entry.name.slice('config/'.length)), entry.data);
+    restored.push(entry.name);
+  }
+
+  const configManager = new ConfigManager(configDir);
+  await configManager.initialize();
+  const dataDir = configManager.getSystemConfig().dataDir;
+
+  for (const entry of entries.filter(entry => entry.name.startsWith('data/'))) {
+    const target = join(dataDir, entry.name.slice('data/'.length));
+    mkdirSync(dirname(target), { recursive: true });
+
+    if (!entry.name.endsWith(`/${DATABASE_FILE}`)) {
+      await Bun.write(target, entry.data);
+      restored.push(entry.name);
+    } else if (restoreDatabase) {
+      await replaceDatabase(target, entry.data);
+      restored.push(entry.name);
+    }
+  }
+
+  return { manifest, restored };
+}
+
+function tomlFiles(dir: string): string[] {
+  if (!existsSync(dir)) {
+    return [];
+  }
+  return readdirSync(dir, { withFileTypes: true })
+    .filter(entry => entry.isFile() && entry.name.endsWith('.toml'))
+    .map(entry => entry.name)
+    .sort();
+}
+
+/**
+ * Consistent copy of a live database; VACUUM INTO folds in the WAL and works while the server writes
+ */
+async function snapshotDatabase(dbPath: string): Promise<Uint8Array> {
+  const snapshotPath = join(tmpdir(), `paf-backup-${crypto.randomUUID()}.db`);
+  const db = new Database(dbPath, { readonly: true });
+  try {
+    db.run('PRAGMA busy_timeout = 5000');
+    db.run(`VACUUM INTO '${snapshotPath.replace(/'/g, "''")}'`);
+    return await Bun.file(snapshotPath).bytes();
+  } finally {
+    db.close();
+    rmSync(snapshotPath, { force: true });
+  }
+}
+
+async function replaceDatabase(dbPath: string, data: Uint8Array): Promise<void> {
+  const incoming = `${dbPath}.restoring`;
+  await Bun.write(incoming, data);
+
+  // The manifest could have been edited; trust the file itself
+  const db = new Database(incoming, { readonly: true });
+  let version: number;
+  try {
+    version = (db.prepare('PRAGMA user_version').get() as { user_version: number }).user_version;
+  } finally {
+    db.close();
+  }
+  if (version > SCHEMA_VERSION) {
+    rmSync(incoming, { force: true });
+    throw new Error(`${dbPath}: schema v${version} is newer than this release supports (v${SCHEMA_VERSION})`);
+  }
+
+  if (existsSync(dbPath)) {
+    const keptAs = `${dbPath}.pre-restore-${new Date().toISOString().replace(/[:.]/g, '-')}`;
+    renameSync(dbPath, keptAs);
+    for (const suffix of ['-wal', '-shm']) {
+      if (existsSync(dbPath + suffix)) {
+        renameSync(dbPath + suffix, keptAs + suffix);
+      }
+    }
+  }
+  renameSync(incoming, dbPath);
+}
+
+// Minimal ustar: regular files only, names up to 100 bytes (ours are short relative paths)
+function writeTar(entries: TarEntry[]): Uint8Array {
+  const encoder = new TextEncoder();
+  const chunks: Uint8Array[] = [];
+  const mtime = Math.floor(Date.now() / 1000);
+
+  for (const entry of entries) {
+    const name = encoder.encode(entry.name);
+    if (name.length > 100) {
+      throw new Error(`Path too long for backup archive: ${entry.name}`);
+    }
+
+    const header = new Uint8Array(BLOCK);
+    const field = (offset: number, value: string) => header.set(encoder.encode(value), offset);
+    header.set(name, 0);
+    field(100, '0000644\0');
+    field(108, '0000000\0');
+    field(116, '0000000\0');
+    field(124, `${entry.data.length.toString(8).padStart(11, '0')}\0`);
+    field(136, `${mtime.toString(8).padStart(11, '0')}\0`);
+    field(148, '        ');
+    field(156, '0');
+    field(257, 'ustar\0');
+    field(263, '00');
+    const checksum = header.reduce((sum, byte) => sum + byte, 0);
+    field(148, `${checksum.toString(8).padStart(6, '0')}\0 `);
+
+    chunks.push(header, entry.data, new Uint8Array((BLOCK - (entry.data.length % BLOCK)) % BLOCK));
+  }
+  chunks.push(new Uint8Array(BLOCK * 2));
+
+  const tar = new Uint8Array(chunks.reduce((size, chunk) => size + chunk.length, 0));
+  let offset = 0;
+  for (const chunk of chunks) {
+    tar.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return tar;
+}
+
+function readTar(tar: Uint8Array): TarEntry[] {
+  const decoder = new TextDecoder();
+  const text = (start: number, length: number) =>
+    decoder.decode(tar.subarray(start, start + length)).replace(/\0.*$/s, '').trim();
+  const entries: TarEntry[] = [];
+
+  let offset = 0;
+  while (offset + BLOCK <= tar.length && tar[offset] !== 0) {
+    const name = text(offset, 100);
+    const size = parseInt(text(offset + 124, 12), 8);
+    const type = text(offset + 156, 1);
+    if (!Number.isFinite(size) || offset + BLOCK + size > tar.length) {
+      throw new Error('Backup archive is truncated or corrupt');
+    }
+    if (type === '' || type === '0') {
+      entries.push({ name, data: tar.slice(offset + BLOCK, offset + BLOCK + size) });
+    }
+    offset += BLOCK + Math.ceil(size / BLOCK) * BLOCK;
+  }
+  return entries;
+}
@@ -0,0 +1,176 @@
+// Config change feed - a journal of added, updated and removed configs, so CLI tools and other paf
+// instances can follow edits with /api/configs/changes instead of re-fetching every config
+
+import type { ProxyConfig, ServiceConfig } from './types';
+
+export type ConfigChangeAction = 'added' | 'updated' | 'removed';
+
+export interface ConfigChange {
+  revision: number;
+  changedAt: number;
+  service: string;
+  config?: string; // Config name; unset for service-level settings (mode, active config, ...)
+  action: ConfigChangeAction;
+}
+
+export interface ConfigChangesSince {
+  cursor: string;
+  reset: boolean; // The cursor was unknown or too old: `changes` lists everything as added
+  changes: ConfigChange[];
+}
+
+// Longest ?wait= a long poll may ask for
+export const CONFIG_CHANGES_MAX_WAIT_SECONDS = 60;
+
+// Oldest entries are dropped beyond this; clients behind them get a reset
+const MAX_JOURNAL_ENTRIES = 1000;
+
+interface ServiceSnapshot {
+  settings: string;
+  configs: Map<string, string>;
+}
+
+function serializeSettings(config: ServiceConfig): string {
+  const { configs: _configs, ...settings } = config;
+  return JSON.stringify(settings);
+}
+
+function serializeConfig(config: ProxyConfig): string {
+  return JSON.stringify(config);
+}
+
+/**
+ * Cursors are `<epoch>-<revision>`; the epoch changes on every start, so cursors from before a restart reset
+ */
+export class ConfigChangeFeed {
+  private epoch = Date.now().toString(36);
+  private revision = 0;
+  private changedAt = Date.now();
+  private journal: ConfigChange[] = [];
+  private trimmedThrough = 0; // Highest revision no longer in the journal
+  // Serialized state as of the last record(); callers mutate ServiceConfig objects in place before saving
+  private snapshots = new Map<string, ServiceSnapshot>();
+  private waiters = new Set<() => void>();
+
+  /**
+   * Diff a service's configs against the last recorded state; bumps the revision when anything changed
+   */
+  record(serviceName: string, config: ServiceConfig): void {
+    const previous = this.snapshots.get(serviceName);
+    const next: ServiceSnapshot = {
+      settings: serializeSettings(config),
+      configs: new Map(config.configs.map(c => [c.name, serializeConfig(c)])),
+    };
+    this.snapshots.set(serviceName, next);
+
+    const changed: Array<Pick<ConfigChange, 'config' | 'action'>> = [];
+    if (previous?.settings !== next.settings) {
+      changed.push({ action: previous ? 'updated' : 'added' });
+    }
+    for (const [name, serialized] of next.configs) {
+      const before = previous?.configs.get(name);
+      if (before !== serialized) {
+        changed.push({ config: name, action: before === undefined ? 'added' : 'updated' });
+      }
+    }
+    for (const name of previous?.configs.keys() ?? []) {
+      if (!next.configs.has(name)) {
+        changed.push({ config: name, action: 'removed' });
+      }
+    }
+    if (changed.length === 0) {
+      return;
+    }
+
+    this.revision++;
+    this.changedAt = Date.now();
+    for (const change of changed) {
+      this.journal.push({ ...change, revision: this.revision, changedAt: this.changedAt, service: serviceName });
+    }
+    if (this.journal.length > MAX_JOURNAL_ENTRIES) {
+      const dropped = this.journal.splice(0, this.journal.length - MAX_JOURNAL_ENTRIES);
+      this.trimmedThrough = dropped[dropped.length - 1].revision;
+    }
+
+    const waiters = [...this.waiters];
+    this.waiters.clear();
+    for (const wake of waiters) {
+      wake();
+    }
+  }
+
+  getRevision(): { revision: number; changedAt: number } {
+    return { revision: this.revision, changedAt: this.changedAt };
+  }
+
+  cursor(): string {
+    return `${this.epoch}-${this.revision}`;
+  }
+
+  /**
+   * Net changes after `cursor`, one per config: added-then-updated stays added, anything ending in a
+   * removal is removed. Without a usable cursor every current config is listed as added.
+   */
+  since(cursor?: string): ConfigChangesSince {
+    const revision = this.parseCursor(cursor);
+    if (revision === null) {
+      return { cursor: this.cursor(), reset: true, changes: this.currentState() };
+    }
+
+    const net = new Map<string, ConfigChange>();
+    for (const change of this.journal) {
+      if (change.revision <= revision) {
+        continue;
+      }
+      const key = `${change.service}\n${change.config ?? ''}`;
+      const first = net.get(key);
+      const action = change.action !== 'removed' && first?.action === 'added' ? 'added' : change.action;
+      net.set(key, { ...change, action });
+    }
+    return { cursor: this.cursor(), reset: false, changes: [...net.values()] };
+  }
+
+  /**
+   * Resolve once something changes after `cursor`, after `timeoutMs`, or when `signal` aborts
+   */
+  async waitForChange(cursor: string | undefined, timeoutMs: number, signal?: AbortSignal): Promise<void> {
+    const revision = this.parseCursor(cursor);
+    if (revision === null || revision < this.revision || signal?.aborted) {
+      return;
+    }
+    await new Promise<void>(resolve => {
+      const done = () => {
+        clearTimeout(timer);
+        this.waiters.delete(done);
+        signal?.removeEventListener('abort', done);
+        resolve();
+      };
+      const timer = setTimeout(done, timeoutMs);
+      this.waiters.add(done);
+      signal?.addEventListener('abort', done);
+    });
+  }
+
+  /**
+   * The revision a cursor points at; null when it is malformed, from another run or older than the journal
+   */
+  private parseCursor(cursor?: string): number | null {
+    const match = cursor?.match(/^([0-9a-z]+)-(\d+)$/);
+    if (!match || match[1] !== this.epoch) {
+      return null;
+    }
+    const revision = Number(match[2]);
+    return revision <= this.revision && revision >= this.trimmedThrough ? revision : null;
+  }
+
+  private currentState(): ConfigChange[] {
+    const changes: ConfigChange[] = [];
+    for (const [service, snapshot] of this.snapshots) {
+      changes.push({ revision: this.revision, changedAt: this.changedAt, service, action: 'added' });
+      for (const name of snapshot.configs.keys()) {
+        changes.push({ revision: this.revision, changedAt: this.changedAt, service, config: name, action: 'added' });
+      }
+    }
+    return changes;
+  }
+}
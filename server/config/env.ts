@@ -0,0 +1,200 @@
+// Environment overrides - lets PAF_* variables replace values from the TOML files
+//
+// Variables map onto the TOML layout, with `__` separating nested keys:
+//   PAF_WEB_PORT=9000                          -> system.toml  web_port
+//   PAF_PROXY_PORTS__CLAUDE=9001               -> system.toml  [proxy_ports] claude
+//   PAF_CLAUDE_MODE=load_balance               -> claude.toml  mode
+//   PAF_CLAUDE_CONFIGS__OFFICIAL__BASE_URL=... -> claude.toml  [[configs]] name = "official", base_url
+//   PAF_CODEX_LOADBALANCER__STRATEGY=weighted  -> codex.toml   [loadbalancer] strategy
+
+const ENV_PREFIX = 'PAF_';
+const SERVICE_NAMES = ['claude', 'codex'];
+
+// Values kept verbatim even when they look like numbers or booleans
+const STRING_KEYS = new Set([
+  'name',
+  'base_url',
+  'api_key',
+  'auth_token',
+  'admin_token',
+  'data_dir',
+  'active',
+  'secret',
+  'peers',
+  'passphrase',
+]);
+
+type Env = Record<string, string | undefined>;
+
+function coerceValue(key: string, raw: string): unknown {
+  if (STRING_KEYS.has(key)) {
+    return raw;
+  }
+  if (raw === 'true' || raw === 'false') {
+    return raw === 'true';
+  }
+  if (raw.trim() !== '' && !Number.isNaN(Number(raw))) {
+    return Number(raw);
+  }
+  if (raw.includes(',') && (key === 'allow' || key === 'deny')) {
+    return raw.split(',').map(item => item.trim()).filter(Boolean);
+  }
+  return raw;
+}
+
+function collectOverrides(env: Env, prefix: string): Array<{ path: string[]; value: string }> {
+  const overrides: Array<{ path: string[]; value: string }> = [];
+
+  for (const [name, value] of Object.entries(env)) {
+    if (value === undefined || !name.startsWith(prefix)) {
+      continue;
+    }
+
+    const path = name
+      .slice(prefix.length)
+      .split('__')
+      .map(segment => segment.toLowerCase());
+
+    if (path.some(segment => segment.length === 0)) {
+      continue;
+    }
+
+    overrides.push({ path, value });
+  }
+
+  return overrides.sort((a, b) => a.path.join('.').localeCompare(b.path.join('.')));
+}
+
+function setPath(target: any, path: string[], value: string): void {
+  let node = target;
+  for (const segment of path.slice(0, -1)) {
+    if (!node[segment] || typeof node[segment] !== 'object' || Array.isArray(node[segment])) {
+      node[segment] = {};
+    }
+    node = node[segment];
+  }
+  const key = path[path.length - 1];
+  node[key] = coerceValue(key, value);
+}
+
+/**
+ * Apply PAF_* overrides to parsed system.toml data; per-service variables are skipped
+ */
+export function applySystemEnvOverrides(data: any, env: Env = process.env): any {
+  const result = { ...(data ?? {}) };
+
+  for (const { path, value } of collectOverrides(env, ENV_PREFIX)) {
+    if (SERVICE_NAMES.some(service => path[0].startsWith(`${service}_`))) {
+      continue;
+    }
+    setPath(result, path, value);
+  }
+
+  return result;
+}
+
+/**
+ * Apply PAF_<SERVICE>_* overrides to parsed service TOML data.
+ * `CONFIGS__<NAME>__<FIELD>` updates the config with that name (case-insensitive) or adds it.
+ */
+export function applyServiceEnvOverrides(serviceName: string, data: any, env: Env = process.env): any {
+  const result = { ...(data ?? {}) };
+  const configs: any[] = Array.isArray(result.configs) ? result.configs.map((c: any) => ({ ...c })) : [];
+
+  for (const { path, value } of collectOverrides(env, `${ENV_PREFIX}${serviceName.toUpperCase()}_`)) {
+    if (path[0] === 'configs') {
+      if (path.length !== 3) {
+        continue;
+      }
+      const [, configName, field] = path;
+      let entry = configs.find(c => typeof c.name === 'string' && c.name.toLowerCase() === configName);
+      if (!entry) {
+        entry = { name: configName };
+        configs.push(entry);
+      }
+      entry[field] = coerceValue(field, value);
+      continue;
+    }
+
+    // `active` is stored as a table in the TOML file
+    if (path.length === 1 && path[0] === 'active') {
+      result.active = { name: value };
+      continue;
+    }
+
+    setPath(result, path, value);
+  }
+
+  if (configs.length > 0) {
+    result.configs = configs.filter(c => typeof c.base_url === 'string' && c.base_url.length > 0);
+  }
+
+  return result;
+}
+
+/**
+ * Take PAF_<SERVICE>_* values back out of service TOML data about to be saved, so secrets and settings
+ * that only live in the environment never end up in the file. Each overridden key gets whatever
+ * `fileData` (the file as it is on disk) has there; configs that only the environment defines are dropped.
+ */
+export function stripServiceEnvOverrides(serviceName: string, data: any, fileData: any, env: Env = process.env): any {
+  const result = { ...(data ?? {}) };
+  const file = fileData ?? {};
+  const fileConfigs: any[] = Array.isArray(file.configs) ? file.configs : [];
+  const byName = (configs: any[], name: string) =>
+    configs.find(c => typeof c.name === 'string' && c.name.toLowerCase() === name);
+
+  for (const { path } of collectOverrides(env, `${ENV_PREFIX}${serviceName.toUpperCase()}_`)) {
+    if (path[0] === 'configs') {
+      if (path.length !== 3 || !Array.isArray(result.configs)) {
+        continue;
+      }
+      const [, configName, field] = path;
+      const original = byName(fileConfigs, configName);
+      if (!original) {
+        result.configs = result.configs.filter((c: any) => c.name?.toLowerCase() !== configName);
+        continue;
+      }
+      result.configs = result.configs.map((c: any) =>
+        c.name?.toLowerCase() === configName ? restorePath({ ...c }, original, [field]) : c
+      );
+      continue;
+    }
+
+    // `active` is stored as a table in the TOML file
+    restorePath(result, file, path.length === 1 && path[0] === 'active' ? ['active'] : path);
+  }
+
+  return result;
+}
+
+/**
+ * Set `path` in `target` to its value in `source`, or remove it when `source` has none; tables along
+ * the way are copied rather than changed in place
+ */
+function restorePath(target: any, source: any, path: string[]): any {
+  let node = target;
+  let original = source;
+  for (const segment of path.slice(0, -1)) {
+    if (!node[segment] || typeof node[segment] !== 'object' || Array.isArray(node[segment])) {
+      return target;
+    }
+    node[segment] = { ...node[segment] };
+    node = node[segment];
+    original = original?.[segment];
+  }
+  const key = path[path.length - 1];
+  if (original && typeof original === 'object' && key in original) {
+    node[key] = original[key];
+  } else {
+    delete node[key];
+  }
+  return target;
+}
+
+/**
+ * Whether any PAF_<SERVICE>_* variable is set, so the service can be configured without a file
+ */
+export function hasServiceEnvOverrides(serviceName: string, env: Env = process.env): boolean {
+  return collectOverrides(env, `${ENV_PREFIX}${serviceName.toUpperCase()}_`).length > 0;
+}
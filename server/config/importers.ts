@@ -0,0 +1,171 @@
+// Config importers - map claude-code-router and LiteLLM definitions onto paf configs
+
+import type { ProxyConfig } from './types';
+
+export type ImportSource = 'claude-code-router' | 'litellm';
+
+export const IMPORT_SOURCES: ReadonlyArray<ImportSource> = ['claude-code-router', 'litellm'];
+
+export interface ImportedConfig {
+  service: 'claude' | 'codex';
+  config: ProxyConfig;
+}
+
+export interface ImportResult {
+  configs: ImportedConfig[];
+  warnings: string[]; // Settings with no paf equivalent, reported instead of silently dropped
+}
+
+/**
+ * Parse a rival tool's config file; `content` is JSON for claude-code-router and YAML for LiteLLM
+ */
+export function importConfigs(source: ImportSource, content: string, env = process.env): ImportResult {
+  return source === 'claude-code-router'
+    ? importClaudeCodeRouter(JSON.parse(content), env)
+    : importLiteLLM(Bun.YAML.parse(content), env);
+}
+
+/**
+ * Reduce a full endpoint URL to the base paf appends client paths to; clients send /v1/... themselves
+ */
+export function toBaseUrl(endpoint: string): string {
+  return endpoint
+    .trim()
+    .replace(/\/+$/, '')
+    .replace(/\/(messages|chat\/completions|completions|responses)$/, '')
+    .replace(/\/v1$/, '');
+}
+
+function resolveSecret(value: unknown, env: Record<string, string | undefined>, warnings: string[], owner: string): string | undefined {
+  if (typeof value !== 'string' || !value) {
+    return undefined;
+  }
+
+  // claude-code-router interpolates $VAR / ${VAR}; LiteLLM uses os.environ/VAR
+  const reference = value.match(/^\$\{?([A-Za-z_][A-Za-z0-9_]*)\}?$/)?.[1] ?? value.match(/^os\.environ\/(.+)$/)?.[1];
+  if (!reference) {
+    return value;
+  }
+  if (!env[reference]) {
+    warnings.push(`${owner}: environment variable ${reference} is not set; imported without a key`);
+    return undefined;
+  }
+  return env[reference];
+}
+
+function uniqueName(name: string, taken: Set<string>): string {
+  const base = name.trim().toLowerCase().replace(/[^a-z0-9_-]+/g, '-') || 'imported';
+  let candidate = base;
+  for (let suffix = 2; taken.has(candidate); suffix++) {
+    candidate = `${base}-${suffix}`;
+  }
+  taken.add(candidate);
+  return candidate;
+}
+
+/**
+ * claude-code-router: each entry in Providers becomes a config. Anthropic-style providers go to the
+ * claude service and OpenAI-compatible ones to codex; Router rules are reported only.
+ */
+function importClaudeCodeRouter(data: any, env: Record<string, string | undefined>): ImportResult {
+  const configs: ImportedConfig[] = [];
+  const warnings: string[] = [];
+  const taken = new Set<string>();
+
+  const providers = Array.isArray(data?.Providers) ? data.Providers : Array.isArray(data?.providers) ? data.providers : [];
+  for (const provider of providers) {
+    const endpoint = provider?.api_base_url;
+    if (typeof provider?.name !== 'string' || typeof endpoint !== 'string') {
+      warnings.push(`Skipped provider without name or api_base_url: ${JSON.stringify(provider?.name ?? provider)}`);
+      continue;
+    }
+
+    const transformers = JSON.stringify(provider.transformer ?? '');
+    const isAnthropic = /\/v1\/messages\/?$/.test(endpoint) || transformers.includes('anthropic');
+    const name = uniqueName(provider.name, taken);
+    const secret = resolveSecret(provider.api_key, env, warnings, provider.name);
+
+    configs.push({
+      service: isAnthropic ? 'claude' : 'codex',
+      config: {
+        name,
+        baseUrl: toBaseUrl(endpoint),
+        apiKey: secret,
+        weight: 1,
+        enabled: true,
+      },
+    });
+
+    if (provider.transformer) {
+      warnings.push(`${provider.name}: request transformers are not supported; requests are forwarded unchanged`);
+    }
+  }
+
+  const router = data?.Router ?? data?.router;
+  if (router && typeof router === 'object') {
+    const rules = Object.entries(router)
+      .filter(([, value]) => typeof value === 'string' && value)
+      .map(([scenario, value]) => `${scenario} -> ${value}`);
+    if (rules.length > 0) {
+      warnings.push(`Router rules were not imported (paf routes by config, not by model): ${rules.join(', ')}`);
+    }
+  }
+
+  return { configs, warnings };
+}
+
+/**
+ * LiteLLM: each model_list deployment becomes a config. anthropic/* models go to the claude service,
+ * openai/* and other OpenAI-compatible models to codex. A model_name alias becomes the config's
+ * model_map; router_settings are reported only.
+ */
+function importLiteLLM(data: any, env: Record<string, string | undefined>): ImportResult {
+  const configs: ImportedConfig[] = [];
+  const warnings: string[] = [];
+  const taken = new Set<string>();
+
+  const deployments = Array.isArray(data?.model_list) ? data.model_list : [];
+  for (const deployment of deployments) {
+    const params = deployment?.litellm_params ?? {};
+    const model = typeof params.model === 'string' ? params.model : '';
+    if (!model) {
+      warnings.push(`Skipped model_list entry without litellm_params.model: ${JSON.stringify(deployment?.model_name)}`);
+      continue;
+    }
+
+    const [prefix] = model.split('/', 1);
+    const isAnthropic = prefix === 'anthropic' || (!model.includes('/') && model.startsWith('claude'));
+    if (!isAnthropic && model.includes('/') && !['openai', 'openai_compatible', 'azure'].includes(prefix)) {
+      warnings.push(`${deployment.model_name ?? model}: provider "${prefix}" is not OpenAI- or Anthropic-compatible; skipped`);
+      continue;
+    }
+
+    const endpoint = typeof params.api_base === 'string' && params.api_base
+      ? params.api_base
+      : isAnthropic ? 'https://api.anthropic.com' : 'https://api.openai.com/v1';
+    const secret = resolveSecret(params.api_key, env, warnings, deployment.model_name ?? model);
+    // The model name LiteLLM clients use maps to the provider's id, minus LiteLLM's provider prefix
+    const upstreamModel = model.includes('/') ? model.slice(prefix.length + 1) : model;
+    const alias = typeof deployment.model_name === 'string' ? deployment.model_name : undefined;
+
+    configs.push({
+      service: isAnthropic ? 'claude' : 'codex',
+      config: {
+        name: uniqueName(deployment.model_name ?? model, taken),
+        baseUrl: toBaseUrl(endpoint),
+        apiKey: secret,
+        weight: typeof params.weight === 'number' && params.weight > 0 ? params.weight : 1,
+        enabled: true,
+        modelMap: alias && alias !== upstreamModel ? { [alias]: upstreamModel } : undefined,
+      },
+    });
+  }
+
+  if (data?.router_settings?.routing_strategy) {
+    warnings.push(
+      `router_settings.routing_strategy "${data.router_settings.routing_strategy}" was not imported; choose a paf load balancer strategy instead`
+    );
+  }
+
+  return { configs, warnings };
+}
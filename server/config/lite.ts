@@ -0,0 +1,33 @@
+// Lite mode - routing and failover only, for Raspberry Pi-class hosts: no dashboard, request logs
+// appended as JSON lines instead of stored in requests.db, and small body buffers
+
+import type { SystemConfig } from './types';
+
+// Inlined by `bun run build:lite` (--define), which also skips the frontend build
+export const LITE_BUILD = process.env.PAF_LITE_BUILD === 'true';
+
+// Upper bounds in lite mode; configured values below them are kept
+const LITE_PASSTHROUGH_BODY_BYTES = 1024 * 1024;
+const LITE_BODY_MEMORY_LIMIT_BYTES = 32 * 1024 * 1024;
+const LITE_RESPONSE_CACHE_MAX_BYTES = 4 * 1024 * 1024;
+
+// Request logs in lite mode, one JSON object per line, next to where requests.db would be
+export const LITE_LOG_FILE = 'requests.jsonl';
+
+const capped = (value: number, limit: number) => (value > 0 ? Math.min(value, limit) : limit);
+
+/**
+ * Clamp buffer sizes and turn off what only the dashboard uses (realtime replay)
+ */
+export function applyLiteLimits(config: SystemConfig): SystemConfig {
+  return {
+    ...config,
+    passthroughBodyBytes: capped(config.passthroughBodyBytes, LITE_PASSTHROUGH_BODY_BYTES),
+    bodyMemoryLimitBytes: capped(config.bodyMemoryLimitBytes, LITE_BODY_MEMORY_LIMIT_BYTES),
+    realtime: { replayMinutes: 0 },
+    responseCache: {
+      ...config.responseCache,
+      maxBytes: Math.min(config.responseCache.maxBytes, LITE_RESPONSE_CACHE_MAX_BYTES),
+    },
+  };
+}
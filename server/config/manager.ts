@@ -3,16 +3,63 @@
 import { join } from 'path';
 import { existsSync, mkdirSync } from 'fs';
 import * as TOML from '@iarna/toml';
-import type { ProxyConfig, ServiceConfig, SystemConfig, LoadBalancerConfig } from './types';
+import type {
+  ProxyConfig,
+  ServiceConfig,
+  SystemConfig,
+  LoadBalancerConfig,
+  ResponseHeaderPolicy,
+  RetryBudgetConfig,
+  OutageQueueConfig,
+  TenantConfig,
+  TenantBudgetConfig,
+  ProxyTlsConfig,
+  WebhookProviderConfig,
+  DlpConfig,
+  DlpRule,
+  LogScrubbingConfig,
+  UpstreamCompressionConfig,
+  AvailabilitySchedule,
+  ApiFormat,
+} from './types';
+import { DEFAULT_RETRY_BUDGET, serviceConfigTemplate } from './defaults';
+import { applyLiteLimits, LITE_BUILD } from './lite';
+import { parseFingerprint, serializeFingerprint } from '../proxy/fingerprint';
+import { validateAnthropicBetas } from '../proxy/anthropicBeta';
+import { parseContextTrim, serializeContextTrim } from '../proxy/contextTrim';
+import { parseQuotaConfig, serializeQuotaConfig } from '../monitoring/quota';
+import { DEFAULT_QUEUE_PATHS } from '../proxy/outageQueue';
+import { parseModelMap, parseModelOverride, serializeModelOverride } from '../proxy/modelOverride';
+import { parseConfigOverridePolicy, serializeConfigOverridePolicy } from '../proxy/configOverride';
+import { parseTimeoutSecs } from '../proxy/timeouts';
+import { parseNoLogClientKeys } from '../proxy/noLog';
+import { parseClientKeyLanguages, parseResponseLanguage } from '../proxy/responseLanguage';
+import { isProviderProfile, parseQuirks, serializeQuirks } from '../proxy/quirks';
+import { parsePromptCache, serializePromptCache } from '../proxy/promptCache';
+import { parseRequestCompression, serializeRequestCompression } from '../proxy/requestCompression';
+import { parseConfigPools, serializeConfigPools } from '../routing/pools';
+import { parseBodyLimit } from '../proxy/bodyLimit';
+import { proxyUrlError } from '../proxy/network';
+import { parseAvailability, serializeAvailability } from '../routing/schedule';
+import { parsePerMinuteLimit } from '../routing/rateLimiter';
+import { apiFormatError } from '../proxy/translation';
+import { ConfigChangeFeed } from './changeFeed';
+import { applyServiceEnvOverrides, applySystemEnvOverrides, hasServiceEnvOverrides, stripServiceEnvOverrides } from './env';
 
 export class ConfigManager {
   private configDir: string;
   private systemConfig!: SystemConfig;
   private services: Map<string, ServiceConfig> = new Map();
+  private applyEnv: boolean;
+  private changes = new ConfigChangeFeed();
 
-  constructor(configDir?: string) {
+  /**
+   * @param applyEnv whether PAF_* environment variables override file values (off for tenant directories)
+   */
+  constructor(configDir?: string, applyEnv = true) {
     // Default to ~/.paf/ directory
     this.configDir = configDir || join(process.env.HOME || '~', '.paf');
+    this.applyEnv = applyEnv;
 
     // Ensure config directory exists
     if (!existsSync(this.configDir)) {
@@ -35,50 +82,446 @@ export class ConfigManager {
           claude: 8801,
           codex: 8802,
         },
+        singlePort: false,
+        readOnly: false,
+        lite: LITE_BUILD,
         logLevel: 'info',
         dataDir: this.configDir,
+        logRetentionDays: 30,
+        passthroughBodyBytes: 16 * 1024 * 1024,
+        bodyMemoryLimitBytes: 256 * 1024 * 1024,
+        maxConcurrentRequests: 0,
+        realtime: {
+          replayMinutes: 10,
+        },
+        network: {
+          ipFamily: 'auto',
+          warmUp: true,
+        },
+        tenants: [],
+        cluster: {
+          peers: [],
+          syncIntervalMs: 5000,
+        },
+        webhooks: [],
+        dlp: {
+          rules: [],
+        },
+        logScrubbing: {
+          emails: true,
+          phoneNumbers: true,
+          awsKeys: true,
+          jwts: true,
+        },
+        modelListCache: {
+          ttlSeconds: 60,
+        },
+        shapeCache: {
+          threshold: 2,
+          ttlSeconds: 3600,
+        },
+        responseCache: {
+          ttlSeconds: 0,
+          maxBytes: 64 * 1024 * 1024,
+        },
+        tlsMonitor: {
+          enabled: true,
+          intervalMinutes: 360,
+          expiryWarningDays: 14,
+        },
+        sync: {},
       };
 
       // Write default config
       const tomlContent = `
 # System Configuration
 web_port = ${defaultConfig.webPort}
+# Serve /claude/*, /codex/* and /ui/* from web_port only (proxy_ports are ignored)
+single_port = ${defaultConfig.singlePort}
+# Disable all configuration changes through the API and CLI
+read_only = ${defaultConfig.readOnly}
+# Routing and failover only: no dashboard, request logs as JSON lines in requests.jsonl,
+# small body buffers (for Raspberry Pi-class hosts)
+lite = ${defaultConfig.lite}
+# debug, info, warn or error, optionally per message prefix: "info,proxy=debug"
 log_level = "${defaultConfig.logLevel}"
 data_dir = "${defaultConfig.dataDir}"
+# Request logs older than this are deleted; 0 keeps everything
+log_retention_days = ${defaultConfig.logRetentionDays}
+# Request/response bodies larger than this are streamed straight through (no body logging,
+# prompt templates or thinking cleanup; requests stay buffered while DLP is on); 0 always buffers
+passthrough_body_bytes = ${defaultConfig.passthroughBodyBytes}
+# Total body bytes buffered across all in-flight requests; beyond it bodies stream through
+# and response capture for logs stops (see /api/stats/memory); 0 removes the cap
+body_memory_limit_bytes = ${defaultConfig.bodyMemoryLimitBytes}
+# Proxied requests in flight at once across all services (streams count until they end); beyond it
+# clients get a 429 with Retry-After; 0 removes the cap. Services can set their own in <service>.toml
+max_concurrent_requests = ${defaultConfig.maxConcurrentRequests}
 
 [proxy_ports]
 claude = ${defaultConfig.proxyPorts.claude}
 codex = ${defaultConfig.proxyPorts.codex}
+
+[realtime]
+replay_minutes = ${defaultConfig.realtime.replayMinutes}
+
+[network]
+# "auto", "ipv4" or "ipv6"
+ip_family = "${defaultConfig.network.ipFamily}"
+# Pre-open upstream connections at startup and after config switches
+warm_up = ${defaultConfig.network.warmUp}
+# Local address or interface name upstream connections leave from, on multi-homed hosts
+# bind_address = "eth1"
+
+[log_scrubbing]
+# Mask these in stored request logs (bodies, errors, headers)
+emails = ${defaultConfig.logScrubbing.emails}
+phone_numbers = ${defaultConfig.logScrubbing.phoneNumbers}
+aws_keys = ${defaultConfig.logScrubbing.awsKeys}
+jwts = ${defaultConfig.logScrubbing.jwts}
+
+[model_list_cache]
+# Reuse GET /v1/models responses per config for this long and merge concurrent polls; 0 disables
+ttl_seconds = ${defaultConfig.modelListCache.ttlSeconds}
+
+[shape_cache]
+# After this many 400s blaming the same parameter or content type, route such requests to another
+# config, or strip it when none is left; 0 disables
+threshold = ${defaultConfig.shapeCache.threshold}
+# Forget a learned rejection after this long
+ttl_seconds = ${defaultConfig.shapeCache.ttlSeconds}
+
+[response_cache]
+# Answer identical non-streaming requests (same path and body, ignoring key order, metadata and user)
+# from the stored response for this long; clients opt out with cache-control: no-cache; 0 disables
+ttl_seconds = ${defaultConfig.responseCache.ttlSeconds}
+# Response bytes kept in memory and in requests.db; least recently used entries go first
+max_bytes = ${defaultConfig.responseCache.maxBytes}
+
+[tls_monitor]
+# Record each upstream host's TLS certificate; warn on upcoming expiry or an unexpected change
+enabled = ${defaultConfig.tlsMonitor.enabled}
+interval_minutes = ${defaultConfig.tlsMonitor.intervalMinutes}
+expiry_warning_days = ${defaultConfig.tlsMonitor.expiryWarningDays}
+# notify_url = "https://hooks.slack.com/services/..."
+
+[sync]
+# Shared by every machine running \`paf sync push/pull\`; bundles are encrypted with it
+# passphrase = "..."
+
+[cluster]
+# Other paf instances (web UI URLs) to share failure counts, freezes and retry budgets with
+peers = []
+sync_interval_ms = ${defaultConfig.cluster.syncIntervalMs}
+
+# HTTPS on the proxy ports; with client_ca, clients must present a certificate signed by it (mTLS)
+# [proxy_tls]
+# cert = "/etc/paf/server.crt"
+# key = "/etc/paf/server.key"
+# client_ca = "/etc/paf/clients-ca.crt"
+# identities = { "build-01.ci.example.com" = "ci" }   # certificate CN -> client identity in logs
+
+# Tenants get their own configs and logs under <data_dir>/tenants/<name>; example:
+# [[tenants]]
+# name = "team-a"
+# client_keys = ["paf-team-a-key"]
+# client_certs = ["ci"]   # client identities from [proxy_tls] certificates
+# proxy_ports = { claude = 8811, codex = 8812 }
+# budget = { tokens = 5000000, period = "month" }   # or "day"; periods are UTC
+
+# Async callbacks from upstream providers, received at POST /api/webhooks/<provider>; example:
+# [[webhooks]]
+# provider = "openai"
+# secret = "whsec_..."
+# scheme = "standard"   # or "hmac-sha256" with signature_header = "x-signature"
+# service = "codex"
+
+# Outbound DLP: scan request bodies before they reach any upstream; action is "redact", "block" or "flag"
+# [[dlp.rules]]
+# name = "aws-access-key"
+# pattern = "AKIA[0-9A-Z]{16}"
+# action = "block"
+#
+# [dlp.moderation]
+# url = "https://api.openai.com/v1/moderations"
+# api_key = "sk-..."
+# action = "flag"
 `;
       await Bun.write(systemConfigPath, tomlContent);
-      return defaultConfig;
+      return this.parseSystemConfig(applySystemEnvOverrides(TOML.parse(tomlContent)));
     }
 
     const content = await Bun.file(systemConfigPath).text();
-    return this.parseSystemConfig(TOML.parse(content));
+    return this.parseSystemConfig(applySystemEnvOverrides(TOML.parse(content)));
   }
 
   private parseSystemConfig(data: any): SystemConfig {
-    return {
+    const config: SystemConfig = {
       webPort: data.web_port || 8800,
       proxyPorts: {
         claude: data.proxy_ports?.claude || 8801,
         codex: data.proxy_ports?.codex || 8802,
       },
-      logLevel: data.log_level || 'info',
+      singlePort: data.single_port === true,
+      readOnly: data.read_only === true,
+      lite: data.lite === true || LITE_BUILD,
+      logLevel: typeof data.log_level === 'string' && data.log_level.trim() ? data.log_level.trim() : 'info',
       dataDir: data.data_dir || this.configDir,
+      logRetentionDays: typeof data.log_retention_days === 'number' ? data.log_retention_days : 30,
+      passthroughBodyBytes:
+        typeof data.passthrough_body_bytes === 'number' ? Math.max(0, data.passthrough_body_bytes) : 16 * 1024 * 1024,
+      bodyMemoryLimitBytes:
+        typeof data.body_memory_limit_bytes === 'number'
+          ? Math.max(0, data.body_memory_limit_bytes)
+          : 256 * 1024 * 1024,
+      maxConcurrentRequests:
+        typeof data.max_concurrent_requests === 'number' ? Math.max(0, Math.floor(data.max_concurrent_requests)) : 0,
+      adminToken: data.admin_token || undefined,
+      realtime: {
+        replayMinutes:
+          typeof data.realtime?.replay_minutes === 'number' ? data.realtime.replay_minutes : 10,
+      },
+      network: {
+        ipFamily: ['ipv4', 'ipv6'].includes(data.network?.ip_family) ? data.network.ip_family : 'auto',
+        warmUp: data.network?.warm_up !== false,
+        bindAddress:
+          typeof data.network?.bind_address === 'string' && data.network.bind_address.trim()
+            ? data.network.bind_address.trim()
+            : undefined,
+      },
+      tenants: this.parseTenants(data.tenants),
+      cluster: {
+        // Accept a comma-separated string too, e.g. from PAF_CLUSTER__PEERS
+        peers: (Array.isArray(data.cluster?.peers)
+          ? data.cluster.peers
+          : typeof data.cluster?.peers === 'string'
+            ? data.cluster.peers.split(',')
+            : []
+        )
+          .filter((peer: unknown): peer is string => typeof peer === 'string')
+          .map((peer: string) => peer.trim())
+          .filter(Boolean),
+        secret: data.cluster?.secret || undefined,
+        syncIntervalMs:
+          typeof data.cluster?.sync_interval_ms === 'number' ? data.cluster.sync_interval_ms : 5000,
+      },
+      webhooks: this.parseWebhooks(data.webhooks),
+      dlp: this.parseDlp(data.dlp),
+      logScrubbing: this.parseLogScrubbing(data.log_scrubbing),
+      modelListCache: {
+        ttlSeconds:
+          typeof data.model_list_cache?.ttl_seconds === 'number' ? Math.max(0, data.model_list_cache.ttl_seconds) : 60,
+      },
+      shapeCache: {
+        threshold: typeof data.shape_cache?.threshold === 'number' ? Math.max(0, Math.floor(data.shape_cache.threshold)) : 2,
+        ttlSeconds:
+          typeof data.shape_cache?.ttl_seconds === 'number' ? Math.max(0, data.shape_cache.ttl_seconds) : 3600,
+      },
+      responseCache: {
+        ttlSeconds:
+          typeof data.response_cache?.ttl_seconds === 'number' ? Math.max(0, data.response_cache.ttl_seconds) : 0,
+        maxBytes:
+          typeof data.response_cache?.max_bytes === 'number'
+            ? Math.max(0, Math.floor(data.response_cache.max_bytes))
+            : 64 * 1024 * 1024,
+      },
+      tlsMonitor: {
+        enabled: data.tls_monitor?.enabled !== false,
+        intervalMinutes:
+          typeof data.tls_monitor?.interval_minutes === 'number' && data.tls_monitor.interval_minutes > 0
+            ? data.tls_monitor.interval_minutes
+            : 360,
+        expiryWarningDays:
+          typeof data.tls_monitor?.expiry_warning_days === 'number' ? Math.max(0, data.tls_monitor.expiry_warning_days) : 14,
+        notifyUrl:
+          typeof data.tls_monitor?.notify_url === 'string' && data.tls_monitor.notify_url
+            ? data.tls_monitor.notify_url
+            : undefined,
+      },
+      sync: {
+        passphrase: typeof data.sync?.passphrase === 'string' && data.sync.passphrase ? data.sync.passphrase : undefined,
+      },
+      proxyTls: this.parseProxyTls(data.proxy_tls),
+    };
+    return config.lite ? applyLiteLimits(config) : config;
+  }
+
+  private parseProxyTls(data: any): ProxyTlsConfig | undefined {
+    if (!data) {
+      return undefined;
+    }
+    if (typeof data.cert !== 'string' || !data.cert || typeof data.key !== 'string' || !data.key) {
+      console.warn('Ignoring [proxy_tls]: cert and key must both be set');
+      return undefined;
+    }
+    const identities: Record<string, string> = {};
+    for (const [commonName, identity] of Object.entries(data.identities ?? {})) {
+      if (typeof identity === 'string' && identity) {
+        identities[commonName] = identity;
+      }
+    }
+    return {
+      cert: data.cert,
+      key: data.key,
+      clientCa: typeof data.client_ca === 'string' && data.client_ca ? data.client_ca : undefined,
+      identities,
+    };
+  }
+
+  private parseTenants(data: any): TenantConfig[] {
+    if (!Array.isArray(data)) {
+      return [];
+    }
+
+    const tenants: TenantConfig[] = [];
+    for (const entry of data) {
+      // Names become directory names, so keep them to a safe character set
+      if (typeof entry?.name !== 'string' || !/^[A-Za-z0-9_-]+$/.test(entry.name) || entry.name === 'default') {
+        console.warn(`Ignoring tenant with invalid name: ${JSON.stringify(entry?.name)}`);
+        continue;
+      }
+      if (tenants.some(t => t.name === entry.name)) {
+        console.warn(`Ignoring duplicate tenant: ${entry.name}`);
+        continue;
+      }
+
+      tenants.push({
+        name: entry.name,
+        clientKeys: Array.isArray(entry.client_keys)
+          ? entry.client_keys.filter((key: unknown): key is string => typeof key === 'string' && key.length > 0)
+          : [],
+        clientCerts: Array.isArray(entry.client_certs)
+          ? entry.client_certs.filter((name: unknown): name is string => typeof name === 'string' && name.length > 0)
+          : [],
+        proxyPorts: entry.proxy_ports
+          ? {
+              claude: typeof entry.proxy_ports.claude === 'number' ? entry.proxy_ports.claude : undefined,
+              codex: typeof entry.proxy_ports.codex === 'number' ? entry.proxy_ports.codex : undefined,
+            }
+          : undefined,
+        budget: this.parseTenantBudget(entry.name, entry.budget),
+      });
+    }
+
+    return tenants;
+  }
+
+  private parseTenantBudget(tenant: string, data: any): TenantBudgetConfig | undefined {
+    if (data === undefined) {
+      return undefined;
+    }
+    if (typeof data?.tokens !== 'number' || data.tokens <= 0) {
+      console.warn(`Ignoring budget of tenant ${tenant}: tokens must be a positive number`);
+      return undefined;
+    }
+    return { tokens: Math.floor(data.tokens), period: data.period === 'day' ? 'day' : 'month' };
+  }
+
+  private parseLogScrubbing(data: any): LogScrubbingConfig {
+    // Every pattern is on unless explicitly disabled
+    return {
+      emails: data?.emails !== false,
+      phoneNumbers: data?.phone_numbers !== false,
+      awsKeys: data?.aws_keys !== false,
+      jwts: data?.jwts !== false,
+    };
+  }
+
+  private parseDlp(data: any): DlpConfig {
+    const rules: DlpRule[] = [];
+    for (const entry of Array.isArray(data?.rules) ? data.rules : []) {
+      const keywords = Array.isArray(entry?.keywords)
+        ? entry.keywords.filter((keyword: unknown): keyword is string => typeof keyword === 'string' && keyword.length > 0)
+        : [];
+      const pattern = typeof entry?.pattern === 'string' && entry.pattern ? entry.pattern : undefined;
+
+      if (typeof entry?.name !== 'string' || !entry.name || (!pattern && keywords.length === 0)) {
+        console.warn(`Ignoring DLP rule without a name or any pattern/keywords: ${JSON.stringify(entry?.name)}`);
+        continue;
+      }
+      if (pattern) {
+        try {
+          new RegExp(pattern);
+        } catch (error) {
+          console.warn(`Ignoring DLP rule ${entry.name}: invalid pattern (${(error as Error).message})`);
+          continue;
+        }
+      }
+
+      rules.push({
+        name: entry.name,
+        pattern,
+        keywords: keywords.length > 0 ? keywords : undefined,
+        action: ['redact', 'block'].includes(entry.action) ? entry.action : 'flag',
+      });
+    }
+
+    const moderation = data?.moderation;
+    return {
+      rules,
+      moderation: typeof moderation?.url === 'string' && moderation.url
+        ? {
+            url: moderation.url,
+            apiKey: moderation.api_key || undefined,
+            action: moderation.action === 'block' ? 'block' : 'flag',
+            timeoutMs: typeof moderation.timeout_ms === 'number' ? moderation.timeout_ms : 5000,
+            failOpen: moderation.fail_open !== false,
+          }
+        : undefined,
     };
   }
 
+  private parseWebhooks(data: any): WebhookProviderConfig[] {
+    if (!Array.isArray(data)) {
+      return [];
+    }
+
+    const webhooks: WebhookProviderConfig[] = [];
+    for (const entry of data) {
+      if (typeof entry?.provider !== 'string' || !/^[A-Za-z0-9_-]+$/.test(entry.provider)) {
+        console.warn(`Ignoring webhook with invalid provider: ${JSON.stringify(entry?.provider)}`);
+        continue;
+      }
+      // An unsigned receiver would let anyone inject events into the dashboard
+      if (typeof entry.secret !== 'string' || entry.secret.length === 0) {
+        console.warn(`Ignoring webhook ${entry.provider}: secret is required`);
+        continue;
+      }
+      if (webhooks.some(w => w.provider === entry.provider)) {
+        console.warn(`Ignoring duplicate webhook: ${entry.provider}`);
+        continue;
+      }
+
+      webhooks.push({
+        provider: entry.provider,
+        secret: entry.secret,
+        scheme: entry.scheme === 'hmac-sha256' ? 'hmac-sha256' : 'standard',
+        signatureHeader: (typeof entry.signature_header === 'string' && entry.signature_header) || 'x-signature',
+        toleranceSeconds: typeof entry.tolerance_seconds === 'number' ? entry.tolerance_seconds : 300,
+        service: entry.service === 'codex' ? 'codex' : 'claude',
+        idPath: typeof entry.id_path === 'string' && entry.id_path ? entry.id_path : undefined,
+      });
+    }
+
+    return webhooks;
+  }
+
   async loadServiceConfig(serviceName: string): Promise<ServiceConfig> {
     const configPath = join(this.configDir, `${serviceName}.toml`);
 
-    if (!existsSync(configPath)) {
+    // Environment variables alone are enough to configure a service (e.g. in containers)
+    if (!existsSync(configPath) && !(this.applyEnv && hasServiceEnvOverrides(serviceName))) {
       throw new Error(`Service config not found: ${serviceName}`);
     }
 
-    const content = await Bun.file(configPath).text();
-    const data = TOML.parse(content) as any;
+    let content = existsSync(configPath) ? await Bun.file(configPath).text() : '';
+    if (existsSync(configPath) && content.trim() === '') {
+      // An emptied file gets the same commented template as a first run
+      content = serviceConfigTemplate(serviceName);
+      await Bun.write(configPath, content);
+    }
+    const parsed = TOML.parse(content);
+    const data = (this.applyEnv ? applyServiceEnvOverrides(serviceName, parsed) : parsed) as any;
 
     const configs: ProxyConfig[] = (Array.isArray(data.configs) ? data.configs : []).map((c: any) => ({
       name: c.name,
@@ -88,6 +531,24 @@ codex = ${defaultConfig.proxyPorts.codex}
       weight: c.weight || 1.0,
       enabled: c.enabled !== false,
       freezeUntil: typeof c.freeze_until === 'number' ? c.freeze_until : undefined,
+      fingerprint: parseFingerprint(c.fingerprint),
+      anthropicBeta: this.parseAnthropicBeta(serviceName, c.name, c.anthropic_beta),
+      contextTrim: parseContextTrim(c.context_trim),
+      quota: parseQuotaConfig(c.quota),
+      responseLanguage: parseResponseLanguage(c.response_language),
+      providerProfile: this.parseProviderProfile(serviceName, c.name, c.provider_profile),
+      quirks: parseQuirks(c.quirks),
+      upstreamCompression: typeof c.upstream_compression === 'boolean' ? c.upstream_compression : undefined,
+      promptCache: parsePromptCache(c.prompt_cache),
+      availability: this.parseAvailability(serviceName, c.name, c.availability),
+      apiFormat: this.parseApiFormat(serviceName, c.name, c.api_format),
+      requestCompression: parseRequestCompression(c.request_compression),
+      requestsPerMinute: parsePerMinuteLimit(c.requests_per_minute),
+      tokensPerMinute: parsePerMinuteLimit(c.tokens_per_minute),
+      proxyUrl: this.parseProxyUrl(`${serviceName}/${c.name}`, c.proxy_url),
+      modelMap: parseModelMap(c.model_map),
+      timeoutSecs: parseTimeoutSecs(c.timeout_secs),
+      connectTimeoutSecs: parseTimeoutSecs(c.connect_timeout_secs),
     }));
 
     const loadBalancer: LoadBalancerConfig = {
@@ -101,19 +562,57 @@ codex = ${defaultConfig.proxyPorts.codex}
       },
       freezeDuration:
         (data.loadbalancer as any)?.freeze_duration ?? 5 * 60 * 1000,
+      retryBudget: this.parseRetryBudget((data.loadbalancer as any)?.retry_budget),
     };
 
+    const responseHeaders = this.parseResponseHeaderPolicy(data.response_headers);
+
     const serviceConfig: ServiceConfig = {
       configs,
       active: (data.active as any)?.name || configs[0]?.name || '',
       mode: (data.mode as 'manual' | 'load_balance') || 'manual',
       loadBalancer,
+      ...(responseHeaders ? { responseHeaders } : {}),
+      resumeInterruptedStreams: data.resume_interrupted_streams === true,
+      keepaliveIntervalSecs:
+        typeof data.keepalive_interval_secs === 'number' ? data.keepalive_interval_secs : undefined,
+      maxTokensContinuations:
+        typeof data.max_tokens_continuations === 'number' ? data.max_tokens_continuations : undefined,
+      thinkingContent: data.thinking_content === 'strip' ? 'strip' : undefined,
+      captureRequestBodies: data.capture_request_bodies === true,
+      validateRequests: data.validate_requests === true,
+      validateResponses: data.validate_responses === true,
+      fingerprint: parseFingerprint(data.fingerprint),
+      outageQueue: this.parseOutageQueue(data.outage_queue),
+      modelOverride: parseModelOverride(data.model_override),
+      clientKeyLanguages: parseClientKeyLanguages(data.response_language),
+      providerProfile: this.parseProviderProfile(serviceName, undefined, data.provider_profile),
+      upstreamCompression: this.parseUpstreamCompression(data.upstream_compression),
+      pools: parseConfigPools(data.pools, configs.map(c => c.name), serviceName),
+      maxConcurrentRequests:
+        typeof data.max_concurrent_requests === 'number' && data.max_concurrent_requests > 0
+          ? Math.floor(data.max_concurrent_requests)
+          : undefined,
+      maxRequestBodyBytes: parseBodyLimit(data.max_request_body_bytes),
+      proxyUrl: this.parseProxyUrl(serviceName, data.proxy_url),
+      configOverride: parseConfigOverridePolicy(data.config_override),
+      timeoutSecs: parseTimeoutSecs(data.timeout_secs),
+      connectTimeoutSecs: parseTimeoutSecs(data.connect_timeout_secs),
+      noLogClientKeys: parseNoLogClientKeys(data.no_log),
     };
 
     this.services.set(serviceName, serviceConfig);
+    this.changes.record(serviceName, serviceConfig);
     return serviceConfig;
   }
 
+  /**
+   * Write the commented starter `<service>.toml`, replacing whatever is there
+   */
+  async writeServiceConfigTemplate(serviceName: string): Promise<void> {
+    await Bun.write(join(this.configDir, `${serviceName}.toml`), serviceConfigTemplate(serviceName));
+  }
+
   async saveServiceConfig(serviceName: string, config: ServiceConfig): Promise<void> {
     const configPath = join(this.configDir, `${serviceName}.toml`);
 
@@ -144,6 +643,33 @@ codex = ${defaultConfig.proxyPorts.codex}
     // Convert to TOML format using standard library
     const tomlData: any = {
       mode: sanitizedConfig.mode,
+      resume_interrupted_streams: sanitizedConfig.resumeInterruptedStreams || undefined,
+      keepalive_interval_secs: sanitizedConfig.keepaliveIntervalSecs || undefined,
+      max_tokens_continuations: sanitizedConfig.maxTokensContinuations || undefined,
+      thinking_content: sanitizedConfig.thinkingContent === 'strip' ? 'strip' : undefined,
+      capture_request_bodies: sanitizedConfig.captureRequestBodies || undefined,
+      validate_requests: sanitizedConfig.validateRequests || undefined,
+      validate_responses: sanitizedConfig.validateResponses || undefined,
+      fingerprint: serializeFingerprint(sanitizedConfig.fingerprint),
+      model_override: serializeModelOverride(sanitizedConfig.modelOverride),
+      provider_profile: sanitizedConfig.providerProfile,
+      upstream_compression: sanitizedConfig.upstreamCompression
+        ? {
+            streaming: sanitizedConfig.upstreamCompression.streaming,
+            non_streaming: sanitizedConfig.upstreamCompression.nonStreaming,
+          }
+        : undefined,
+      response_language: sanitizedConfig.clientKeyLanguages
+        ? { client_keys: sanitizedConfig.clientKeyLanguages }
+        : undefined,
+      pools: serializeConfigPools(sanitizedConfig.pools, normalizedConfigs.map(c => c.name)),
+      max_concurrent_requests: sanitizedConfig.maxConcurrentRequests || undefined,
+      max_request_body_bytes: sanitizedConfig.maxRequestBodyBytes || undefined,
+      proxy_url: sanitizedConfig.proxyUrl,
+      config_override: serializeConfigOverridePolicy(sanitizedConfig.configOverride),
+      timeout_secs: sanitizedConfig.timeoutSecs,
+      connect_timeout_secs: sanitizedConfig.connectTimeoutSecs,
+      no_log: sanitizedConfig.noLogClientKeys ? { client_keys: sanitizedConfig.noLogClientKeys } : undefined,
       configs: sanitizedConfig.configs.map(c => ({
         name: c.name,
         base_url: c.baseUrl,
@@ -152,6 +678,24 @@ codex = ${defaultConfig.proxyPorts.codex}
         weight: c.weight,
         enabled: c.enabled,
         freeze_until: typeof c.freezeUntil === 'number' ? Math.floor(c.freezeUntil) : undefined,
+        fingerprint: serializeFingerprint(c.fingerprint),
+        anthropic_beta: c.anthropicBeta?.length ? c.anthropicBeta : undefined,
+        context_trim: serializeContextTrim(c.contextTrim),
+        quota: serializeQuotaConfig(c.quota),
+        response_language: c.responseLanguage,
+        provider_profile: c.providerProfile,
+        quirks: serializeQuirks(c.quirks),
+        upstream_compression: c.upstreamCompression,
+        prompt_cache: serializePromptCache(c.promptCache),
+        availability: serializeAvailability(c.availability),
+        api_format: c.apiFormat,
+        request_compression: serializeRequestCompression(c.requestCompression),
+        requests_per_minute: c.requestsPerMinute,
+        tokens_per_minute: c.tokensPerMinute,
+        proxy_url: c.proxyUrl,
+        model_map: c.modelMap,
+        timeout_secs: c.timeoutSecs,
+        connect_timeout_secs: c.connectTimeoutSecs,
       })),
       active: {
         name: sanitizedConfig.active,
@@ -166,14 +710,174 @@ codex = ${defaultConfig.proxyPorts.codex}
           failure_threshold: sanitizedConfig.loadBalancer.healthCheck.failureThreshold,
           success_threshold: sanitizedConfig.loadBalancer.healthCheck.successThreshold,
         },
+        retry_budget: sanitizedConfig.loadBalancer.retryBudget
+          ? {
+              ratio: sanitizedConfig.loadBalancer.retryBudget.ratio,
+              window_ms: sanitizedConfig.loadBalancer.retryBudget.windowMs,
+              min_retries: sanitizedConfig.loadBalancer.retryBudget.minRetries,
+            }
+          : undefined,
       },
     };
 
-    const tomlContent = TOML.stringify(tomlData);
+    if (sanitizedConfig.outageQueue) {
+      tomlData.outage_queue = {
+        enabled: sanitizedConfig.outageQueue.enabled,
+        paths: sanitizedConfig.outageQueue.paths,
+        max_attempts: sanitizedConfig.outageQueue.maxAttempts,
+        max_items: sanitizedConfig.outageQueue.maxItems,
+      };
+    }
+
+    if (sanitizedConfig.responseHeaders) {
+      tomlData.response_headers = {
+        allow: sanitizedConfig.responseHeaders.allow,
+        deny: sanitizedConfig.responseHeaders.deny,
+      };
+    }
+
+    // The in-memory config carries PAF_* overrides; the file keeps its own values for those keys
+    const fileData = this.applyEnv && existsSync(configPath) ? TOML.parse(await Bun.file(configPath).text()) : {};
+    const tomlContent = TOML.stringify(
+      this.applyEnv ? stripServiceEnvOverrides(serviceName, tomlData, fileData) : tomlData
+    );
     await Bun.write(configPath, tomlContent);
 
     // Update in-memory cache
     this.services.set(serviceName, sanitizedConfig);
+    this.changes.record(serviceName, sanitizedConfig);
+  }
+
+  /**
+   * Changes to any service config, for conditional GETs on /api/configs
+   */
+  getRevision(): { revision: number; changedAt: number } {
+    return this.changes.getRevision();
+  }
+
+  /**
+   * Journal of config edits behind /api/configs/changes
+   */
+  getChangeFeed(): ConfigChangeFeed {
+    return this.changes;
+  }
+
+  private parseUpstreamCompression(data: any): UpstreamCompressionConfig | undefined {
+    if (!data || typeof data !== 'object') {
+      return undefined;
+    }
+    return {
+      streaming: data.streaming !== false,
+      nonStreaming: data.non_streaming !== false,
+    };
+  }
+
+  private parseAvailability(serviceName: string, configName: string, data: any): AvailabilitySchedule | undefined {
+    if (data === undefined) {
+      return undefined;
+    }
+    const parsed = parseAvailability(data);
+    if ('error' in parsed) {
+      console.warn(`[config] ${serviceName}/${configName}: ignoring availability: ${parsed.error}`);
+      return undefined;
+    }
+    return parsed;
+  }
+
+  private parseProviderProfile(serviceName: string, configName: string | undefined, data: any): string | undefined {
+    if (data === undefined) {
+      return undefined;
+    }
+    if (!isProviderProfile(data)) {
+      const where = configName ? `${serviceName}/${configName}` : serviceName;
+      console.warn(`[config] ${where}: ignoring unknown provider_profile ${JSON.stringify(data)}`);
+      return undefined;
+    }
+    return data;
+  }
+
+  private parseProxyUrl(owner: string, data: any): string | undefined {
+    if (data === undefined) {
+      return undefined;
+    }
+    const error = proxyUrlError(data);
+    if (error) {
+      console.warn(`[config] ${owner}: ignoring proxy_url: ${error}`);
+      return undefined;
+    }
+    return data.trim();
+  }
+
+  private parseApiFormat(serviceName: string, configName: string, data: any): ApiFormat | undefined {
+    if (data === undefined) {
+      return undefined;
+    }
+    const error = apiFormatError(serviceName, data);
+    if (error) {
+      console.warn(`[config] ${serviceName}/${configName}: ignoring api_format: ${error}`);
+      return undefined;
+    }
+    return data;
+  }
+
+  private parseAnthropicBeta(serviceName: string, configName: string, data: any): string[] | undefined {
+    if (data === undefined) {
+      return undefined;
+    }
+
+    const result = validateAnthropicBetas(data);
+    if ('error' in result) {
+      console.warn(`[config] ${serviceName}/${configName}: ignoring anthropic_beta: ${result.error}`);
+      return undefined;
+    }
+    result.warnings.forEach(warning => console.warn(`[config] ${serviceName}/${configName}: ${warning}`));
+    return result.betas.length > 0 ? result.betas : undefined;
+  }
+
+  private parseRetryBudget(data: any): RetryBudgetConfig | undefined {
+    if (!data || typeof data !== 'object') {
+      return undefined;
+    }
+
+    return {
+      ratio: typeof data.ratio === 'number' ? data.ratio : DEFAULT_RETRY_BUDGET.ratio,
+      windowMs: typeof data.window_ms === 'number' ? data.window_ms : DEFAULT_RETRY_BUDGET.windowMs,
+      minRetries: typeof data.min_retries === 'number' ? data.min_retries : DEFAULT_RETRY_BUDGET.minRetries,
+    };
+  }
+
+  private parseOutageQueue(data: any): OutageQueueConfig | undefined {
+    if (!data || typeof data !== 'object') {
+      return undefined;
+    }
+
+    const paths = Array.isArray(data.paths)
+      ? data.paths.filter((path: unknown): path is string => typeof path === 'string' && path.startsWith('/'))
+      : DEFAULT_QUEUE_PATHS;
+    return {
+      enabled: data.enabled === true,
+      paths,
+      maxAttempts: typeof data.max_attempts === 'number' && data.max_attempts > 0 ? data.max_attempts : 20,
+      maxItems: typeof data.max_items === 'number' && data.max_items > 0 ? data.max_items : 1000,
+    };
+  }
+
+  private parseResponseHeaderPolicy(data: any): ResponseHeaderPolicy | undefined {
+    if (!data || typeof data !== 'object') {
+      return undefined;
+    }
+
+    const toList = (value: unknown): string[] | undefined =>
+      Array.isArray(value) ? value.filter((item): item is string => typeof item === 'string') : undefined;
+
+    return {
+      allow: toList(data.allow),
+      deny: toList(data.deny),
+    };
+  }
+
+  getConfigDir(): string {
+    return this.configDir;
   }
 
   getSystemConfig(): SystemConfig {
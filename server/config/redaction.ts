@@ -0,0 +1,107 @@
+// Secret redaction - configs leave the server without their credentials
+
+import { timingSafeEqual } from 'crypto';
+import type { ProxyConfig } from './types';
+import { serializeFingerprint } from '../proxy/fingerprint';
+import { serializeContextTrim } from '../proxy/contextTrim';
+import { serializeQuotaConfig } from '../monitoring/quota';
+import { serializeQuirks } from '../proxy/quirks';
+import { serializePromptCache } from '../proxy/promptCache';
+import { serializeRequestCompression } from '../proxy/requestCompression';
+import { serializeAvailability } from '../routing/schedule';
+import { maskProxyUrl } from '../proxy/network';
+
+export interface RedactedProxyConfig {
+  name: string;
+  base_url: string;
+  weight: number;
+  enabled: boolean;
+  freeze_until?: number;
+  has_api_key: boolean;
+  has_auth_token: boolean;
+  api_key_hint?: string;
+  auth_token_hint?: string;
+  fingerprint?: Record<string, unknown>;
+  anthropic_beta?: string[];
+  context_trim?: Record<string, unknown>;
+  quota?: Record<string, unknown>;
+  response_language?: string;
+  provider_profile?: string;
+  quirks?: Record<string, unknown>;
+  upstream_compression?: boolean;
+  prompt_cache?: Record<string, unknown>;
+  availability?: Record<string, unknown>;
+  api_format?: string;
+  request_compression?: Record<string, unknown>;
+  requests_per_minute?: number;
+  tokens_per_minute?: number;
+  proxy_url?: string; // Password masked
+  model_map?: Record<string, string>;
+  timeout_secs?: number;
+  connect_timeout_secs?: number;
+}
+
+/**
+ * Mask a secret, keeping only a short suffix when the value is long enough to stay unguessable
+ */
+export function maskSecret(value: string | undefined): string | undefined {
+  if (!value) {
+    return undefined;
+  }
+  const suffix = value.length >= 12 ? value.slice(-4) : '';
+  return `••••${suffix}`;
+}
+
+/**
+ * Convert a config into the browser-facing shape without credentials
+ */
+export function redactConfig(config: ProxyConfig): RedactedProxyConfig {
+  return {
+    name: config.name,
+    base_url: config.baseUrl,
+    weight: config.weight,
+    enabled: config.enabled !== false,
+    freeze_until: config.freezeUntil,
+    has_api_key: Boolean(config.apiKey),
+    has_auth_token: Boolean(config.authToken),
+    api_key_hint: maskSecret(config.apiKey),
+    auth_token_hint: maskSecret(config.authToken),
+    fingerprint: serializeFingerprint(config.fingerprint),
+    anthropic_beta: config.anthropicBeta,
+    context_trim: serializeContextTrim(config.contextTrim),
+    quota: serializeQuotaConfig(config.quota),
+    response_language: config.responseLanguage,
+    provider_profile: config.providerProfile,
+    quirks: serializeQuirks(config.quirks),
+    upstream_compression: config.upstreamCompression,
+    prompt_cache: serializePromptCache(config.promptCache),
+    availability: serializeAvailability(config.availability),
+    api_format: config.apiFormat,
+    request_compression: serializeRequestCompression(config.requestCompression),
+    requests_per_minute: config.requestsPerMinute,
+    tokens_per_minute: config.tokensPerMinute,
+    proxy_url: maskProxyUrl(config.proxyUrl),
+    model_map: config.modelMap,
+    timeout_secs: config.timeoutSecs,
+    connect_timeout_secs: config.connectTimeoutSecs,
+  };
+}
+
+/**
+ * Check a request's bearer token against the configured admin token
+ */
+export function isAdminRequest(req: Request, adminToken: string | undefined): boolean {
+  if (!adminToken) {
+    return false;
+  }
+
+  const header = req.headers.get('authorization') || '';
+  const match = header.match(/^Bearer\s+(.+)$/i);
+  if (!match) {
+    return false;
+  }
+
+  const provided = Buffer.from(match[1].trim());
+  const expected = Buffer.from(adminToken);
+  return provided.length === expected.length && timingSafeEqual(provided, expected);
+}
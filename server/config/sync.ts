@@ -0,0 +1,170 @@
+// Config sync - encrypted snapshots of service configs exchanged between paf instances
+
+import { createCipheriv, createDecipheriv, randomBytes, scryptSync } from 'crypto';
+import type { ConfigManager } from './manager';
+import type { ProxyConfig, ServiceConfig } from './types';
+
+const SYNC_SERVICES = ['claude', 'codex'] as const;
+const BUNDLE_VERSION = 1;
+
+export interface SyncBundle {
+  v: number;
+  exportedAt: number;
+  services: Record<string, { mode: ServiceConfig['mode']; configs: ProxyConfig[] }>;
+}
+
+export interface SyncChange {
+  service: string;
+  config: string;
+  kind: 'added' | 'changed' | 'removed';
+  fields?: string[]; // Changed fields; credentials are named but never shown
+}
+
+interface EncryptedBundle {
+  v: number;
+  salt: string;
+  iv: string;
+  tag: string;
+  data: string;
+}
+
+/**
+ * Snapshot every service's configs, credentials included; freezes are runtime state and stay local
+ */
+export function exportSyncBundle(configManager: ConfigManager): SyncBundle {
+  const services: SyncBundle['services'] = {};
+  for (const service of SYNC_SERVICES) {
+    const serviceConfig = configManager.getServiceConfig(service);
+    if (serviceConfig) {
+      services[service] = {
+        mode: serviceConfig.mode,
+        configs: serviceConfig.configs.map(({ freezeUntil: _freezeUntil, ...config }) => config),
+      };
+    }
+  }
+  return { v: BUNDLE_VERSION, exportedAt: Date.now(), services };
+}
+
+/**
+ * What applying `remote` would change locally; configs only present locally count as removed
+ */
+export function diffSyncBundle(local: SyncBundle, remote: SyncBundle): SyncChange[] {
+  const changes: SyncChange[] = [];
+  for (const service of SYNC_SERVICES) {
+    const localConfigs = new Map((local.services[service]?.configs ?? []).map(config => [config.name, config]));
+    const remoteConfigs = remote.services[service]?.configs ?? [];
+
+    for (const config of remoteConfigs) {
+      const current = localConfigs.get(config.name);
+      if (!current) {
+        changes.push({ service, config: config.name, kind: 'added' });
+        continue;
+      }
+      const keys = new Set([...Object.keys(current), ...Object.keys(config)]) as Set<keyof ProxyConfig>;
+      const fields = [...keys].filter(key => JSON.stringify(current[key]) !== JSON.stringify(config[key]));
+      if (fields.length > 0) {
+        changes.push({ service, config: config.name, kind: 'changed', fields });
+      }
+    }
+
+    for (const name of localConfigs.keys()) {
+      if (!remoteConfigs.some(config => config.name === name)) {
+        changes.push({ service, config: name, kind: 'removed' });
+      }
+    }
+  }
+  return changes;
+}
+
+/**
+ * Apply only what differs. Local configs missing from the bundle are kept unless `prune` is set,
+ * and local freezes survive an update of the same config.
+ */
+export async function applySyncBundle(
+  configManager: ConfigManager,
+  remote: SyncBundle,
+  options: { prune?: boolean; dryRun?: boolean } = {}
+): Promise<SyncChange[]> {
+  const changes = diffSyncBundle(exportSyncBundle(configManager), remote).filter(
+    change => change.kind !== 'removed' || options.prune
+  );
+  if (options.dryRun || changes.length === 0) {
+    return changes;
+  }
+
+  for (const service of SYNC_SERVICES) {
+    const serviceChanges = changes.filter(change => change.service === service);
+    const serviceConfig = configManager.getServiceConfig(service);
+    const incoming = remote.services[service];
+    if (serviceChanges.length === 0 || !serviceConfig || !incoming) {
+      continue;
+    }
+
+    let configs = [...serviceConfig.configs];
+    for (const change of serviceChanges) {
+      const remoteConfig = incoming.configs.find(config => config.name === change.config);
+      if (change.kind === 'removed') {
+        configs = configs.filter(config => config.name !== change.config);
+      } else if (change.kind === 'added' && remoteConfig) {
+        configs.push(remoteConfig);
+      } else if (remoteConfig) {
+        configs = configs.map(config =>
+          config.name === change.config ? { ...remoteConfig, freezeUntil: config.freezeUntil } : config
+        );
+      }
+    }
+    await configManager.saveServiceConfig(service, { ...serviceConfig, configs });
+  }
+  return changes;
+}
+
+function deriveKey(passphrase: string, salt: Buffer): Buffer {
+  return scryptSync(passphrase, salt, 32);
+}
+
+/**
+ * AES-256-GCM with a scrypt-derived key; the result is safe to park on shared storage
+ */
+export function encryptSyncBundle(bundle: SyncBundle, passphrase: string): string {
+  const salt = randomBytes(16);
+  const iv = randomBytes(12);
+  const cipher = createCipheriv('aes-256-gcm', deriveKey(passphrase, salt), iv);
+  const data = Buffer.concat([cipher.update(JSON.stringify(bundle), 'utf8'), cipher.final()]);
+  const envelope: EncryptedBundle = {
+    v: BUNDLE_VERSION,
+    salt: salt.toString('base64'),
+    iv: iv.toString('base64'),
+    tag: cipher.getAuthTag().toString('base64'),
+    data: data.toString('base64'),
+  };
+  return JSON.stringify(envelope);
+}
+
+export function decryptSyncBundle(text: string, passphrase: string): SyncBundle | { error: string } {
+  let envelope: EncryptedBundle;
+  try {
+    envelope = JSON.parse(text);
+  } catch {
+    return { error: 'Sync bundle is not valid JSON' };
+  }
+  if (envelope?.v !== BUNDLE_VERSION || !envelope.salt || !envelope.iv || !envelope.tag || !envelope.data) {
+    return { error: 'Unsupported sync bundle format' };
+  }
+
+  try {
+    const decipher = createDecipheriv(
+      'aes-256-gcm',
+      deriveKey(passphrase, Buffer.from(envelope.salt, 'base64')),
+      Buffer.from(envelope.iv, 'base64')
+    );
+    decipher.setAuthTag(Buffer.from(envelope.tag, 'base64'));
+    const plain = Buffer.concat([decipher.update(Buffer.from(envelope.data, 'base64')), decipher.final()]);
+    const bundle = JSON.parse(plain.toString('utf8')) as SyncBundle;
+    if (bundle?.v !== BUNDLE_VERSION || typeof bundle.services !== 'object') {
+      return { error: 'Unsupported sync bundle version' };
+    }
+    return bundle;
+  } catch {
+    return { error: 'Could not decrypt sync bundle (wrong passphrase?)' };
+  }
+}
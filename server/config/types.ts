@@ -9,10 +9,94 @@ export interface ProxyConfig {
   weight: number;
   enabled: boolean;
   freezeUntil?: number; // Unix timestamp in milliseconds
+  fingerprint?: ClientFingerprint; // Replaces the service-level fingerprint for this config
+  anthropicBeta?: string[];        // Claude only: merged into the anthropic-beta header of every request
+  contextTrim?: ContextTrimConfig; // Shrink prompts that exceed this config's context window
+  quota?: QuotaConfig;             // Poll the provider's balance endpoint for this config
+  responseLanguage?: string;       // Append a system instruction to always answer in this language, e.g. "Chinese"
+  providerProfile?: string;        // Built-in quirk profile (see PROVIDER_PROFILES); replaces the service-level one
+  quirks?: ProviderQuirks;         // Individual quirk toggles on top of the profile
+  upstreamCompression?: boolean;   // false sends accept-encoding: identity on every request to this config
+  promptCache?: PromptCacheConfig; // Claude only: add cache_control breakpoints when the client sets none
+  availability?: AvailabilitySchedule; // Only selected within these local time windows; always when unset
+  apiFormat?: ApiFormat;           // The upstream speaks this API instead of the one the service's clients use
+  requestCompression?: RequestCompressionConfig; // gzip request bodies; only for upstreams that accept content-encoding: gzip
+  requestsPerMinute?: number;      // Skip this config (or wait briefly) once it took this many requests in a minute
+  tokensPerMinute?: number;        // Likewise for input plus output tokens, estimated until the upstream reports usage
+  proxyUrl?: string;               // HTTP(S) proxy for requests to this upstream; replaces the service-level one
+  modelMap?: Record<string, string>; // Client model -> this upstream's model id, rewritten in the body before forwarding
+  timeoutSecs?: number;            // Abort a request to this upstream after this long, response body included
+  connectTimeoutSecs?: number;     // Abort when no response headers arrived within this long
+}
+
+// openai-chat (claude configs): /v1/messages calls are translated to and from OpenAI Chat Completions;
+// anthropic (codex configs): Chat Completions and Responses calls to and from Messages (see proxy/translation.ts)
+export type ApiFormat = 'openai-chat' | 'anthropic';
+
+export interface AvailabilitySchedule {
+  timezone?: string; // IANA name, e.g. "Europe/Berlin"; the server's local time when unset
+  windows: AvailabilityWindow[];
+}
+
+export interface AvailabilityWindow {
+  days: number[]; // 0 = Sunday
+  start: string;  // "HH:MM"
+  end: string;    // "HH:MM"; before start for windows that run past midnight
+}
+
+export interface RequestCompressionConfig {
+  minBytes: number; // Bodies smaller than this are sent uncompressed
+}
+
+export interface PromptCacheConfig {
+  minTokens: number; // Estimated prefix size (tools + system) below which no breakpoint is added
+  ttl?: '1h';        // Extended cache lifetime; the provider default (5 minutes) when unset
+}
+
+// passthrough forwards the client's headers unchanged; override/strip replace or remove them
+export type FingerprintMode = 'passthrough' | 'override' | 'strip';
+
+export interface ClientFingerprint {
+  mode: FingerprintMode;
+  userAgent?: string;                  // Sent in override mode
+  stainless?: Record<string, string>;  // x-stainless-<key> headers sent in override mode, e.g. { lang: 'js' }
+}
+
+// drop removes the oldest non-system messages; summarize replaces them with a summary from summaryModel
+export type ContextTrimStrategy = 'drop' | 'summarize';
+
+export interface ContextTrimConfig {
+  limit: number;          // Estimated prompt tokens allowed before trimming
+  strategy: ContextTrimStrategy;
+  summaryModel?: string;  // Cheap model on the same config used by the summarize strategy
+}
+
+// openai-billing: /v1/dashboard/billing/* served by one-api/new-api style relays
+export type QuotaProvider = 'openai-billing' | 'openrouter' | 'deepseek' | 'custom';
+
+export interface QuotaConfig {
+  provider: QuotaProvider;
+  url?: string;         // Overrides the provider's endpoint; custom only: absolute or relative to base_url
+  balancePath?: string; // custom only: dot path to the remaining balance in the JSON response
+  unit?: string;
+  warnBelow?: number;   // Flag the config as low once the remaining balance drops below this
+}
+
+export interface RetryBudgetConfig {
+  ratio: number;      // Retries allowed as a fraction of requests in the window (0.2 = 20%)
+  windowMs: number;   // Sliding window used to measure recent volume
+  minRetries: number; // Floor so low-traffic configs can still retry occasionally
+}
+
+export interface OutageQueueConfig {
+  enabled: boolean;
+  paths: string[];     // Path prefixes whose POSTs may be queued, e.g. /v1/embeddings
+  maxAttempts: number; // Replays before a queued request is marked failed
+  maxItems: number;    // Pending requests kept per service; beyond it requests fail as usual
 }
 
 export interface LoadBalancerConfig {
-  strategy: 'weighted' | 'round-robin';
+  strategy: string; // 'weighted', 'round-robin' or a strategy registered through the library API
   healthCheck: {
     enabled: boolean;
     interval: number; // milliseconds
@@ -21,6 +105,12 @@ export interface LoadBalancerConfig {
     successThreshold: number;
   };
   freezeDuration: number; // milliseconds, default 5 minutes (300000)
+  retryBudget?: RetryBudgetConfig;
+}
+
+export interface ResponseHeaderPolicy {
+  allow?: string[]; // When non-empty, only these headers (plus content-type) are forwarded; `x-foo-*` wildcards allowed
+  deny?: string[];  // Replaces the built-in CDN deny-list when set
 }
 
 export interface ServiceConfig {
@@ -28,6 +118,124 @@ export interface ServiceConfig {
   active: string;
   mode: 'manual' | 'load_balance';
   loadBalancer: LoadBalancerConfig;
+  responseHeaders?: ResponseHeaderPolicy;
+  resumeInterruptedStreams?: boolean; // Continue a cut stream on another config when the protocol allows
+  keepaliveIntervalSecs?: number; // Inject `: ping` SSE comments after this much upstream silence, 0 disables
+  maxTokensContinuations?: number; // Follow-up requests that extend a stream stopped by max_tokens, 0 disables
+  thinkingContent?: 'capture' | 'strip'; // Keep (default) or drop thinking blocks in stored request/response previews
+  captureRequestBodies?: boolean; // Store each request body as sent upstream, making session exports replayable
+  validateRequests?: boolean; // Reject Messages / Responses bodies that fail the provider's request schema with a local 400
+  validateResponses?: boolean; // Check successful responses for protocol violations, see /api/stats/compliance
+  fingerprint?: ClientFingerprint; // Default user-agent / SDK header handling for configs without their own
+  outageQueue?: OutageQueueConfig; // Store-and-retry for non-interactive requests while every config is down
+  modelOverride?: ModelOverrideConfig; // Default model and deprecated-alias rewrites applied to request bodies
+  clientKeyLanguages?: Record<string, string>; // Inbound client key -> response language; beats the config's own
+  providerProfile?: string; // Quirk profile for configs without their own
+  upstreamCompression?: UpstreamCompressionConfig; // Whether upstreams may compress responses; both on when unset
+  pools?: ConfigPool[]; // Model family pools; load_balance mode routes matching requests within their pool
+  maxConcurrentRequests?: number; // In-flight requests to this service; beyond it clients get a 429
+  maxRequestBodyBytes?: number; // Larger request bodies are refused with a 413 before being buffered
+  proxyUrl?: string; // HTTP(S) proxy for configs without their own proxy_url
+  configOverride?: ConfigOverridePolicy; // Configs a request may force with x-paf-config; the header is refused when unset
+  timeoutSecs?: number; // timeout_secs for configs without their own
+  connectTimeoutSecs?: number; // connect_timeout_secs for configs without their own
+  noLogClientKeys?: string[]; // Inbound client keys whose requests are handled as x-paf-no-log
+}
+
+// Configs serving one model family, e.g. sonnet-pool for claude-sonnet-*; weights and health are per pool
+export interface ConfigPool {
+  name: string;
+  models: string[]; // Requested model patterns, `*` matches anything; the first matching pool wins
+  members: Array<{ config: string; weight: number }>;
+}
+
+// Some relays send broken gzip for SSE; turning a kind off forces accept-encoding: identity for it
+export interface UpstreamCompressionConfig {
+  streaming: boolean;
+  nonStreaming: boolean;
+}
+
+// Request body rewrites for upstreams that reject parts of the current APIs
+export interface ProviderQuirks {
+  stripFields?: string[];   // Top-level body fields removed before forwarding
+  contentBlocks?: string[]; // Content block types the upstream accepts; others are turned into text blocks
+  toolChoice?: 'keep' | 'basic'; // basic: forced-tool and allowed_tools variants become "required"/"any"
+  maxTokensField?: 'max_tokens' | 'max_completion_tokens'; // Chat Completions name the upstream expects
+}
+
+export interface ConfigOverridePolicy {
+  allow: string[]; // Config names, or "*" for any
+  token?: string;  // Also require x-paf-config-token to match
+}
+
+export interface ModelOverrideConfig {
+  defaultModel?: string;          // Used when a generation request names no model
+  aliases: Record<string, string>; // Requested model -> model sent upstream, e.g. claude-3-sonnet -> claude-3-5-sonnet-latest
+}
+
+export type WebhookSignatureScheme = 'standard' | 'hmac-sha256';
+
+export interface WebhookProviderConfig {
+  provider: string;               // Path segment in /api/webhooks/<provider>
+  secret: string;
+  scheme: WebhookSignatureScheme; // 'standard' = Standard Webhooks (webhook-id/-timestamp/-signature, used by OpenAI)
+  signatureHeader: string;        // Header carrying the hex digest for 'hmac-sha256'
+  toleranceSeconds: number;       // Max clock skew accepted for signed timestamps
+  service: string;                // Realtime hub for callbacks that match no logged request
+  idPath?: string;                // Dotted path to the upstream object id in the payload (default data.id, then id)
+}
+
+export type DlpAction = 'redact' | 'block' | 'flag';
+
+export interface DlpRule {
+  name: string;
+  pattern?: string;    // Regular expression
+  keywords?: string[]; // Matched case-insensitively as literal text
+  action: DlpAction;
+}
+
+export interface DlpConfig {
+  rules: DlpRule[];
+  moderation?: {
+    url: string;               // OpenAI-compatible moderation endpoint ({ input } -> { results: [{ flagged }] })
+    apiKey?: string;
+    action: 'block' | 'flag';
+    timeoutMs: number;
+    failOpen: boolean;         // Forward the request when the endpoint errors or times out
+  };
+}
+
+export interface LogScrubbingConfig {
+  emails: boolean;
+  phoneNumbers: boolean;
+  awsKeys: boolean;
+  jwts: boolean;
+}
+
+export interface TenantConfig {
+  name: string;
+  clientKeys: string[]; // Inbound keys that route a request to this tenant on the shared proxy ports
+  clientCerts: string[]; // Client identities (from [proxy_tls] certificates) that route to this tenant likewise
+  proxyPorts?: {        // Optional dedicated ports that always belong to this tenant
+    claude?: number;
+    codex?: number;
+  };
+  budget?: TenantBudgetConfig; // Token allowance; once spent, the tenant's proxy requests get 429 until the period resets
+}
+
+export interface TenantBudgetConfig {
+  tokens: number;          // Input plus output tokens per period
+  period: 'day' | 'month'; // Calendar periods in UTC
+}
+
+/**
+ * HTTPS on the dedicated proxy ports; with a client CA, clients must present a certificate it signed (mTLS)
+ */
+export interface ProxyTlsConfig {
+  cert: string;      // PEM file paths
+  key: string;
+  clientCa?: string;
+  identities: Record<string, string>; // Certificate CN -> client identity; unmapped CNs are their own identity
 }
 
 export interface SystemConfig {
@@ -36,6 +244,52 @@ export interface SystemConfig {
     claude: number;
     codex: number;
   };
-  logLevel: 'debug' | 'info' | 'warn' | 'error';
+  singlePort: boolean; // Serve /claude/*, /codex/* and /ui/* from webPort instead of dedicated proxy ports
+  readOnly: boolean; // Reject every mutating management request (dashboard/stats stay viewable)
+  lite: boolean; // No dashboard, JSONL request logs instead of requests.db, capped body buffers (see config/lite.ts)
+  logLevel: string; // Level or per-prefix filter, e.g. "info" or "warn,proxy=debug" (see logging/logFilter.ts)
   dataDir: string;
+  logRetentionDays: number; // Request logs older than this are deleted; 0 keeps everything
+  passthroughBodyBytes: number; // Non-streaming bodies above this size are forwarded unbuffered; 0 always buffers
+  bodyMemoryLimitBytes: number; // Cap on body bytes buffered across all requests at once; 0 only tracks usage
+  maxConcurrentRequests: number; // In-flight proxied requests across all services; beyond it clients get a 429, 0 removes the cap
+  adminToken?: string; // Bearer token for privileged management endpoints
+  realtime: {
+    replayMinutes: number; // completed-request events replayed to new WebSocket clients, 0 disables
+  };
+  network: {
+    ipFamily: 'auto' | 'ipv4' | 'ipv6'; // Preferred address family when upstream hosts resolve to both
+    warmUp: boolean; // Pre-open upstream connections at startup and whenever the active config changes
+    bindAddress?: string; // Local address or interface name outbound upstream sockets leave from
+  };
+  tenants: TenantConfig[]; // Isolated config/log namespaces stored under <dataDir>/tenants/<name>
+  cluster: {
+    peers: string[];        // Web UI base URLs of other paf instances sharing load balancer state
+    secret?: string;        // Bearer token peers must present to read /api/cluster/state
+    syncIntervalMs: number;
+  };
+  webhooks: WebhookProviderConfig[]; // Providers allowed to POST async callbacks to /api/webhooks/<provider>
+  dlp: DlpConfig; // Outbound request body scanning, applied to every service and tenant
+  logScrubbing: LogScrubbingConfig; // PII masked in request logs before they are stored
+  modelListCache: {
+    ttlSeconds: number; // How long GET /v1/models responses are reused per config, 0 disables caching and coalescing
+  };
+  shapeCache: {
+    threshold: number;  // Consecutive 400s blaming the same request feature before a config is assumed not to support it, 0 disables
+    ttlSeconds: number; // How long such a rejection is remembered before the config is tried again
+  };
+  responseCache: {
+    ttlSeconds: number; // How long identical non-streaming requests are answered from a stored response, 0 disables
+    maxBytes: number;   // Total response body bytes kept; least recently used entries are evicted beyond it
+  };
+  tlsMonitor: {
+    enabled: boolean;
+    intervalMinutes: number;   // How often every https upstream host is inspected
+    expiryWarningDays: number; // Certificates expiring within this many days are reported
+    notifyUrl?: string;        // Receives a JSON POST per new warning, like alert rules
+  };
+  sync: {
+    passphrase?: string; // Encrypts config bundles for `paf sync`; sync endpoints stay off without it
+  };
+  proxyTls?: ProxyTlsConfig;
 }
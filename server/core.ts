@@ -0,0 +1,234 @@
+// Proxy core - wires config, load balancers, logging and proxy services together
+
+import { ConfigManager } from './config/manager';
+import { createDefaultServiceConfig } from './config/defaults';
+import { ExperimentRegistry } from './experiments/registry';
+import { PromptLibrary } from './prompts/library';
+import { LogMonitor } from './monitoring/alerts';
+import { RequestLogger } from './logging/logger';
+import { LoadBalancer } from './routing/loadbalancer';
+import type { ProxyConfig } from './config/types';
+import { ConfigRateLimiter } from './routing/rateLimiter';
+import type { ProxyService } from './proxy/baseProxyService';
+import { ClaudeProxyService } from './proxy/claudeProxyService';
+import { CodexProxyService } from './proxy/codexProxyService';
+import type { ConnectionStats } from './proxy/connectionStats';
+import { DlpFilter } from './proxy/dlp';
+import { ModelListCache } from './proxy/modelListCache';
+import type { BodyMemoryBudget } from './proxy/memoryBudget';
+import { ConcurrencyLimiter } from './proxy/concurrency';
+import { OutageQueue } from './proxy/outageQueue';
+import { RequestShapeCache, type ShapeCacheConfig } from './proxy/shapeCache';
+import { ResponseCache, type ResponseCacheConfig } from './proxy/responseCache';
+import { QuotaMonitor } from './monitoring/quota';
+import { ReplayRunner } from './replay/replay';
+import { HookEventFeed, HookSessions } from './hooks/claudeCode';
+
+export type ServiceName = 'claude' | 'codex';
+
+export const SERVICE_NAMES: ReadonlyArray<ServiceName> = ['claude', 'codex'];
+
+// Answered by the proxy listener itself (never forwarded), for `paf health` and orchestrator probes
+export const SERVICE_HEALTH_PATH = '/paf/health';
+
+// Also answered by the proxy listener: which config the service's traffic goes to, for a quick curl
+export const SERVICE_WHOAMI_PATH = '/api/whoami';
+
+// degraded: no config can take a request right now (none configured, or all disabled, frozen or failing)
+export type ServiceHealthState = 'ok' | 'degraded';
+
+export interface ServiceHealth {
+  state: ServiceHealthState;
+  configs: number;
+  usable: string[];
+}
+
+export interface ProxyCore {
+  configManager: ConfigManager;
+  logger: RequestLogger;
+  experiments: ExperimentRegistry;
+  prompts: PromptLibrary;
+  monitor: LogMonitor;
+  dlp: DlpFilter;
+  modelListCache: ModelListCache;
+  outageQueue: OutageQueue;
+  replays: ReplayRunner;
+  shapeCaches: Record<ServiceName, RequestShapeCache>;
+  responseCache: ResponseCache;
+  rateLimiters: Record<ServiceName, ConfigRateLimiter>;
+  concurrencyLimiters: Record<ServiceName, ConcurrencyLimiter>;
+  quota: QuotaMonitor;
+  hookEvents: HookEventFeed;
+  hookSessions: HookSessions;
+  loadBalancers: Record<ServiceName, LoadBalancer>;
+  proxies: Record<ServiceName, ProxyService>;
+}
+
+/**
+ * Load both service configs, writing the commented starter TOML for any that don't exist yet
+ */
+export async function ensureServiceConfigs(configManager: ConfigManager, verbose = false): Promise<void> {
+  for (const serviceName of SERVICE_NAMES) {
+    await configManager.loadServiceConfig(serviceName).catch(async () => {
+      if (verbose) {
+        console.log(`${serviceName} config not found, writing template (run \`bunx proxy-ai-fusion init\` to add one)...`);
+      }
+      await configManager.writeServiceConfigTemplate(serviceName);
+      await configManager.loadServiceConfig(serviceName);
+    });
+  }
+}
+
+export function serviceHealth(core: ProxyCore, serviceName: ServiceName): ServiceHealth {
+  const servers = core.configManager.getAllConfigs(serviceName);
+  const pinned = core.loadBalancers[serviceName].getPinnedServer(servers);
+  const usable = pinned ? [pinned] : core.loadBalancers[serviceName].usableServers(servers);
+  return {
+    state: usable.length > 0 ? 'ok' : 'degraded',
+    configs: servers.length,
+    usable: usable.map(server => server.name),
+  };
+}
+
+/**
+ * The config the next request would most likely use, without touching balancer state: the pin, the
+ * active config in manual mode, else the balancer's current choice. Null when load balancing hasn't
+ * settled on one yet.
+ */
+export function selectedConfig(
+  core: ProxyCore,
+  serviceName: ServiceName
+): { config: ProxyConfig; via: 'pin' | 'active' | 'load_balancer' } | null {
+  const servers = core.configManager.getAllConfigs(serviceName);
+  const balancer = core.loadBalancers[serviceName];
+  const pinned = balancer.getPinnedServer(servers);
+  if (pinned) {
+    return { config: pinned, via: 'pin' };
+  }
+  if (core.configManager.getServiceConfig(serviceName)?.mode !== 'load_balance') {
+    return servers[0] ? { config: servers[0], via: 'active' } : null;
+  }
+  const current = servers.find(server => server.name === balancer.getCurrentServerName());
+  return current ? { config: current, via: 'load_balancer' } : null;
+}
+
+/**
+ * Build load balancers and proxy services on top of loaded service configs; pass `dlp` to share
+ * one filter across cores, and `modelListTtlSeconds` / `passthroughBodyBytes` / `shapeCacheConfig` /
+ * `responseCacheConfig` to override system.toml (tenants follow the top-level rules). Model list and
+ * response caches are never shared, since tenants hold different credentials; the memory budget and
+ * the max_concurrent_requests limiter, like connection stats, are process-wide.
+ */
+export function createProxyCore(
+  configManager: ConfigManager,
+  logger: RequestLogger,
+  connectionStats?: ConnectionStats,
+  dlp = new DlpFilter(configManager.getSystemConfig().dlp),
+  modelListTtlSeconds = configManager.getSystemConfig().modelListCache.ttlSeconds,
+  passthroughBodyBytes = configManager.getSystemConfig().passthroughBodyBytes,
+  memoryBudget?: BodyMemoryBudget,
+  shapeCacheConfig: ShapeCacheConfig = configManager.getSystemConfig().shapeCache,
+  responseCacheConfig: ResponseCacheConfig = configManager.getSystemConfig().responseCache,
+  concurrency?: ConcurrencyLimiter
+): ProxyCore {
+  const modelListCache = new ModelListCache(modelListTtlSeconds * 1000);
+  // Learned per config, so each service keeps its own
+  const shapeCaches: Record<ServiceName, RequestShapeCache> = {
+    claude: new RequestShapeCache(shapeCacheConfig),
+    codex: new RequestShapeCache(shapeCacheConfig),
+  };
+  // One per core, persisted in that core's requests.db; keys include the service
+  const responseCache = new ResponseCache(responseCacheConfig, logger);
+  // requests_per_minute / tokens_per_minute buckets; estimates are replaced by reported usage once logged
+  const rateLimiters: Record<ServiceName, ConfigRateLimiter> = {
+    claude: new ConfigRateLimiter(),
+    codex: new ConfigRateLimiter(),
+  };
+  // Each service's max_concurrent_requests, re-read on every request so config edits apply at once
+  const concurrencyLimiters: Record<ServiceName, ConcurrencyLimiter> = {
+    claude: new ConcurrencyLimiter(() => configManager.getServiceConfig('claude')?.maxConcurrentRequests ?? 0),
+    codex: new ConcurrencyLimiter(() => configManager.getServiceConfig('codex')?.maxConcurrentRequests ?? 0),
+  };
+  // Claude Code hook callbacks: events they are told about, and task tags per session
+  const hookEvents = new HookEventFeed();
+  const hookSessions = new HookSessions();
+  logger.onRequestLogged(log => {
+    if (log.service === 'claude' || log.service === 'codex') {
+      const reported = log.inputTokens !== undefined || log.outputTokens !== undefined;
+      rateLimiters[log.service].settle(log.id, reported ? (log.inputTokens ?? 0) + (log.outputTokens ?? 0) : undefined);
+      hookEvents.observeRequest(log);
+    }
+  });
+  const outageQueue = new OutageQueue(logger);
+  const experiments = new ExperimentRegistry(logger);
+  const prompts = new PromptLibrary(logger);
+  const monitor = new LogMonitor(logger);
+  const loadBalancers: Record<ServiceName, LoadBalancer> = {
+    claude: new LoadBalancer(
+      configManager.getServiceConfig('claude')?.loadBalancer ?? createDefaultServiceConfig().loadBalancer
+    ),
+    codex: new LoadBalancer(
+      configManager.getServiceConfig('codex')?.loadBalancer ?? createDefaultServiceConfig().loadBalancer
+    ),
+  };
+
+  return {
+    configManager,
+    logger,
+    experiments,
+    prompts,
+    monitor,
+    dlp,
+    modelListCache,
+    outageQueue,
+    replays: new ReplayRunner(),
+    shapeCaches,
+    responseCache,
+    rateLimiters,
+    concurrencyLimiters,
+    quota: new QuotaMonitor(),
+    hookEvents,
+    hookSessions,
+    loadBalancers,
+    proxies: {
+      claude: new ClaudeProxyService({
+        loadBalancer: loadBalancers.claude,
+        logger,
+        configManager,
+        connectionStats,
+        experiments,
+        prompts,
+        dlp,
+        modelListCache,
+        passthroughBodyBytes,
+        memoryBudget,
+        outageQueue,
+        shapeCache: shapeCaches.claude,
+        responseCache,
+        rateLimiter: rateLimiters.claude,
+        concurrency,
+        serviceConcurrency: concurrencyLimiters.claude,
+        hookSessions,
+      }),
+      codex: new CodexProxyService({
+        loadBalancer: loadBalancers.codex,
+        logger,
+        configManager,
+        connectionStats,
+        experiments,
+        prompts,
+        dlp,
+        modelListCache,
+        passthroughBodyBytes,
+        memoryBudget,
+        outageQueue,
+        shapeCache: shapeCaches.codex,
+        responseCache,
+        rateLimiter: rateLimiters.codex,
+        concurrency,
+        serviceConcurrency: concurrencyLimiters.codex,
+        hookSessions,
+      }),
+    },
+  };
+}
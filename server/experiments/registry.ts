@@ -0,0 +1,218 @@
+// Experiment registry - time-boxed A/B traffic splits between two configs of a service
+
+import type { ProxyConfig } from '../config/types';
+import {
+  differenceOfMeans,
+  differenceOfRates,
+  percentile,
+  summarizeMean,
+  summarizeRate,
+  type MeanSummary,
+  type RateSummary,
+} from './stats';
+
+export interface Experiment {
+  id: string;
+  name: string;
+  service: string;
+  arms: [string, string]; // config names for arm 'a' and arm 'b'
+  split: number;          // share of traffic sent to arm 'a' (0-1)
+  startsAt: number;
+  endsAt: number;
+  createdAt: number;
+}
+
+export interface ExperimentSample {
+  arm: string;
+  duration?: number;
+  failed: boolean;
+  inputTokens?: number;
+  outputTokens?: number;
+}
+
+export interface ExperimentStore {
+  saveExperiment(experiment: Experiment): void;
+  listExperiments(): Experiment[];
+  deleteExperiment(id: string): boolean;
+  getExperimentSamples(experimentId: string): ExperimentSample[];
+}
+
+export interface ExperimentAssignment {
+  experimentId: string;
+  arm: 'a' | 'b';
+  server: ProxyConfig;
+}
+
+export interface ArmResults {
+  config: string;
+  requests: number;
+  latencyMs: MeanSummary & { p50: number | null; p95: number | null };
+  errorRate: RateSummary;
+  inputTokens: MeanSummary;
+  outputTokens: MeanSummary;
+}
+
+export interface ExperimentResults {
+  experiment: Experiment;
+  status: 'scheduled' | 'running' | 'finished';
+  arms: { a: ArmResults; b: ArmResults };
+  // b minus a; an interval that excludes 0 indicates a difference at the 95% level
+  differences: {
+    latencyMs: ReturnType<typeof differenceOfMeans>;
+    errorRate: ReturnType<typeof differenceOfRates>;
+    outputTokens: ReturnType<typeof differenceOfMeans>;
+  };
+}
+
+export interface CreateExperimentInput {
+  name?: string;
+  service: string;
+  arms: [string, string];
+  split?: number;
+  startsAt?: number;
+  endsAt: number;
+}
+
+export class ExperimentRegistry {
+  private store: ExperimentStore;
+  private experiments: Experiment[];
+
+  constructor(store: ExperimentStore) {
+    this.store = store;
+    this.experiments = store.listExperiments();
+  }
+
+  list(): Experiment[] {
+    return [...this.experiments];
+  }
+
+  get(id: string): Experiment | undefined {
+    return this.experiments.find(e => e.id === id);
+  }
+
+  /**
+   * Create an experiment; fails when it overlaps another experiment on the same service
+   */
+  create(input: CreateExperimentInput): Experiment | { error: string } {
+    const now = Date.now();
+    const startsAt = input.startsAt ?? now;
+    const split = input.split ?? 0.5;
+
+    if (input.arms[0] === input.arms[1]) {
+      return { error: 'Experiment arms must be two different configs' };
+    }
+    if (!(split > 0 && split < 1)) {
+      return { error: 'split must be between 0 and 1 (exclusive)' };
+    }
+    if (!(input.endsAt > startsAt) || input.endsAt <= now) {
+      return { error: 'Experiment must end in the future and after it starts' };
+    }
+
+    const overlapping = this.experiments.find(
+      e => e.service === input.service && e.startsAt < input.endsAt && startsAt < e.endsAt
+    );
+    if (overlapping) {
+      return { error: `Overlaps experiment ${overlapping.id} on ${input.service}` };
+    }
+
+    const experiment: Experiment = {
+      id: crypto.randomUUID(),
+      name: input.name || `${input.arms[0]} vs ${input.arms[1]}`,
+      service: input.service,
+      arms: input.arms,
+      split,
+      startsAt,
+      endsAt: input.endsAt,
+      createdAt: now,
+    };
+
+    this.store.saveExperiment(experiment);
+    this.experiments.unshift(experiment);
+    return experiment;
+  }
+
+  /**
+   * End an experiment early; its results stay available
+   */
+  stop(id: string): Experiment | undefined {
+    const experiment = this.get(id);
+    if (!experiment) {
+      return undefined;
+    }
+
+    experiment.endsAt = Math.min(experiment.endsAt, Date.now());
+    this.store.saveExperiment(experiment);
+    return experiment;
+  }
+
+  delete(id: string): boolean {
+    this.experiments = this.experiments.filter(e => e.id !== id);
+    return this.store.deleteExperiment(id);
+  }
+
+  /**
+   * Route a request into the running experiment for the service, if both arms are currently eligible
+   */
+  assign(service: string, servers: ProxyConfig[]): ExperimentAssignment | null {
+    const now = Date.now();
+    const experiment = this.experiments.find(e => e.service === service && e.startsAt <= now && now < e.endsAt);
+    if (!experiment) {
+      return null;
+    }
+
+    const isAvailable = (server: ProxyConfig | undefined): server is ProxyConfig =>
+      !!server && server.enabled !== false && !(typeof server.freezeUntil === 'number' && server.freezeUntil > now);
+    const a = servers.find(s => s.name === experiment.arms[0]);
+    const b = servers.find(s => s.name === experiment.arms[1]);
+
+    // A frozen or disabled arm would skew the comparison; let the load balancer route instead
+    if (!isAvailable(a) || !isAvailable(b)) {
+      return null;
+    }
+
+    return Math.random() < experiment.split
+      ? { experimentId: experiment.id, arm: 'a', server: a }
+      : { experimentId: experiment.id, arm: 'b', server: b };
+  }
+
+  results(id: string): ExperimentResults | undefined {
+    const experiment = this.get(id);
+    if (!experiment) {
+      return undefined;
+    }
+
+    const samples = this.store.getExperimentSamples(id);
+    const a = summarizeArm(experiment.arms[0], samples.filter(s => s.arm === 'a'));
+    const b = summarizeArm(experiment.arms[1], samples.filter(s => s.arm === 'b'));
+    const now = Date.now();
+
+    return {
+      experiment,
+      status: now < experiment.startsAt ? 'scheduled' : now < experiment.endsAt ? 'running' : 'finished',
+      arms: { a, b },
+      differences: {
+        latencyMs: differenceOfMeans(a.latencyMs, b.latencyMs),
+        errorRate: differenceOfRates(a.errorRate, b.errorRate),
+        outputTokens: differenceOfMeans(a.outputTokens, b.outputTokens),
+      },
+    };
+  }
+}
+
+function summarizeArm(config: string, samples: ExperimentSample[]): ArmResults {
+  const durations = samples.filter(s => !s.failed && typeof s.duration === 'number').map(s => s.duration!);
+  const numbers = (values: Array<number | undefined>) => values.filter((v): v is number => typeof v === 'number');
+
+  return {
+    config,
+    requests: samples.length,
+    latencyMs: {
+      ...summarizeMean(durations),
+      p50: percentile(durations, 0.5),
+      p95: percentile(durations, 0.95),
+    },
+    errorRate: summarizeRate(samples.filter(s => s.failed).length, samples.length),
+    inputTokens: summarizeMean(numbers(samples.map(s => s.inputTokens))),
+    outputTokens: summarizeMean(numbers(samples.map(s => s.outputTokens))),
+  };
+}
@@ -0,0 +1,91 @@
+// Experiment statistics - per-arm summaries with 95% confidence intervals
+
+const Z_95 = 1.96;
+
+export interface ConfidenceInterval {
+  low: number;
+  high: number;
+}
+
+export interface MeanSummary {
+  n: number;
+  mean: number | null;
+  stddev: number | null;
+  ci95: ConfidenceInterval | null;
+}
+
+export interface RateSummary {
+  n: number;
+  rate: number | null;
+  ci95: ConfidenceInterval | null;
+}
+
+/**
+ * Mean with a normal-approximation interval (needs at least two samples)
+ */
+export function summarizeMean(values: number[]): MeanSummary {
+  const n = values.length;
+  if (n === 0) {
+    return { n, mean: null, stddev: null, ci95: null };
+  }
+
+  const mean = values.reduce((sum, v) => sum + v, 0) / n;
+  if (n < 2) {
+    return { n, mean, stddev: null, ci95: null };
+  }
+
+  const variance = values.reduce((sum, v) => sum + (v - mean) ** 2, 0) / (n - 1);
+  const stddev = Math.sqrt(variance);
+  const margin = (Z_95 * stddev) / Math.sqrt(n);
+  return { n, mean, stddev, ci95: { low: mean - margin, high: mean + margin } };
+}
+
+/**
+ * Proportion with a Wilson score interval, which stays sensible for small n and rates near 0 or 1
+ */
+export function summarizeRate(successes: number, n: number): RateSummary {
+  if (n === 0) {
+    return { n, rate: null, ci95: null };
+  }
+
+  const p = successes / n;
+  const z2 = Z_95 * Z_95;
+  const denominator = 1 + z2 / n;
+  const center = (p + z2 / (2 * n)) / denominator;
+  const margin = (Z_95 * Math.sqrt((p * (1 - p)) / n + z2 / (4 * n * n))) / denominator;
+  return { n, rate: p, ci95: { low: Math.max(0, center - margin), high: Math.min(1, center + margin) } };
+}
+
+/**
+ * Difference of means (b - a) with a Welch interval; null when either side lacks variance data
+ */
+export function differenceOfMeans(a: MeanSummary, b: MeanSummary): { estimate: number; ci95: ConfidenceInterval } | null {
+  if (a.mean === null || b.mean === null || a.stddev === null || b.stddev === null) {
+    return null;
+  }
+
+  const estimate = b.mean - a.mean;
+  const margin = Z_95 * Math.sqrt(a.stddev ** 2 / a.n + b.stddev ** 2 / b.n);
+  return { estimate, ci95: { low: estimate - margin, high: estimate + margin } };
+}
+
+/**
+ * Difference of proportions (b - a) with a normal-approximation interval
+ */
+export function differenceOfRates(a: RateSummary, b: RateSummary): { estimate: number; ci95: ConfidenceInterval } | null {
+  if (a.rate === null || b.rate === null) {
+    return null;
+  }
+
+  const estimate = b.rate - a.rate;
+  const margin = Z_95 * Math.sqrt((a.rate * (1 - a.rate)) / a.n + (b.rate * (1 - b.rate)) / b.n);
+  return { estimate, ci95: { low: estimate - margin, high: estimate + margin } };
+}
+
+export function percentile(values: number[], fraction: number): number | null {
+  if (values.length === 0) {
+    return null;
+  }
+  const sorted = values.slice().sort((x, y) => x - y);
+  return sorted[Math.min(sorted.length - 1, Math.floor(sorted.length * fraction))];
+}
@@ -0,0 +1,206 @@
+// Claude Code hooks - `paf hook` forwards hook input to /api/hooks/claude-code. paf tags the session's
+// later requests with its task metadata and answers with notices (provider switches, low balances)
+// that Claude Code shows to the user.
+
+import { basename } from 'path';
+import type { RequestLog } from '../logging/database';
+import { TAG_KEY_PATTERN, type RequestTags } from '../proxy/tags';
+
+export type HookEventType = 'provider_switch' | 'budget_threshold';
+
+export interface HookEvent {
+  seq: number;
+  type: HookEventType;
+  service: string;
+  session?: string; // Only this session's hooks see it; unset for events every session should hear about
+  timestamp: number;
+  message: string;
+  data: Record<string, unknown>;
+}
+
+/**
+ * The fields paf reads from Claude Code's hook input (stdin of the hook command), plus optional
+ * `tags` from custom hook scripts
+ */
+export interface ClaudeCodeHookInput {
+  session_id?: string;
+  hook_event_name?: string;
+  cwd?: string;
+  prompt?: string;
+  tags?: Record<string, unknown>;
+}
+
+const MAX_EVENTS = 500;
+const MAX_SESSIONS = 1000;
+const MAX_TRACKED_ROUTES = 10_000;
+const SESSION_IDLE_MS = 24 * 60 * 60 * 1000;
+const MAX_TAG_VALUE_LENGTH = 128;
+
+/**
+ * Recent events for hook callers, numbered so each session is told about each event once
+ */
+export class HookEventFeed {
+  private events: HookEvent[] = [];
+  private seq = 0;
+  // Config that last served each service/session, to notice provider switches
+  private lastConfig = new Map<string, string>();
+
+  record(event: Omit<HookEvent, 'seq'>): HookEvent {
+    const recorded = { ...event, seq: ++this.seq };
+    this.events.push(recorded);
+    if (this.events.length > MAX_EVENTS) {
+      this.events.shift();
+    }
+    return recorded;
+  }
+
+  latest(): number {
+    return this.seq;
+  }
+
+  /**
+   * Events after `seq`; with a session, only those meant for it or for everyone
+   */
+  since(seq: number, session?: string): HookEvent[] {
+    return this.events.filter(
+      event => event.seq > seq && (!session || !event.session || event.session === session)
+    );
+  }
+
+  /**
+   * Record a provider_switch when a successful request was served by another config than the previous
+   * one of its session. Load balancing rotates configs by design, so there only failovers count.
+   */
+  observeRequest(log: RequestLog): void {
+    // Proxied requests only (they carry a selection); connectivity tests and cache hits don't count
+    const succeeded = typeof log.statusCode === 'number' && log.statusCode >= 200 && log.statusCode < 300;
+    if (!log.service || !log.context?.selection || !succeeded) {
+      return;
+    }
+    // A config the client forced with x-paf-config is its own choice, not a switch
+    if (log.context.selection.via === 'header') {
+      return;
+    }
+    const key = `${log.service}\n${log.conversationId ?? ''}`;
+    const previous = this.lastConfig.get(key);
+    this.lastConfig.delete(key);
+    this.lastConfig.set(key, log.configName);
+    if (this.lastConfig.size > MAX_TRACKED_ROUTES) {
+      this.lastConfig.delete(this.lastConfig.keys().next().value!);
+    }
+    if (!previous || previous === log.configName) {
+      return;
+    }
+
+    const failedOver = log.context?.attempts.some(attempt => attempt.config !== log.configName) ?? false;
+    if (!failedOver && log.context?.selection?.mode === 'load_balance') {
+      return;
+    }
+    this.record({
+      type: 'provider_switch',
+      service: log.service,
+      session: log.conversationId,
+      timestamp: log.timestamp,
+      message: `${log.service} switched from ${previous} to ${log.configName}${failedOver ? ' after a failure' : ''}`,
+      data: { from: previous, to: log.configName, reason: failedOver ? 'failover' : 'selection', request_id: log.id },
+    });
+  }
+}
+
+interface HookSession {
+  tags: RequestTags;
+  delivered: number; // Last event seq reported to the session
+  updatedAt: number;
+}
+
+/**
+ * Per-session task metadata from hook callbacks, kept in memory for a day of inactivity
+ */
+export class HookSessions {
+  private sessions = new Map<string, HookSession>();
+
+  /**
+   * Apply one hook callback; returns the events the session has not been told about yet
+   */
+  handle(input: ClaudeCodeHookInput, feed: HookEventFeed): HookEvent[] {
+    const sessionId = typeof input.session_id === 'string' ? input.session_id.trim() : '';
+    if (!sessionId) {
+      return [];
+    }
+    if (input.hook_event_name === 'SessionEnd') {
+      this.sessions.delete(sessionId);
+      return [];
+    }
+
+    this.prune();
+    // A new session starts from now; older events are not its business
+    const session = this.sessions.get(sessionId) ?? { tags: {}, delivered: feed.latest(), updatedAt: 0 };
+    Object.assign(session.tags, tagsFromHook(input));
+    session.updatedAt = Date.now();
+    this.sessions.delete(sessionId);
+    this.sessions.set(sessionId, session);
+
+    const events = feed.since(session.delivered, sessionId);
+    session.delivered = feed.latest();
+    return events;
+  }
+
+  /**
+   * The session's hook tags under the request's own x-paf-tags, which win on conflicts
+   */
+  tagsFor(sessionId: string | undefined, requestTags: RequestTags | undefined): RequestTags | undefined {
+    const session = sessionId ? this.sessions.get(sessionId) : undefined;
+    if (!session || Object.keys(session.tags).length === 0) {
+      return requestTags;
+    }
+    return { ...session.tags, ...requestTags };
+  }
+
+  list(): Array<{ session: string; tags: RequestTags; updatedAt: number }> {
+    return [...this.sessions].map(([session, { tags, updatedAt }]) => ({ session, tags, updatedAt }));
+  }
+
+  private prune(): void {
+    const cutoff = Date.now() - SESSION_IDLE_MS;
+    for (const [id, session] of this.sessions) {
+      if (session.updatedAt < cutoff || this.sessions.size > MAX_SESSIONS) {
+        this.sessions.delete(id);
+      }
+    }
+  }
+}
+
+/**
+ * `project` from the working directory, `task` from the first line of the latest prompt, plus any
+ * valid custom tags
+ */
+function tagsFromHook(input: ClaudeCodeHookInput): RequestTags {
+  const tags: RequestTags = {};
+  if (typeof input.cwd === 'string' && input.cwd.trim()) {
+    tags.project = basename(input.cwd.trim()).slice(0, MAX_TAG_VALUE_LENGTH);
+  }
+  if (input.hook_event_name === 'UserPromptSubmit' && typeof input.prompt === 'string') {
+    const task = input.prompt.trim().split('\n')[0].trim();
+    if (task) {
+      tags.task = task.slice(0, MAX_TAG_VALUE_LENGTH);
+    }
+  }
+  if (input.tags && typeof input.tags === 'object') {
+    for (const [key, value] of Object.entries(input.tags)) {
+      if (TAG_KEY_PATTERN.test(key) && (typeof value === 'string' || typeof value === 'number') && String(value)) {
+        tags[key] = String(value).slice(0, MAX_TAG_VALUE_LENGTH);
+      }
+    }
+  }
+  return tags;
+}
+
+/**
+ * Hook output for Claude Code: pending events become a systemMessage shown to the user; `{}` changes nothing
+ */
+export function claudeCodeHookOutput(events: HookEvent[]): Record<string, unknown> {
+  if (events.length === 0) {
+    return {};
+  }
+  return { systemMessage: events.map(event => `paf: ${event.message}`).join('\n') };
+}
@@ -2,16 +2,98 @@
 
 import { serve } from 'bun';
 import { ConfigManager } from './config/manager';
-import { LoadBalancer } from './routing/loadbalancer';
+import { isSelectionStrategy, selectionStrategyNames } from './routing/strategies';
 import { RequestLogger, type LastRequestSnapshot } from './logging/logger';
-import { ClaudeProxyService } from './proxy/claudeProxyService';
-import { CodexProxyService } from './proxy/codexProxyService';
+import { isBusyError } from './logging/database';
 import type { ProxyService } from './proxy/baseProxyService';
-import type { ProxyConfig, ServiceConfig } from './config/types';
+import { ConnectionStats } from './proxy/connectionStats';
+import { BodyMemoryBudget } from './proxy/memoryBudget';
+import { ConcurrencyLimiter } from './proxy/concurrency';
+import type { QueuedRequestStatus } from './proxy/outageQueue';
+import { parseReplayFile } from './replay/replay';
+import { setClientIdentity } from './proxy/clientIdentity';
+import { serveTlsProxy, type ClientCertificate } from './proxy/tlsServer';
+import { TAG_KEY_PATTERN } from './proxy/tags';
+import { parseFingerprint } from './proxy/fingerprint';
+import { KNOWN_ANTHROPIC_BETAS, validateAnthropicBetas } from './proxy/anthropicBeta';
+import { parseContextTrim } from './proxy/contextTrim';
+import { parseQuotaConfig } from './monitoring/quota';
+import { parseResponseLanguage } from './proxy/responseLanguage';
+import { parseModelMap } from './proxy/modelOverride';
+import { parseTimeoutSecs } from './proxy/timeouts';
+import { apiFormatError } from './proxy/translation';
+import { isProviderProfile, parseQuirks, PROVIDER_PROFILES } from './proxy/quirks';
+import { parsePromptCache } from './proxy/promptCache';
+import { parseRequestCompression } from './proxy/requestCompression';
+import { isWithinSchedule, parseAvailability, serializeAvailability } from './routing/schedule';
+import { parsePerMinuteLimit } from './routing/rateLimiter';
+import {
+  SESSION_EXPORT_FORMATS,
+  toCurlScript,
+  toHar,
+  toJsonLines,
+  type SessionExportFormat,
+} from './logging/sessionExport';
+import { proxyErrorResponse, serviceErrorDialect } from './proxy/errors';
+import { CONFIG_OVERRIDE_TOKEN_HEADER } from './proxy/configOverride';
+import { FANOUT_PATH, fanOut, readFanoutRequest } from './proxy/fanout';
+import { deliverAlert, parseLogQuery } from './monitoring/alerts';
+import {
+  CertificateMonitor,
+  deliverCertificateWarning,
+  upstreamTlsHosts,
+  type CertificateWarning,
+} from './monitoring/certificates';
+import {
+  applyNetworkPreferences,
+  fetchProxy,
+  maskProxyUrl,
+  proxyUrlError,
+  upstreamProxy,
+  warmUpServiceConnections,
+} from './proxy/network';
+import {
+  RealtimeHub,
+  RealtimeClientRegistry,
+  attachRealtimeClient,
+  createRealtimeClientData,
+  detachRealtimeClient,
+  type RealtimeClientData,
+} from './realtime/hub';
+import type { AvailabilitySchedule, ProxyConfig, ServiceConfig } from './config/types';
+import { isAdminRequest, redactConfig } from './config/redaction';
+import { getLogFilter, setLogFilter, type LogFilter } from './logging/logFilter';
+import { installCrashReporter } from './monitoring/crashes';
+import { recoverStaleInstance, writePidFile } from './monitoring/pidFile';
+import { applySyncBundle, decryptSyncBundle, encryptSyncBundle, exportSyncBundle } from './config/sync';
+import { startClusterSync, type ClusterStateSnapshot } from './cluster/peerSync';
+import {
+  createProxyCore,
+  ensureServiceConfigs,
+  selectedConfig,
+  SERVICE_HEALTH_PATH,
+  SERVICE_NAMES,
+  SERVICE_WHOAMI_PATH,
+  serviceHealth,
+  type ServiceName,
+} from './core';
+import { toWireRequestLog, WIRE_VERSION } from './protocol';
+import { MAX_WEBHOOK_BODY_BYTES, parseWebhookPayload, readWebhookBody, verifyWebhookSignature } from './webhooks/signature';
+import {
+  DEFAULT_TENANT,
+  createTenantRuntime,
+  findTenantByClientKey,
+  type TenantRuntime,
+} from './tenancy/runtime';
+import type { TenantBudgetStatus } from './tenancy/budget';
 import { join, dirname } from 'path';
 import { homedir, tmpdir } from 'os';
 import { existsSync, mkdirSync, mkdtempSync, rmSync, renameSync, writeFileSync } from 'fs';
 import { fileURLToPath } from 'url';
+import { serveStaticFile } from './web/staticAssets';
+import { changeValidators, notModified, validatorHeaders } from './web/conditional';
+import { CONFIG_CHANGES_MAX_WAIT_SECONDS } from './config/changeFeed';
+import { claudeCodeHookOutput, type ClaudeCodeHookInput } from './hooks/claudeCode';
 
 const moduleDir = dirname(fileURLToPath(import.meta.url));
 const rootDir = join(moduleDir, '..');
@@ -22,8 +104,38 @@ const configManager = new ConfigManager();
 await configManager.initialize();
 
 const systemConfig = configManager.getSystemConfig();
-const logger = new RequestLogger(systemConfig.dataDir);
+if (process.argv.includes('--read-only')) {
+  systemConfig.readOnly = true;
+}
+await applyNetworkPreferences(systemConfig.network);
+if ('error' in setLogFilter(systemConfig.logLevel)) {
+  console.warn(`[config] ignoring log_level ${JSON.stringify(systemConfig.logLevel)}; using info`);
+  setLogFilter('info');
+}
+
+// A previous server that left its PID file behind either still runs (keep it) or died half-way with
+// children holding the ports (clear them out so this start can bind)
+const pidFile = join(configManager.getConfigDir(), 'paf.pid');
+const previous = await recoverStaleInstance(pidFile, `http://localhost:${systemConfig.webPort}/api/status`);
+if (previous.state === 'running') {
+  console.error(`Proxy AI Fusion is already running (pid ${previous.pid}) at http://localhost:${systemConfig.webPort}`);
+  process.exit(1);
+}
+if (previous.state === 'cleaned') {
+  if (previous.killed) {
+    console.warn(`[startup] terminated unresponsive leftovers of a previous server (pid ${previous.pid})`);
+  } else {
+    console.log(`[startup] removed stale PID file of pid ${previous.pid}`);
+  }
+}
+writePidFile(pidFile);
+
+// Pending return to the configured log_level after a temporary /api/admin/loglevel change
+let logFilterRevert: ReturnType<typeof setTimeout> | null = null;
+const logger = new RequestLogger(systemConfig.dataDir, { scrubbing: systemConfig.logScrubbing, jsonl: systemConfig.lite });
+installCrashReporter(logger);
 
+// Keyed by `${tenant}:${config}` so tenants with identically named configs don't block each other
 const autoRetestLocks: Record<'claude' | 'codex', Set<string>> = {
   claude: new Set(),
   codex: new Set(),
@@ -31,127 +143,487 @@ const autoRetestLocks: Record<'claude' | 'codex', Set<string>> = {
 
 const AUTO_RETEST_INTERVAL_MS = 60 * 1000;
 
-// Load service configurations
-await configManager.loadServiceConfig('claude').catch(async () => {
-  console.log('Claude config not found, creating default...');
-  // Create default config if not exists
-  await configManager.saveServiceConfig('claude', {
-    configs: [],
-    active: '',
-    mode: 'manual',
-    loadBalancer: {
-      strategy: 'weighted',
-      healthCheck: {
-        enabled: true,
-        interval: 30000,
-        timeout: 5000,
-        failureThreshold: 3,
-        successThreshold: 2,
-      },
-      freezeDuration: 5 * 60 * 1000, // 5 minutes
-    },
-  });
+// Load service configurations (default TOML is created for missing services)
+await ensureServiceConfigs(configManager, true);
+
+// Initialize load balancers and proxy services
+const connectionStats = new ConnectionStats();
+const memoryBudget = new BodyMemoryBudget(systemConfig.bodyMemoryLimitBytes);
+// max_concurrent_requests counts every service and tenant together
+const concurrency = new ConcurrencyLimiter(systemConfig.maxConcurrentRequests);
+const core = createProxyCore(
+  configManager,
+  logger,
+  connectionStats,
+  undefined,
+  systemConfig.modelListCache.ttlSeconds,
+  systemConfig.passthroughBodyBytes,
+  memoryBudget,
+  undefined,
+  undefined,
+  concurrency
+);
+const claudeLoadBalancer = core.loadBalancers.claude;
+const codexLoadBalancer = core.loadBalancers.codex;
+const claudeProxy = core.proxies.claude;
+const codexProxy = core.proxies.codex;
+
+// Realtime hubs feed the dashboard over WebSocket
+const realtimeHubs: Record<'claude' | 'codex', RealtimeHub> = {
+  claude: new RealtimeHub({
+    service: 'claude',
+    store: logger,
+    replayMinutes: systemConfig.realtime.replayMinutes,
+  }),
+  codex: new RealtimeHub({
+    service: 'codex',
+    store: logger,
+    replayMinutes: systemConfig.realtime.replayMinutes,
+  }),
+};
+
+// Connected dashboard clients across both hubs, see /api/realtime/clients
+const realtimeClients = new RealtimeClientRegistry();
+
+logger.onRequestLogged(log => {
+  if (log.service === 'claude' || log.service === 'codex') {
+    realtimeHubs[log.service].publishRequestLog(log);
+  }
 });
 
-await configManager.loadServiceConfig('codex').catch(async () => {
-  console.log('Codex config not found, creating default...');
-  // Create default config if not exists
-  await configManager.saveServiceConfig('codex', {
-    configs: [],
-    active: '',
-    mode: 'manual',
-    loadBalancer: {
-      strategy: 'weighted',
-      healthCheck: {
-        enabled: true,
-        interval: 30000,
-        timeout: 5000,
-        failureThreshold: 3,
-        successThreshold: 2,
+// Bodies streamed or captures skipped because of body_memory_limit_bytes
+memoryBudget.onPressure(event => {
+  if (event.service === 'claude' || event.service === 'codex') {
+    realtimeHubs[event.service].publish({
+      v: WIRE_VERSION,
+      type: 'memory_pressure',
+      service: event.service,
+      timestamp: event.timestamp,
+      data: {
+        action: event.action,
+        bytes: event.bytes,
+        buffered_bytes: event.bufferedBytes,
+        limit_bytes: event.limitBytes,
       },
-      freezeDuration: 5 * 60 * 1000, // 5 minutes
-    },
-  });
+    });
+  }
 });
 
-// Initialize load balancers
-const claudeConfig = configManager.getServiceConfig('claude');
-const claudeLoadBalancer = new LoadBalancer(
-  claudeConfig?.loadBalancer || {
-    strategy: 'weighted',
-    healthCheck: {
-      enabled: true,
-      interval: 30000,
-      timeout: 5000,
-      failureThreshold: 3,
-      successThreshold: 2,
-    },
-    freezeDuration: 5 * 60 * 1000,
+// Tenants: the top-level ~/.paf state is the default tenant, others are isolated under tenants/<name>
+const defaultTenant: TenantRuntime = { name: DEFAULT_TENANT, ...core };
+
+const tenants = new Map<string, TenantRuntime>([[DEFAULT_TENANT, defaultTenant]]);
+for (const tenant of systemConfig.tenants) {
+  tenants.set(
+    tenant.name,
+    await createTenantRuntime(tenant, systemConfig.dataDir, connectionStats, {
+      dlp: core.dlp,
+      logScrubbing: systemConfig.logScrubbing,
+      modelListTtlSeconds: systemConfig.modelListCache.ttlSeconds,
+      passthroughBodyBytes: systemConfig.passthroughBodyBytes,
+      memoryBudget,
+      shapeCache: systemConfig.shapeCache,
+      responseCache: systemConfig.responseCache,
+      concurrency,
+      lite: systemConfig.lite,
+    })
+  );
+}
+
+/**
+ * Pre-open connections to the upstreams a service will use next, unless disabled in [network]
+ */
+function warmUpConnections(manager: ConfigManager, serviceName: string): void {
+  if (!systemConfig.network.warmUp) {
+    return;
   }
-);
+  const origins = warmUpServiceConnections(manager, serviceName);
+  if (origins.length > 0) {
+    console.log(`[network] warming up ${serviceName} connections: ${origins.join(', ')}`);
+  }
+}
 
-const codexConfig = configManager.getServiceConfig('codex');
-const codexLoadBalancer = new LoadBalancer(
-  codexConfig?.loadBalancer || {
-    strategy: 'weighted',
-    healthCheck: {
-      enabled: true,
-      interval: 30000,
-      timeout: 5000,
-      failureThreshold: 3,
-      successThreshold: 2,
-    },
-    freezeDuration: 5 * 60 * 1000,
+for (const tenant of tenants.values()) {
+  for (const serviceName of SERVICE_NAMES) {
+    warmUpConnections(tenant.configManager, serviceName);
   }
-);
+}
 
-// Initialize proxy services
-const claudeProxy = new ClaudeProxyService({
-  loadBalancer: claudeLoadBalancer,
-  logger,
-  configManager,
-});
+// A request log database that failed its integrity check was recreated; tell whoever is watching
+for (const tenant of tenants.values()) {
+  const recovery = tenant.logger.getDatabaseRecovery();
+  if (!recovery) {
+    continue;
+  }
+  for (const service of SERVICE_NAMES) {
+    realtimeHubs[service].publish({
+      v: WIRE_VERSION,
+      type: 'database_recovered',
+      service,
+      timestamp: recovery.at,
+      data: { tenant: tenant.name, reason: recovery.reason, moved_to: recovery.movedTo },
+    });
+  }
+}
 
-const codexProxy = new CodexProxyService({
-  loadBalancer: codexLoadBalancer,
-  logger,
-  configManager,
-});
+// Cluster mode: poll peers and merge their load balancer state into ours
+const clusterNodeId = crypto.randomUUID();
+
+if (systemConfig.cluster.peers.length > 0) {
+  startClusterSync({
+    nodeId: clusterNodeId,
+    peers: systemConfig.cluster.peers,
+    secret: systemConfig.cluster.secret,
+    intervalMs: systemConfig.cluster.syncIntervalMs,
+    applySnapshot: applyClusterSnapshot,
+  });
+  console.log(`Cluster sync enabled with ${systemConfig.cluster.peers.length} peer(s)`);
+}
+
+function buildClusterSnapshot(): ClusterStateSnapshot {
+  const now = Date.now();
+  const snapshot: ClusterStateSnapshot = { node: clusterNodeId, tenants: {} };
+
+  for (const tenant of tenants.values()) {
+    snapshot.tenants[tenant.name] = {};
+    for (const serviceName of ['claude', 'codex'] as const) {
+      const freezes: Record<string, number> = {};
+      for (const config of tenant.configManager.getServiceConfig(serviceName)?.configs ?? []) {
+        if (typeof config.freezeUntil === 'number' && config.freezeUntil > now) {
+          freezes[config.name] = config.freezeUntil;
+        }
+      }
+      snapshot.tenants[tenant.name][serviceName] = {
+        servers: tenant.loadBalancers[serviceName].exportSharedState(),
+        freezes,
+      };
+    }
+  }
+
+  return snapshot;
+}
+
+async function applyClusterSnapshot(snapshot: ClusterStateSnapshot): Promise<void> {
+  const now = Date.now();
+
+  for (const [tenantName, services] of Object.entries(snapshot.tenants)) {
+    const tenant = tenants.get(tenantName);
+    if (!tenant) {
+      continue;
+    }
+
+    for (const serviceName of ['claude', 'codex'] as const) {
+      const remote = services[serviceName];
+      if (!remote) {
+        continue;
+      }
+
+      tenant.loadBalancers[serviceName].mergePeerState(snapshot.node, remote.servers ?? {});
+
+      // Adopt a peer's freeze when it lasts longer than ours
+      const serviceConfig = tenant.configManager.getServiceConfig(serviceName);
+      if (!serviceConfig) {
+        continue;
+      }
+      for (const [configName, freezeUntil] of Object.entries(remote.freezes ?? {})) {
+        const local = serviceConfig.configs.find(c => c.name === configName);
+        if (local && freezeUntil > now && freezeUntil > (local.freezeUntil ?? 0)) {
+          await applyConfigFreeze(serviceName, serviceConfig, configName, freezeUntil, tenant.configManager);
+          console.log(`[cluster] ${tenant.name}/${serviceName}/${configName} frozen by peer ${snapshot.node}`);
+        }
+      }
+    }
+  }
+}
+
+function autoRetestAllTenants(serviceName: 'claude' | 'codex'): void {
+  for (const tenant of tenants.values()) {
+    void autoRetestFrozenConfigs(serviceName, tenant);
+  }
+}
 
 setTimeout(() => {
-  void autoRetestFrozenConfigs('claude');
-  void autoRetestFrozenConfigs('codex');
+  autoRetestAllTenants('claude');
+  autoRetestAllTenants('codex');
 }, 0);
 
 setInterval(() => {
-  void autoRetestFrozenConfigs('claude');
+  autoRetestAllTenants('claude');
 }, AUTO_RETEST_INTERVAL_MS);
 
 setInterval(() => {
-  void autoRetestFrozenConfigs('codex');
+  autoRetestAllTenants('codex');
 }, AUTO_RETEST_INTERVAL_MS);
 
+// Roll request logs up into daily SLA figures; the first pass backfills every day still in the logs
+const SLA_ROLLUP_INTERVAL_MS = 15 * 60 * 1000;
+
+for (const tenant of tenants.values()) {
+  tenant.logger.refreshSla(Infinity);
+}
+setInterval(() => {
+  for (const tenant of tenants.values()) {
+    tenant.logger.refreshSla();
+  }
+}, SLA_ROLLUP_INTERVAL_MS);
+
+// Replay requests held in the outage queue once a config is reachable again
+const OUTAGE_QUEUE_DRAIN_INTERVAL_MS = 30 * 1000;
+let drainingOutageQueue = false;
+
+async function drainOutageQueues(): Promise<void> {
+  if (drainingOutageQueue) {
+    return;
+  }
+  drainingOutageQueue = true;
+  try {
+    for (const tenant of tenants.values()) {
+      for (const serviceName of SERVICE_NAMES) {
+        await tenant.proxies[serviceName]
+          .drainOutageQueue(tenant.configManager.getAllConfigs(serviceName))
+          .catch(error => console.error(`[queue] ${tenant.name}/${serviceName} drain failed:`, error));
+      }
+    }
+  } finally {
+    drainingOutageQueue = false;
+  }
+}
+
+setInterval(drainOutageQueues, OUTAGE_QUEUE_DRAIN_INTERVAL_MS);
+
+// Apply the request log retention policy at startup and hourly afterwards
+const LOG_RETENTION_INTERVAL_MS = 60 * 60 * 1000;
+
+function applyLogRetention(): void {
+  if (systemConfig.logRetentionDays <= 0) {
+    return;
+  }
+  for (const tenant of tenants.values()) {
+    const removed = tenant.logger.cleanupOldLogs(systemConfig.logRetentionDays);
+    if (removed > 0) {
+      console.log(
+        `Removed ${removed} request log(s) older than ${systemConfig.logRetentionDays} day(s) (tenant ${tenant.name})`
+      );
+    }
+  }
+}
+
+applyLogRetention();
+setInterval(applyLogRetention, LOG_RETENTION_INTERVAL_MS);
+
+// Evaluate log alert rules every minute; dashboards only see the default tenant's alerts
+const ALERT_EVALUATION_INTERVAL_MS = 60 * 1000;
+
+function evaluateAlerts(): void {
+  for (const tenant of tenants.values()) {
+    for (const alert of tenant.monitor.evaluate()) {
+      console.warn(
+        `[alerts] ${tenant.name}: "${alert.rule.name}" fired (${alert.count} >= ${alert.rule.threshold} in ${alert.rule.windowMinutes} min)`
+      );
+      void deliverAlert(alert);
+
+      if (tenant.name !== DEFAULT_TENANT) {
+        continue;
+      }
+      const service = alert.view.query.service;
+      const hubServices = service === 'claude' || service === 'codex' ? [service] : SERVICE_NAMES;
+      for (const hubService of hubServices) {
+        realtimeHubs[hubService].publish({
+          v: WIRE_VERSION,
+          type: 'alert_fired',
+          service: hubService,
+          timestamp: alert.firedAt,
+          data: {
+            alert_id: alert.rule.id,
+            name: alert.rule.name,
+            view: alert.view.name,
+            count: alert.count,
+            threshold: alert.rule.threshold,
+            window_minutes: alert.rule.windowMinutes,
+          },
+        });
+      }
+    }
+  }
+}
+
+setInterval(evaluateAlerts, ALERT_EVALUATION_INTERVAL_MS);
+
+// Balance/credit of configs with a [configs.quota] setting, shown next to each config
+const QUOTA_POLL_INTERVAL_MS = 15 * 60 * 1000;
+
+async function pollQuotas(): Promise<void> {
+  for (const tenant of tenants.values()) {
+    for (const serviceName of SERVICE_NAMES) {
+      const turnedLow = await tenant.quota.poll(
+        serviceName,
+        tenant.configManager.getAllConfigs(serviceName),
+        tenant.configManager.getServiceConfig(serviceName)?.proxyUrl
+      );
+      for (const snapshot of turnedLow) {
+        console.warn(
+          `[quota] ${tenant.name}/${serviceName}/${snapshot.configName} balance is low: ${snapshot.remaining} ${snapshot.unit}`
+        );
+        tenant.hookEvents.record({
+          type: 'budget_threshold',
+          service: serviceName,
+          timestamp: snapshot.checkedAt,
+          message: `${serviceName} config ${snapshot.configName} balance is low: ${snapshot.remaining} ${snapshot.unit}`,
+          data: { config: snapshot.configName, remaining: snapshot.remaining, unit: snapshot.unit },
+        });
+        if (tenant.name !== DEFAULT_TENANT) {
+          continue;
+        }
+        realtimeHubs[serviceName].publish({
+          v: WIRE_VERSION,
+          type: 'quota_low',
+          service: serviceName,
+          timestamp: snapshot.checkedAt,
+          data: { config: snapshot.configName, remaining: snapshot.remaining, unit: snapshot.unit },
+        });
+      }
+    }
+  }
+}
+
+void pollQuotas();
+setInterval(pollQuotas, QUOTA_POLL_INTERVAL_MS);
+
+function withQuota(serviceName: string, configs: ProxyConfig[], tenant: TenantRuntime = defaultTenant) {
+  return configs.map(config => ({ ...redactConfig(config), quota_status: tenant.quota.get(serviceName, config.name) }));
+}
+
+// Upstream TLS certificates of every tenant's configs, recorded in the default tenant's database
+const certificateMonitor = new CertificateMonitor(logger, systemConfig.tlsMonitor.expiryWarningDays);
+
+function toWireCertificateWarning(warning: CertificateWarning): Record<string, unknown> {
+  return {
+    kind: warning.kind,
+    host: warning.host,
+    message: warning.message,
+    fingerprint: warning.record.fingerprint,
+    previous_fingerprint: warning.record.previousFingerprint,
+    valid_to: warning.record.validTo,
+  };
+}
+
+async function checkUpstreamCertificates(): Promise<void> {
+  const baseUrls: string[] = [];
+  for (const tenant of tenants.values()) {
+    for (const serviceName of SERVICE_NAMES) {
+      const serviceConfig = tenant.configManager.getServiceConfig(serviceName);
+      // Hosts behind a proxy_url are usually not reachable directly
+      baseUrls.push(
+        ...tenant.configManager
+          .getAllConfigs(serviceName)
+          .filter(config => !upstreamProxy(config, serviceConfig))
+          .map(config => config.baseUrl)
+      );
+    }
+  }
+
+  for (const warning of await certificateMonitor.check(upstreamTlsHosts(baseUrls))) {
+    console.warn(`[tls] ${warning.message}`);
+    if (systemConfig.tlsMonitor.notifyUrl) {
+      void deliverCertificateWarning(systemConfig.tlsMonitor.notifyUrl, warning);
+    }
+    for (const service of SERVICE_NAMES) {
+      realtimeHubs[service].publish({
+        v: WIRE_VERSION,
+        type: 'certificate_warning',
+        service,
+        timestamp: warning.record.checkedAt,
+        data: toWireCertificateWarning(warning),
+      });
+    }
+  }
+}
+
+if (systemConfig.tlsMonitor.enabled) {
+  void checkUpstreamCertificates();
+  setInterval(checkUpstreamCertificates, systemConfig.tlsMonitor.intervalMinutes * 60 * 1000);
+}
+
+// Path prefixes served on the web port when single-port mode is enabled
+const SINGLE_PORT_PROXIES: Array<[string, 'claude' | 'codex']> = [
+  ['/claude', 'claude'],
+  ['/codex', 'codex'],
+];
+
 const pkg = await Bun.file(join(rootDir, 'package.json')).json();
 const version = typeof pkg?.version === 'string' ? pkg.version : 'unknown';
 
 console.log(`Starting Proxy AI Fusion server (v${version})...`);
-console.log(`Web UI: http://localhost:${systemConfig.webPort}`);
-console.log(`Claude proxy: http://localhost:${systemConfig.proxyPorts.claude}`);
-console.log(`Codex proxy: http://localhost:${systemConfig.proxyPorts.codex}`);
+const proxyScheme = systemConfig.proxyTls ? 'https' : 'http';
+if (systemConfig.singlePort) {
+  console.log(`Web UI: http://localhost:${systemConfig.webPort}/ui/`);
+  console.log(`Claude proxy: http://localhost:${systemConfig.webPort}/claude`);
+  console.log(`Codex proxy: http://localhost:${systemConfig.webPort}/codex`);
+} else {
+  console.log(`Web UI: http://localhost:${systemConfig.webPort}`);
+  console.log(`Claude proxy: ${proxyScheme}://localhost:${systemConfig.proxyPorts.claude}`);
+  console.log(`Codex proxy: ${proxyScheme}://localhost:${systemConfig.proxyPorts.codex}`);
+  if (systemConfig.proxyTls?.clientCa) {
+    console.log('Proxy ports require client certificates (mTLS).');
+  }
+}
+if (systemConfig.readOnly) {
+  console.log('Read-only mode: management changes are disabled.');
+}
+if (systemConfig.lite) {
+  console.log('Lite mode: dashboard disabled, request logs appended to requests.jsonl.');
+}
 console.log('Proxy AI Fusion server ready.');
 
 // Start Bun fullstack server for dashboard + API
-serve({
+serve<RealtimeClientData>({
   port: systemConfig.webPort,
   development: process.env.NODE_ENV !== 'production',
 
   // HTTP request handler
-  async fetch(req) {
+  async fetch(req, server) {
     const url = new URL(req.url);
-    const path = url.pathname;
+    let path = url.pathname;
+
+    // Single-port mode: route service traffic by path prefix instead of by port
+    if (systemConfig.singlePort) {
+      for (const [prefix, serviceName] of SINGLE_PORT_PROXIES) {
+        if (path === prefix || path.startsWith(`${prefix}/`)) {
+          const proxiedUrl = new URL(req.url);
+          proxiedUrl.pathname = path.slice(prefix.length) || '/';
+          return handleDirectProxyRequest(new Request(proxiedUrl, req), serviceName);
+        }
+      }
+
+      if (path === '/ui' || path.startsWith('/ui/')) {
+        path = path.slice('/ui'.length) || '/';
+      }
+    }
+
+    // Realtime event streams: /ws/realtime/<service> or /ws/realtime?services=claude,codex, with
+    // ?since=<ms> to resume after a reconnect
+    if (!systemConfig.lite && (path === '/ws/realtime' || path.startsWith('/ws/realtime/'))) {
+      const services = parseRealtimeServices(path, url);
+      if (!services) {
+        return Response.json({ error: 'Unknown service' }, { status: 404 });
+      }
+      const data = createRealtimeClientData(services, {
+        ip: server.requestIP(req)?.address,
+        userAgent: req.headers.get('user-agent') || undefined,
+        since: parseRealtimeSince(url),
+      });
+      if (server.upgrade(req, { data })) {
+        return undefined;
+      }
+      return new Response('WebSocket upgrade required', { status: 426 });
+    }
 
     // API Routes
     if (path.startsWith('/api/')) {
+      if (path === '/api/configs/changes') {
+        // Long polls stay silent for longer than Bun's default idle timeout
+        server.timeout(req, CONFIG_CHANGES_MAX_WAIT_SECONDS + 10);
+      }
       return handleApiRequest(req, path);
     }
 
@@ -159,7 +631,7 @@ serve({
     if (path.startsWith('/v1/')) {
       const servers = configManager.getAllConfigs('claude');
       if (servers.length === 0) {
-        return Response.json({ error: 'No claude configs available' }, { status: 503 });
+        return proxyErrorResponse('anthropic', 'no_upstream', 'No claude configs available');
       }
       return claudeProxy.handleRequest(req, servers);
     }
@@ -168,7 +640,7 @@ serve({
     if (path.startsWith('/codex/v1/')) {
       const servers = configManager.getAllConfigs('codex');
       if (servers.length === 0) {
-        return Response.json({ error: 'No codex configs available' }, { status: 503 });
+        return proxyErrorResponse('openai', 'no_upstream', 'No codex configs available');
       }
       // Remove /codex prefix before forwarding
       const modifiedUrl = new URL(req.url);
@@ -177,24 +649,26 @@ serve({
       return codexProxy.handleRequest(modifiedReq, servers);
     }
 
+    // Lite mode has no dashboard; only the API above is served
+    if (systemConfig.lite) {
+      return new Response('Not found (the dashboard is disabled in lite mode)', { status: 404 });
+    }
+
     // Serve frontend
+    const indexHtml = join(publicDir, 'index.html');
     if (path === '/') {
-      return new Response(Bun.file(join(publicDir, 'index.html')), {
-        headers: { 'Content-Type': 'text/html' },
-      });
+      return (await serveStaticFile(req, indexHtml)) ?? new Response('Not found', { status: 404 });
     }
 
-    // Serve static files from public directory
+    // Serve static files from public directory (ETag, compression and cache headers in web/staticAssets.ts)
     const sanitizedPath = path.replace(/^\/+/, '');
 
     if (sanitizedPath.includes('..')) {
       return new Response('Not found', { status: 404 });
     }
-    const publicPath = join(publicDir, sanitizedPath);
-    const file = Bun.file(publicPath);
-
-    if (await file.exists()) {
-      return new Response(file);
+    const publicFile = await serveStaticFile(req, join(publicDir, sanitizedPath));
+    if (publicFile) {
+      return publicFile;
     }
 
     // Try serving from root (for src/ during development)
@@ -206,56 +680,88 @@ serve({
     }
 
     // Fallback to index.html for SPA routing
-    return new Response(Bun.file(join(publicDir, 'index.html')), {
-      headers: { 'Content-Type': 'text/html' },
-    });
+    return (await serveStaticFile(req, indexHtml)) ?? new Response('Not found', { status: 404 });
   },
-});
 
-// Start dedicated proxy servers to mirror legacy CLI behaviour
-serve({
-  port: systemConfig.proxyPorts.claude,
-  development: process.env.NODE_ENV !== 'production',
-  async fetch(req) {
-    return handleDirectProxyRequest(req, 'claude', claudeProxy);
+  websocket: {
+    open(ws) {
+      realtimeClients.add(ws);
+      attachRealtimeClient(ws, resolveRealtimeHubs(ws.data.services));
+    },
+    message() {
+      // Clients only receive events; inbound messages are ignored.
+    },
+    close(ws) {
+      realtimeClients.remove(ws);
+      detachRealtimeClient(ws, resolveRealtimeHubs(ws.data.services));
+    },
   },
 });
 
-serve({
-  port: systemConfig.proxyPorts.codex,
-  development: process.env.NODE_ENV !== 'production',
-  async fetch(req) {
-    return handleDirectProxyRequest(req, 'codex', codexProxy);
-  },
-});
+/**
+ * Resolve the services a realtime client subscribes to; null when any name is unknown
+ */
+function parseRealtimeServices(path: string, url: URL): Array<'claude' | 'codex'> | null {
+  const fromPath = path.startsWith('/ws/realtime/') ? path.slice('/ws/realtime/'.length) : '';
+  const raw = fromPath || url.searchParams.get('services') || 'claude,codex';
+  const names = Array.from(new Set(raw.split(',').map(name => name.trim()).filter(Boolean)));
+
+  if (names.length === 0 || names.some(name => name !== 'claude' && name !== 'codex')) {
+    return null;
+  }
+
+  return names as Array<'claude' | 'codex'>;
+}
 
 /**
- * Convert RequestLog from backend format to frontend format
+ * Resume point of a reconnecting realtime client; undefined replays the whole window
  */
-function convertLogToFrontendFormat(log: any): any {
-  return {
-    id: log.id,
-    timestamp: log.timestamp,
-    service: log.service || 'claude', // Use actual service field or default to claude
-    method: log.method,
-    path: log.path,
-    target_url: log.targetUrl,
-    status_code: log.statusCode,
-    duration_ms: log.duration,
-    error_message: log.error,
-    channel: log.configName,
-    request_body: log.requestBody,
-    response_body: log.responsePreview,
-    request_headers: log.requestHeaders,
-    response_headers: log.responseHeaders,
-    // Build usage object if we have token data
-    usage: (log.inputTokens || log.outputTokens || log.model || log.requestModel) ? {
-      model: log.model || log.requestModel,
-      prompt_tokens: log.inputTokens || 0,
-      completion_tokens: log.outputTokens || 0,
-      total_tokens: (log.inputTokens || 0) + (log.outputTokens || 0),
-    } : undefined,
-  };
+function parseRealtimeSince(url: URL): number | undefined {
+  const since = Number(url.searchParams.get('since'));
+  return Number.isFinite(since) && since > 0 ? since : undefined;
+}
+
+function resolveRealtimeHubs(services: string[]): RealtimeHub[] {
+  return services
+    .filter((service): service is 'claude' | 'codex' => service === 'claude' || service === 'codex')
+    .map(service => realtimeHubs[service]);
+}
+
+/**
+ * Serve one dedicated proxy port, over HTTPS (and mTLS) when [proxy_tls] is configured
+ */
+function serveProxyPort(port: number, serviceName: 'claude' | 'codex', tenant?: TenantRuntime): void {
+  const proxyTls = systemConfig.proxyTls;
+  if (proxyTls) {
+    serveTlsProxy(port, proxyTls, (req, client) => handleDirectProxyRequest(req, serviceName, tenant, client));
+    return;
+  }
+  serve({
+    port,
+    development: process.env.NODE_ENV !== 'production',
+    async fetch(req) {
+      return handleDirectProxyRequest(req, serviceName, tenant);
+    },
+  });
+}
+
+// Start dedicated proxy servers to mirror legacy CLI behaviour (skipped in single-port mode)
+if (!systemConfig.singlePort) {
+  serveProxyPort(systemConfig.proxyPorts.claude, 'claude');
+  serveProxyPort(systemConfig.proxyPorts.codex, 'codex');
+}
+
+// Tenant-dedicated proxy ports always route to their tenant, regardless of client key
+for (const tenantConfig of systemConfig.tenants) {
+  const tenant = tenants.get(tenantConfig.name)!;
+  for (const serviceName of ['claude', 'codex'] as const) {
+    const port = tenantConfig.proxyPorts?.[serviceName];
+    if (!port) {
+      continue;
+    }
+    serveProxyPort(port, serviceName, tenant);
+    console.log(`Tenant ${tenant.name} ${serviceName} proxy: ${proxyScheme}://localhost:${port}`);
+  }
 }
 
 function serializeLastResult(result: LastRequestSnapshot) {
@@ -272,8 +778,8 @@ function serializeLastResult(result: LastRequestSnapshot) {
   };
 }
 
-function buildLastResults(serviceName: string) {
-  const snapshots = logger.getLastResultsByService(serviceName);
+function buildLastResults(serviceName: string, tenant: TenantRuntime = defaultTenant) {
+  const snapshots = tenant.logger.getLastResultsByService(serviceName);
   const payload: Record<string, ReturnType<typeof serializeLastResult>> = {};
   for (const [configName, snapshot] of Object.entries(snapshots)) {
     payload[configName] = serializeLastResult(snapshot);
@@ -285,7 +791,8 @@ async function applyConfigFreeze(
   serviceName: 'claude' | 'codex',
   serviceConfig: ServiceConfig,
   configName: string,
-  freezeUntil?: number
+  freezeUntil?: number,
+  manager: ConfigManager = configManager
 ): Promise<ProxyConfig | undefined> {
   const index = serviceConfig.configs.findIndex(c => c.name === configName);
   if (index === -1) {
@@ -300,9 +807,9 @@ async function applyConfigFreeze(
   }
 
   serviceConfig.configs[index] = nextConfig;
-  await configManager.saveServiceConfig(serviceName, serviceConfig);
+  await manager.saveServiceConfig(serviceName, serviceConfig);
 
-  const refreshed = configManager.getServiceConfig(serviceName);
+  const refreshed = manager.getServiceConfig(serviceName);
   if (!refreshed) {
     return undefined;
   }
@@ -325,8 +832,9 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
   // CORS headers
   const corsHeaders = {
     'Access-Control-Allow-Origin': '*',
-    'Access-Control-Allow-Methods': 'GET, POST, PUT, DELETE, OPTIONS',
-    'Access-Control-Allow-Headers': 'Content-Type, Authorization',
+    'Access-Control-Allow-Methods': 'GET, POST, PUT, PATCH, DELETE, OPTIONS',
+    'Access-Control-Allow-Headers': 'Content-Type, Authorization, If-None-Match, If-Modified-Since',
+    'Access-Control-Expose-Headers': 'ETag',
   };
 
   // Handle OPTIONS preflight
@@ -334,65 +842,267 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
     return new Response(null, { headers: corsHeaders });
   }
 
+  // Management endpoints operate on the tenant named by ?tenant= (default tenant when omitted)
+  const tenant = tenants.get(url.searchParams.get('tenant') || DEFAULT_TENANT);
+  if (!tenant) {
+    return Response.json({ error: 'Unknown tenant' }, { status: 404, headers: corsHeaders });
+  }
+  const { configManager, logger } = tenant;
+  const claudeLoadBalancer = tenant.loadBalancers.claude;
+  const codexLoadBalancer = tenant.loadBalancers.codex;
+
   try {
     // Health check
     if (path === '/api/status') {
+      const services = Object.fromEntries(SERVICE_NAMES.map(name => [name, serviceHealth(tenant, name)]));
       return Response.json({
-        status: 'ok',
+        status: Object.values(services).every(health => health.state === 'ok') ? 'ok' : 'degraded',
+        services,
         uptime: process.uptime(),
+        readOnly: systemConfig.readOnly,
+        databaseRecovery: logger.getDatabaseRecovery(),
+        certificateWarnings: certificateMonitor.warnings().map(toWireCertificateWarning),
+        lowQuota: tenant.quota.list().filter(snapshot => snapshot.low),
+        lastCrash: logger.listCrashes(1)[0] ?? null,
       }, { headers: corsHeaders });
     }
 
-    if (path === '/api/docs/claude/setup' && req.method === 'POST') {
-      const claudeDir = join(homedir(), '.claude');
-      const settingsPath = join(claudeDir, 'settings.json');
-      const backupPath = `${settingsPath}.backup`;
-      const settingsContent = {
-        env: {
-          ANTHROPIC_AUTH_TOKEN: '-',
-          ANTHROPIC_BASE_URL: 'http://localhost:8801',
-          CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC: '1',
-          CLAUDE_CODE_MAX_OUTPUT_TOKENS: '32000',
-          MAX_THINKING_TOKENS: '30000',
-          DISABLE_AUTOUPDATER: '1',
-        },
-        permissions: {
-          allow: [],
-          deny: [],
-        },
-        alwaysThinkingEnabled: true,
-      };
+    // Recorded uncaught exceptions and unhandled rejections, newest first
+    if (path === '/api/crashes' && req.method === 'GET') {
+      const limit = parseInt(url.searchParams.get('limit') || '20');
+      if (!Number.isFinite(limit) || limit <= 0 || limit > 500) {
+        return Response.json({ error: 'limit must be between 1 and 500' }, { status: 400, headers: corsHeaders });
+      }
+      return Response.json({ crashes: logger.listCrashes(limit) }, { headers: corsHeaders });
+    }
 
-      let backupCreated = false;
+    // Last certificate seen per upstream host (https configs of every tenant)
+    if (path === '/api/certificates' && req.method === 'GET') {
+      return Response.json({
+        enabled: systemConfig.tlsMonitor.enabled,
+        certificates: certificateMonitor.list(),
+        warnings: certificateMonitor.warnings().map(toWireCertificateWarning),
+      }, { headers: corsHeaders });
+    }
 
-      try {
-        mkdirSync(claudeDir, { recursive: true });
+    // Load balancer state for cluster peers
+    if (path === '/api/cluster/state' && req.method === 'GET') {
+      if (systemConfig.cluster.secret && !isAdminRequest(req, systemConfig.cluster.secret)) {
+        return Response.json({ error: 'Unauthorized' }, { status: 401, headers: corsHeaders });
+      }
+      return Response.json(buildClusterSnapshot(), { headers: corsHeaders });
+    }
 
-        if (existsSync(settingsPath)) {
-          if (existsSync(backupPath)) {
-            const timestampedBackup = `${backupPath}.${Date.now()}`;
-            renameSync(backupPath, timestampedBackup);
-          }
-          renameSync(settingsPath, backupPath);
-          backupCreated = true;
+    // Tenant names (client keys are never listed)
+    if (path === '/api/tenants' && req.method === 'GET') {
+      const budgets: Record<string, TenantBudgetStatus> = {};
+      for (const tenant of tenants.values()) {
+        if (tenant.budget) {
+          budgets[tenant.name] = tenant.budget.status();
         }
+      }
+      return Response.json({
+        tenants: Array.from(tenants.keys()),
+        budgets,
+      }, { headers: corsHeaders });
+    }
 
-        writeFileSync(settingsPath, `${JSON.stringify(settingsContent, null, 2)}\n`, 'utf8');
+    // Provider callbacks authenticate with their own signatures, so they bypass read-only mode
+    if (path.startsWith('/api/webhooks/') && req.method === 'POST') {
+      const provider = path.slice('/api/webhooks/'.length);
+      const webhook = systemConfig.webhooks.find(w => w.provider === provider);
+      if (!webhook) {
+        return Response.json({ error: 'Unknown webhook provider' }, { status: 404, headers: corsHeaders });
+      }
 
+      const rawBody = await readWebhookBody(req);
+      if (rawBody === null) {
         return Response.json(
-          {
-            success: true,
-            settingsPath,
-            backupCreated,
-            backupPath: backupCreated ? backupPath : null,
-          },
-          { headers: corsHeaders },
-        );
-      } catch (error) {
-        console.error('Failed to setup Claude Code settings:', error);
-        return Response.json(
-          { success: false, error: error instanceof Error ? error.message : String(error) },
-          { status: 500, headers: corsHeaders },
+          { error: `Webhook body is larger than ${MAX_WEBHOOK_BODY_BYTES} bytes` },
+          { status: 413, headers: corsHeaders }
+        );
+      }
+      const verification = verifyWebhookSignature(webhook, req.headers, rawBody);
+      if (!verification.ok) {
+        console.warn(`[webhook:${provider}] rejected: ${verification.error}`);
+        return Response.json({ error: verification.error }, { status: 401, headers: corsHeaders });
+      }
+
+      const payload = parseWebhookPayload(webhook, rawBody);
+      if (!payload) {
+        return Response.json({ error: 'Webhook body must be a JSON object' }, { status: 400, headers: corsHeaders });
+      }
+
+      const correlated = payload.upstreamId ? logger.getLogByUpstreamId(payload.upstreamId) : null;
+      const stored = logger.saveWebhookEvent({
+        id: verification.deliveryId,
+        provider,
+        eventType: payload.eventType,
+        upstreamId: payload.upstreamId,
+        requestId: correlated?.id,
+        receivedAt: Date.now(),
+        payload: rawBody.substring(0, 2000),
+      });
+
+      // Providers retry until they get a 2xx, so acknowledge redeliveries without publishing again
+      if (!stored) {
+        return Response.json({ received: true, duplicate: true }, { headers: corsHeaders });
+      }
+
+      const hubService = correlated?.service === 'codex' || correlated?.service === 'claude'
+        ? correlated.service
+        : webhook.service === 'codex' ? 'codex' : 'claude';
+      if (tenant.name === DEFAULT_TENANT) {
+        realtimeHubs[hubService].publish({
+          v: WIRE_VERSION,
+          type: 'webhook_received',
+          service: hubService,
+          timestamp: Date.now(),
+          data: {
+            provider,
+            event_type: payload.eventType,
+            upstream_id: payload.upstreamId,
+            request_id: correlated?.id,
+            channel: correlated?.configName,
+          },
+        });
+      }
+
+      return Response.json({ received: true, request_id: correlated?.id ?? null }, { headers: corsHeaders });
+    }
+
+    // Received webhook deliveries, optionally filtered to one request log
+    if (path === '/api/webhooks' && req.method === 'GET') {
+      const limit = parseInt(url.searchParams.get('limit') || '100');
+      const requestId = url.searchParams.get('request_id') || undefined;
+      const events = logger.getWebhookEvents(limit, requestId).map(event => ({
+        id: event.id,
+        provider: event.provider,
+        event_type: event.eventType,
+        upstream_id: event.upstreamId,
+        request_id: event.requestId,
+        received_at: event.receivedAt,
+        payload: event.payload,
+      }));
+      return Response.json({ events }, { headers: corsHeaders });
+    }
+
+    // Console log filter; a runtime setting, so it stays adjustable in read-only mode
+    if (path === '/api/admin/loglevel' && (req.method === 'GET' || req.method === 'PUT')) {
+      if (!systemConfig.adminToken) {
+        return Response.json(
+          { error: 'Log level changes are disabled. Set admin_token in system.toml or PAF_ADMIN_TOKEN.' },
+          { status: 403, headers: corsHeaders }
+        );
+      }
+      if (!isAdminRequest(req, systemConfig.adminToken)) {
+        return Response.json({ error: 'Unauthorized' }, { status: 401, headers: corsHeaders });
+      }
+
+      if (req.method === 'PUT') {
+        const body = await req.json().catch(() => ({}));
+        const ttlSeconds = body.ttl_seconds;
+        if (typeof body.filter !== 'string' || !body.filter.trim()) {
+          return Response.json({ error: 'filter is required, e.g. "info,proxy=debug"' }, { status: 400, headers: corsHeaders });
+        }
+        if (ttlSeconds !== undefined && (typeof ttlSeconds !== 'number' || ttlSeconds <= 0)) {
+          return Response.json({ error: 'ttl_seconds must be a positive number' }, { status: 400, headers: corsHeaders });
+        }
+        const applied = setLogFilter(body.filter);
+        if ('error' in applied) {
+          return Response.json({ error: applied.error }, { status: 400, headers: corsHeaders });
+        }
+
+        if (logFilterRevert) {
+          clearTimeout(logFilterRevert);
+          logFilterRevert = null;
+        }
+        if (ttlSeconds !== undefined) {
+          logFilterRevert = setTimeout(() => {
+            logFilterRevert = null;
+            setLogFilter(systemConfig.logLevel);
+            console.log(`[logging] log filter reverted to ${getLogFilter()?.spec}`);
+          }, ttlSeconds * 1000);
+        }
+        console.log(`[logging] log filter set to ${applied.spec}${ttlSeconds ? ` for ${ttlSeconds}s` : ''}`);
+      }
+
+      return Response.json(serializeLogFilter(getLogFilter(), systemConfig.logLevel), { headers: corsHeaders });
+    }
+
+    // Read-only mode: only GET requests reach the handlers below
+    if (systemConfig.readOnly && req.method !== 'GET') {
+      return Response.json(
+        { error: 'Server is running in read-only mode' },
+        { status: 403, headers: corsHeaders }
+      );
+    }
+
+    // Who is watching this instance: connected WebSocket clients with delivery counters and lag
+    if (path === '/api/realtime/clients' && req.method === 'GET') {
+      return Response.json({ clients: realtimeClients.list() }, { headers: corsHeaders });
+    }
+
+    const realtimeClientMatch = path.match(/^\/api\/realtime\/clients\/([^/]+)$/);
+    if (realtimeClientMatch && req.method === 'DELETE') {
+      const id = decodeURIComponent(realtimeClientMatch[1]);
+      if (!realtimeClients.disconnect(id)) {
+        return Response.json({ error: 'Realtime client not found' }, { status: 404, headers: corsHeaders });
+      }
+      console.log(`[realtime] disconnected client ${id}`);
+      return Response.json({ disconnected: id }, { headers: corsHeaders });
+    }
+
+    if (path === '/api/docs/claude/setup' && req.method === 'POST') {
+      const claudeDir = join(homedir(), '.claude');
+      const settingsPath = join(claudeDir, 'settings.json');
+      const backupPath = `${settingsPath}.backup`;
+      const settingsContent = {
+        env: {
+          ANTHROPIC_AUTH_TOKEN: '-',
+          ANTHROPIC_BASE_URL: 'http://localhost:8801',
+          CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC: '1',
+          CLAUDE_CODE_MAX_OUTPUT_TOKENS: '32000',
+          MAX_THINKING_TOKENS: '30000',
+          DISABLE_AUTOUPDATER: '1',
+        },
+        permissions: {
+          allow: [],
+          deny: [],
+        },
+        alwaysThinkingEnabled: true,
+      };
+
+      let backupCreated = false;
+
+      try {
+        mkdirSync(claudeDir, { recursive: true });
+
+        if (existsSync(settingsPath)) {
+          if (existsSync(backupPath)) {
+            const timestampedBackup = `${backupPath}.${Date.now()}`;
+            renameSync(backupPath, timestampedBackup);
+          }
+          renameSync(settingsPath, backupPath);
+          backupCreated = true;
+        }
+
+        writeFileSync(settingsPath, `${JSON.stringify(settingsContent, null, 2)}\n`, 'utf8');
+
+        return Response.json(
+          {
+            success: true,
+            settingsPath,
+            backupCreated,
+            backupPath: backupCreated ? backupPath : null,
+          },
+          { headers: corsHeaders },
+        );
+      } catch (error) {
+        console.error('Failed to setup Claude Code settings:', error);
+        return Response.json(
+          { success: false, error: error instanceof Error ? error.message : String(error) },
+          { status: 500, headers: corsHeaders },
         );
       }
     }
@@ -401,6 +1111,22 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
     if (path === '/api/configs/separated' && req.method === 'GET') {
       const claudeConfig = configManager.getServiceConfig('claude');
       const codexConfig = configManager.getServiceConfig('codex');
+      // The "current" config also depends on balancer picks and on freezes running out
+      const validators = changeValidators(
+        [configManager.getRevision(), logger.getRevision(), tenant.quota.getRevision()],
+        [
+          url.search,
+          claudeLoadBalancer.getCurrentServerName(),
+          codexLoadBalancer.getCurrentServerName(),
+          ...[...(claudeConfig?.configs ?? []), ...(codexConfig?.configs ?? [])]
+            .filter(c => c.freezeUntil && Date.now() < c.freezeUntil)
+            .map(c => c.name),
+        ].join('\n')
+      );
+      const unchanged = notModified(req, validators, corsHeaders);
+      if (unchanged) {
+        return unchanged;
+      }
 
       // For load_balance mode, simulate selecting a config to display as "current"
       const getCurrentConfig = (serviceName: 'claude' | 'codex', config: ServiceConfig | undefined) => {
@@ -443,33 +1169,84 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
 
       return Response.json({
         claude: {
-          configs: claudeConfig?.configs || [],
+          configs: withQuota('claude', claudeConfig?.configs || [], tenant),
           active: claudeConfig?.active,
           mode: claudeConfig?.mode || 'manual',
           current: getCurrentConfig('claude', claudeConfig),
-          last_results: buildLastResults('claude'),
+          last_results: buildLastResults('claude', tenant),
         },
         codex: {
-          configs: codexConfig?.configs || [],
+          configs: withQuota('codex', codexConfig?.configs || [], tenant),
           active: codexConfig?.active,
           mode: codexConfig?.mode || 'manual',
           current: getCurrentConfig('codex', codexConfig),
-          last_results: buildLastResults('codex'),
+          last_results: buildLastResults('codex', tenant),
         },
+      }, { headers: { ...corsHeaders, ...validatorHeaders(validators) } });
+    }
+
+    // Configs added, updated or removed after ?since=<cursor>; ?wait=<seconds> holds the request until
+    // something changes (long polling). Without a cursor (or after a restart) everything is listed as added.
+    if (path === '/api/configs/changes' && req.method === 'GET') {
+      const feed = configManager.getChangeFeed();
+      const since = url.searchParams.get('since') || undefined;
+      const wait = Math.min(CONFIG_CHANGES_MAX_WAIT_SECONDS, Math.max(0, Number(url.searchParams.get('wait')) || 0));
+      if (wait > 0) {
+        await feed.waitForChange(since, wait * 1000, req.signal);
+      }
+
+      const { cursor, reset, changes } = feed.since(since);
+      return Response.json({
+        cursor,
+        reset,
+        changes: changes.map(change => {
+          const serviceConfig = configManager.getServiceConfig(change.service);
+          const config = change.config ? serviceConfig?.configs.find(c => c.name === change.config) : undefined;
+          return {
+            service: change.service,
+            config: change.config ?? null,
+            action: change.action,
+            revision: change.revision,
+            changed_at: change.changedAt,
+            // Current state: a redacted config, or the service's mode and active config
+            data:
+              change.action === 'removed'
+                ? null
+                : change.config
+                  ? config && withQuota(change.service, [config], tenant)[0]
+                  : { mode: serviceConfig?.mode || 'manual', active: serviceConfig?.active },
+          };
+        }),
       }, { headers: corsHeaders });
     }
 
     // Get all configs
     if (path === '/api/configs' && req.method === 'GET') {
+      const validators = changeValidators(
+        [configManager.getRevision(), logger.getRevision(), tenant.quota.getRevision()],
+        url.search
+      );
+      const unchanged = notModified(req, validators, corsHeaders);
+      if (unchanged) {
+        return unchanged;
+      }
+
       const serviceName = url.searchParams.get('service') || 'claude';
       const serviceConfig = configManager.getServiceConfig(serviceName);
-      const lastResults = buildLastResults(serviceName);
+      const lastResults = buildLastResults(serviceName, tenant);
 
       return Response.json({
-        configs: serviceConfig?.configs || [],
+        configs: withQuota(serviceName, serviceConfig?.configs || [], tenant),
         active: serviceConfig?.active,
         mode: serviceConfig?.mode || 'manual',
         last_results: lastResults,
+      }, { headers: { ...corsHeaders, ...validatorHeaders(validators) } });
+    }
+
+    // Known anthropic-beta flags, for the config form
+    if (path === '/api/anthropic-betas' && req.method === 'GET') {
+      return Response.json({
+        betas: Object.entries(KNOWN_ANTHROPIC_BETAS).map(([value, description]) => ({ value, description })),
       }, { headers: corsHeaders });
     }
 
@@ -483,6 +1260,22 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
         return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
       }
 
+      const betas = body.anthropic_beta === undefined ? undefined : validateAnthropicBetas(body.anthropic_beta);
+      if (betas && 'error' in betas) {
+        return Response.json({ error: betas.error }, { status: 400, headers: corsHeaders });
+      }
+      if (body.provider_profile != null && !isProviderProfile(body.provider_profile)) {
+        return Response.json({ error: unknownProviderProfile(body.provider_profile) }, { status: 400, headers: corsHeaders });
+      }
+      const apiFormatProblem = body.api_format == null ? null : apiFormatError(serviceName, body.api_format);
+      if (apiFormatProblem) {
+        return Response.json({ error: apiFormatProblem }, { status: 400, headers: corsHeaders });
+      }
+      const proxyUrlProblem = body.proxy_url == null || body.proxy_url === '' ? null : proxyUrlError(body.proxy_url);
+      if (proxyUrlProblem) {
+        return Response.json({ error: proxyUrlProblem }, { status: 400, headers: corsHeaders });
+      }
+
       // Convert snake_case to camelCase
       const config = {
         name: body.name,
@@ -491,13 +1284,51 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
         apiKey: body.api_key || body.apiKey,
         weight: body.weight || 1,
         enabled: body.enabled !== false,
+        fingerprint: parseFingerprint(body.fingerprint),
+        anthropicBeta: betas?.betas.length ? betas.betas : undefined,
+        contextTrim: parseContextTrim(body.context_trim),
+        quota: parseQuotaConfig(body.quota),
+        responseLanguage: parseResponseLanguage(body.response_language),
+        providerProfile: body.provider_profile ?? undefined,
+        quirks: parseQuirks(body.quirks),
+        upstreamCompression: typeof body.upstream_compression === 'boolean' ? body.upstream_compression : undefined,
+        promptCache: parsePromptCache(body.prompt_cache),
+        apiFormat: body.api_format ?? undefined,
+        requestCompression: parseRequestCompression(body.request_compression),
+        requestsPerMinute: parsePerMinuteLimit(body.requests_per_minute),
+        tokensPerMinute: parsePerMinuteLimit(body.tokens_per_minute),
+        proxyUrl: body.proxy_url ? body.proxy_url.trim() : undefined,
+        modelMap: parseModelMap(body.model_map),
+        timeoutSecs: parseTimeoutSecs(body.timeout_secs),
+        connectTimeoutSecs: parseTimeoutSecs(body.connect_timeout_secs),
       };
 
+      // ?verify=true runs the connectivity test first and only saves a config that passes
+      let test: ConfigTestExecutionResult | undefined;
+      if (url.searchParams.get('verify') === 'true') {
+        if (serviceName !== 'claude' && serviceName !== 'codex') {
+          return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+        }
+        if (serviceConfig.configs.some(c => c.name === config.name)) {
+          return Response.json(
+            { error: `Config "${config.name}" already exists` },
+            { status: 409, headers: corsHeaders }
+          );
+        }
+        test = await runConfigTest(serviceName, config, serviceConfig, tenant);
+        if (!test.success) {
+          return Response.json(
+            { error: `Connectivity test failed: ${test.message}`, test },
+            { status: 422, headers: corsHeaders }
+          );
+        }
+      }
+
       // Add new config
       serviceConfig.configs.push(config);
       await configManager.saveServiceConfig(serviceName, serviceConfig);
 
-      return Response.json({ success: true }, { headers: corsHeaders });
+      return Response.json({ success: true, warnings: betas?.warnings ?? [], test }, { headers: corsHeaders });
     }
 
     // Update service mode (must be before dynamic routes)
@@ -524,6 +1355,7 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
       }
 
       await configManager.saveServiceConfig(serviceName, serviceConfig);
+      warmUpConnections(configManager, serviceName);
 
       return Response.json({ success: true }, { headers: corsHeaders });
     }
@@ -555,151 +1387,1134 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
       if (body.apiKey !== undefined) updates.apiKey = body.apiKey;
       if (body.weight !== undefined) updates.weight = body.weight;
       if (body.enabled !== undefined) updates.enabled = body.enabled;
+      if (body.fingerprint !== undefined) updates.fingerprint = parseFingerprint(body.fingerprint);
+      if (body.context_trim !== undefined) updates.contextTrim = parseContextTrim(body.context_trim);
+      if (body.quota !== undefined) updates.quota = parseQuotaConfig(body.quota);
+      if (body.response_language !== undefined) updates.responseLanguage = parseResponseLanguage(body.response_language);
+      if (body.quirks !== undefined) updates.quirks = parseQuirks(body.quirks);
+      if (body.prompt_cache !== undefined) updates.promptCache = parsePromptCache(body.prompt_cache);
+      if (body.request_compression !== undefined) {
+        updates.requestCompression = parseRequestCompression(body.request_compression);
+      }
+      if (body.requests_per_minute !== undefined) updates.requestsPerMinute = parsePerMinuteLimit(body.requests_per_minute);
+      if (body.tokens_per_minute !== undefined) updates.tokensPerMinute = parsePerMinuteLimit(body.tokens_per_minute);
+      if (body.model_map !== undefined) updates.modelMap = parseModelMap(body.model_map);
+      if (body.timeout_secs !== undefined) updates.timeoutSecs = parseTimeoutSecs(body.timeout_secs);
+      if (body.connect_timeout_secs !== undefined) {
+        updates.connectTimeoutSecs = parseTimeoutSecs(body.connect_timeout_secs);
+      }
+      if (body.proxy_url !== undefined) {
+        if (!body.proxy_url) {
+          updates.proxyUrl = undefined;
+        } else if (body.proxy_url !== maskProxyUrl(serviceConfig.configs[index].proxyUrl)) {
+          // A masked URL echoed back from GET keeps the stored one
+          const proxyUrlProblem = proxyUrlError(body.proxy_url);
+          if (proxyUrlProblem) {
+            return Response.json({ error: proxyUrlProblem }, { status: 400, headers: corsHeaders });
+          }
+          updates.proxyUrl = body.proxy_url.trim();
+        }
+      }
+      if (body.upstream_compression !== undefined) {
+        updates.upstreamCompression = typeof body.upstream_compression === 'boolean' ? body.upstream_compression : undefined;
+      }
+      if (body.provider_profile !== undefined) {
+        if (body.provider_profile !== null && !isProviderProfile(body.provider_profile)) {
+          return Response.json({ error: unknownProviderProfile(body.provider_profile) }, { status: 400, headers: corsHeaders });
+        }
+        updates.providerProfile = body.provider_profile ?? undefined;
+      }
+      if (body.api_format !== undefined) {
+        const apiFormatProblem = body.api_format === null ? null : apiFormatError(serviceName, body.api_format);
+        if (apiFormatProblem) {
+          return Response.json({ error: apiFormatProblem }, { status: 400, headers: corsHeaders });
+        }
+        updates.apiFormat = body.api_format ?? undefined;
+      }
+
+      const betas = body.anthropic_beta === undefined ? undefined : validateAnthropicBetas(body.anthropic_beta);
+      if (betas && 'error' in betas) {
+        return Response.json({ error: betas.error }, { status: 400, headers: corsHeaders });
+      }
+      if (betas) updates.anthropicBeta = betas.betas.length ? betas.betas : undefined;
+
+      serviceConfig.configs[index] = { ...serviceConfig.configs[index], ...updates };
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+
+      return Response.json({ success: true, warnings: betas?.warnings ?? [] }, { headers: corsHeaders });
+    }
+
+    // Reveal stored credentials (admin token required)
+    const secretsMatch = path.match(/^\/api\/configs\/([^/]+)\/([^/]+)\/secrets$/);
+    if (secretsMatch && req.method === 'GET') {
+      if (!systemConfig.adminToken) {
+        return Response.json(
+          { error: 'Secret reveal is disabled. Set admin_token in system.toml or PAF_ADMIN_TOKEN.' },
+          { status: 403, headers: corsHeaders }
+        );
+      }
+      if (!isAdminRequest(req, systemConfig.adminToken)) {
+        return Response.json({ error: 'Unauthorized' }, { status: 401, headers: corsHeaders });
+      }
+
+      const serviceConfig = configManager.getServiceConfig(decodeURIComponent(secretsMatch[1]));
+      const config = serviceConfig?.configs.find(c => c.name === decodeURIComponent(secretsMatch[2]));
+      if (!config) {
+        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      return Response.json({
+        api_key: config.apiKey ?? null,
+        auth_token: config.authToken ?? null,
+      }, { headers: corsHeaders });
+    }
+
+    // Encrypted config bundle for `paf sync`: GET exports, POST applies only what differs
+    if (path === '/api/sync/bundle' && (req.method === 'GET' || req.method === 'POST')) {
+      const passphrase = systemConfig.sync.passphrase;
+      if (!systemConfig.adminToken || !passphrase) {
+        return Response.json(
+          { error: 'Config sync is disabled. Set admin_token and [sync] passphrase in system.toml.' },
+          { status: 403, headers: corsHeaders }
+        );
+      }
+      if (!isAdminRequest(req, systemConfig.adminToken)) {
+        return Response.json({ error: 'Unauthorized' }, { status: 401, headers: corsHeaders });
+      }
+
+      if (req.method === 'GET') {
+        return new Response(encryptSyncBundle(exportSyncBundle(configManager), passphrase), {
+          headers: { ...corsHeaders, 'Content-Type': 'application/json' },
+        });
+      }
+
+      const bundle = decryptSyncBundle(await req.text(), passphrase);
+      if ('error' in bundle) {
+        return Response.json({ error: bundle.error }, { status: 400, headers: corsHeaders });
+      }
+      const dryRun = url.searchParams.get('dry_run') === 'true';
+      const changes = await applySyncBundle(configManager, bundle, {
+        prune: url.searchParams.get('prune') === 'true',
+        dryRun,
+      });
+
+      if (!dryRun) {
+        for (const service of SERVICE_NAMES) {
+          const count = changes.filter(change => change.service === service).length;
+          if (count > 0) {
+            realtimeHubs[service].publish({
+              v: WIRE_VERSION,
+              type: 'settings_changed',
+              service,
+              timestamp: Date.now(),
+              data: { setting: 'sync', changes: count },
+            });
+          }
+        }
+      }
+
+      return Response.json({ dry_run: dryRun, exported_at: bundle.exportedAt, changes }, { headers: corsHeaders });
+    }
+
+    // Partially update non-credential fields: PATCH /api/configs/:service/:name
+    const patchMatch = path.match(/^\/api\/configs\/([^/]+)\/([^/]+)$/);
+    if (patchMatch && req.method === 'PATCH') {
+      const serviceName = decodeURIComponent(patchMatch[1]);
+      const configName = decodeURIComponent(patchMatch[2]);
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const index = serviceConfig.configs.findIndex(c => c.name === configName);
+      if (index === -1) {
+        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const body = await req.json();
+      const parsed = parseConfigPatch(body);
+      if ('error' in parsed) {
+        return Response.json({ error: parsed.error }, { status: 400, headers: corsHeaders });
+      }
+
+      serviceConfig.configs[index] = { ...serviceConfig.configs[index], ...parsed.updates };
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+
+      return Response.json({ success: true }, { headers: corsHeaders });
+    }
+
+    // Delete config
+    if (path.match(/^\/api\/configs\/[^/]+$/) && req.method === 'DELETE') {
+      const configName = path.split('/').pop()!;
+      const serviceName = url.searchParams.get('service') || 'claude';
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      // Remove config
+      serviceConfig.configs = serviceConfig.configs.filter(c => c.name !== configName);
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+      logger.clearLastResult(serviceName, configName);
+
+      return Response.json({ success: true }, { headers: corsHeaders });
+    }
+
+    // Freeze/unfreeze config
+    if (path.match(/^\/api\/configs\/[^/]+\/freeze$/) && req.method === 'PUT') {
+      const configName = path.split('/')[3];
+      const serviceName = url.searchParams.get('service') || 'claude';
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const body = await req.json();
+      const freezeUntil = body.freezeUntil || null;
+
+      // Update config
+      const index = serviceConfig.configs.findIndex(c => c.name === configName);
+      if (index === -1) {
+        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      serviceConfig.configs[index] = {
+        ...serviceConfig.configs[index],
+        freezeUntil,
+      };
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+
+      return Response.json({ success: true }, { headers: corsHeaders });
+    }
+
+    // Activate config
+    if (path.match(/^\/api\/configs\/[^/]+\/activate$/) && req.method === 'POST') {
+      const configName = path.split('/')[3];
+      const serviceName = url.searchParams.get('service') || 'claude';
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const exists = serviceConfig.configs.some(c => c.name === configName);
+      if (!exists) {
+        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      // Set active config
+      serviceConfig.active = configName;
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+      warmUpConnections(configManager, serviceName);
+
+      return Response.json({ success: true }, { headers: corsHeaders });
+    }
+
+    // Get load balancer config
+    if (path === '/api/loadbalancer' && req.method === 'GET') {
+      const serviceName = url.searchParams.get('service') || 'claude';
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      const isService = serviceName === 'claude' || serviceName === 'codex';
+      const loadBalancerInstance = isService ? tenant.loadBalancers[serviceName] : null;
+
+      return Response.json({
+        loadBalancer: serviceConfig?.loadBalancer || null,
+        pin: loadBalancerInstance?.getPin() ?? null,
+        strategies: selectionStrategyNames(),
+        pools: isService ? tenant.proxies[serviceName].getPoolStatus() : [],
+      }, { headers: corsHeaders });
+    }
+
+    // Update load balancer config
+    if (path === '/api/loadbalancer' && req.method === 'PUT') {
+      const body = await req.json();
+      const serviceName = url.searchParams.get('service') || 'claude';
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      if (body.strategy !== undefined && !isSelectionStrategy(body.strategy)) {
+        return Response.json(
+          { error: `Invalid strategy. Must be one of: ${selectionStrategyNames().join(', ')}` },
+          { status: 400, headers: corsHeaders }
+        );
+      }
+
+      // Merge so fields the dashboard does not edit (e.g. retryBudget) survive a save
+      serviceConfig.loadBalancer = { ...serviceConfig.loadBalancer, ...body };
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+
+      // Update load balancer based on service
+      if (serviceName === 'claude') {
+        claudeLoadBalancer.updateConfig(serviceConfig.loadBalancer);
+      } else if (serviceName === 'codex') {
+        codexLoadBalancer.updateConfig(serviceConfig.loadBalancer);
+      }
+
+      return Response.json({ success: true }, { headers: corsHeaders });
+    }
+
+    // Switch load balancer strategy without resending the whole config
+    const lbModeMatch = path.match(/^\/api\/loadbalancer\/([^/]+)\/mode$/);
+    if (lbModeMatch && req.method === 'POST') {
+      const serviceName = decodeURIComponent(lbModeMatch[1]);
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig || (serviceName !== 'claude' && serviceName !== 'codex')) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const body = await req.json();
+      if (!isSelectionStrategy(body.mode)) {
+        return Response.json(
+          { error: `Invalid mode. Must be one of: ${selectionStrategyNames().join(', ')}` },
+          { status: 400, headers: corsHeaders }
+        );
+      }
+
+      serviceConfig.loadBalancer = { ...serviceConfig.loadBalancer, strategy: body.mode };
+      await configManager.saveServiceConfig(serviceName, serviceConfig);
+
+      const loadBalancerInstance = serviceName === 'claude' ? claudeLoadBalancer : codexLoadBalancer;
+      loadBalancerInstance.updateConfig(serviceConfig.loadBalancer);
+
+      realtimeHubs[serviceName].publish({
+        v: WIRE_VERSION,
+        type: 'settings_changed',
+        service: serviceName,
+        timestamp: Date.now(),
+        data: { setting: 'loadbalancer.strategy', value: body.mode },
+      });
+
+      return Response.json({ success: true, mode: body.mode }, { headers: corsHeaders });
+    }
+
+    // Pin all routing to one config for a while, e.g. during a provider incident; in memory only
+    const lbPinMatch = path.match(/^\/api\/loadbalancer\/([^/]+)\/pin$/);
+    if (lbPinMatch) {
+      const serviceName = decodeURIComponent(lbPinMatch[1]);
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig || (serviceName !== 'claude' && serviceName !== 'codex')) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+      const loadBalancerInstance = tenant.loadBalancers[serviceName];
+
+      if (req.method === 'GET') {
+        return Response.json({ pin: loadBalancerInstance.getPin() }, { headers: corsHeaders });
+      }
+
+      if (req.method === 'POST' || req.method === 'DELETE') {
+        let pin = null;
+        if (req.method === 'POST') {
+          const body = await req.json().catch(() => null);
+          if (typeof body?.config !== 'string' || !serviceConfig.configs.some(c => c.name === body.config)) {
+            return Response.json({ error: 'config must name an existing config' }, { status: 400, headers: corsHeaders });
+          }
+          const ttlSeconds = body.ttl_seconds ?? 3600;
+          if (typeof ttlSeconds !== 'number' || ttlSeconds <= 0 || ttlSeconds > 86400) {
+            return Response.json(
+              { error: 'ttl_seconds must be between 1 and 86400' },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+
+          pin = loadBalancerInstance.pinServer(
+            body.config,
+            ttlSeconds * 1000,
+            typeof body.reason === 'string' ? body.reason : undefined
+          );
+          console.log(`[loadbalancer] ${serviceName} pinned to ${body.config} for ${ttlSeconds}s`);
+        } else if (loadBalancerInstance.unpinServer()) {
+          console.log(`[loadbalancer] ${serviceName} pin removed`);
+        }
+
+        realtimeHubs[serviceName].publish({
+          v: WIRE_VERSION,
+          type: 'settings_changed',
+          service: serviceName,
+          timestamp: Date.now(),
+          data: { setting: 'loadbalancer.pin', value: pin },
+        });
+
+        return Response.json({ success: true, pin }, { headers: corsHeaders });
+      }
+    }
+
+    // Per-config availability windows; configs outside theirs are skipped by selection
+    const lbScheduleMatch = path.match(/^\/api\/loadbalancer\/([^/]+)\/schedule$/);
+    if (lbScheduleMatch) {
+      const serviceName = decodeURIComponent(lbScheduleMatch[1]);
+      const serviceConfig = configManager.getServiceConfig(serviceName);
+
+      if (!serviceConfig || (serviceName !== 'claude' && serviceName !== 'codex')) {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const describeSchedules = () =>
+        serviceConfig.configs.map(config => ({
+          config: config.name,
+          availability: serializeAvailability(config.availability) ?? null,
+          available_now: isWithinSchedule(config.availability),
+        }));
+
+      if (req.method === 'GET') {
+        return Response.json({ schedules: describeSchedules() }, { headers: corsHeaders });
+      }
+
+      // PUT sets a config's schedule; null (or DELETE) makes it available around the clock again
+      if (req.method === 'PUT' || req.method === 'DELETE') {
+        const body = await req.json().catch(() => null);
+        const configName = req.method === 'DELETE' ? url.searchParams.get('config') ?? body?.config : body?.config;
+        const target = serviceConfig.configs.find(c => c.name === configName);
+        if (!target) {
+          return Response.json({ error: 'config must name an existing config' }, { status: 400, headers: corsHeaders });
+        }
+
+        let availability: AvailabilitySchedule | undefined;
+        if (req.method === 'PUT' && body.availability != null) {
+          const parsed = parseAvailability(body.availability);
+          if ('error' in parsed) {
+            return Response.json({ error: parsed.error }, { status: 400, headers: corsHeaders });
+          }
+          availability = parsed;
+        }
+
+        target.availability = availability;
+        await configManager.saveServiceConfig(serviceName, serviceConfig);
+        console.log(
+          `[loadbalancer] ${serviceName}/${target.name} ${availability ? 'availability schedule updated' : 'available around the clock'}`
+        );
+
+        realtimeHubs[serviceName].publish({
+          v: WIRE_VERSION,
+          type: 'settings_changed',
+          service: serviceName,
+          timestamp: Date.now(),
+          data: { setting: 'loadbalancer.schedule', value: { config: target.name, availability: serializeAvailability(availability) ?? null } },
+        });
+
+        return Response.json({ success: true, schedules: describeSchedules() }, { headers: corsHeaders });
+      }
+    }
+
+    // Every request of one client session as HAR, a curl script or JSON lines; credentials are removed
+    const sessionExportMatch = path.match(/^\/api\/logs\/export\/session\/([^/]+)$/);
+    if (sessionExportMatch && req.method === 'GET') {
+      const conversationId = decodeURIComponent(sessionExportMatch[1]);
+      const format = (url.searchParams.get('format') || 'har') as SessionExportFormat;
+      if (!SESSION_EXPORT_FORMATS.includes(format)) {
+        return Response.json(
+          { error: `format must be one of: ${SESSION_EXPORT_FORMATS.join(', ')}` },
+          { status: 400, headers: corsHeaders }
+        );
+      }
+
+      const logs = logger.getConversationLogs(conversationId);
+      if (logs.length === 0) {
+        return Response.json({ error: 'No requests logged for this conversation' }, { status: 404, headers: corsHeaders });
+      }
+
+      const filename = `paf-session-${conversationId.replace(/[^A-Za-z0-9_-]/g, '_')}`;
+      const [body, contentType, extension] =
+        format === 'curl'
+          ? [toCurlScript(logs, conversationId), 'text/x-shellscript', 'sh']
+          : format === 'jsonl'
+            ? [toJsonLines(logs), 'application/x-ndjson', 'jsonl']
+            : [JSON.stringify(toHar(logs, version), null, 2), 'application/json', 'har'];
+      return new Response(body, {
+        headers: {
+          ...corsHeaders,
+          'content-type': contentType,
+          'content-disposition': `attachment; filename="${filename}.${extension}"`,
+        },
+      });
+    }
+
+    // Get logs
+    if (path === '/api/logs' && req.method === 'GET') {
+      const limit = parseInt(url.searchParams.get('limit') || '100');
+      const offset = parseInt(url.searchParams.get('offset') || '0');
+
+      // ?view=<id> applies a saved view; otherwise filters come from the query string
+      const viewId = url.searchParams.get('view');
+      const view = viewId ? tenant.monitor.getView(viewId) : undefined;
+      if (viewId && !view) {
+        return Response.json({ error: 'Log view not found' }, { status: 404, headers: corsHeaders });
+      }
+      // Dashboard polls skip the query entirely while nothing was logged; a view's own query is part of the tag
+      const validators = changeValidators(
+        [logger.getRevision()],
+        `${url.search}\n${JSON.stringify(view?.query ?? null)}`
+      );
+      const unchanged = notModified(req, validators, corsHeaders);
+      if (unchanged) {
+        return unchanged;
+      }
+      const logs = logger.getRecentLogs(limit, offset, view?.query ?? parseLogQuery(url.searchParams));
+
+      // Convert logs to frontend format
+      const convertedLogs = logs.map(toWireRequestLog);
+
+      return Response.json({ logs: convertedLogs }, { headers: { ...corsHeaders, ...validatorHeaders(validators) } });
+    }
+
+    // Clear all logs
+    if (path === '/api/logs' && req.method === 'DELETE') {
+      const deletedCount = logger.clearAllLogs();
+      return Response.json({ success: true, deletedCount }, { headers: corsHeaders });
+    }
+
+    // What paf did to a request: config selection, transforms and upstream attempts (id from x-paf-request-id)
+    const logContextMatch = path.match(/^\/api\/logs\/([^/]+)\/context$/);
+    if (logContextMatch && req.method === 'GET') {
+      const log = logger.getLogById(decodeURIComponent(logContextMatch[1]));
+      if (!log) {
+        return Response.json({ error: 'Log not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      return Response.json({
+        id: log.id,
+        service: log.service,
+        timestamp: log.timestamp,
+        config_name: log.configName,
+        status_code: log.statusCode,
+        outcome: log.outcome,
+        // Requests logged before contexts were recorded have none
+        context: log.context ?? null,
+      }, { headers: corsHeaders });
+    }
+
+    // Get log by ID
+    if (path.match(/^\/api\/logs\/[^/]+$/) && req.method === 'GET') {
+      const logId = path.split('/').pop()!;
+      const log = logger.getLogById(logId);
+
+      if (!log) {
+        return Response.json({ error: 'Log not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      // Convert log to frontend format
+      const convertedLog = toWireRequestLog(log);
+
+      return Response.json({ log: convertedLog }, { headers: corsHeaders });
+    }
+
+    // Get usage stats
+    if (path === '/api/stats' && req.method === 'GET') {
+      const stats = logger.getUsageStats(parseLogQuery(url.searchParams));
+      return Response.json({ stats }, { headers: corsHeaders });
+    }
+
+    // Latency per config split into proxy, headers (connection + queueing), first byte and body phases
+    if (path === '/api/stats/latency' && req.method === 'GET') {
+      const windowMinutes = Number(url.searchParams.get('window_minutes') || 24 * 60);
+      if (!(windowMinutes > 0)) {
+        return Response.json({ error: 'window_minutes must be positive' }, { status: 400, headers: corsHeaders });
+      }
+      const service = url.searchParams.get('service') || undefined;
+      return Response.json({
+        window_minutes: windowMinutes,
+        configs: logger.getLatencyBreakdown(windowMinutes, service),
+      }, { headers: corsHeaders });
+    }
+
+    // Prompt cache reads and writes per config, see prompt_cache in the config settings
+    if (path === '/api/stats/prompt-cache' && req.method === 'GET') {
+      const windowMinutes = Number(url.searchParams.get('window_minutes') || 24 * 60);
+      if (!(windowMinutes > 0)) {
+        return Response.json({ error: 'window_minutes must be positive' }, { status: 400, headers: corsHeaders });
+      }
+      const service = url.searchParams.get('service') || undefined;
+      return Response.json({
+        window_minutes: windowMinutes,
+        configs: logger.getPromptCacheStats(windowMinutes, service),
+      }, { headers: corsHeaders });
+    }
+
+    // Upload bytes saved by gzipping request bodies, see request_compression in the config settings
+    if (path === '/api/stats/request-compression' && req.method === 'GET') {
+      const windowMinutes = Number(url.searchParams.get('window_minutes') || 24 * 60);
+      if (!(windowMinutes > 0)) {
+        return Response.json({ error: 'window_minutes must be positive' }, { status: 400, headers: corsHeaders });
+      }
+      const service = url.searchParams.get('service') || undefined;
+      return Response.json({
+        window_minutes: windowMinutes,
+        configs: logger.getRequestCompressionStats(windowMinutes, service),
+      }, { headers: corsHeaders });
+    }
+
+    // Protocol compliance score per config, see validate_responses in the service TOML
+    if (path === '/api/stats/compliance' && req.method === 'GET') {
+      const windowMinutes = Number(url.searchParams.get('window_minutes') || 24 * 60);
+      if (!(windowMinutes > 0)) {
+        return Response.json({ error: 'window_minutes must be positive' }, { status: 400, headers: corsHeaders });
+      }
+      const service = url.searchParams.get('service') || undefined;
+      return Response.json({
+        window_minutes: windowMinutes,
+        configs: logger.getComplianceStats(windowMinutes, service),
+      }, { headers: corsHeaders });
+    }
+
+    // Prompt/completion token and body size histograms per service and model
+    if (path === '/api/stats/distributions' && req.method === 'GET') {
+      const windowMinutes = Number(url.searchParams.get('window_minutes') || 24 * 60);
+      if (!(windowMinutes > 0)) {
+        return Response.json({ error: 'window_minutes must be positive' }, { status: 400, headers: corsHeaders });
+      }
+      const service = url.searchParams.get('service') || undefined;
+      return Response.json({
+        window_minutes: windowMinutes,
+        models: logger.getDistributions(windowMinutes, service),
+      }, { headers: corsHeaders });
+    }
+
+    // Availability per config (successful minutes / minutes with traffic or config tests)
+    if (path === '/api/stats/sla' && req.method === 'GET') {
+      const service = url.searchParams.get('service') || undefined;
+      return Response.json({ configs: logger.getSlaReport([7, 30, 90], service) }, { headers: corsHeaders });
+    }
+
+    // Usage per value of a request tag (x-paf-tags), e.g. ?key=project
+    if (path === '/api/stats/tags' && req.method === 'GET') {
+      const key = url.searchParams.get('key') || '';
+      if (!TAG_KEY_PATTERN.test(key)) {
+        return Response.json({ error: 'key must be a tag name' }, { status: 400, headers: corsHeaders });
+      }
+      return Response.json({ key, values: logger.getUsageByTag(key) }, { headers: corsHeaders });
+    }
+
+    // Retry budget consumption per config
+    if (path === '/api/stats/retry-budget' && req.method === 'GET') {
+      return Response.json({
+        claude: claudeLoadBalancer.getRetryBudgetStats(),
+        codex: codexLoadBalancer.getRetryBudgetStats(),
+      }, { headers: corsHeaders });
+    }
+
+    // Per-upstream-host connection metrics
+    if (path === '/api/stats/connections' && req.method === 'GET') {
+      return Response.json({ hosts: connectionStats.snapshot() }, { headers: corsHeaders });
+    }
+
+    // Body buffering against body_memory_limit_bytes (process-wide, not per tenant)
+    if (path === '/api/stats/memory' && req.method === 'GET') {
+      return Response.json(memoryBudget.snapshot(), { headers: corsHeaders });
+    }
+
+    // In-flight proxied requests against max_concurrent_requests (system-wide, then per service of this tenant)
+    if (path === '/api/stats/concurrency' && req.method === 'GET') {
+      return Response.json({
+        global: concurrency.snapshot(),
+        claude: tenant.concurrencyLimiters.claude.snapshot(),
+        codex: tenant.concurrencyLimiters.codex.snapshot(),
+      }, { headers: corsHeaders });
+    }
+
+    // Claude Code hook callback (`paf hook` forwards the hook's stdin): tags the session's later requests
+    // and answers with a systemMessage for events the session hasn't been told about
+    if (path === '/api/hooks/claude-code' && req.method === 'POST') {
+      const body = await req.json().catch(() => null);
+      if (!body || typeof body !== 'object' || Array.isArray(body)) {
+        return Response.json({ error: 'Hook input must be a JSON object' }, { status: 400, headers: corsHeaders });
+      }
+      const events = tenant.hookSessions.handle(body as ClaudeCodeHookInput, tenant.hookEvents);
+      return Response.json(claudeCodeHookOutput(events), { headers: corsHeaders });
+    }
+
+    // Hook events after ?since=<seq> (provider_switch, budget_threshold); ?session=<id> leaves out other
+    // sessions' switches. Poll again with the returned cursor.
+    if (path === '/api/hooks/events' && req.method === 'GET') {
+      const since = Math.max(0, Number(url.searchParams.get('since')) || 0);
+      const session = url.searchParams.get('session') || undefined;
+      return Response.json({
+        cursor: tenant.hookEvents.latest(),
+        events: tenant.hookEvents.since(since, session).map(event => ({
+          seq: event.seq,
+          type: event.type,
+          service: event.service,
+          session: event.session ?? null,
+          timestamp: event.timestamp,
+          message: event.message,
+          data: event.data,
+        })),
+      }, { headers: corsHeaders });
+    }
+
+    // Sessions that sent hook callbacks, with the tags their requests get
+    if (path === '/api/hooks/sessions' && req.method === 'GET') {
+      return Response.json({
+        sessions: tenant.hookSessions.list().map(({ session, tags, updatedAt }) => ({
+          session,
+          tags,
+          updated_at: updatedAt,
+        })),
+      }, { headers: corsHeaders });
+    }
+
+    // Requests held while every config of a service was down; bodies only on the single-item view
+    if (path === '/api/queue' && req.method === 'GET') {
+      const service = url.searchParams.get('service') || undefined;
+      const status = url.searchParams.get('status') || undefined;
+      if (status && !['pending', 'delivered', 'failed', 'cancelled'].includes(status)) {
+        return Response.json(
+          { error: 'status must be pending, delivered, failed or cancelled' },
+          { status: 400, headers: corsHeaders }
+        );
+      }
+
+      const items = tenant.outageQueue
+        .list(service, status as QueuedRequestStatus | undefined)
+        .map(({ body, headers: _headers, responseBody: _responseBody, ...item }) => ({
+          ...item,
+          bodyBytes: body.length,
+        }));
+      return Response.json({ depth: tenant.outageQueue.depth(), items }, { headers: corsHeaders });
+    }
+
+    const queueMatch = path.match(/^\/api\/queue\/([^/]+)$/);
+    if (queueMatch) {
+      const id = decodeURIComponent(queueMatch[1]);
+
+      if (req.method === 'GET') {
+        const item = tenant.outageQueue.get(id);
+        if (!item) {
+          return Response.json({ error: 'Queued request not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json(item, { headers: corsHeaders });
+      }
+
+      if (req.method === 'DELETE') {
+        const item = tenant.outageQueue.cancel(id);
+        if (!item) {
+          return Response.json({ error: 'Queued request not found' }, { status: 404, headers: corsHeaders });
+        }
+        if (item.status !== 'cancelled') {
+          return Response.json(
+            { error: `Queued request is already ${item.status}` },
+            { status: 409, headers: corsHeaders }
+          );
+        }
+        return Response.json(item, { headers: corsHeaders });
+      }
+    }
+
+    // Replay captured traffic (HAR or JSON lines) through one config; results are tagged replay=<job id>
+    if (path === '/api/replay/import' && req.method === 'POST') {
+      let text: string;
+      const contentType = req.headers.get('content-type') || '';
+      if (contentType.includes('multipart/form-data')) {
+        const file = (await req.formData()).get('file');
+        if (!file || typeof file === 'string') {
+          return Response.json({ error: 'Upload the capture as a "file" field' }, { status: 400, headers: corsHeaders });
+        }
+        text = await file.text();
+      } else {
+        text = await req.text();
+      }
+
+      const parsed = parseReplayFile(text);
+      if ('error' in parsed) {
+        return Response.json({ error: parsed.error }, { status: 400, headers: corsHeaders });
+      }
+
+      // Anthropic Messages traffic goes to claude, everything else to codex, unless named
+      const service =
+        url.searchParams.get('service') ||
+        (parsed.requests.every(request => request.path.includes('/v1/messages')) ? 'claude' : 'codex');
+      if (service !== 'claude' && service !== 'codex') {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const configName = url.searchParams.get('config');
+      if (!configName) {
+        return Response.json({ error: 'config is required' }, { status: 400, headers: corsHeaders });
+      }
+      const config = configManager.getServiceConfig(service)?.configs.find(c => c.name === configName);
+      if (!config) {
+        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      }
+
+      const rate = Number(url.searchParams.get('rate') ?? 1);
+      if (!Number.isFinite(rate) || rate <= 0 || rate > 50) {
+        return Response.json(
+          { error: 'rate must be between 0 and 50 requests per second' },
+          { status: 400, headers: corsHeaders }
+        );
+      }
 
-      serviceConfig.configs[index] = { ...serviceConfig.configs[index], ...updates };
-      await configManager.saveServiceConfig(serviceName, serviceConfig);
+      const job = tenant.replays.start(service, config, parsed.requests, parsed.skipped, rate, (request, servers) =>
+        tenant.proxies[service].handleRequest(request, servers)
+      );
+      console.log(
+        `[replay] ${job.id}: ${job.total} request(s) through ${service}/${config.name} at ${rate}/s (${job.skipped} skipped)`
+      );
+      return Response.json(job, { status: 202, headers: corsHeaders });
+    }
 
-      return Response.json({ success: true }, { headers: corsHeaders });
+    if (path === '/api/replay' && req.method === 'GET') {
+      return Response.json({ jobs: tenant.replays.list() }, { headers: corsHeaders });
     }
 
-    // Delete config
-    if (path.match(/^\/api\/configs\/[^/]+$/) && req.method === 'DELETE') {
-      const configName = path.split('/').pop()!;
-      const serviceName = url.searchParams.get('service') || 'claude';
-      const serviceConfig = configManager.getServiceConfig(serviceName);
+    const replayMatch = path.match(/^\/api\/replay\/([^/]+)$/);
+    if (replayMatch && (req.method === 'GET' || req.method === 'DELETE')) {
+      const id = decodeURIComponent(replayMatch[1]);
+      const job = req.method === 'DELETE' ? tenant.replays.cancel(id) : tenant.replays.get(id);
+      if (!job) {
+        return Response.json({ error: 'Replay job not found' }, { status: 404, headers: corsHeaders });
+      }
+      return Response.json(job, { headers: corsHeaders });
+    }
 
-      if (!serviceConfig) {
+    // The dashboard's compare view: /paf/fanout without the caller's own headers, so no dashboard
+    // cookies reach an upstream; only x-paf-config-token is passed on for [config_override]
+    const fanoutMatch = path.match(/^\/api\/fanout\/([^/]+)$/);
+    if (fanoutMatch && req.method === 'POST') {
+      const serviceName = decodeURIComponent(fanoutMatch[1]);
+      if (serviceName !== 'claude' && serviceName !== 'codex') {
         return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
       }
+      const fanout = await readFanoutRequest(
+        req,
+        serviceName,
+        tenant.configManager.getServiceConfig(serviceName)?.maxRequestBodyBytes
+      );
+      if ('error' in fanout) {
+        return Response.json(
+          { error: fanout.error },
+          { status: fanout.kind === 'too_large' ? 413 : 400, headers: corsHeaders }
+        );
+      }
+      const headers = new Headers();
+      const token = req.headers.get(CONFIG_OVERRIDE_TOKEN_HEADER);
+      if (token) {
+        headers.set(CONFIG_OVERRIDE_TOKEN_HEADER, token);
+      }
+      const results = await fanOut(
+        tenant.proxies[serviceName],
+        new Request(new URL(FANOUT_PATH, req.url), { method: 'POST', headers, signal: req.signal }),
+        fanout,
+        tenant.configManager.getAllConfigs(serviceName),
+        tenant.logger,
+        memoryBudget
+      );
+      return Response.json({ service: serviceName, path: fanout.path, results }, { headers: corsHeaders });
+    }
 
-      // Remove config
-      serviceConfig.configs = serviceConfig.configs.filter(c => c.name !== configName);
-      await configManager.saveServiceConfig(serviceName, serviceConfig);
-      logger.clearLastResult(serviceName, configName);
+    // Request features configs were found to reject, see [shape_cache] in system.toml
+    if (path === '/api/shape-cache' && (req.method === 'GET' || req.method === 'DELETE')) {
+      const service = url.searchParams.get('service') || undefined;
+      if (service && service !== 'claude' && service !== 'codex') {
+        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      }
+      const services: ReadonlyArray<ServiceName> = service ? [service] : SERVICE_NAMES;
 
-      return Response.json({ success: true }, { headers: corsHeaders });
+      if (req.method === 'DELETE') {
+        const config = url.searchParams.get('config') || undefined;
+        const removed = services.reduce((sum, name) => sum + tenant.shapeCaches[name].clear(config), 0);
+        return Response.json({ removed }, { headers: corsHeaders });
+      }
+      return Response.json({
+        entries: services.flatMap(name =>
+          tenant.shapeCaches[name].list().map(entry => ({
+            service: name,
+            config: entry.config,
+            model: entry.model,
+            feature: entry.feature,
+            error: entry.error,
+            learned_at: entry.learnedAt,
+            expires_at: entry.expiresAt,
+          }))
+        ),
+      }, { headers: corsHeaders });
     }
 
-    // Freeze/unfreeze config
-    if (path.match(/^\/api\/configs\/[^/]+\/freeze$/) && req.method === 'PUT') {
-      const configName = path.split('/')[3];
-      const serviceName = url.searchParams.get('service') || 'claude';
-      const serviceConfig = configManager.getServiceConfig(serviceName);
-
-      if (!serviceConfig) {
+    // Stored responses of [response_cache] in system.toml; bodies are not listed
+    if (path === '/api/cache' && (req.method === 'GET' || req.method === 'DELETE')) {
+      const service = url.searchParams.get('service') || undefined;
+      if (service && service !== 'claude' && service !== 'codex') {
         return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
       }
 
-      const body = await req.json();
-      const freezeUntil = body.freezeUntil || null;
-
-      // Update config
-      const index = serviceConfig.configs.findIndex(c => c.name === configName);
-      if (index === -1) {
-        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      if (req.method === 'DELETE') {
+        return Response.json({ removed: tenant.responseCache.clear(service) }, { headers: corsHeaders });
       }
+      const stats = tenant.responseCache.stats();
+      return Response.json({
+        enabled: stats.enabled,
+        ttl_seconds: stats.ttlSeconds,
+        max_bytes: stats.maxBytes,
+        bytes: stats.bytes,
+        hits: stats.hits,
+        misses: stats.misses,
+        entries: tenant.responseCache
+          .list()
+          .filter(entry => !service || entry.service === service)
+          .map(entry => ({
+            key: entry.key,
+            service: entry.service,
+            path: entry.path,
+            model: entry.model,
+            config: entry.configName,
+            status: entry.status,
+            bytes: entry.bytes,
+            hits: entry.hits,
+            created_at: entry.createdAt,
+            expires_at: entry.expiresAt,
+          })),
+      }, { headers: corsHeaders });
+    }
 
-      serviceConfig.configs[index] = {
-        ...serviceConfig.configs[index],
-        freezeUntil,
-      };
-      await configManager.saveServiceConfig(serviceName, serviceConfig);
+    // Request heatmap: counts by day of week (0 = Sunday) x hour, per service
+    if (path === '/api/stats/heatmap' && req.method === 'GET') {
+      const days = parseInt(url.searchParams.get('days') || '30');
+      if (!Number.isFinite(days) || days <= 0 || days > 365) {
+        return Response.json({ error: 'days must be between 1 and 365' }, { status: 400, headers: corsHeaders });
+      }
 
-      return Response.json({ success: true }, { headers: corsHeaders });
+      return Response.json({
+        days,
+        heatmap: logger.getRequestHeatmap(days),
+      }, { headers: corsHeaders });
     }
 
-    // Activate config
-    if (path.match(/^\/api\/configs\/[^/]+\/activate$/) && req.method === 'POST') {
-      const configName = path.split('/')[3];
-      const serviceName = url.searchParams.get('service') || 'claude';
-      const serviceConfig = configManager.getServiceConfig(serviceName);
+    // A/B experiments between two configs of a service
+    if (path === '/api/experiments' && req.method === 'GET') {
+      return Response.json({ experiments: tenant.experiments.list() }, { headers: corsHeaders });
+    }
 
-      if (!serviceConfig) {
-        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+    if (path === '/api/experiments' && req.method === 'POST') {
+      const body = await req.json().catch(() => null);
+      const service = body?.service;
+      const arms = body?.configs;
+      if (service !== 'claude' && service !== 'codex') {
+        return Response.json({ error: 'service must be claude or codex' }, { status: 400, headers: corsHeaders });
+      }
+      if (!Array.isArray(arms) || arms.length !== 2 || arms.some((name: unknown) => typeof name !== 'string')) {
+        return Response.json({ error: 'configs must list exactly two config names' }, { status: 400, headers: corsHeaders });
       }
 
-      const exists = serviceConfig.configs.some(c => c.name === configName);
-      if (!exists) {
-        return Response.json({ error: 'Config not found' }, { status: 404, headers: corsHeaders });
+      const serviceConfig = configManager.getServiceConfig(service);
+      const missing = arms.filter((name: string) => !serviceConfig?.configs.some(c => c.name === name));
+      if (missing.length > 0) {
+        return Response.json({ error: `Unknown config(s): ${missing.join(', ')}` }, { status: 404, headers: corsHeaders });
       }
 
-      // Set active config
-      serviceConfig.active = configName;
-      await configManager.saveServiceConfig(serviceName, serviceConfig);
+      const startsAt = typeof body.starts_at === 'number' ? body.starts_at : Date.now();
+      const durationHours = typeof body.duration_hours === 'number' ? body.duration_hours : 24;
+      const created = tenant.experiments.create({
+        name: typeof body.name === 'string' ? body.name : undefined,
+        service,
+        arms: [arms[0], arms[1]],
+        split: typeof body.split === 'number' ? body.split : undefined,
+        startsAt,
+        endsAt: typeof body.ends_at === 'number' ? body.ends_at : startsAt + durationHours * 60 * 60 * 1000,
+      });
 
-      return Response.json({ success: true }, { headers: corsHeaders });
+      if ('error' in created) {
+        return Response.json(created, { status: 400, headers: corsHeaders });
+      }
+      return Response.json(created, { status: 201, headers: corsHeaders });
     }
 
-    // Get load balancer config
-    if (path === '/api/loadbalancer' && req.method === 'GET') {
-      const serviceName = url.searchParams.get('service') || 'claude';
-      const serviceConfig = configManager.getServiceConfig(serviceName);
+    const experimentMatch = path.match(/^\/api\/experiments\/([^/]+)(\/results|\/stop)?$/);
+    if (experimentMatch) {
+      const experimentId = decodeURIComponent(experimentMatch[1]);
+      const action = experimentMatch[2];
 
-      return Response.json({
-        loadBalancer: serviceConfig?.loadBalancer || null,
-      }, { headers: corsHeaders });
-    }
+      if (action === '/results' && req.method === 'GET') {
+        const results = tenant.experiments.results(experimentId);
+        if (!results) {
+          return Response.json({ error: 'Experiment not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json(results, { headers: corsHeaders });
+      }
 
-    // Update load balancer config
-    if (path === '/api/loadbalancer' && req.method === 'PUT') {
-      const body = await req.json();
-      const serviceName = url.searchParams.get('service') || 'claude';
-      const serviceConfig = configManager.getServiceConfig(serviceName);
+      if (action === '/stop' && req.method === 'POST') {
+        const stopped = tenant.experiments.stop(experimentId);
+        if (!stopped) {
+          return Response.json({ error: 'Experiment not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json(stopped, { headers: corsHeaders });
+      }
 
-      if (!serviceConfig) {
-        return Response.json({ error: 'Service not found' }, { status: 404, headers: corsHeaders });
+      if (!action && req.method === 'GET') {
+        const experiment = tenant.experiments.get(experimentId);
+        if (!experiment) {
+          return Response.json({ error: 'Experiment not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json(experiment, { headers: corsHeaders });
       }
 
-      serviceConfig.loadBalancer = body;
-      await configManager.saveServiceConfig(serviceName, serviceConfig);
+      if (!action && req.method === 'DELETE') {
+        if (!tenant.experiments.delete(experimentId)) {
+          return Response.json({ error: 'Experiment not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json({ success: true }, { headers: corsHeaders });
+      }
+    }
 
-      // Update load balancer based on service
-      if (serviceName === 'claude') {
-        claudeLoadBalancer.updateConfig(body);
-      } else if (serviceName === 'codex') {
-        codexLoadBalancer.updateConfig(body);
+    // Saved log views ("codex 5xx last hour") and alert rules evaluated against them
+    if (path === '/api/log-views' && req.method === 'GET') {
+      return Response.json({ views: tenant.monitor.listViews() }, { headers: corsHeaders });
+    }
+
+    if (path === '/api/log-views' && req.method === 'POST') {
+      const body = await req.json().catch(() => null);
+      if (typeof body?.name !== 'string' || !body.name) {
+        return Response.json({ error: 'name is required' }, { status: 400, headers: corsHeaders });
       }
+      const view = tenant.monitor.createView(body.name, parseLogQuery(body));
+      return Response.json(view, { status: 201, headers: corsHeaders });
+    }
 
-      return Response.json({ success: true }, { headers: corsHeaders });
+    const logViewMatch = path.match(/^\/api\/log-views\/([^/]+)$/);
+    if (logViewMatch) {
+      const viewId = decodeURIComponent(logViewMatch[1]);
+      if (req.method === 'GET') {
+        const view = tenant.monitor.getView(viewId);
+        if (!view) {
+          return Response.json({ error: 'Log view not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json(view, { headers: corsHeaders });
+      }
+      if (req.method === 'DELETE') {
+        if (!tenant.monitor.deleteView(viewId)) {
+          return Response.json({ error: 'Log view not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json({ success: true }, { headers: corsHeaders });
+      }
     }
 
-    // Get logs
-    if (path === '/api/logs' && req.method === 'GET') {
-      const limit = parseInt(url.searchParams.get('limit') || '100');
-      const offset = parseInt(url.searchParams.get('offset') || '0');
-      const logs = logger.getRecentLogs(limit, offset);
+    if (path === '/api/alerts' && req.method === 'GET') {
+      return Response.json({ alerts: tenant.monitor.listRules() }, { headers: corsHeaders });
+    }
 
-      // Convert logs to frontend format
-      const convertedLogs = logs.map(convertLogToFrontendFormat);
+    if (path === '/api/alerts' && req.method === 'POST') {
+      const body = await req.json().catch(() => null);
+      const created = tenant.monitor.createRule({
+        name: typeof body?.name === 'string' ? body.name : '',
+        viewId: typeof body?.view_id === 'string' ? body.view_id : '',
+        threshold: Number(body?.threshold),
+        windowMinutes: Number(body?.window_minutes),
+        cooldownMinutes: typeof body?.cooldown_minutes === 'number' ? body.cooldown_minutes : undefined,
+        notifyUrl: typeof body?.notify_url === 'string' && body.notify_url ? body.notify_url : undefined,
+        enabled: typeof body?.enabled === 'boolean' ? body.enabled : undefined,
+      });
+      if ('error' in created) {
+        return Response.json(created, { status: 400, headers: corsHeaders });
+      }
+      return Response.json(created, { status: 201, headers: corsHeaders });
+    }
+
+    const alertMatch = path.match(/^\/api\/alerts\/([^/]+)$/);
+    if (alertMatch) {
+      const alertId = decodeURIComponent(alertMatch[1]);
+      if (req.method === 'GET') {
+        const rule = tenant.monitor.getRule(alertId);
+        if (!rule) {
+          return Response.json({ error: 'Alert rule not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json(rule, { headers: corsHeaders });
+      }
+      if (req.method === 'PUT') {
+        const body = await req.json().catch(() => null);
+        const updated = tenant.monitor.updateRule(alertId, {
+          ...(typeof body?.name === 'string' ? { name: body.name } : {}),
+          ...(typeof body?.threshold === 'number' ? { threshold: body.threshold } : {}),
+          ...(typeof body?.window_minutes === 'number' ? { windowMinutes: body.window_minutes } : {}),
+          ...(typeof body?.cooldown_minutes === 'number' ? { cooldownMinutes: body.cooldown_minutes } : {}),
+          ...(typeof body?.notify_url === 'string' ? { notifyUrl: body.notify_url || undefined } : {}),
+          ...(typeof body?.enabled === 'boolean' ? { enabled: body.enabled } : {}),
+        });
+        if (!updated) {
+          return Response.json({ error: 'Alert rule not found' }, { status: 404, headers: corsHeaders });
+        }
+        if ('error' in updated) {
+          return Response.json(updated, { status: 400, headers: corsHeaders });
+        }
+        return Response.json(updated, { headers: corsHeaders });
+      }
+      if (req.method === 'DELETE') {
+        if (!tenant.monitor.deleteRule(alertId)) {
+          return Response.json({ error: 'Alert rule not found' }, { status: 404, headers: corsHeaders });
+        }
+        return Response.json({ success: true }, { headers: corsHeaders });
+      }
+    }
 
-      return Response.json({ logs: convertedLogs }, { headers: corsHeaders });
+    // Prompt library; requests reference templates with {"paf_template": "<name>", "vars": {...}}
+    if (path === '/api/prompts' && req.method === 'GET') {
+      return Response.json({ prompts: tenant.prompts.list() }, { headers: corsHeaders });
     }
 
-    // Clear all logs
-    if (path === '/api/logs' && req.method === 'DELETE') {
-      const deletedCount = logger.clearAllLogs();
-      return Response.json({ success: true, deletedCount }, { headers: corsHeaders });
+    if (path === '/api/prompts' && req.method === 'POST') {
+      const body = await req.json().catch(() => null);
+      if (typeof body?.name !== 'string' || typeof body?.content !== 'string') {
+        return Response.json({ error: 'name and content are required' }, { status: 400, headers: corsHeaders });
+      }
+      if (tenant.prompts.get(body.name)) {
+        return Response.json({ error: `Prompt template "${body.name}" already exists` }, { status: 409, headers: corsHeaders });
+      }
+
+      const saved = tenant.prompts.save({
+        name: body.name,
+        content: body.content,
+        description: typeof body.description === 'string' ? body.description : undefined,
+      });
+      if ('error' in saved) {
+        return Response.json(saved, { status: 400, headers: corsHeaders });
+      }
+      return Response.json(saved, { status: 201, headers: corsHeaders });
     }
 
-    // Get log by ID
-    if (path.match(/^\/api\/logs\/[^/]+$/) && req.method === 'GET') {
-      const logId = path.split('/').pop()!;
-      const log = logger.getLogById(logId);
+    const promptMatch = path.match(/^\/api\/prompts\/([^/]+)(\/render)?$/);
+    if (promptMatch) {
+      const promptName = decodeURIComponent(promptMatch[1]);
+      const existing = tenant.prompts.get(promptName);
+      if (!existing) {
+        return Response.json({ error: 'Prompt template not found' }, { status: 404, headers: corsHeaders });
+      }
 
-      if (!log) {
-        return Response.json({ error: 'Log not found' }, { status: 404, headers: corsHeaders });
+      // Preview the expansion a request would get
+      if (promptMatch[2] && req.method === 'POST') {
+        const body = await req.json().catch(() => null);
+        const rendered = tenant.prompts.render(promptName, body?.vars);
+        if ('error' in rendered) {
+          return Response.json(rendered, { status: 400, headers: corsHeaders });
+        }
+        return Response.json(rendered, { headers: corsHeaders });
       }
 
-      // Convert log to frontend format
-      const convertedLog = convertLogToFrontendFormat(log);
+      if (!promptMatch[2] && req.method === 'GET') {
+        return Response.json(existing, { headers: corsHeaders });
+      }
 
-      return Response.json({ log: convertedLog }, { headers: corsHeaders });
-    }
+      if (!promptMatch[2] && req.method === 'PUT') {
+        const body = await req.json().catch(() => null);
+        const saved = tenant.prompts.save({
+          name: promptName,
+          content: typeof body?.content === 'string' ? body.content : existing.content,
+          description: typeof body?.description === 'string' ? body.description : existing.description,
+        });
+        if ('error' in saved) {
+          return Response.json(saved, { status: 400, headers: corsHeaders });
+        }
+        return Response.json(saved, { headers: corsHeaders });
+      }
 
-    // Get usage stats
-    if (path === '/api/stats' && req.method === 'GET') {
-      const stats = logger.getUsageStats();
-      return Response.json({ stats }, { headers: corsHeaders });
+      if (!promptMatch[2] && req.method === 'DELETE') {
+        tenant.prompts.delete(promptName);
+        return Response.json({ success: true }, { headers: corsHeaders });
+      }
     }
 
     // Test API connection
@@ -730,43 +2545,20 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
         }, { status: 400, headers: corsHeaders });
       }
 
-      try {
-        if (serviceName === 'claude') {
-          const result = await runClaudeConfigTest({
-            configName,
-            config,
-            serviceConfig,
-          });
-          return Response.json(result, { headers: corsHeaders });
-        }
-
-        const result = await runOpenAICompatTest({
-          serviceName: serviceName as 'claude' | 'codex',
-          configName,
-          config,
-          serviceConfig,
-        });
-
-        return Response.json(result, { headers: corsHeaders });
-      } catch (error) {
-        console.error(`[proxy:${serviceName}] Test execution failed:`, error);
-        return Response.json({
-          success: false,
-          status_code: 0,
-          duration_ms: 0,
-          message: error instanceof Error ? error.message : 'Test execution failed',
-          response_preview: '',
-          completed_at: Date.now(),
-          source: serviceName === 'claude' ? 'cli' : 'proxy',
-          method: serviceName === 'claude' ? 'CLI' : 'POST',
-          path: '/test',
-        }, { headers: corsHeaders });
-      }
+      const result = await runConfigTest(serviceName as 'claude' | 'codex', config, serviceConfig, tenant);
+      return Response.json(result, { headers: corsHeaders });
     }
 
 
     return Response.json({ error: 'Not found' }, { status: 404, headers: corsHeaders });
   } catch (error) {
+    // The database stayed locked through busy_timeout and retries; the client can simply try again
+    if (isBusyError(error)) {
+      return Response.json(
+        { error: 'Database is busy, please retry' },
+        { status: 503, headers: { ...corsHeaders, 'Retry-After': '1' } }
+      );
+    }
     console.error('API error:', error);
     return Response.json(
       { error: error instanceof Error ? error.message : 'Internal server error' },
@@ -775,6 +2567,71 @@ async function handleApiRequest(req: Request, path: string): Promise<Response> {
   }
 }
 
+const PATCHABLE_CONFIG_FIELDS = new Set(['base_url', 'baseUrl', 'weight', 'enabled']);
+
+/**
+ * Validate a PATCH body; credentials are rejected so they never need to round-trip through the UI
+ */
+function parseConfigPatch(body: any): { updates: Partial<ProxyConfig> } | { error: string } {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    return { error: 'Request body must be a JSON object' };
+  }
+
+  const unsupported = Object.keys(body).filter(key => !PATCHABLE_CONFIG_FIELDS.has(key));
+  if (unsupported.length > 0) {
+    return { error: `Unsupported fields for PATCH: ${unsupported.join(', ')}` };
+  }
+
+  const updates: Partial<ProxyConfig> = {};
+
+  const baseUrl = body.base_url ?? body.baseUrl;
+  if (baseUrl !== undefined) {
+    if (typeof baseUrl !== 'string' || !isValidUrl(baseUrl)) {
+      return { error: 'base_url must be a valid URL' };
+    }
+    updates.baseUrl = baseUrl;
+  }
+
+  if (body.weight !== undefined) {
+    if (typeof body.weight !== 'number' || !Number.isFinite(body.weight) || body.weight < 0) {
+      return { error: 'weight must be a non-negative number' };
+    }
+    updates.weight = body.weight;
+  }
+
+  if (body.enabled !== undefined) {
+    if (typeof body.enabled !== 'boolean') {
+      return { error: 'enabled must be a boolean' };
+    }
+    updates.enabled = body.enabled;
+  }
+
+  return { updates };
+}
+
+function serializeLogFilter(filter: LogFilter | null, configured: string) {
+  return {
+    filter: filter?.spec ?? configured,
+    default_level: filter?.defaultLevel,
+    targets: filter?.targets ?? [],
+    configured,
+    temporary: logFilterRevert !== null, // Returns to the configured filter when ttl_seconds runs out
+  };
+}
+
+function unknownProviderProfile(value: unknown): string {
+  return `Unknown provider_profile ${JSON.stringify(value)}; expected one of: ${Object.keys(PROVIDER_PROFILES).join(', ')}`;
+}
+
+function isValidUrl(value: string): boolean {
+  try {
+    new URL(value);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
 const CLAUDE_CLI_TIMEOUT_MS = 10000;
 
 interface ConfigTestExecutionResult {
@@ -789,10 +2646,43 @@ interface ConfigTestExecutionResult {
   path: string;
 }
 
+/**
+ * Run the connectivity test for `config`; it need not be saved yet, in which case a failure
+ * only shows up in the logs (there is nothing to freeze)
+ */
+async function runConfigTest(
+  serviceName: 'claude' | 'codex',
+  config: ProxyConfig,
+  serviceConfig: ServiceConfig,
+  tenant: TenantRuntime = defaultTenant
+): Promise<ConfigTestExecutionResult> {
+  try {
+    // A translated config only understands Chat Completions, which is what the OpenAI-compatible test sends
+    if (serviceName === 'claude' && !config.apiFormat) {
+      return await runClaudeConfigTest({ configName: config.name, config, serviceConfig, tenant });
+    }
+    return await runOpenAICompatTest({ serviceName, configName: config.name, config, serviceConfig, tenant });
+  } catch (error) {
+    console.error(`[proxy:${serviceName}] Test execution failed:`, error);
+    return {
+      success: false,
+      status_code: 0,
+      duration_ms: 0,
+      message: error instanceof Error ? error.message : 'Test execution failed',
+      response_preview: '',
+      completed_at: Date.now(),
+      source: serviceName === 'claude' ? 'cli' : 'proxy',
+      method: serviceName === 'claude' ? 'CLI' : 'POST',
+      path: '/test',
+    };
+  }
+}
+
 interface ClaudeConfigTestParams {
   configName: string;
   config: ProxyConfig;
   serviceConfig: ServiceConfig;
+  tenant?: TenantRuntime;
 }
 
 interface OpenAICompatTestParams {
@@ -800,12 +2690,14 @@ interface OpenAICompatTestParams {
   configName: string;
   config: ProxyConfig;
   serviceConfig: ServiceConfig;
+  tenant?: TenantRuntime;
 }
 
 async function runClaudeConfigTest({
   configName,
   config,
   serviceConfig,
+  tenant = defaultTenant,
 }: ClaudeConfigTestParams): Promise<ConfigTestExecutionResult> {
   const testStartTime = Date.now();
   const logId = `test-${testStartTime}-${Math.random().toString(36).substring(7)}`;
@@ -836,7 +2728,7 @@ async function runClaudeConfigTest({
       responsePreview = trimPreview(message);
     }
 
-    await logger.logRequest({
+    await tenant.logger.logRequest({
       id: logId,
       timestamp: testStartTime,
       service: 'claude',
@@ -859,12 +2751,18 @@ async function runClaudeConfigTest({
 
     if (shouldFreeze && !success) {
       const freezeDuration = serviceConfig.loadBalancer.freezeDuration || 5 * 60 * 1000;
-      const updatedConfig = await applyConfigFreeze('claude', serviceConfig, configName, Date.now() + freezeDuration);
+      const updatedConfig = await applyConfigFreeze(
+        'claude',
+        serviceConfig,
+        configName,
+        Date.now() + freezeDuration,
+        tenant.configManager
+      );
       if (updatedConfig) {
         Object.assign(config, updatedConfig);
       }
     } else if (success && config.freezeUntil !== undefined) {
-      const updatedConfig = await applyConfigFreeze('claude', serviceConfig, configName, undefined);
+      const updatedConfig = await applyConfigFreeze('claude', serviceConfig, configName, undefined, tenant.configManager);
       if (updatedConfig) {
         delete config.freezeUntil;
         Object.assign(config, updatedConfig);
@@ -928,6 +2826,12 @@ async function runClaudeConfigTest({
     env.ANTHROPIC_BASE_URL = baseUrl;
     env.ANTHROPIC_AUTH_TOKEN = token;
     env.ANTHROPIC_API_KEY = token;
+    const proxyUrl = await fetchProxy(config, serviceConfig);
+    if (proxyUrl) {
+      // The Claude CLI reads the standard proxy variables
+      env.HTTPS_PROXY = proxyUrl;
+      env.HTTP_PROXY = proxyUrl;
+    }
     env.NO_COLOR = '1';
     env.HOME = sandboxHome;
     env.XDG_CONFIG_HOME = sandboxHome;
@@ -1037,13 +2941,14 @@ async function runOpenAICompatTest({
   configName,
   config,
   serviceConfig,
+  tenant = defaultTenant,
 }: OpenAICompatTestParams): Promise<ConfigTestExecutionResult> {
   const testStartTime = Date.now();
   const logId = `test-${testStartTime}-${Math.random().toString(36).substring(7)}`;
 
   if (!config.baseUrl) {
     const message = 'Configuration is missing a base URL';
-    await logger.logRequest({
+    await tenant.logger.logRequest({
       id: logId,
       timestamp: testStartTime,
       service: serviceName,
@@ -1071,7 +2976,9 @@ async function runOpenAICompatTest({
   const normalizedBase =
     config.baseUrl.endsWith('/') ? config.baseUrl : `${config.baseUrl}/`;
 
-  const testUrl = new URL('v1/chat/completions', normalizedBase).toString();
+  // A codex config translated to Anthropic is tested with the equivalent Messages call
+  const anthropic = config.apiFormat === 'anthropic';
+  const testUrl = new URL(anthropic ? 'v1/messages' : 'v1/chat/completions', normalizedBase).toString();
 
   const authHeaders: Record<string, string> = {
     'Accept-Encoding': 'identity',
@@ -1083,14 +2990,18 @@ async function runOpenAICompatTest({
   if (config.authToken) {
     authHeaders['Authorization'] = `Bearer ${config.authToken}`;
   }
+  if (anthropic) {
+    authHeaders['anthropic-version'] = '2023-06-01';
+  }
 
   const testHeaders: HeadersInit = {
     'Content-Type': 'application/json',
     ...authHeaders,
   };
 
+  const testModel = anthropic ? 'claude-3-5-haiku-latest' : 'gpt-3.5-turbo';
   const testBody = {
-    model: 'gpt-3.5-turbo',
+    model: config.modelMap?.[testModel] ?? testModel,
     max_tokens: 10,
     messages: [{ role: 'user', content: 'hi' }],
   };
@@ -1102,6 +3013,7 @@ async function runOpenAICompatTest({
       method: 'POST',
       headers: testHeaders,
       body: JSON.stringify(testBody),
+      proxy: await fetchProxy(config, serviceConfig),
     });
 
     const duration = Date.now() - testStartTime;
@@ -1125,7 +3037,7 @@ async function runOpenAICompatTest({
 
     responsePreview = trimPreview(responsePreview);
 
-    const usage = logger.parseUsage(responseJson);
+    const usage = tenant.logger.parseUsage(responseJson);
 
     const responseHeaders: Record<string, string> = {};
     response.headers.forEach((value, key) => {
@@ -1135,7 +3047,7 @@ async function runOpenAICompatTest({
     const testUrlObj = new URL(testUrl);
     const pathWithQuery = `${testUrlObj.pathname}${testUrlObj.search}`;
 
-    await logger.logRequest({
+    await tenant.logger.logRequest({
       id: logId,
       timestamp: testStartTime,
       service: serviceName,
@@ -1160,13 +3072,14 @@ async function runOpenAICompatTest({
         serviceName,
         serviceConfig,
         configName,
-        Date.now() + freezeDuration
+        Date.now() + freezeDuration,
+        tenant.configManager
       );
       if (updated) {
         Object.assign(config, updated);
       }
     } else if (config.freezeUntil !== undefined) {
-      const updated = await applyConfigFreeze(serviceName, serviceConfig, configName, undefined);
+      const updated = await applyConfigFreeze(serviceName, serviceConfig, configName, undefined, tenant.configManager);
       if (updated) {
         delete config.freezeUntil;
         Object.assign(config, updated);
@@ -1199,7 +3112,7 @@ async function runOpenAICompatTest({
       }
     })();
 
-    await logger.logRequest({
+    await tenant.logger.logRequest({
       id: logId,
       timestamp: testStartTime,
       service: serviceName,
@@ -1218,7 +3131,8 @@ async function runOpenAICompatTest({
       serviceName,
       serviceConfig,
       configName,
-      Date.now() + freezeDuration
+      Date.now() + freezeDuration,
+      tenant.configManager
     );
     if (updated) {
       Object.assign(config, updated);
@@ -1238,8 +3152,11 @@ async function runOpenAICompatTest({
   }
 }
 
-async function autoRetestFrozenConfigs(serviceName: 'claude' | 'codex'): Promise<void> {
-  const serviceConfig = configManager.getServiceConfig(serviceName);
+async function autoRetestFrozenConfigs(
+  serviceName: 'claude' | 'codex',
+  tenant: TenantRuntime = defaultTenant
+): Promise<void> {
+  const serviceConfig = tenant.configManager.getServiceConfig(serviceName);
   if (!serviceConfig) {
     return;
   }
@@ -1254,11 +3171,12 @@ async function autoRetestFrozenConfigs(serviceName: 'claude' | 'codex'): Promise
   const lock = autoRetestLocks[serviceName];
 
   for (const frozenConfig of pending) {
-    if (lock.has(frozenConfig.name)) {
+    const lockKey = `${tenant.name}:${frozenConfig.name}`;
+    if (lock.has(lockKey)) {
       continue;
     }
 
-    lock.add(frozenConfig.name);
+    lock.add(lockKey);
 
     (async () => {
       try {
@@ -1267,6 +3185,7 @@ async function autoRetestFrozenConfigs(serviceName: 'claude' | 'codex'): Promise
             configName: frozenConfig.name,
             config: frozenConfig,
             serviceConfig,
+            tenant,
           });
         } else {
           await runOpenAICompatTest({
@@ -1274,12 +3193,13 @@ async function autoRetestFrozenConfigs(serviceName: 'claude' | 'codex'): Promise
             configName: frozenConfig.name,
             config: frozenConfig,
             serviceConfig,
+            tenant,
           });
         }
       } catch (error) {
         console.error(`[proxy:${serviceName}] Auto retest failed for ${frozenConfig.name}:`, error);
       } finally {
-        lock.delete(frozenConfig.name);
+        lock.delete(lockKey);
       }
     })();
   }
@@ -1324,12 +3244,50 @@ function cleanupClaudeSandbox(dir: string | null): void {
 }
 
 /**
- * Handle direct proxy traffic on dedicated service ports (e.g. 8801/8802)
+ * Where this service's traffic goes: the selected config with its model_map and balance, the configs
+ * load balancing can choose from, and the service-wide model_override. No credentials.
+ */
+function whoami(tenant: TenantRuntime, serviceName: 'claude' | 'codex') {
+  const serviceConfig = tenant.configManager.getServiceConfig(serviceName);
+  const selected = selectedConfig(tenant, serviceName);
+  const quota = selected ? tenant.quota.get(serviceName, selected.config.name) : undefined;
+  return {
+    service: serviceName,
+    tenant: tenant.name,
+    mode: serviceConfig?.mode ?? 'manual',
+    selected: selected && {
+      name: selected.config.name,
+      via: selected.via,
+      base_url: selected.config.baseUrl,
+      api_format: selected.config.apiFormat,
+      model_map: selected.config.modelMap ?? {},
+      budget: quota && {
+        remaining: quota.remaining,
+        total: quota.total,
+        unit: quota.unit,
+        low: quota.low,
+        checked_at: quota.checkedAt,
+        error: quota.error,
+      },
+    },
+    candidates: serviceHealth(tenant, serviceName).usable,
+    model_override: serviceConfig?.modelOverride && {
+      default: serviceConfig.modelOverride.defaultModel,
+      aliases: serviceConfig.modelOverride.aliases,
+    },
+  };
+}
+
+/**
+ * Handle direct proxy traffic on dedicated service ports (e.g. 8801/8802).
+ * Without a fixed tenant, a client certificate identity or client key listed by a tenant selects that tenant;
+ * everything else is the default tenant.
  */
 async function handleDirectProxyRequest(
   req: Request,
   serviceName: 'claude' | 'codex',
-  proxy: ProxyService
+  fixedTenant?: TenantRuntime,
+  client?: ClientCertificate
 ): Promise<Response> {
   if (req.method === 'OPTIONS') {
     return new Response(null, {
@@ -1341,14 +3299,69 @@ async function handleDirectProxyRequest(
     });
   }
 
-  const servers = configManager.getAllConfigs(serviceName);
+  let tenant = fixedTenant ?? defaultTenant;
+  const certTenant = client ? systemConfig.tenants.find(t => t.clientCerts.includes(client.identity)) : undefined;
+  if (!fixedTenant && certTenant) {
+    tenant = tenants.get(certTenant.name) ?? defaultTenant;
+  } else if (!fixedTenant) {
+    const keyTenant = findTenantByClientKey(req, systemConfig.tenants);
+    if (keyTenant) {
+      tenant = tenants.get(keyTenant.name) ?? defaultTenant;
+      // The tenant key only identifies the caller to paf; never forward it upstream
+      const headers = new Headers(req.headers);
+      headers.delete('x-api-key');
+      headers.delete('authorization');
+      req = new Request(req, { headers });
+    }
+  }
 
-  if (servers.length === 0) {
-    console.warn(`[proxy:${serviceName}] No configs available when handling ${req.method} ${req.url}`);
+  if (client) {
+    setClientIdentity(req, client.identity);
+  }
+
+  // 503 lets probes that only look at the status code see a service that cannot serve
+  if (req.method === 'GET' && new URL(req.url).pathname === SERVICE_HEALTH_PATH) {
+    const health = serviceHealth(tenant, serviceName);
     return Response.json(
-      { error: `No ${serviceName} configs available` },
-      { status: 503 }
+      { service: serviceName, tenant: tenant.name, ...health },
+      { status: health.state === 'ok' ? 200 : 503 }
+    );
+  }
+
+  if (req.method === 'GET' && new URL(req.url).pathname === SERVICE_WHOAMI_PATH) {
+    return Response.json(whoami(tenant, serviceName));
+  }
+
+  const exhausted = tenant.budget?.exhausted();
+  if (exhausted) {
+    return proxyErrorResponse(
+      serviceErrorDialect(serviceName),
+      'rate_limit',
+      `Tenant ${tenant.name} has used its token budget (${exhausted.limit}) for this period`,
+      {},
+      exhausted
+    );
+  }
+
+  const proxy: ProxyService = tenant.proxies[serviceName];
+  const servers = tenant.configManager.getAllConfigs(serviceName);
+
+  if (req.method === 'POST' && new URL(req.url).pathname === FANOUT_PATH) {
+    const fanout = await readFanoutRequest(
+      req,
+      serviceName,
+      tenant.configManager.getServiceConfig(serviceName)?.maxRequestBodyBytes
     );
+    if ('error' in fanout) {
+      return proxyErrorResponse(serviceErrorDialect(serviceName), fanout.kind, fanout.error);
+    }
+    const results = await fanOut(proxy, req, fanout, servers, tenant.logger, memoryBudget);
+    return Response.json({ service: serviceName, tenant: tenant.name, path: fanout.path, results });
+  }
+
+  if (servers.length === 0) {
+    console.warn(`[proxy:${serviceName}] No configs available for tenant ${tenant.name} when handling ${req.method} ${req.url}`);
+    return proxyErrorResponse(serviceErrorDialect(serviceName), 'no_upstream', `No ${serviceName} configs available`);
   }
 
   try {
@@ -1356,6 +3369,6 @@ async function handleDirectProxyRequest(
   } catch (error) {
     const msg = error instanceof Error ? error.message : 'Proxy error';
     console.error(`[proxy:${serviceName}] Request failed: ${msg}`);
-    return Response.json({ error: msg }, { status: 502 });
+    return proxyErrorResponse(serviceErrorDialect(serviceName), 'upstream', msg);
   }
 }
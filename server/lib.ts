@@ -0,0 +1,227 @@
+// Library entry point - embed the proxy core in another Bun service
+//
+//   const proxy = await new ProxyBuilder()
+//     .withConfigDir('/etc/my-service/paf')
+//     .withHooks({ selectConfigs: (req, service, configs) => configs.filter(c => c.name !== 'backup') })
+//     .build();
+//
+//   Bun.serve({ fetch: req => proxy.handle(req, 'claude') });
+
+import { ConfigManager } from './config/manager';
+import type { ProxyConfig } from './config/types';
+import { createProxyCore, ensureServiceConfigs, SERVICE_NAMES, type ProxyCore, type ServiceName } from './core';
+import type { RequestLog } from './logging/database';
+import { RequestLogger } from './logging/logger';
+import { ConnectionStats } from './proxy/connectionStats';
+import { RealtimeHub } from './realtime/hub';
+
+export { ConfigManager } from './config/manager';
+export { ConfigChangeFeed } from './config/changeFeed';
+export type { ConfigChange, ConfigChangeAction, ConfigChangesSince } from './config/changeFeed';
+export {
+  applySyncBundle,
+  decryptSyncBundle,
+  diffSyncBundle,
+  encryptSyncBundle,
+  exportSyncBundle,
+  type SyncBundle,
+  type SyncChange,
+} from './config/sync';
+export { LoadBalancer, LOAD_BALANCER_STRATEGIES, type ServerPin } from './routing/loadbalancer';
+export {
+  registerSelectionStrategy,
+  selectionStrategyNames,
+  RoundRobinStrategy,
+  WeightedStrategy,
+  type SelectionCandidate,
+  type SelectionInput,
+  type SelectionRequest,
+  type SelectionResult,
+  type SelectionStrategy,
+  type SelectionStrategyFactory,
+} from './routing/strategies';
+export { findConfigPool, parseConfigPools, poolServers } from './routing/pools';
+export { isWithinSchedule, parseAvailability } from './routing/schedule';
+export { RequestLogger } from './logging/logger';
+export { BaseProxyService } from './proxy/baseProxyService';
+export { ClaudeProxyService } from './proxy/claudeProxyService';
+export { CodexProxyService } from './proxy/codexProxyService';
+export {
+  RealtimeHub,
+  RealtimeClientRegistry,
+  attachRealtimeClient,
+  createRealtimeClientData,
+  detachRealtimeClient,
+} from './realtime/hub';
+export type { RealtimeClientData, RealtimeClientInfo } from './realtime/hub';
+export { ExperimentRegistry } from './experiments/registry';
+export { DlpFilter } from './proxy/dlp';
+export { ModelListCache } from './proxy/modelListCache';
+export { BodyMemoryBudget } from './proxy/memoryBudget';
+export { OutageQueue } from './proxy/outageQueue';
+export { clientIdentityOf, setClientIdentity } from './proxy/clientIdentity';
+export { serveTlsProxy, type ClientCertificate } from './proxy/tlsServer';
+export { ReplayRunner, parseReplayFile } from './replay/replay';
+export type { ReplayJob, ReplayJobStatus, ReplayRequest } from './replay/replay';
+export { RequestShapeCache, requestShape } from './proxy/shapeCache';
+export { validateRequestBody, hasRequestSchema, type SchemaViolation } from './proxy/requestSchema';
+export { checkJsonResponse, checkSseResponse, type ProtocolIssueCode } from './proxy/responseCompliance';
+export {
+  upstreamTranslation,
+  messagesToChatCompletions,
+  chatCompletionToMessage,
+  createChatStreamTranslator,
+  translateChatResponse,
+  type UpstreamTranslation,
+} from './proxy/translation';
+export {
+  chatCompletionsToMessages,
+  responsesToMessages,
+  messageToChatCompletion,
+  messageToResponse,
+  translateMessagesResponse,
+} from './proxy/reverseTranslation';
+export { compressRequestBody } from './proxy/requestCompression';
+export type { KnownBadShape, RequestShape, ShapeCacheConfig } from './proxy/shapeCache';
+export { ResponseCache, responseCacheKey, RESPONSE_CACHE_HEADER } from './proxy/responseCache';
+export type { CachedResponse, ResponseCacheConfig, ResponseCacheStats } from './proxy/responseCache';
+export { ConcurrencyLimiter, releaseWhenDone } from './proxy/concurrency';
+export type { ConcurrencySnapshot } from './proxy/concurrency';
+export { CONFIG_OVERRIDE_HEADER, CONFIG_OVERRIDE_TOKEN_HEADER, resolveConfigOverride } from './proxy/configOverride';
+export { UpstreamDeadline, upstreamTimeouts } from './proxy/timeouts';
+export { isNoLogRequest, NO_LOG_HEADER } from './proxy/noLog';
+export { FANOUT_PATH, fanOut, MAX_FANOUT_CONFIGS, parseFanoutRequest, readFanoutRequest } from './proxy/fanout';
+export type { UpstreamTimeouts } from './proxy/timeouts';
+export type { FanoutRequest, FanoutResult } from './proxy/fanout';
+export { HookEventFeed, HookSessions, claudeCodeHookOutput } from './hooks/claudeCode';
+export type { ClaudeCodeHookInput, HookEvent, HookEventType } from './hooks/claudeCode';
+export { REQUEST_ID_HEADER, type RequestContext } from './proxy/requestContext';
+export { CertificateMonitor } from './monitoring/certificates';
+export type { CrashRecord, CrashKind } from './monitoring/crashes';
+export { QuotaMonitor } from './monitoring/quota';
+export { proxyErrorResponse, proxyErrorBody, rateLimitHeaders, serviceErrorDialect } from './proxy/errors';
+export type { ProxyErrorKind, ErrorDialect, RateLimitState } from './proxy/errors';
+export { PromptLibrary } from './prompts/library';
+export { LogMonitor } from './monitoring/alerts';
+export type { PromptTemplate } from './prompts/library';
+export type { AlertRule, LogView } from './monitoring/alerts';
+export type { LogQuery } from './logging/database';
+export {
+  createProxyCore,
+  ensureServiceConfigs,
+  selectedConfig,
+  serviceHealth,
+  SERVICE_HEALTH_PATH,
+  SERVICE_NAMES,
+  SERVICE_WHOAMI_PATH,
+} from './core';
+export type { ProxyCore, ServiceHealth, ServiceHealthState, ServiceName } from './core';
+export type { ProxyConfig, ServiceConfig, SystemConfig, LoadBalancerConfig, ConfigPool, AvailabilitySchedule, ProxyTlsConfig, ApiFormat, RequestCompressionConfig } from './config/types';
+export type { RequestLog } from './logging/database';
+export { WIRE_VERSION, toWireRequestLog } from './protocol';
+export type { RealtimeEvent, RealtimeEventType, RequestOutcome, WireRequestLog, WireUsage, WireTimings, OpenUnion } from './protocol';
+
+export interface ProxyHooks {
+  /**
+   * Narrow or reorder the configs eligible for this request, or answer it directly with a Response
+   */
+  selectConfigs?: (
+    request: Request,
+    service: ServiceName,
+    configs: ProxyConfig[]
+  ) => ProxyConfig[] | Response | Promise<ProxyConfig[] | Response>;
+  /** Called after each request log is persisted */
+  onRequestLogged?: (log: RequestLog) => void;
+}
+
+export interface EmbeddedProxy extends ProxyCore {
+  realtimeHubs: Record<ServiceName, RealtimeHub>;
+  connectionStats: ConnectionStats;
+  /** Forward a client request through the given service's proxy */
+  handle(request: Request, service: ServiceName): Promise<Response>;
+  close(): void;
+}
+
+export class ProxyBuilder {
+  private configDir?: string;
+  private dataDir?: string;
+  private applyEnv = true;
+  private hooks: ProxyHooks = {};
+
+  /** Directory holding system.toml and the service TOML files (default ~/.paf) */
+  withConfigDir(configDir: string): this {
+    this.configDir = configDir;
+    return this;
+  }
+
+  /** Directory for requests.db (default: data_dir from system.toml) */
+  withDataDir(dataDir: string): this {
+    this.dataDir = dataDir;
+    return this;
+  }
+
+  /** Whether PAF_* environment variables override file values (default true) */
+  withEnvOverrides(enabled: boolean): this {
+    this.applyEnv = enabled;
+    return this;
+  }
+
+  withHooks(hooks: ProxyHooks): this {
+    this.hooks = { ...this.hooks, ...hooks };
+    return this;
+  }
+
+  async build(): Promise<EmbeddedProxy> {
+    const configManager = new ConfigManager(this.configDir, this.applyEnv);
+    await configManager.initialize();
+    await ensureServiceConfigs(configManager);
+
+    const systemConfig = configManager.getSystemConfig();
+    const logger = new RequestLogger(this.dataDir ?? systemConfig.dataDir, { scrubbing: systemConfig.logScrubbing });
+    const connectionStats = new ConnectionStats();
+    const core = createProxyCore(configManager, logger, connectionStats);
+    const hooks = this.hooks;
+
+    const realtimeHubs: Record<ServiceName, RealtimeHub> = {
+      claude: new RealtimeHub({ service: 'claude', store: logger, replayMinutes: systemConfig.realtime.replayMinutes }),
+      codex: new RealtimeHub({ service: 'codex', store: logger, replayMinutes: systemConfig.realtime.replayMinutes }),
+    };
+
+    const unsubscribe = logger.onRequestLogged(log => {
+      if (log.service === 'claude' || log.service === 'codex') {
+        realtimeHubs[log.service].publishRequestLog(log);
+      }
+      hooks.onRequestLogged?.(log);
+    });
+
+    return {
+      ...core,
+      realtimeHubs,
+      connectionStats,
+      async handle(request: Request, service: ServiceName): Promise<Response> {
+        if (!SERVICE_NAMES.includes(service)) {
+          return Response.json({ error: `Unknown service: ${service}` }, { status: 404 });
+        }
+
+        let servers = configManager.getAllConfigs(service);
+        if (hooks.selectConfigs) {
+          const selected = await hooks.selectConfigs(request, service, servers);
+          if (selected instanceof Response) {
+            return selected;
+          }
+          servers = selected;
+        }
+
+        if (servers.length === 0) {
+          return Response.json({ error: `No ${service} configs available` }, { status: 503 });
+        }
+
+        return core.proxies[service].handleRequest(request, servers);
+      },
+      close() {
+        unsubscribe();
+        logger.close();
+      },
+    };
+  }
+}
@@ -0,0 +1,23 @@
+// Conversation ids - group the requests of one agent session so they can be viewed and exported together
+
+// Lets any client name its conversation explicitly
+export const CONVERSATION_HEADER = 'x-paf-conversation';
+
+const MAX_CONVERSATION_ID_LENGTH = 128;
+
+/**
+ * The client's conversation id, from (in order) the x-paf-conversation header, the Codex CLI's
+ * conversation_id / session_id headers, the session in Claude Code's metadata.user_id
+ * (`user_<hash>_account_<uuid>_session_<uuid>`) or a Responses API prompt_cache_key
+ */
+export function conversationIdOf(headers: Headers, body: any): string | undefined {
+  const candidates = [
+    headers.get(CONVERSATION_HEADER),
+    headers.get('conversation_id'),
+    headers.get('session_id'),
+    typeof body?.metadata?.user_id === 'string' ? body.metadata.user_id.match(/_session_([0-9a-f-]{8,})$/i)?.[1] : null,
+    typeof body?.prompt_cache_key === 'string' ? body.prompt_cache_key : null,
+  ];
+  const id = candidates.find((candidate): candidate is string => typeof candidate === 'string' && candidate.trim() !== '');
+  return id?.trim().slice(0, MAX_CONVERSATION_ID_LENGTH);
+}
@@ -1,7 +1,20 @@
 // Database manager using Bun's built-in SQLite
 
 import { Database } from 'bun:sqlite';
+import { existsSync, renameSync } from 'fs';
 import { join } from 'path';
+import type { Experiment, ExperimentSample } from '../experiments/registry';
+import type { PromptTemplate } from '../prompts/library';
+import type { AlertRule, LogView } from '../monitoring/alerts';
+import type { QueuedRequest, QueuedRequestStatus } from '../proxy/outageQueue';
+import type { CachedResponse } from '../proxy/responseCache';
+import type { CertificateRecord } from '../monitoring/certificates';
+import type { CrashRecord } from '../monitoring/crashes';
+import type { RequestContext } from '../proxy/requestContext';
+import type { InternalLogLine } from './tracing';
+
+// Stored as PRAGMA user_version; bump when a change cannot be read by older releases
+export const SCHEMA_VERSION = 1;
 
 export interface RequestLog {
   id: string;
@@ -14,23 +27,250 @@ export interface RequestLog {
   statusCode?: number;
   duration?: number;
   inputTokens?: number;
-  outputTokens?: number;
+  outputTokens?: number;        // Includes thinking/reasoning tokens, as providers bill them
+  thinkingTokens?: number;      // Reasoning tokens when reported; estimated from visible thinking for Anthropic
+  cacheReadTokens?: number;     // Prompt tokens served from the provider's prompt cache
+  cacheWriteTokens?: number;    // Prompt tokens written to the cache (Anthropic only)
+  dlpMatches?: string[];        // DLP rules that matched the request body, as "<action>:<rule>"
+  scrubbedItems?: number;       // PII occurrences masked before the log was stored
+  promptTemplates?: string[];   // Prompt library templates expanded into the request body
+  tags?: Record<string, string>; // Client labels from the x-paf-tags header
+  // Phase timings; Bun's fetch does not split DNS, connect and TLS, so they are all inside headersMs
+  proxyMs?: number;             // Inside paf before the upstream call: body parsing, DLP, routing
+  headersMs?: number;           // Upstream call until response headers: connection setup plus queueing
+  firstByteMs?: number;         // Response headers until the first body chunk (time to first token)
+  bodyMs?: number;              // First body chunk until the body ended (generation for streams)
+  requestBytes?: number;        // Request body size as received
+  responseBytes?: number;       // Upstream response body size (the first upstream call only for resumed streams)
+  uncompressedRequestBytes?: number; // Body sent upstream before gzip; only set when request_compression applied
+  compressedRequestBytes?: number;   // Body sent upstream after gzip
+  modelOverride?: string;       // Service model override applied, as "<requested> -> <sent>"
+  internalLogs?: InternalLogLine[]; // Warnings and errors paf printed while handling the request
+  context?: RequestContext;     // Config selection, transforms and upstream attempts; served by /api/logs/:id/context
   model?: string;
   error?: string;
   requestModel?: string;       // Model requested in the API call
   requestBody?: string;         // Truncated request body (first 500 chars)
+  fullRequestBody?: string;     // Body as sent upstream; only with the service's capture_request_bodies
+  conversationId?: string;      // Client session the request belongs to (see logging/conversation.ts)
+  clientIdentity?: string;      // Caller identified by the proxy port, e.g. from a TLS client certificate
   responsePreview?: string;     // Truncated response preview (first 500 chars)
   requestHeaders?: Record<string, string>;   // Request headers
   responseHeaders?: Record<string, string>;  // Response headers
+  outcome?: string;             // Non-normal terminations: 'interrupted' | 'client_disconnected' | 'blocked' | 'invalid' | 'queued' | 'cached'
+  experimentId?: string;        // Set when the request was routed by an A/B experiment
+  experimentArm?: string;       // 'a' | 'b'
+  upstreamId?: string;          // Provider object id from the response (msg_..., resp_..., batch_...)
+  protocolIssues?: string[];    // Response protocol violations as "<code>: <detail>"; [] when checked and clean
+  contentOmitted?: boolean;     // x-paf-no-log or the service's [no_log]: bodies, previews and headers were not stored
+}
+
+export interface TagFilter {
+  key: string;
+  value?: string; // Any value when omitted
+}
+
+// Tag keys are limited to [A-Za-z0-9_-], so quoting them is enough to form a JSON path
+function tagPath(key: string): string {
+  return `$."${key}"`;
+}
+
+export type StatusClass = 'success' | 'error' | '4xx' | '5xx';
+
+/**
+ * Filter shared by the logs API, usage stats and saved log views; all fields are ANDed
+ */
+export interface LogQuery {
+  service?: string;
+  configName?: string;
+  model?: string;         // Matches the requested or the reported model
+  status?: StatusClass;
+  tag?: TagFilter;
+  windowMinutes?: number; // Only logs from the last N minutes
+  since?: number;         // Only logs at or after this timestamp
+}
+
+const STATUS_CONDITIONS: Record<StatusClass, string> = {
+  success: 'status_code >= 200 AND status_code < 300 AND error IS NULL',
+  error: '(status_code >= 400 OR error IS NOT NULL)',
+  '4xx': 'status_code >= 400 AND status_code < 500',
+  '5xx': 'status_code >= 500',
+};
+
+function queryConditions(query: LogQuery = {}, now = Date.now()): { where: string; params: Array<string | number> } {
+  const clauses: string[] = [];
+  const params: Array<string | number> = [];
+
+  if (query.service) {
+    clauses.push('service = ?');
+    params.push(query.service);
+  }
+  if (query.configName) {
+    clauses.push('config_name = ?');
+    params.push(query.configName);
+  }
+  if (query.model) {
+    clauses.push('(request_model = ? OR model = ?)');
+    params.push(query.model, query.model);
+  }
+  if (query.status) {
+    clauses.push(STATUS_CONDITIONS[query.status]);
+  }
+  if (query.tag) {
+    if (query.tag.value === undefined) {
+      clauses.push('json_extract(tags, ?) IS NOT NULL');
+      params.push(tagPath(query.tag.key));
+    } else {
+      clauses.push('json_extract(tags, ?) = ?');
+      params.push(tagPath(query.tag.key), query.tag.value);
+    }
+  }
+  const since = Math.max(query.since ?? 0, query.windowMinutes ? now - query.windowMinutes * 60_000 : 0);
+  if (since > 0) {
+    clauses.push('timestamp >= ?');
+    params.push(since);
+  }
+
+  return { where: clauses.length > 0 ? `WHERE ${clauses.join(' AND ')}` : '', params };
+}
+
+export type PhaseTimingSample = Pick<
+  RequestLog,
+  'service' | 'configName' | 'proxyMs' | 'headersMs' | 'firstByteMs' | 'bodyMs'
+>;
+
+export type SizeSample = Pick<
+  RequestLog,
+  'service' | 'model' | 'inputTokens' | 'outputTokens' | 'requestBytes' | 'responseBytes'
+>;
+
+export interface SlaDay {
+  service: string;
+  configName: string;
+  day: string;          // UTC date, YYYY-MM-DD
+  totalMinutes: number; // Minutes with at least one request or config test
+  upMinutes: number;    // Of those, minutes where most attempts did not fail upstream
+}
+
+export interface WebhookEvent {
+  id: string;                   // Provider delivery id, used to drop redelivered callbacks
+  provider: string;
+  eventType?: string;
+  upstreamId?: string;
+  requestId?: string;           // Logged request the callback was correlated to
+  receivedAt: number;
+  payload: string;              // Truncated raw body
+}
+
+// How long a connection waits on another's lock before SQLite reports SQLITE_BUSY
+const BUSY_TIMEOUT_MS = 5000;
+const BUSY_RETRIES = 3;
+
+/**
+ * SQLITE_BUSY / SQLITE_LOCKED: another connection held the lock for longer than busy_timeout
+ */
+export function isBusyError(error: unknown): boolean {
+  const code = (error as { code?: unknown } | null)?.code;
+  return typeof code === 'string' && (code.startsWith('SQLITE_BUSY') || code.startsWith('SQLITE_LOCKED'));
+}
+
+/**
+ * Run a statement, retrying a few times with a short backoff when the lock outlasts busy_timeout
+ */
+function retryOnBusy<T>(operation: () => T): T {
+  for (let attempt = 1; ; attempt++) {
+    try {
+      return operation();
+    } catch (error) {
+      if (!isBusyError(error) || attempt >= BUSY_RETRIES) {
+        throw error;
+      }
+      Bun.sleepSync(50 * attempt);
+    }
+  }
+}
+
+/**
+ * A corrupted database file that was moved aside at startup
+ */
+export interface DatabaseRecovery {
+  reason: string;
+  movedTo: string;
+  at: number;
+}
+
+/**
+ * Open the database and run `PRAGMA integrity_check`; null when the file is healthy (or new)
+ */
+function checkIntegrity(dbPath: string): string | null {
+  if (!existsSync(dbPath)) {
+    return null;
+  }
+
+  let db: Database | undefined;
+  try {
+    db = new Database(dbPath);
+    const rows = db.prepare('PRAGMA integrity_check').all() as Array<{ integrity_check: string }>;
+    const problems = rows.map(row => row.integrity_check).filter(result => result !== 'ok');
+    return problems.length > 0 ? problems.slice(0, 5).join('; ') : null;
+  } catch (error) {
+    // SQLITE_NOTADB / SQLITE_CORRUPT surface here rather than as check results
+    return error instanceof Error ? error.message : String(error);
+  } finally {
+    db?.close();
+  }
+}
+
+/**
+ * Move a broken database (and its WAL/shared-memory files) aside so a fresh one can be created
+ */
+function quarantineDatabase(dbPath: string): string {
+  const movedTo = `${dbPath}.corrupt-${new Date().toISOString().replace(/[:.]/g, '-')}`;
+  renameSync(dbPath, movedTo);
+  for (const suffix of ['-wal', '-shm']) {
+    if (existsSync(dbPath + suffix)) {
+      renameSync(dbPath + suffix, movedTo + suffix);
+    }
+  }
+  return movedTo;
 }
 
 export class LogDatabase {
-  private db: Database;
+  private db: Database;     // Writer; also runs schema setup and maintenance
+  private reader: Database; // Read-only connection for queries, so UI reads never queue behind log writes
+  readonly recovery: DatabaseRecovery | null = null;
+
+  /**
+   * `inMemory` keeps everything in an in-memory database instead of requests.db (lite mode)
+   */
+  constructor(dataDir: string, inMemory = false) {
+    if (inMemory) {
+      this.db = new Database(':memory:');
+      this.initialize();
+      // A second connection to :memory: would open a separate, empty database
+      this.reader = this.db;
+      return;
+    }
 
-  constructor(dataDir: string) {
     const dbPath = join(dataDir, 'requests.db');
+
+    // A corrupt file would fail every later write, so start over with an empty one and keep the old file
+    const problem = checkIntegrity(dbPath);
+    if (problem) {
+      const movedTo = quarantineDatabase(dbPath);
+      this.recovery = { reason: problem, movedTo, at: Date.now() };
+      console.error(`[database] ${dbPath} failed integrity check (${problem}); moved to ${movedTo} and recreated`);
+    }
+
     this.db = new Database(dbPath);
+    // WAL lets the reader see committed rows while the writer holds its lock
+    this.db.run('PRAGMA journal_mode = WAL');
+    this.db.run(`PRAGMA busy_timeout = ${BUSY_TIMEOUT_MS}`);
     this.initialize();
+
+    // Opened after initialize() so the schema exists; bun:sqlite is synchronous, so one reader is enough
+    this.reader = new Database(dbPath, { readonly: true });
+    this.reader.run(`PRAGMA busy_timeout = ${BUSY_TIMEOUT_MS}`);
   }
 
   private initialize(): void {
@@ -73,11 +313,196 @@ export class LogDatabase {
     addColumnIfNotExists('request_headers', 'TEXT');
     addColumnIfNotExists('response_headers', 'TEXT');
     addColumnIfNotExists('target_url', 'TEXT');
+    addColumnIfNotExists('outcome', 'TEXT');
+    addColumnIfNotExists('experiment_id', 'TEXT');
+    addColumnIfNotExists('experiment_arm', 'TEXT');
+    addColumnIfNotExists('upstream_id', 'TEXT');
+    addColumnIfNotExists('thinking_tokens', 'INTEGER');
+    addColumnIfNotExists('dlp_matches', 'TEXT');
+    addColumnIfNotExists('scrubbed_items', 'INTEGER');
+    addColumnIfNotExists('prompt_templates', 'TEXT');
+    addColumnIfNotExists('tags', 'TEXT');
+    addColumnIfNotExists('proxy_ms', 'INTEGER');
+    addColumnIfNotExists('headers_ms', 'INTEGER');
+    addColumnIfNotExists('first_byte_ms', 'INTEGER');
+    addColumnIfNotExists('body_ms', 'INTEGER');
+    addColumnIfNotExists('request_bytes', 'INTEGER');
+    addColumnIfNotExists('response_bytes', 'INTEGER');
+    addColumnIfNotExists('context', 'TEXT');
+    addColumnIfNotExists('model_override', 'TEXT');
+    addColumnIfNotExists('internal_logs', 'TEXT');
+    addColumnIfNotExists('cache_read_tokens', 'INTEGER');
+    addColumnIfNotExists('cache_write_tokens', 'INTEGER');
+    addColumnIfNotExists('conversation_id', 'TEXT');
+    addColumnIfNotExists('request_body_full', 'TEXT');
+    addColumnIfNotExists('protocol_issues', 'TEXT');
+    addColumnIfNotExists('client_identity', 'TEXT');
+    addColumnIfNotExists('request_bytes_uncompressed', 'INTEGER');
+    addColumnIfNotExists('request_bytes_compressed', 'INTEGER');
+    addColumnIfNotExists('content_omitted', 'INTEGER');
 
     // Create indices for common queries
     this.db.run('CREATE INDEX IF NOT EXISTS idx_timestamp ON requests(timestamp DESC)');
     this.db.run('CREATE INDEX IF NOT EXISTS idx_config_name ON requests(config_name)');
     this.db.run('CREATE INDEX IF NOT EXISTS idx_status_code ON requests(status_code)');
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_experiment_id ON requests(experiment_id)');
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_upstream_id ON requests(upstream_id)');
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_conversation_id ON requests(conversation_id, timestamp)');
+
+    // Upstream A/B experiments
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS experiments (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        service TEXT NOT NULL,
+        arm_a TEXT NOT NULL,
+        arm_b TEXT NOT NULL,
+        split REAL NOT NULL,
+        starts_at INTEGER NOT NULL,
+        ends_at INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+      )
+    `);
+
+    // Recent realtime events, replayed to dashboard clients after a restart
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS realtime_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        service TEXT NOT NULL,
+        type TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        payload TEXT NOT NULL
+      )
+    `);
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_realtime_events_service_time ON realtime_events(service, timestamp)');
+
+    // Verified provider callbacks
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS webhook_events (
+        id TEXT PRIMARY KEY,
+        provider TEXT NOT NULL,
+        event_type TEXT,
+        upstream_id TEXT,
+        request_id TEXT,
+        received_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+      )
+    `);
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_webhook_events_request ON webhook_events(request_id)');
+
+    // Daily availability per config, kept after the underlying request logs are pruned
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS sla_daily (
+        service TEXT NOT NULL,
+        config_name TEXT NOT NULL,
+        day TEXT NOT NULL,
+        total_minutes INTEGER NOT NULL,
+        up_minutes INTEGER NOT NULL,
+        PRIMARY KEY (service, config_name, day)
+      )
+    `);
+
+    // Saved log views and the alert rules evaluated against them
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS log_views (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        query TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+      )
+    `);
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS alert_rules (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        view_id TEXT NOT NULL,
+        threshold INTEGER NOT NULL,
+        window_minutes REAL NOT NULL,
+        cooldown_minutes REAL NOT NULL,
+        notify_url TEXT,
+        enabled INTEGER NOT NULL,
+        last_fired_at INTEGER,
+        created_at INTEGER NOT NULL
+      )
+    `);
+
+    // Prompt library templates
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS prompt_templates (
+        name TEXT PRIMARY KEY,
+        description TEXT,
+        content TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+      )
+    `);
+
+    // Requests held while every upstream was down, replayed once one recovers
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS outage_queue (
+        id TEXT PRIMARY KEY,
+        service TEXT NOT NULL,
+        method TEXT NOT NULL,
+        path TEXT NOT NULL,
+        headers TEXT NOT NULL,
+        body TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        last_error TEXT,
+        response_status INTEGER,
+        response_body TEXT
+      )
+    `);
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_outage_queue_status ON outage_queue(service, status, created_at)');
+
+    // Responses kept by [response_cache], keyed on a hash of the normalized request
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS response_cache (
+        key TEXT PRIMARY KEY,
+        service TEXT NOT NULL,
+        path TEXT NOT NULL,
+        model TEXT,
+        config_name TEXT NOT NULL,
+        status INTEGER NOT NULL,
+        headers TEXT NOT NULL,
+        body TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+      )
+    `);
+
+    // Last TLS certificate seen per upstream host:port
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS tls_certificates (
+        host TEXT PRIMARY KEY,
+        fingerprint TEXT NOT NULL,
+        subject TEXT NOT NULL,
+        issuer TEXT NOT NULL,
+        valid_to INTEGER NOT NULL,
+        checked_at INTEGER NOT NULL,
+        previous_fingerprint TEXT,
+        changed_at INTEGER,
+        error TEXT
+      )
+    `);
+
+    // Uncaught exceptions and unhandled rejections, see monitoring/crashes.ts
+    this.db.run(`
+      CREATE TABLE IF NOT EXISTS crashes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        message TEXT NOT NULL,
+        stack TEXT,
+        context TEXT,
+        fatal INTEGER NOT NULL
+      )
+    `);
+    this.db.run('CREATE INDEX IF NOT EXISTS idx_crashes_timestamp ON crashes(timestamp DESC)');
+
+    this.db.run(`PRAGMA user_version = ${SCHEMA_VERSION}`);
   }
 
   /**
@@ -89,60 +514,114 @@ export class LogDatabase {
         id, timestamp, service, method, path, target_url, config_name,
         status_code, duration, input_tokens, output_tokens, model, error,
         request_model, request_body, response_preview,
-        request_headers, response_headers
-      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        request_headers, response_headers, outcome, experiment_id, experiment_arm, upstream_id,
+        thinking_tokens, dlp_matches, scrubbed_items, prompt_templates, tags,
+        proxy_ms, headers_ms, first_byte_ms, body_ms, request_bytes, response_bytes, context,
+        model_override, internal_logs, cache_read_tokens, cache_write_tokens, conversation_id, request_body_full,
+        protocol_issues, client_identity, request_bytes_uncompressed, request_bytes_compressed, content_omitted
+      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     `);
 
-    stmt.run(
-      log.id,
-      log.timestamp,
-      log.service ?? null,
-      log.method,
-      log.path,
-      log.targetUrl ?? null,
-      log.configName,
-      log.statusCode ?? null,
-      log.duration ?? null,
-      log.inputTokens ?? null,
-      log.outputTokens ?? null,
-      log.model ?? null,
-      log.error ?? null,
-      log.requestModel ?? null,
-      log.requestBody ?? null,
-      log.responsePreview ?? null,
-      log.requestHeaders ? JSON.stringify(log.requestHeaders) : null,
-      log.responseHeaders ? JSON.stringify(log.responseHeaders) : null
+    retryOnBusy(() =>
+      stmt.run(
+        log.id,
+        log.timestamp,
+        log.service ?? null,
+        log.method,
+        log.path,
+        log.targetUrl ?? null,
+        log.configName,
+        log.statusCode ?? null,
+        log.duration ?? null,
+        log.inputTokens ?? null,
+        log.outputTokens ?? null,
+        log.model ?? null,
+        log.error ?? null,
+        log.requestModel ?? null,
+        log.requestBody ?? null,
+        log.responsePreview ?? null,
+        log.requestHeaders ? JSON.stringify(log.requestHeaders) : null,
+        log.responseHeaders ? JSON.stringify(log.responseHeaders) : null,
+        log.outcome ?? null,
+        log.experimentId ?? null,
+        log.experimentArm ?? null,
+        log.upstreamId ?? null,
+        log.thinkingTokens ?? null,
+        log.dlpMatches?.length ? JSON.stringify(log.dlpMatches) : null,
+        log.scrubbedItems ?? null,
+        log.promptTemplates?.length ? JSON.stringify(log.promptTemplates) : null,
+        log.tags ? JSON.stringify(log.tags) : null,
+        log.proxyMs ?? null,
+        log.headersMs ?? null,
+        log.firstByteMs ?? null,
+        log.bodyMs ?? null,
+        log.requestBytes ?? null,
+        log.responseBytes ?? null,
+        log.context ? JSON.stringify(log.context) : null,
+        log.modelOverride ?? null,
+        log.internalLogs?.length ? JSON.stringify(log.internalLogs) : null,
+        log.cacheReadTokens ?? null,
+        log.cacheWriteTokens ?? null,
+        log.conversationId ?? null,
+        log.fullRequestBody ?? null,
+        log.protocolIssues ? JSON.stringify(log.protocolIssues) : null,
+        log.clientIdentity ?? null,
+        log.uncompressedRequestBytes ?? null,
+        log.compressedRequestBytes ?? null,
+        log.contentOmitted ? 1 : null
+      )
     );
   }
 
   /**
-   * Get recent logs with pagination
+   * Get recent logs with pagination, optionally filtered
    */
-  getRecentLogs(limit = 100, offset = 0): RequestLog[] {
-    const stmt = this.db.prepare(`
+  getRecentLogs(limit = 100, offset = 0, query?: LogQuery): RequestLog[] {
+    const { where, params } = queryConditions(query);
+    const stmt = this.reader.prepare(`
       SELECT * FROM requests
+      ${where}
       ORDER BY timestamp DESC
       LIMIT ? OFFSET ?
     `);
 
-    const rows = stmt.all(limit, offset) as any[];
+    const rows = stmt.all(...params, limit, offset) as any[];
     return rows.map(this.rowToLog);
   }
 
+  /**
+   * Count logs matching a query
+   */
+  countLogs(query: LogQuery): number {
+    const { where, params } = queryConditions(query);
+    const row = this.reader.prepare(`SELECT COUNT(*) as count FROM requests ${where}`).get(...params) as any;
+    return row?.count ?? 0;
+  }
+
   /**
    * Get log by ID
    */
   getLogById(id: string): RequestLog | null {
-    const stmt = this.db.prepare('SELECT * FROM requests WHERE id = ?');
+    const stmt = this.reader.prepare('SELECT * FROM requests WHERE id = ?');
     const row = stmt.get(id) as any;
     return row ? this.rowToLog(row) : null;
   }
 
+  /**
+   * Every request of a conversation, oldest first
+   */
+  getLogsByConversation(conversationId: string, limit = 1000): RequestLog[] {
+    const rows = this.reader
+      .prepare('SELECT * FROM requests WHERE conversation_id = ? ORDER BY timestamp ASC LIMIT ?')
+      .all(conversationId, limit) as any[];
+    return rows.map(row => this.rowToLog(row));
+  }
+
   /**
    * Get logs by config name
    */
   getLogsByConfig(configName: string, limit = 100): RequestLog[] {
-    const stmt = this.db.prepare(`
+    const stmt = this.reader.prepare(`
       SELECT * FROM requests
       WHERE config_name = ?
       ORDER BY timestamp DESC
@@ -154,16 +633,17 @@ export class LogDatabase {
   }
 
   /**
-   * Get usage statistics
+   * Get usage statistics, optionally filtered
    */
-  getUsageStats(): {
+  getUsageStats(query?: LogQuery): {
     totalRequests: number;
     successfulRequests: number;
     failedRequests: number;
     totalInputTokens: number;
     totalOutputTokens: number;
   } {
-    const stmt = this.db.prepare(`
+    const { where, params } = queryConditions(query);
+    const stmt = this.reader.prepare(`
       SELECT
         COUNT(*) as total_requests,
         SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END) as successful_requests,
@@ -171,9 +651,10 @@ export class LogDatabase {
         SUM(COALESCE(input_tokens, 0)) as total_input_tokens,
         SUM(COALESCE(output_tokens, 0)) as total_output_tokens
       FROM requests
+      ${where}
     `);
 
-    const row = stmt.get() as any;
+    const row = stmt.get(...params) as any;
 
     return {
       totalRequests: row.total_requests || 0,
@@ -184,6 +665,260 @@ export class LogDatabase {
     };
   }
 
+  /**
+   * Usage totals grouped by the values of one tag key, busiest first
+   */
+  getUsageByTag(key: string): Array<{
+    value: string;
+    totalRequests: number;
+    failedRequests: number;
+    totalInputTokens: number;
+    totalOutputTokens: number;
+  }> {
+    const path = tagPath(key);
+    const stmt = this.reader.prepare(`
+      SELECT
+        json_extract(tags, ?) as tag_value,
+        COUNT(*) as total_requests,
+        SUM(CASE WHEN status_code >= 400 OR error IS NOT NULL THEN 1 ELSE 0 END) as failed_requests,
+        SUM(COALESCE(input_tokens, 0)) as total_input_tokens,
+        SUM(COALESCE(output_tokens, 0)) as total_output_tokens
+      FROM requests
+      WHERE tags IS NOT NULL AND json_extract(tags, ?) IS NOT NULL
+      GROUP BY tag_value
+      ORDER BY total_requests DESC
+    `);
+
+    const rows = stmt.all(path, path) as any[];
+    return rows.map(row => ({
+      value: String(row.tag_value),
+      totalRequests: row.total_requests || 0,
+      failedRequests: row.failed_requests || 0,
+      totalInputTokens: row.total_input_tokens || 0,
+      totalOutputTokens: row.total_output_tokens || 0,
+    }));
+  }
+
+  /**
+   * Prompt token and cache totals per config since `since`, for successful requests that reported usage
+   */
+  getPromptCacheTotals(since: number, service?: string): Array<{
+    service: string;
+    configName: string;
+    requests: number;
+    cachedRequests: number;
+    inputTokens: number;
+    cacheReadTokens: number;
+    cacheWriteTokens: number;
+  }> {
+    const params: Array<string | number> = [since];
+    const serviceFilter = service ? 'AND service = ?' : '';
+    if (service) {
+      params.push(service);
+    }
+    const rows = this.reader.prepare(`
+      SELECT
+        service,
+        config_name,
+        COUNT(*) as requests,
+        SUM(CASE WHEN COALESCE(cache_read_tokens, 0) > 0 THEN 1 ELSE 0 END) as cached_requests,
+        SUM(input_tokens) as input_tokens,
+        SUM(COALESCE(cache_read_tokens, 0)) as cache_read_tokens,
+        SUM(COALESCE(cache_write_tokens, 0)) as cache_write_tokens
+      FROM requests
+      WHERE timestamp >= ? ${serviceFilter} AND input_tokens IS NOT NULL AND status_code < 400
+      GROUP BY service, config_name
+      ORDER BY requests DESC
+    `).all(...params) as any[];
+
+    return rows.map(row => ({
+      service: row.service ?? '',
+      configName: row.config_name,
+      requests: row.requests || 0,
+      cachedRequests: row.cached_requests || 0,
+      inputTokens: row.input_tokens || 0,
+      cacheReadTokens: row.cache_read_tokens || 0,
+      cacheWriteTokens: row.cache_write_tokens || 0,
+    }));
+  }
+
+  /**
+   * Responses checked for protocol compliance per config since `since`, with how often each issue code occurred
+   */
+  getComplianceTotals(since: number, service?: string): Array<{
+    service: string;
+    configName: string;
+    checked: number;
+    violating: number;
+    issues: Record<string, number>;
+  }> {
+    const params: Array<string | number> = [since];
+    const serviceFilter = service ? 'AND service = ?' : '';
+    if (service) {
+      params.push(service);
+    }
+    const rows = this.reader.prepare(`
+      SELECT
+        service,
+        config_name,
+        COUNT(*) as checked,
+        SUM(CASE WHEN protocol_issues != '[]' THEN 1 ELSE 0 END) as violating
+      FROM requests
+      WHERE timestamp >= ? ${serviceFilter} AND protocol_issues IS NOT NULL
+      GROUP BY service, config_name
+      ORDER BY checked DESC
+    `).all(...params) as any[];
+    const issueRows = this.reader.prepare(`
+      SELECT
+        service,
+        config_name,
+        substr(issue.value, 1, instr(issue.value || ':', ':') - 1) as code,
+        COUNT(*) as count
+      FROM requests, json_each(requests.protocol_issues) as issue
+      WHERE timestamp >= ? ${serviceFilter} AND protocol_issues IS NOT NULL AND protocol_issues != '[]'
+      GROUP BY service, config_name, code
+    `).all(...params) as any[];
+
+    return rows.map(row => ({
+      service: row.service ?? '',
+      configName: row.config_name,
+      checked: row.checked || 0,
+      violating: row.violating || 0,
+      issues: Object.fromEntries(
+        issueRows
+          .filter(issue => issue.service === row.service && issue.config_name === row.config_name)
+          .map(issue => [issue.code, issue.count])
+      ),
+    }));
+  }
+
+  /**
+   * Gzipped request bodies per config at or after `since`, with their sizes before and after compression
+   */
+  getRequestCompressionTotals(since: number, service?: string): Array<{
+    service: string;
+    configName: string;
+    requests: number;
+    uncompressedBytes: number;
+    compressedBytes: number;
+  }> {
+    const params: Array<string | number> = [since];
+    const serviceFilter = service ? 'AND service = ?' : '';
+    if (service) {
+      params.push(service);
+    }
+    const rows = this.reader.prepare(`
+      SELECT
+        service,
+        config_name,
+        COUNT(*) as requests,
+        SUM(request_bytes_uncompressed) as uncompressed_bytes,
+        SUM(request_bytes_compressed) as compressed_bytes
+      FROM requests
+      WHERE timestamp >= ? ${serviceFilter} AND request_bytes_compressed IS NOT NULL
+      GROUP BY service, config_name
+      ORDER BY uncompressed_bytes DESC
+    `).all(...params) as any[];
+
+    return rows.map(row => ({
+      service: row.service ?? '',
+      configName: row.config_name,
+      requests: row.requests || 0,
+      uncompressedBytes: row.uncompressed_bytes || 0,
+      compressedBytes: row.compressed_bytes || 0,
+    }));
+  }
+
+  /**
+   * Recompute daily availability from request logs at or after `since`. A minute is down when
+   * most attempts in it failed upstream (5xx, no response, or an interrupted stream); 4xx
+   * answers mean the upstream was reachable. Rollups never shrink, so a day whose logs were
+   * partly pruned keeps the figures computed while they were complete.
+   */
+  refreshSlaRollups(since: number): void {
+    this.db.prepare(`
+      INSERT INTO sla_daily (service, config_name, day, total_minutes, up_minutes)
+      SELECT service, config_name, day, COUNT(*), SUM(up)
+      FROM (
+        SELECT
+          service,
+          config_name,
+          strftime('%Y-%m-%d', MIN(timestamp) / 1000, 'unixepoch') as day,
+          CASE
+            WHEN SUM(CASE WHEN status_code IS NULL OR status_code >= 500 OR outcome = 'interrupted' THEN 1 ELSE 0 END) * 2 > COUNT(*)
+            THEN 0 ELSE 1
+          END as up
+        FROM requests
+        WHERE timestamp >= ?
+          AND service IS NOT NULL
+          AND config_name != ''
+          AND (outcome IS NULL OR outcome NOT IN ('client_disconnected', 'blocked', 'invalid', 'queued', 'cached'))
+        GROUP BY service, config_name, timestamp / 60000
+      )
+      WHERE true -- required by SQLite to parse INSERT ... SELECT ... ON CONFLICT
+      GROUP BY service, config_name, day
+      ON CONFLICT (service, config_name, day) DO UPDATE SET
+        total_minutes = excluded.total_minutes,
+        up_minutes = excluded.up_minutes
+      WHERE excluded.total_minutes >= sla_daily.total_minutes
+    `).run(since);
+  }
+
+  /**
+   * Daily availability rollups from `sinceDay` (YYYY-MM-DD) onwards
+   */
+  getSlaDays(sinceDay: string): SlaDay[] {
+    const rows = this.reader
+      .prepare('SELECT * FROM sla_daily WHERE day >= ? ORDER BY service, config_name, day')
+      .all(sinceDay) as any[];
+    return rows.map(row => ({
+      service: row.service,
+      configName: row.config_name,
+      day: row.day,
+      totalMinutes: row.total_minutes,
+      upMinutes: row.up_minutes,
+    }));
+  }
+
+  /**
+   * Phase timings of proxied requests since a timestamp, for latency breakdowns
+   */
+  getPhaseTimings(since: number, service?: string): PhaseTimingSample[] {
+    const rows = this.reader.prepare(`
+      SELECT service, config_name, proxy_ms, headers_ms, first_byte_ms, body_ms
+      FROM requests
+      WHERE timestamp >= ? AND headers_ms IS NOT NULL ${service ? 'AND service = ?' : ''}
+    `).all(...(service ? [since, service] : [since])) as any[];
+    return rows.map(row => ({
+      service: row.service ?? undefined,
+      configName: row.config_name,
+      proxyMs: row.proxy_ms ?? undefined,
+      headersMs: row.headers_ms ?? undefined,
+      firstByteMs: row.first_byte_ms ?? undefined,
+      bodyMs: row.body_ms ?? undefined,
+    }));
+  }
+
+  /**
+   * Token counts and body sizes of requests that reached an upstream since `since`; the model is the
+   * one the upstream reported, falling back to the requested one
+   */
+  getSizeSamples(since: number, service?: string): SizeSample[] {
+    const rows = this.reader.prepare(`
+      SELECT service, COALESCE(model, request_model) AS model, input_tokens, output_tokens, request_bytes, response_bytes
+      FROM requests
+      WHERE timestamp >= ? AND status_code IS NOT NULL AND target_url IS NOT NULL ${service ? 'AND service = ?' : ''}
+    `).all(...(service ? [since, service] : [since])) as any[];
+    return rows.map(row => ({
+      service: row.service ?? undefined,
+      model: row.model ?? undefined,
+      inputTokens: row.input_tokens ?? undefined,
+      outputTokens: row.output_tokens ?? undefined,
+      requestBytes: row.request_bytes ?? undefined,
+      responseBytes: row.response_bytes ?? undefined,
+    }));
+  }
+
   /**
    * Get usage stats by config
    */
@@ -193,7 +928,7 @@ export class LogDatabase {
     totalOutputTokens: number;
     avgDuration: number;
   } {
-    const stmt = this.db.prepare(`
+    const stmt = this.reader.prepare(`
       SELECT
         COUNT(*) as total_requests,
         SUM(COALESCE(input_tokens, 0)) as total_input_tokens,
@@ -213,6 +948,35 @@ export class LogDatabase {
     };
   }
 
+  /**
+   * Count requests per service bucketed by local day-of-week (0 = Sunday) and hour
+   */
+  getRequestHeatmap(since: number): Array<{
+    service: string;
+    dayOfWeek: number;
+    hour: number;
+    count: number;
+  }> {
+    const stmt = this.reader.prepare(`
+      SELECT
+        COALESCE(service, 'claude') as service,
+        CAST(strftime('%w', timestamp / 1000, 'unixepoch', 'localtime') AS INTEGER) as day_of_week,
+        CAST(strftime('%H', timestamp / 1000, 'unixepoch', 'localtime') AS INTEGER) as hour,
+        COUNT(*) as count
+      FROM requests
+      WHERE timestamp >= ?
+      GROUP BY 1, 2, 3
+    `);
+
+    const rows = stmt.all(since) as any[];
+    return rows.map(row => ({
+      service: row.service,
+      dayOfWeek: row.day_of_week,
+      hour: row.hour,
+      count: row.count,
+    }));
+  }
+
   /**
    * Delete old logs (retention policy)
    */
@@ -220,6 +984,7 @@ export class LogDatabase {
     const cutoffTime = Date.now() - daysToKeep * 24 * 60 * 60 * 1000;
     const stmt = this.db.prepare('DELETE FROM requests WHERE timestamp < ?');
     const result = stmt.run(cutoffTime);
+    this.db.prepare('DELETE FROM webhook_events WHERE received_at < ?').run(cutoffTime);
     return result.changes;
   }
 
@@ -232,6 +997,413 @@ export class LogDatabase {
     return result.changes;
   }
 
+  /**
+   * Persist a realtime event payload
+   */
+  insertRealtimeEvent(service: string, type: string, timestamp: number, payload: string): void {
+    const stmt = this.db.prepare(`
+      INSERT INTO realtime_events (service, type, timestamp, payload)
+      VALUES (?, ?, ?, ?)
+    `);
+    stmt.run(service, type, timestamp, payload);
+  }
+
+  /**
+   * Get persisted realtime event payloads for a service, oldest first
+   */
+  getRealtimeEventsSince(service: string, since: number): string[] {
+    const stmt = this.reader.prepare(`
+      SELECT payload FROM realtime_events
+      WHERE service = ? AND timestamp >= ?
+      ORDER BY timestamp ASC, id ASC
+    `);
+
+    const rows = stmt.all(service, since) as any[];
+    return rows.map(row => row.payload);
+  }
+
+  /**
+   * Delete realtime events older than the cutoff
+   */
+  deleteRealtimeEventsBefore(cutoff: number): number {
+    const stmt = this.db.prepare('DELETE FROM realtime_events WHERE timestamp < ?');
+    const result = stmt.run(cutoff);
+    return result.changes;
+  }
+
+  /**
+   * Most recent request whose response carried the given provider object id
+   */
+  getLogByUpstreamId(upstreamId: string): RequestLog | null {
+    const stmt = this.reader.prepare(`
+      SELECT * FROM requests
+      WHERE upstream_id = ?
+      ORDER BY timestamp DESC
+      LIMIT 1
+    `);
+
+    const row = stmt.get(upstreamId) as any;
+    return row ? this.rowToLog(row) : null;
+  }
+
+  /**
+   * Record a webhook delivery; returns false when the delivery id was already stored
+   */
+  insertWebhookEvent(event: WebhookEvent): boolean {
+    const stmt = this.db.prepare(`
+      INSERT OR IGNORE INTO webhook_events (id, provider, event_type, upstream_id, request_id, received_at, payload)
+      VALUES (?, ?, ?, ?, ?, ?, ?)
+    `);
+    const result = stmt.run(
+      event.id,
+      event.provider,
+      event.eventType ?? null,
+      event.upstreamId ?? null,
+      event.requestId ?? null,
+      event.receivedAt,
+      event.payload
+    );
+    return result.changes > 0;
+  }
+
+  /**
+   * Recent webhook deliveries, optionally only those correlated to one request
+   */
+  getWebhookEvents(limit = 100, requestId?: string): WebhookEvent[] {
+    const rows = (requestId
+      ? this.reader
+          .prepare('SELECT * FROM webhook_events WHERE request_id = ? ORDER BY received_at DESC LIMIT ?')
+          .all(requestId, limit)
+      : this.reader.prepare('SELECT * FROM webhook_events ORDER BY received_at DESC LIMIT ?').all(limit)) as any[];
+
+    return rows.map(row => ({
+      id: row.id,
+      provider: row.provider,
+      eventType: row.event_type ?? undefined,
+      upstreamId: row.upstream_id ?? undefined,
+      requestId: row.request_id ?? undefined,
+      receivedAt: row.received_at,
+      payload: row.payload,
+    }));
+  }
+
+  /**
+   * Insert or update an experiment definition
+   */
+  upsertExperiment(experiment: Experiment): void {
+    const stmt = this.db.prepare(`
+      INSERT OR REPLACE INTO experiments (id, name, service, arm_a, arm_b, split, starts_at, ends_at, created_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    `);
+    stmt.run(
+      experiment.id,
+      experiment.name,
+      experiment.service,
+      experiment.arms[0],
+      experiment.arms[1],
+      experiment.split,
+      experiment.startsAt,
+      experiment.endsAt,
+      experiment.createdAt
+    );
+  }
+
+  /**
+   * Get all experiments, newest first
+   */
+  getExperiments(): Experiment[] {
+    const rows = this.reader.prepare('SELECT * FROM experiments ORDER BY created_at DESC').all() as any[];
+    return rows.map(row => ({
+      id: row.id,
+      name: row.name,
+      service: row.service,
+      arms: [row.arm_a, row.arm_b],
+      split: row.split,
+      startsAt: row.starts_at,
+      endsAt: row.ends_at,
+      createdAt: row.created_at,
+    }));
+  }
+
+  deleteExperiment(id: string): boolean {
+    const result = this.db.prepare('DELETE FROM experiments WHERE id = ?').run(id);
+    return result.changes > 0;
+  }
+
+  /**
+   * Insert or update a prompt template; variables are derived from the content when read back
+   */
+  upsertPromptTemplate(template: PromptTemplate): void {
+    this.db.prepare(`
+      INSERT OR REPLACE INTO prompt_templates (name, description, content, created_at, updated_at)
+      VALUES (?, ?, ?, ?, ?)
+    `).run(template.name, template.description ?? null, template.content, template.createdAt, template.updatedAt);
+  }
+
+  getPromptTemplates(): Omit<PromptTemplate, 'variables'>[] {
+    const rows = this.reader.prepare('SELECT * FROM prompt_templates ORDER BY name').all() as any[];
+    return rows.map(row => ({
+      name: row.name,
+      description: row.description ?? undefined,
+      content: row.content,
+      createdAt: row.created_at,
+      updatedAt: row.updated_at,
+    }));
+  }
+
+  deletePromptTemplate(name: string): boolean {
+    const result = this.db.prepare('DELETE FROM prompt_templates WHERE name = ?').run(name);
+    return result.changes > 0;
+  }
+
+  upsertLogView(view: LogView): void {
+    this.db.prepare(`
+      INSERT OR REPLACE INTO log_views (id, name, query, created_at) VALUES (?, ?, ?, ?)
+    `).run(view.id, view.name, JSON.stringify(view.query), view.createdAt);
+  }
+
+  getLogViews(): LogView[] {
+    const rows = this.reader.prepare('SELECT * FROM log_views ORDER BY created_at').all() as any[];
+    return rows.map(row => ({
+      id: row.id,
+      name: row.name,
+      query: JSON.parse(row.query),
+      createdAt: row.created_at,
+    }));
+  }
+
+  deleteLogView(id: string): boolean {
+    return this.db.prepare('DELETE FROM log_views WHERE id = ?').run(id).changes > 0;
+  }
+
+  upsertAlertRule(rule: AlertRule): void {
+    this.db.prepare(`
+      INSERT OR REPLACE INTO alert_rules (
+        id, name, view_id, threshold, window_minutes, cooldown_minutes, notify_url, enabled, last_fired_at, created_at
+      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(
+      rule.id,
+      rule.name,
+      rule.viewId,
+      rule.threshold,
+      rule.windowMinutes,
+      rule.cooldownMinutes,
+      rule.notifyUrl ?? null,
+      rule.enabled ? 1 : 0,
+      rule.lastFiredAt ?? null,
+      rule.createdAt
+    );
+  }
+
+  getAlertRules(): AlertRule[] {
+    const rows = this.reader.prepare('SELECT * FROM alert_rules ORDER BY created_at').all() as any[];
+    return rows.map(row => ({
+      id: row.id,
+      name: row.name,
+      viewId: row.view_id,
+      threshold: row.threshold,
+      windowMinutes: row.window_minutes,
+      cooldownMinutes: row.cooldown_minutes,
+      notifyUrl: row.notify_url ?? undefined,
+      enabled: row.enabled === 1,
+      lastFiredAt: row.last_fired_at ?? undefined,
+      createdAt: row.created_at,
+    }));
+  }
+
+  deleteAlertRule(id: string): boolean {
+    return this.db.prepare('DELETE FROM alert_rules WHERE id = ?').run(id).changes > 0;
+  }
+
+  upsertQueuedRequest(item: QueuedRequest): void {
+    this.db.prepare(`
+      INSERT OR REPLACE INTO outage_queue (
+        id, service, method, path, headers, body, status, attempts, created_at, updated_at,
+        last_error, response_status, response_body
+      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(
+      item.id,
+      item.service,
+      item.method,
+      item.path,
+      JSON.stringify(item.headers),
+      item.body,
+      item.status,
+      item.attempts,
+      item.createdAt,
+      item.updatedAt,
+      item.lastError ?? null,
+      item.responseStatus ?? null,
+      item.responseBody ?? null
+    );
+  }
+
+  getQueuedRequest(id: string): QueuedRequest | null {
+    const row = this.reader.prepare('SELECT * FROM outage_queue WHERE id = ?').get(id) as any;
+    return row ? this.rowToQueuedRequest(row) : null;
+  }
+
+  /**
+   * Queued requests, newest first, optionally filtered by service and status
+   */
+  getQueuedRequests(service?: string, status?: QueuedRequestStatus): QueuedRequest[] {
+    const conditions: string[] = [];
+    const params: string[] = [];
+    if (service) {
+      conditions.push('service = ?');
+      params.push(service);
+    }
+    if (status) {
+      conditions.push('status = ?');
+      params.push(status);
+    }
+    const where = conditions.length > 0 ? `WHERE ${conditions.join(' AND ')}` : '';
+    const rows = this.reader
+      .prepare(`SELECT * FROM outage_queue ${where} ORDER BY created_at DESC`)
+      .all(...params) as any[];
+    return rows.map(row => this.rowToQueuedRequest(row));
+  }
+
+  upsertCachedResponse(entry: CachedResponse): void {
+    this.db.prepare(`
+      INSERT OR REPLACE INTO response_cache (
+        key, service, path, model, config_name, status, headers, body, created_at, expires_at
+      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(
+      entry.key,
+      entry.service,
+      entry.path,
+      entry.model ?? null,
+      entry.configName,
+      entry.status,
+      JSON.stringify(entry.headers),
+      entry.body,
+      entry.createdAt,
+      entry.expiresAt
+    );
+  }
+
+  /**
+   * Unexpired cached responses, oldest first; expired rows are deleted on the way
+   */
+  getCachedResponses(now: number): CachedResponse[] {
+    this.db.prepare('DELETE FROM response_cache WHERE expires_at <= ?').run(now);
+    const rows = this.reader.prepare('SELECT * FROM response_cache ORDER BY created_at').all() as any[];
+    return rows.map(row => ({
+      key: row.key,
+      service: row.service,
+      path: row.path,
+      model: row.model ?? undefined,
+      configName: row.config_name,
+      status: row.status,
+      headers: JSON.parse(row.headers),
+      body: row.body,
+      bytes: Buffer.byteLength(row.body),
+      createdAt: row.created_at,
+      expiresAt: row.expires_at,
+      hits: 0,
+    }));
+  }
+
+  deleteCachedResponses(keys: string[]): void {
+    const remove = this.db.prepare('DELETE FROM response_cache WHERE key = ?');
+    this.db.transaction(() => {
+      for (const key of keys) {
+        remove.run(key);
+      }
+    })();
+  }
+
+  upsertCertificate(record: CertificateRecord): void {
+    this.db.prepare(`
+      INSERT OR REPLACE INTO tls_certificates (
+        host, fingerprint, subject, issuer, valid_to, checked_at, previous_fingerprint, changed_at, error
+      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(
+      record.host,
+      record.fingerprint,
+      record.subject,
+      record.issuer,
+      record.validTo,
+      record.checkedAt,
+      record.previousFingerprint ?? null,
+      record.changedAt ?? null,
+      record.error ?? null
+    );
+  }
+
+  getCertificates(): CertificateRecord[] {
+    const rows = this.reader.prepare('SELECT * FROM tls_certificates ORDER BY host').all() as any[];
+    return rows.map(row => ({
+      host: row.host,
+      fingerprint: row.fingerprint,
+      subject: row.subject,
+      issuer: row.issuer,
+      validTo: row.valid_to,
+      checkedAt: row.checked_at,
+      previousFingerprint: row.previous_fingerprint ?? undefined,
+      changedAt: row.changed_at ?? undefined,
+      error: row.error ?? undefined,
+    }));
+  }
+
+  insertCrash(crash: CrashRecord): void {
+    this.db.prepare(`
+      INSERT INTO crashes (timestamp, kind, message, stack, context, fatal) VALUES (?, ?, ?, ?, ?, ?)
+    `).run(crash.timestamp, crash.kind, crash.message, crash.stack ?? null, crash.context ?? null, crash.fatal ? 1 : 0);
+  }
+
+  getCrashes(limit: number): CrashRecord[] {
+    const rows = this.reader.prepare('SELECT * FROM crashes ORDER BY timestamp DESC, id DESC LIMIT ?').all(limit) as any[];
+    return rows.map(row => ({
+      id: row.id,
+      timestamp: row.timestamp,
+      kind: row.kind,
+      message: row.message,
+      stack: row.stack ?? undefined,
+      context: row.context ?? undefined,
+      fatal: row.fatal === 1,
+    }));
+  }
+
+  private rowToQueuedRequest(row: any): QueuedRequest {
+    return {
+      id: row.id,
+      service: row.service,
+      method: row.method,
+      path: row.path,
+      headers: JSON.parse(row.headers),
+      body: row.body,
+      status: row.status,
+      attempts: row.attempts,
+      createdAt: row.created_at,
+      updatedAt: row.updated_at,
+      lastError: row.last_error ?? undefined,
+      responseStatus: row.response_status ?? undefined,
+      responseBody: row.response_body ?? undefined,
+    };
+  }
+
+  /**
+   * Per-request measurements recorded for an experiment
+   */
+  getExperimentSamples(experimentId: string): ExperimentSample[] {
+    const stmt = this.reader.prepare(`
+      SELECT experiment_arm, duration, status_code, error, input_tokens, output_tokens
+      FROM requests
+      WHERE experiment_id = ?
+    `);
+
+    const rows = stmt.all(experimentId) as any[];
+    return rows.map(row => ({
+      arm: row.experiment_arm,
+      duration: row.duration ?? undefined,
+      failed: Boolean(row.error) || !row.status_code || row.status_code >= 400,
+      inputTokens: row.input_tokens ?? undefined,
+      outputTokens: row.output_tokens ?? undefined,
+    }));
+  }
+
   /**
    * Convert database row to RequestLog
    */
@@ -255,6 +1427,33 @@ export class LogDatabase {
       responsePreview: row.response_preview,
       requestHeaders: row.request_headers ? JSON.parse(row.request_headers) : undefined,
       responseHeaders: row.response_headers ? JSON.parse(row.response_headers) : undefined,
+      outcome: row.outcome ?? undefined,
+      experimentId: row.experiment_id ?? undefined,
+      experimentArm: row.experiment_arm ?? undefined,
+      upstreamId: row.upstream_id ?? undefined,
+      thinkingTokens: row.thinking_tokens ?? undefined,
+      dlpMatches: row.dlp_matches ? JSON.parse(row.dlp_matches) : undefined,
+      scrubbedItems: row.scrubbed_items ?? undefined,
+      promptTemplates: row.prompt_templates ? JSON.parse(row.prompt_templates) : undefined,
+      tags: row.tags ? JSON.parse(row.tags) : undefined,
+      proxyMs: row.proxy_ms ?? undefined,
+      headersMs: row.headers_ms ?? undefined,
+      firstByteMs: row.first_byte_ms ?? undefined,
+      bodyMs: row.body_ms ?? undefined,
+      requestBytes: row.request_bytes ?? undefined,
+      responseBytes: row.response_bytes ?? undefined,
+      context: row.context ? JSON.parse(row.context) : undefined,
+      modelOverride: row.model_override ?? undefined,
+      internalLogs: row.internal_logs ? JSON.parse(row.internal_logs) : undefined,
+      cacheReadTokens: row.cache_read_tokens ?? undefined,
+      cacheWriteTokens: row.cache_write_tokens ?? undefined,
+      conversationId: row.conversation_id ?? undefined,
+      fullRequestBody: row.request_body_full ?? undefined,
+      protocolIssues: row.protocol_issues ? JSON.parse(row.protocol_issues) : undefined,
+      clientIdentity: row.client_identity ?? undefined,
+      uncompressedRequestBytes: row.request_bytes_uncompressed ?? undefined,
+      compressedRequestBytes: row.request_bytes_compressed ?? undefined,
+      contentOmitted: row.content_omitted ? true : undefined,
     };
   }
 
@@ -262,6 +1461,7 @@ export class LogDatabase {
    * Close the database connection
    */
   close(): void {
+    this.reader.close();
     this.db.close();
   }
 }
@@ -0,0 +1,100 @@
+// Log filter - console output levels per message prefix, swappable at runtime without a restart
+
+export type LogLevel = 'debug' | 'info' | 'warn' | 'error';
+
+const LEVEL_RANK: Record<LogLevel, number> = { debug: 0, info: 1, warn: 2, error: 3 };
+
+// console.log and console.info are both info
+const CONSOLE_LEVELS: Array<['debug' | 'log' | 'info' | 'warn' | 'error', LogLevel]> = [
+  ['debug', 'debug'],
+  ['log', 'info'],
+  ['info', 'info'],
+  ['warn', 'warn'],
+  ['error', 'error'],
+];
+
+export interface LogFilter {
+  spec: string;
+  defaultLevel: LogLevel;
+  targets: Array<{ target: string; level: LogLevel }>; // Longest target first
+}
+
+function isLogLevel(value: string): value is LogLevel {
+  return Object.prototype.hasOwnProperty.call(LEVEL_RANK, value);
+}
+
+/**
+ * Parse a filter such as `info`, `debug` or `warn,proxy=debug,proxy:codex=info`. Targets are the
+ * bracketed prefixes paf prints (`[proxy:claude] ...`); `proxy` also covers `proxy:claude`.
+ */
+export function parseLogFilter(spec: string): LogFilter | { error: string } {
+  let defaultLevel: LogLevel = 'info';
+  const targets: LogFilter['targets'] = [];
+
+  for (const directive of spec.split(',').map(part => part.trim()).filter(Boolean)) {
+    const [rawTarget, rawLevel] = directive.includes('=') ? directive.split('=', 2) : [undefined, directive];
+    const level = rawLevel.trim().toLowerCase();
+    if (!isLogLevel(level)) {
+      return { error: `Unknown log level "${rawLevel.trim()}"; expected debug, info, warn or error` };
+    }
+    if (rawTarget === undefined) {
+      defaultLevel = level;
+      continue;
+    }
+    // Accept Rust-style module paths too: proxy:: and proxy::claude
+    const target = rawTarget.trim().replace(/::/g, ':').replace(/:+$/, '');
+    if (!target) {
+      return { error: `Missing target in "${directive}"` };
+    }
+    targets.push({ target, level });
+  }
+
+  targets.sort((a, b) => b.target.length - a.target.length);
+  return { spec: formatLogFilter(defaultLevel, targets), defaultLevel, targets };
+}
+
+function formatLogFilter(defaultLevel: LogLevel, targets: LogFilter['targets']): string {
+  return [defaultLevel, ...targets.map(({ target, level }) => `${target}=${level}`)].join(',');
+}
+
+function messageTarget(args: unknown[]): string | undefined {
+  const first = args[0];
+  return typeof first === 'string' ? first.match(/^\[([^\]\s]+)\]/)?.[1] : undefined;
+}
+
+export function isLevelEnabled(filter: LogFilter, level: LogLevel, target?: string): boolean {
+  const rule = target
+    ? filter.targets.find(entry => target === entry.target || target.startsWith(`${entry.target}:`))
+    : undefined;
+  return LEVEL_RANK[level] >= LEVEL_RANK[rule?.level ?? filter.defaultLevel];
+}
+
+let active: LogFilter | null = null;
+
+/**
+ * Apply `spec` to console output. The first call installs the filter; later calls swap it in place,
+ * so in-flight requests and streams are unaffected.
+ */
+export function setLogFilter(spec: string): LogFilter | { error: string } {
+  const parsed = parseLogFilter(spec);
+  if ('error' in parsed) {
+    return parsed;
+  }
+
+  if (!active) {
+    for (const [method, level] of CONSOLE_LEVELS) {
+      const print = console[method].bind(console);
+      console[method] = (...args: unknown[]) => {
+        if (!active || isLevelEnabled(active, level, messageTarget(args))) {
+          print(...args);
+        }
+      };
+    }
+  }
+  active = parsed;
+  return parsed;
+}
+
+export function getLogFilter(): LogFilter | null {
+  return active;
+}
@@ -1,6 +1,22 @@
 // Request logger - handles logging of proxy requests
 
-import { LogDatabase, type RequestLog } from './database';
+import { appendFileSync, existsSync, renameSync, statSync } from 'fs';
+import { join } from 'path';
+import { LogDatabase, type DatabaseRecovery, type LogQuery, type RequestLog, type SlaDay, type WebhookEvent } from './database';
+import { percentile } from '../experiments/stats';
+import type { AlertRule, LogView } from '../monitoring/alerts';
+import type { QueuedRequest, QueuedRequestStatus } from '../proxy/outageQueue';
+import type { CachedResponse } from '../proxy/responseCache';
+import type { CertificateRecord } from '../monitoring/certificates';
+import type { CrashRecord } from '../monitoring/crashes';
+import type { Experiment, ExperimentSample } from '../experiments/registry';
+import { extractVariables, type PromptTemplate } from '../prompts/library';
+import { estimateTokens } from '../proxy/streamSalvage';
+import type { LogScrubbingConfig } from '../config/types';
+import { scrubRequestLog, withoutContent } from './scrubber';
+import { spanLogsFor } from './tracing';
+import { toWireRequestLog } from '../protocol';
+import { LITE_LOG_FILE } from '../config/lite';
 
 export interface LastRequestSnapshot {
   service: string;
@@ -16,36 +32,219 @@ export interface LastRequestSnapshot {
   source: 'cli' | 'proxy';
 }
 
+export type RequestLoggedListener = (log: RequestLog) => void;
+
+export interface SlaWindow {
+  days: number;
+  availability: number | null; // upMinutes / totalMinutes; null without any traffic or probes
+  totalMinutes: number;
+  upMinutes: number;
+}
+
+export interface ConfigSla {
+  service: string;
+  configName: string;
+  windows: SlaWindow[];
+  daily: SlaDay[]; // Covers the longest window
+}
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+
+export interface PhaseSummary {
+  avg: number | null;
+  p50: number | null;
+  p95: number | null;
+}
+
+export interface ConfigComplianceStats {
+  service: string;
+  configName: string;
+  checked: number;                // Responses checked (validate_responses on, successful, complete)
+  violating: number;              // Of those, responses with at least one protocol issue
+  score: number | null;           // Share of checked responses without issues
+  issues: Record<string, number>; // Issue code -> responses it was found in
+}
+
+export interface ConfigRequestCompressionStats {
+  service: string;
+  configName: string;
+  requests: number;          // Requests whose body was gzipped (request_compression)
+  uncompressedBytes: number;
+  compressedBytes: number;
+  savedBytes: number;
+  ratio: number | null;      // compressed / uncompressed
+}
+
+export interface ConfigPromptCacheStats {
+  service: string;
+  configName: string;
+  requests: number;
+  cachedRequests: number;        // Requests that read anything from the cache
+  inputTokens: number;           // As reported by the provider
+  promptTokens: number;          // All prompt tokens, cached or not
+  cacheReadTokens: number;
+  cacheWriteTokens: number;
+  requestHitRate: number | null;
+  tokenHitRate: number | null;   // Share of prompt tokens served from the cache
+}
+
+export interface ConfigLatencyBreakdown {
+  service: string;
+  configName: string;
+  requests: number;
+  proxyMs: PhaseSummary;
+  headersMs: PhaseSummary;
+  firstByteMs: PhaseSummary;
+  bodyMs: PhaseSummary;
+}
+
+function summarizePhase(values: Array<number | undefined>): PhaseSummary {
+  const samples = values.filter((value): value is number => typeof value === 'number');
+  return {
+    avg: samples.length > 0 ? Math.round(samples.reduce((sum, value) => sum + value, 0) / samples.length) : null,
+    p50: percentile(samples, 0.5),
+    p95: percentile(samples, 0.95),
+  };
+}
+
+// Upper bounds of histogram buckets; a final open-ended bucket catches everything above
+const TOKEN_BUCKETS = [256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536, 131072, 262144];
+const BYTE_BUCKETS = [1024, 4096, 16384, 65536, 262144, 1048576, 4194304, 16777216];
+
+export interface HistogramBucket {
+  le: number | null; // Inclusive upper bound; null for the open-ended last bucket
+  count: number;
+}
+
+export interface Distribution {
+  samples: number;
+  p50: number | null;
+  p95: number | null;
+  max: number | null;
+  buckets: HistogramBucket[];
+}
+
+export interface ModelDistributions {
+  service: string;
+  model: string;
+  requests: number;
+  promptTokens: Distribution;
+  completionTokens: Distribution;
+  requestBytes: Distribution;
+  responseBytes: Distribution;
+}
+
+function histogram(values: Array<number | undefined>, bounds: number[]): Distribution {
+  const samples = values.filter((value): value is number => typeof value === 'number');
+  const buckets: HistogramBucket[] = [...bounds, null].map(le => ({ le, count: 0 }));
+  for (const value of samples) {
+    const index = bounds.findIndex(bound => value <= bound);
+    buckets[index === -1 ? bounds.length : index].count++;
+  }
+  return {
+    samples: samples.length,
+    p50: percentile(samples, 0.5),
+    p95: percentile(samples, 0.95),
+    max: samples.length > 0 ? samples.reduce((max, value) => Math.max(max, value), 0) : null,
+    buckets,
+  };
+}
+
+export interface RequestLoggerOptions {
+  scrubbing?: LogScrubbingConfig;
+  jsonl?: boolean; // Append request logs to requests.jsonl; everything else stays in memory (lite mode)
+}
+
+// A full requests.jsonl is moved to requests.jsonl.1 (replacing the previous one) before the next append
+const JSONL_ROTATE_BYTES = 10 * 1024 * 1024;
+
 export class RequestLogger {
   private db: LogDatabase;
   private lastResults: Map<string, LastRequestSnapshot>;
-
-  constructor(dataDir: string) {
-    this.db = new LogDatabase(dataDir);
+  private listeners: Set<RequestLoggedListener>;
+  private scrubbing?: LogScrubbingConfig;
+  private jsonlPath?: string;
+  private revision = 0; // Bumped whenever stored request logs or last results change
+  private changedAt = Date.now();
+
+  constructor(dataDir: string, options: RequestLoggerOptions = {}) {
+    this.db = new LogDatabase(dataDir, options.jsonl === true);
     this.lastResults = new Map();
+    this.listeners = new Set();
+    this.scrubbing = options.scrubbing;
+    this.jsonlPath = options.jsonl ? join(dataDir, LITE_LOG_FILE) : undefined;
+  }
+
+  /**
+   * Set when the database failed its startup integrity check and was recreated
+   */
+  getDatabaseRecovery(): DatabaseRecovery | null {
+    return this.db.recovery;
+  }
+
+  /**
+   * Register a listener invoked after each request log is persisted
+   */
+  onRequestLogged(listener: RequestLoggedListener): () => void {
+    this.listeners.add(listener);
+    return () => this.listeners.delete(listener);
   }
 
   /**
    * Log a request
    */
-  async logRequest(log: RequestLog): Promise<void> {
+  async logRequest(entry: RequestLog): Promise<void> {
+    // Lines printed so far inside the request's span; copied now, the span keeps collecting
+    const internalLogs = entry.internalLogs ?? spanLogsFor(entry.id);
+
     // Insert asynchronously to avoid blocking
     queueMicrotask(() => {
+      // No-log requests: their content never reaches storage, listeners or the realtime feed
+      let log = entry.contentOmitted ? withoutContent(entry) : internalLogs ? { ...entry, internalLogs } : entry;
       try {
-        this.db.insertLog(log);
+        if (this.scrubbing) {
+          log = scrubRequestLog(log, this.scrubbing);
+        }
+
+        if (this.jsonlPath) {
+          this.appendJsonl(log);
+        } else {
+          this.db.insertLog(log);
+        }
         this.updateLastResult(log);
+        this.markChanged();
       } catch (error) {
         console.error('Failed to log request:', error);
+        return;
+      }
+
+      for (const listener of this.listeners) {
+        try {
+          listener(log);
+        } catch (error) {
+          console.error('Request log listener failed:', error);
+        }
       }
     });
   }
 
+  private appendJsonl(log: RequestLog): void {
+    const path = this.jsonlPath!;
+    if (existsSync(path) && statSync(path).size >= JSONL_ROTATE_BYTES) {
+      renameSync(path, `${path}.1`);
+    }
+    appendFileSync(path, `${JSON.stringify(toWireRequestLog(log))}\n`);
+  }
+
   /**
    * Parse usage information from response
    */
   parseUsage(responseBody: any): {
     inputTokens?: number;
     outputTokens?: number;
+    thinkingTokens?: number;
+    cacheReadTokens?: number;
+    cacheWriteTokens?: number;
     model?: string;
   } {
     try {
@@ -53,11 +252,24 @@ export class RequestLogger {
         return {};
       }
 
-      // Handle Anthropic format (has input_tokens/output_tokens)
+      // Handle Anthropic / OpenAI Responses format (has input_tokens/output_tokens)
       if (responseBody?.usage?.input_tokens !== undefined) {
+        // Anthropic bills thinking inside output_tokens without a breakdown, so estimate from the blocks
+        const thinking = Array.isArray(responseBody.content)
+          ? responseBody.content
+              .filter((block: any) => block?.type === 'thinking' && typeof block.thinking === 'string')
+              .map((block: any) => block.thinking)
+              .join('')
+          : '';
         return {
           inputTokens: responseBody.usage.input_tokens,
           outputTokens: responseBody.usage.output_tokens,
+          thinkingTokens:
+            responseBody.usage.output_tokens_details?.reasoning_tokens ??
+            (thinking ? estimateTokens(thinking) : undefined),
+          cacheReadTokens:
+            responseBody.usage.cache_read_input_tokens ?? responseBody.usage.input_tokens_details?.cached_tokens,
+          cacheWriteTokens: responseBody.usage.cache_creation_input_tokens,
           model: responseBody.model,
         };
       }
@@ -67,6 +279,8 @@ export class RequestLogger {
         return {
           inputTokens: responseBody.usage.prompt_tokens,
           outputTokens: responseBody.usage.completion_tokens,
+          thinkingTokens: responseBody.usage.completion_tokens_details?.reasoning_tokens,
+          cacheReadTokens: responseBody.usage.prompt_tokens_details?.cached_tokens,
           model: responseBody.model,
         };
       }
@@ -78,10 +292,20 @@ export class RequestLogger {
     }
   }
 
+  /**
+   * Provider object id of a JSON response (msg_..., resp_..., batch_...), used to match async callbacks
+   */
+  extractUpstreamId(responseBody: any): string | undefined {
+    if (!responseBody || typeof responseBody !== 'object') {
+      return undefined;
+    }
+    return typeof responseBody.id === 'string' ? responseBody.id : undefined;
+  }
+
   /**
    * Extract request information from request body
    */
-  extractRequestInfo(requestBody: any): {
+  extractRequestInfo(requestBody: any, stripThinking = false): {
     model?: string;
     preview?: string;
   } {
@@ -92,8 +316,18 @@ export class RequestLogger {
 
       const model = requestBody.model;
 
-      // Create a preview of the request (truncated)
-      const preview = JSON.stringify(requestBody).substring(0, 500);
+      // Create a preview of the request (truncated); earlier assistant turns may replay thinking blocks
+      const stored = stripThinking && Array.isArray(requestBody.messages)
+        ? {
+            ...requestBody,
+            messages: requestBody.messages.map((message: any) =>
+              Array.isArray(message?.content)
+                ? { ...message, content: withoutThinkingBlocks(message.content) }
+                : message
+            ),
+          }
+        : requestBody;
+      const preview = JSON.stringify(stored).substring(0, 500);
 
       return { model, preview };
     } catch (error) {
@@ -105,7 +339,7 @@ export class RequestLogger {
   /**
    * Extract response preview
    */
-  extractResponsePreview(responseBody: any): string {
+  extractResponsePreview(responseBody: any, stripThinking = false): string {
     try {
       if (!responseBody) {
         return '';
@@ -116,9 +350,12 @@ export class RequestLogger {
         return responseBody.substring(0, 500);
       }
 
-      // Handle Anthropic format - get first content block
-      if (responseBody.content && Array.isArray(responseBody.content) && responseBody.content[0]?.text) {
-        return responseBody.content[0].text.substring(0, 500);
+      // Handle Anthropic format - get first text block (thinking blocks come before it)
+      const textBlock = Array.isArray(responseBody.content)
+        ? responseBody.content.find((block: any) => typeof block?.text === 'string' && block.text)
+        : undefined;
+      if (textBlock) {
+        return textBlock.text.substring(0, 500);
       }
 
       // Handle OpenAI format - get message content
@@ -135,7 +372,10 @@ export class RequestLogger {
       }
 
       // Fallback: stringify the whole object
-      return JSON.stringify(responseBody).substring(0, 500);
+      const stored = stripThinking && Array.isArray(responseBody.content)
+        ? { ...responseBody, content: withoutThinkingBlocks(responseBody.content) }
+        : responseBody;
+      return JSON.stringify(stored).substring(0, 500);
     } catch (error) {
       console.error('Failed to extract response preview:', error);
       return '';
@@ -145,8 +385,12 @@ export class RequestLogger {
   /**
    * Get recent logs
    */
-  getRecentLogs(limit = 100, offset = 0): RequestLog[] {
-    return this.db.getRecentLogs(limit, offset);
+  getRecentLogs(limit = 100, offset = 0, query?: LogQuery): RequestLog[] {
+    return this.db.getRecentLogs(limit, offset, query);
+  }
+
+  countLogs(query: LogQuery): number {
+    return this.db.countLogs(query);
   }
 
   /**
@@ -156,6 +400,10 @@ export class RequestLogger {
     return this.db.getLogById(id);
   }
 
+  getConversationLogs(conversationId: string): RequestLog[] {
+    return this.db.getLogsByConversation(conversationId);
+  }
+
   /**
    * Get logs by config
    */
@@ -166,8 +414,15 @@ export class RequestLogger {
   /**
    * Get usage statistics
    */
-  getUsageStats() {
-    return this.db.getUsageStats();
+  getUsageStats(query?: LogQuery) {
+    return this.db.getUsageStats(query);
+  }
+
+  /**
+   * Get usage totals per value of a tag key
+   */
+  getUsageByTag(key: string) {
+    return this.db.getUsageByTag(key);
   }
 
   /**
@@ -177,11 +432,167 @@ export class RequestLogger {
     return this.db.getUsageStatsByConfig(configName);
   }
 
+  /**
+   * Build a day-of-week x hour request count grid per service
+   */
+  getRequestHeatmap(days = 30): Record<string, number[][]> {
+    const since = Date.now() - days * 24 * 60 * 60 * 1000;
+    const grids: Record<string, number[][]> = {};
+
+    for (const row of this.db.getRequestHeatmap(since)) {
+      if (!grids[row.service]) {
+        grids[row.service] = Array.from({ length: 7 }, () => new Array<number>(24).fill(0));
+      }
+      grids[row.service][row.dayOfWeek][row.hour] += row.count;
+    }
+
+    return grids;
+  }
+
   /**
    * Clean up old logs
    */
   cleanupOldLogs(daysToKeep = 30): number {
-    return this.db.deleteOldLogs(daysToKeep);
+    const deleted = this.db.deleteOldLogs(daysToKeep);
+    if (deleted > 0) {
+      this.markChanged();
+    }
+    return deleted;
+  }
+
+  /**
+   * Per-config latency split into phases over the last `windowMinutes`
+   */
+  getLatencyBreakdown(windowMinutes = 24 * 60, service?: string): ConfigLatencyBreakdown[] {
+    const samples = this.db.getPhaseTimings(Date.now() - windowMinutes * 60_000, service);
+    const byConfig = new Map<string, typeof samples>();
+    for (const sample of samples) {
+      const key = `${sample.service}:${sample.configName}`;
+      const group = byConfig.get(key);
+      if (group) {
+        group.push(sample);
+      } else {
+        byConfig.set(key, [sample]);
+      }
+    }
+
+    return [...byConfig.values()].map(group => ({
+      service: group[0].service ?? '',
+      configName: group[0].configName,
+      requests: group.length,
+      proxyMs: summarizePhase(group.map(s => s.proxyMs)),
+      headersMs: summarizePhase(group.map(s => s.headersMs)),
+      firstByteMs: summarizePhase(group.map(s => s.firstByteMs)),
+      bodyMs: summarizePhase(group.map(s => s.bodyMs)),
+    }));
+  }
+
+  /**
+   * Prompt cache effectiveness per config over the last `windowMinutes`. Anthropic reports cached tokens
+   * on top of input_tokens, OpenAI inside them, so the prompt total is normalized per service.
+   */
+  getPromptCacheStats(windowMinutes = 24 * 60, service?: string): ConfigPromptCacheStats[] {
+    return this.db.getPromptCacheTotals(Date.now() - windowMinutes * 60_000, service).map(totals => {
+      const promptTokens =
+        totals.service === 'codex'
+          ? totals.inputTokens
+          : totals.inputTokens + totals.cacheReadTokens + totals.cacheWriteTokens;
+      return {
+        ...totals,
+        promptTokens,
+        requestHitRate: totals.requests > 0 ? totals.cachedRequests / totals.requests : null,
+        tokenHitRate: promptTokens > 0 ? totals.cacheReadTokens / promptTokens : null,
+      };
+    });
+  }
+
+  /**
+   * Protocol compliance per config over the last `windowMinutes`; configs never checked are left out
+   */
+  getComplianceStats(windowMinutes = 24 * 60, service?: string): ConfigComplianceStats[] {
+    return this.db.getComplianceTotals(Date.now() - windowMinutes * 60_000, service).map(totals => ({
+      ...totals,
+      score: totals.checked > 0 ? (totals.checked - totals.violating) / totals.checked : null,
+    }));
+  }
+
+  /**
+   * Upload bandwidth saved by request_compression per config over the last `windowMinutes`
+   */
+  getRequestCompressionStats(windowMinutes = 24 * 60, service?: string): ConfigRequestCompressionStats[] {
+    return this.db.getRequestCompressionTotals(Date.now() - windowMinutes * 60_000, service).map(totals => ({
+      ...totals,
+      savedBytes: totals.uncompressedBytes - totals.compressedBytes,
+      ratio: totals.uncompressedBytes > 0 ? totals.compressedBytes / totals.uncompressedBytes : null,
+    }));
+  }
+
+  /**
+   * Histograms of prompt/completion tokens and body sizes per service and model over the last `windowMinutes`
+   */
+  getDistributions(windowMinutes = 24 * 60, service?: string): ModelDistributions[] {
+    const samples = this.db.getSizeSamples(Date.now() - windowMinutes * 60_000, service);
+    const byModel = new Map<string, typeof samples>();
+    for (const sample of samples) {
+      const key = `${sample.service}:${sample.model}`;
+      const group = byModel.get(key);
+      if (group) {
+        group.push(sample);
+      } else {
+        byModel.set(key, [sample]);
+      }
+    }
+
+    return [...byModel.values()]
+      .map(group => ({
+        service: group[0].service ?? '',
+        model: group[0].model ?? 'unknown',
+        requests: group.length,
+        promptTokens: histogram(group.map(s => s.inputTokens), TOKEN_BUCKETS),
+        completionTokens: histogram(group.map(s => s.outputTokens), TOKEN_BUCKETS),
+        requestBytes: histogram(group.map(s => s.requestBytes), BYTE_BUCKETS),
+        responseBytes: histogram(group.map(s => s.responseBytes), BYTE_BUCKETS),
+      }))
+      .sort((a, b) => b.requests - a.requests);
+  }
+
+  /**
+   * Roll request logs up into daily availability; recent days are recomputed as traffic arrives
+   */
+  refreshSla(sinceDays = 2): void {
+    const todayStart = Math.floor(Date.now() / DAY_MS) * DAY_MS;
+    this.db.refreshSlaRollups(sinceDays === Infinity ? 0 : todayStart - (sinceDays - 1) * DAY_MS);
+  }
+
+  /**
+   * Availability per config over trailing windows of UTC days, today included
+   */
+  getSlaReport(windows: number[] = [7, 30, 90], service?: string): ConfigSla[] {
+    this.refreshSla();
+
+    const longest = Math.max(...windows);
+    const dayKey = (offset: number) => new Date(Date.now() - offset * DAY_MS).toISOString().slice(0, 10);
+    const rows = this.db.getSlaDays(dayKey(longest - 1)).filter(row => !service || row.service === service);
+
+    const byConfig = new Map<string, ConfigSla>();
+    for (const row of rows) {
+      const key = `${row.service}:${row.configName}`;
+      if (!byConfig.has(key)) {
+        byConfig.set(key, { service: row.service, configName: row.configName, windows: [], daily: [] });
+      }
+      byConfig.get(key)!.daily.push(row);
+    }
+
+    for (const entry of byConfig.values()) {
+      entry.windows = windows.map(days => {
+        const included = entry.daily.filter(row => row.day >= dayKey(days - 1));
+        const totalMinutes = included.reduce((sum, row) => sum + row.totalMinutes, 0);
+        const upMinutes = included.reduce((sum, row) => sum + row.upMinutes, 0);
+        return { days, availability: totalMinutes > 0 ? upMinutes / totalMinutes : null, totalMinutes, upMinutes };
+      });
+    }
+
+    return [...byConfig.values()];
   }
 
   /**
@@ -189,9 +600,143 @@ export class RequestLogger {
    */
   clearAllLogs(): number {
     this.lastResults.clear();
+    this.markChanged();
     return this.db.clearAllLogs();
   }
 
+  /**
+   * Persist a serialized realtime event for replay after restarts
+   */
+  saveRealtimeEvent(service: string, type: string, timestamp: number, payload: string): void {
+    this.db.insertRealtimeEvent(service, type, timestamp, payload);
+  }
+
+  /**
+   * Get serialized realtime events recorded for a service since the given time
+   */
+  getRealtimeEventsSince(service: string, since: number): string[] {
+    return this.db.getRealtimeEventsSince(service, since);
+  }
+
+  /**
+   * Drop realtime events that fell out of the replay window
+   */
+  pruneRealtimeEvents(before: number): number {
+    return this.db.deleteRealtimeEventsBefore(before);
+  }
+
+  getLogByUpstreamId(upstreamId: string): RequestLog | null {
+    return this.db.getLogByUpstreamId(upstreamId);
+  }
+
+  /**
+   * Store a verified webhook delivery; false means it was a redelivery
+   */
+  saveWebhookEvent(event: WebhookEvent): boolean {
+    return this.db.insertWebhookEvent(event);
+  }
+
+  getWebhookEvents(limit = 100, requestId?: string): WebhookEvent[] {
+    return this.db.getWebhookEvents(limit, requestId);
+  }
+
+  saveExperiment(experiment: Experiment): void {
+    this.db.upsertExperiment(experiment);
+  }
+
+  listExperiments(): Experiment[] {
+    return this.db.getExperiments();
+  }
+
+  deleteExperiment(id: string): boolean {
+    return this.db.deleteExperiment(id);
+  }
+
+  getExperimentSamples(experimentId: string): ExperimentSample[] {
+    return this.db.getExperimentSamples(experimentId);
+  }
+
+  savePromptTemplate(template: PromptTemplate): void {
+    this.db.upsertPromptTemplate(template);
+  }
+
+  listPromptTemplates(): PromptTemplate[] {
+    return this.db
+      .getPromptTemplates()
+      .map(template => ({ ...template, variables: extractVariables(template.content) }));
+  }
+
+  deletePromptTemplate(name: string): boolean {
+    return this.db.deletePromptTemplate(name);
+  }
+
+  saveLogView(view: LogView): void {
+    this.db.upsertLogView(view);
+  }
+
+  listLogViews(): LogView[] {
+    return this.db.getLogViews();
+  }
+
+  deleteLogView(id: string): boolean {
+    return this.db.deleteLogView(id);
+  }
+
+  saveAlertRule(rule: AlertRule): void {
+    this.db.upsertAlertRule(rule);
+  }
+
+  listAlertRules(): AlertRule[] {
+    return this.db.getAlertRules();
+  }
+
+  deleteAlertRule(id: string): boolean {
+    return this.db.deleteAlertRule(id);
+  }
+
+  saveQueuedRequest(item: QueuedRequest): void {
+    this.db.upsertQueuedRequest(item);
+  }
+
+  getQueuedRequest(id: string): QueuedRequest | null {
+    return this.db.getQueuedRequest(id);
+  }
+
+  listQueuedRequests(service?: string, status?: QueuedRequestStatus): QueuedRequest[] {
+    return this.db.getQueuedRequests(service, status);
+  }
+
+  loadCachedResponses(now: number): CachedResponse[] {
+    return this.db.getCachedResponses(now);
+  }
+
+  saveCachedResponse(entry: CachedResponse): void {
+    this.db.upsertCachedResponse(entry);
+  }
+
+  deleteCachedResponses(keys: string[]): void {
+    this.db.deleteCachedResponses(keys);
+  }
+
+  saveCertificate(record: CertificateRecord): void {
+    this.db.upsertCertificate(record);
+  }
+
+  listCertificates(): CertificateRecord[] {
+    return this.db.getCertificates();
+  }
+
+  /**
+   * Stored synchronously: the process may exit right after an uncaught exception
+   */
+  recordCrash(crash: CrashRecord): void {
+    this.db.insertCrash(crash);
+  }
+
+  listCrashes(limit = 20): CrashRecord[] {
+    return this.db.getCrashes(limit);
+  }
+
   /**
    * Close the logger
    */
@@ -217,6 +762,19 @@ export class RequestLogger {
    */
   clearLastResult(serviceName: string, configName: string): void {
     this.lastResults.delete(this.buildKey(serviceName, configName));
+    this.markChanged();
+  }
+
+  /**
+   * Changes to request logs and last results, for conditional GETs on /api/logs and /api/configs
+   */
+  getRevision(): { revision: number; changedAt: number } {
+    return { revision: this.revision, changedAt: this.changedAt };
+  }
+
+  private markChanged(): void {
+    this.revision++;
+    this.changedAt = Date.now();
   }
 
   private updateLastResult(log: RequestLog): void {
@@ -263,3 +821,7 @@ export class RequestLogger {
     return `${serviceName}::${configName}`;
   }
 }
+
+function withoutThinkingBlocks(content: any[]): any[] {
+  return content.filter(block => block?.type !== 'thinking' && block?.type !== 'redacted_thinking');
+}
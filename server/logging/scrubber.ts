@@ -0,0 +1,113 @@
+// PII scrubber - masks personal data and credentials in request logs before they are stored, or drops
+// a no-log request's content altogether
+
+import type { LogScrubbingConfig } from '../config/types';
+import type { RequestLog } from './database';
+
+type PiiKind = keyof LogScrubbingConfig;
+
+const PII_PATTERNS: Record<PiiKind, { regex: RegExp; replacement: string }> = {
+  emails: {
+    regex: /[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}/g,
+    replacement: '[EMAIL]',
+  },
+  // Needs separators between digit groups so timestamps and token counts are left alone
+  phoneNumbers: {
+    regex: /(?<![\w+])(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)|\d{2,4})[\s.-]\d{3,4}[\s.-]\d{3,4}(?!\w)/g,
+    replacement: '[PHONE]',
+  },
+  awsKeys: {
+    regex: /\b(?:AKIA|ASIA)[0-9A-Z]{16}\b|(aws_secret_access_key["']?\s*[:=]\s*["']?)[A-Za-z0-9/+=]{40}/gi,
+    replacement: '[AWS_KEY]',
+  },
+  jwts: {
+    regex: /\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*/g,
+    replacement: '[JWT]',
+  },
+};
+
+function scrubText(text: string, kinds: PiiKind[]): { text: string; count: number } {
+  let count = 0;
+  let result = text;
+  for (const kind of kinds) {
+    const { regex, replacement } = PII_PATTERNS[kind];
+    result = result.replace(regex, (_match: string, label: unknown) => {
+      count++;
+      // Keep the "aws_secret_access_key =" label so the log still shows what was there; patterns
+      // without a capture group receive the match offset here instead
+      return `${typeof label === 'string' ? label : ''}${replacement}`;
+    });
+  }
+  return { text: result, count };
+}
+
+function scrubHeaders(
+  headers: Record<string, string> | undefined,
+  kinds: PiiKind[]
+): { headers?: Record<string, string>; count: number } {
+  if (!headers) {
+    return { count: 0 };
+  }
+  let count = 0;
+  const scrubbed: Record<string, string> = {};
+  for (const [key, value] of Object.entries(headers)) {
+    const result = scrubText(value, kinds);
+    scrubbed[key] = result.text;
+    count += result.count;
+  }
+  return { headers: scrubbed, count };
+}
+
+/**
+ * Drop everything a request said or got back (x-paf-no-log): bodies, previews, headers and printed
+ * lines; timings, tokens, status, config and tags stay
+ */
+export function withoutContent(log: RequestLog): RequestLog {
+  return {
+    ...log,
+    requestBody: undefined,
+    fullRequestBody: undefined,
+    responsePreview: undefined,
+    requestHeaders: undefined,
+    responseHeaders: undefined,
+    internalLogs: undefined,
+    contentOmitted: true,
+  };
+}
+
+/**
+ * Mask enabled PII kinds in the stored text fields of a log; scrubbedItems counts the replacements
+ */
+export function scrubRequestLog(log: RequestLog, config: LogScrubbingConfig): RequestLog {
+  const kinds = (Object.keys(PII_PATTERNS) as PiiKind[]).filter(kind => config[kind]);
+  if (kinds.length === 0) {
+    return log;
+  }
+
+  let total = 0;
+  const text = (value: string | undefined): string | undefined => {
+    if (!value) {
+      return value;
+    }
+    const result = scrubText(value, kinds);
+    total += result.count;
+    return result.text;
+  };
+
+  const requestHeaders = scrubHeaders(log.requestHeaders, kinds);
+  const responseHeaders = scrubHeaders(log.responseHeaders, kinds);
+  total += requestHeaders.count + responseHeaders.count;
+
+  const scrubbed: RequestLog = {
+    ...log,
+    requestBody: text(log.requestBody),
+    fullRequestBody: text(log.fullRequestBody),
+    responsePreview: text(log.responsePreview),
+    error: text(log.error),
+    requestHeaders: requestHeaders.headers,
+    responseHeaders: responseHeaders.headers,
+    internalLogs: log.internalLogs?.map(line => ({ ...line, message: text(line.message) ?? line.message })),
+  };
+
+  return total > 0 ? { ...scrubbed, scrubbedItems: total } : log;
+}
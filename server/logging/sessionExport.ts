@@ -0,0 +1,163 @@
+// Session export - a conversation's requests as HAR, a curl script or JSON lines, for reproducible bug reports
+
+import type { RequestLog } from './database';
+
+export type SessionExportFormat = 'har' | 'curl' | 'jsonl';
+
+export const SESSION_EXPORT_FORMATS: SessionExportFormat[] = ['har', 'curl', 'jsonl'];
+
+// Credentials never leave paf; curl scripts read the key from $API_KEY instead
+const CREDENTIAL_HEADERS = new Set([
+  'authorization',
+  'proxy-authorization',
+  'x-api-key',
+  'api-key',
+  'x-goog-api-key',
+  'cookie',
+]);
+
+// Set by the HTTP client on replay, or only meaningful on the original connection
+const TRANSPORT_HEADERS = new Set(['host', 'content-length', 'connection', 'accept-encoding', 'transfer-encoding', 'keep-alive']);
+
+function exportedHeaders(headers: Record<string, string> | undefined, placeholder: (name: string) => string): Array<[string, string]> {
+  return Object.entries(headers ?? {})
+    .filter(([name]) => !TRANSPORT_HEADERS.has(name.toLowerCase()))
+    .map(([name, value]) => {
+      if (!CREDENTIAL_HEADERS.has(name.toLowerCase())) {
+        return [name, value];
+      }
+      return [name, /^bearer /i.test(value) ? `Bearer ${placeholder(name)}` : placeholder(name)];
+    });
+}
+
+/**
+ * The body as sent upstream when it was captured (capture_request_bodies), else the stored preview
+ */
+function requestBodyOf(log: RequestLog): { text?: string; truncated: boolean } {
+  if (log.fullRequestBody !== undefined) {
+    return { text: log.fullRequestBody, truncated: false };
+  }
+  return { text: log.requestBody, truncated: log.requestBody !== undefined };
+}
+
+function requestUrl(log: RequestLog): string {
+  return log.targetUrl ?? `http://localhost${log.path}`;
+}
+
+export function toHar(logs: RequestLog[], creatorVersion: string): Record<string, unknown> {
+  return {
+    log: {
+      version: '1.2',
+      creator: { name: 'proxy-ai-fusion', version: creatorVersion },
+      entries: logs.map(log => {
+        const body = requestBodyOf(log);
+        const url = new URL(requestUrl(log));
+        const contentType = log.requestHeaders?.['content-type'] ?? 'application/json';
+        const wait = log.headersMs ?? log.duration ?? 0;
+        return {
+          startedDateTime: new Date(log.timestamp).toISOString(),
+          time: log.duration ?? 0,
+          request: {
+            method: log.method,
+            url: url.toString(),
+            httpVersion: 'HTTP/1.1',
+            headers: exportedHeaders(log.requestHeaders, () => '<redacted>').map(([name, value]) => ({ name, value })),
+            queryString: [...url.searchParams].map(([name, value]) => ({ name, value })),
+            cookies: [],
+            headersSize: -1,
+            bodySize: body.text !== undefined ? Buffer.byteLength(body.text) : 0,
+            ...(body.text !== undefined ? { postData: { mimeType: contentType, text: body.text } } : {}),
+          },
+          response: {
+            status: log.statusCode ?? 0,
+            statusText: log.error ?? '',
+            httpVersion: 'HTTP/1.1',
+            headers: Object.entries(log.responseHeaders ?? {}).map(([name, value]) => ({ name, value })),
+            cookies: [],
+            content: {
+              size: log.responseBytes ?? -1,
+              mimeType: log.responseHeaders?.['content-type'] ?? 'application/json',
+              text: log.responsePreview ?? '',
+              comment: 'Response preview only',
+            },
+            redirectURL: '',
+            headersSize: -1,
+            bodySize: log.responseBytes ?? -1,
+          },
+          cache: {},
+          timings: { send: 0, wait, receive: Math.max(0, (log.duration ?? 0) - wait) },
+          comment: [
+            `paf request ${log.id} via ${log.configName || 'no config'}`,
+            body.truncated ? 'request body truncated to the stored preview' : undefined,
+          ]
+            .filter(Boolean)
+            .join('; '),
+        };
+      }),
+    },
+  };
+}
+
+const shellQuote = (value: string) => `'${value.replace(/'/g, `'\\''`)}'`;
+
+/**
+ * A shell script with one curl call per request, in order; credentials come from $API_KEY
+ */
+export function toCurlScript(logs: RequestLog[], conversationId: string): string {
+  const lines = [
+    '#!/bin/sh',
+    `# Conversation ${conversationId}: ${logs.length} request(s) exported by proxy-ai-fusion`,
+    '# Set API_KEY before running; credentials were removed from the export.',
+    '',
+  ];
+
+  for (const log of logs) {
+    const body = requestBodyOf(log);
+    lines.push(
+      `# ${new Date(log.timestamp).toISOString()} ${log.id} -> ${log.statusCode ?? 'no response'} via ${log.configName || 'no config'}`
+    );
+    if (body.truncated) {
+      lines.push('# NOTE: request body truncated to the stored preview; enable capture_request_bodies for replayable exports');
+    }
+    const parts = [`curl -sS -X ${log.method} ${shellQuote(requestUrl(log))}`];
+    for (const [name, value] of exportedHeaders(log.requestHeaders, () => '$API_KEY')) {
+      // Double quotes keep $API_KEY expandable; everything else is quoted literally
+      parts.push(
+        value.includes('$API_KEY') ? `-H "${name}: ${value}"` : `-H ${shellQuote(`${name}: ${value}`)}`
+      );
+    }
+    if (body.text !== undefined) {
+      parts.push(`--data-binary ${shellQuote(body.text)}`);
+    }
+    lines.push(parts.join(' \\\n  '), '');
+  }
+
+  return lines.join('\n');
+}
+
+/**
+ * One JSON object per request with credentials removed, for scripted replays
+ */
+export function toJsonLines(logs: RequestLog[]): string {
+  return logs
+    .map(log => {
+      const body = requestBodyOf(log);
+      return JSON.stringify({
+        id: log.id,
+        timestamp: log.timestamp,
+        service: log.service,
+        config: log.configName,
+        method: log.method,
+        url: requestUrl(log),
+        headers: Object.fromEntries(exportedHeaders(log.requestHeaders, () => '<redacted>')),
+        body: body.text,
+        body_truncated: body.truncated,
+        status: log.statusCode,
+        duration_ms: log.duration,
+        response_preview: log.responsePreview,
+        error: log.error,
+      });
+    })
+    .map(line => `${line}\n`)
+    .join('');
+}
@@ -0,0 +1,83 @@
+// Request tracing - a span per proxied request; warnings and errors printed inside it are kept with its log entry
+
+import { AsyncLocalStorage } from 'async_hooks';
+import { format } from 'util';
+
+// Per request; a retry storm should not turn one log row into a second paf.log
+const MAX_INTERNAL_LOG_LINES = 50;
+const MAX_INTERNAL_LOG_CHARS = 2000;
+
+export interface InternalLogLine {
+  level: 'warn' | 'error';
+  message: string;
+  at: number;
+}
+
+export interface RequestSpan {
+  requestId: string;
+  service: string;
+  config?: string;  // Set once an upstream config is selected
+  startedAt: number;
+  logs: InternalLogLine[];
+  droppedLogs: number;
+}
+
+const spans = new AsyncLocalStorage<RequestSpan>();
+let captureInstalled = false;
+
+export function createSpan(service: string, requestId: string = crypto.randomUUID()): RequestSpan {
+  return { requestId, service, startedAt: Date.now(), logs: [], droppedLogs: 0 };
+}
+
+/**
+ * Run `fn` inside `span`; console.warn and console.error calls made from it (including awaited work
+ * and stream callbacks it starts) are recorded on the span as well as printed
+ */
+export function runInSpan<T>(span: RequestSpan, fn: () => T): T {
+  installConsoleCapture();
+  return spans.run(span, fn);
+}
+
+export function currentSpan(): RequestSpan | undefined {
+  return spans.getStore();
+}
+
+/**
+ * Lines captured so far for the log entry `requestId`, or undefined outside its span
+ */
+export function spanLogsFor(requestId: string): InternalLogLine[] | undefined {
+  const span = spans.getStore();
+  if (!span || span.requestId !== requestId || span.logs.length === 0) {
+    return undefined;
+  }
+  if (span.droppedLogs === 0) {
+    return [...span.logs];
+  }
+  return [
+    ...span.logs,
+    { level: 'warn', message: `${span.droppedLogs} more line(s) not kept`, at: Date.now() },
+  ];
+}
+
+function installConsoleCapture(): void {
+  if (captureInstalled) {
+    return;
+  }
+  captureInstalled = true;
+
+  for (const level of ['warn', 'error'] as const) {
+    const print = console[level].bind(console);
+    console[level] = (...args: unknown[]) => {
+      print(...args);
+      const span = spans.getStore();
+      if (!span) {
+        return;
+      }
+      if (span.logs.length >= MAX_INTERNAL_LOG_LINES) {
+        span.droppedLogs++;
+        return;
+      }
+      span.logs.push({ level, message: format(...args).slice(0, MAX_INTERNAL_LOG_CHARS), at: Date.now() });
+    };
+  }
+}
@@ -0,0 +1,242 @@
+// Log monitor - saved log views and alert rules evaluated against them on a timer
+
+import type { LogQuery, StatusClass } from '../logging/database';
+import { parseTagFilter } from '../proxy/tags';
+
+export interface LogView {
+  id: string;
+  name: string;
+  query: LogQuery;
+  createdAt: number;
+}
+
+export interface AlertRule {
+  id: string;
+  name: string;
+  viewId: string;
+  threshold: number;       // Fires when at least this many logs match within the window
+  windowMinutes: number;
+  cooldownMinutes: number; // Minimum gap between two notifications for the rule
+  notifyUrl?: string;      // Receives a JSON POST; the `text` field suits Slack-style incoming webhooks
+  enabled: boolean;
+  lastFiredAt?: number;
+  createdAt: number;
+}
+
+export interface MonitoringStore {
+  saveLogView(view: LogView): void;
+  listLogViews(): LogView[];
+  deleteLogView(id: string): boolean;
+  saveAlertRule(rule: AlertRule): void;
+  listAlertRules(): AlertRule[];
+  deleteAlertRule(id: string): boolean;
+  countLogs(query: LogQuery): number;
+}
+
+export interface FiredAlert {
+  rule: AlertRule;
+  view: LogView;
+  count: number;
+  firedAt: number;
+}
+
+export type CreateAlertInput = Pick<AlertRule, 'name' | 'viewId' | 'threshold' | 'windowMinutes'> &
+  Partial<Pick<AlertRule, 'cooldownMinutes' | 'notifyUrl' | 'enabled'>>;
+
+const NOTIFY_TIMEOUT_MS = 10_000;
+const STATUS_CLASSES: ReadonlyArray<StatusClass> = ['success', 'error', '4xx', '5xx'];
+
+/**
+ * Build a log query from query-string style fields: service, config, model, status, tag, window_minutes
+ */
+export function parseLogQuery(input: URLSearchParams | Record<string, unknown>): LogQuery {
+  const fields: Record<string, unknown> = input instanceof URLSearchParams ? Object.fromEntries(input) : input;
+  const text = (key: string) => (typeof fields[key] === 'string' && fields[key] ? (fields[key] as string) : undefined);
+  const status = text('status');
+  const tag = parseTagFilter(text('tag') ?? null);
+  const windowMinutes = Number(fields.window_minutes);
+
+  return {
+    service: text('service'),
+    configName: text('config'),
+    model: text('model'),
+    status: STATUS_CLASSES.includes(status as StatusClass) ? (status as StatusClass) : undefined,
+    tag: tag ?? undefined,
+    windowMinutes: windowMinutes > 0 ? windowMinutes : undefined,
+  };
+}
+
+export class LogMonitor {
+  private store: MonitoringStore;
+  private views: LogView[];
+  private rules: AlertRule[];
+
+  constructor(store: MonitoringStore) {
+    this.store = store;
+    this.views = store.listLogViews();
+    this.rules = store.listAlertRules();
+  }
+
+  listViews(): LogView[] {
+    return [...this.views];
+  }
+
+  getView(id: string): LogView | undefined {
+    return this.views.find(v => v.id === id);
+  }
+
+  createView(name: string, query: LogQuery): LogView {
+    const view: LogView = { id: crypto.randomUUID(), name, query, createdAt: Date.now() };
+    this.store.saveLogView(view);
+    this.views.push(view);
+    return view;
+  }
+
+  /**
+   * Delete a view together with the alert rules built on it
+   */
+  deleteView(id: string): boolean {
+    for (const rule of this.rules.filter(r => r.viewId === id)) {
+      this.deleteRule(rule.id);
+    }
+    this.views = this.views.filter(v => v.id !== id);
+    return this.store.deleteLogView(id);
+  }
+
+  listRules(): AlertRule[] {
+    return [...this.rules];
+  }
+
+  getRule(id: string): AlertRule | undefined {
+    return this.rules.find(r => r.id === id);
+  }
+
+  createRule(input: CreateAlertInput): AlertRule | { error: string } {
+    const rule: AlertRule = {
+      id: crypto.randomUUID(),
+      name: input.name,
+      viewId: input.viewId,
+      threshold: input.threshold,
+      windowMinutes: input.windowMinutes,
+      cooldownMinutes: input.cooldownMinutes ?? input.windowMinutes,
+      notifyUrl: input.notifyUrl,
+      enabled: input.enabled ?? true,
+      createdAt: Date.now(),
+    };
+    const error = this.validate(rule);
+    if (error) {
+      return { error };
+    }
+
+    this.store.saveAlertRule(rule);
+    this.rules.push(rule);
+    return rule;
+  }
+
+  updateRule(
+    id: string,
+    changes: Partial<Omit<AlertRule, 'id' | 'viewId' | 'createdAt'>>
+  ): AlertRule | { error: string } | undefined {
+    const index = this.rules.findIndex(r => r.id === id);
+    if (index === -1) {
+      return undefined;
+    }
+
+    const rule = { ...this.rules[index], ...changes };
+    const error = this.validate(rule);
+    if (error) {
+      return { error };
+    }
+
+    this.store.saveAlertRule(rule);
+    this.rules[index] = rule;
+    return rule;
+  }
+
+  deleteRule(id: string): boolean {
+    this.rules = this.rules.filter(r => r.id !== id);
+    return this.store.deleteAlertRule(id);
+  }
+
+  /**
+   * Count matches for every enabled rule outside its cooldown; returns the rules that crossed their threshold
+   */
+  evaluate(now = Date.now()): FiredAlert[] {
+    const fired: FiredAlert[] = [];
+
+    for (const rule of this.rules) {
+      const view = this.getView(rule.viewId);
+      if (!rule.enabled || !view) {
+        continue;
+      }
+      if (rule.lastFiredAt && now - rule.lastFiredAt < rule.cooldownMinutes * 60_000) {
+        continue;
+      }
+
+      // The rule's window replaces any time range saved with the view
+      const count = this.store.countLogs({
+        ...view.query,
+        windowMinutes: undefined,
+        since: now - rule.windowMinutes * 60_000,
+      });
+      if (count < rule.threshold) {
+        continue;
+      }
+
+      rule.lastFiredAt = now;
+      this.store.saveAlertRule(rule);
+      fired.push({ rule, view, count, firedAt: now });
+    }
+
+    return fired;
+  }
+
+  private validate(rule: AlertRule): string | null {
+    if (!rule.name) {
+      return 'name is required';
+    }
+    if (!this.getView(rule.viewId)) {
+      return `Unknown log view ${rule.viewId}`;
+    }
+    if (!(Number.isInteger(rule.threshold) && rule.threshold > 0)) {
+      return 'threshold must be a positive integer';
+    }
+    if (!(rule.windowMinutes > 0) || !(rule.cooldownMinutes >= 0)) {
+      return 'window_minutes must be positive and cooldown_minutes non-negative';
+    }
+    if (rule.notifyUrl && !/^https?:\/\//.test(rule.notifyUrl)) {
+      return 'notify_url must be an http(s) URL';
+    }
+    return null;
+  }
+}
+
+/**
+ * POST a fired alert to the rule's notify URL; failures are logged, not retried
+ */
+export async function deliverAlert(alert: FiredAlert): Promise<void> {
+  const { rule, view, count, firedAt } = alert;
+  if (!rule.notifyUrl) {
+    return;
+  }
+
+  try {
+    const response = await fetch(rule.notifyUrl, {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({
+        text: `[paf] ${rule.name}: ${count} request(s) matched "${view.name}" in the last ${rule.windowMinutes} min (threshold ${rule.threshold})`,
+        alert: { id: rule.id, name: rule.name, threshold: rule.threshold, window_minutes: rule.windowMinutes },
+        view: { id: view.id, name: view.name },
+        count,
+        fired_at: firedAt,
+      }),
+      signal: AbortSignal.timeout(NOTIFY_TIMEOUT_MS),
+    });
+    if (!response.ok) {
+      throw new Error(`HTTP ${response.status}`);
+    }
+  } catch (error) {
+    console.warn(`[alerts] failed to notify ${rule.notifyUrl} for "${rule.name}":`, error instanceof Error ? error.message : error);
+  }
+}
@@ -0,0 +1,215 @@
+// Certificate monitor - records the TLS certificate each upstream host presents and flags expiries and changes
+
+import { connect } from 'node:tls';
+
+export interface CertificateRecord {
+  host: string;                 // host:port
+  fingerprint: string;          // SHA-256, colon separated
+  subject: string;
+  issuer: string;
+  validTo: number;              // Epoch ms
+  checkedAt: number;
+  previousFingerprint?: string; // Set when the fingerprint differed from the check before
+  changedAt?: number;
+  error?: string;               // The last check failed; the other fields describe the last certificate seen
+}
+
+export type CertificateWarningKind = 'expiring' | 'expired' | 'changed';
+
+export interface CertificateWarning {
+  kind: CertificateWarningKind;
+  host: string;
+  message: string;
+  record: CertificateRecord;
+}
+
+export interface CertificateStore {
+  saveCertificate(record: CertificateRecord): void;
+  listCertificates(): CertificateRecord[];
+}
+
+const CONNECT_TIMEOUT_MS = 10_000;
+const NOTIFY_TIMEOUT_MS = 10_000;
+const DAY_MS = 24 * 60 * 60 * 1000;
+// A certificate change stays in the status this long; repeat notifications for the same issue wait as long
+const CHANGE_VISIBLE_MS = 7 * DAY_MS;
+const RENOTIFY_MS = DAY_MS;
+
+/**
+ * Distinct host:port pairs of the https base URLs; plain http upstreams have nothing to inspect
+ */
+export function upstreamTlsHosts(baseUrls: string[]): string[] {
+  const hosts = new Set<string>();
+  for (const baseUrl of baseUrls) {
+    try {
+      const url = new URL(baseUrl);
+      if (url.protocol === 'https:') {
+        hosts.add(`${url.hostname}:${url.port || '443'}`);
+      }
+    } catch {
+      // Invalid URLs are reported by config validation, not here
+    }
+  }
+  return [...hosts];
+}
+
+/**
+ * Complete a TLS handshake with `host` and read its leaf certificate. Verification is off so
+ * expired or otherwise invalid certificates can still be reported.
+ */
+export function fetchPeerCertificate(
+  host: string
+): Promise<Pick<CertificateRecord, 'fingerprint' | 'subject' | 'issuer' | 'validTo'>> {
+  const separator = host.lastIndexOf(':');
+  const hostname = host.slice(0, separator);
+  const port = Number(host.slice(separator + 1));
+
+  return new Promise((resolve, reject) => {
+    const socket = connect({ host: hostname, port, servername: hostname, rejectUnauthorized: false });
+    const timer = setTimeout(() => {
+      socket.destroy();
+      reject(new Error(`TLS handshake timed out after ${CONNECT_TIMEOUT_MS} ms`));
+    }, CONNECT_TIMEOUT_MS);
+
+    socket.once('secureConnect', () => {
+      clearTimeout(timer);
+      const cert = socket.getPeerCertificate();
+      socket.end();
+      if (!cert || !cert.fingerprint256) {
+        reject(new Error('No certificate presented'));
+        return;
+      }
+      resolve({
+        fingerprint: cert.fingerprint256,
+        subject: cert.subject?.CN ?? '',
+        issuer: cert.issuer?.O ?? cert.issuer?.CN ?? '',
+        validTo: Date.parse(cert.valid_to),
+      });
+    });
+    socket.once('error', error => {
+      clearTimeout(timer);
+      reject(error);
+    });
+  });
+}
+
+export class CertificateMonitor {
+  private store: CertificateStore;
+  private expiryWarningDays: number;
+  private notifiedAt = new Map<string, number>();
+
+  constructor(store: CertificateStore, expiryWarningDays: number) {
+    this.store = store;
+    this.expiryWarningDays = expiryWarningDays;
+  }
+
+  /**
+   * Inspect every host and store what it presents; returns warnings not already reported in the last day
+   */
+  async check(hosts: string[]): Promise<CertificateWarning[]> {
+    const known = new Map(this.store.listCertificates().map(record => [record.host, record]));
+    const now = Date.now();
+    const fresh: CertificateWarning[] = [];
+
+    for (const host of hosts) {
+      const previous = known.get(host);
+      let record: CertificateRecord;
+      try {
+        const cert = await fetchPeerCertificate(host);
+        const changed = previous !== undefined && !previous.error && previous.fingerprint !== cert.fingerprint;
+        record = {
+          host,
+          ...cert,
+          checkedAt: now,
+          previousFingerprint: changed ? previous.fingerprint : previous?.previousFingerprint,
+          changedAt: changed ? now : previous?.changedAt,
+        };
+      } catch (error) {
+        const message = error instanceof Error ? error.message : String(error);
+        if (!previous) {
+          console.warn(`[tls] ${host}: ${message}`);
+          continue;
+        }
+        record = { ...previous, checkedAt: now, error: message };
+      }
+      this.store.saveCertificate(record);
+
+      for (const warning of this.warningsFor(record, now)) {
+        const key = `${warning.host}:${warning.kind}:${warning.record.fingerprint}`;
+        if (now - (this.notifiedAt.get(key) ?? 0) >= RENOTIFY_MS) {
+          this.notifiedAt.set(key, now);
+          fresh.push(warning);
+        }
+      }
+    }
+    return fresh;
+  }
+
+  /**
+   * Everything currently worth surfacing: upcoming or past expiries and changes within the last week
+   */
+  warnings(now = Date.now()): CertificateWarning[] {
+    return this.store.listCertificates().flatMap(record => this.warningsFor(record, now));
+  }
+
+  list(): CertificateRecord[] {
+    return this.store.listCertificates();
+  }
+
+  private warningsFor(record: CertificateRecord, now: number): CertificateWarning[] {
+    const warnings: CertificateWarning[] = [];
+    const daysLeft = Math.floor((record.validTo - now) / DAY_MS);
+
+    if (record.validTo <= now) {
+      warnings.push({
+        kind: 'expired',
+        host: record.host,
+        message: `${record.host} certificate expired on ${new Date(record.validTo).toISOString()}`,
+        record,
+      });
+    } else if (daysLeft < this.expiryWarningDays) {
+      warnings.push({
+        kind: 'expiring',
+        host: record.host,
+        message: `${record.host} certificate expires in ${daysLeft} day(s) (${new Date(record.validTo).toISOString()})`,
+        record,
+      });
+    }
+
+    if (record.changedAt !== undefined && now - record.changedAt < CHANGE_VISIBLE_MS) {
+      warnings.push({
+        kind: 'changed',
+        host: record.host,
+        message: `${record.host} presented a different certificate (issuer ${record.issuer || 'unknown'}); expected on renewal or provider migration, otherwise check for interception`,
+        record,
+      });
+    }
+    return warnings;
+  }
+}
+
+/**
+ * POST a certificate warning; the `text` field suits Slack-style incoming webhooks
+ */
+export async function deliverCertificateWarning(notifyUrl: string, warning: CertificateWarning): Promise<void> {
+  try {
+    const response = await fetch(notifyUrl, {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({
+        text: `[paf] TLS: ${warning.message}`,
+        kind: warning.kind,
+        host: warning.host,
+        fingerprint: warning.record.fingerprint,
+        previous_fingerprint: warning.record.previousFingerprint,
+        valid_to: warning.record.validTo,
+      }),
+      signal: AbortSignal.timeout(NOTIFY_TIMEOUT_MS),
+    });
+    if (!response.ok) {
+      throw new Error(`HTTP ${response.status}`);
+    }
+  } catch (error) {
+    console.warn(`[tls] failed to notify ${notifyUrl}:`, error instanceof Error ? error.message : error);
+  }
+}
@@ -0,0 +1,65 @@
+// Crash reporter - records uncaught exceptions and unhandled rejections so a daemonized crash leaves a trace
+
+import type { RequestLogger } from '../logging/logger';
+import { currentSpan } from '../logging/tracing';
+
+// uncaught_exception ends the process; unhandled_rejection is logged and the server keeps running
+export type CrashKind = 'uncaught_exception' | 'unhandled_rejection';
+
+export interface CrashRecord {
+  id?: number;
+  timestamp: number;
+  kind: CrashKind;
+  message: string;
+  stack?: string;
+  context?: string; // The request being handled when it happened, e.g. "claude request <id> via <config>"
+  fatal: boolean;
+}
+
+let installed = false;
+
+function describeError(error: unknown): { message: string; stack?: string } {
+  if (error instanceof Error) {
+    return { message: `${error.name}: ${error.message}`, stack: error.stack };
+  }
+  return { message: typeof error === 'string' ? error : JSON.stringify(error) ?? String(error) };
+}
+
+function spanContext(): string | undefined {
+  const span = currentSpan();
+  if (!span) {
+    return undefined;
+  }
+  return `${span.service} request ${span.requestId}${span.config ? ` via ${span.config}` : ''}`;
+}
+
+/**
+ * Store crashes in the request log database; call once, after the logger is open
+ */
+export function installCrashReporter(logger: RequestLogger): void {
+  if (installed) {
+    return;
+  }
+  installed = true;
+
+  const record = (kind: CrashKind, error: unknown, fatal: boolean) => {
+    const crash: CrashRecord = { timestamp: Date.now(), kind, fatal, context: spanContext(), ...describeError(error) };
+    try {
+      logger.recordCrash(crash);
+    } catch (storeError) {
+      console.error('[crash] failed to store crash report:', storeError);
+    }
+    return crash;
+  };
+
+  process.on('uncaughtException', error => {
+    const crash = record('uncaught_exception', error, true);
+    console.error(`[crash] uncaught exception${crash.context ? ` in ${crash.context}` : ''}:`, error);
+    process.exit(1);
+  });
+
+  process.on('unhandledRejection', reason => {
+    const crash = record('unhandled_rejection', reason, false);
+    console.error(`[crash] unhandled rejection${crash.context ? ` in ${crash.context}` : ''}:`, reason);
+  });
+}
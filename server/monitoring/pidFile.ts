@@ -0,0 +1,280 @@
+// PID file - lets a new start tell a server that is still running from the half-dead leftovers of one
+// (a parent that died while children kept the ports open) and clear the latter out of the way
+
+import { existsSync, readFileSync, rmSync, writeFileSync } from 'node:fs';
+import { uptime } from 'node:os';
+
+interface PidRecord {
+  pid: number;
+  pgid?: number; // Process group of the server; undefined where `ps` is unavailable (Windows)
+  command?: string; // The server's command line, to recognise its group once the leader is gone
+  startedAt: number;
+}
+
+export type StaleInstanceResult =
+  | { state: 'none' }
+  | { state: 'running'; pid: number }                  // The recorded server is alive and answering
+  | { state: 'cleaned'; pid: number; killed: boolean }; // PID file removed; `killed` when leftovers were terminated
+
+export type StopResult =
+  | { state: 'not_running' }
+  | { state: 'stopped'; pid: number; group: boolean }; // `group` when the whole process group was signalled
+
+// How long terminated leftovers get to exit before they are killed outright
+const TERMINATE_GRACE_MS = 5000;
+
+// How long a stopped server's ports may stay bound while the kernel tears the sockets down
+const PORT_RELEASE_TIMEOUT_MS = 5000;
+
+function errorCode(error: unknown): string | undefined {
+  return (error as NodeJS.ErrnoException)?.code;
+}
+
+// EPERM means the process exists but belongs to another user
+function signalReaches(target: number): boolean {
+  try {
+    process.kill(target, 0);
+    return true;
+  } catch (error) {
+    return errorCode(error) === 'EPERM';
+  }
+}
+
+function processGroupOf(pid: number): number | undefined {
+  if (process.platform === 'win32') {
+    return undefined;
+  }
+  try {
+    const result = Bun.spawnSync(['ps', '-o', 'pgid=', '-p', String(pid)]);
+    const pgid = Number(result.stdout.toString().trim());
+    return result.exitCode === 0 && pgid > 0 ? pgid : undefined;
+  } catch {
+    return undefined;
+  }
+}
+
+function commandOf(pid: number): string | undefined {
+  if (process.platform === 'win32') {
+    return undefined;
+  }
+  try {
+    const result = Bun.spawnSync(['ps', '-o', 'args=', '-p', String(pid)]);
+    const command = result.stdout.toString().trim();
+    return result.exitCode === 0 && command ? command : undefined;
+  } catch {
+    return undefined;
+  }
+}
+
+/**
+ * Command lines of the processes left in a group; `ps -A` rather than `-g`, whose meaning differs between
+ * Linux and BSD. Null when `ps` can't tell.
+ */
+function groupCommands(pgid: number): string[] | null {
+  try {
+    const result = Bun.spawnSync(['ps', '-A', '-o', 'pgid=,args=']);
+    if (result.exitCode !== 0) {
+      return null;
+    }
+    const commands: string[] = [];
+    for (const line of result.stdout.toString().split('\n')) {
+      const match = line.trim().match(/^(\d+)\s+(.*)$/);
+      if (match && Number(match[1]) === pgid) {
+        commands.push(match[2]);
+      }
+    }
+    return commands;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Whether signalling the recorded process group can only hit paf. paf never starts a group of its own, so
+ * the group is only paf's when whatever launched the server made it the leader (a service manager, setsid);
+ * one started from a shell or a supervisor shares its parent's group, which is never signalled. Then:
+ * always while the leader is alive in it, otherwise only when every process left in the group runs the
+ * recorded server command. A stale file's group id may since have been handed to an unrelated group.
+ */
+function groupIsOurs(record: PidRecord, leaderAlive: boolean): boolean {
+  if (
+    record.pgid === undefined ||
+    record.pgid !== record.pid ||
+    record.pgid === processGroupOf(process.pid) ||
+    !signalReaches(-record.pgid)
+  ) {
+    return false;
+  }
+  if (leaderAlive) {
+    return true;
+  }
+  if (!record.command) {
+    return false;
+  }
+  const commands = groupCommands(record.pgid);
+  return commands !== null && commands.length > 0 && commands.every(command => command === record.command);
+}
+
+function readRecord(path: string): PidRecord | null {
+  if (!existsSync(path)) {
+    return null;
+  }
+  try {
+    const record = JSON.parse(readFileSync(path, 'utf8'));
+    return typeof record?.pid === 'number' ? record : null;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * SIGTERM `target` (a pid, or -pgid for a whole group), then SIGKILL whatever is left after the grace period
+ */
+async function terminate(target: number): Promise<void> {
+  try {
+    process.kill(target, 'SIGTERM');
+  } catch (error) {
+    if (errorCode(error) === 'ESRCH') {
+      return;
+    }
+    throw error;
+  }
+  const deadline = Date.now() + TERMINATE_GRACE_MS;
+  while (Date.now() < deadline) {
+    if (!signalReaches(target)) {
+      return;
+    }
+    await Bun.sleep(100);
+  }
+  try {
+    process.kill(target, 'SIGKILL');
+  } catch (error) {
+    if (errorCode(error) !== 'ESRCH') {
+      throw error;
+    }
+  }
+}
+
+/**
+ * Record this process as the running server; the file is removed again on a clean exit
+ */
+export function writePidFile(path: string): void {
+  const record: PidRecord = {
+    pid: process.pid,
+    pgid: processGroupOf(process.pid),
+    command: commandOf(process.pid),
+    startedAt: Date.now(),
+  };
+  writeFileSync(path, JSON.stringify(record));
+  process.on('exit', () => {
+    if (readRecord(path)?.pid === process.pid) {
+      rmSync(path, { force: true });
+    }
+  });
+  // Signals end the process without an exit event; route Ctrl+C and service stops through one
+  process.once('SIGINT', () => process.exit(130));
+  process.once('SIGTERM', () => process.exit(143));
+}
+
+/**
+ * Deal with the PID file a previous server left behind. A server that still answers `statusUrl` is left
+ * alone; otherwise its process group (when it led one, see groupIsOurs) or just the process is terminated
+ * and the file removed.
+ */
+export async function recoverStaleInstance(path: string, statusUrl: string): Promise<StaleInstanceResult> {
+  const record = readRecord(path);
+  if (!record || record.pid === process.pid) {
+    return { state: 'none' };
+  }
+
+  // Written before the last boot: nothing of that server survived, and its ids may belong to anything now
+  if (record.startedAt < Date.now() - uptime() * 1000) {
+    rmSync(path, { force: true });
+    return { state: 'cleaned', pid: record.pid, killed: false };
+  }
+
+  // A reused PID belongs to an unrelated program; it only counts while still in the recorded group
+  const leaderAlive =
+    signalReaches(record.pid) && (record.pgid === undefined || processGroupOf(record.pid) === record.pgid);
+
+  if (leaderAlive) {
+    const answering = await fetch(statusUrl, { signal: AbortSignal.timeout(2000) }).then(
+      response => response.ok,
+      () => false
+    );
+    if (answering) {
+      return { state: 'running', pid: record.pid };
+    }
+  }
+
+  // The group outlives its leader, so orphaned children still holding the ports are found through it,
+  // as long as groupIsOurs can vouch for them; a group the server merely joined, like our own, never is
+  let killed = false;
+  if (groupIsOurs(record, leaderAlive)) {
+    await terminate(-record.pgid);
+    killed = true;
+  } else if (leaderAlive) {
+    await terminate(record.pid);
+    killed = true;
+  }
+
+  rmSync(path, { force: true });
+  return { state: 'cleaned', pid: record.pid, killed };
+}
+
+/**
+ * Stop the server recorded in the PID file: when it leads its process group the whole group is terminated,
+ * so helpers and subprocesses it spawned go down with it. Otherwise only the process is signalled.
+ */
+export async function stopInstance(path: string): Promise<StopResult> {
+  const record = readRecord(path);
+  if (!record || record.pid === process.pid) {
+    return { state: 'not_running' };
+  }
+
+  // Same reasoning as recoverStaleInstance: ids from before the last boot or outside the group are not ours
+  if (record.startedAt < Date.now() - uptime() * 1000) {
+    rmSync(path, { force: true });
+    return { state: 'not_running' };
+  }
+  const leaderAlive =
+    signalReaches(record.pid) && (record.pgid === undefined || processGroupOf(record.pid) === record.pgid);
+
+  let group = false;
+  if (groupIsOurs(record, leaderAlive)) {
+    await terminate(-record.pgid);
+    group = true;
+  } else if (leaderAlive) {
+    await terminate(record.pid);
+  } else {
+    rmSync(path, { force: true });
+    return { state: 'not_running' };
+  }
+
+  // A clean exit removes the file itself; a SIGKILLed server cannot
+  rmSync(path, { force: true });
+  return { state: 'stopped', pid: record.pid, group };
+}
+
+function portInUse(port: number): boolean {
+  try {
+    const listener = Bun.listen({ hostname: '0.0.0.0', port, socket: { data() {} } });
+    listener.stop(true);
+    return false;
+  } catch {
+    return true;
+  }
+}
+
+/**
+ * Wait until every port can be bound again; returns the ports still in use when the timeout passes
+ */
+export async function waitForPortsReleased(ports: number[], timeoutMs = PORT_RELEASE_TIMEOUT_MS): Promise<number[]> {
+  const deadline = Date.now() + timeoutMs;
+  let busy = ports.filter(portInUse);
+  while (busy.length > 0 && Date.now() < deadline) {
+    await Bun.sleep(100);
+    busy = busy.filter(portInUse);
+  }
+  return busy;
+}
@@ -0,0 +1,204 @@
+// Quota monitor - polls provider balance/credit endpoints per config and flags configs running low
+
+import type { ProxyConfig, QuotaConfig, QuotaProvider } from '../config/types';
+
+export const QUOTA_PROVIDERS: ReadonlyArray<QuotaProvider> = ['openai-billing', 'openrouter', 'deepseek', 'custom'];
+
+export interface QuotaSnapshot {
+  service: string;
+  configName: string;
+  provider: QuotaProvider;
+  remaining: number | null; // null when the provider reports no limit
+  total?: number;
+  used?: number;
+  unit: string;             // Currency or credit unit as reported, e.g. USD, CNY, credits
+  low: boolean;             // remaining below the config's warn_below
+  checkedAt: number;
+  error?: string;           // Last poll failed; the figures are from the poll before
+}
+
+type QuotaReading = Pick<QuotaSnapshot, 'remaining' | 'total' | 'used' | 'unit'>;
+
+const FETCH_TIMEOUT_MS = 10_000;
+
+/**
+ * Read the per-config `quota` table; `custom` needs a url and a balance_path
+ */
+export function parseQuotaConfig(data: any): QuotaConfig | undefined {
+  if (!data || typeof data !== 'object' || !QUOTA_PROVIDERS.includes(data.provider)) {
+    return undefined;
+  }
+  const url = typeof data.url === 'string' && data.url ? data.url : undefined;
+  const balancePath = typeof data.balance_path === 'string' && data.balance_path ? data.balance_path : undefined;
+  if (data.provider === 'custom' && (!url || !balancePath)) {
+    return undefined;
+  }
+  return {
+    provider: data.provider,
+    url,
+    balancePath,
+    unit: typeof data.unit === 'string' && data.unit ? data.unit : undefined,
+    warnBelow: typeof data.warn_below === 'number' ? data.warn_below : undefined,
+  };
+}
+
+/**
+ * TOML/API shape of a quota setting, the inverse of parseQuotaConfig
+ */
+export function serializeQuotaConfig(quota: QuotaConfig | undefined): Record<string, unknown> | undefined {
+  if (!quota) {
+    return undefined;
+  }
+  return {
+    provider: quota.provider,
+    url: quota.url,
+    balance_path: quota.balancePath,
+    unit: quota.unit,
+    warn_below: quota.warnBelow,
+  };
+}
+
+function readPath(data: any, path: string): unknown {
+  return path.split('.').reduce((value: any, key) => (value == null ? undefined : value[key]), data);
+}
+
+function toNumber(value: unknown): number | undefined {
+  const number = typeof value === 'string' ? Number(value) : value;
+  return typeof number === 'number' && Number.isFinite(number) ? number : undefined;
+}
+
+async function getJson(url: string, config: ProxyConfig, proxy?: string): Promise<any> {
+  const token = config.apiKey || config.authToken;
+  const response = await fetch(url, {
+    headers: token ? { authorization: `Bearer ${token}` } : {},
+    signal: AbortSignal.timeout(FETCH_TIMEOUT_MS),
+    proxy,
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status} from ${new URL(url).pathname}`);
+  }
+  return response.json();
+}
+
+/**
+ * Ask the provider for the config's balance, through `proxy` when set. `openai-billing` is the dashboard
+ * billing API that one-api/new-api style relays still serve; the others are the providers' own balance endpoints.
+ */
+export async function fetchQuota(config: ProxyConfig, quota: QuotaConfig, proxy?: string): Promise<QuotaReading> {
+  const base = config.baseUrl.replace(/\/+$/, '').replace(/\/v1$/, '');
+
+  switch (quota.provider) {
+    case 'openai-billing': {
+      const subscription = await getJson(`${base}/v1/dashboard/billing/subscription`, config, proxy);
+      const today = new Date();
+      const start = new Date(today.getTime() - 99 * 24 * 60 * 60 * 1000).toISOString().slice(0, 10);
+      const end = new Date(today.getTime() + 24 * 60 * 60 * 1000).toISOString().slice(0, 10);
+      const usage = await getJson(`${base}/v1/dashboard/billing/usage?start_date=${start}&end_date=${end}`, config, proxy);
+      const total = toNumber(subscription?.hard_limit_usd);
+      const used = (toNumber(usage?.total_usage) ?? 0) / 100; // Reported in cents
+      return { remaining: total === undefined ? null : total - used, total, used, unit: quota.unit ?? 'USD' };
+    }
+    case 'openrouter': {
+      const key = await getJson(quota.url ?? 'https://openrouter.ai/api/v1/key', config, proxy);
+      const limit = toNumber(key?.data?.limit);
+      const used = toNumber(key?.data?.usage);
+      const remaining = toNumber(key?.data?.limit_remaining);
+      return { remaining: remaining ?? null, total: limit, used, unit: quota.unit ?? 'credits' };
+    }
+    case 'deepseek': {
+      const balance = await getJson(quota.url ?? `${base}/user/balance`, config, proxy);
+      const info = Array.isArray(balance?.balance_infos) ? balance.balance_infos[0] : undefined;
+      const remaining = toNumber(info?.total_balance);
+      if (remaining === undefined) {
+        throw new Error('No balance in response');
+      }
+      return { remaining, unit: quota.unit ?? info?.currency ?? 'CNY' };
+    }
+    case 'custom': {
+      const url = quota.url!.startsWith('/') ? `${base}${quota.url}` : quota.url!;
+      const remaining = toNumber(readPath(await getJson(url, config, proxy), quota.balancePath!));
+      if (remaining === undefined) {
+        throw new Error(`No number at ${quota.balancePath}`);
+      }
+      return { remaining, unit: quota.unit ?? '' };
+    }
+  }
+}
+
+export class QuotaMonitor {
+  private snapshots = new Map<string, QuotaSnapshot>();
+  private revision = 0; // Bumped by every poll that touched a snapshot
+  private changedAt = Date.now();
+
+  /**
+   * Poll every enabled config with a quota setting; returns snapshots that just turned low. Requests go
+   * through each config's proxy_url, or `serviceProxyUrl` for configs without one.
+   */
+  async poll(service: string, configs: ProxyConfig[], serviceProxyUrl?: string): Promise<QuotaSnapshot[]> {
+    const turnedLow: QuotaSnapshot[] = [];
+    const names = new Set<string>();
+
+    for (const config of configs) {
+      if (!config.quota || config.enabled === false) {
+        continue;
+      }
+      names.add(config.name);
+      const key = `${service}:${config.name}`;
+      const previous = this.snapshots.get(key);
+      const checkedAt = Date.now();
+
+      try {
+        const reading = await fetchQuota(config, config.quota, config.proxyUrl ?? serviceProxyUrl);
+        const warnBelow = config.quota.warnBelow;
+        const snapshot: QuotaSnapshot = {
+          service,
+          configName: config.name,
+          provider: config.quota.provider,
+          ...reading,
+          low: warnBelow !== undefined && reading.remaining !== null && reading.remaining < warnBelow,
+          checkedAt,
+        };
+        this.snapshots.set(key, snapshot);
+        if (snapshot.low && !previous?.low) {
+          turnedLow.push(snapshot);
+        }
+      } catch (error) {
+        const message = error instanceof Error ? error.message : String(error);
+        this.snapshots.set(key, {
+          ...(previous ?? { service, configName: config.name, provider: config.quota.provider, remaining: null, unit: '', low: false }),
+          checkedAt,
+          error: message,
+        });
+      }
+    }
+
+    // Forget configs that were removed or lost their quota setting
+    let forgotten = false;
+    for (const [key, snapshot] of this.snapshots) {
+      if (snapshot.service === service && !names.has(snapshot.configName)) {
+        this.snapshots.delete(key);
+        forgotten = true;
+      }
+    }
+    if (names.size > 0 || forgotten) {
+      this.revision++;
+      this.changedAt = Date.now();
+    }
+    return turnedLow;
+  }
+
+  /**
+   * Snapshot changes, for conditional GETs on /api/configs
+   */
+  getRevision(): { revision: number; changedAt: number } {
+    return { revision: this.revision, changedAt: this.changedAt };
+  }
+
+  get(service: string, configName: string): QuotaSnapshot | undefined {
+    return this.snapshots.get(`${service}:${configName}`);
+  }
+
+  list(service?: string): QuotaSnapshot[] {
+    return [...this.snapshots.values()].filter(snapshot => !service || snapshot.service === service);
+  }
+}
@@ -0,0 +1,176 @@
+// Prompt library - named templates expanded into requests before they are forwarded
+//
+// A request can reference a template in two places:
+//   { "model": "...", "paf_template": "reviewer", "vars": { "lang": "Go" }, "messages": [...] }
+//     -> the rendered text becomes (or is prepended to) the request's system prompt
+//   { "role": "user", "content": { "paf_template": "bug-report", "vars": { ... } } }
+//     -> any nested object with a paf_template key is replaced by the rendered text
+
+export interface PromptTemplate {
+  name: string;
+  description?: string;
+  content: string;     // Text with {{variable}} placeholders
+  variables: string[]; // Placeholder names found in content, in order of first use
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface PromptStore {
+  savePromptTemplate(template: PromptTemplate): void;
+  listPromptTemplates(): PromptTemplate[];
+  deletePromptTemplate(name: string): boolean;
+}
+
+export interface SavePromptInput {
+  name: string;
+  content: string;
+  description?: string;
+}
+
+export type PromptExpansion =
+  | {
+      body: any;
+      systemPrompt?: string; // Rendered top-level template; the proxy service decides where it goes
+      templates: string[];   // Names of every template used, for logging
+    }
+  | { error: string };
+
+const TEMPLATE_KEY = 'paf_template';
+const VARS_KEY = 'vars';
+const NAME_PATTERN = /^[A-Za-z0-9._-]+$/;
+const PLACEHOLDER = /\{\{\s*([A-Za-z_][\w.-]*)\s*\}\}/g;
+
+export function extractVariables(content: string): string[] {
+  return [...new Set([...content.matchAll(PLACEHOLDER)].map(match => match[1]))];
+}
+
+export class PromptLibrary {
+  private store: PromptStore;
+  private templates: Map<string, PromptTemplate>;
+
+  constructor(store: PromptStore) {
+    this.store = store;
+    this.templates = new Map(store.listPromptTemplates().map(template => [template.name, template]));
+  }
+
+  list(): PromptTemplate[] {
+    return [...this.templates.values()].sort((a, b) => a.name.localeCompare(b.name));
+  }
+
+  get(name: string): PromptTemplate | undefined {
+    return this.templates.get(name);
+  }
+
+  /**
+   * Create or replace a template; the name is used in URLs and request bodies, so it stays simple
+   */
+  save(input: SavePromptInput): PromptTemplate | { error: string } {
+    if (!NAME_PATTERN.test(input.name)) {
+      return { error: 'Template name may only contain letters, digits, ".", "_" and "-"' };
+    }
+    if (!input.content.trim()) {
+      return { error: 'Template content must not be empty' };
+    }
+
+    const now = Date.now();
+    const template: PromptTemplate = {
+      name: input.name,
+      description: input.description || undefined,
+      content: input.content,
+      variables: extractVariables(input.content),
+      createdAt: this.templates.get(input.name)?.createdAt ?? now,
+      updatedAt: now,
+    };
+
+    this.store.savePromptTemplate(template);
+    this.templates.set(template.name, template);
+    return template;
+  }
+
+  delete(name: string): boolean {
+    this.templates.delete(name);
+    return this.store.deletePromptTemplate(name);
+  }
+
+  /**
+   * Fill a template's placeholders; every variable it uses must be provided
+   */
+  render(name: string, vars: unknown): { text: string } | { error: string } {
+    const template = this.templates.get(name);
+    if (!template) {
+      return { error: `Unknown prompt template "${name}"` };
+    }
+
+    const values = vars && typeof vars === 'object' && !Array.isArray(vars) ? (vars as Record<string, unknown>) : {};
+    const missing = template.variables.filter(variable => values[variable] === undefined || values[variable] === null);
+    if (missing.length > 0) {
+      return { error: `Prompt template "${name}" is missing variable(s): ${missing.join(', ')}` };
+    }
+
+    const text = template.content.replace(PLACEHOLDER, (_match: string, variable: string) => {
+      const value = values[variable];
+      return typeof value === 'string' ? value : JSON.stringify(value);
+    });
+    return { text };
+  }
+
+  /**
+   * Expand every template reference in a request body; null when the body references none
+   */
+  expand(body: any): PromptExpansion | null {
+    if (!body || typeof body !== 'object' || Array.isArray(body) || !containsReference(body)) {
+      return null;
+    }
+
+    const templates: string[] = [];
+    let error: string | undefined;
+
+    const renderReference = (reference: any): string | undefined => {
+      if (typeof reference[TEMPLATE_KEY] !== 'string') {
+        error ??= `${TEMPLATE_KEY} must be a template name`;
+        return undefined;
+      }
+      const result = this.render(reference[TEMPLATE_KEY], reference[VARS_KEY]);
+      if ('error' in result) {
+        error ??= result.error;
+        return undefined;
+      }
+      templates.push(reference[TEMPLATE_KEY]);
+      return result.text;
+    };
+
+    const visit = (value: any): any => {
+      if (Array.isArray(value)) {
+        return value.map(visit);
+      }
+      if (value && typeof value === 'object') {
+        if (TEMPLATE_KEY in value) {
+          return renderReference(value) ?? value;
+        }
+        return Object.fromEntries(Object.entries(value).map(([key, entry]) => [key, visit(entry)]));
+      }
+      return value;
+    };
+
+    let systemPrompt: string | undefined;
+    let rest = body;
+    if (TEMPLATE_KEY in body) {
+      const { [TEMPLATE_KEY]: name, [VARS_KEY]: vars, ...others } = body;
+      systemPrompt = renderReference({ [TEMPLATE_KEY]: name, [VARS_KEY]: vars });
+      rest = others;
+    }
+
+    const expanded = Object.fromEntries(Object.entries(rest).map(([key, entry]) => [key, visit(entry)]));
+    return error ? { error } : { body: expanded, systemPrompt, templates };
+  }
+}
+
+function containsReference(value: any): boolean {
+  if (Array.isArray(value)) {
+    return value.some(containsReference);
+  }
+  if (value && typeof value === 'object') {
+    return TEMPLATE_KEY in value || Object.values(value).some(containsReference);
+  }
+  return false;
+}
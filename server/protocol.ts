@@ -0,0 +1,166 @@
+// Wire formats - the JSON shapes sent to dashboards, API clients and realtime subscribers
+//
+// Every payload carries `v`, bumped only for breaking changes. Within a version fields are
+// only ever added, and union-typed values (event types, outcomes) may gain new members, so
+// consumers should ignore unknown fields and treat unknown union values as opaque strings.
+
+import type { RequestLog } from './logging/database';
+
+export const WIRE_VERSION = 1;
+
+export type WireVersion = typeof WIRE_VERSION;
+
+/** Allows new members without breaking exhaustive switches in consumer code */
+export type OpenUnion<T extends string> = T | (string & {});
+
+export type RealtimeEventType =
+  | 'request_completed'
+  | 'settings_changed'
+  | 'webhook_received'
+  | 'alert_fired'
+  | 'memory_pressure'
+  | 'database_recovered'
+  | 'certificate_warning'
+  | 'quota_low';
+
+// Only set for non-normal terminations
+export type RequestOutcome = 'interrupted' | 'client_disconnected' | 'blocked' | 'invalid' | 'queued' | 'cached';
+
+export interface RealtimeEvent {
+  v: WireVersion;
+  type: OpenUnion<RealtimeEventType>;
+  service: string;
+  timestamp: number;
+  data: Record<string, unknown>;
+  historical?: boolean;
+}
+
+export interface WireUsage {
+  model?: string;
+  prompt_tokens: number;
+  completion_tokens: number;
+  total_tokens: number;
+  thinking_tokens?: number; // Already counted in completion_tokens
+  cache_read_tokens?: number;
+  cache_write_tokens?: number;
+}
+
+// Request phases in milliseconds; headers_ms includes DNS, connect and TLS
+export interface WireTimings {
+  proxy_ms?: number;
+  headers_ms?: number;
+  first_byte_ms?: number;
+  body_ms?: number;
+}
+
+export interface WireRequestLog {
+  v: WireVersion;
+  id: string;
+  timestamp: number;
+  service: string;
+  method: string;
+  path: string;
+  target_url?: string;
+  status_code?: number;
+  duration_ms?: number;
+  error_message?: string;
+  channel?: string;
+  request_body?: string;
+  response_body?: string;
+  request_headers?: Record<string, string>;
+  response_headers?: Record<string, string>;
+  outcome?: OpenUnion<RequestOutcome>;
+  experiment_id?: string;
+  experiment_arm?: string;
+  upstream_id?: string;
+  conversation_id?: string; // Export the whole session via /api/logs/export/session/:conversation_id
+  client_identity?: string; // From the client's TLS certificate on mTLS proxy ports
+  protocol_issues?: string[]; // Set when the service checks responses (validate_responses); [] means compliant
+  content_omitted?: boolean; // A no-log request: bodies, previews and headers were never stored
+  dlp_matches?: string[];
+  scrubbed_items?: number;
+  prompt_templates?: string[];
+  tags?: Record<string, string>;
+  timings?: WireTimings;
+  usage?: WireUsage;
+  request_bytes?: number;
+  response_bytes?: number;
+  // Set when the config's request_compression gzipped the body sent upstream
+  request_compression?: { uncompressed_bytes: number; compressed_bytes: number };
+  model_override?: string; // "<requested> -> <sent>", "(none)" when the service default filled in a missing model
+  internal_logs?: WireInternalLogLine[];
+}
+
+export interface WireInternalLogLine {
+  level: 'warn' | 'error';
+  message: string;
+  timestamp: number;
+}
+
+/**
+ * Serialize a stored request log for the HTTP API
+ */
+export function toWireRequestLog(log: RequestLog): WireRequestLog {
+  return {
+    v: WIRE_VERSION,
+    id: log.id,
+    timestamp: log.timestamp,
+    service: log.service || 'claude', // Rows predating the service column were all claude
+    method: log.method,
+    path: log.path,
+    target_url: log.targetUrl,
+    status_code: log.statusCode,
+    duration_ms: log.duration,
+    error_message: log.error,
+    channel: log.configName,
+    request_body: log.requestBody,
+    response_body: log.responsePreview,
+    request_headers: log.requestHeaders,
+    response_headers: log.responseHeaders,
+    outcome: log.outcome,
+    experiment_id: log.experimentId,
+    experiment_arm: log.experimentArm,
+    upstream_id: log.upstreamId,
+    conversation_id: log.conversationId,
+    client_identity: log.clientIdentity,
+    protocol_issues: log.protocolIssues,
+    content_omitted: log.contentOmitted,
+    dlp_matches: log.dlpMatches,
+    scrubbed_items: log.scrubbedItems,
+    prompt_templates: log.promptTemplates,
+    tags: log.tags,
+    timings: log.headersMs !== undefined || log.proxyMs !== undefined
+      ? {
+          proxy_ms: log.proxyMs,
+          headers_ms: log.headersMs,
+          first_byte_ms: log.firstByteMs,
+          body_ms: log.bodyMs,
+        }
+      : undefined,
+    // Build usage object if we have token data
+    usage: (log.inputTokens || log.outputTokens || log.model || log.requestModel) ? {
+      model: log.model || log.requestModel,
+      prompt_tokens: log.inputTokens || 0,
+      completion_tokens: log.outputTokens || 0,
+      total_tokens: (log.inputTokens || 0) + (log.outputTokens || 0),
+      thinking_tokens: log.thinkingTokens,
+      cache_read_tokens: log.cacheReadTokens,
+      cache_write_tokens: log.cacheWriteTokens,
+    } : undefined,
+    request_bytes: log.requestBytes,
+    response_bytes: log.responseBytes,
+    request_compression:
+      log.uncompressedRequestBytes !== undefined && log.compressedRequestBytes !== undefined
+        ? { uncompressed_bytes: log.uncompressedRequestBytes, compressed_bytes: log.compressedRequestBytes }
+        : undefined,
+    model_override: log.modelOverride,
+    internal_logs: log.internalLogs?.map(line => ({ level: line.level, message: line.message, timestamp: line.at })),
+  };
+}
+
+/**
+ * Upgrade an event read back from storage; payloads persisted before versioning are v1
+ */
+export function normalizeRealtimeEvent(raw: Omit<RealtimeEvent, 'v'> & { v?: number }): RealtimeEvent {
+  return { ...raw, v: WIRE_VERSION };
+}
@@ -0,0 +1,57 @@
+// Anthropic beta flags - per-config `anthropic-beta` values merged into forwarded requests
+
+export const ANTHROPIC_BETA_HEADER = 'anthropic-beta';
+
+/**
+ * Betas paf knows about; other well-formed `<name>-YYYY-MM-DD` values are accepted with a warning
+ * so newly released betas do not wait on a paf release
+ */
+export const KNOWN_ANTHROPIC_BETAS: Readonly<Record<string, string>> = {
+  'prompt-caching-2024-07-31': 'Prompt caching',
+  'extended-cache-ttl-2025-04-11': '1-hour prompt cache TTL',
+  'context-1m-2025-08-07': '1M token context window',
+  'computer-use-2024-10-22': 'Computer use (Claude 3.5 Sonnet)',
+  'computer-use-2025-01-24': 'Computer use (Claude 3.7 Sonnet / Claude 4)',
+  'token-efficient-tools-2025-02-19': 'Token-efficient tool use',
+  'output-128k-2025-02-19': '128k output tokens',
+  'interleaved-thinking-2025-05-14': 'Interleaved thinking',
+  'fine-grained-tool-streaming-2025-05-14': 'Fine-grained tool streaming',
+  'context-management-2025-06-27': 'Context management',
+  'files-api-2025-04-14': 'Files API',
+  'code-execution-2025-05-22': 'Code execution tool',
+  'mcp-client-2025-04-04': 'MCP connector',
+  'pdfs-2024-09-25': 'PDF support',
+};
+
+const BETA_PATTERN = /^[a-z0-9]+(?:-[a-z0-9]+)*-\d{4}-\d{2}-\d{2}$/;
+
+/**
+ * Normalize a list of beta flags; malformed values are errors, unknown ones only warnings
+ */
+export function validateAnthropicBetas(values: unknown): { betas: string[]; warnings: string[] } | { error: string } {
+  if (!Array.isArray(values) || values.some(value => typeof value !== 'string')) {
+    return { error: 'anthropic_beta must be a list of strings' };
+  }
+
+  const betas = [...new Set(values.map(value => value.trim().toLowerCase()).filter(Boolean))];
+  const malformed = betas.filter(beta => !BETA_PATTERN.test(beta));
+  if (malformed.length > 0) {
+    return { error: `Invalid anthropic-beta value(s): ${malformed.join(', ')} (expected e.g. prompt-caching-2024-07-31)` };
+  }
+
+  const warnings = betas
+    .filter(beta => !(beta in KNOWN_ANTHROPIC_BETAS))
+    .map(beta => `Unknown anthropic-beta "${beta}"; forwarded as-is`);
+  return { betas, warnings };
+}
+
+/**
+ * Add configured betas to whatever the client already asked for, without duplicates
+ */
+export function mergeAnthropicBetas(header: string | undefined, betas: string[] | undefined): string | undefined {
+  if (!betas?.length) {
+    return header;
+  }
+  const existing = (header ?? '').split(',').map(value => value.trim()).filter(Boolean);
+  return [...new Set([...existing, ...betas])].join(',');
+}
@@ -1,15 +1,130 @@
 // Shared proxy service base class - handles forwarding to upstream APIs
 
 import type { ProxyConfig, ServiceConfig } from '../config/types';
-import type { LoadBalancer } from '../routing/loadbalancer';
+import { LoadBalancer } from '../routing/loadbalancer';
+import { findConfigPool, poolServers } from '../routing/pools';
+import { isRateLimited, RATE_LIMIT_MAX_WAIT_MS, type ConfigRateLimiter } from '../routing/rateLimiter';
+import { createDefaultServiceConfig } from '../config/defaults';
 import type { RequestLogger } from '../logging/logger';
+import { createSpan, currentSpan, runInSpan, type RequestSpan } from '../logging/tracing';
+import { conversationIdOf } from '../logging/conversation';
+import { clientIdentityOf } from './clientIdentity';
+import type { RequestOutcome } from '../protocol';
 import { ConfigManager } from '../config/manager';
+import { filterResponseHeaders } from './headerPolicy';
+import {
+  estimateTokens,
+  extractStreamText,
+  extractStreamThinking,
+  parseSseData,
+  splitSseEvents,
+  stripStreamThinking,
+  type SseEvent,
+} from './streamSalvage';
+import { startSseKeepalive } from './sseKeepalive';
+import type { ConnectionStats } from './connectionStats';
+import type { ExperimentAssignment, ExperimentRegistry } from '../experiments/registry';
+import type { RequestLog } from '../logging/database';
+import type { DlpFilter } from './dlp';
+import type { PromptLibrary } from '../prompts/library';
+import { parseRequestTags, TAGS_HEADER } from './tags';
+import { CONFIG_OVERRIDE_HEADER, CONFIG_OVERRIDE_TOKEN_HEADER, resolveConfigOverride } from './configOverride';
+import { UpstreamDeadline, upstreamTimeouts } from './timeouts';
+import { isNoLogRequest, NO_LOG_HEADER } from './noLog';
+import { applyFingerprint } from './fingerprint';
+import type { HookSessions } from '../hooks/claudeCode';
+import {
+  isModelListRequest,
+  modelListCacheKey,
+  type ModelListCache,
+  type ModelListCacheStatus,
+} from './modelListCache';
+import type { BodyMemoryBudget } from './memoryBudget';
+import { CONCURRENCY_RETRY_AFTER_MS, releaseWhenDone, type ConcurrencyLimiter } from './concurrency';
+import { readTextWithin } from './bodyLimit';
+import { fetchProxy } from './network';
+import {
+  proxyErrorResponse,
+  serviceErrorDialect,
+  type ProxyErrorKind,
+  type RateLimitState,
+  type StreamErrorCode,
+} from './errors';
+import { isQueueable, QUEUED_REQUEST_HEADER, type OutageQueue } from './outageQueue';
+import { applyModelMap, applyModelOverride, describeModelOverride } from './modelOverride';
+import { isChatPath, resolveResponseLanguage, responseLanguageInstruction } from './responseLanguage';
+import { applyQuirks, resolveQuirks } from './quirks';
+import { applyPromptCacheHints } from './promptCache';
+import { compressRequestBody } from './requestCompression';
+import { StreamUsageTap } from './usageTap';
+import {
+  isCacheableRequest,
+  RESPONSE_CACHE_HEADER,
+  responseCacheKey,
+  type CachedResponse,
+  type ResponseCache,
+} from './responseCache';
+import { upstreamTranslation } from './translation';
+import { checkJsonResponse, checkSseResponse, hasResponseProtocol } from './responseCompliance';
+import { describeViolations, hasRequestSchema, validateRequestBody, type SchemaViolation } from './requestSchema';
+import { requestShape, withoutFeatures, type RequestShape, type RequestShapeCache } from './shapeCache';
+import { buildSummaryRequest, insertSummary, readSummary, trimOldestMessages } from './contextTrim';
+import {
+  createRequestContext,
+  noteAttempt,
+  noteTransform,
+  REQUEST_ID_HEADER,
+  type RequestContext,
+} from './requestContext';
 
 export interface BaseProxyOptions {
   loadBalancer: LoadBalancer;
   logger: RequestLogger;
   serviceName: string;
   configManager: ConfigManager;
+  connectionStats?: ConnectionStats;
+  experiments?: ExperimentRegistry;
+  prompts?: PromptLibrary;
+  dlp?: DlpFilter;
+  modelListCache?: ModelListCache;
+  passthroughBodyBytes?: number; // Bodies larger than this stream through unparsed; 0 or unset buffers everything
+  memoryBudget?: BodyMemoryBudget;
+  outageQueue?: OutageQueue;
+  shapeCache?: RequestShapeCache;
+  responseCache?: ResponseCache;
+  rateLimiter?: ConfigRateLimiter;
+  concurrency?: ConcurrencyLimiter;        // max_concurrent_requests in system.toml, shared by every service
+  serviceConcurrency?: ConcurrencyLimiter; // max_concurrent_requests of this service
+  hookSessions?: HookSessions;
+}
+
+/**
+ * Per-request fields decided before forwarding and copied onto every log entry for the request
+ */
+export type RequestLogAnnotations = Pick<
+  RequestLog,
+  | 'experimentId'
+  | 'experimentArm'
+  | 'dlpMatches'
+  | 'promptTemplates'
+  | 'tags'
+  | 'requestBytes'
+  | 'modelOverride'
+  | 'context'
+  | 'conversationId'
+  | 'clientIdentity'
+  | 'fullRequestBody'
+  | 'uncompressedRequestBytes'
+  | 'compressedRequestBytes'
+  | 'contentOmitted'
+>;
+
+/**
+ * When the upstream call started and when its response headers arrived
+ */
+interface UpstreamTiming {
+  fetchStartedAt: number;
+  headersAt: number;
 }
 
 export interface RequestPreparationResult {
@@ -19,44 +134,187 @@ export interface RequestPreparationResult {
   thinkingBlocksRemoved: number;
 }
 
+/**
+ * How to continue an interrupted stream: the follow-up request body and a per-event rewrite
+ * that splices the new stream into the one the client already has (null drops the event)
+ */
+export interface StreamContinuationPlan {
+  body: any;
+  rewriteEvent: (event: SseEvent) => string | null;
+}
+
 export abstract class BaseProxyService {
   protected loadBalancer: LoadBalancer;
   protected logger: RequestLogger;
   protected serviceName: string;
   protected configManager: ConfigManager;
+  protected connectionStats?: ConnectionStats;
+  protected experiments?: ExperimentRegistry;
+  protected prompts?: PromptLibrary;
+  protected dlp?: DlpFilter;
+  protected modelListCache?: ModelListCache;
+  protected passthroughBodyBytes: number;
+  protected memoryBudget?: BodyMemoryBudget;
+  protected outageQueue?: OutageQueue;
+  protected shapeCache?: RequestShapeCache;
+  protected responseCache?: ResponseCache;
+  protected rateLimiter?: ConfigRateLimiter;
+  protected concurrency?: ConcurrencyLimiter;
+  protected serviceConcurrency?: ConcurrencyLimiter;
+  protected hookSessions?: HookSessions;
+  private poolBalancers = new Map<string, LoadBalancer>();
 
   constructor(options: BaseProxyOptions) {
     this.loadBalancer = options.loadBalancer;
     this.logger = options.logger;
     this.serviceName = options.serviceName;
     this.configManager = options.configManager;
+    this.connectionStats = options.connectionStats;
+    this.experiments = options.experiments;
+    this.prompts = options.prompts;
+    this.dlp = options.dlp;
+    this.modelListCache = options.modelListCache;
+    this.passthroughBodyBytes = options.passthroughBodyBytes ?? 0;
+    this.memoryBudget = options.memoryBudget;
+    this.outageQueue = options.outageQueue;
+    this.shapeCache = options.shapeCache;
+    this.responseCache = options.responseCache;
+    this.rateLimiter = options.rateLimiter;
+    this.concurrency = options.concurrency;
+    this.serviceConcurrency = options.serviceConcurrency;
+    this.hookSessions = options.hookSessions;
+    this.useConnectionLatency(this.loadBalancer);
+  }
+
+  /**
+   * Give the balancer's selection strategy each candidate's observed latency
+   */
+  private useConnectionLatency(balancer: LoadBalancer): void {
+    const stats = this.connectionStats;
+    if (stats) {
+      balancer.setLatencySource(server => stats.averageHeadersMs(server.baseUrl));
+    }
   }
 
   /**
    * Handle incoming proxy request
    */
   async handleRequest(request: Request, servers: ProxyConfig[]): Promise<Response> {
-    const requestId = crypto.randomUUID();
+    const slot = this.acquireConcurrencySlot();
+    if ('rejected' in slot) {
+      return slot.rejected;
+    }
+    // Request body bytes reserved against the memory budget; the buffered copy is done with once forwarded
+    const held = { bytes: 0 };
+    // Warnings printed while handling the request end up in its log entry (internal_logs)
+    const span = createSpan(this.serviceName);
+    try {
+      const response = await runInSpan(span, () => this.proxyRequest(request, servers, held, span));
+      return releaseWhenDone(response, slot.release);
+    } catch (error) {
+      slot.release();
+      throw error;
+    } finally {
+      this.memoryBudget?.release(held.bytes);
+    }
+  }
+
+  /**
+   * Take a slot of this service's limit and the system-wide one; a 429 with Retry-After when either is full.
+   * Slots are held until the response body ends, so streams count for their whole duration.
+   */
+  private acquireConcurrencySlot(): { release: () => void } | { rejected: Response } {
+    const releaseService = this.serviceConcurrency?.tryAcquire() ?? null;
+    if (this.serviceConcurrency && !releaseService) {
+      return { rejected: this.concurrencyLimitResponse(this.serviceConcurrency, `${this.serviceName} requests`) };
+    }
+    const releaseGlobal = this.concurrency?.tryAcquire() ?? null;
+    if (this.concurrency && !releaseGlobal) {
+      releaseService?.();
+      return { rejected: this.concurrencyLimitResponse(this.concurrency, 'proxied requests') };
+    }
+    return {
+      release: () => {
+        releaseGlobal?.();
+        releaseService?.();
+      },
+    };
+  }
+
+  private concurrencyLimitResponse(limiter: ConcurrencyLimiter, what: string): Response {
+    const { limit } = limiter.snapshot();
+    return this.errorResponse('rate_limit', `Too many concurrent ${what} (limit ${limit}); retry shortly`, {
+      limit,
+      remaining: 0,
+      resetAt: Date.now() + CONCURRENCY_RETRY_AFTER_MS,
+    });
+  }
+
+  private async proxyRequest(
+    request: Request,
+    servers: ProxyConfig[],
+    held: { bytes: number },
+    span: RequestSpan
+  ): Promise<Response> {
+    const requestId = span.requestId;
     const startTime = Date.now();
     let upstreamUrl: string | null = null;
+    let fetchStartedAt: number | null = null;
     let sanitizedThinking = false;
     let thinkingBlocksRemoved = 0;
-
-    // Select upstream server
-    const server = this.loadBalancer.selectServer(servers);
-
-    if (!server) {
-      return new Response('No upstream server available', { status: 503 });
-    }
+    const headerTags = parseRequestTags(request.headers.get(TAGS_HEADER));
+    const clientIdentity = clientIdentityOf(request);
+    const context = createRequestContext();
+    // Sensitive one-off prompts: logged as metadata only, never cached or queued
+    const contentOmitted = isNoLogRequest(request, this.configManager.getServiceConfig(this.serviceName)) || undefined;
 
     // Clone and read request body for logging
     let requestBodyJson: any = null;
     let requestBodyForUpstream: BodyInit | null = null;
 
-    if (request.body) {
+    // Oversized bodies are forwarded as the incoming stream: no thinking cleanup, prompt expansion or
+    // body logging. DLP has to see the text, so it keeps every body buffered while enabled.
+    const requestLength = Number(request.headers.get('content-length'));
+    // max_request_body_bytes: declared sizes are refused before anything is reserved or read
+    const maxBodyBytes = this.configManager.getServiceConfig(this.serviceName)?.maxRequestBodyBytes;
+    if (maxBodyBytes && requestLength > maxBodyBytes) {
+      return this.bodyTooLargeResponse(maxBodyBytes);
+    }
+    let requestBytes = requestLength > 0 ? requestLength : undefined;
+    let streamRequestBody = this.exceedsPassthroughThreshold(requestLength) && !this.dlp?.isEnabled();
+
+    // Over the memory budget, bodies stream as above; DLP still needs the text, so it buffers regardless
+    const budget = this.memoryBudget;
+    if (budget && request.body && !streamRequestBody && requestLength > 0) {
+      if (budget.tryReserve(requestLength)) {
+        held.bytes = requestLength;
+      } else if (this.dlp?.isEnabled()) {
+        budget.reserve(requestLength);
+        held.bytes = requestLength;
+      } else {
+        streamRequestBody = true;
+        budget.reportPressure(this.serviceName, 'request_streamed', requestLength);
+      }
+    }
+
+    if (request.body && streamRequestBody) {
+      requestBodyForUpstream = request.body;
+      noteTransform(context, 'body_passthrough', 'request body forwarded unparsed');
+    } else if (request.body) {
       try {
         const requestClone = request.clone();
-        const requestText = await requestClone.text();
+        // Without content-length the size is only known while reading, so stop once past the limit
+        const limitedText = maxBodyBytes ? await readTextWithin(requestClone, maxBodyBytes) : await requestClone.text();
+        if (limitedText === null) {
+          return this.bodyTooLargeResponse(maxBodyBytes!);
+        }
+        const requestText = limitedText;
+        requestBytes ??= Buffer.byteLength(requestText);
+        if (budget && held.bytes === 0) {
+          // No content-length (chunked upload): count what was actually read
+          budget.reserve(requestText.length);
+          held.bytes = requestText.length;
+        }
 
         if (requestText) {
           const parsedBody = JSON.parse(requestText);
@@ -69,6 +327,9 @@ export abstract class BaseProxyService {
               : requestText;
           sanitizedThinking = prepared.sanitized;
           thinkingBlocksRemoved = prepared.thinkingBlocksRemoved;
+          if (sanitizedThinking) {
+            noteTransform(context, 'strip_thinking', `${thinkingBlocksRemoved} thinking block(s) removed`);
+          }
         } else {
           requestBodyForUpstream = requestText;
         }
@@ -78,45 +339,456 @@ export abstract class BaseProxyService {
       }
     }
 
-    try {
-      // Build upstream URL
-      const url = new URL(request.url);
-      const base = server.baseUrl.replace(/\/+$/, '');
-      const path = url.pathname.startsWith('/') ? url.pathname : `/${url.pathname}`;
-      upstreamUrl = `${base}${path}${url.search}`;
-
-      // Build headers
-      const headers = this.buildForwardHeaders(request, server);
-      if (sanitizedThinking) {
-        console.log(
-          `[proxy:${this.serviceName}] removed ${thinkingBlocksRemoved} thinking block(s) before forwarding to ${server.name}`
-        );
-      }
+    // Service default model and deprecated aliases, before anything else reads the model
+    let modelOverride: string | undefined;
+    const override = applyModelOverride(
+      requestBodyJson,
+      this.configManager.getServiceConfig(this.serviceName)?.modelOverride
+    );
+    if (override) {
+      requestBodyJson = override.body;
+      requestBodyForUpstream = JSON.stringify(override.body);
+      modelOverride = describeModelOverride(override.from, override.to);
+      noteTransform(context, 'model_override', modelOverride);
+    }
 
-      // Use the request body
-      const body = requestBodyForUpstream;
+    // Expand prompt library references first so DLP scans the text that is actually sent
+    let promptTemplates: string[] | undefined;
+    const expansion = requestBodyJson ? this.prompts?.expand(requestBodyJson) : null;
+    if (expansion) {
+      if ('error' in expansion) {
+        return this.errorResponse('invalid_request', expansion.error);
+      }
+      requestBodyJson = expansion.systemPrompt
+        ? this.applySystemPrompt(expansion.body, expansion.systemPrompt)
+        : expansion.body;
+      requestBodyForUpstream = JSON.stringify(requestBodyJson);
+      promptTemplates = expansion.templates;
+      noteTransform(context, 'prompt_templates', expansion.templates.join(', '));
+    }
 
-      // Check if streaming response is expected
-      const acceptHeader = request.headers.get('accept') || '';
-      const isStreaming = acceptHeader.includes('text/event-stream');
+    const conversationId = conversationIdOf(request.headers, requestBodyJson);
+    // Task metadata a Claude Code hook attached to this session
+    const tags = this.hookSessions?.tagsFor(conversationId, headerTags) ?? headerTags;
+
+    // Opt-in schema check; passthrough bodies were never parsed, so they go upstream unchecked
+    const requestPath = new URL(request.url).pathname;
+    if (
+      this.configManager.getServiceConfig(this.serviceName)?.validateRequests &&
+      request.method === 'POST' &&
+      !streamRequestBody &&
+      hasRequestSchema(requestPath)
+    ) {
+      const violations = validateRequestBody(requestPath, requestBodyJson);
+      if (violations.length > 0) {
+        noteTransform(context, 'schema_reject', violations.map(violation => violation.path).join(', '));
+        return this.rejectInvalid(request, requestId, startTime, requestBodyJson, violations, {
+          promptTemplates,
+          tags,
+          requestBytes,
+          modelOverride,
+          context,
+          conversationId,
+          clientIdentity,
+          contentOmitted,
+        });
+      }
+    }
 
-      // Remove Accept-Encoding to get uncompressed responses from upstream
-      // This prevents Brotli compression issues
-      delete headers['accept-encoding'];
+    // Outbound DLP runs before any upstream is chosen so blocked requests never count against one
+    let dlpMatches: string[] | undefined;
+    if (requestBodyJson && this.dlp?.isEnabled()) {
+      const verdict = await this.dlp.inspect(requestBodyJson);
+      dlpMatches = verdict.matches.length > 0 ? verdict.matches : undefined;
+
+      if (verdict.blockedBy) {
+        noteTransform(context, 'dlp_block', verdict.blockedBy);
+        return this.rejectByDlp(request, requestId, startTime, requestBodyJson, verdict.blockedBy, {
+          dlpMatches: verdict.matches,
+          promptTemplates,
+          tags,
+          requestBytes,
+          modelOverride,
+          context,
+          conversationId,
+          clientIdentity,
+          contentOmitted,
+        });
+      }
+      if (verdict.body) {
+        requestBodyJson = verdict.body;
+        requestBodyForUpstream = JSON.stringify(verdict.body);
+        noteTransform(context, 'dlp_redact', verdict.matches.join(', '));
+      }
+    }
 
-      // Make upstream request
-      const upstreamResponse = await fetch(upstreamUrl, {
-        method: request.method,
-        headers,
-        body,
+    // x-paf-config picks the config itself; a cached answer or a queued replay from another config would defeat that
+    const configOverride = resolveConfigOverride(
+      request,
+      this.serviceName,
+      this.configManager.getServiceConfig(this.serviceName)
+    );
+    if ('error' in configOverride) {
+      return this.errorResponse(configOverride.kind, configOverride.error);
+    }
+    const forced = configOverride.config;
+
+    // Identical non-streaming requests are answered from [response_cache] without choosing an upstream
+    const cacheKey =
+      !forced &&
+      !contentOmitted &&
+      this.responseCache?.isEnabled() &&
+      typeof requestBodyForUpstream === 'string' &&
+      isCacheableRequest(request, requestBodyJson)
+        ? responseCacheKey(this.serviceName, requestPath, requestBodyJson)
+        : undefined;
+    const cacheHit = cacheKey ? this.responseCache!.get(cacheKey) : null;
+    if (cacheHit) {
+      noteTransform(context, 'response_cache', `hit from ${cacheHit.configName}`);
+      return this.respondFromCache(cacheHit, request, requestId, startTime, requestBodyJson, {
+        dlpMatches,
+        promptTemplates,
+        tags,
+        requestBytes,
+        modelOverride,
+        context,
+        conversationId,
+        clientIdentity,
+        contentOmitted,
       });
+    }
 
-      // Mark server health based on response
-      if (upstreamResponse.ok) {
-        this.loadBalancer.markSuccess(server.name);
-      } else {
-        this.loadBalancer.markFailure(server.name);
-        await this.maybeFreezeAfterFailure(server);
+    // With every config down, opted-in batch endpoints are stored for background replay instead of failing
+    const queueable =
+      !forced &&
+      !contentOmitted &&
+      typeof requestBodyForUpstream === 'string' &&
+      isQueueable(this.configManager.getServiceConfig(this.serviceName)?.outageQueue, request, new URL(request.url).pathname);
+    if (queueable && this.loadBalancer.allServersDown(servers)) {
+      const queued = this.enqueueForOutage(request, requestBodyForUpstream as string);
+      if (queued) {
+        noteTransform(context, 'outage_queue', 'all configs down; stored for replay');
+        await this.logQueuedRequest(request, requestId, startTime, requestBodyJson, {
+          dlpMatches,
+          promptTemplates,
+          tags,
+          requestBytes,
+          modelOverride,
+          context,
+          conversationId,
+          clientIdentity,
+          contentOmitted,
+        });
+        return queued;
+      }
+    }
+
+    // Select upstream server; a config forced by the request beats everything, then an operator pin, then a
+    // running A/B experiment, then the load balancer. Forced configs, like pins, never fail over.
+    const serviceConfig = this.configManager.getServiceConfig(this.serviceName);
+    const allConfigs = serviceConfig?.configs ?? servers;
+    const pinned = forced ?? this.loadBalancer.getPinnedServer(servers);
+    if (forced) {
+      // Nothing else to fail over or resume a stream on
+      servers = [forced];
+    }
+    const experiment = pinned ? null : this.experiments?.assign(this.serviceName, allConfigs) ?? null;
+
+    // A model family pool narrows the candidates and is balanced and health-tracked on its own
+    const pool = pinned || experiment ? null : findConfigPool(serviceConfig?.pools, requestBodyJson?.model);
+    const pooled = pool ? poolServers(pool, servers) : [];
+    const balancer = pool && pooled.length > 0 ? this.poolBalancer(pool.name) : this.loadBalancer;
+    if (pooled.length > 0) {
+      servers = pooled;
+    }
+
+    // Skip configs known to reject this request's parameters while a compatible one is left
+    const shape = this.shapeCache?.isEnabled() ? requestShape(requestBodyJson) : null;
+    const avoided = shape ? servers.filter(candidate => this.shapeCache!.rejectedBy(candidate.name, shape).length > 0) : [];
+    const compatible = servers.filter(candidate => candidate.enabled !== false && !avoided.includes(candidate));
+    const routable = !pinned && avoided.length > 0 && compatible.length > 0 ? compatible : servers;
+
+    // Configs at their requests_per_minute / tokens_per_minute are skipped while another has room; forced
+    // configs, pins and experiments bypass the limits like they bypass health
+    const estimatedTokens = typeof requestBodyForUpstream === 'string' ? estimateTokens(requestBodyForUpstream) : 0;
+    let candidates = routable;
+    if (this.rateLimiter && !forced && !pinned && !experiment && routable.some(isRateLimited)) {
+      const admitted = await this.admitByRateLimit(routable, estimatedTokens, request.signal);
+      if ('retryAt' in admitted) {
+        noteTransform(context, 'rate_limit', `every config limited for ${admitted.retryAt - Date.now()}ms`);
+        return this.errorResponse('rate_limit', `Every ${this.serviceName} config is at its configured rate limit`, {
+          limit: admitted.limit,
+          remaining: 0,
+          resetAt: admitted.retryAt,
+        });
+      }
+      if (admitted.waitedMs > 0) {
+        noteTransform(context, 'rate_limit_wait', `${admitted.waitedMs}ms`);
+      }
+      if (admitted.configs.length < routable.length) {
+        const skipped = routable.filter(candidate => !admitted.configs.includes(candidate));
+        noteTransform(context, 'rate_limit', `skipped ${skipped.map(candidate => candidate.name).join(', ')}`);
+      }
+      candidates = admitted.configs;
+    }
+
+    const selected = forced
+      ? { config: forced, reason: `${CONFIG_OVERRIDE_HEADER} header` }
+      : experiment
+      ? { config: experiment.server, reason: `experiment arm ${experiment.arm}` }
+      : balancer.select(candidates, {
+          service: this.serviceName,
+          model: typeof requestBodyJson?.model === 'string' ? requestBodyJson.model : undefined,
+          path: new URL(request.url).pathname,
+          tags,
+        });
+    let server = selected?.config ?? null;
+
+    if (!server) {
+      return this.errorResponse('no_upstream', 'No upstream server available');
+    }
+    balancer.recordRequest(server.name);
+    this.rateLimiter?.take(server, estimatedTokens, requestId);
+    span.config = server.name;
+
+    context.selection = {
+      config: server.name,
+      via: forced ? 'header' : experiment ? 'experiment' : pinned?.name === server.name ? 'pin' : 'load_balancer',
+      mode: serviceConfig?.mode,
+      strategy: experiment || pinned ? undefined : serviceConfig?.loadBalancer.strategy,
+      experiment: experiment ? `${experiment.experimentId}:${experiment.arm}` : undefined,
+      pool: balancer !== this.loadBalancer ? pool!.name : undefined,
+      reason: selected?.reason,
+      candidates: candidates.map(candidate => candidate.name),
+    };
+    console.debug(`[proxy:${this.serviceName}] ${requestId} -> ${server.name} via ${context.selection.via}`);
+    if (routable !== servers && !experiment) {
+      noteTransform(context, 'shape_cache', `avoided ${avoided.map(candidate => candidate.name).join(', ')}`);
+    }
+
+    const annotations: RequestLogAnnotations = {
+      experimentId: experiment?.experimentId,
+      experimentArm: experiment?.arm,
+      dlpMatches,
+      promptTemplates,
+      tags,
+      requestBytes,
+      modelOverride,
+      context,
+      conversationId,
+      clientIdentity,
+      contentOmitted,
+    };
+
+    const unadaptedBody = { json: requestBodyJson, upstream: requestBodyForUpstream };
+    const tried = [server.name];
+    let isStreaming = false;
+    let upstreamResponse!: Response;
+    let timing!: UpstreamTiming;
+
+    try {
+      while (true) {
+        // Per-config body changes below start over from the shared body on every attempt
+        requestBodyJson = unadaptedBody.json;
+        requestBodyForUpstream = unadaptedBody.upstream;
+
+        // Build upstream URL
+        const url = new URL(request.url);
+        const base = server.baseUrl.replace(/\/+$/, '');
+        const path = url.pathname.startsWith('/') ? url.pathname : `/${url.pathname}`;
+        // Bodies forwarded unbuffered can't be rewritten, so they go out untranslated
+        const translation =
+          requestBodyJson && typeof requestBodyForUpstream === 'string' ? upstreamTranslation(server.apiFormat, path) : null;
+        const targetUrl = `${base}${translation?.path ?? path}${url.search}`;
+        upstreamUrl = targetUrl;
+
+        // Build headers
+        const headers = this.buildForwardHeaders(request, server, context);
+        if (streamRequestBody) {
+          // Keep the upstream from seeing a chunked upload of a body whose size is already known
+          headers['content-length'] = String(requestLength);
+        }
+        if (sanitizedThinking) {
+          console.log(
+            `[proxy:${this.serviceName}] removed ${thinkingBlocksRemoved} thinking block(s) before forwarding to ${server.name}`
+          );
+        }
+
+        // This config's own name for the requested model; first, so later changes see what the upstream gets
+        const mapped = typeof requestBodyForUpstream === 'string' ? applyModelMap(requestBodyJson, server.modelMap) : null;
+        if (mapped) {
+          requestBodyJson = mapped.body;
+          requestBodyForUpstream = JSON.stringify(mapped.body);
+          noteTransform(context, 'model_map', describeModelOverride(mapped.from, mapped.to));
+        }
+
+        // Pin the reply language for this client key or config; appended so it outranks the client's own prompt
+        const language = resolveResponseLanguage(request, server, serviceConfig);
+        if (language && requestBodyJson && typeof requestBodyForUpstream === 'string' && isChatPath(url.pathname)) {
+          requestBodyJson = this.appendSystemInstruction(requestBodyJson, responseLanguageInstruction(language));
+          requestBodyForUpstream = JSON.stringify(requestBodyJson);
+          noteTransform(context, 'response_language', language);
+        }
+
+        // Cache the large stable prefix of clients that don't mark one themselves; Messages API only
+        if (
+          server.promptCache &&
+          requestBodyJson &&
+          typeof requestBodyForUpstream === 'string' &&
+          serviceErrorDialect(this.serviceName) === 'anthropic' &&
+          url.pathname.endsWith('/messages')
+        ) {
+          const hinted = applyPromptCacheHints(requestBodyJson, server.promptCache);
+          if (hinted) {
+            requestBodyJson = hinted.body;
+            requestBodyForUpstream = JSON.stringify(hinted.body);
+            noteTransform(context, 'prompt_cache', `breakpoints after ${hinted.breakpoints.join(', ')}`);
+          }
+        }
+
+        // Rewrite what this upstream is known to reject; runs after every other body change so it sees the final shape
+        const quirks = resolveQuirks(server, serviceConfig);
+        if (quirks && requestBodyJson && typeof requestBodyForUpstream === 'string') {
+          const adapted = applyQuirks(requestBodyJson, quirks.quirks, serviceErrorDialect(this.serviceName));
+          if (adapted) {
+            requestBodyJson = adapted.body;
+            requestBodyForUpstream = JSON.stringify(adapted.body);
+            noteTransform(context, 'quirks', `${quirks.profile ?? 'custom'}: ${adapted.changes.join('; ')}`);
+          }
+        }
+
+        // No compatible config was left; drop what this one is known to reject rather than spend a round trip on a 400
+        const rejected = shape && requestBodyJson ? this.shapeCache!.rejectedBy(server.name, requestShape(requestBodyJson)!) : [];
+        if (rejected.length > 0 && typeof requestBodyForUpstream === 'string') {
+          requestBodyJson = withoutFeatures(requestBodyJson, rejected);
+          requestBodyForUpstream = JSON.stringify(requestBodyJson);
+          noteTransform(context, 'shape_cache', `removed ${rejected.join(', ')}`);
+        }
+
+        // Fit the prompt into this config's context window instead of letting a small-context relay reject it
+        if (server.contextTrim && requestBodyJson && typeof requestBodyForUpstream === 'string') {
+          const trimmed = await this.trimContext(server, headers, requestBodyJson, context);
+          if (trimmed) {
+            requestBodyJson = trimmed;
+            requestBodyForUpstream = JSON.stringify(trimmed);
+          }
+        }
+
+        // Last body change: a config speaking another API gets the final body in that API's format
+        if (translation) {
+          requestBodyForUpstream = JSON.stringify(translation.request(requestBodyJson));
+          noteTransform(context, 'api_format', server.apiFormat!);
+        }
+
+        // Use the request body
+        let body = requestBodyForUpstream;
+        if (serviceConfig?.captureRequestBodies && typeof body === 'string') {
+          annotations.fullRequestBody = body;
+        }
+
+        // gzip large bodies for upstreams that accept it; the capture above keeps the readable text
+        const compressed =
+          server.requestCompression && typeof body === 'string' ? compressRequestBody(body, server.requestCompression) : null;
+        annotations.uncompressedRequestBytes = compressed?.rawBytes;
+        annotations.compressedRequestBytes = compressed?.compressedBytes;
+        if (compressed) {
+          body = compressed.body;
+          headers['content-encoding'] = 'gzip';
+          noteTransform(context, 'request_compression', `${compressed.rawBytes} -> ${compressed.compressedBytes} bytes`);
+        }
+        const sentShape = shape ? requestShape(requestBodyJson) : null;
+
+        // Check if streaming response is expected
+        const acceptHeader = request.headers.get('accept') || '';
+        isStreaming = acceptHeader.includes('text/event-stream');
+
+        if (this.setUpstreamAcceptEncoding(headers, server, isStreaming)) {
+          noteTransform(context, 'accept_encoding', 'identity');
+        }
+
+        // Make upstream request; tied to the client's signal so a disconnect aborts it, and to the config's timeouts
+        fetchStartedAt = Date.now();
+        const deadline = this.deadlineFor(server);
+        const forward = async (signal?: AbortSignal) => {
+          try {
+            return await fetch(targetUrl, {
+              method: request.method,
+              headers,
+              body,
+              signal: deadline.signal(signal),
+              timeout: deadline.fetchTimeout(),
+              proxy: await this.proxyFor(server),
+            });
+          } catch (error) {
+            // A failed attempt leaves nothing for the timers to abort
+            deadline.clear();
+            throw error;
+          }
+        };
+
+        // Model list polling is coalesced and cached; the shared call outlives any single client
+        let cacheStatus: ModelListCacheStatus | undefined;
+        if (this.modelListCache?.isEnabled() && isModelListRequest(request.method, url.pathname)) {
+          const cached = await this.modelListCache.fetch(modelListCacheKey(server.name, targetUrl, headers), () =>
+            forward()
+          );
+          upstreamResponse = cached.response;
+          cacheStatus = cached.status;
+          noteTransform(context, 'model_list_cache', cacheStatus);
+        } else {
+          upstreamResponse = await forward(request.signal);
+        }
+        timing = { fetchStartedAt, headersAt: Date.now() };
+        deadline.responded();
+        if (translation) {
+          upstreamResponse = await translation.response(upstreamResponse, requestBodyJson);
+        }
+        noteAttempt(context, {
+          kind: tried.length > 1 ? 'failover' : 'initial',
+          config: server.name,
+          url: targetUrl,
+          status: upstreamResponse.status,
+        });
+        console.debug(
+          `[proxy:${this.serviceName}] ${requestId} ${server.name} answered ${upstreamResponse.status} after ${timing.headersAt - fetchStartedAt}ms`
+        );
+
+        // Only the request that actually reached the upstream counts towards its stats and health
+        if (cacheStatus === undefined || cacheStatus === 'miss') {
+          this.connectionStats?.recordResponse(targetUrl, timing.headersAt - fetchStartedAt);
+
+          if (upstreamResponse.ok) {
+            balancer.markSuccess(server.name);
+          } else {
+            balancer.markFailure(server.name);
+            // A pool's failures may be specific to its models, so only the service balancer freezes configs
+            if (balancer === this.loadBalancer) {
+              await this.maybeFreezeAfterFailure(server);
+            }
+          }
+
+          if (sentShape) {
+            await this.learnRequestShape(server, sentShape, upstreamResponse);
+          }
+        }
+
+        // A 5xx or 429 goes to the next eligible config while one is left and the retry budget allows
+        if (cacheStatus !== 'hit' && this.shouldFailOver(upstreamResponse, request, streamRequestBody, experiment, pinned)) {
+          const next = this.nextFailoverServer(routable, server, tried, balancer);
+          if (next) {
+            console.warn(
+              `[proxy:${this.serviceName}] ${server.name} answered ${upstreamResponse.status}; retrying on ${next.name}`
+            );
+            await upstreamResponse.body?.cancel().catch(() => {});
+            deadline.clear();
+            server = next;
+            this.rateLimiter?.take(server, estimatedTokens, requestId);
+            tried.push(server.name);
+            span.config = server.name;
+            continue;
+          }
+        }
+        break;
       }
 
       // Handle response
@@ -133,7 +805,11 @@ export abstract class BaseProxyService {
           startTime,
           request,
           requestBodyJson,
-          upstreamUrl
+          upstreamUrl,
+          servers,
+          balancer,
+          annotations,
+          timing
         );
       } else {
         if (!upstreamResponse.ok) {
@@ -148,25 +824,40 @@ export abstract class BaseProxyService {
           startTime,
           request,
           requestBodyJson,
-          upstreamUrl
+          upstreamUrl,
+          annotations,
+          timing,
+          cacheKey
         );
       }
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : String(error);
 
-      // Mark server as failed
-      this.loadBalancer.markFailure(server.name);
+      // A client that gave up before headers arrived is not an upstream failure
+      const clientDisconnected = request.signal.aborted;
+      noteAttempt(context, {
+        kind: tried.length > 1 ? 'failover' : 'initial',
+        config: server.name,
+        url: upstreamUrl ?? undefined,
+        error: clientDisconnected ? 'client disconnected' : errorMessage,
+      });
+
+      if (!clientDisconnected) {
+        if (upstreamUrl) {
+          this.connectionStats?.recordError(upstreamUrl, error);
+        }
 
-      await this.freezeConfig(server, 'proxy failure');
+        // Mark server as failed
+        balancer.markFailure(server.name);
+
+        await this.freezeConfig(server, 'proxy failure');
+      }
 
       // Extract request info
-      const requestInfo = this.logger.extractRequestInfo(requestBodyJson);
+      const requestInfo = this.logger.extractRequestInfo(requestBodyJson, this.shouldStripThinking());
 
       // Collect request headers
-      const requestHeaders: Record<string, string> = {};
-      request.headers.forEach((value, key) => {
-        requestHeaders[key] = value;
-      });
+      const requestHeaders = this.loggedRequestHeaders(request);
 
       const originalUrl = new URL(request.url);
       const pathWithQuery = `${originalUrl.pathname}${originalUrl.search}`;
@@ -180,30 +871,510 @@ export abstract class BaseProxyService {
         path: pathWithQuery,
         targetUrl: upstreamUrl ?? undefined,
         configName: server.name,
-        error: errorMessage,
+        error: clientDisconnected ? 'Client disconnected before upstream responded' : errorMessage,
         duration: Date.now() - startTime,
         requestModel: requestInfo.model,
         requestBody: requestInfo.preview,
         requestHeaders,
+        outcome: clientDisconnected ? 'client_disconnected' : undefined,
+        proxyMs: fetchStartedAt !== null ? fetchStartedAt - startTime : undefined,
+        ...annotations,
       });
 
-      return new Response(JSON.stringify({ error: errorMessage }), {
-        status: 502,
-        headers: { 'Content-Type': 'application/json' },
+      if (clientDisconnected) {
+        // Nobody is listening; 499 mirrors the nginx convention for client-closed requests
+        return new Response(null, { status: 499 });
+      }
+
+      // That was the last config standing; hold the request until one recovers
+      if (queueable && this.loadBalancer.allServersDown(servers)) {
+        const queued = this.enqueueForOutage(request, requestBodyForUpstream as string);
+        if (queued) {
+          return queued;
+        }
+      }
+
+      return this.errorResponse('upstream', errorMessage);
+    }
+  }
+
+  /**
+   * The balancer of a model family pool, created on first use with the service's balancer settings
+   */
+  private poolBalancer(poolName: string): LoadBalancer {
+    let balancer = this.poolBalancers.get(poolName);
+    if (!balancer) {
+      const config = this.configManager.getServiceConfig(this.serviceName)?.loadBalancer;
+      balancer = new LoadBalancer(config ?? createDefaultServiceConfig().loadBalancer);
+      this.useConnectionLatency(balancer);
+      this.poolBalancers.set(poolName, balancer);
+    }
+    return balancer;
+  }
+
+  /**
+   * Members of each configured pool with their pool weight and the pool's own health view of them
+   */
+  getPoolStatus(): Array<{
+    pool: string;
+    models: string[];
+    members: Array<{ config: string; weight: number; healthy: boolean; consecutiveFailures: number }>;
+  }> {
+    const pools = this.configManager.getServiceConfig(this.serviceName)?.pools ?? [];
+    return pools.map(pool => {
+      const balancer = this.poolBalancers.get(pool.name);
+      return {
+        pool: pool.name,
+        models: pool.models,
+        members: pool.members.map(member => ({
+          config: member.config,
+          weight: member.weight,
+          healthy: balancer?.isServerHealthy(member.config) ?? true,
+          consecutiveFailures: balancer?.getServerHealth(member.config).consecutiveFailures ?? 0,
+        })),
+      };
+    });
+  }
+
+  /**
+   * Drop the client's Accept-Encoding so fetch negotiates (and decodes) compression itself, unless this
+   * config or response kind must stay uncompressed. Returns true when identity was forced.
+   */
+  private setUpstreamAcceptEncoding(headers: Record<string, string>, server: ProxyConfig, streaming: boolean): boolean {
+    delete headers['accept-encoding'];
+    const compression = this.configManager.getServiceConfig(this.serviceName)?.upstreamCompression;
+    const allowed =
+      server.upstreamCompression !== false &&
+      (streaming ? compression?.streaming !== false : compression?.nonStreaming !== false);
+    if (!allowed) {
+      headers['accept-encoding'] = 'identity';
+    }
+    return !allowed;
+  }
+
+  /**
+   * Feed a response into the shape cache; 400 bodies are read from a clone so the client still gets the original
+   */
+  private async learnRequestShape(
+    server: ProxyConfig,
+    shape: RequestShape,
+    upstreamResponse: Response
+  ): Promise<void> {
+    if (upstreamResponse.ok) {
+      this.shapeCache!.recordSuccess(server.name, shape);
+      return;
+    }
+    if (upstreamResponse.status !== 400) {
+      return;
+    }
+    const errorText = await upstreamResponse.clone().text().catch(() => '');
+    for (const entry of this.shapeCache!.recordRejection(server.name, shape, errorText)) {
+      console.warn(
+        `[proxy:${this.serviceName}] ${server.name} keeps rejecting ${entry.feature} for ${entry.model || 'requests without a model'}; avoiding it for ${Math.round((entry.expiresAt - entry.learnedAt) / 1000)}s`
+      );
+    }
+  }
+
+  /**
+   * Answer a request stopped by DLP with 403 and log it without contacting any upstream
+   */
+  private async rejectByDlp(
+    request: Request,
+    requestId: string,
+    startTime: number,
+    requestBodyJson: any,
+    blockedBy: string,
+    annotations: RequestLogAnnotations
+  ): Promise<Response> {
+    const message =
+      blockedBy === 'moderation' ? 'Request blocked by content moderation' : `Request blocked by DLP rule "${blockedBy}"`;
+    return this.rejectLocally(request, requestId, startTime, requestBodyJson, 'permission', 403, message, 'blocked', annotations);
+  }
+
+  /**
+   * Answer a request that failed schema validation with 400; logged as outcome 'invalid', so it counts
+   * against neither a config nor the service's success rate
+   */
+  private async rejectInvalid(
+    request: Request,
+    requestId: string,
+    startTime: number,
+    requestBodyJson: any,
+    violations: SchemaViolation[],
+    annotations: RequestLogAnnotations
+  ): Promise<Response> {
+    const message = describeViolations(violations);
+    return this.rejectLocally(request, requestId, startTime, requestBodyJson, 'invalid_request', 400, message, 'invalid', annotations);
+  }
+
+  private async rejectLocally(
+    request: Request,
+    requestId: string,
+    startTime: number,
+    requestBodyJson: any,
+    kind: ProxyErrorKind,
+    statusCode: number,
+    message: string,
+    outcome: RequestOutcome,
+    annotations: RequestLogAnnotations
+  ): Promise<Response> {
+    console.warn(`[proxy:${this.serviceName}] ${message}`);
+
+    const requestHeaders = this.loggedRequestHeaders(request);
+    const url = new URL(request.url);
+    const requestInfo = this.logger.extractRequestInfo(requestBodyJson, this.shouldStripThinking());
+
+    await this.logger.logRequest({
+      id: requestId,
+      timestamp: startTime,
+      service: this.serviceName,
+      method: request.method,
+      path: `${url.pathname}${url.search}`,
+      configName: '',
+      statusCode,
+      duration: Date.now() - startTime,
+      error: message,
+      requestModel: requestInfo.model,
+      requestBody: requestInfo.preview,
+      requestHeaders,
+      outcome,
+      ...annotations,
+    });
+
+    return this.errorResponse(kind, message);
+  }
+
+  /**
+   * Store the request in the outage queue and answer 202 with where to poll; null when the queue is full
+   */
+  private enqueueForOutage(request: Request, body: string): Response | null {
+    const config = this.configManager.getServiceConfig(this.serviceName)?.outageQueue;
+    if (!this.outageQueue || !config) {
+      return null;
+    }
+
+    const url = new URL(request.url);
+    const item = this.outageQueue.enqueue(this.serviceName, request, `${url.pathname}${url.search}`, body, config.maxItems);
+    if ('error' in item) {
+      console.warn(`[proxy:${this.serviceName}] ${item.error}; failing request instead`);
+      return null;
+    }
+
+    console.log(`[proxy:${this.serviceName}] all configs down; queued ${item.method} ${item.path} as ${item.id}`);
+    return Response.json(
+      { id: item.id, status: 'queued', status_url: `/api/queue/${item.id}` },
+      { status: 202, headers: { [QUEUED_REQUEST_HEADER]: item.id } }
+    );
+  }
+
+  /**
+   * Answer from the response cache; the log entry names the config that produced the response but
+   * carries no token usage, since nothing was spent upstream
+   */
+  private async respondFromCache(
+    cached: CachedResponse,
+    request: Request,
+    requestId: string,
+    startTime: number,
+    requestBodyJson: any,
+    annotations: RequestLogAnnotations
+  ): Promise<Response> {
+    const cachedResponse = new Response(cached.body, { status: cached.status, headers: cached.headers });
+    const headers = this.buildClientResponseHeaders(cachedResponse, requestId);
+    headers.set(RESPONSE_CACHE_HEADER, 'hit');
+
+    const requestHeaders = this.loggedRequestHeaders(request);
+    const responseHeaders: Record<string, string> = {};
+    headers.forEach((value, key) => {
+      responseHeaders[key] = value;
+    });
+    const url = new URL(request.url);
+    const stripThinking = this.shouldStripThinking();
+    const requestInfo = this.logger.extractRequestInfo(requestBodyJson, stripThinking);
+    let responseBody: any = cached.body;
+    try {
+      responseBody = JSON.parse(cached.body);
+    } catch {
+      // Not JSON; previewed as text
+    }
+
+    await this.logger.logRequest({
+      id: requestId,
+      timestamp: startTime,
+      service: this.serviceName,
+      method: request.method,
+      path: `${url.pathname}${url.search}`,
+      configName: cached.configName,
+      statusCode: cached.status,
+      duration: Date.now() - startTime,
+      requestModel: requestInfo.model,
+      requestBody: requestInfo.preview,
+      responsePreview: this.logger.extractResponsePreview(responseBody, stripThinking),
+      requestHeaders,
+      responseHeaders,
+      responseBytes: cached.bytes,
+      outcome: 'cached',
+      ...annotations,
+    });
+
+    return new Response(cached.body, { status: cached.status, headers });
+  }
+
+  private async logQueuedRequest(
+    request: Request,
+    requestId: string,
+    startTime: number,
+    requestBodyJson: any,
+    annotations: RequestLogAnnotations
+  ): Promise<void> {
+    const requestHeaders = this.loggedRequestHeaders(request);
+    const url = new URL(request.url);
+    const requestInfo = this.logger.extractRequestInfo(requestBodyJson, this.shouldStripThinking());
+
+    await this.logger.logRequest({
+      id: requestId,
+      timestamp: startTime,
+      service: this.serviceName,
+      method: request.method,
+      path: `${url.pathname}${url.search}`,
+      configName: '',
+      statusCode: 202,
+      duration: Date.now() - startTime,
+      requestModel: requestInfo.model,
+      requestBody: requestInfo.preview,
+      requestHeaders,
+      outcome: 'queued',
+      ...annotations,
+    });
+  }
+
+  /**
+   * Replay queued requests oldest first while at least one config is up. Replays go through the
+   * normal path, so they are load balanced, logged and counted like any other request.
+   */
+  async drainOutageQueue(servers: ProxyConfig[]): Promise<void> {
+    const config = this.configManager.getServiceConfig(this.serviceName)?.outageQueue;
+    if (!this.outageQueue || !config?.enabled || servers.length === 0) {
+      return;
+    }
+
+    for (const item of this.outageQueue.pending(this.serviceName)) {
+      if (this.loadBalancer.allServersDown(servers)) {
+        return;
+      }
+
+      const response = await this.handleRequest(
+        new Request(`http://paf.local${item.path}`, {
+          method: item.method,
+          headers: { ...item.headers, [QUEUED_REQUEST_HEADER]: item.id },
+          body: item.body,
+        }),
+        servers
+      );
+      const text = await response.text().catch(() => '');
+      const updated = this.outageQueue.recordAttempt(item, response.status, text, config.maxAttempts);
+      if (updated.status !== 'pending') {
+        console.log(`[proxy:${this.serviceName}] queued request ${item.id} ${updated.status} after ${updated.attempts} attempt(s)`);
+      }
+    }
+  }
+
+  /**
+   * Apply the config's context trim: drop the oldest turns and, with the summarize strategy, put a
+   * summary from the config's summary model in their place (plain dropping if that call fails)
+   */
+  private async trimContext(
+    server: ProxyConfig,
+    headers: Record<string, string>,
+    body: any,
+    context?: RequestContext
+  ): Promise<any | null> {
+    const config = server.contextTrim!;
+    const trimmed = trimOldestMessages(body, config.limit);
+    if (!trimmed) {
+      return null;
+    }
+
+    const overLimit = trimmed.tokens > config.limit ? `, still ~${trimmed.tokens} tokens` : '';
+    if (config.strategy !== 'summarize' || !config.summaryModel) {
+      console.log(
+        `[proxy:${this.serviceName}] dropped ${trimmed.dropped.length} oldest message(s) to fit ${server.name}'s ${config.limit}-token limit${overLimit}`
+      );
+      noteTransform(context, 'context_trim', `dropped ${trimmed.dropped.length} message(s)`);
+      return trimmed.body;
+    }
+
+    const dialect = serviceErrorDialect(this.serviceName);
+    const summaryRequest = buildSummaryRequest(dialect, config.summaryModel, trimmed.dropped, config.limit);
+    try {
+      const response = await fetch(`${server.baseUrl.replace(/\/+$/, '')}${summaryRequest.path}`, {
+        method: 'POST',
+        headers: { ...headers, accept: 'application/json', 'content-type': 'application/json' },
+        body: JSON.stringify(summaryRequest.body),
+        signal: AbortSignal.timeout(30_000),
+        proxy: await this.proxyFor(server),
       });
+      if (!response.ok) {
+        throw new Error(`HTTP ${response.status}`);
+      }
+      const summary = readSummary(dialect, await response.json());
+      if (!summary) {
+        throw new Error('empty summary');
+      }
+      console.log(
+        `[proxy:${this.serviceName}] summarized ${trimmed.dropped.length} oldest message(s) with ${config.summaryModel} to fit ${server.name}'s ${config.limit}-token limit${overLimit}`
+      );
+      noteTransform(context, 'context_trim', `summarized ${trimmed.dropped.length} message(s) with ${config.summaryModel}`);
+      return insertSummary(trimmed.body, summary);
+    } catch (error) {
+      console.warn(
+        `[proxy:${this.serviceName}] summary via ${config.summaryModel} failed (${error instanceof Error ? error.message : error}); dropped ${trimmed.dropped.length} message(s) instead`
+      );
+      noteTransform(context, 'context_trim', `dropped ${trimmed.dropped.length} message(s) after the summary failed`);
+      return trimmed.body;
+    }
+  }
+
+  /**
+   * Response compliance checks are opt-in per service (validate_responses)
+   */
+  private shouldCheckCompliance(pathname: string): boolean {
+    return (
+      this.configManager.getServiceConfig(this.serviceName)?.validateResponses === true && hasResponseProtocol(pathname)
+    );
+  }
+
+  private warnProtocolIssues(server: ProxyConfig, issues: string[]): void {
+    if (issues.length > 0) {
+      console.warn(`[proxy:${this.serviceName}] response from ${server.name} breaks protocol: ${issues.join('; ')}`);
+    }
+  }
+
+  /**
+   * An error raised by paf itself, shaped like the provider's own errors so client tools display it
+   */
+  protected errorResponse(kind: ProxyErrorKind, message: string, rateLimit?: RateLimitState): Response {
+    return proxyErrorResponse(serviceErrorDialect(this.serviceName), kind, message, {}, rateLimit);
+  }
+
+  /**
+   * The config's proxy_url, or the service's, or the [network] bind_address bridge
+   */
+  protected proxyFor(server: ProxyConfig): Promise<string | undefined> {
+    return fetchProxy(server, this.configManager.getServiceConfig(this.serviceName));
+  }
+
+  /**
+   * The timeouts of one attempt on `server`: its timeout_secs / connect_timeout_secs, or the service's
+   */
+  protected deadlineFor(server: ProxyConfig): UpstreamDeadline {
+    return new UpstreamDeadline(upstreamTimeouts(server, this.configManager.getServiceConfig(this.serviceName)), server.name);
+  }
+
+  private bodyTooLargeResponse(maxBodyBytes: number): Response {
+    console.warn(`[proxy:${this.serviceName}] request body over max_request_body_bytes (${maxBodyBytes}), refused`);
+    return this.errorResponse('too_large', `Request body is larger than ${maxBodyBytes} bytes (max_request_body_bytes)`);
+  }
+
+  /**
+   * Put a rendered prompt template in front of the request's own system prompt. The default
+   * covers OpenAI Chat Completions (leading system message) and Responses (instructions).
+   */
+  protected applySystemPrompt(body: any, prompt: string): any {
+    if (Array.isArray(body.messages)) {
+      return { ...body, messages: [{ role: 'system', content: prompt }, ...body.messages] };
     }
+    const instructions = typeof body.instructions === 'string' && body.instructions ? body.instructions : '';
+    return { ...body, instructions: instructions ? `${prompt}\n\n${instructions}` : prompt };
+  }
+
+  /**
+   * Add an instruction after the request's own system prompt: a system message following any leading
+   * ones for Chat Completions, the end of `instructions` for Responses
+   */
+  protected appendSystemInstruction(body: any, instruction: string): any {
+    if (Array.isArray(body.messages)) {
+      const at = body.messages.findIndex((message: any) => message?.role !== 'system' && message?.role !== 'developer');
+      const position = at === -1 ? body.messages.length : at;
+      return {
+        ...body,
+        messages: [...body.messages.slice(0, position), { role: 'system', content: instruction }, ...body.messages.slice(position)],
+      };
+    }
+    const instructions = typeof body.instructions === 'string' && body.instructions ? body.instructions : '';
+    return { ...body, instructions: instructions ? `${instructions}\n\n${instruction}` : instruction };
+  }
+
+  /**
+   * Allow subclasses to manipulate the parsed request body and outbound payload.
+   */
+  protected prepareRequestBody(parsedBody: any, originalText: string): RequestPreparationResult {
+    return {
+      updatedBody: parsedBody,
+      bodyForUpstream: originalText,
+      sanitized: false,
+      thinkingBlocksRemoved: 0,
+    };
+  }
+
+  /**
+   * Allow subclasses to describe how an interrupted stream can be continued; null means unsupported.
+   */
+  protected buildContinuationPlan(_requestBody: any, _partialSse: string): StreamContinuationPlan | null {
+    return null;
+  }
+
+  /**
+   * Whether a finished stream may be extended after hitting max_tokens; its terminal events are held back
+   */
+  protected supportsMaxTokensContinuation(_requestBody: any): boolean {
+    return false;
+  }
+
+  /**
+   * Events that end a message and must reach the client only once no continuation follows
+   */
+  protected isTerminalStreamEvent(_event: SseEvent): boolean {
+    return false;
+  }
+
+  /**
+   * Request headers as stored in the log; the x-paf-config-token secret never is
+   */
+  private loggedRequestHeaders(request: Request): Record<string, string> {
+    const headers: Record<string, string> = {};
+    request.headers.forEach((value, key) => {
+      if (key !== CONFIG_OVERRIDE_TOKEN_HEADER) {
+        headers[key] = value;
+      }
+    });
+    return headers;
+  }
+
+  /**
+   * Whether a stream that fails before its first content event may be restarted on another config
+   */
+  protected supportsPreContentFailover(_requestBody: any): boolean {
+    return false;
+  }
+
+  /**
+   * Events a stream opens with before any content (message_start, ping); held back until content arrives
+   */
+  protected isPreContentStreamEvent(_event: SseEvent): boolean {
+    return false;
   }
 
   /**
-   * Allow subclasses to manipulate the parsed request body and outbound payload.
+   * Describe the follow-up request after a max_tokens stop; null when the stream ended for another reason.
+   * `terminalSse` holds the withheld terminal events, whose rewrites should carry cumulative usage.
    */
-  protected prepareRequestBody(parsedBody: any, originalText: string): RequestPreparationResult {
-    return {
-      updatedBody: parsedBody,
-      bodyForUpstream: originalText,
-      sanitized: false,
-      thinkingBlocksRemoved: 0,
-    };
+  protected buildMaxTokensContinuationPlan(
+    _requestBody: any,
+    _sse: string,
+    _terminalSse: string
+  ): StreamContinuationPlan | null {
+    return null;
   }
 
   /**
@@ -229,39 +1400,77 @@ export abstract class BaseProxyService {
     startTime: number,
     originalRequest: Request,
     requestBodyJson: any,
-    targetUrl: string
+    targetUrl: string,
+    annotations: RequestLogAnnotations,
+    timing: UpstreamTiming,
+    cacheKey?: string
   ): Promise<Response> {
     const duration = Date.now() - startTime;
     const originalUrl = new URL(originalRequest.url);
     const pathWithQuery = `${originalUrl.pathname}${originalUrl.search}`;
 
+    const responseLength = Number(upstreamResponse.headers.get('content-length'));
+    let passthroughReason = this.exceedsPassthroughThreshold(responseLength) ? 'passthrough_body_bytes' : null;
+    let reservedBytes = 0;
+    if (!passthroughReason && this.memoryBudget && responseLength > 0) {
+      if (this.memoryBudget.tryReserve(responseLength)) {
+        reservedBytes = responseLength;
+      } else {
+        passthroughReason = 'memory budget';
+        this.memoryBudget.reportPressure(this.serviceName, 'response_streamed', responseLength);
+      }
+    }
+    if (passthroughReason) {
+      return this.passThroughLargeResponse(
+        upstreamResponse,
+        requestId,
+        server,
+        startTime,
+        originalRequest,
+        requestBodyJson,
+        targetUrl,
+        annotations,
+        timing,
+        responseLength,
+        passthroughReason
+      );
+    }
+
     // Clone response to read body
     const responseClone = upstreamResponse.clone();
     let responseBody: any;
+    let responseText: string | undefined;
+    let responseBytes: number | undefined;
+    let protocolIssues: string[] | undefined;
 
     try {
       const contentType = upstreamResponse.headers.get('content-type') || '';
-      if (contentType.includes('application/json')) {
-        responseBody = await responseClone.json();
-      } else {
-        responseBody = await responseClone.text();
+      responseText = await responseClone.text();
+      responseBytes = Buffer.byteLength(responseText);
+      if (this.memoryBudget && reservedBytes === 0) {
+        this.memoryBudget.reserve(responseText.length);
+        reservedBytes = responseText.length;
       }
+      if (upstreamResponse.ok && this.shouldCheckCompliance(originalUrl.pathname)) {
+        protocolIssues = checkJsonResponse(originalUrl.pathname, contentType, responseText);
+        this.warnProtocolIssues(server, protocolIssues);
+      }
+      responseBody = contentType.includes('application/json') ? JSON.parse(responseText) : responseText;
     } catch (error) {
       console.error('Failed to read response body:', error);
     }
+    const bodyReadAt = Date.now();
 
     // Parse usage information
     const usage = this.logger.parseUsage(responseBody);
 
     // Extract request and response info
-    const requestInfo = this.logger.extractRequestInfo(requestBodyJson);
-    const responsePreview = this.logger.extractResponsePreview(responseBody);
+    const stripThinking = this.shouldStripThinking();
+    const requestInfo = this.logger.extractRequestInfo(requestBodyJson, stripThinking);
+    const responsePreview = this.logger.extractResponsePreview(responseBody, stripThinking);
 
     // Collect request headers
-    const requestHeaders: Record<string, string> = {};
-    originalRequest.headers.forEach((value, key) => {
-      requestHeaders[key] = value;
-    });
+    const requestHeaders = this.loggedRequestHeaders(originalRequest);
 
     // Collect response headers for logging
     const headersForLogging: Record<string, string> = {};
@@ -270,99 +1479,431 @@ export abstract class BaseProxyService {
     });
 
     // Log request
+    try {
+      await this.logger.logRequest({
+        id: requestId,
+        timestamp: startTime,
+        service: this.serviceName,
+        method: originalRequest.method,
+        path: pathWithQuery,
+        targetUrl,
+        configName: server.name,
+        statusCode: upstreamResponse.status,
+        duration,
+        inputTokens: usage.inputTokens,
+        outputTokens: usage.outputTokens,
+        thinkingTokens: usage.thinkingTokens,
+        cacheReadTokens: usage.cacheReadTokens,
+        cacheWriteTokens: usage.cacheWriteTokens,
+        model: usage.model,
+        requestModel: requestInfo.model,
+        requestBody: requestInfo.preview,
+        responsePreview,
+        requestHeaders,
+        responseHeaders: headersForLogging,
+        proxyMs: timing.fetchStartedAt - startTime,
+        headersMs: timing.headersAt - timing.fetchStartedAt,
+        bodyMs: bodyReadAt - timing.headersAt,
+        responseBytes,
+        protocolIssues,
+        ...annotations,
+        upstreamId: this.logger.extractUpstreamId(responseBody),
+      });
+    } finally {
+      this.memoryBudget?.release(reservedBytes);
+    }
+
+    // Filter headers per service policy; content-encoding/length are always dropped
+    // because the client receives the already-decompressed body
+    const modifiedHeaders = this.buildClientResponseHeaders(upstreamResponse, requestId);
+
+    // Only clean successes are worth repeating; responses with protocol issues are not
+    if (cacheKey && upstreamResponse.ok && responseText !== undefined && !protocolIssues?.length) {
+      const upstreamHeaders: [string, string][] = [];
+      upstreamResponse.headers.forEach((value, key) => {
+        upstreamHeaders.push([key, value]);
+      });
+      this.responseCache!.set({
+        key: cacheKey,
+        service: this.serviceName,
+        path: originalUrl.pathname,
+        model: requestInfo.model,
+        configName: server.name,
+        status: upstreamResponse.status,
+        headers: upstreamHeaders,
+        body: responseText,
+      });
+    }
+    if (cacheKey) {
+      modifiedHeaders.set(RESPONSE_CACHE_HEADER, 'miss');
+    }
+
+    return new Response(upstreamResponse.body, {
+      status: upstreamResponse.status,
+      statusText: upstreamResponse.statusText,
+      headers: modifiedHeaders,
+    });
+  }
+
+  /**
+   * Forward an oversized non-streaming response without buffering it (over passthrough_body_bytes or
+   * the memory budget); the log gets status and timings but no usage or preview, since those would
+   * require holding the whole body
+   */
+  private async passThroughLargeResponse(
+    upstreamResponse: Response,
+    requestId: string,
+    server: ProxyConfig,
+    startTime: number,
+    originalRequest: Request,
+    requestBodyJson: any,
+    targetUrl: string,
+    annotations: RequestLogAnnotations,
+    timing: UpstreamTiming,
+    responseLength: number,
+    reason: string
+  ): Promise<Response> {
+    const originalUrl = new URL(originalRequest.url);
+    const requestInfo = this.logger.extractRequestInfo(requestBodyJson, this.shouldStripThinking());
+
+    const requestHeaders = this.loggedRequestHeaders(originalRequest);
+    const headersForLogging: Record<string, string> = {};
+    upstreamResponse.headers.forEach((value, key) => {
+      headersForLogging[key] = value;
+    });
+
     await this.logger.logRequest({
       id: requestId,
       timestamp: startTime,
       service: this.serviceName,
       method: originalRequest.method,
-      path: pathWithQuery,
+      path: `${originalUrl.pathname}${originalUrl.search}`,
       targetUrl,
       configName: server.name,
       statusCode: upstreamResponse.status,
-      duration,
-      inputTokens: usage.inputTokens,
-      outputTokens: usage.outputTokens,
-      model: usage.model,
+      duration: Date.now() - startTime,
       requestModel: requestInfo.model,
       requestBody: requestInfo.preview,
-      responsePreview,
+      responsePreview: `[${responseLength} bytes streamed without buffering: ${reason}]`,
       requestHeaders,
       responseHeaders: headersForLogging,
+      proxyMs: timing.fetchStartedAt - startTime,
+      headersMs: timing.headersAt - timing.fetchStartedAt,
+      responseBytes: responseLength > 0 ? responseLength : undefined,
+      ...annotations,
     });
 
-    // Clone response and remove content-encoding header to prevent decompression errors
-    // This ensures the client receives uncompressed data
-    const modifiedHeaders = new Headers(upstreamResponse.headers);
-    modifiedHeaders.delete('content-encoding');
-    modifiedHeaders.delete('content-length'); // Content-Length may be invalid after decompression
-
     return new Response(upstreamResponse.body, {
       status: upstreamResponse.status,
       statusText: upstreamResponse.statusText,
-      headers: modifiedHeaders,
+      headers: this.buildClientResponseHeaders(upstreamResponse, requestId),
     });
   }
 
+  private exceedsPassthroughThreshold(contentLength: number): boolean {
+    return this.passthroughBodyBytes > 0 && Number.isFinite(contentLength) && contentLength > this.passthroughBodyBytes;
+  }
+
   /**
    * Handle streaming response (SSE)
    */
   private handleStreamingResponse(
     upstreamResponse: Response,
     requestId: string,
-    server: ProxyConfig,
+    initialServer: ProxyConfig,
     startTime: number,
     originalRequest: Request,
     requestBodyJson: any,
-    targetUrl: string
+    targetUrl: string,
+    servers: ProxyConfig[],
+    balancer: LoadBalancer,
+    annotations: RequestLogAnnotations,
+    timing: UpstreamTiming
   ): Response {
     const { readable, writable } = new TransformStream();
     const writer = writable.getWriter();
-    const reader = upstreamResponse.body!.getReader();
-    const decoder = new TextDecoder();
+    let server = initialServer;
+    let reader = upstreamResponse.body!.getReader();
+    let decoder = new TextDecoder();
     const originalUrl = new URL(originalRequest.url);
     const pathWithQuery = `${originalUrl.pathname}${originalUrl.search}`;
 
     // Collect headers early
-    const requestHeaders: Record<string, string> = {};
-    originalRequest.headers.forEach((value, key) => {
-      requestHeaders[key] = value;
-    });
+    const requestHeaders = this.loggedRequestHeaders(originalRequest);
 
     const headersForLogging: Record<string, string> = {};
     upstreamResponse.headers.forEach((value, key) => {
       headersForLogging[key] = value;
     });
 
+    const keepaliveSeconds = this.configManager.getServiceConfig(this.serviceName)?.keepaliveIntervalSecs ?? 0;
+    const keepalive = startSseKeepalive(writer, keepaliveSeconds * 1000);
+
+    // With max_tokens continuation on, forward whole events and withhold the terminal ones until we know
+    // whether the message will be extended
+    const continuationLimit = this.supportsMaxTokensContinuation(requestBodyJson)
+      ? this.configManager.getServiceConfig(this.serviceName)?.maxTokensContinuations ?? 0
+      : 0;
+    const holdTerminal = continuationLimit > 0;
+    const encoder = new TextEncoder();
+    let pendingSse = '';
+    let heldTerminal: string[] = [];
+
+    // Until the first content event nothing reaches the client, so a failing upstream can be swapped for
+    // another config without the client noticing. Only a 2xx event stream qualifies: an error body has no
+    // events, and holding it back would replace the client's 4xx (or an already failed-over 5xx) with
+    // another config's answer
+    let awaitingContent =
+      upstreamResponse.ok &&
+      (upstreamResponse.headers.get('content-type') ?? '').includes('text/event-stream') &&
+      this.supportsPreContentFailover(requestBodyJson);
+    let preContentSse = '';
+    const triedServers = [server.name];
+
+    // Stop pulling from upstream as soon as the client goes away so generation is not billed for nothing
+    let clientDisconnected = false;
+    const onClientAbort = () => {
+      clientDisconnected = true;
+      reader.cancel().catch(() => {});
+    };
+    originalRequest.signal.addEventListener('abort', onClientAbort, { once: true });
+
+    // Chunks are kept for usage parsing, logging and resume; past the memory budget they are only forwarded
+    const budget = this.memoryBudget;
+    let capturedBytes = 0;
+    let captureSkipped = false;
+    // Past the budget only the usage-bearing events are kept, so the log still gets token counts
+    const usageTap = new StreamUsageTap();
+
     // Stream response chunks
     (async () => {
       try {
         const chunks: string[] = [];
+        let upstreamError: string | undefined;
+        let firstChunkAt: number | undefined;
+        let upstreamBytes = 0;
+
+        // Failed before any content: start over on another config and stream that attempt instead
+        const failOver = async (): Promise<boolean> => {
+          if (!awaitingContent || captureSkipped || clientDisconnected) {
+            return false;
+          }
+          const failover = await this.failoverStream(
+            requestBodyJson,
+            originalRequest,
+            server,
+            servers,
+            balancer,
+            triedServers,
+            annotations.context
+          );
+          if (!failover) {
+            return false;
+          }
+          console.warn(
+            `[proxy:${this.serviceName}] upstream stream from ${server.name} failed before any content (${upstreamError}); switched to ${failover.server.name}`
+          );
+          balancer.markFailure(server.name);
+          server = failover.server;
+          const span = currentSpan();
+          if (span) {
+            span.config = server.name;
+          }
+          reader = failover.response.body!.getReader();
+          decoder = new TextDecoder();
+          chunks.length = 0;
+          usageTap.reset();
+          preContentSse = '';
+          upstreamError = undefined;
+          return true;
+        };
 
         while (true) {
-          const { done, value } = await reader.read();
+          // Read failures come from upstream unless the client aborted; write failures mean the client went away
+          let result: ReadableStreamReadResult<Uint8Array>;
+          try {
+            result = await reader.read();
+          } catch (error) {
+            if (clientDisconnected || originalRequest.signal.aborted) {
+              clientDisconnected = true;
+            } else {
+              upstreamError = error instanceof Error ? error.message : String(error);
+              if (await failOver()) {
+                continue;
+              }
+            }
+            break;
+          }
 
-          if (done) {
+          if (clientDisconnected) {
+            break;
+          }
+
+          if (result.done) {
+            if (awaitingContent && !upstreamError) {
+              // A clean end before any content is a cut too; a real message always ends with message_stop
+              upstreamError = 'stream ended before any content';
+              if (await failOver()) {
+                continue;
+              }
+            }
+            break;
+          }
+
+          firstChunkAt ??= Date.now();
+          upstreamBytes += result.value.byteLength;
+
+          // Decode chunk before writing so keepalive pings never split an event
+          const chunk = decoder.decode(result.value, { stream: true });
+          if (!captureSkipped) {
+            if (!budget || budget.tryReserve(chunk.length)) {
+              capturedBytes += chunk.length;
+              chunks.push(chunk);
+            } else {
+              captureSkipped = true;
+              budget.reportPressure(this.serviceName, 'capture_skipped', 0);
+            }
+          }
+          usageTap.push(chunk);
+          keepalive.noteChunk(chunk);
+
+          if (awaitingContent) {
+            preContentSse += chunk;
+            if (splitSseEvents(preContentSse).events.every(event => this.isPreContentStreamEvent(event))) {
+              continue;
+            }
+            awaitingContent = false;
+          }
+          const pending = preContentSse || chunk;
+          preContentSse = '';
+
+          // Write chunk to output stream; each chunk is flushed to the client as it arrives
+          try {
+            if (holdTerminal) {
+              const { events, remainder } = splitSseEvents(pendingSse + pending);
+              pendingSse = remainder;
+              const passthrough = events
+                .filter(event => {
+                  if (this.isTerminalStreamEvent(event)) {
+                    heldTerminal.push(event.raw);
+                    return false;
+                  }
+                  return true;
+                })
+                .map(event => event.raw)
+                .join('');
+              if (passthrough) {
+                await writer.write(encoder.encode(passthrough));
+              }
+            } else {
+              await writer.write(pending === chunk ? result.value : encoder.encode(pending));
+            }
+          } catch {
+            clientDisconnected = true;
+            await reader.cancel().catch(() => {});
             break;
           }
+        }
+
+        originalRequest.signal.removeEventListener('abort', onClientAbort);
+        const upstreamEndedAt = Date.now();
+
+        // Judged on the upstream's own events only, before any resumed or continued segments are appended
+        let protocolIssues: string[] | undefined;
+        if (
+          !upstreamError &&
+          !clientDisconnected &&
+          !captureSkipped &&
+          upstreamResponse.ok &&
+          this.shouldCheckCompliance(originalUrl.pathname)
+        ) {
+          protocolIssues = checkSseResponse(originalUrl.pathname, chunks.join(''), requestBodyJson);
+          this.warnProtocolIssues(server, protocolIssues);
+        }
 
-          // Write chunk to output stream
-          await writer.write(value);
+        let resumedOn: string | undefined;
+        if (upstreamError) {
+          console.warn(
+            `[proxy:${this.serviceName}] upstream stream from ${server.name} interrupted: ${upstreamError}`
+          );
+          balancer.markFailure(server.name);
+
+          // A partial transcript can't be resumed, so skipped capture also rules out resuming
+          const resumed = captureSkipped || awaitingContent
+            ? null
+            : await this.tryResumeStream(
+                writer,
+                requestBodyJson,
+                chunks.join(''),
+                originalRequest,
+                server,
+                servers,
+                balancer,
+                annotations.context
+              );
+          if (resumed) {
+            chunks.push(resumed.sse);
+            resumedOn = resumed.server.name;
+          } else {
+            // Tell the client the stream was cut rather than letting it look like a normal end
+            const code = awaitingContent ? 'paf_stream_failed_before_content' : 'paf_stream_interrupted';
+            await writer.write(encoder.encode(this.buildStreamErrorEvent(upstreamError, code)));
+          }
+        } else if (holdTerminal && !clientDisconnected) {
+          const continued = captureSkipped
+            ? null
+            : await this.continueAfterMaxTokens(
+                writer,
+                requestBodyJson,
+                chunks.join(''),
+                heldTerminal,
+                originalRequest,
+                server,
+                continuationLimit,
+                annotations.context
+              );
+          if (continued && continued.count > 0) {
+            chunks.push(continued.sse, ...continued.terminal);
+            heldTerminal = continued.terminal;
+          }
 
-          // Decode chunk
-          const chunk = decoder.decode(value, { stream: true });
-          chunks.push(chunk);
+          try {
+            await writer.write(encoder.encode(heldTerminal.join('') + pendingSse));
+          } catch {
+            clientDisconnected = true;
+          }
         }
 
-        // Complete the stream
-        await writer.close();
+        // Complete the stream; a disconnected client has nothing left to close
+        keepalive.stop();
+        if (clientDisconnected) {
+          console.log(
+            `[proxy:${this.serviceName}] client disconnected mid-stream; cancelled upstream ${server.name}`
+          );
+          await writer.abort().catch(() => {});
+        } else {
+          await writer.close();
+        }
 
-        // Parse final usage from collected chunks
+        // Parse final usage from collected chunks, plus what the tap saw after capture stopped
         const fullResponse = chunks.join('');
-        const usage = this.parseStreamingUsage(fullResponse);
+        const usageSource = captureSkipped ? fullResponse + usageTap.events() : fullResponse;
+        const usage = this.parseStreamingUsage(usageSource);
 
         // Extract request and response info
-        const requestInfo = this.logger.extractRequestInfo(requestBodyJson);
-        const responsePreview = fullResponse.substring(0, 500);
+        const stripThinking = this.shouldStripThinking();
+        const requestInfo = this.logger.extractRequestInfo(requestBodyJson, stripThinking);
+        let responsePreview = (stripThinking ? stripStreamThinking(fullResponse) : fullResponse).substring(0, 500);
+        let outputTokens = usage.outputTokens;
+
+        // Salvage what was generated before the cut: content preview plus a token estimate
+        if (upstreamError || clientDisconnected) {
+          const partialText = extractStreamText(fullResponse);
+          if (partialText) {
+            responsePreview = partialText.substring(0, 500);
+          }
+          outputTokens = outputTokens ?? estimateTokens(partialText);
+        }
 
         // Log request
         const duration = Date.now() - startTime;
@@ -377,24 +1918,51 @@ export abstract class BaseProxyService {
           statusCode: upstreamResponse.status,
           duration,
           inputTokens: usage.inputTokens,
-          outputTokens: usage.outputTokens,
+          outputTokens,
+          thinkingTokens: usage.thinkingTokens,
+          cacheReadTokens: usage.cacheReadTokens,
+          cacheWriteTokens: usage.cacheWriteTokens,
           model: usage.model,
           requestModel: requestInfo.model,
           requestBody: requestInfo.preview,
           responsePreview,
           requestHeaders,
           responseHeaders: headersForLogging,
+          error: upstreamError
+            ? `Upstream stream interrupted: ${upstreamError}${resumedOn ? ` (continued on ${resumedOn})` : ''}`
+            : clientDisconnected
+              ? 'Client disconnected mid-stream'
+              : undefined,
+          outcome: upstreamError ? 'interrupted' : clientDisconnected ? 'client_disconnected' : undefined,
+          proxyMs: timing.fetchStartedAt - startTime,
+          headersMs: timing.headersAt - timing.fetchStartedAt,
+          firstByteMs: firstChunkAt !== undefined ? firstChunkAt - timing.headersAt : undefined,
+          // Resumed or continued segments are excluded; they run against a fresh upstream call
+          bodyMs: firstChunkAt !== undefined ? upstreamEndedAt - firstChunkAt : undefined,
+          responseBytes: upstreamBytes,
+          protocolIssues,
+          ...annotations,
+          upstreamId: this.parseStreamingUpstreamId(usageSource),
         });
       } catch (error) {
+        keepalive.stop();
+        originalRequest.signal.removeEventListener('abort', onClientAbort);
         console.error('Streaming error:', error);
-        await writer.abort(error);
+        await writer.abort(error).catch(() => {});
+      } finally {
+        budget?.release(capturedBytes);
       }
     })();
 
-    // Return streaming response
-    const modifiedHeaders = new Headers(upstreamResponse.headers);
-    modifiedHeaders.delete('content-encoding');
-    modifiedHeaders.delete('content-length');
+    // Return streaming response; disable intermediary buffering so events arrive as they are produced
+    const modifiedHeaders = this.buildClientResponseHeaders(upstreamResponse, requestId);
+    if (!modifiedHeaders.has('content-type')) {
+      modifiedHeaders.set('content-type', 'text/event-stream');
+    }
+    if (modifiedHeaders.get('content-type')?.includes('text/event-stream')) {
+      modifiedHeaders.set('cache-control', 'no-cache');
+      modifiedHeaders.set('x-accel-buffering', 'no');
+    }
 
     return new Response(readable, {
       status: upstreamResponse.status,
@@ -403,6 +1971,383 @@ export abstract class BaseProxyService {
     });
   }
 
+  /**
+   * Whether a failed response may be retried on another config: 5xx or 429, a body that can be sent again,
+   * and a config chosen by the load balancer rather than an operator pin or experiment arm
+   */
+  private shouldFailOver(
+    response: Response,
+    request: Request,
+    bodyStreamed: boolean,
+    experiment: ExperimentAssignment | null,
+    pinned: ProxyConfig | null
+  ): boolean {
+    if (response.status !== 429 && response.status < 500) {
+      return false;
+    }
+    return !bodyStreamed && !experiment && !pinned && !request.signal.aborted;
+  }
+
+  /**
+   * Configs with room under their rate limits; when every candidate is limited, wait up to
+   * RATE_LIMIT_MAX_WAIT_MS for the first one to free up rather than fail right away
+   */
+  private async admitByRateLimit(
+    configs: ProxyConfig[],
+    tokens: number,
+    signal: AbortSignal
+  ): Promise<{ configs: ProxyConfig[]; waitedMs: number } | { retryAt: number; limit: number }> {
+    const limiter = this.rateLimiter!;
+    const startedAt = Date.now();
+    while (true) {
+      const open = limiter.available(configs, tokens);
+      if (open.length > 0) {
+        return { configs: open, waitedMs: Date.now() - startedAt };
+      }
+      const soonest = limiter.soonest(configs, tokens)!;
+      const waitedMs = Date.now() - startedAt;
+      if (signal.aborted || waitedMs + soonest.waitMs > RATE_LIMIT_MAX_WAIT_MS) {
+        return {
+          retryAt: Date.now() + soonest.waitMs,
+          limit: soonest.config.requestsPerMinute ?? soonest.config.tokensPerMinute ?? 0,
+        };
+      }
+      await Bun.sleep(soonest.waitMs);
+    }
+  }
+
+  private withinRateLimits(configs: ProxyConfig[]): ProxyConfig[] {
+    return this.rateLimiter ? this.rateLimiter.available(configs, 0) : configs;
+  }
+
+  /**
+   * The next config for a request whose attempt on `failed` answered 5xx/429; null when every
+   * candidate was tried or the failed config's retry budget is spent
+   */
+  private nextFailoverServer(
+    candidates: ProxyConfig[],
+    failed: ProxyConfig,
+    tried: string[],
+    balancer: LoadBalancer
+  ): ProxyConfig | null {
+    const untried = this.withinRateLimits(
+      candidates.filter(candidate => candidate.enabled !== false && !tried.includes(candidate.name))
+    );
+    if (untried.length === 0 || !balancer.tryConsumeRetry(failed.name)) {
+      return null;
+    }
+    const next = balancer.selectServer(untried);
+    if (next) {
+      balancer.recordRequest(next.name);
+    }
+    return next;
+  }
+
+  /**
+   * Send the request again to a config not tried yet, for a stream that failed before the client saw content.
+   * Returns the new attempt once it answered 200 with a body; null when no config or retry budget is left.
+   */
+  private async failoverStream(
+    requestBodyJson: any,
+    originalRequest: Request,
+    failedServer: ProxyConfig,
+    servers: ProxyConfig[],
+    balancer: LoadBalancer,
+    tried: string[],
+    context?: RequestContext
+  ): Promise<{ server: ProxyConfig; response: Response } | null> {
+    while (requestBodyJson && balancer.tryConsumeRetry(failedServer.name)) {
+      const server = balancer.selectServer(this.withinRateLimits(servers.filter(s => !tried.includes(s.name))));
+      if (!server) {
+        return null;
+      }
+      tried.push(server.name);
+      this.rateLimiter?.take(server, 0);
+
+      const upstreamUrl = this.followUpUrl(server, originalRequest);
+      const headers = this.buildForwardHeaders(originalRequest, server);
+      this.setUpstreamAcceptEncoding(headers, server, true);
+      try {
+        const response = await this.fetchFollowUp(server, upstreamUrl, originalRequest, headers, requestBodyJson);
+        noteAttempt(context, { kind: 'stream_failover', config: server.name, url: upstreamUrl, status: response.status });
+        if (response.ok && response.body) {
+          balancer.recordRequest(server.name);
+          return { server, response };
+        }
+        await response.body?.cancel().catch(() => {});
+      } catch (error) {
+        if (originalRequest.signal.aborted) {
+          return null;
+        }
+        noteAttempt(context, {
+          kind: 'stream_failover',
+          config: server.name,
+          url: upstreamUrl,
+          error: error instanceof Error ? error.message : String(error),
+        });
+      }
+      balancer.markFailure(server.name);
+      failedServer = server;
+    }
+    return null;
+  }
+
+  private followUpUrl(server: ProxyConfig, originalRequest: Request): string {
+    const url = new URL(originalRequest.url);
+    const path = upstreamTranslation(server.apiFormat, url.pathname)?.path ?? url.pathname;
+    return `${server.baseUrl.replace(/\/+$/, '')}${path}${url.search}`;
+  }
+
+  /**
+   * Send a stream failover, resume or continuation request; translated like the first attempt when
+   * the config has an api_format, so the events that come back are always in the client's format
+   */
+  private async fetchFollowUp(
+    server: ProxyConfig,
+    upstreamUrl: string,
+    originalRequest: Request,
+    headers: Record<string, string>,
+    body: any
+  ): Promise<Response> {
+    const translation = upstreamTranslation(server.apiFormat, new URL(originalRequest.url).pathname);
+    const deadline = this.deadlineFor(server);
+    let response: Response;
+    try {
+      response = await fetch(upstreamUrl, {
+        method: originalRequest.method,
+        headers,
+        body: JSON.stringify(translation ? translation.request(body) : body),
+        signal: deadline.signal(originalRequest.signal),
+        timeout: deadline.fetchTimeout(),
+        proxy: await this.proxyFor(server),
+      });
+    } catch (error) {
+      deadline.clear();
+      throw error;
+    }
+    deadline.responded();
+    return translation ? translation.response(response, body) : response;
+  }
+
+  /**
+   * Continue an interrupted stream on another config when the service opted in and the protocol allows
+   */
+  private async tryResumeStream(
+    writer: WritableStreamDefaultWriter<any>,
+    requestBodyJson: any,
+    partialSse: string,
+    originalRequest: Request,
+    failedServer: ProxyConfig,
+    servers: ProxyConfig[],
+    balancer: LoadBalancer,
+    context?: RequestContext
+  ): Promise<{ server: ProxyConfig; sse: string } | null> {
+    const serviceConfig = this.configManager.getServiceConfig(this.serviceName);
+    if (!serviceConfig?.resumeInterruptedStreams || !requestBodyJson) {
+      return null;
+    }
+
+    const plan = this.buildContinuationPlan(requestBodyJson, partialSse);
+    if (!plan) {
+      return null;
+    }
+
+    if (!balancer.tryConsumeRetry(failedServer.name)) {
+      console.warn(
+        `[proxy:${this.serviceName}] retry budget exhausted for ${failedServer.name}; not continuing stream`
+      );
+      return null;
+    }
+
+    const server = balancer.selectServer(servers.filter(s => s.name !== failedServer.name));
+    if (!server) {
+      return null;
+    }
+
+    const upstreamUrl = this.followUpUrl(server, originalRequest);
+    const headers = this.buildForwardHeaders(originalRequest, server);
+    this.setUpstreamAcceptEncoding(headers, server, true);
+
+    try {
+      const response = await this.fetchFollowUp(server, upstreamUrl, originalRequest, headers, plan.body);
+      noteAttempt(context, { kind: 'stream_resume', config: server.name, url: upstreamUrl, status: response.status });
+
+      if (!response.ok || !response.body) {
+        balancer.markFailure(server.name);
+        await response.body?.cancel().catch(() => {});
+        return null;
+      }
+
+      console.log(
+        `[proxy:${this.serviceName}] continuing interrupted stream from ${failedServer.name} on ${server.name}`
+      );
+
+      const reader = response.body.getReader();
+      const decoder = new TextDecoder();
+      const encoder = new TextEncoder();
+      let buffer = '';
+      let forwarded = '';
+
+      while (true) {
+        const { done, value } = await reader.read();
+        if (done) {
+          break;
+        }
+
+        buffer += decoder.decode(value, { stream: true });
+        const { events, remainder } = splitSseEvents(buffer);
+        buffer = remainder;
+
+        for (const event of events) {
+          const rewritten = plan.rewriteEvent(event);
+          if (rewritten) {
+            await writer.write(encoder.encode(rewritten));
+            forwarded += rewritten;
+          }
+        }
+      }
+
+      balancer.markSuccess(server.name);
+      return { server, sse: forwarded };
+    } catch (error) {
+      console.warn(`[proxy:${this.serviceName}] stream continuation on ${server.name} failed:`, error);
+      noteAttempt(context, {
+        kind: 'stream_resume',
+        config: server.name,
+        url: upstreamUrl,
+        error: error instanceof Error ? error.message : String(error),
+      });
+      balancer.markFailure(server.name);
+      return null;
+    }
+  }
+
+  /**
+   * Keep extending a stream that stopped at max_tokens, up to `limit` follow-up requests on the same config.
+   * Returns the forwarded events and the terminal events the client should finally receive.
+   */
+  private async continueAfterMaxTokens(
+    writer: WritableStreamDefaultWriter<any>,
+    requestBodyJson: any,
+    sse: string,
+    terminal: string[],
+    originalRequest: Request,
+    server: ProxyConfig,
+    limit: number,
+    context?: RequestContext
+  ): Promise<{ sse: string; terminal: string[]; count: number }> {
+    const encoder = new TextEncoder();
+    let transcript = sse;
+    let forwarded = '';
+    let count = 0;
+
+    while (count < limit) {
+      const plan = this.buildMaxTokensContinuationPlan(requestBodyJson, transcript, terminal.join(''));
+      if (!plan) {
+        break;
+      }
+
+      const upstreamUrl = this.followUpUrl(server, originalRequest);
+      const headers = this.buildForwardHeaders(originalRequest, server);
+      this.setUpstreamAcceptEncoding(headers, server, true);
+
+      try {
+        const response = await this.fetchFollowUp(server, upstreamUrl, originalRequest, headers, plan.body);
+        noteAttempt(context, {
+          kind: 'max_tokens_continuation',
+          config: server.name,
+          url: upstreamUrl,
+          status: response.status,
+        });
+        if (!response.ok || !response.body) {
+          console.warn(`[proxy:${this.serviceName}] max_tokens continuation on ${server.name} returned ${response.status}`);
+          await response.body?.cancel().catch(() => {});
+          break;
+        }
+
+        const reader = response.body.getReader();
+        const decoder = new TextDecoder();
+        const nextTerminal: string[] = [];
+        let buffer = '';
+
+        while (true) {
+          const { done, value } = await reader.read();
+          if (done) {
+            break;
+          }
+
+          buffer += decoder.decode(value, { stream: true });
+          const { events, remainder } = splitSseEvents(buffer);
+          buffer = remainder;
+
+          for (const event of events) {
+            const rewritten = plan.rewriteEvent(event);
+            if (!rewritten) {
+              continue;
+            }
+            if (this.isTerminalStreamEvent(event)) {
+              nextTerminal.push(rewritten);
+            } else {
+              await writer.write(encoder.encode(rewritten));
+              transcript += rewritten;
+              forwarded += rewritten;
+            }
+          }
+        }
+
+        // A continuation that never finished leaves the previous stop reason in place
+        if (nextTerminal.length === 0) {
+          break;
+        }
+        terminal = nextTerminal;
+        count++;
+      } catch (error) {
+        console.warn(`[proxy:${this.serviceName}] max_tokens continuation on ${server.name} failed:`, error);
+        noteAttempt(context, {
+          kind: 'max_tokens_continuation',
+          config: server.name,
+          url: upstreamUrl,
+          error: error instanceof Error ? error.message : String(error),
+        });
+        break;
+      }
+    }
+
+    if (count > 0) {
+      console.log(`[proxy:${this.serviceName}] extended max_tokens response on ${server.name} ${count} time(s)`);
+    }
+    return { sse: forwarded, terminal, count };
+  }
+
+  /**
+   * Final SSE event emitted when the upstream stream fails mid-flight; `code` tells clients whether any
+   * content was delivered before the cut, i.e. whether they can simply retry
+   */
+  protected buildStreamErrorEvent(message: string, code: StreamErrorCode): string {
+    // Anthropic clients only know their own error types; overloaded_error is the one they retry
+    const type =
+      serviceErrorDialect(this.serviceName) === 'anthropic'
+        ? code === 'paf_stream_failed_before_content' ? 'overloaded_error' : 'api_error'
+        : 'upstream_stream_error';
+    const payload = {
+      type: 'error',
+      error: {
+        type,
+        message: `Upstream stream interrupted: ${message}`,
+        code,
+      },
+    };
+    return `event: error\ndata: ${JSON.stringify(payload)}\n\n`;
+  }
+
+  private buildClientResponseHeaders(upstreamResponse: Response, requestId: string): Headers {
+    const policy = this.configManager.getServiceConfig(this.serviceName)?.responseHeaders;
+    const headers = filterResponseHeaders(upstreamResponse.headers, policy);
+    // Lets client-side tools look the request up in /api/logs/:id and /api/logs/:id/context
+    headers.set(REQUEST_ID_HEADER, requestId);
+    return headers;
+  }
+
   private async maybeFreezeAfterFailure(server: ProxyConfig): Promise<void> {
     if (!this.loadBalancer.hasExceededFailureThreshold(server.name)) {
       return;
@@ -461,11 +2406,21 @@ export abstract class BaseProxyService {
   /**
    * Build headers for upstream request
    */
-  private buildForwardHeaders(request: Request, server: ProxyConfig): Record<string, string> {
+  private buildForwardHeaders(request: Request, server: ProxyConfig, context?: RequestContext): Record<string, string> {
     const headers: Record<string, string> = {};
 
-    // Forward almost all original headers to mimic legacy proxy behaviour.
-    const excluded = new Set(['host', 'content-length', 'authorization', 'x-api-key']);
+    // Forward almost all original headers to mimic legacy proxy behaviour; proxy-only headers stay here.
+    const excluded = new Set([
+      'host',
+      'content-length',
+      'authorization',
+      'x-api-key',
+      TAGS_HEADER,
+      QUEUED_REQUEST_HEADER,
+      CONFIG_OVERRIDE_HEADER,
+      CONFIG_OVERRIDE_TOKEN_HEADER,
+      NO_LOG_HEADER,
+    ]);
     request.headers.forEach((value, key) => {
       if (!excluded.has(key)) {
         headers[key] = value;
@@ -478,13 +2433,25 @@ export abstract class BaseProxyService {
       // Fallback: let fetch compute the host header if baseUrl is invalid.
     }
 
+    // Normalize the client fingerprint; explicit per-config headers below still win
+    const fingerprint = server.fingerprint ?? this.configManager.getServiceConfig(this.serviceName)?.fingerprint;
+    applyFingerprint(headers, fingerprint);
+    if (fingerprint && fingerprint.mode !== 'passthrough') {
+      noteTransform(context, 'fingerprint', fingerprint.mode);
+    }
+
     // Use per-config auth if provided, otherwise forward from client headers.
     if (server.headers) {
+      const applied: string[] = [];
       for (const [key, value] of Object.entries(server.headers)) {
         if (typeof value === 'string' && value.length > 0) {
           headers[key.toLowerCase()] = value;
+          applied.push(key.toLowerCase());
         }
       }
+      if (applied.length > 0) {
+        noteTransform(context, 'config_headers', applied.join(', '));
+      }
     }
 
     if (server.apiKey) {
@@ -519,48 +2486,96 @@ export abstract class BaseProxyService {
   /**
    * Parse usage from streaming response
    */
-  protected parseStreamingUsage(fullResponse: string): {
-    inputTokens?: number;
-    outputTokens?: number;
-    model?: string;
-  } {
+  protected parseStreamingUsage(fullResponse: string): ReturnType<RequestLogger['parseUsage']> {
+    const usage: ReturnType<RequestLogger['parseUsage']> = {};
+
     try {
-      // Extract SSE events
-      const events = fullResponse.split('\n\n');
+      const { events } = splitSseEvents(`${fullResponse}\n\n`);
 
       for (const event of events) {
-        if (event.includes('message_stop') || event.includes('[DONE]')) {
+        const data = parseSseData(event);
+        if (!data) {
           continue;
         }
 
-        const dataMatch = event.match(/data: (.+)/);
-        if (dataMatch) {
-          const data = JSON.parse(dataMatch[1]);
+        // Anthropic: message_start carries input tokens and model, message_delta the cumulative output
+        if (data.type === 'message_start' && data.message) {
+          usage.inputTokens = data.message.usage?.input_tokens ?? usage.inputTokens;
+          usage.outputTokens = data.message.usage?.output_tokens ?? usage.outputTokens;
+          usage.cacheReadTokens = data.message.usage?.cache_read_input_tokens ?? usage.cacheReadTokens;
+          usage.cacheWriteTokens = data.message.usage?.cache_creation_input_tokens ?? usage.cacheWriteTokens;
+          usage.model = data.message.model ?? usage.model;
+          continue;
+        }
+        if (data.type === 'message_delta' && data.usage) {
+          usage.outputTokens = data.usage.output_tokens ?? usage.outputTokens;
+          usage.inputTokens = data.usage.input_tokens ?? usage.inputTokens;
+          usage.cacheReadTokens = data.usage.cache_read_input_tokens ?? usage.cacheReadTokens;
+          usage.cacheWriteTokens = data.usage.cache_creation_input_tokens ?? usage.cacheWriteTokens;
+          continue;
+        }
 
-          // Anthropic format
-          if (data.type === 'message_stop' && data.message?.usage) {
-            return {
-              inputTokens: data.message.usage.input_tokens,
-              outputTokens: data.message.usage.output_tokens,
-              model: data.message.model,
-            };
-          }
+        // OpenAI Responses: the final response.completed event holds the usage
+        if (data.type === 'response.completed' && data.response?.usage) {
+          usage.inputTokens = data.response.usage.input_tokens;
+          usage.outputTokens = data.response.usage.output_tokens;
+          usage.thinkingTokens = data.response.usage.output_tokens_details?.reasoning_tokens;
+          usage.cacheReadTokens = data.response.usage.input_tokens_details?.cached_tokens;
+          usage.model = data.response.model ?? usage.model;
+          continue;
+        }
 
-          // OpenAI format
-          if (data.usage) {
-            return {
-              inputTokens: data.usage.prompt_tokens,
-              outputTokens: data.usage.completion_tokens,
-              model: data.model,
-            };
-          }
+        // OpenAI chat completions: usage arrives on the last chunk
+        if (data.usage?.prompt_tokens !== undefined) {
+          usage.inputTokens = data.usage.prompt_tokens;
+          usage.outputTokens = data.usage.completion_tokens;
+          usage.thinkingTokens = data.usage.completion_tokens_details?.reasoning_tokens;
+          usage.cacheReadTokens = data.usage.prompt_tokens_details?.cached_tokens;
+          usage.model = data.model ?? usage.model;
+        }
+      }
+
+      // Anthropic reports no thinking breakdown; estimate from the streamed thinking text
+      if (usage.thinkingTokens === undefined) {
+        const thinking = extractStreamThinking(fullResponse);
+        if (thinking) {
+          usage.thinkingTokens = estimateTokens(thinking);
         }
       }
     } catch (error) {
       console.error('Failed to parse streaming usage:', error);
     }
 
-    return {};
+    return usage;
+  }
+
+  protected shouldStripThinking(): boolean {
+    return this.configManager.getServiceConfig(this.serviceName)?.thinkingContent === 'strip';
+  }
+
+  /**
+   * Provider object id announced at the start of a stream
+   */
+  protected parseStreamingUpstreamId(fullResponse: string): string | undefined {
+    for (const event of fullResponse.split('\n\n')) {
+      const dataMatch = event.match(/data: (.+)/);
+      if (!dataMatch || dataMatch[1] === '[DONE]') {
+        continue;
+      }
+
+      try {
+        const data = JSON.parse(dataMatch[1]);
+        // Anthropic message_start, OpenAI Responses events, then chat completion chunks
+        const id = data.message?.id ?? data.response?.id ?? data.id;
+        if (typeof id === 'string') {
+          return id;
+        }
+      } catch {
+        // Not JSON; keep scanning
+      }
+    }
+
+    return undefined;
   }
 }
 
@@ -0,0 +1,36 @@
+// Request body limit - a service's max_request_body_bytes, enforced before the body is buffered so
+// one oversized upload can't take the daemon's memory with it
+
+/**
+ * A positive byte limit from TOML or the API; anything else means no limit
+ */
+export function parseBodyLimit(value: unknown): number | undefined {
+  return typeof value === 'number' && Number.isFinite(value) && value > 0 ? Math.floor(value) : undefined;
+}
+
+/**
+ * Read the body as text, giving up (and cancelling the upload) as soon as it passes `maxBytes`; null then.
+ * For bodies without content-length, where the size is only known while reading.
+ */
+export async function readTextWithin(request: Request, maxBytes: number): Promise<string | null> {
+  if (!request.body) {
+    return '';
+  }
+  const reader = request.body.getReader();
+  const chunks: Uint8Array[] = [];
+  let bytes = 0;
+  while (true) {
+    const { done, value } = await reader.read();
+    if (done) {
+      break;
+    }
+    bytes += value.byteLength;
+    if (bytes > maxBytes) {
+      // Not awaited: on a cloned request the cancel only settles once the other branch is cancelled too
+      reader.cancel().catch(() => {});
+      return null;
+    }
+    chunks.push(value);
+  }
+  return Buffer.concat(chunks).toString('utf8');
+}
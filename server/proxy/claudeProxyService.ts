@@ -1,12 +1,30 @@
-import type { BaseProxyOptions } from './baseProxyService';
+import type { ProxyConfig } from '../config/types';
+import type { BaseProxyOptions, StreamContinuationPlan } from './baseProxyService';
+import { ANTHROPIC_BETA_HEADER, mergeAnthropicBetas } from './anthropicBeta';
 import { BaseProxyService } from './baseProxyService';
+import { parseSseData, splitSseEvents, type SseEvent } from './streamSalvage';
 
 export class ClaudeProxyService extends BaseProxyService {
   constructor(options: Omit<BaseProxyOptions, 'serviceName'>) {
     super({ ...options, serviceName: 'claude' });
   }
 
-  protected override adjustForwardHeaders(headers: Record<string, string>): void {
+  protected override adjustForwardHeaders(
+    headers: Record<string, string>,
+    _request: Request,
+    server: ProxyConfig
+  ): void {
+    // An OpenAI-compatible upstream takes a bearer token and has no use for the Anthropic headers
+    if (server.apiFormat === 'openai-chat') {
+      if (!headers['authorization'] && headers['x-api-key']) {
+        headers['authorization'] = `Bearer ${headers['x-api-key']}`;
+      }
+      for (const name of ['x-api-key', 'anthropic-version', ANTHROPIC_BETA_HEADER]) {
+        delete headers[name];
+      }
+      return;
+    }
+
     // Anthropic expects the API key in x-api-key; fall back to Authorization header if present
     if (!headers['x-api-key']) {
       const authHeader = headers['authorization'];
@@ -19,5 +37,230 @@ export class ClaudeProxyService extends BaseProxyService {
     if (!headers['anthropic-version']) {
       headers['anthropic-version'] = '2023-06-01';
     }
+
+    // Betas enabled on the config apply to every client without them having to send the header
+    const betas = mergeAnthropicBetas(headers[ANTHROPIC_BETA_HEADER], server.anthropicBeta);
+    if (betas) {
+      headers[ANTHROPIC_BETA_HEADER] = betas;
+    }
+  }
+
+  /**
+   * Messages takes the system prompt as a top-level string or list of text blocks
+   */
+  protected override applySystemPrompt(body: any, prompt: string): any {
+    if (Array.isArray(body.system)) {
+      return { ...body, system: [{ type: 'text', text: prompt }, ...body.system] };
+    }
+    const system = typeof body.system === 'string' && body.system ? body.system : '';
+    return { ...body, system: system ? `${prompt}\n\n${system}` : prompt };
+  }
+
+  protected override appendSystemInstruction(body: any, instruction: string): any {
+    if (Array.isArray(body.system)) {
+      return { ...body, system: [...body.system, { type: 'text', text: instruction }] };
+    }
+    const system = typeof body.system === 'string' && body.system ? body.system : '';
+    return { ...body, system: system ? `${system}\n\n${instruction}` : instruction };
+  }
+
+  /**
+   * Continue a cut Messages stream by prefilling the generated text as an assistant turn.
+   * Only possible while every block so far is text and the last one is still open.
+   */
+  protected override buildContinuationPlan(requestBody: any, partialSse: string): StreamContinuationPlan | null {
+    if (!Array.isArray(requestBody?.messages) || requestBody.stream !== true) {
+      return null;
+    }
+
+    let started = false;
+    let openIndex: number | null = null;
+    let prefix = '';
+
+    for (const event of splitSseEvents(partialSse).events) {
+      const data = parseSseData(event);
+      if (!data) {
+        continue;
+      }
+
+      if (data.type === 'message_start') {
+        started = true;
+      } else if (data.type === 'content_block_start') {
+        if (data.content_block?.type !== 'text') {
+          return null;
+        }
+        openIndex = data.index;
+      } else if (data.type === 'content_block_delta' && data.delta?.type === 'text_delta') {
+        prefix += data.delta.text ?? '';
+      } else if (data.type === 'content_block_stop') {
+        openIndex = null;
+      } else if (data.type === 'message_delta' || data.type === 'message_stop') {
+        return null;
+      }
+    }
+
+    // Anthropic rejects assistant prefill that ends in whitespace
+    prefix = prefix.trimEnd();
+    if (!started || openIndex === null || !prefix) {
+      return null;
+    }
+
+    const messages = withAssistantPrefill(requestBody.messages, prefix);
+    if (!messages) {
+      return null;
+    }
+
+    const offset = openIndex;
+    let skippedFirstBlockStart = false;
+
+    const rewriteEvent = (event: SseEvent): string | null => {
+      const data = parseSseData(event);
+      if (!data || typeof data.type !== 'string') {
+        return event.raw;
+      }
+
+      if (data.type === 'message_start' || data.type === 'ping') {
+        return null;
+      }
+
+      // The client already has the interrupted text block open; keep appending to it
+      if (data.type === 'content_block_start' && data.index === 0 && !skippedFirstBlockStart) {
+        skippedFirstBlockStart = true;
+        return null;
+      }
+
+      if (typeof data.index === 'number') {
+        data.index += offset;
+      }
+
+      return `event: ${data.type}\ndata: ${JSON.stringify(data)}\n\n`;
+    };
+
+    return {
+      body: { ...requestBody, messages },
+      rewriteEvent,
+    };
+  }
+
+  protected override supportsMaxTokensContinuation(requestBody: any): boolean {
+    return Array.isArray(requestBody?.messages) && requestBody.stream === true;
+  }
+
+  protected override isTerminalStreamEvent(event: SseEvent): boolean {
+    const data = parseSseData(event);
+    return data?.type === 'message_delta' || data?.type === 'message_stop';
+  }
+
+  protected override supportsPreContentFailover(requestBody: any): boolean {
+    return Array.isArray(requestBody?.messages) && requestBody.stream === true;
+  }
+
+  // An empty content_block_start is still only framing; the first delta is the first content
+  protected override isPreContentStreamEvent(event: SseEvent): boolean {
+    const data = parseSseData(event);
+    return data?.type === 'message_start' || data?.type === 'ping' || data?.type === 'content_block_start';
+  }
+
+  /**
+   * Extend a message that stopped at max_tokens by prefilling everything generated so far.
+   * The follow-up's blocks are appended after the existing ones and its usage is added to the totals.
+   */
+  protected override buildMaxTokensContinuationPlan(
+    requestBody: any,
+    sse: string,
+    terminalSse: string
+  ): StreamContinuationPlan | null {
+    const delta = splitSseEvents(terminalSse).events
+      .map(parseSseData)
+      .find(data => data?.type === 'message_delta');
+    if (delta?.delta?.stop_reason !== 'max_tokens') {
+      return null;
+    }
+
+    let blockCount = 0;
+    let startInput: number | undefined;
+    let prefix = '';
+
+    for (const event of splitSseEvents(sse).events) {
+      const data = parseSseData(event);
+      if (data?.type === 'message_start') {
+        startInput = data.message?.usage?.input_tokens;
+      } else if (data?.type === 'content_block_start') {
+        // Thinking and tool_use blocks cannot be replayed as plain assistant text
+        if (data.content_block?.type !== 'text') {
+          return null;
+        }
+        blockCount = Math.max(blockCount, data.index + 1);
+      } else if (data?.type === 'content_block_delta' && data.delta?.type === 'text_delta') {
+        prefix += data.delta.text ?? '';
+      }
+    }
+
+    // Anthropic rejects assistant prefill that ends in whitespace
+    const messages = withAssistantPrefill(requestBody.messages, prefix.trimEnd());
+    if (!messages) {
+      return null;
+    }
+
+    const priorOutput: number = delta.usage?.output_tokens ?? 0;
+    const priorInput: number | undefined = delta.usage?.input_tokens ?? startInput;
+    let legInput: number | undefined;
+
+    const rewriteEvent = (event: SseEvent): string | null => {
+      const data = parseSseData(event);
+      if (!data || typeof data.type !== 'string') {
+        return event.raw;
+      }
+
+      if (data.type === 'message_start') {
+        legInput = data.message?.usage?.input_tokens;
+        return null;
+      }
+      if (data.type === 'ping') {
+        return null;
+      }
+
+      if (typeof data.index === 'number') {
+        data.index += blockCount;
+      }
+      if (data.type === 'message_delta') {
+        const input = data.usage?.input_tokens ?? legInput;
+        data.usage = {
+          ...data.usage,
+          output_tokens: priorOutput + (data.usage?.output_tokens ?? 0),
+          ...(priorInput !== undefined || input !== undefined
+            ? { input_tokens: (priorInput ?? 0) + (input ?? 0) }
+            : {}),
+        };
+      }
+
+      return `event: ${data.type}\ndata: ${JSON.stringify(data)}\n\n`;
+    };
+
+    return {
+      body: { ...requestBody, messages },
+      rewriteEvent,
+    };
+  }
+}
+
+/**
+ * Append generated text as an assistant turn (or extend a trailing string assistant turn); null if impossible
+ */
+function withAssistantPrefill(source: any[], prefix: string): any[] | null {
+  if (!prefix) {
+    return null;
+  }
+
+  const messages = [...source];
+  const last = messages[messages.length - 1];
+  if (last?.role === 'assistant') {
+    if (typeof last.content !== 'string') {
+      return null;
+    }
+    messages[messages.length - 1] = { ...last, content: `${last.content}${prefix}` };
+  } else {
+    messages.push({ role: 'assistant', content: prefix });
   }
+  return messages;
 }
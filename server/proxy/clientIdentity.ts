@@ -0,0 +1,12 @@
+// Client identity - who sent a proxy request, as established by the proxy port (e.g. a TLS client certificate)
+
+// Kept beside the request rather than in a header, so clients can't claim an identity themselves
+const identities = new WeakMap<Request, string>();
+
+export function setClientIdentity(request: Request, identity: string): void {
+  identities.set(request, identity);
+}
+
+export function clientIdentityOf(request: Request): string | undefined {
+  return identities.get(request);
+}
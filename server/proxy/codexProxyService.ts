@@ -1,3 +1,4 @@
+import type { ProxyConfig } from '../config/types';
 import type { BaseProxyOptions } from './baseProxyService';
 import { BaseProxyService } from './baseProxyService';
 
@@ -5,4 +6,25 @@ export class CodexProxyService extends BaseProxyService {
   constructor(options: Omit<BaseProxyOptions, 'serviceName'>) {
     super({ ...options, serviceName: 'codex' });
   }
+
+  protected override adjustForwardHeaders(
+    headers: Record<string, string>,
+    _request: Request,
+    server: ProxyConfig
+  ): void {
+    if (server.apiFormat !== 'anthropic') {
+      return;
+    }
+
+    // Translated to Messages: Anthropic reads the key from x-api-key and needs a version header
+    if (!headers['x-api-key']) {
+      const authHeader = headers['authorization'];
+      const bearerPrefix = 'bearer ';
+      if (authHeader?.toLowerCase().startsWith(bearerPrefix)) {
+        headers['x-api-key'] = authHeader.slice(bearerPrefix.length).trim();
+      }
+    }
+    headers['anthropic-version'] ??= '2023-06-01';
+    delete headers['openai-organization'];
+  }
 }
@@ -0,0 +1,81 @@
+// Concurrency limits - cap in-flight proxied requests (system-wide and per service) so slow upstreams
+// can't pile up unbounded open requests; over the cap, clients get a 429 to retry instead
+
+// Suggested to rejected clients; slots free up as soon as any in-flight response finishes
+export const CONCURRENCY_RETRY_AFTER_MS = 1000;
+
+export interface ConcurrencySnapshot {
+  active: number;
+  limit: number; // 0 means unlimited
+  rejected: number;
+}
+
+/**
+ * Non-blocking counting semaphore. The limit is read on every acquire, so config reloads apply at once.
+ */
+export class ConcurrencyLimiter {
+  private limit: () => number;
+  private active = 0;
+  private rejected = 0;
+
+  constructor(limit: number | (() => number)) {
+    this.limit = typeof limit === 'number' ? () => limit : limit;
+  }
+
+  /**
+   * Take a slot; returns its release function (safe to call more than once), or null at the limit
+   */
+  tryAcquire(): (() => void) | null {
+    const limit = this.limit();
+    if (limit > 0 && this.active >= limit) {
+      this.rejected++;
+      return null;
+    }
+    this.active++;
+    let released = false;
+    return () => {
+      if (!released) {
+        released = true;
+        this.active--;
+      }
+    };
+  }
+
+  snapshot(): ConcurrencySnapshot {
+    return { active: this.active, limit: Math.max(0, this.limit()), rejected: this.rejected };
+  }
+}
+
+/**
+ * Call `release` once the response body has been fully delivered, failed or was cancelled by the client;
+ * a streamed response holds its slot for as long as the stream runs
+ */
+export function releaseWhenDone(response: Response, release: () => void): Response {
+  if (!response.body) {
+    release();
+    return response;
+  }
+
+  const reader = response.body.getReader();
+  const body = new ReadableStream<Uint8Array>({
+    async pull(controller) {
+      try {
+        const { done, value } = await reader.read();
+        if (done) {
+          release();
+          controller.close();
+        } else {
+          controller.enqueue(value);
+        }
+      } catch (error) {
+        release();
+        controller.error(error);
+      }
+    },
+    cancel(reason) {
+      release();
+      return reader.cancel(reason);
+    },
+  });
+  return new Response(body, { status: response.status, statusText: response.statusText, headers: response.headers });
+}
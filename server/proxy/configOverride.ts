@@ -0,0 +1,74 @@
+// Config override - `x-paf-config: <name>` sends a single request to that config, bypassing the load
+// balancer, so clients can compare providers without pinning the whole service
+
+import { timingSafeEqual } from 'crypto';
+import type { ConfigOverridePolicy, ProxyConfig, ServiceConfig } from '../config/types';
+import type { ProxyErrorKind } from './errors';
+
+export const CONFIG_OVERRIDE_HEADER = 'x-paf-config';
+// Required alongside the override when [config_override] sets a token
+export const CONFIG_OVERRIDE_TOKEN_HEADER = 'x-paf-config-token';
+
+/**
+ * Read a service's `[config_override]` table; without an `allow` list the header stays disabled
+ */
+export function parseConfigOverridePolicy(data: any): ConfigOverridePolicy | undefined {
+  if (!data || typeof data !== 'object' || !Array.isArray(data.allow)) {
+    return undefined;
+  }
+  const allow = data.allow.filter((name: unknown): name is string => typeof name === 'string' && name.trim() !== '');
+  if (allow.length === 0) {
+    return undefined;
+  }
+  const token = typeof data.token === 'string' && data.token ? data.token : undefined;
+  return { allow, token };
+}
+
+/**
+ * TOML shape of the policy, the inverse of parseConfigOverridePolicy
+ */
+export function serializeConfigOverridePolicy(
+  policy: ConfigOverridePolicy | undefined
+): Record<string, unknown> | undefined {
+  return policy ? { allow: policy.allow, token: policy.token } : undefined;
+}
+
+/**
+ * The config a request forces, null without the header, or why it may not
+ */
+export function resolveConfigOverride(
+  request: Request,
+  serviceName: string,
+  serviceConfig: ServiceConfig | undefined
+): { config: ProxyConfig | null } | { kind: ProxyErrorKind; error: string } {
+  const name = request.headers.get(CONFIG_OVERRIDE_HEADER)?.trim();
+  if (!name) {
+    return { config: null };
+  }
+
+  const policy = serviceConfig?.configOverride;
+  if (!policy) {
+    return { kind: 'permission', error: `${CONFIG_OVERRIDE_HEADER} is not enabled for ${serviceName} (see [config_override])` };
+  }
+  if (policy.token && !tokenMatches(request.headers.get(CONFIG_OVERRIDE_TOKEN_HEADER), policy.token)) {
+    return { kind: 'authentication', error: `${CONFIG_OVERRIDE_HEADER} requires a valid ${CONFIG_OVERRIDE_TOKEN_HEADER}` };
+  }
+  if (!policy.allow.includes('*') && !policy.allow.includes(name)) {
+    return { kind: 'permission', error: `Config "${name}" may not be chosen with ${CONFIG_OVERRIDE_HEADER}` };
+  }
+
+  const config = serviceConfig.configs.find(c => c.name === name);
+  if (!config) {
+    return { kind: 'not_found', error: `No ${serviceName} config named "${name}"` };
+  }
+  if (config.enabled === false) {
+    return { kind: 'permission', error: `Config "${name}" is disabled` };
+  }
+  return { config };
+}
+
+function tokenMatches(provided: string | null, expected: string): boolean {
+  const a = Buffer.from(provided?.trim() ?? '');
+  const b = Buffer.from(expected);
+  return a.length === b.length && timingSafeEqual(a, b);
+}
@@ -0,0 +1,122 @@
+// Connection statistics - per-upstream-host latency and connection failures
+
+export type ConnectionErrorKind = 'dns' | 'connect' | 'tls' | 'timeout' | 'other';
+
+interface HostStats {
+  requests: number;
+  totalHeadersMs: number;
+  maxHeadersMs: number;
+  recentHeadersMs: number[];
+  errors: Record<ConnectionErrorKind, number>;
+  lastError?: { kind: ConnectionErrorKind; message: string; at: number };
+}
+
+const RECENT_SAMPLE_LIMIT = 200;
+
+/**
+ * Bun's fetch pools connections internally and exposes neither socket reuse nor DNS/TLS
+ * phase timings, so this tracks what is observable: time until response headers and
+ * failures classified by the phase they most likely came from.
+ */
+export class ConnectionStats {
+  private hosts: Map<string, HostStats> = new Map();
+
+  recordResponse(url: string, headersMs: number): void {
+    const stats = this.getOrCreate(url);
+    stats.requests++;
+    stats.totalHeadersMs += headersMs;
+    stats.maxHeadersMs = Math.max(stats.maxHeadersMs, headersMs);
+    stats.recentHeadersMs.push(headersMs);
+    if (stats.recentHeadersMs.length > RECENT_SAMPLE_LIMIT) {
+      stats.recentHeadersMs.shift();
+    }
+  }
+
+  recordError(url: string, error: unknown): ConnectionErrorKind {
+    const stats = this.getOrCreate(url);
+    const kind = classifyConnectionError(error);
+    stats.requests++;
+    stats.errors[kind]++;
+    stats.lastError = {
+      kind,
+      message: error instanceof Error ? error.message : String(error),
+      at: Date.now(),
+    };
+    return kind;
+  }
+
+  /**
+   * Mean time to response headers from the host of `url`; undefined before its first success
+   */
+  averageHeadersMs(url: string): number | undefined {
+    const stats = this.hosts.get(hostOf(url));
+    if (!stats) {
+      return undefined;
+    }
+    const successful = stats.requests - Object.values(stats.errors).reduce((sum, n) => sum + n, 0);
+    return successful > 0 ? stats.totalHeadersMs / successful : undefined;
+  }
+
+  snapshot(): Record<string, unknown> {
+    const result: Record<string, unknown> = {};
+    for (const [host, stats] of this.hosts) {
+      const successful = stats.requests - Object.values(stats.errors).reduce((sum, n) => sum + n, 0);
+      const sorted = stats.recentHeadersMs.slice().sort((a, b) => a - b);
+      result[host] = {
+        requests: stats.requests,
+        connection_errors: { ...stats.errors },
+        avg_headers_ms: successful > 0 ? Math.round(stats.totalHeadersMs / successful) : null,
+        p95_headers_ms: sorted.length > 0 ? sorted[Math.min(sorted.length - 1, Math.floor(sorted.length * 0.95))] : null,
+        max_headers_ms: successful > 0 ? stats.maxHeadersMs : null,
+        last_error: stats.lastError ?? null,
+      };
+    }
+    return result;
+  }
+
+  private getOrCreate(url: string): HostStats {
+    const host = hostOf(url);
+    let stats = this.hosts.get(host);
+    if (!stats) {
+      stats = {
+        requests: 0,
+        totalHeadersMs: 0,
+        maxHeadersMs: 0,
+        recentHeadersMs: [],
+        errors: { dns: 0, connect: 0, tls: 0, timeout: 0, other: 0 },
+      };
+      this.hosts.set(host, stats);
+    }
+    return stats;
+  }
+}
+
+function hostOf(url: string): string {
+  try {
+    return new URL(url).host;
+  } catch {
+    return url;
+  }
+}
+
+/**
+ * Map a fetch failure to the connection phase it most likely came from
+ */
+export function classifyConnectionError(error: unknown): ConnectionErrorKind {
+  const code = String((error as any)?.code ?? '');
+  const text = `${code} ${error instanceof Error ? error.message : String(error)}`;
+
+  if (/ENOTFOUND|EAI_AGAIN|getaddrinfo|dns/i.test(text)) {
+    return 'dns';
+  }
+  if (/CERT|TLS|SSL|self[- ]signed|UNABLE_TO_VERIFY/i.test(text)) {
+    return 'tls';
+  }
+  if (/timeout|timed out|ETIMEDOUT|abort/i.test(text)) {
+    return 'timeout';
+  }
+  if (/refused|reset|ConnectionClosed|FailedToOpenSocket|EPIPE|socket|connect/i.test(text)) {
+    return 'connect';
+  }
+  return 'other';
+}
@@ -0,0 +1,168 @@
+// Context trimming - shrinks prompts that would overflow a config's context window before forwarding
+
+import type { ContextTrimConfig } from '../config/types';
+import type { ErrorDialect } from './errors';
+import { estimateTokens } from './streamSalvage';
+
+const STRATEGIES = ['drop', 'summarize'];
+const SUMMARY_MAX_TOKENS = 1024;
+const SUMMARY_PREFIX = '[Summary of the earlier conversation, condensed by the proxy]';
+
+/**
+ * Read the per-config `context_trim` table; a missing or non-positive limit disables trimming
+ */
+export function parseContextTrim(data: any): ContextTrimConfig | undefined {
+  if (!data || typeof data !== 'object' || typeof data.limit !== 'number' || data.limit <= 0) {
+    return undefined;
+  }
+  const summaryModel = typeof data.summary_model === 'string' && data.summary_model ? data.summary_model : undefined;
+  const strategy = STRATEGIES.includes(data.strategy) ? data.strategy : 'drop';
+  return {
+    limit: Math.floor(data.limit),
+    // Summarizing needs a model to ask; without one it falls back to dropping
+    strategy: strategy === 'summarize' && !summaryModel ? 'drop' : strategy,
+    summaryModel,
+  };
+}
+
+/**
+ * TOML/API shape of a context trim setting, the inverse of parseContextTrim
+ */
+export function serializeContextTrim(config: ContextTrimConfig | undefined): Record<string, unknown> | undefined {
+  if (!config) {
+    return undefined;
+  }
+  return { limit: config.limit, strategy: config.strategy, summary_model: config.summaryModel };
+}
+
+// Chat Completions and Messages keep the conversation in `messages`, the Responses API in `input`
+function conversationKey(body: any): 'messages' | 'input' | null {
+  if (Array.isArray(body?.messages)) {
+    return 'messages';
+  }
+  return Array.isArray(body?.input) ? 'input' : null;
+}
+
+function isSystemItem(item: any): boolean {
+  return item?.role === 'system' || item?.role === 'developer';
+}
+
+/**
+ * Items a conversation must not start with once older turns are gone: assistant turns and the
+ * halves of tool exchanges whose other half was dropped
+ */
+function isOrphanedStart(item: any): boolean {
+  if (item?.role === 'assistant' || item?.role === 'tool') {
+    return true;
+  }
+  if (['function_call', 'function_call_output', 'reasoning'].includes(item?.type)) {
+    return true;
+  }
+  return item?.role === 'user' && Array.isArray(item.content) && item.content.some((part: any) => part?.type === 'tool_result');
+}
+
+/**
+ * Rough prompt size using the same chars/4 estimate as stream salvage; tool definitions count too
+ */
+export function estimatePromptTokens(body: any): number {
+  return estimateTokens(
+    JSON.stringify([body?.system, body?.instructions, body?.tools, body?.messages, body?.input].filter(Boolean))
+  );
+}
+
+/**
+ * Drop the oldest non-system items until the estimate fits `limit`. The latest item always stays,
+ * so an oversized final message is forwarded as is. Null when nothing had to (or could) go.
+ */
+export function trimOldestMessages(body: any, limit: number): { body: any; dropped: any[]; tokens: number } | null {
+  const key = conversationKey(body);
+  let tokens = estimatePromptTokens(body);
+  if (!key || tokens <= limit) {
+    return null;
+  }
+
+  const kept = (body[key] as any[]).map(item => ({ item, tokens: estimateTokens(JSON.stringify(item)) }));
+  const dropped: any[] = [];
+  const firstDroppable = () => kept.findIndex((entry, index) => !isSystemItem(entry.item) && index < kept.length - 1);
+
+  while (tokens > limit) {
+    let index = firstDroppable();
+    if (index === -1) {
+      break;
+    }
+    do {
+      const [entry] = kept.splice(index, 1);
+      dropped.push(entry.item);
+      tokens -= entry.tokens;
+      index = firstDroppable();
+    } while (index !== -1 && isOrphanedStart(kept[index].item));
+  }
+
+  if (dropped.length === 0) {
+    return null;
+  }
+  return { body: { ...body, [key]: kept.map(entry => entry.item) }, dropped, tokens };
+}
+
+function itemText(item: any): string {
+  const content = item?.content ?? item?.output ?? item?.arguments;
+  if (typeof content === 'string') {
+    return content;
+  }
+  if (Array.isArray(content)) {
+    return content
+      .map((part: any) => (typeof part?.text === 'string' ? part.text : typeof part?.content === 'string' ? part.content : ''))
+      .filter(Boolean)
+      .join('\n');
+  }
+  return content === undefined ? '' : JSON.stringify(content);
+}
+
+/**
+ * Request asking `model` to condense the dropped turns; capped at `limit` tokens of transcript
+ */
+export function buildSummaryRequest(
+  dialect: ErrorDialect,
+  model: string,
+  dropped: any[],
+  limit: number
+): { path: string; body: any } {
+  const transcript = dropped
+    .map(item => `${item?.role ?? item?.type ?? 'item'}: ${itemText(item)}`)
+    .join('\n\n')
+    .slice(-limit * 4);
+  const prompt =
+    'Summarize the following earlier part of a conversation so it can replace it. Keep facts, decisions, ' +
+    `file names, code identifiers and open questions; omit pleasantries.\n\n${transcript}`;
+  const messages = [{ role: 'user', content: prompt }];
+
+  return dialect === 'anthropic'
+    ? { path: '/v1/messages', body: { model, max_tokens: SUMMARY_MAX_TOKENS, messages } }
+    : { path: '/v1/chat/completions', body: { model, max_tokens: SUMMARY_MAX_TOKENS, messages } };
+}
+
+export function readSummary(dialect: ErrorDialect, response: any): string | null {
+  const text =
+    dialect === 'anthropic'
+      ? (Array.isArray(response?.content) ? response.content : [])
+          .filter((part: any) => part?.type === 'text')
+          .map((part: any) => part.text)
+          .join('\n')
+      : response?.choices?.[0]?.message?.content;
+  return typeof text === 'string' && text.trim() ? text.trim() : null;
+}
+
+/**
+ * Put the summary where the dropped turns were: right after any leading system items
+ */
+export function insertSummary(body: any, summary: string): any {
+  const key = conversationKey(body);
+  if (!key) {
+    return body;
+  }
+  const items = body[key] as any[];
+  const at = items.findIndex(item => !isSystemItem(item));
+  const position = at === -1 ? items.length : at;
+  const summaryItem = { role: 'user', content: `${SUMMARY_PREFIX}\n${summary}` };
+  return { ...body, [key]: [...items.slice(0, position), summaryItem, ...items.slice(position)] };
+}
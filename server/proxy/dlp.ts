@@ -0,0 +1,140 @@
+// DLP filter - scans outbound request bodies before they leave for an upstream relay
+
+import type { DlpAction, DlpConfig } from '../config/types';
+
+export interface DlpVerdict {
+  blockedBy?: string; // Rule (or "moderation") that stopped the request
+  matches: string[];  // "<action>:<rule>" for every rule that matched, recorded in the request log
+  body?: any;         // Body with redactions applied; only set when something was redacted
+}
+
+interface CompiledRule {
+  name: string;
+  regex: RegExp;
+  action: DlpAction;
+}
+
+// Moderation only needs the gist of the conversation; keep the outbound payload bounded
+const MAX_MODERATION_INPUT = 32_000;
+
+function escapeRegExp(value: string): string {
+  return value.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+}
+
+export class DlpFilter {
+  private rules: CompiledRule[];
+  private moderation: DlpConfig['moderation'];
+
+  constructor(config: DlpConfig) {
+    this.moderation = config.moderation;
+    this.rules = config.rules.map(rule => ({
+      name: rule.name,
+      action: rule.action,
+      regex: rule.pattern
+        ? new RegExp(rule.pattern, 'g')
+        : new RegExp((rule.keywords ?? []).map(escapeRegExp).join('|'), 'gi'),
+    }));
+  }
+
+  isEnabled(): boolean {
+    return this.rules.length > 0 || !!this.moderation;
+  }
+
+  /**
+   * Apply rules to every string in the body, then ask the moderation endpoint if one is configured
+   */
+  async inspect(body: any): Promise<DlpVerdict> {
+    const matched = new Set<string>();
+    let blockedBy: string | undefined;
+    let redacted = false;
+
+    const visit = (value: any): any => {
+      if (typeof value === 'string') {
+        let result = value;
+        for (const rule of this.rules) {
+          rule.regex.lastIndex = 0;
+          if (!rule.regex.test(result)) {
+            continue;
+          }
+          matched.add(`${rule.action}:${rule.name}`);
+          if (rule.action === 'block') {
+            blockedBy ??= rule.name;
+          } else if (rule.action === 'redact') {
+            rule.regex.lastIndex = 0;
+            result = result.replace(rule.regex, `[REDACTED:${rule.name}]`);
+            redacted = true;
+          }
+        }
+        return result;
+      }
+      if (Array.isArray(value)) {
+        return value.map(visit);
+      }
+      if (value && typeof value === 'object') {
+        return Object.fromEntries(Object.entries(value).map(([key, entry]) => [key, visit(entry)]));
+      }
+      return value;
+    };
+
+    const scanned = visit(body);
+    if (blockedBy) {
+      return { blockedBy, matches: [...matched] };
+    }
+
+    if (this.moderation) {
+      const flagged = await this.moderate(scanned);
+      if (flagged) {
+        matched.add(`${this.moderation.action}:moderation`);
+        if (this.moderation.action === 'block') {
+          return { blockedBy: 'moderation', matches: [...matched] };
+        }
+      }
+    }
+
+    return { matches: [...matched], body: redacted ? scanned : undefined };
+  }
+
+  /**
+   * True when the endpoint flags the text; errors follow the fail_open setting
+   */
+  private async moderate(body: any): Promise<boolean> {
+    const moderation = this.moderation!;
+    const strings: string[] = [];
+    const collect = (value: any) => {
+      if (typeof value === 'string') {
+        strings.push(value);
+      } else if (value && typeof value === 'object') {
+        Object.values(value).forEach(collect);
+      }
+    };
+    collect(body);
+
+    const input = strings.join('\n').slice(0, MAX_MODERATION_INPUT);
+    if (!input) {
+      return false;
+    }
+
+    try {
+      const response = await fetch(moderation.url, {
+        method: 'POST',
+        headers: {
+          'content-type': 'application/json',
+          ...(moderation.apiKey ? { authorization: `Bearer ${moderation.apiKey}` } : {}),
+        },
+        body: JSON.stringify({ input }),
+        signal: AbortSignal.timeout(moderation.timeoutMs),
+      });
+      if (!response.ok) {
+        throw new Error(`HTTP ${response.status}`);
+      }
+      const result = (await response.json()) as any;
+      return Array.isArray(result?.results) && result.results.some((entry: any) => entry?.flagged === true);
+    } catch (error) {
+      console.warn(
+        `[dlp] moderation request failed (${error instanceof Error ? error.message : String(error)}); ` +
+          (moderation.failOpen ? 'forwarding request' : 'treating as flagged')
+      );
+      return !moderation.failOpen;
+    }
+  }
+}
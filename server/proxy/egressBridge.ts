@@ -0,0 +1,207 @@
+// Egress bridge - a loopback HTTP proxy that opens upstream connections itself. Bun's fetch has no
+// local-address option and no SOCKS client, so for those fetch is pointed at this bridge through its
+// `proxy` option and the bridge dials with node:net, directly or through a SOCKS5 proxy.
+
+import { once } from 'node:events';
+import { connect, createServer, isIPv6, type AddressInfo, type Server, type Socket } from 'node:net';
+
+/**
+ * Opens a TCP connection to host:port, resolving once it is established. `route` is the username
+ * fetch presented in Proxy-Authorization, which picks the way out (e.g. one SOCKS proxy of several).
+ */
+export type Dialer = (host: string, port: number, route?: string) => Promise<Socket>;
+
+// Longest request head accepted from fetch; real ones are a few hundred bytes
+const MAX_HEAD_BYTES = 64 * 1024;
+
+// Hop-by-hop headers dropped when forwarding a plain-HTTP request
+const HOP_BY_HOP_HEADERS = new Set(['connection', 'keep-alive', 'proxy-connection', 'proxy-authorization']);
+
+/**
+ * Dial directly, optionally from a local address (which also pins the address family)
+ */
+export function directDialer(localAddress?: string): Dialer {
+  return (host, port) =>
+    new Promise((resolve, reject) => {
+      const socket = connect({
+        host,
+        port,
+        localAddress,
+        ...(localAddress ? { family: isIPv6(localAddress) ? 6 : 4 } : {}),
+      });
+      socket.once('error', reject);
+      socket.once('connect', () => {
+        socket.off('error', reject);
+        resolve(socket);
+      });
+    });
+}
+
+export class EgressBridge {
+  private server: Server;
+  private url?: string;
+
+  constructor(private dial: Dialer) {
+    this.server = createServer(client => {
+      void this.handle(client);
+    });
+  }
+
+  /**
+   * Listen on an ephemeral loopback port; returns the proxy URL to hand to fetch
+   */
+  async listen(): Promise<string> {
+    if (this.url) {
+      return this.url;
+    }
+    this.server.listen(0, '127.0.0.1');
+    await once(this.server, 'listening');
+    const { port } = this.server.address() as AddressInfo;
+    this.url = `http://127.0.0.1:${port}`;
+    return this.url;
+  }
+
+  close(): void {
+    this.server.close();
+    this.url = undefined;
+  }
+
+  private async handle(client: Socket): Promise<void> {
+    client.on('error', () => client.destroy());
+
+    let head: RequestHead;
+    try {
+      head = await readRequestHead(client);
+    } catch {
+      client.destroy();
+      return;
+    }
+
+    const target = requestTarget(head);
+    if (!target) {
+      reply(client, '400 Bad Request');
+      return;
+    }
+
+    let upstream: Socket;
+    try {
+      upstream = await this.dial(target.host, target.port, proxyRoute(head));
+    } catch (error) {
+      console.warn(
+        `[network] egress connection to ${target.host}:${target.port} failed:`,
+        error instanceof Error ? error.message : error
+      );
+      reply(client, '502 Bad Gateway');
+      return;
+    }
+
+    upstream.on('error', () => client.destroy());
+    upstream.on('close', () => client.destroy());
+    client.on('close', () => upstream.destroy());
+
+    if (head.method === 'CONNECT') {
+      client.write('HTTP/1.1 200 Connection Established\r\n\r\n');
+    } else {
+      // One request per connection, so the target never has to be re-read from a kept-alive socket
+      upstream.write(forwardedHead(head, target.path));
+    }
+    if (head.rest.length > 0) {
+      upstream.write(head.rest);
+    }
+    client.pipe(upstream);
+    upstream.pipe(client);
+  }
+}
+
+interface RequestHead {
+  method: string;
+  target: string;
+  version: string;
+  headers: string[]; // Raw header lines
+  rest: Buffer;      // Bytes read past the head (a tunnel's first TLS record, a request body)
+}
+
+/**
+ * Read up to the blank line ending the request head, leaving the socket paused
+ */
+function readRequestHead(socket: Socket): Promise<RequestHead> {
+  return new Promise((resolve, reject) => {
+    let buffered = Buffer.alloc(0);
+
+    const finish = (error: Error | null, head?: RequestHead) => {
+      socket.off('data', onData);
+      socket.off('end', onEnd);
+      socket.pause();
+      if (error) {
+        reject(error);
+      } else {
+        resolve(head!);
+      }
+    };
+    const onEnd = () => finish(new Error('connection closed before the request head'));
+    const onData = (chunk: Buffer) => {
+      buffered = Buffer.concat([buffered, chunk]);
+      const end = buffered.indexOf('\r\n\r\n');
+      if (end === -1) {
+        if (buffered.length > MAX_HEAD_BYTES) {
+          finish(new Error('request head too large'));
+        }
+        return;
+      }
+      const [requestLine, ...headers] = buffered.subarray(0, end).toString('latin1').split('\r\n');
+      const [method = '', target = '', version = 'HTTP/1.1'] = requestLine.split(' ');
+      finish(null, { method: method.toUpperCase(), target, version, headers, rest: buffered.subarray(end + 4) });
+    };
+
+    socket.on('data', onData);
+    socket.once('end', onEnd);
+  });
+}
+
+/**
+ * Where a request goes: host:port for CONNECT, an absolute http:// URL otherwise
+ */
+function requestTarget(head: RequestHead): { host: string; port: number; path: string } | null {
+  try {
+    if (head.method === 'CONNECT') {
+      const url = new URL(`tcp://${head.target}`);
+      const port = Number(url.port);
+      return url.hostname && port ? { host: unbracket(url.hostname), port, path: '' } : null;
+    }
+    const url = new URL(head.target);
+    if (url.protocol !== 'http:') {
+      return null;
+    }
+    return { host: unbracket(url.hostname), port: Number(url.port) || 80, path: `${url.pathname}${url.search}` };
+  } catch {
+    return null;
+  }
+}
+
+function proxyRoute(head: RequestHead): string | undefined {
+  for (const line of head.headers) {
+    const colon = line.indexOf(':');
+    if (line.slice(0, colon).trim().toLowerCase() !== 'proxy-authorization') {
+      continue;
+    }
+    const [scheme, encoded] = line.slice(colon + 1).trim().split(/\s+/);
+    if (scheme?.toLowerCase() === 'basic' && encoded) {
+      const [username] = Buffer.from(encoded, 'base64').toString('utf8').split(':');
+      return username || undefined;
+    }
+  }
+  return undefined;
+}
+
+function forwardedHead(head: RequestHead, path: string): string {
+  const headers = head.headers.filter(line => !HOP_BY_HOP_HEADERS.has(line.slice(0, line.indexOf(':')).trim().toLowerCase()));
+  return [`${head.method} ${path} ${head.version}`, ...headers, 'Connection: close', '', ''].join('\r\n');
+}
+
+function unbracket(hostname: string): string {
+  return hostname.startsWith('[') ? hostname.slice(1, -1) : hostname;
+}
+
+function reply(client: Socket, status: string): void {
+  client.end(`HTTP/1.1 ${status}\r\nConnection: close\r\nContent-Length: 0\r\n\r\n`);
+}
@@ -0,0 +1,94 @@
+// Proxy errors - failures raised by paf itself, returned in the error format of the API the client speaks
+
+export type ProxyErrorKind =
+  | 'invalid_request' // Malformed request or prompt template problem
+  | 'authentication'
+  | 'permission'      // Blocked by policy, e.g. DLP
+  | 'not_found'
+  | 'too_large'       // Request body over the service's max_request_body_bytes
+  | 'rate_limit'      // Budgets and local rate limits
+  | 'no_upstream'     // No config available or every config frozen
+  | 'upstream'        // Upstream unreachable or failed before responding
+  | 'internal';
+
+export type ErrorDialect = 'anthropic' | 'openai';
+
+// Lets clients and logs tell paf's own errors apart from ones relayed from an upstream
+export const PROXY_ERROR_HEADER = 'x-paf-error';
+
+// `code` of the error event that ends a cut stream. Before content nothing was delivered and the request can
+// simply be retried; an interrupted stream already delivered part of the message.
+export type StreamErrorCode = 'paf_stream_failed_before_content' | 'paf_stream_interrupted';
+
+const ERROR_KINDS: Record<ProxyErrorKind, { status: number; anthropic: string; openai: string; openaiCode?: string }> = {
+  invalid_request: { status: 400, anthropic: 'invalid_request_error', openai: 'invalid_request_error' },
+  authentication: { status: 401, anthropic: 'authentication_error', openai: 'invalid_request_error', openaiCode: 'invalid_api_key' },
+  permission: { status: 403, anthropic: 'permission_error', openai: 'invalid_request_error', openaiCode: 'permission_denied' },
+  not_found: { status: 404, anthropic: 'not_found_error', openai: 'invalid_request_error', openaiCode: 'not_found' },
+  too_large: { status: 413, anthropic: 'request_too_large', openai: 'invalid_request_error', openaiCode: 'request_too_large' },
+  rate_limit: { status: 429, anthropic: 'rate_limit_error', openai: 'requests', openaiCode: 'rate_limit_exceeded' },
+  // overloaded_error is what Anthropic clients already retry with backoff
+  no_upstream: { status: 503, anthropic: 'overloaded_error', openai: 'server_error', openaiCode: 'service_unavailable' },
+  upstream: { status: 502, anthropic: 'api_error', openai: 'server_error', openaiCode: 'bad_gateway' },
+  internal: { status: 500, anthropic: 'api_error', openai: 'server_error' },
+};
+
+/**
+ * Limiter state behind a throttling error; resetAt is when the client may try again (epoch ms)
+ */
+export interface RateLimitState {
+  limit: number;
+  remaining: number;
+  resetAt: number;
+}
+
+/**
+ * Retry-After plus the x-ratelimit-* headers both Anthropic and OpenAI SDKs read
+ */
+export function rateLimitHeaders(state: RateLimitState, now = Date.now()): Record<string, string> {
+  const resetSeconds = Math.max(1, Math.ceil((state.resetAt - now) / 1000));
+  return {
+    'retry-after': String(resetSeconds),
+    'x-ratelimit-limit-requests': String(state.limit),
+    'x-ratelimit-remaining-requests': String(Math.max(0, state.remaining)),
+    'x-ratelimit-reset-requests': `${resetSeconds}s`,
+  };
+}
+
+/**
+ * Claude ports speak the Anthropic API; everything else is OpenAI-compatible
+ */
+export function serviceErrorDialect(serviceName: string): ErrorDialect {
+  return serviceName === 'claude' ? 'anthropic' : 'openai';
+}
+
+/**
+ * `{"type":"error","error":{...}}` for Anthropic, `{"error":{message,type,param,code}}` for OpenAI
+ */
+export function proxyErrorBody(dialect: ErrorDialect, kind: ProxyErrorKind, message: string): Record<string, unknown> {
+  const spec = ERROR_KINDS[kind];
+  if (dialect === 'anthropic') {
+    return { type: 'error', error: { type: spec.anthropic, message } };
+  }
+  return { error: { message, type: spec.openai, param: null, code: spec.openaiCode ?? null } };
+}
+
+/**
+ * Build the error response; pass the limiter state for throttling errors so clients know when to retry
+ */
+export function proxyErrorResponse(
+  dialect: ErrorDialect,
+  kind: ProxyErrorKind,
+  message: string,
+  headers: Record<string, string> = {},
+  rateLimit?: RateLimitState
+): Response {
+  return Response.json(proxyErrorBody(dialect, kind, message), {
+    status: ERROR_KINDS[kind].status,
+    headers: {
+      ...headers,
+      ...(rateLimit ? rateLimitHeaders(rateLimit) : {}),
+      [PROXY_ERROR_HEADER]: kind,
+    },
+  });
+}
@@ -0,0 +1,159 @@
+// Fan-out - POST /paf/fanout sends one prompt to several configs at once and returns every answer side
+// by side, for comparing models and providers. Each leg is an ordinary x-paf-config request (logged,
+// filtered and mapped like any other), so only configs the service's [config_override] allows can be used.
+
+import type { ProxyConfig } from '../config/types';
+import type { RequestLogger } from '../logging/logger';
+import type { ProxyService } from './baseProxyService';
+import { readTextWithin } from './bodyLimit';
+import { clientIdentityOf, setClientIdentity } from './clientIdentity';
+import { CONFIG_OVERRIDE_HEADER } from './configOverride';
+import type { ProxyErrorKind } from './errors';
+import type { BodyMemoryBudget } from './memoryBudget';
+import { REQUEST_ID_HEADER } from './requestContext';
+
+export const FANOUT_PATH = '/paf/fanout';
+
+export const MAX_FANOUT_CONFIGS = 8;
+
+// Where the body is sent unless the envelope names another path
+const DEFAULT_FANOUT_PATHS: Record<string, string> = {
+  claude: '/v1/messages',
+  codex: '/v1/chat/completions',
+};
+
+export interface FanoutRequest {
+  configs: string[];
+  path: string;
+  body: Record<string, unknown>;
+}
+
+export interface FanoutResult {
+  config: string;
+  status: number;
+  duration_ms: number;
+  request_id?: string;
+  usage: ReturnType<RequestLogger['parseUsage']>;
+  response: unknown; // Parsed JSON, or the raw text when the body isn't JSON
+  error?: string;
+}
+
+/**
+ * Validate the `{configs, path?, body}` envelope; names are deduplicated, order kept
+ */
+export function parseFanoutRequest(data: any, serviceName: string): FanoutRequest | { error: string } {
+  if (!data || typeof data !== 'object') {
+    return { error: 'Expected a JSON object with configs and body' };
+  }
+  if (!Array.isArray(data.configs) || !data.configs.every((name: unknown) => typeof name === 'string' && name.trim())) {
+    return { error: 'configs must be a non-empty array of config names' };
+  }
+  const configs = [...new Set<string>(data.configs.map((name: string) => name.trim()))];
+  if (configs.length === 0) {
+    return { error: 'configs must be a non-empty array of config names' };
+  }
+  if (configs.length > MAX_FANOUT_CONFIGS) {
+    return { error: `At most ${MAX_FANOUT_CONFIGS} configs per fan-out` };
+  }
+  if (!data.body || typeof data.body !== 'object' || Array.isArray(data.body)) {
+    return { error: 'body must be the request body to send to each config' };
+  }
+  const path = data.path ?? DEFAULT_FANOUT_PATHS[serviceName];
+  if (typeof path !== 'string' || !path.startsWith('/') || path.startsWith('/paf/')) {
+    return { error: 'path must be an upstream API path such as /v1/messages' };
+  }
+  return { configs, path, body: data.body };
+}
+
+/**
+ * Read and validate the envelope under the service's max_request_body_bytes, like any proxied body:
+ * declared sizes are refused before reading, chunked ones as soon as they pass the limit
+ */
+export async function readFanoutRequest(
+  request: Request,
+  serviceName: string,
+  maxBodyBytes: number | undefined
+): Promise<FanoutRequest | { kind: ProxyErrorKind; error: string }> {
+  const tooLarge = {
+    kind: 'too_large' as const,
+    error: `Request body is larger than ${maxBodyBytes} bytes (max_request_body_bytes)`,
+  };
+  if (maxBodyBytes && Number(request.headers.get('content-length')) > maxBodyBytes) {
+    return tooLarge;
+  }
+  const text = maxBodyBytes ? await readTextWithin(request, maxBodyBytes) : await request.text();
+  if (text === null) {
+    return tooLarge;
+  }
+  let data: unknown;
+  try {
+    data = JSON.parse(text);
+  } catch {
+    return { kind: 'invalid_request', error: 'Request body is not valid JSON' };
+  }
+  const fanout = parseFanoutRequest(data, serviceName);
+  return 'error' in fanout ? { kind: 'invalid_request', error: fanout.error } : fanout;
+}
+
+/**
+ * Send the body to every config concurrently, without streaming. The caller's headers (client key,
+ * x-paf-config-token, tags) and client identity go with each leg; a leg that fails still gets its own result.
+ */
+export async function fanOut(
+  proxy: ProxyService,
+  request: Request,
+  fanout: FanoutRequest,
+  servers: ProxyConfig[],
+  logger: RequestLogger,
+  budget?: BodyMemoryBudget
+): Promise<FanoutResult[]> {
+  const url = new URL(fanout.path, request.url);
+  const body = JSON.stringify({ ...fanout.body, stream: false });
+  // The shared body is held until every leg is done; each leg reserves its own copy as it is proxied
+  budget?.reserve(body.length);
+
+  const results = Promise.all(fanout.configs.map(async config => {
+    const headers = new Headers(request.headers);
+    headers.delete('content-length');
+    headers.set('content-type', 'application/json');
+    headers.set('accept', 'application/json');
+    headers.set(CONFIG_OVERRIDE_HEADER, config);
+
+    // The client's mTLS identity lives beside the request, so each leg needs it set again
+    const leg = new Request(url, { method: 'POST', headers, body, signal: request.signal });
+    const identity = clientIdentityOf(request);
+    if (identity) {
+      setClientIdentity(leg, identity);
+    }
+
+    const started = Date.now();
+    try {
+      const response = await proxy.handleRequest(leg, servers);
+      const text = await response.text();
+      let parsed: unknown = text;
+      try {
+        parsed = JSON.parse(text);
+      } catch {
+        // Not JSON; keep the text
+      }
+      return {
+        config,
+        status: response.status,
+        duration_ms: Date.now() - started,
+        request_id: response.headers.get(REQUEST_ID_HEADER) ?? undefined,
+        usage: response.ok ? logger.parseUsage(parsed) : {},
+        response: parsed,
+      };
+    } catch (error) {
+      return {
+        config,
+        status: 502,
+        duration_ms: Date.now() - started,
+        usage: {},
+        response: null,
+        error: error instanceof Error ? error.message : 'Fan-out request failed',
+      };
+    }
+  }));
+  return results.finally(() => budget?.release(body.length));
+}
@@ -0,0 +1,72 @@
+// Client fingerprint - the user-agent and SDK metadata headers upstreams may key behaviour on
+
+import type { ClientFingerprint, FingerprintMode } from '../config/types';
+
+const FINGERPRINT_MODES: ReadonlyArray<FingerprintMode> = ['passthrough', 'override', 'strip'];
+
+// Official SDKs send x-stainless-lang, -os, -arch, -runtime, -package-version, -retry-count, ...
+const STAINLESS_PREFIX = 'x-stainless-';
+
+/**
+ * Parse a `fingerprint` table (TOML or API JSON, snake_case); anything unusable yields undefined
+ */
+export function parseFingerprint(data: any): ClientFingerprint | undefined {
+  if (!data || typeof data !== 'object' || !FINGERPRINT_MODES.includes(data.mode)) {
+    return undefined;
+  }
+
+  const stainless: Record<string, string> = {};
+  if (data.stainless && typeof data.stainless === 'object') {
+    for (const [key, value] of Object.entries(data.stainless)) {
+      if (typeof value === 'string') {
+        stainless[key.toLowerCase().replace(STAINLESS_PREFIX, '')] = value;
+      }
+    }
+  }
+
+  return {
+    mode: data.mode,
+    userAgent: typeof data.user_agent === 'string' && data.user_agent ? data.user_agent : undefined,
+    stainless: Object.keys(stainless).length > 0 ? stainless : undefined,
+  };
+}
+
+/**
+ * TOML/API shape of a fingerprint, the inverse of parseFingerprint
+ */
+export function serializeFingerprint(fingerprint: ClientFingerprint | undefined): Record<string, unknown> | undefined {
+  if (!fingerprint) {
+    return undefined;
+  }
+  return {
+    mode: fingerprint.mode,
+    user_agent: fingerprint.userAgent,
+    stainless: fingerprint.stainless,
+  };
+}
+
+/**
+ * Rewrite forwarded headers (lower-case keys) in place. `override` and `strip` both drop the
+ * client's user-agent and x-stainless-* headers; `override` then sends the configured values.
+ * Without a user-agent, fetch sends its own default.
+ */
+export function applyFingerprint(headers: Record<string, string>, fingerprint: ClientFingerprint | undefined): void {
+  if (!fingerprint || fingerprint.mode === 'passthrough') {
+    return;
+  }
+
+  for (const key of Object.keys(headers)) {
+    if (key === 'user-agent' || key.startsWith(STAINLESS_PREFIX)) {
+      delete headers[key];
+    }
+  }
+
+  if (fingerprint.mode === 'override') {
+    if (fingerprint.userAgent) {
+      headers['user-agent'] = fingerprint.userAgent;
+    }
+    for (const [key, value] of Object.entries(fingerprint.stainless ?? {})) {
+      headers[`${STAINLESS_PREFIX}${key}`] = value;
+    }
+  }
+}
@@ -0,0 +1,72 @@
+// Response header policy - decides which upstream headers reach the client
+
+import type { ResponseHeaderPolicy } from '../config/types';
+
+/**
+ * Always removed: hop-by-hop headers, plus encoding/length which no longer match once Bun decodes the body
+ */
+const ALWAYS_STRIPPED = new Set([
+  'connection',
+  'keep-alive',
+  'proxy-authenticate',
+  'proxy-authorization',
+  'te',
+  'trailer',
+  'transfer-encoding',
+  'upgrade',
+  'content-encoding',
+  'content-length',
+]);
+
+/**
+ * Removed unless a service overrides `deny`: CDN and relay internals that Anthropic/OpenAI clients never expect
+ */
+export const DEFAULT_RESPONSE_HEADER_DENY = [
+  'alt-svc',
+  'via',
+  'server-timing',
+  'nel',
+  'report-to',
+  'cf-cache-status',
+  'cf-ray',
+  'x-amz-cf-*',
+  'x-cache',
+  'x-served-by',
+  'x-timer',
+];
+
+function matchesPattern(name: string, pattern: string): boolean {
+  const normalized = pattern.toLowerCase();
+  if (normalized.endsWith('*')) {
+    return name.startsWith(normalized.slice(0, -1));
+  }
+  return name === normalized;
+}
+
+/**
+ * Apply the policy to upstream headers; a non-empty allow-list wins over deny (content-type always passes)
+ */
+export function filterResponseHeaders(headers: Headers, policy?: ResponseHeaderPolicy): Headers {
+  const allow = policy?.allow?.length ? policy.allow : null;
+  const deny = policy?.deny ?? DEFAULT_RESPONSE_HEADER_DENY;
+  const filtered = new Headers();
+
+  headers.forEach((value, key) => {
+    const name = key.toLowerCase();
+    if (ALWAYS_STRIPPED.has(name)) {
+      return;
+    }
+
+    if (allow) {
+      if (name !== 'content-type' && !allow.some(pattern => matchesPattern(name, pattern))) {
+        return;
+      }
+    } else if (deny.some(pattern => matchesPattern(name, pattern))) {
+      return;
+    }
+
+    filtered.append(key, value);
+  });
+
+  return filtered;
+}
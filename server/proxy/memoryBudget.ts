@@ -0,0 +1,105 @@
+// Body memory budget - bounds the bytes held for request/response bodies across every proxied request
+
+export type MemoryPressureAction = 'request_streamed' | 'response_streamed' | 'capture_skipped';
+
+export interface MemoryPressureEvent {
+  service: string;
+  action: MemoryPressureAction;
+  bytes: number;         // Size of the body that did not fit (0 when unknown)
+  bufferedBytes: number; // Bytes held at the time
+  limitBytes: number;
+  timestamp: number;
+}
+
+type PressureListener = (event: MemoryPressureEvent) => void;
+
+// Repeated warnings for the same service/action are collapsed into one per interval
+const WARNING_INTERVAL_MS = 60_000;
+
+/**
+ * Process-wide accounting of buffered body bytes. Callers reserve before buffering and release
+ * once the buffer is dropped; with a limit of 0 everything is counted but nothing is refused.
+ */
+export class BodyMemoryBudget {
+  private limitBytes: number;
+  private bufferedBytes = 0;
+  private peakBytes = 0;
+  private refusals: Record<MemoryPressureAction, number> = {
+    request_streamed: 0,
+    response_streamed: 0,
+    capture_skipped: 0,
+  };
+  private lastWarnedAt = new Map<string, number>();
+  private listeners = new Set<PressureListener>();
+
+  constructor(limitBytes: number) {
+    this.limitBytes = limitBytes;
+  }
+
+  /**
+   * Reserve `bytes` if they fit under the limit
+   */
+  tryReserve(bytes: number): boolean {
+    if (this.limitBytes > 0 && this.bufferedBytes + bytes > this.limitBytes) {
+      return false;
+    }
+    this.reserve(bytes);
+    return true;
+  }
+
+  /**
+   * Count bytes that are already buffered (e.g. a body of unknown length that has been read)
+   */
+  reserve(bytes: number): void {
+    this.bufferedBytes += bytes;
+    this.peakBytes = Math.max(this.peakBytes, this.bufferedBytes);
+  }
+
+  release(bytes: number): void {
+    this.bufferedBytes = Math.max(0, this.bufferedBytes - bytes);
+  }
+
+  /**
+   * Record that a body was not buffered because of the limit and notify listeners (rate limited)
+   */
+  reportPressure(service: string, action: MemoryPressureAction, bytes: number): void {
+    this.refusals[action]++;
+
+    const key = `${service}:${action}`;
+    const now = Date.now();
+    if (now - (this.lastWarnedAt.get(key) ?? 0) < WARNING_INTERVAL_MS) {
+      return;
+    }
+    this.lastWarnedAt.set(key, now);
+
+    const event: MemoryPressureEvent = {
+      service,
+      action,
+      bytes,
+      bufferedBytes: this.bufferedBytes,
+      limitBytes: this.limitBytes,
+      timestamp: now,
+    };
+    console.warn(
+      `[memory] ${service}: ${action.replace('_', ' ')} (${bytes || 'unknown'} bytes; ${this.bufferedBytes}/${this.limitBytes} buffered)`
+    );
+    for (const listener of this.listeners) {
+      listener(event);
+    }
+  }
+
+  onPressure(listener: PressureListener): () => void {
+    this.listeners.add(listener);
+    return () => this.listeners.delete(listener);
+  }
+
+  snapshot(): Record<string, unknown> {
+    return {
+      buffered_bytes: this.bufferedBytes,
+      peak_bytes: this.peakBytes,
+      limit_bytes: this.limitBytes,
+      refusals: { ...this.refusals },
+      rss_bytes: process.memoryUsage().rss,
+    };
+  }
+}
@@ -0,0 +1,109 @@
+// Model list cache - coalesces and briefly caches GET /v1/models so client polling doesn't eat upstream rate limits
+
+export const MODEL_LIST_CACHE_HEADER = 'x-paf-cache';
+
+// hit: served from cache; coalesced: shared an in-flight upstream call; miss: made the upstream call
+export type ModelListCacheStatus = 'hit' | 'coalesced' | 'miss';
+
+interface CachedResponse {
+  status: number;
+  statusText: string;
+  headers: [string, string][];
+  body: ArrayBuffer;
+  storedAt: number;
+}
+
+// The list and single-model lookups, e.g. /v1/models?limit=100 and /v1/models/claude-sonnet-4-5
+const MODEL_LIST_PATH = /\/v1\/models(?:\/[^/]+)?\/?$/;
+
+export function isModelListRequest(method: string, pathname: string): boolean {
+  return method === 'GET' && MODEL_LIST_PATH.test(pathname);
+}
+
+/**
+ * Build the cache key; credentials are hashed in so clients forwarding their own keys never share a list
+ */
+export function modelListCacheKey(configName: string, upstreamUrl: string, headers: Record<string, string>): string {
+  const credentials = `${headers['authorization'] ?? ''}\n${headers['x-api-key'] ?? ''}`;
+  return `${configName}\n${upstreamUrl}\n${Bun.hash(credentials).toString(36)}`;
+}
+
+export class ModelListCache {
+  private ttlMs: number;
+  private entries = new Map<string, CachedResponse>();
+  private inflight = new Map<string, Promise<CachedResponse>>();
+
+  constructor(ttlMs: number) {
+    this.ttlMs = ttlMs;
+  }
+
+  isEnabled(): boolean {
+    return this.ttlMs > 0;
+  }
+
+  /**
+   * Answer from cache, join an identical request already in flight, or call `load`. Only 2xx
+   * responses are cached; errors are shared with concurrent callers but not kept.
+   */
+  async fetch(key: string, load: () => Promise<Response>): Promise<{ response: Response; status: ModelListCacheStatus }> {
+    const now = Date.now();
+    const cached = this.entries.get(key);
+    if (cached && now - cached.storedAt < this.ttlMs) {
+      return { response: toResponse(cached, 'hit'), status: 'hit' };
+    }
+
+    const pending = this.inflight.get(key);
+    if (pending) {
+      return { response: toResponse(await pending, 'coalesced'), status: 'coalesced' };
+    }
+
+    const request = load().then(snapshot);
+    this.inflight.set(key, request);
+    try {
+      const entry = await request;
+      if (entry.status >= 200 && entry.status < 300) {
+        this.prune(now);
+        this.entries.set(key, entry);
+      }
+      return { response: toResponse(entry, 'miss'), status: 'miss' };
+    } finally {
+      this.inflight.delete(key);
+    }
+  }
+
+  clear(): void {
+    this.entries.clear();
+  }
+
+  private prune(now: number): void {
+    for (const [key, entry] of this.entries) {
+      if (now - entry.storedAt >= this.ttlMs) {
+        this.entries.delete(key);
+      }
+    }
+  }
+}
+
+async function snapshot(response: Response): Promise<CachedResponse> {
+  const headers: [string, string][] = [];
+  response.headers.forEach((value, key) => {
+    headers.push([key, value]);
+  });
+  return {
+    status: response.status,
+    statusText: response.statusText,
+    headers,
+    body: await response.arrayBuffer(),
+    storedAt: Date.now(),
+  };
+}
+
+function toResponse(entry: CachedResponse, status: ModelListCacheStatus): Response {
+  const headers = new Headers(entry.headers);
+  headers.set(MODEL_LIST_CACHE_HEADER, status);
+  return new Response(entry.body.slice(0), {
+    status: entry.status,
+    statusText: entry.statusText,
+    headers,
+  });
+}
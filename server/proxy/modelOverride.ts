@@ -0,0 +1,107 @@
+// Model override - per-service default model and alias rewrites applied to request bodies, and
+// per-config model_map renames for upstreams with their own model ids
+
+import type { ModelOverrideConfig } from '../config/types';
+
+/**
+ * Read the service-level `model_override` table; empty settings are dropped
+ */
+export function parseModelOverride(data: any): ModelOverrideConfig | undefined {
+  if (!data || typeof data !== 'object') {
+    return undefined;
+  }
+
+  const aliases: Record<string, string> = {};
+  if (data.aliases && typeof data.aliases === 'object') {
+    for (const [from, to] of Object.entries(data.aliases)) {
+      if (typeof to === 'string' && to && to !== from) {
+        aliases[from] = to;
+      }
+    }
+  }
+  const defaultModel = typeof data.default === 'string' && data.default ? data.default : undefined;
+
+  if (!defaultModel && Object.keys(aliases).length === 0) {
+    return undefined;
+  }
+  return { defaultModel, aliases };
+}
+
+/**
+ * TOML shape of a model override, the inverse of parseModelOverride
+ */
+export function serializeModelOverride(config: ModelOverrideConfig | undefined): Record<string, unknown> | undefined {
+  if (!config) {
+    return undefined;
+  }
+  return {
+    default: config.defaultModel,
+    aliases: Object.keys(config.aliases).length > 0 ? config.aliases : undefined,
+  };
+}
+
+// Bodies that describe a generation or embedding call; anything else is left without a model
+function isModelRequest(body: any): boolean {
+  return ['messages', 'input', 'prompt'].some(key => body[key] !== undefined);
+}
+
+/**
+ * Substitute an aliased model, or fill in the default when the client sent none. Returns null when
+ * the body stays as is; `from` is undefined for a filled-in default.
+ */
+export function applyModelOverride(
+  body: any,
+  config: ModelOverrideConfig | undefined
+): { body: any; from?: string; to: string } | null {
+  if (!config || !body || typeof body !== 'object' || Array.isArray(body)) {
+    return null;
+  }
+
+  const requested = typeof body.model === 'string' && body.model ? body.model : undefined;
+  if (requested) {
+    const to = Object.prototype.hasOwnProperty.call(config.aliases, requested) ? config.aliases[requested] : undefined;
+    return to ? { body: { ...body, model: to }, from: requested, to } : null;
+  }
+
+  if (config.defaultModel && isModelRequest(body)) {
+    return { body: { ...body, model: config.defaultModel }, to: config.defaultModel };
+  }
+  return null;
+}
+
+/**
+ * Read a config's `model_map` table (client model -> upstream model); identity and empty entries are dropped
+ */
+export function parseModelMap(data: any): Record<string, string> | undefined {
+  if (!data || typeof data !== 'object' || Array.isArray(data)) {
+    return undefined;
+  }
+  const map: Record<string, string> = {};
+  for (const [from, to] of Object.entries(data)) {
+    if (from && typeof to === 'string' && to.trim() && to.trim() !== from) {
+      map[from] = to.trim();
+    }
+  }
+  return Object.keys(map).length > 0 ? map : undefined;
+}
+
+/**
+ * Rename the body's model for one config; null when the config has no entry for it
+ */
+export function applyModelMap(
+  body: any,
+  map: Record<string, string> | undefined
+): { body: any; from: string; to: string } | null {
+  if (!map || !body || typeof body !== 'object' || Array.isArray(body) || typeof body.model !== 'string') {
+    return null;
+  }
+  const to = Object.prototype.hasOwnProperty.call(map, body.model) ? map[body.model] : undefined;
+  return to ? { body: { ...body, model: to }, from: body.model, to } : null;
+}
+
+/**
+ * How a substitution is shown in request logs, e.g. "claude-3-sonnet -> claude-3-5-sonnet-latest"
+ */
+export function describeModelOverride(from: string | undefined, to: string): string {
+  return `${from ?? '(none)'} -> ${to}`;
+}
@@ -0,0 +1,207 @@
+// Outbound network preferences shared by every upstream request
+
+import { setDefaultResultOrder } from 'dns';
+import { isIP } from 'node:net';
+import { networkInterfaces, type NetworkInterfaceInfo } from 'node:os';
+import type { ConfigManager } from '../config/manager';
+import type { ProxyConfig, ServiceConfig, SystemConfig } from '../config/types';
+import { directDialer, EgressBridge } from './egressBridge';
+import { isSocksProxyUrl, SOCKS_PROXY_PROTOCOLS, socks5Connect } from './socks';
+
+// Local address from [network] bind_address; upstream connections not made through an HTTP proxy leave from it
+let bindAddress: string | undefined;
+let egressBridge: Promise<string> | undefined;
+// SOCKS proxy_urls by the route name fetch presents to the bridge, so their passwords never leave the process
+const socksRoutes = new Map<string, string>();
+
+/**
+ * Order resolved addresses by the preferred family, e.g. to sidestep providers with broken AAAA records,
+ * and bind outbound sockets to [network] bind_address on multi-homed hosts
+ */
+export async function applyNetworkPreferences(network: SystemConfig['network']): Promise<void> {
+  if (network.ipFamily !== 'auto') {
+    try {
+      setDefaultResultOrder((network.ipFamily === 'ipv4' ? 'ipv4first' : 'ipv6first') as any);
+      console.log(`Upstream DNS results ordered ${network.ipFamily} first`);
+    } catch (error) {
+      console.warn(`Failed to apply ${network.ipFamily} preference:`, error);
+    }
+  }
+
+  if (network.bindAddress) {
+    const local = resolveBindAddress(network.bindAddress, network.ipFamily);
+    if ('error' in local) {
+      console.warn(`[network] ignoring bind_address: ${local.error}`);
+      return;
+    }
+    bindAddress = local.address;
+    await egressBridgeUrl();
+    console.log(`[network] upstream connections leave from ${local.address}`);
+  }
+}
+
+/**
+ * The local address for a bind_address: an address of this host, or an interface name whose address
+ * of the preferred family is used (IPv4 unless ip_family = "ipv6")
+ */
+export function resolveBindAddress(
+  value: string,
+  ipFamily: SystemConfig['network']['ipFamily'],
+  interfaces: NodeJS.Dict<NetworkInterfaceInfo[]> = networkInterfaces()
+): { address: string } | { error: string } {
+  const addresses = Object.values(interfaces).flatMap(entries => entries ?? []);
+  if (isIP(value)) {
+    return addresses.some(entry => entry.address === value)
+      ? { address: value }
+      : { error: `${value} is not an address of this host` };
+  }
+
+  // Link-local IPv6 addresses need a scope id to bind and are never what a multi-homed setup means
+  const usable = (interfaces[value] ?? []).filter(entry => !entry.address.toLowerCase().startsWith('fe80:'));
+  const preferred = ipFamily === 'ipv6' ? 'IPv6' : 'IPv4';
+  const match = usable.find(entry => familyName(entry.family) === preferred) ?? usable[0];
+  if (!match) {
+    return { error: interfaces[value] ? `interface ${value} has no usable address` : `no network interface named ${value}` };
+  }
+  return { address: match.address };
+}
+
+// Node reports the family as a number on some versions
+function familyName(family: string | number): string {
+  return family === 4 ? 'IPv4' : family === 6 ? 'IPv6' : String(family);
+}
+
+/**
+ * Open connections to the upstreams a service would use next (the active config in manual mode,
+ * every enabled config when load balancing) so the first proxied request skips DNS, TCP and TLS.
+ * Bun's fetch speaks HTTP/1.1 upstream, so there is no HTTP/2 session to prime; the warmed
+ * socket simply waits in Bun's keep-alive pool. Returns the origins contacted.
+ */
+export function warmUpServiceConnections(configManager: ConfigManager, serviceName: string): string[] {
+  const origins = new Set<string>();
+  const serviceConfig = configManager.getServiceConfig(serviceName);
+  for (const config of configManager.getAllConfigs(serviceName)) {
+    if (upstreamProxy(config, serviceConfig) || bindAddress) {
+      // Connections go to a proxy (or the bind_address bridge), which preconnect can't open a tunnel through
+      continue;
+    }
+    try {
+      origins.add(new URL(config.baseUrl).origin);
+    } catch {
+      // Invalid base URLs fail loudly on the first request instead
+    }
+  }
+
+  for (const origin of origins) {
+    try {
+      fetch.preconnect(origin);
+    } catch (error) {
+      console.warn(`[network] failed to warm up ${origin}:`, error instanceof Error ? error.message : error);
+    }
+  }
+  return [...origins];
+}
+
+// Bun's fetch tunnels through HTTP(S) proxies (CONNECT) itself; SOCKS5 goes through the egress bridge
+const PROXY_URL_PROTOCOLS = new Set(['http:', 'https:', ...SOCKS_PROXY_PROTOCOLS]);
+
+/**
+ * Why `value` can't be a proxy_url; null when it can
+ */
+export function proxyUrlError(value: unknown): string | null {
+  if (typeof value !== 'string' || !value.trim()) {
+    return 'proxy_url must be a URL such as http://proxy.internal:3128';
+  }
+  let url: URL;
+  try {
+    url = new URL(value.trim());
+  } catch {
+    return `invalid proxy_url ${JSON.stringify(value)}`;
+  }
+  if (url.protocol.startsWith('socks') && !SOCKS_PROXY_PROTOCOLS.has(url.protocol)) {
+    return `${url.protocol.slice(0, -1).toUpperCase()} proxies are not supported; use socks5:// or socks5h://`;
+  }
+  if (!PROXY_URL_PROTOCOLS.has(url.protocol)) {
+    return `unsupported proxy_url scheme ${url.protocol}; expected http:, https:, socks5: or socks5h:`;
+  }
+  return null;
+}
+
+/**
+ * The proxy a config's upstream requests go through: its own proxy_url, else the service's
+ */
+export function upstreamProxy(config: ProxyConfig, serviceConfig?: ServiceConfig): string | undefined {
+  return config.proxyUrl ?? serviceConfig?.proxyUrl;
+}
+
+/**
+ * The proxy fetch uses for a config: an HTTP proxy_url as is, the egress bridge for a SOCKS5 one, else
+ * the bridge when outbound sockets are bound to [network] bind_address. Connections to an HTTP
+ * proxy_url are not bound; connections to a SOCKS5 proxy are.
+ */
+export async function fetchProxy(config: ProxyConfig, serviceConfig?: ServiceConfig): Promise<string | undefined> {
+  const proxy = upstreamProxy(config, serviceConfig);
+  if (proxy && isSocksProxyUrl(proxy)) {
+    const url = new URL(await egressBridgeUrl());
+    url.username = socksRoute(proxy);
+    url.password = 'paf';
+    return url.toString();
+  }
+  if (proxy || !bindAddress) {
+    return proxy;
+  }
+  return egressBridgeUrl();
+}
+
+/**
+ * Start the egress bridge on first use
+ */
+function egressBridgeUrl(): Promise<string> {
+  egressBridge ??= new EgressBridge(dialEgress).listen().catch(error => {
+    egressBridge = undefined;
+    throw error;
+  });
+  return egressBridge;
+}
+
+function dialEgress(host: string, port: number, route?: string) {
+  const direct = directDialer(bindAddress);
+  if (!route) {
+    // Only bind_address sends direct connections through the bridge
+    return bindAddress ? direct(host, port) : Promise.reject(new Error('no egress route given'));
+  }
+  const socksUrl = socksRoutes.get(route);
+  if (!socksUrl) {
+    return Promise.reject(new Error(`unknown egress route ${route}`));
+  }
+  return socks5Connect(socksUrl, host, port, direct);
+}
+
+function socksRoute(proxyUrl: string): string {
+  for (const [route, url] of socksRoutes) {
+    if (url === proxyUrl) {
+      return route;
+    }
+  }
+  const route = `socks${socksRoutes.size + 1}`;
+  socksRoutes.set(route, proxyUrl);
+  return route;
+}
+
+/**
+ * A proxy_url with its password hidden, for API responses and log lines
+ */
+export function maskProxyUrl(value: string | undefined): string | undefined {
+  if (!value) {
+    return undefined;
+  }
+  try {
+    const url = new URL(value);
+    if (url.password) {
+      url.password = '••••';
+    }
+    return url.toString();
+  } catch {
+    return value;
+  }
+}
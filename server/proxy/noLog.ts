@@ -0,0 +1,29 @@
+// No-log requests - `x-paf-no-log: 1`, or a client key listed in the service's [no_log], keeps a request's
+// content (bodies, previews, headers) out of the request log, the response cache and the outage queue
+
+import type { ServiceConfig } from '../config/types';
+import { getClientKey } from '../tenancy/runtime';
+
+export const NO_LOG_HEADER = 'x-paf-no-log';
+
+/**
+ * Read the service-level `[no_log]` table's `client_keys`
+ */
+export function parseNoLogClientKeys(data: any): string[] | undefined {
+  const keys = Array.isArray(data?.client_keys)
+    ? data.client_keys.filter((key: unknown): key is string => typeof key === 'string' && key.length > 0)
+    : [];
+  return keys.length > 0 ? keys : undefined;
+}
+
+/**
+ * Any value except 0 / false / no opts in; the client key policy applies whatever the header says
+ */
+export function isNoLogRequest(request: Request, serviceConfig: ServiceConfig | undefined): boolean {
+  const header = request.headers.get(NO_LOG_HEADER);
+  if (header !== null && !/^\s*(0|false|no)\s*$/i.test(header)) {
+    return true;
+  }
+  const keys = serviceConfig?.noLogClientKeys;
+  return keys !== undefined && keys.includes(getClientKey(request) ?? '');
+}
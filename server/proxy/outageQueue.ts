@@ -0,0 +1,145 @@
+// Outage queue - opt-in store-and-retry for non-interactive requests while every upstream is down
+
+import type { OutageQueueConfig } from '../config/types';
+
+export type QueuedRequestStatus = 'pending' | 'delivered' | 'failed' | 'cancelled';
+
+export interface QueuedRequest {
+  id: string;
+  service: string;
+  method: string;
+  path: string;                    // Path and query as received
+  headers: Record<string, string>; // Client headers minus credentials; configs supply their own on replay
+  body: string;
+  status: QueuedRequestStatus;
+  attempts: number;
+  createdAt: number;
+  updatedAt: number;
+  lastError?: string;
+  responseStatus?: number;         // Final upstream answer once delivered (or the last failure)
+  responseBody?: string;           // Truncated
+}
+
+export interface OutageQueueStore {
+  saveQueuedRequest(item: QueuedRequest): void;
+  getQueuedRequest(id: string): QueuedRequest | null;
+  listQueuedRequests(service?: string, status?: QueuedRequestStatus): QueuedRequest[];
+}
+
+// Set on replays so a request that fails again is retried from the queue, never queued twice
+export const QUEUED_REQUEST_HEADER = 'x-paf-queued';
+
+// Batch and embedding style endpoints whose callers don't wait on an open connection
+export const DEFAULT_QUEUE_PATHS = ['/v1/embeddings', '/v1/batches', '/v1/messages/batches', '/v1/moderations'];
+
+const CREDENTIAL_HEADERS = new Set(['authorization', 'x-api-key', 'cookie']);
+const MAX_RESPONSE_BODY = 64 * 1024;
+
+/**
+ * Only buffered, non-streaming POSTs to a configured path are worth holding on to
+ */
+export function isQueueable(config: OutageQueueConfig | undefined, request: Request, pathname: string): boolean {
+  return (
+    config?.enabled === true &&
+    request.method === 'POST' &&
+    !request.headers.has(QUEUED_REQUEST_HEADER) &&
+    !(request.headers.get('accept') ?? '').includes('text/event-stream') &&
+    config.paths.some(prefix => pathname === prefix || pathname.startsWith(`${prefix}/`))
+  );
+}
+
+export class OutageQueue {
+  private store: OutageQueueStore;
+
+  constructor(store: OutageQueueStore) {
+    this.store = store;
+  }
+
+  enqueue(
+    service: string,
+    request: Request,
+    path: string,
+    body: string,
+    maxItems: number
+  ): QueuedRequest | { error: string } {
+    if (this.store.listQueuedRequests(service, 'pending').length >= maxItems) {
+      return { error: `Outage queue for ${service} is full (${maxItems} requests)` };
+    }
+
+    const headers: Record<string, string> = {};
+    request.headers.forEach((value, key) => {
+      if (!CREDENTIAL_HEADERS.has(key)) {
+        headers[key] = value;
+      }
+    });
+
+    const now = Date.now();
+    const item: QueuedRequest = {
+      id: crypto.randomUUID(),
+      service,
+      method: request.method,
+      path,
+      headers,
+      body,
+      status: 'pending',
+      attempts: 0,
+      createdAt: now,
+      updatedAt: now,
+    };
+    this.store.saveQueuedRequest(item);
+    return item;
+  }
+
+  get(id: string): QueuedRequest | null {
+    return this.store.getQueuedRequest(id);
+  }
+
+  list(service?: string, status?: QueuedRequestStatus): QueuedRequest[] {
+    return this.store.listQueuedRequests(service, status);
+  }
+
+  /**
+   * Oldest first, so replays keep the order clients sent them in
+   */
+  pending(service: string): QueuedRequest[] {
+    return this.store.listQueuedRequests(service, 'pending').sort((a, b) => a.createdAt - b.createdAt);
+  }
+
+  depth(): Record<string, number> {
+    const depth: Record<string, number> = {};
+    for (const item of this.store.listQueuedRequests(undefined, 'pending')) {
+      depth[item.service] = (depth[item.service] ?? 0) + 1;
+    }
+    return depth;
+  }
+
+  cancel(id: string): QueuedRequest | null {
+    const item = this.store.getQueuedRequest(id);
+    if (!item || item.status !== 'pending') {
+      return item;
+    }
+    const cancelled: QueuedRequest = { ...item, status: 'cancelled', updatedAt: Date.now() };
+    this.store.saveQueuedRequest(cancelled);
+    return cancelled;
+  }
+
+  /**
+   * Record a replay. Any answer below 500 came from an upstream and counts as delivered; 5xx and
+   * connection failures keep the request pending until `maxAttempts`.
+   */
+  recordAttempt(item: QueuedRequest, status: number, body: string, maxAttempts: number): QueuedRequest {
+    const attempts = item.attempts + 1;
+    const delivered = status < 500;
+    const updated: QueuedRequest = {
+      ...item,
+      attempts,
+      status: delivered ? 'delivered' : attempts >= maxAttempts ? 'failed' : 'pending',
+      updatedAt: Date.now(),
+      lastError: delivered ? undefined : `HTTP ${status}`,
+      responseStatus: status,
+      responseBody: body.slice(0, MAX_RESPONSE_BODY),
+    };
+    this.store.saveQueuedRequest(updated);
+    return updated;
+  }
+}
@@ -0,0 +1,92 @@
+// Prompt cache hints - adds Anthropic cache_control breakpoints to large stable prefixes the client left unmarked
+
+import type { PromptCacheConfig } from '../config/types';
+import { estimateTokens } from './streamSalvage';
+
+// Anthropic refuses to cache prefixes below 1024 tokens (2048 on Haiku), so smaller hints only cost a field
+const DEFAULT_MIN_TOKENS = 1024;
+
+export function parsePromptCache(data: any): PromptCacheConfig | undefined {
+  if (data === true) {
+    return { minTokens: DEFAULT_MIN_TOKENS };
+  }
+  if (!data || typeof data !== 'object' || data.enabled === false) {
+    return undefined;
+  }
+  return {
+    minTokens:
+      typeof data.min_tokens === 'number' && data.min_tokens > 0 ? Math.floor(data.min_tokens) : DEFAULT_MIN_TOKENS,
+    ttl: data.ttl === '1h' ? '1h' : undefined,
+  };
+}
+
+/**
+ * TOML/API shape of a prompt cache setting, the inverse of parsePromptCache
+ */
+export function serializePromptCache(config: PromptCacheConfig | undefined): Record<string, unknown> | undefined {
+  if (!config) {
+    return undefined;
+  }
+  return { min_tokens: config.minTokens, ttl: config.ttl };
+}
+
+const hasCacheControl = (blocks: unknown): boolean =>
+  Array.isArray(blocks) && blocks.some(block => block && typeof block === 'object' && 'cache_control' in block);
+
+/**
+ * Whether the client already placed its own breakpoints (Claude Code does); those are never touched
+ */
+function clientMarkedCache(body: any): boolean {
+  return (
+    hasCacheControl(body.tools) ||
+    hasCacheControl(body.system) ||
+    (Array.isArray(body.messages) && body.messages.some((message: any) => hasCacheControl(message?.content)))
+  );
+}
+
+/**
+ * Mark the end of the tool definitions and of the system prompt as cache breakpoints once the prefix
+ * up to them is at least `minTokens`. Returns null when the body is left alone.
+ */
+export function applyPromptCacheHints(
+  body: any,
+  config: PromptCacheConfig | undefined
+): { body: any; breakpoints: string[] } | null {
+  if (!config || !body || typeof body !== 'object' || !Array.isArray(body.messages) || clientMarkedCache(body)) {
+    return null;
+  }
+
+  const cacheControl = config.ttl ? { type: 'ephemeral', ttl: config.ttl } : { type: 'ephemeral' };
+  const next = { ...body };
+  const breakpoints: string[] = [];
+
+  // Cached prefixes are cumulative in request order: tools, then system, then messages
+  let prefixTokens = 0;
+  if (Array.isArray(body.tools) && body.tools.length > 0) {
+    prefixTokens += estimateTokens(JSON.stringify(body.tools));
+    if (prefixTokens >= config.minTokens) {
+      const tools = [...body.tools];
+      tools[tools.length - 1] = { ...tools[tools.length - 1], cache_control: cacheControl };
+      next.tools = tools;
+      breakpoints.push('tools');
+    }
+  }
+
+  const system =
+    typeof body.system === 'string' && body.system
+      ? [{ type: 'text', text: body.system }]
+      : Array.isArray(body.system) && body.system.length > 0
+        ? body.system
+        : null;
+  if (system) {
+    prefixTokens += estimateTokens(JSON.stringify(system));
+    if (prefixTokens >= config.minTokens) {
+      const blocks = [...system];
+      blocks[blocks.length - 1] = { ...blocks[blocks.length - 1], cache_control: cacheControl };
+      next.system = blocks;
+      breakpoints.push('system');
+    }
+  }
+
+  return breakpoints.length > 0 ? { body: next, breakpoints } : null;
+}
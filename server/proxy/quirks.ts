@@ -0,0 +1,198 @@
+// Provider quirks - body rewrites that keep standard client requests acceptable to limited upstreams
+
+import type { ProviderQuirks, ProxyConfig, ServiceConfig } from '../config/types';
+import type { ErrorDialect } from './errors';
+
+const MAX_TOKENS_FIELDS = ['max_tokens', 'max_completion_tokens'] as const;
+const TOOL_CHOICE_MODES = ['keep', 'basic'] as const;
+
+// Request fields newer than what OpenAI-compatible servers (vLLM, one-api style relays, ...) commonly accept
+const OPENAI_EXTENSION_FIELDS = [
+  'metadata',
+  'store',
+  'service_tier',
+  'parallel_tool_calls',
+  'stream_options',
+  'prediction',
+  'modalities',
+  'audio',
+  'reasoning_effort',
+];
+
+/**
+ * Built-in profiles for `provider_profile`; a config's `quirks` table overrides individual toggles
+ */
+export const PROVIDER_PROFILES: Record<string, ProviderQuirks> = {
+  // OpenAI-compatible servers that only implement the classic Chat Completions surface
+  'openai-compatible': {
+    stripFields: OPENAI_EXTENSION_FIELDS,
+    contentBlocks: ['text', 'image_url'],
+    toolChoice: 'basic',
+    maxTokensField: 'max_tokens',
+  },
+  // OpenAI reasoning models reject max_tokens
+  'openai-reasoning': {
+    maxTokensField: 'max_completion_tokens',
+  },
+  deepseek: {
+    stripFields: OPENAI_EXTENSION_FIELDS,
+    contentBlocks: ['text'],
+    maxTokensField: 'max_tokens',
+  },
+  // Claude relays that lag behind the Messages API
+  'anthropic-relay': {
+    stripFields: ['metadata', 'container', 'mcp_servers', 'context_management', 'service_tier'],
+    contentBlocks: ['text', 'image', 'tool_use', 'tool_result', 'thinking', 'redacted_thinking'],
+    toolChoice: 'basic',
+  },
+};
+
+export function isProviderProfile(value: unknown): value is string {
+  return typeof value === 'string' && Object.prototype.hasOwnProperty.call(PROVIDER_PROFILES, value);
+}
+
+const stringList = (value: unknown): string[] | undefined =>
+  Array.isArray(value) ? value.filter((item): item is string => typeof item === 'string' && item !== '') : undefined;
+
+/**
+ * Read a config's `quirks` table (TOML or API JSON, snake_case); unknown values are ignored
+ */
+export function parseQuirks(data: any): ProviderQuirks | undefined {
+  if (!data || typeof data !== 'object') {
+    return undefined;
+  }
+  const quirks: ProviderQuirks = {
+    stripFields: stringList(data.strip_fields),
+    contentBlocks: stringList(data.content_blocks),
+    toolChoice: TOOL_CHOICE_MODES.includes(data.tool_choice) ? data.tool_choice : undefined,
+    maxTokensField: MAX_TOKENS_FIELDS.includes(data.max_tokens_field) ? data.max_tokens_field : undefined,
+  };
+  return Object.values(quirks).some(value => value !== undefined) ? quirks : undefined;
+}
+
+/**
+ * TOML/API shape of quirk overrides, the inverse of parseQuirks
+ */
+export function serializeQuirks(quirks: ProviderQuirks | undefined): Record<string, unknown> | undefined {
+  if (!quirks) {
+    return undefined;
+  }
+  return {
+    strip_fields: quirks.stripFields,
+    content_blocks: quirks.contentBlocks,
+    tool_choice: quirks.toolChoice,
+    max_tokens_field: quirks.maxTokensField,
+  };
+}
+
+/**
+ * Profile toggles (the config's profile, else the service's) with the config's own overrides on top
+ */
+export function resolveQuirks(
+  server: ProxyConfig,
+  serviceConfig: ServiceConfig | undefined
+): { profile?: string; quirks: ProviderQuirks } | null {
+  const profile = server.providerProfile ?? serviceConfig?.providerProfile;
+  if (!profile && !server.quirks) {
+    return null;
+  }
+  const overrides = Object.fromEntries(
+    Object.entries(server.quirks ?? {}).filter(([, value]) => value !== undefined)
+  ) as ProviderQuirks;
+  return { profile, quirks: { ...(profile ? PROVIDER_PROFILES[profile] : undefined), ...overrides } };
+}
+
+function blockText(block: any): string {
+  if (typeof block?.text === 'string') {
+    return block.text;
+  }
+  // Anthropic documents with a plain-text source keep their content
+  if (block?.source?.type === 'text' && typeof block.source.data === 'string') {
+    return block.source.data;
+  }
+  return `[${block?.type ?? 'unknown'} content omitted by the proxy: not supported by this upstream]`;
+}
+
+/**
+ * Apply `quirks` to a request body. Returns the rewritten body and a short description of each change,
+ * or null when nothing applied.
+ */
+export function applyQuirks(
+  body: any,
+  quirks: ProviderQuirks,
+  dialect: ErrorDialect
+): { body: any; changes: string[] } | null {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    return null;
+  }
+  const next = { ...body };
+  const changes: string[] = [];
+
+  const stripped = (quirks.stripFields ?? []).filter(field => next[field] !== undefined);
+  for (const field of stripped) {
+    delete next[field];
+  }
+  if (stripped.length > 0) {
+    changes.push(`stripped ${stripped.join(', ')}`);
+  }
+
+  // Chat Completions only; the Messages API always requires max_tokens
+  if (quirks.maxTokensField && dialect === 'openai' && Array.isArray(next.messages)) {
+    const other = quirks.maxTokensField === 'max_tokens' ? 'max_completion_tokens' : 'max_tokens';
+    if (next[other] !== undefined) {
+      next[quirks.maxTokensField] ??= next[other];
+      delete next[other];
+      changes.push(`${other} -> ${quirks.maxTokensField}`);
+    }
+  }
+
+  if (quirks.toolChoice === 'basic' && next.tool_choice !== undefined) {
+    if (dialect === 'openai' && typeof next.tool_choice === 'object') {
+      // Forcing a named function or an allowed_tools subset becomes "must call some tool"
+      next.tool_choice = next.tool_choice?.mode === 'auto' ? 'auto' : 'required';
+      changes.push(`tool_choice -> ${next.tool_choice}`);
+    } else if (dialect === 'anthropic' && next.tool_choice && typeof next.tool_choice === 'object') {
+      const { type, disable_parallel_tool_use: _disableParallel, name: _name } = next.tool_choice;
+      const downgraded = { type: type === 'tool' ? 'any' : type };
+      if (JSON.stringify(downgraded) !== JSON.stringify(next.tool_choice)) {
+        next.tool_choice = downgraded;
+        changes.push(`tool_choice -> ${downgraded.type}`);
+      }
+    }
+  }
+
+  const allowed = quirks.contentBlocks;
+  if (allowed) {
+    const downgraded = downgradeContentBlocks(next, type => allowed.includes(type));
+    if (downgraded > 0) {
+      changes.push(`${downgraded} content block(s) downgraded to text`);
+    }
+  }
+
+  return changes.length > 0 ? { body: next, changes } : null;
+}
+
+/**
+ * Replace message content blocks whose type is not `allowed` with text blocks, in place on `body`
+ * (messages are copied). Returns how many blocks were replaced.
+ */
+export function downgradeContentBlocks(body: any, allowed: (type: string) => boolean): number {
+  if (!Array.isArray(body?.messages)) {
+    return 0;
+  }
+  let downgraded = 0;
+  body.messages = body.messages.map((message: any) => {
+    if (!Array.isArray(message?.content)) {
+      return message;
+    }
+    const content = message.content.map((block: any) => {
+      if (!block || typeof block !== 'object' || allowed(block.type)) {
+        return block;
+      }
+      downgraded++;
+      return { type: 'text', text: blockText(block) };
+    });
+    return { ...message, content };
+  });
+  return downgraded;
+}
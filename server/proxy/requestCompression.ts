@@ -0,0 +1,53 @@
+// Request compression - gzip large request bodies (long contexts) for upstreams that accept
+// content-encoding: gzip, trading a little CPU for upload bandwidth
+
+import { gzipSync } from 'node:zlib';
+import type { RequestCompressionConfig } from '../config/types';
+
+// Below this a gzip header and the CPU time cost more than the bytes saved
+const DEFAULT_MIN_BYTES = 64 * 1024;
+
+export function parseRequestCompression(data: any): RequestCompressionConfig | undefined {
+  if (data === true) {
+    return { minBytes: DEFAULT_MIN_BYTES };
+  }
+  if (!data || typeof data !== 'object' || data.enabled === false) {
+    return undefined;
+  }
+  return {
+    minBytes: typeof data.min_bytes === 'number' && data.min_bytes >= 0 ? Math.floor(data.min_bytes) : DEFAULT_MIN_BYTES,
+  };
+}
+
+/**
+ * TOML/API shape of a request compression setting, the inverse of parseRequestCompression
+ */
+export function serializeRequestCompression(
+  config: RequestCompressionConfig | undefined
+): Record<string, unknown> | undefined {
+  if (!config) {
+    return undefined;
+  }
+  return { min_bytes: config.minBytes };
+}
+
+export interface CompressedRequestBody {
+  body: Uint8Array;
+  rawBytes: number;
+  compressedBytes: number;
+}
+
+/**
+ * The gzipped body when it is at least the configured size and actually gets smaller; null otherwise
+ */
+export function compressRequestBody(body: string, config: RequestCompressionConfig): CompressedRequestBody | null {
+  const raw = Buffer.from(body);
+  if (raw.byteLength < config.minBytes) {
+    return null;
+  }
+  const compressed = gzipSync(raw);
+  if (compressed.byteLength >= raw.byteLength) {
+    return null;
+  }
+  return { body: compressed, rawBytes: raw.byteLength, compressedBytes: compressed.byteLength };
+}
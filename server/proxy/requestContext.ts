@@ -0,0 +1,56 @@
+// Request context - a structured record of what paf did to a request, stored with its log entry
+
+export const REQUEST_CONTEXT_VERSION = 1;
+
+// Set on every proxied response: the id of the request's log entry
+export const REQUEST_ID_HEADER = 'x-paf-request-id';
+
+// header: the client forced the config with x-paf-config
+export type UpstreamSelectionVia = 'pin' | 'header' | 'experiment' | 'load_balancer';
+
+export type UpstreamAttemptKind = 'initial' | 'failover' | 'stream_failover' | 'stream_resume' | 'max_tokens_continuation';
+
+export interface RequestContextTransform {
+  name: string;    // e.g. strip_thinking, prompt_templates, dlp_redact, context_trim, fingerprint
+  detail?: string;
+}
+
+export interface RequestContextAttempt {
+  kind: UpstreamAttemptKind;
+  config: string;
+  url?: string;
+  status?: number;
+  error?: string;
+  at: number;
+}
+
+export interface RequestContext {
+  v: number;
+  selection?: {
+    config: string;
+    via: UpstreamSelectionVia;
+    mode?: string;         // Service mode: manual or load_balance
+    strategy?: string;     // Load balancer strategy when via is load_balancer
+    experiment?: string;   // "<experiment id>:<arm>" when via is experiment
+    pool?: string;         // Model family pool the candidates came from
+    reason?: string;       // Why the selection strategy chose this config
+    candidates: string[];  // Configs that were eligible
+  };
+  transforms: RequestContextTransform[];
+  attempts: RequestContextAttempt[];
+}
+
+export function createRequestContext(): RequestContext {
+  return { v: REQUEST_CONTEXT_VERSION, transforms: [], attempts: [] };
+}
+
+export function noteTransform(context: RequestContext | undefined, name: string, detail?: string): void {
+  context?.transforms.push(detail === undefined ? { name } : { name, detail });
+}
+
+export function noteAttempt(
+  context: RequestContext | undefined,
+  attempt: Omit<RequestContextAttempt, 'at'>
+): void {
+  context?.attempts.push({ ...attempt, at: Date.now() });
+}
@@ -0,0 +1,208 @@
+// Request validation - check inbound bodies against the Anthropic Messages and OpenAI Responses request schemas,
+// so malformed requests get a precise local 400 instead of a vague upstream error counted against a config
+
+// The subset of JSON Schema the provider schemas below need
+interface Schema {
+  type?: SchemaType | SchemaType[];
+  required?: string[];
+  properties?: Record<string, Schema>;
+  items?: Schema;
+  enum?: unknown[];
+  minimum?: number;
+  maximum?: number;
+  minItems?: number;
+  anyOf?: Schema[];
+}
+
+type SchemaType = 'string' | 'number' | 'integer' | 'boolean' | 'object' | 'array' | 'null';
+
+export interface SchemaViolation {
+  path: string; // e.g. messages[2].content[0].type
+  message: string;
+}
+
+const contentBlock: Schema = { type: 'object', required: ['type'], properties: { type: { type: 'string' } } };
+
+const messagesProperties: Record<string, Schema> = {
+  model: { type: 'string' },
+  messages: {
+    type: 'array',
+    minItems: 1,
+    items: {
+      type: 'object',
+      required: ['role', 'content'],
+      properties: {
+        role: { enum: ['user', 'assistant'] },
+        content: { anyOf: [{ type: 'string' }, { type: 'array', items: contentBlock }] },
+      },
+    },
+  },
+  system: { anyOf: [{ type: 'string' }, { type: 'array', items: contentBlock }] },
+  max_tokens: { type: 'integer', minimum: 1 },
+  temperature: { type: 'number', minimum: 0, maximum: 1 },
+  top_p: { type: 'number', minimum: 0, maximum: 1 },
+  top_k: { type: 'integer', minimum: 0 },
+  stream: { type: 'boolean' },
+  stop_sequences: { type: 'array', items: { type: 'string' } },
+  tools: { type: 'array', items: { type: 'object', required: ['name'], properties: { name: { type: 'string' } } } },
+  tool_choice: { type: 'object', required: ['type'], properties: { type: { type: 'string' } } },
+  thinking: { type: 'object', required: ['type'], properties: { type: { type: 'string' } } },
+  metadata: { type: 'object' },
+};
+
+const ANTHROPIC_MESSAGES: Schema = {
+  type: 'object',
+  required: ['model', 'max_tokens', 'messages'],
+  properties: messagesProperties,
+};
+
+const ANTHROPIC_COUNT_TOKENS: Schema = {
+  type: 'object',
+  required: ['model', 'messages'],
+  properties: messagesProperties,
+};
+
+const OPENAI_RESPONSES: Schema = {
+  type: 'object',
+  required: ['model'],
+  properties: {
+    model: { type: 'string' },
+    input: { anyOf: [{ type: 'string' }, { type: 'array', items: { type: 'object' } }] },
+    instructions: { type: ['string', 'null'] },
+    max_output_tokens: { type: ['integer', 'null'], minimum: 1 },
+    temperature: { type: ['number', 'null'], minimum: 0, maximum: 2 },
+    top_p: { type: ['number', 'null'], minimum: 0, maximum: 1 },
+    stream: { type: 'boolean' },
+    store: { type: ['boolean', 'null'] },
+    parallel_tool_calls: { type: ['boolean', 'null'] },
+    previous_response_id: { type: ['string', 'null'] },
+    tools: { type: 'array', items: { type: 'object', required: ['type'], properties: { type: { type: 'string' } } } },
+    tool_choice: { anyOf: [{ type: 'string' }, { type: 'object', required: ['type'] }] },
+    reasoning: { type: ['object', 'null'] },
+    text: { type: ['object', 'null'] },
+    metadata: { type: ['object', 'null'] },
+  },
+};
+
+const SCHEMAS: Array<[RegExp, Schema]> = [
+  [/\/v1\/messages$/, ANTHROPIC_MESSAGES],
+  [/\/v1\/messages\/count_tokens$/, ANTHROPIC_COUNT_TOKENS],
+  [/\/v1\/responses$/, OPENAI_RESPONSES],
+];
+
+// Enough to locate the problem; a body broken in hundreds of places doesn't need all of them listed
+const MAX_VIOLATIONS = 5;
+
+function typeOf(value: unknown): SchemaType {
+  if (value === null) {
+    return 'null';
+  }
+  if (Array.isArray(value)) {
+    return 'array';
+  }
+  if (typeof value === 'number') {
+    return Number.isInteger(value) ? 'integer' : 'number';
+  }
+  return typeof value as SchemaType;
+}
+
+function matchesType(value: unknown, expected: SchemaType | SchemaType[]): boolean {
+  const actual = typeOf(value);
+  const types = Array.isArray(expected) ? expected : [expected];
+  return types.includes(actual) || (actual === 'integer' && types.includes('number'));
+}
+
+function check(schema: Schema, value: unknown, path: string, violations: SchemaViolation[]): void {
+  if (violations.length >= MAX_VIOLATIONS) {
+    return;
+  }
+  const at = path || 'body';
+
+  if (schema.anyOf) {
+    // When only one option has the right type, its own errors say more than "matches no option"
+    const typed = schema.anyOf.filter(option => !option.type || matchesType(value, option.type));
+    if (typed.length === 1) {
+      check(typed[0], value, path, violations);
+      return;
+    }
+    const matches = typed.some(option => {
+      const nested: SchemaViolation[] = [];
+      check(option, value, path, nested);
+      return nested.length === 0;
+    });
+    if (!matches) {
+      const expected = schema.anyOf.map(option => [option.type ?? 'value'].flat().join(' or '));
+      violations.push({ path: at, message: `must be ${expected.join(' or ')}, got ${typeOf(value)}` });
+    }
+    return;
+  }
+
+  if (schema.type && !matchesType(value, schema.type)) {
+    violations.push({ path: at, message: `must be ${[schema.type].flat().join(' or ')}, got ${typeOf(value)}` });
+    return;
+  }
+  if (schema.enum && !schema.enum.includes(value)) {
+    violations.push({ path: at, message: `must be one of ${schema.enum.map(v => JSON.stringify(v)).join(', ')}` });
+    return;
+  }
+  if (typeof value === 'number') {
+    if (schema.minimum !== undefined && value < schema.minimum) {
+      violations.push({ path: at, message: `must be at least ${schema.minimum}` });
+    } else if (schema.maximum !== undefined && value > schema.maximum) {
+      violations.push({ path: at, message: `must be at most ${schema.maximum}` });
+    }
+    return;
+  }
+
+  if (Array.isArray(value)) {
+    if (schema.minItems !== undefined && value.length < schema.minItems) {
+      violations.push({ path: at, message: `must have at least ${schema.minItems} item(s)` });
+    }
+    if (schema.items) {
+      value.forEach((item, index) => check(schema.items!, item, `${path}[${index}]`, violations));
+    }
+    return;
+  }
+
+  if (value && typeof value === 'object') {
+    const record = value as Record<string, unknown>;
+    for (const key of schema.required ?? []) {
+      if (record[key] === undefined && violations.length < MAX_VIOLATIONS) {
+        violations.push({ path: path ? `${path}.${key}` : key, message: 'is required' });
+      }
+    }
+    // Unknown fields are left to the provider; new API features shouldn't need a paf release
+    for (const [key, propertySchema] of Object.entries(schema.properties ?? {})) {
+      if (record[key] !== undefined) {
+        check(propertySchema, record[key], path ? `${path}.${key}` : key, violations);
+      }
+    }
+  }
+}
+
+/**
+ * Whether requests to `pathname` have a schema to be checked against
+ */
+export function hasRequestSchema(pathname: string): boolean {
+  return SCHEMAS.some(([pattern]) => pattern.test(pathname));
+}
+
+/**
+ * Problems with `body` for a POST to `pathname`; empty when it is valid or the endpoint has no schema
+ */
+export function validateRequestBody(pathname: string, body: unknown): SchemaViolation[] {
+  const schema = SCHEMAS.find(([pattern]) => pattern.test(pathname))?.[1];
+  if (!schema) {
+    return [];
+  }
+  const violations: SchemaViolation[] = [];
+  check(schema, body, '', violations);
+  return violations;
+}
+
+/**
+ * One-line error message naming every violation found, e.g. `Invalid request: max_tokens: is required`
+ */
+export function describeViolations(violations: SchemaViolation[]): string {
+  return `Invalid request: ${violations.map(violation => `${violation.path}: ${violation.message}`).join('; ')}`;
+}
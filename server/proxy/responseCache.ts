@@ -0,0 +1,219 @@
+// Response cache - answers identical non-streaming requests from a stored response for a while, so
+// repeated health checks and deterministic tool calls don't spend upstream quota
+
+import type { SystemConfig } from '../config/types';
+
+export type ResponseCacheConfig = SystemConfig['responseCache'];
+
+// Set on every cacheable response: hit when served from the cache, miss when it went upstream
+export const RESPONSE_CACHE_HEADER = 'x-paf-cache';
+
+// Fields that vary per caller or session without changing the answer
+const IGNORED_FIELDS = new Set(['metadata', 'user', 'stream']);
+
+export interface CachedResponse {
+  key: string;
+  service: string;
+  path: string;
+  model?: string;
+  configName: string;         // Config that produced the response
+  status: number;
+  headers: [string, string][]; // Upstream response headers; the service's header policy applies on each hit
+  body: string;
+  bytes: number;
+  createdAt: number;
+  expiresAt: number;
+  hits: number;
+}
+
+export interface ResponseCacheStore {
+  loadCachedResponses(now: number): CachedResponse[];
+  saveCachedResponse(entry: CachedResponse): void;
+  deleteCachedResponses(keys: string[]): void;
+}
+
+export interface ResponseCacheStats {
+  enabled: boolean;
+  ttlSeconds: number;
+  maxBytes: number;
+  entries: number;
+  bytes: number;
+  hits: number;
+  misses: number;
+}
+
+/**
+ * Only buffered JSON POSTs that don't ask for a stream; `cache-control: no-cache` or `no-store` opts a request out
+ */
+export function isCacheableRequest(request: Request, body: any): boolean {
+  const cacheControl = request.headers.get('cache-control') ?? '';
+  return (
+    request.method === 'POST' &&
+    body !== null &&
+    typeof body === 'object' &&
+    !Array.isArray(body) &&
+    body.stream !== true &&
+    !(request.headers.get('accept') ?? '').includes('text/event-stream') &&
+    !/no-cache|no-store/i.test(cacheControl)
+  );
+}
+
+/**
+ * Key on the service, path and the body with sorted keys, so field order and per-caller fields don't split entries
+ */
+export function responseCacheKey(service: string, pathname: string, body: any): string {
+  const normalized = Object.fromEntries(Object.entries(body).filter(([key]) => !IGNORED_FIELDS.has(key)));
+  return new Bun.CryptoHasher('sha256')
+    .update(`${service}\n${pathname}\n${canonicalJson(normalized)}`)
+    .digest('hex');
+}
+
+function canonicalJson(value: any): string {
+  if (Array.isArray(value)) {
+    return `[${value.map(canonicalJson).join(',')}]`;
+  }
+  if (value && typeof value === 'object') {
+    const fields = Object.keys(value)
+      .filter(key => value[key] !== undefined)
+      .sort()
+      .map(key => `${JSON.stringify(key)}:${canonicalJson(value[key])}`);
+    return `{${fields.join(',')}}`;
+  }
+  return JSON.stringify(value);
+}
+
+/**
+ * In-memory entries in least recently used order, written through to the request log database so a
+ * restart keeps them. Entries past `maxBytes` are evicted oldest first.
+ */
+export class ResponseCache {
+  private config: ResponseCacheConfig;
+  private store?: ResponseCacheStore;
+  private entries = new Map<string, CachedResponse>();
+  private bytes = 0;
+  private hits = 0;
+  private misses = 0;
+
+  constructor(config: ResponseCacheConfig, store?: ResponseCacheStore) {
+    this.config = config;
+    this.store = store;
+    if (this.isEnabled() && store) {
+      for (const entry of store.loadCachedResponses(Date.now())) {
+        this.insert(entry);
+      }
+      this.evict();
+    }
+  }
+
+  isEnabled(): boolean {
+    return this.config.ttlSeconds > 0 && this.config.maxBytes > 0;
+  }
+
+  get(key: string): CachedResponse | null {
+    const entry = this.entries.get(key);
+    if (entry && entry.expiresAt > Date.now()) {
+      // Re-insert to mark it most recently used
+      this.entries.delete(key);
+      this.entries.set(key, entry);
+      entry.hits++;
+      this.hits++;
+      return entry;
+    }
+    if (entry) {
+      this.remove([key]);
+    }
+    this.misses++;
+    return null;
+  }
+
+  /**
+   * Store a successful response; bodies larger than the whole cache are skipped
+   */
+  set(entry: Omit<CachedResponse, 'bytes' | 'createdAt' | 'expiresAt' | 'hits'>): void {
+    const bytes = Buffer.byteLength(entry.body);
+    if (bytes > this.config.maxBytes) {
+      return;
+    }
+    const now = Date.now();
+    const cached: CachedResponse = {
+      ...entry,
+      bytes,
+      createdAt: now,
+      expiresAt: now + this.config.ttlSeconds * 1000,
+      hits: 0,
+    };
+    this.insert(cached);
+    this.store?.saveCachedResponse(cached);
+    this.evict();
+  }
+
+  list(): Omit<CachedResponse, 'headers' | 'body'>[] {
+    const now = Date.now();
+    return [...this.entries.values()]
+      .filter(entry => entry.expiresAt > now)
+      .map(({ headers: _headers, body: _body, ...entry }) => entry);
+  }
+
+  /**
+   * Drop entries, for one service or all of them; returns how many were dropped
+   */
+  clear(service?: string): number {
+    const keys = [...this.entries.values()]
+      .filter(entry => !service || entry.service === service)
+      .map(entry => entry.key);
+    this.remove(keys);
+    return keys.length;
+  }
+
+  stats(): ResponseCacheStats {
+    return {
+      enabled: this.isEnabled(),
+      ttlSeconds: this.config.ttlSeconds,
+      maxBytes: this.config.maxBytes,
+      entries: this.entries.size,
+      bytes: this.bytes,
+      hits: this.hits,
+      misses: this.misses,
+    };
+  }
+
+  private insert(entry: CachedResponse): void {
+    const previous = this.entries.get(entry.key);
+    if (previous) {
+      this.bytes -= previous.bytes;
+      this.entries.delete(entry.key);
+    }
+    this.entries.set(entry.key, entry);
+    this.bytes += entry.bytes;
+  }
+
+  private remove(keys: string[]): void {
+    for (const key of keys) {
+      const entry = this.entries.get(key);
+      if (entry) {
+        this.bytes -= entry.bytes;
+        this.entries.delete(key);
+      }
+    }
+    if (keys.length > 0) {
+      this.store?.deleteCachedResponses(keys);
+    }
+  }
+
+  private evict(): void {
+    const now = Date.now();
+    const expired = [...this.entries.values()].filter(entry => entry.expiresAt <= now).map(entry => entry.key);
+    this.remove(expired);
+
+    const evicted: string[] = [];
+    let bytes = this.bytes;
+    for (const entry of this.entries.values()) {
+      if (bytes <= this.config.maxBytes) {
+        break;
+      }
+      evicted.push(entry.key);
+      bytes -= entry.bytes;
+    }
+    this.remove(evicted);
+  }
+}
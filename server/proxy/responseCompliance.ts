@@ -0,0 +1,265 @@
+// Response compliance - flag upstream responses that break the provider's protocol (missing usage, SSE events
+// out of order, invalid JSON), so sloppy relays show up as a per-config compliance score
+
+import { splitSseEvents } from './streamSalvage';
+
+export type ProtocolIssueCode =
+  | 'invalid_json'           // A body or SSE data line that doesn't parse
+  | 'wrong_content_type'     // JSON endpoint answered with something else
+  | 'unexpected_shape'       // Parsed, but not the object the endpoint returns
+  | 'missing_usage'          // No token counts where the protocol always has them
+  | 'bad_event_order'        // SSE events the protocol never sends in this order
+  | 'missing_terminal_event'; // Stream ended cleanly without its final event
+
+type Protocol = 'anthropic_messages' | 'anthropic_count_tokens' | 'openai_responses' | 'openai_chat';
+
+const PROTOCOLS: Array<[RegExp, Protocol]> = [
+  [/\/v1\/messages$/, 'anthropic_messages'],
+  [/\/v1\/messages\/count_tokens$/, 'anthropic_count_tokens'],
+  [/\/v1\/responses$/, 'openai_responses'],
+  [/\/chat\/completions$/, 'openai_chat'],
+];
+
+const RESPONSES_TERMINAL_EVENTS = new Set(['response.completed', 'response.incomplete', 'response.failed']);
+
+// Distinct problems per response; a relay that mangles every event shouldn't produce hundreds
+const MAX_ISSUES = 5;
+
+class IssueList {
+  readonly items: string[] = [];
+
+  add(code: ProtocolIssueCode, detail: string): void {
+    if (this.items.length < MAX_ISSUES && !this.items.some(item => item.startsWith(`${code}:`))) {
+      this.items.push(`${code}: ${detail}`);
+    }
+  }
+}
+
+function protocolOf(pathname: string): Protocol | null {
+  return PROTOCOLS.find(([pattern]) => pattern.test(pathname))?.[1] ?? null;
+}
+
+/**
+ * Whether responses to `pathname` follow a protocol paf knows how to check
+ */
+export function hasResponseProtocol(pathname: string): boolean {
+  return protocolOf(pathname) !== null;
+}
+
+/**
+ * Issues with a successful non-streaming response; empty when compliant or the endpoint isn't checked
+ */
+export function checkJsonResponse(pathname: string, contentType: string, text: string): string[] {
+  const protocol = protocolOf(pathname);
+  const issues = new IssueList();
+  if (!protocol) {
+    return issues.items;
+  }
+  if (!contentType.includes('application/json')) {
+    issues.add('wrong_content_type', `got "${contentType || 'none'}"`);
+  }
+
+  let body: any;
+  try {
+    body = JSON.parse(text);
+  } catch {
+    issues.add('invalid_json', 'response body is not JSON');
+    return issues.items;
+  }
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    issues.add('unexpected_shape', 'response body is not an object');
+    return issues.items;
+  }
+
+  switch (protocol) {
+    case 'anthropic_messages':
+      if (body.type !== 'message' || !Array.isArray(body.content)) {
+        issues.add('unexpected_shape', 'expected type "message" with a content array');
+      }
+      if (typeof body.usage?.input_tokens !== 'number' || typeof body.usage?.output_tokens !== 'number') {
+        issues.add('missing_usage', 'usage.input_tokens/output_tokens missing');
+      }
+      break;
+    case 'anthropic_count_tokens':
+      if (typeof body.input_tokens !== 'number') {
+        issues.add('unexpected_shape', 'input_tokens missing');
+      }
+      break;
+    case 'openai_responses':
+      if (body.object !== 'response' || !Array.isArray(body.output)) {
+        issues.add('unexpected_shape', 'expected object "response" with an output array');
+      }
+      if (typeof body.usage?.input_tokens !== 'number' || typeof body.usage?.output_tokens !== 'number') {
+        issues.add('missing_usage', 'usage.input_tokens/output_tokens missing');
+      }
+      break;
+    case 'openai_chat':
+      if (!Array.isArray(body.choices)) {
+        issues.add('unexpected_shape', 'choices array missing');
+      }
+      if (typeof body.usage?.prompt_tokens !== 'number') {
+        issues.add('missing_usage', 'usage.prompt_tokens missing');
+      }
+      break;
+  }
+  return issues.items;
+}
+
+/**
+ * Issues with a complete, uninterrupted SSE response. `requestBody` tells whether chat completions
+ * asked for usage (stream_options.include_usage), the only case where their streams carry it.
+ */
+export function checkSseResponse(pathname: string, sse: string, requestBody: any): string[] {
+  const protocol = protocolOf(pathname);
+  const issues = new IssueList();
+  if (!protocol || protocol === 'anthropic_count_tokens') {
+    return issues.items;
+  }
+
+  const { events } = splitSseEvents(`${sse}\n\n`);
+  const parsed: Array<{ name?: string; data: any }> = [];
+  let sawDone = false;
+  for (const event of events) {
+    if (event.data === undefined) {
+      continue; // Comments such as keepalive pings
+    }
+    if (event.data === '[DONE]') {
+      sawDone = true;
+      continue;
+    }
+    try {
+      parsed.push({ name: event.event, data: JSON.parse(event.data) });
+    } catch {
+      issues.add('invalid_json', `data line of ${event.event ?? 'an event'} is not JSON`);
+    }
+  }
+
+  // Mid-stream error events are part of every protocol; the stream is judged by what came before
+  const errorAt = parsed.findIndex(event => event.data?.type === 'error' || event.name === 'error');
+  const stream = errorAt === -1 ? parsed : parsed.slice(0, errorAt);
+  const ended = errorAt !== -1;
+
+  if (protocol === 'anthropic_messages') {
+    checkAnthropicStream(stream, ended, issues);
+  } else if (protocol === 'openai_responses') {
+    checkResponsesStream(stream, ended, issues);
+  } else {
+    if (!sawDone && !ended) {
+      issues.add('missing_terminal_event', 'no data: [DONE]');
+    }
+    if (requestBody?.stream_options?.include_usage === true && !ended) {
+      if (!stream.some(event => typeof event.data?.usage?.prompt_tokens === 'number')) {
+        issues.add('missing_usage', 'include_usage was requested but no chunk carried usage');
+      }
+    }
+  }
+  return issues.items;
+}
+
+function checkAnthropicStream(stream: Array<{ name?: string; data: any }>, ended: boolean, issues: IssueList): void {
+  const openBlocks = new Set<number>();
+  let started = false;
+  let stopped = false;
+  let sawUsage = false;
+
+  for (const { name, data } of stream) {
+    const type = data?.type;
+    if (name && type && name !== type) {
+      issues.add('bad_event_order', `event "${name}" carries a ${type} payload`);
+    }
+    if (type === 'ping') {
+      continue;
+    }
+    if (stopped) {
+      issues.add('bad_event_order', `${type} after message_stop`);
+      continue;
+    }
+    if (!started && type !== 'message_start') {
+      issues.add('bad_event_order', `${type} before message_start`);
+    }
+
+    switch (type) {
+      case 'message_start':
+        if (started) {
+          issues.add('bad_event_order', 'second message_start');
+        }
+        started = true;
+        if (typeof data.message?.usage?.input_tokens === 'number') {
+          sawUsage = true;
+        }
+        break;
+      case 'content_block_start':
+        if (openBlocks.has(data.index)) {
+          issues.add('bad_event_order', `content_block_start for open block ${data.index}`);
+        }
+        openBlocks.add(data.index);
+        break;
+      case 'content_block_delta':
+        if (!openBlocks.has(data.index)) {
+          issues.add('bad_event_order', `content_block_delta for block ${data.index} that was never started`);
+        }
+        break;
+      case 'content_block_stop':
+        if (!openBlocks.delete(data.index)) {
+          issues.add('bad_event_order', `content_block_stop for block ${data.index} that was never started`);
+        }
+        break;
+      case 'message_delta':
+        if (openBlocks.size > 0) {
+          issues.add('bad_event_order', 'message_delta while a content block is still open');
+        }
+        if (typeof data.usage?.output_tokens !== 'number') {
+          issues.add('missing_usage', 'message_delta without usage.output_tokens');
+        }
+        break;
+      case 'message_stop':
+        stopped = true;
+        break;
+    }
+  }
+
+  if (!ended) {
+    if (!stopped) {
+      issues.add('missing_terminal_event', 'no message_stop');
+    }
+    if (!sawUsage) {
+      issues.add('missing_usage', 'message_start without usage.input_tokens');
+    }
+  }
+}
+
+function checkResponsesStream(stream: Array<{ name?: string; data: any }>, ended: boolean, issues: IssueList): void {
+  let lastSequence = -1;
+  let terminal: any = null;
+
+  for (const [index, { name, data }] of stream.entries()) {
+    const type = data?.type;
+    if (name && type && name !== type) {
+      issues.add('bad_event_order', `event "${name}" carries a ${type} payload`);
+    }
+    if (index === 0 && type !== 'response.created') {
+      issues.add('bad_event_order', `first event is ${type}, not response.created`);
+    }
+    if (terminal) {
+      issues.add('bad_event_order', `${type} after ${terminal.type}`);
+    }
+    if (typeof data?.sequence_number === 'number') {
+      if (data.sequence_number <= lastSequence) {
+        issues.add('bad_event_order', `sequence_number ${data.sequence_number} after ${lastSequence}`);
+      }
+      lastSequence = data.sequence_number;
+    }
+    if (RESPONSES_TERMINAL_EVENTS.has(type)) {
+      terminal = data;
+    }
+  }
+
+  if (ended) {
+    return;
+  }
+  if (!terminal) {
+    issues.add('missing_terminal_event', 'no response.completed, response.incomplete or response.failed');
+  } else if (terminal.type === 'response.completed' && typeof terminal.response?.usage?.input_tokens !== 'number') {
+    issues.add('missing_usage', 'response.completed without usage');
+  }
+}
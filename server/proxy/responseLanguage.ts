@@ -0,0 +1,49 @@
+// Response language - appends a system instruction pinning the reply language per config or client key
+
+import type { ProxyConfig, ServiceConfig } from '../config/types';
+import { getClientKey } from '../tenancy/runtime';
+
+// Conversational endpoints only; embeddings and token counting also carry `input`/`messages`
+const CHAT_PATH_SUFFIXES = ['/messages', '/chat/completions', '/responses'];
+
+export function parseResponseLanguage(data: unknown): string | undefined {
+  return typeof data === 'string' && data.trim() ? data.trim() : undefined;
+}
+
+/**
+ * Read the service-level `[response_language.client_keys]` table: inbound client key -> language
+ */
+export function parseClientKeyLanguages(data: any): Record<string, string> | undefined {
+  const keys = data?.client_keys;
+  if (!keys || typeof keys !== 'object') {
+    return undefined;
+  }
+  const languages: Record<string, string> = {};
+  for (const [key, language] of Object.entries(keys)) {
+    const parsed = parseResponseLanguage(language);
+    if (parsed) {
+      languages[key] = parsed;
+    }
+  }
+  return Object.keys(languages).length > 0 ? languages : undefined;
+}
+
+/**
+ * The client key's language wins over the config's, so one relay can serve teams with different needs
+ */
+export function resolveResponseLanguage(
+  request: Request,
+  server: ProxyConfig,
+  serviceConfig: ServiceConfig | undefined
+): string | undefined {
+  const clientKey = serviceConfig?.clientKeyLanguages ? getClientKey(request) : undefined;
+  return (clientKey && serviceConfig?.clientKeyLanguages?.[clientKey]) || server.responseLanguage;
+}
+
+export function isChatPath(pathname: string): boolean {
+  return CHAT_PATH_SUFFIXES.some(suffix => pathname.endsWith(suffix));
+}
+
+export function responseLanguageInstruction(language: string): string {
+  return `Always write your responses in ${language}, regardless of the language used in the conversation or in earlier replies. Keep code, identifiers and quoted text unchanged.`;
+}
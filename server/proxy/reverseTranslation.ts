@@ -0,0 +1,598 @@
+// Reverse protocol translation - the mirror of translation.ts: serve OpenAI-protocol clients (Chat Completions
+// and Responses) from Anthropic configs (api_format = "anthropic"). Responses features that need server-side
+// state (previous_response_id, store) or OpenAI-hosted tools have no Messages equivalent and are dropped.
+
+import { parseSseData, splitSseEvents } from './streamSalvage';
+
+// Messages requires max_tokens; OpenAI clients usually leave it to the model's default
+const DEFAULT_MAX_TOKENS = 4096;
+
+const CHAT_FINISH_REASONS: Record<string, string> = {
+  end_turn: 'stop',
+  stop_sequence: 'stop',
+  pause_turn: 'stop',
+  max_tokens: 'length',
+  tool_use: 'tool_calls',
+  refusal: 'content_filter',
+};
+
+function parseArguments(args: unknown): any {
+  if (typeof args !== 'string' || !args) {
+    return {};
+  }
+  try {
+    return JSON.parse(args);
+  } catch {
+    return {};
+  }
+}
+
+function imageBlock(url: unknown): any {
+  if (typeof url !== 'string') {
+    return null;
+  }
+  const dataUrl = /^data:([^;,]+);base64,(.*)$/s.exec(url);
+  if (dataUrl) {
+    return { type: 'image', source: { type: 'base64', media_type: dataUrl[1], data: dataUrl[2] } };
+  }
+  return { type: 'image', source: { type: 'url', url } };
+}
+
+// OpenAI content is a string or a list of text/image parts, named differently by the two APIs
+function contentBlocks(content: unknown): any[] {
+  if (typeof content === 'string') {
+    return content ? [{ type: 'text', text: content }] : [];
+  }
+  if (!Array.isArray(content)) {
+    return [];
+  }
+  const blocks: any[] = [];
+  for (const part of content) {
+    if (['text', 'input_text', 'output_text'].includes(part?.type) && typeof part.text === 'string') {
+      blocks.push({ type: 'text', text: part.text });
+    } else if (part?.type === 'image_url' || part?.type === 'input_image') {
+      const image = imageBlock(typeof part.image_url === 'string' ? part.image_url : part.image_url?.url);
+      if (image) {
+        blocks.push(image);
+      }
+    }
+  }
+  return blocks;
+}
+
+function textOf(content: unknown): string {
+  return contentBlocks(content)
+    .filter(block => block.type === 'text')
+    .map(block => block.text)
+    .join('\n');
+}
+
+/**
+ * Builds the Messages turn list; consecutive blocks of one role share a turn, so tool results answering
+ * parallel calls end up in the single user turn Anthropic expects
+ */
+class TurnList {
+  readonly system: string[] = [];
+  readonly messages: Array<{ role: 'user' | 'assistant'; content: any[] }> = [];
+
+  add(role: 'user' | 'assistant', blocks: any[]): void {
+    if (blocks.length === 0) {
+      return;
+    }
+    const last = this.messages[this.messages.length - 1];
+    if (last?.role === role) {
+      last.content.push(...blocks);
+    } else {
+      this.messages.push({ role, content: [...blocks] });
+    }
+  }
+}
+
+function toolResult(callId: unknown, output: unknown): any {
+  return { type: 'tool_result', tool_use_id: callId, content: typeof output === 'string' ? output : JSON.stringify(output ?? '') };
+}
+
+function messagesToolChoice(choice: unknown, parallel: unknown): any {
+  let result: any;
+  if (choice === 'auto') {
+    result = { type: 'auto' };
+  } else if (choice === 'required') {
+    result = { type: 'any' };
+  } else if (choice === 'none') {
+    result = { type: 'none' };
+  } else if (choice && typeof choice === 'object') {
+    const name = (choice as any).function?.name ?? (choice as any).name;
+    result = typeof name === 'string' ? { type: 'tool', name } : undefined;
+  }
+  if (parallel === false) {
+    result = { ...(result ?? { type: 'auto' }), disable_parallel_tool_use: true };
+  }
+  return result;
+}
+
+function commonFields(body: any, turns: TurnList, maxTokens: unknown, tools: any[]): any {
+  const messages: any = { model: body.model, max_tokens: typeof maxTokens === 'number' ? maxTokens : DEFAULT_MAX_TOKENS };
+  if (turns.system.length > 0) {
+    messages.system = turns.system.join('\n\n');
+  }
+  messages.messages = turns.messages;
+  if (typeof body.temperature === 'number') {
+    // OpenAI allows up to 2, Anthropic up to 1
+    messages.temperature = Math.min(body.temperature, 1);
+  }
+  if (typeof body.top_p === 'number') {
+    messages.top_p = body.top_p;
+  }
+  if (body.stream === true) {
+    messages.stream = true;
+  }
+  if (typeof body.user === 'string') {
+    messages.metadata = { user_id: body.user };
+  }
+  if (tools.length > 0) {
+    messages.tools = tools;
+    const choice = messagesToolChoice(body.tool_choice, body.parallel_tool_calls);
+    if (choice) {
+      messages.tool_choice = choice;
+    }
+  }
+  return messages;
+}
+
+/**
+ * A Chat Completions request body as a Messages one
+ */
+export function chatCompletionsToMessages(body: any): any {
+  const turns = new TurnList();
+  for (const message of Array.isArray(body.messages) ? body.messages : []) {
+    switch (message?.role) {
+      case 'system':
+      case 'developer':
+        turns.system.push(textOf(message.content));
+        break;
+      case 'assistant': {
+        const blocks = contentBlocks(message.content).filter(block => block.type === 'text');
+        for (const call of Array.isArray(message.tool_calls) ? message.tool_calls : []) {
+          blocks.push({ type: 'tool_use', id: call.id, name: call.function?.name, input: parseArguments(call.function?.arguments) });
+        }
+        turns.add('assistant', blocks);
+        break;
+      }
+      case 'tool':
+        turns.add('user', [toolResult(message.tool_call_id, textOf(message.content))]);
+        break;
+      default:
+        turns.add('user', contentBlocks(message?.content));
+    }
+  }
+
+  const tools = (Array.isArray(body.tools) ? body.tools : [])
+    .filter((tool: any) => tool?.type === 'function' && tool.function?.name)
+    .map((tool: any) => ({
+      name: tool.function.name,
+      description: tool.function.description,
+      input_schema: tool.function.parameters ?? { type: 'object', properties: {} },
+    }));
+
+  const messages = commonFields(body, turns, body.max_completion_tokens ?? body.max_tokens, tools);
+  const stop = typeof body.stop === 'string' ? [body.stop] : body.stop;
+  if (Array.isArray(stop) && stop.length > 0) {
+    messages.stop_sequences = stop;
+  }
+  return messages;
+}
+
+/**
+ * A Responses request body as a Messages one
+ */
+export function responsesToMessages(body: any): any {
+  const turns = new TurnList();
+  if (typeof body.instructions === 'string' && body.instructions) {
+    turns.system.push(body.instructions);
+  }
+
+  const input = typeof body.input === 'string' ? [{ role: 'user', content: body.input }] : body.input;
+  for (const item of Array.isArray(input) ? input : []) {
+    const type = item?.type ?? 'message';
+    if (type === 'message') {
+      if (item.role === 'system' || item.role === 'developer') {
+        turns.system.push(textOf(item.content));
+      } else {
+        turns.add(item.role === 'assistant' ? 'assistant' : 'user', contentBlocks(item.content));
+      }
+    } else if (type === 'function_call') {
+      turns.add('assistant', [{ type: 'tool_use', id: item.call_id, name: item.name, input: parseArguments(item.arguments) }]);
+    } else if (type === 'function_call_output') {
+      turns.add('user', [toolResult(item.call_id, item.output)]);
+    }
+    // Reasoning items are encrypted for OpenAI models and mean nothing to Claude
+  }
+
+  const tools = (Array.isArray(body.tools) ? body.tools : [])
+    .filter((tool: any) => tool?.type === 'function' && tool.name)
+    .map((tool: any) => ({
+      name: tool.name,
+      description: tool.description,
+      input_schema: tool.parameters ?? { type: 'object', properties: {} },
+    }));
+
+  return commonFields(body, turns, body.max_output_tokens, tools);
+}
+
+// Anthropic's input_tokens leaves out cached prompt tokens; OpenAI's prompt counts include them
+function promptTokens(usage: any): { total: number; cached: number } {
+  const cached = usage?.cache_read_input_tokens ?? 0;
+  return { total: (usage?.input_tokens ?? 0) + cached + (usage?.cache_creation_input_tokens ?? 0), cached };
+}
+
+function chatUsage(usage: any): any {
+  const prompt = promptTokens(usage);
+  const completion = usage?.output_tokens ?? 0;
+  return {
+    prompt_tokens: prompt.total,
+    completion_tokens: completion,
+    total_tokens: prompt.total + completion,
+    prompt_tokens_details: { cached_tokens: prompt.cached },
+  };
+}
+
+function responsesUsage(usage: any): any {
+  const prompt = promptTokens(usage);
+  const output = usage?.output_tokens ?? 0;
+  return {
+    input_tokens: prompt.total,
+    input_tokens_details: { cached_tokens: prompt.cached },
+    output_tokens: output,
+    output_tokens_details: { reasoning_tokens: 0 },
+    total_tokens: prompt.total + output,
+  };
+}
+
+const nowSeconds = () => Math.floor(Date.now() / 1000);
+
+/**
+ * A Messages response body as a Chat Completions one
+ */
+export function messageToChatCompletion(body: any, requestedModel?: string): any {
+  const blocks: any[] = Array.isArray(body?.content) ? body.content : [];
+  const text = blocks.filter(block => block?.type === 'text').map(block => block.text).join('');
+  const toolCalls = blocks
+    .filter(block => block?.type === 'tool_use')
+    .map(block => ({ id: block.id, type: 'function', function: { name: block.name, arguments: JSON.stringify(block.input ?? {}) } }));
+
+  const message: any = { role: 'assistant', content: text || null };
+  if (toolCalls.length > 0) {
+    message.tool_calls = toolCalls;
+  }
+  return {
+    id: `chatcmpl-${body?.id ?? crypto.randomUUID()}`,
+    object: 'chat.completion',
+    created: nowSeconds(),
+    model: body?.model ?? requestedModel,
+    choices: [{ index: 0, message, finish_reason: CHAT_FINISH_REASONS[body?.stop_reason] ?? 'stop' }],
+    usage: chatUsage(body?.usage),
+  };
+}
+
+function responseOutput(blocks: any[]): any[] {
+  const output: any[] = [];
+  const text = blocks.filter(block => block?.type === 'text').map(block => block.text).join('');
+  if (text) {
+    output.push({
+      type: 'message',
+      id: `msg_${crypto.randomUUID()}`,
+      status: 'completed',
+      role: 'assistant',
+      content: [{ type: 'output_text', text, annotations: [] }],
+    });
+  }
+  for (const block of blocks.filter(block => block?.type === 'tool_use')) {
+    output.push({
+      type: 'function_call',
+      id: `fc_${block.id}`,
+      call_id: block.id,
+      name: block.name,
+      arguments: JSON.stringify(block.input ?? {}),
+      status: 'completed',
+    });
+  }
+  return output;
+}
+
+function responseObject(id: string, model: string | undefined, status: string, output: any[], usage: any): any {
+  return {
+    id,
+    object: 'response',
+    created_at: nowSeconds(),
+    status,
+    incomplete_details: status === 'incomplete' ? { reason: 'max_output_tokens' } : null,
+    model,
+    output,
+    usage,
+  };
+}
+
+/**
+ * A Messages response body as a Responses one
+ */
+export function messageToResponse(body: any, requestedModel?: string): any {
+  return responseObject(
+    `resp_${body?.id ?? crypto.randomUUID()}`,
+    body?.model ?? requestedModel,
+    body?.stop_reason === 'max_tokens' ? 'incomplete' : 'completed',
+    responseOutput(Array.isArray(body?.content) ? body.content : []),
+    responsesUsage(body?.usage)
+  );
+}
+
+/**
+ * An Anthropic error body in the OpenAI error format
+ */
+export function messagesErrorToOpenAI(text: string, status: number): any {
+  let message = text;
+  let type = status >= 500 ? 'server_error' : 'invalid_request_error';
+  try {
+    const parsed = JSON.parse(text);
+    message = parsed?.error?.message ?? text;
+    type = parsed?.error?.type ?? type;
+  } catch {
+    // Plain text error pages are passed on as the message
+  }
+  return { error: { message, type, param: null, code: null } };
+}
+
+/**
+ * Feeds Messages SSE events to `onEvent` and sends the client what it returns. A stream the upstream cut
+ * gets no closing events, so it still looks cut to the client.
+ */
+function messagesStreamTransform(onEvent: (data: any) => string): TransformStream<Uint8Array, Uint8Array> {
+  const decoder = new TextDecoder();
+  const encoder = new TextEncoder();
+  let buffer = '';
+
+  const consume = (text: string): string => {
+    const { events, remainder } = splitSseEvents(text);
+    buffer = remainder;
+    let out = '';
+    for (const event of events) {
+      const data = parseSseData(event);
+      if (data && typeof data.type === 'string') {
+        out += onEvent(data);
+      }
+    }
+    return out;
+  };
+
+  return new TransformStream({
+    transform(chunk, controller) {
+      const out = consume(buffer + decoder.decode(chunk, { stream: true }));
+      if (out) {
+        controller.enqueue(encoder.encode(out));
+      }
+    },
+    flush(controller) {
+      const out = consume(`${buffer}${decoder.decode()}\n\n`);
+      if (out) {
+        controller.enqueue(encoder.encode(out));
+      }
+    },
+  });
+}
+
+/**
+ * Messages SSE in, Chat Completions chunks out; usage only arrives when the client asked for it
+ */
+export function createMessagesToChatStream(requestBody: any): TransformStream<Uint8Array, Uint8Array> {
+  const includeUsage = requestBody?.stream_options?.include_usage === true;
+  let id = `chatcmpl-${crypto.randomUUID()}`;
+  let model: string | undefined = requestBody?.model;
+  let usage: any = {};
+  let stopReason: string | undefined;
+  let toolCount = 0;
+  const toolIndexes = new Map<number, number>(); // Content block index -> tool call index
+  const created = nowSeconds();
+
+  const chunk = (delta: any, finishReason: string | null = null): string =>
+    `data: ${JSON.stringify({
+      id,
+      object: 'chat.completion.chunk',
+      created,
+      model,
+      choices: [{ index: 0, delta, finish_reason: finishReason }],
+    })}\n\n`;
+
+  return messagesStreamTransform(
+    data => {
+      switch (data.type) {
+        case 'message_start':
+          id = `chatcmpl-${data.message?.id ?? crypto.randomUUID()}`;
+          model = data.message?.model ?? model;
+          usage = { ...data.message?.usage };
+          return chunk({ role: 'assistant', content: '' });
+        case 'content_block_start':
+          if (data.content_block?.type === 'tool_use') {
+            const index = toolCount++;
+            toolIndexes.set(data.index, index);
+            return chunk({
+              tool_calls: [
+                { index, id: data.content_block.id, type: 'function', function: { name: data.content_block.name, arguments: '' } },
+              ],
+            });
+          }
+          return '';
+        case 'content_block_delta':
+          if (data.delta?.type === 'text_delta') {
+            return chunk({ content: data.delta.text });
+          }
+          if (data.delta?.type === 'input_json_delta' && toolIndexes.has(data.index)) {
+            return chunk({ tool_calls: [{ index: toolIndexes.get(data.index), function: { arguments: data.delta.partial_json } }] });
+          }
+          return '';
+        case 'message_delta':
+          stopReason = data.delta?.stop_reason ?? stopReason;
+          usage = { ...usage, ...data.usage };
+          return '';
+        case 'message_stop': {
+          let out = chunk({}, CHAT_FINISH_REASONS[stopReason ?? ''] ?? 'stop');
+          if (includeUsage) {
+            out += `data: ${JSON.stringify({ id, object: 'chat.completion.chunk', created, model, choices: [], usage: chatUsage(usage) })}\n\n`;
+          }
+          return `${out}data: [DONE]\n\n`;
+        }
+        case 'error':
+          return `data: ${JSON.stringify(messagesErrorToOpenAI(JSON.stringify(data), 500))}\n\n`;
+        default:
+          return '';
+      }
+    }
+  );
+}
+
+/**
+ * Messages SSE in, Responses events out, numbered and named the way the Responses API streams them
+ */
+export function createMessagesToResponsesStream(requestBody: any): TransformStream<Uint8Array, Uint8Array> {
+  let sequence = 0;
+  let id = `resp_${crypto.randomUUID()}`;
+  let model: string | undefined = requestBody?.model;
+  let usage: any = {};
+  let stopReason: string | undefined;
+  const output: any[] = [];
+  // Content block index -> the output item it streams into
+  const items = new Map<number, { outputIndex: number; item: any; text: string }>();
+
+  const event = (type: string, data: Record<string, unknown>): string =>
+    `event: ${type}\ndata: ${JSON.stringify({ type, sequence_number: sequence++, ...data })}\n\n`;
+
+  return messagesStreamTransform(
+    data => {
+      switch (data.type) {
+        case 'message_start': {
+          id = `resp_${data.message?.id ?? crypto.randomUUID()}`;
+          model = data.message?.model ?? model;
+          usage = { ...data.message?.usage };
+          const response = responseObject(id, model, 'in_progress', [], null);
+          return event('response.created', { response }) + event('response.in_progress', { response });
+        }
+        case 'content_block_start': {
+          const block = data.content_block;
+          const outputIndex = output.length;
+          if (block?.type === 'text') {
+            const item = { type: 'message', id: `msg_${crypto.randomUUID()}`, status: 'in_progress', role: 'assistant', content: [] };
+            output.push(item);
+            items.set(data.index, { outputIndex, item, text: '' });
+            return (
+              event('response.output_item.added', { output_index: outputIndex, item }) +
+              event('response.content_part.added', {
+                item_id: item.id,
+                output_index: outputIndex,
+                content_index: 0,
+                part: { type: 'output_text', text: '', annotations: [] },
+              })
+            );
+          }
+          if (block?.type === 'tool_use') {
+            const item = { type: 'function_call', id: `fc_${block.id}`, call_id: block.id, name: block.name, arguments: '', status: 'in_progress' };
+            output.push(item);
+            items.set(data.index, { outputIndex, item, text: '' });
+            return event('response.output_item.added', { output_index: outputIndex, item });
+          }
+          return '';
+        }
+        case 'content_block_delta': {
+          const entry = items.get(data.index);
+          if (!entry) {
+            return '';
+          }
+          if (data.delta?.type === 'text_delta') {
+            entry.text += data.delta.text;
+            return event('response.output_text.delta', {
+              item_id: entry.item.id,
+              output_index: entry.outputIndex,
+              content_index: 0,
+              delta: data.delta.text,
+            });
+          }
+          if (data.delta?.type === 'input_json_delta') {
+            entry.text += data.delta.partial_json;
+            return event('response.function_call_arguments.delta', {
+              item_id: entry.item.id,
+              output_index: entry.outputIndex,
+              delta: data.delta.partial_json,
+            });
+          }
+          return '';
+        }
+        case 'content_block_stop': {
+          const entry = items.get(data.index);
+          if (!entry) {
+            return '';
+          }
+          const { item, outputIndex, text } = entry;
+          item.status = 'completed';
+          if (item.type === 'message') {
+            const part = { type: 'output_text', text, annotations: [] };
+            item.content = [part];
+            return (
+              event('response.output_text.done', { item_id: item.id, output_index: outputIndex, content_index: 0, text }) +
+              event('response.content_part.done', { item_id: item.id, output_index: outputIndex, content_index: 0, part }) +
+              event('response.output_item.done', { output_index: outputIndex, item })
+            );
+          }
+          item.arguments = text;
+          return (
+            event('response.function_call_arguments.done', { item_id: item.id, output_index: outputIndex, arguments: text }) +
+            event('response.output_item.done', { output_index: outputIndex, item })
+          );
+        }
+        case 'message_delta':
+          stopReason = data.delta?.stop_reason ?? stopReason;
+          usage = { ...usage, ...data.usage };
+          return '';
+        case 'message_stop': {
+          const status = stopReason === 'max_tokens' ? 'incomplete' : 'completed';
+          const response = responseObject(id, model, status, output, responsesUsage(usage));
+          return event(status === 'incomplete' ? 'response.incomplete' : 'response.completed', { response });
+        }
+        case 'error':
+          return event('error', { code: data.error?.type ?? 'api_error', message: data.error?.message ?? 'Upstream stream error', param: null });
+        default:
+          return '';
+      }
+    }
+  );
+}
+
+/**
+ * An upstream Messages response as the Chat Completions or Responses response the client asked for
+ */
+export async function translateMessagesResponse(
+  response: Response,
+  requestBody: any,
+  target: 'chat' | 'responses'
+): Promise<Response> {
+  const headers = new Headers(response.headers);
+  headers.delete('content-length');
+  headers.delete('content-encoding');
+
+  if (response.ok && response.body && (response.headers.get('content-type') ?? '').includes('text/event-stream')) {
+    const stream = target === 'chat' ? createMessagesToChatStream(requestBody) : createMessagesToResponsesStream(requestBody);
+    return new Response(response.body.pipeThrough(stream), { status: response.status, statusText: response.statusText, headers });
+  }
+
+  const text = await response.text();
+  let body: any;
+  if (response.ok) {
+    try {
+      const message = JSON.parse(text);
+      body = target === 'chat' ? messageToChatCompletion(message, requestBody?.model) : messageToResponse(message, requestBody?.model);
+    } catch {
+      // Not JSON; pass it through and let compliance checking flag it
+      return new Response(text, { status: response.status, statusText: response.statusText, headers });
+    }
+  } else {
+    body = messagesErrorToOpenAI(text, response.status);
+  }
+  headers.set('content-type', 'application/json');
+  return new Response(JSON.stringify(body), { status: response.status, statusText: response.statusText, headers });
+}
@@ -0,0 +1,189 @@
+// Shape cache - remembers request features a config keeps rejecting with 400 so later requests avoid them
+
+import type { SystemConfig } from '../config/types';
+import { downgradeContentBlocks } from './quirks';
+
+// Always needed to describe a request; a 400 naming one of these is about its value, not its presence
+const CORE_FIELDS = new Set(['model', 'messages', 'input', 'prompt', 'stream', 'max_tokens', 'system']);
+
+// Wording upstreams use when they don't implement a parameter, as opposed to rejecting its value
+const UNSUPPORTED_PATTERN =
+  /unsupported|not supported|does not support|unknown|unrecognized|not permitted|extra inputs|extra fields|not allowed|invalid (?:field|parameter|argument)/i;
+
+export type ShapeCacheConfig = SystemConfig['shapeCache'];
+
+/**
+ * What a request is made of: its model plus optional top-level fields (`metadata`) and
+ * non-text content block types (`content:document`)
+ */
+export interface RequestShape {
+  model: string;
+  features: string[];
+}
+
+export interface KnownBadShape {
+  config: string;
+  model: string;
+  feature: string;
+  error: string;      // Excerpt of the upstream message the rejection was learned from
+  learnedAt: number;
+  expiresAt: number;
+}
+
+export function requestShape(body: any): RequestShape | null {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    return null;
+  }
+
+  const features = new Set<string>();
+  for (const key of Object.keys(body)) {
+    if (!CORE_FIELDS.has(key) && body[key] !== undefined) {
+      features.add(key);
+    }
+  }
+  if (Array.isArray(body.messages)) {
+    for (const message of body.messages) {
+      for (const block of Array.isArray(message?.content) ? message.content : []) {
+        if (typeof block?.type === 'string' && block.type !== 'text') {
+          features.add(`content:${block.type}`);
+        }
+      }
+    }
+  }
+
+  return {
+    model: typeof body.model === 'string' ? body.model : '',
+    features: [...features].sort(),
+  };
+}
+
+const escapeRegExp = (value: string) => value.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+
+/**
+ * Features of `shape` that an error message blames, e.g. "Unrecognized request argument supplied: metadata"
+ */
+export function rejectedFeatures(shape: RequestShape, errorText: string): string[] {
+  if (!UNSUPPORTED_PATTERN.test(errorText)) {
+    return [];
+  }
+  return shape.features.filter(feature => {
+    const word = feature.startsWith('content:') ? feature.slice('content:'.length) : feature;
+    return new RegExp(`\\b${escapeRegExp(word)}\\b`).test(errorText);
+  });
+}
+
+/**
+ * Remove or downgrade `features` in a request body (content blocks become text). Returns a new body.
+ */
+export function withoutFeatures(body: any, features: string[]): any {
+  const next = { ...body };
+  const blockTypes = new Set<string>();
+  for (const feature of features) {
+    if (feature.startsWith('content:')) {
+      blockTypes.add(feature.slice('content:'.length));
+    } else {
+      delete next[feature];
+    }
+  }
+  if (blockTypes.size > 0) {
+    downgradeContentBlocks(next, type => !blockTypes.has(type));
+  }
+  return next;
+}
+
+export class RequestShapeCache {
+  private config: ShapeCacheConfig;
+  private strikes = new Map<string, number>();
+  private known = new Map<string, KnownBadShape>();
+
+  constructor(config: ShapeCacheConfig) {
+    this.config = config;
+  }
+
+  isEnabled(): boolean {
+    return this.config.threshold > 0 && this.config.ttlSeconds > 0;
+  }
+
+  /**
+   * Features of `shape` that `configName` is known to reject
+   */
+  rejectedBy(configName: string, shape: RequestShape): string[] {
+    const now = Date.now();
+    return shape.features.filter(feature => {
+      const key = shapeKey(configName, shape.model, feature);
+      const entry = this.known.get(key);
+      if (entry && entry.expiresAt <= now) {
+        this.known.delete(key);
+        return false;
+      }
+      return Boolean(entry);
+    });
+  }
+
+  /**
+   * Count a 400 from `configName`; returns the entries that just crossed the threshold
+   */
+  recordRejection(configName: string, shape: RequestShape, errorText: string): KnownBadShape[] {
+    const learned: KnownBadShape[] = [];
+    for (const feature of rejectedFeatures(shape, errorText)) {
+      const key = shapeKey(configName, shape.model, feature);
+      const strikes = (this.strikes.get(key) ?? 0) + 1;
+      this.strikes.set(key, strikes);
+      if (strikes < this.config.threshold || this.known.has(key)) {
+        continue;
+      }
+      const now = Date.now();
+      const entry: KnownBadShape = {
+        config: configName,
+        model: shape.model,
+        feature,
+        error: errorText.slice(0, 300),
+        learnedAt: now,
+        expiresAt: now + this.config.ttlSeconds * 1000,
+      };
+      this.known.set(key, entry);
+      this.strikes.delete(key);
+      learned.push(entry);
+    }
+    return learned;
+  }
+
+  /**
+   * A success proves every feature the request carried is accepted, so earlier strikes are dropped
+   */
+  recordSuccess(configName: string, shape: RequestShape): void {
+    for (const feature of shape.features) {
+      const key = shapeKey(configName, shape.model, feature);
+      this.strikes.delete(key);
+      this.known.delete(key);
+    }
+  }
+
+  list(): KnownBadShape[] {
+    const now = Date.now();
+    return [...this.known.values()].filter(entry => entry.expiresAt > now);
+  }
+
+  /**
+   * Forget learned rejections, for one config or all of them; returns how many were dropped
+   */
+  clear(configName?: string): number {
+    let removed = 0;
+    for (const [key, entry] of this.known) {
+      if (!configName || entry.config === configName) {
+        this.known.delete(key);
+        removed++;
+      }
+    }
+    for (const key of this.strikes.keys()) {
+      if (!configName || key.startsWith(`${configName}\n`)) {
+        this.strikes.delete(key);
+      }
+    }
+    return removed;
+  }
+}
+
+function shapeKey(configName: string, model: string, feature: string): string {
+  return `${configName}\n${model}\n${feature}`;
+}
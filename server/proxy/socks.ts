@@ -0,0 +1,209 @@
+// SOCKS5 client (RFC 1928, username/password from RFC 1929) for the egress bridge. socks5:// resolves
+// upstream hostnames locally; socks5h:// leaves resolution to the proxy.
+
+import { lookup } from 'node:dns/promises';
+import { isIP, type Socket } from 'node:net';
+import type { Dialer } from './egressBridge';
+
+export const SOCKS_PROXY_PROTOCOLS = new Set(['socks5:', 'socks5h:']);
+
+const SOCKS_VERSION = 5;
+const AUTH_NONE = 0x00;
+const AUTH_PASSWORD = 0x02;
+const AUTH_REFUSED = 0xff;
+const CMD_CONNECT = 0x01;
+const ATYP_IPV4 = 0x01;
+const ATYP_DOMAIN = 0x03;
+const ATYP_IPV6 = 0x04;
+
+// A proxy that accepts the TCP connection but never answers should not hold a request forever
+const HANDSHAKE_TIMEOUT_MS = 10_000;
+
+const REPLY_MESSAGES: Record<number, string> = {
+  1: 'general failure',
+  2: 'connection not allowed by ruleset',
+  3: 'network unreachable',
+  4: 'host unreachable',
+  5: 'connection refused',
+  6: 'TTL expired',
+  7: 'command not supported',
+  8: 'address type not supported',
+};
+
+export function isSocksProxyUrl(value: string): boolean {
+  try {
+    return SOCKS_PROXY_PROTOCOLS.has(new URL(value).protocol);
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Open a connection to host:port through the SOCKS5 proxy at `proxyUrl`; `dial` reaches the proxy itself.
+ * The socket comes back paused, so nothing that follows the handshake is lost before it is piped.
+ */
+export async function socks5Connect(proxyUrl: string, host: string, port: number, dial: Dialer): Promise<Socket> {
+  const proxy = new URL(proxyUrl);
+  const proxyHost = proxy.hostname.startsWith('[') ? proxy.hostname.slice(1, -1) : proxy.hostname;
+  const socket = await dial(proxyHost, Number(proxy.port) || 1080);
+  socket.setTimeout(HANDSHAKE_TIMEOUT_MS, () => socket.destroy(new Error('SOCKS5 handshake timed out')));
+  const reader = new SocketReader(socket);
+
+  try {
+    const username = Buffer.from(decodeURIComponent(proxy.username));
+    const password = Buffer.from(decodeURIComponent(proxy.password));
+    if (username.length > 255 || password.length > 255) {
+      throw new Error('SOCKS5 username and password are limited to 255 bytes');
+    }
+
+    const methods = username.length > 0 ? [AUTH_NONE, AUTH_PASSWORD] : [AUTH_NONE];
+    socket.write(Buffer.from([SOCKS_VERSION, methods.length, ...methods]));
+    const [version, method] = await reader.read(2);
+    if (version !== SOCKS_VERSION) {
+      throw new Error(`${proxy.host} is not a SOCKS5 proxy`);
+    }
+    if (method === AUTH_PASSWORD) {
+      socket.write(Buffer.concat([Buffer.from([1, username.length]), username, Buffer.from([password.length]), password]));
+      const [, status] = await reader.read(2);
+      if (status !== 0) {
+        throw new Error(`SOCKS5 proxy ${proxy.host} rejected the credentials`);
+      }
+    } else if (method !== AUTH_NONE) {
+      throw new Error(
+        method === AUTH_REFUSED && username.length === 0
+          ? `SOCKS5 proxy ${proxy.host} requires credentials`
+          : `SOCKS5 proxy ${proxy.host} offered no supported authentication method`
+      );
+    }
+
+    const address = await encodeAddress(host, proxy.protocol === 'socks5h:');
+    socket.write(Buffer.concat([Buffer.from([SOCKS_VERSION, CMD_CONNECT, 0]), address, Buffer.from([port >> 8, port & 0xff])]));
+    const [, reply, , addressType] = await reader.read(4);
+    if (reply !== 0) {
+      throw new Error(`SOCKS5 proxy could not reach ${host}:${port}: ${REPLY_MESSAGES[reply] ?? `reply ${reply}`}`);
+    }
+    // Skip the bound address and port the proxy reports
+    const boundLength =
+      addressType === ATYP_IPV4 ? 4 : addressType === ATYP_IPV6 ? 16 : (await reader.read(1))[0];
+    await reader.read(boundLength + 2);
+
+    socket.setTimeout(0);
+    const rest = reader.release();
+    if (rest.length > 0) {
+      socket.unshift(rest);
+    }
+    return socket;
+  } catch (error) {
+    reader.release();
+    socket.destroy();
+    throw error;
+  }
+}
+
+async function encodeAddress(host: string, remoteDns: boolean): Promise<Buffer> {
+  let address = host;
+  if (!isIP(host) && !remoteDns) {
+    address = (await lookup(host)).address;
+  }
+
+  const family = isIP(address);
+  if (family === 4) {
+    return Buffer.from([ATYP_IPV4, ...address.split('.').map(Number)]);
+  }
+  if (family === 6) {
+    return Buffer.concat([Buffer.from([ATYP_IPV6]), ipv6Bytes(address)]);
+  }
+  const name = Buffer.from(address);
+  if (name.length > 255) {
+    throw new Error(`hostname too long for SOCKS5: ${address}`);
+  }
+  return Buffer.concat([Buffer.from([ATYP_DOMAIN, name.length]), name]);
+}
+
+function ipv6Bytes(address: string): Buffer {
+  // An embedded IPv4 tail (::ffff:192.0.2.1) becomes two hex groups
+  const ipv4Tail = address.match(/(\d+)\.(\d+)\.(\d+)\.(\d+)$/);
+  let text = address.replace(/%.*$/, '');
+  if (ipv4Tail) {
+    const [a, b, c, d] = ipv4Tail.slice(1).map(Number);
+    text = text.slice(0, text.length - ipv4Tail[0].length) + `${((a << 8) | b).toString(16)}:${((c << 8) | d).toString(16)}`;
+  }
+
+  const [head, tail] = text.split('::');
+  const headGroups = head ? head.split(':') : [];
+  const tailGroups = tail ? tail.split(':') : [];
+  const groups =
+    tail === undefined
+      ? headGroups
+      : [...headGroups, ...Array(8 - headGroups.length - tailGroups.length).fill('0'), ...tailGroups];
+
+  const bytes = Buffer.alloc(16);
+  groups.forEach((group, index) => bytes.writeUInt16BE(parseInt(group, 16), index * 2));
+  return bytes;
+}
+
+/**
+ * Reads exact byte counts off a socket during the handshake, then hands back whatever arrived early
+ */
+class SocketReader {
+  private buffered = Buffer.alloc(0);
+  private waiting?: { length: number; resolve: (bytes: Buffer) => void; reject: (error: Error) => void };
+  private failure?: Error;
+
+  constructor(private socket: Socket) {
+    socket.on('data', this.onData);
+    socket.on('error', this.onError);
+    socket.on('close', this.onClose);
+  }
+
+  read(length: number): Promise<Buffer> {
+    return new Promise((resolve, reject) => {
+      this.waiting = { length, resolve, reject };
+      this.settle();
+    });
+  }
+
+  /**
+   * Stop reading; the socket is left paused
+   */
+  release(): Buffer {
+    this.socket.off('data', this.onData);
+    this.socket.off('error', this.onError);
+    this.socket.off('close', this.onClose);
+    this.socket.pause();
+    const rest = this.buffered;
+    this.buffered = Buffer.alloc(0);
+    return rest;
+  }
+
+  private onData = (chunk: Buffer) => {
+    this.buffered = Buffer.concat([this.buffered, chunk]);
+    this.settle();
+  };
+
+  private onError = (error: Error) => {
+    this.failure = error;
+    this.settle();
+  };
+
+  private onClose = () => {
+    this.failure ??= new Error('SOCKS5 proxy closed the connection during the handshake');
+    this.settle();
+  };
+
+  private settle(): void {
+    const waiting = this.waiting;
+    if (!waiting) {
+      return;
+    }
+    if (this.buffered.length >= waiting.length) {
+      this.waiting = undefined;
+      const bytes = this.buffered.subarray(0, waiting.length);
+      this.buffered = this.buffered.subarray(waiting.length);
+      waiting.resolve(bytes);
+    } else if (this.failure) {
+      this.waiting = undefined;
+      waiting.reject(this.failure);
+    }
+  }
+}
@@ -0,0 +1,46 @@
+// SSE keepalive - injects `: ping` comments while an upstream stream is silent
+
+export interface SseKeepalive {
+  /** Record text about to be forwarded so pings only land between complete events */
+  noteChunk(text: string): void;
+  stop(): void;
+}
+
+const PING = new TextEncoder().encode(': ping\n\n');
+
+/**
+ * Start injecting keepalive comments into a client stream; a zero interval disables it
+ */
+export function startSseKeepalive(
+  writer: WritableStreamDefaultWriter<any>,
+  intervalMs: number
+): SseKeepalive {
+  if (!intervalMs || intervalMs <= 0) {
+    return { noteChunk: () => {}, stop: () => {} };
+  }
+
+  let lastWriteAt = Date.now();
+  let atEventBoundary = true;
+
+  const timer = setInterval(() => {
+    if (!atEventBoundary || Date.now() - lastWriteAt < intervalMs) {
+      return;
+    }
+    lastWriteAt = Date.now();
+    writer.write(PING).catch(() => {
+      clearInterval(timer);
+    });
+  }, Math.min(intervalMs, 1000));
+
+  return {
+    noteChunk(text: string) {
+      lastWriteAt = Date.now();
+      if (text.length > 0) {
+        atEventBoundary = text.endsWith('\n\n') || text.endsWith('\r\n\r\n');
+      }
+    },
+    stop() {
+      clearInterval(timer);
+    },
+  };
+}
@@ -0,0 +1,117 @@
+// Stream salvage helpers - recover what an interrupted SSE stream already delivered
+
+export interface SseEvent {
+  event?: string;
+  data?: string;
+  raw: string;
+}
+
+/**
+ * Split SSE text into complete events; a trailing partial event is returned separately
+ */
+export function splitSseEvents(text: string): { events: SseEvent[]; remainder: string } {
+  const normalized = text.replace(/\r\n/g, '\n');
+  const blocks = normalized.split('\n\n');
+  const remainder = blocks.pop() ?? '';
+  const events = blocks
+    .filter(block => block.trim().length > 0)
+    .map(block => {
+      const event: SseEvent = { raw: `${block}\n\n` };
+      const dataLines: string[] = [];
+      for (const line of block.split('\n')) {
+        if (line.startsWith('event:')) {
+          event.event = line.slice(6).trim();
+        } else if (line.startsWith('data:')) {
+          dataLines.push(line.slice(5).trimStart());
+        }
+      }
+      if (dataLines.length > 0) {
+        event.data = dataLines.join('\n');
+      }
+      return event;
+    });
+
+  return { events, remainder };
+}
+
+export function parseSseData(event: SseEvent): any {
+  if (!event.data || event.data === '[DONE]') {
+    return null;
+  }
+  try {
+    return JSON.parse(event.data);
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Concatenate generated text from Anthropic text deltas or OpenAI chat/responses deltas
+ */
+export function extractStreamText(sse: string): string {
+  const { events } = splitSseEvents(`${sse}\n\n`);
+  let text = '';
+
+  for (const event of events) {
+    const data = parseSseData(event);
+    if (!data) {
+      continue;
+    }
+
+    if (data.type === 'content_block_delta' && data.delta?.type === 'text_delta') {
+      text += data.delta.text ?? '';
+    } else if (data.type === 'response.output_text.delta' && typeof data.delta === 'string') {
+      text += data.delta;
+    } else if (typeof data.choices?.[0]?.delta?.content === 'string') {
+      text += data.choices[0].delta.content;
+    }
+  }
+
+  return text;
+}
+
+/**
+ * Concatenate Anthropic thinking deltas (a summary of the full thinking on newer models)
+ */
+export function extractStreamThinking(sse: string): string {
+  const { events } = splitSseEvents(`${sse}\n\n`);
+  let thinking = '';
+
+  for (const event of events) {
+    const data = parseSseData(event);
+    if (data?.type === 'content_block_delta' && data.delta?.type === 'thinking_delta') {
+      thinking += data.delta.thinking ?? '';
+    }
+  }
+
+  return thinking;
+}
+
+/**
+ * Remove thinking block events from SSE text so their content is never stored
+ */
+export function stripStreamThinking(sse: string): string {
+  const { events, remainder } = splitSseEvents(sse.replace(/\r\n/g, '\n'));
+  const thinkingIndexes = new Set<number>();
+
+  const kept = events.filter(event => {
+    const data = parseSseData(event);
+    if (data?.type === 'content_block_start' && /thinking/.test(data.content_block?.type ?? '')) {
+      thinkingIndexes.add(data.index);
+      return false;
+    }
+    return !(
+      (data?.type === 'content_block_delta' || data?.type === 'content_block_stop') &&
+      thinkingIndexes.has(data.index)
+    );
+  });
+
+  return kept.map(event => event.raw).join('') + remainder;
+}
+
+/**
+ * Rough token estimate (~4 characters per token) used when the stream ended before usage arrived
+ */
+export function estimateTokens(text: string): number {
+  return text.length === 0 ? 0 : Math.ceil(text.length / 4);
+}
@@ -0,0 +1,54 @@
+// Request tags - client-supplied key/value labels for attributing usage, e.g. per project
+
+export const TAGS_HEADER = 'x-paf-tags';
+
+export type RequestTags = Record<string, string>;
+
+// Keys are used as JSON paths in stats queries, so they stay to a safe character set
+export const TAG_KEY_PATTERN = /^[A-Za-z0-9_-]{1,64}$/;
+const MAX_TAGS = 16;
+const MAX_VALUE_LENGTH = 128;
+
+/**
+ * Parse `project=foo,ticket=BUG-123`; malformed entries are skipped rather than failing the request
+ */
+export function parseRequestTags(header: string | null): RequestTags | undefined {
+  if (!header) {
+    return undefined;
+  }
+
+  const tags: RequestTags = {};
+  let count = 0;
+  for (const entry of header.split(',')) {
+    const separator = entry.indexOf('=');
+    if (separator <= 0) {
+      continue;
+    }
+    const key = entry.slice(0, separator).trim();
+    const value = entry.slice(separator + 1).trim().slice(0, MAX_VALUE_LENGTH);
+    if (!TAG_KEY_PATTERN.test(key) || !value) {
+      continue;
+    }
+    if (!(key in tags) && ++count > MAX_TAGS) {
+      break;
+    }
+    tags[key] = value;
+  }
+
+  return count > 0 ? tags : undefined;
+}
+
+/**
+ * Parse a `key=value` (or bare `key`) filter from a query string
+ */
+export function parseTagFilter(value: string | null): { key: string; value?: string } | null {
+  if (!value) {
+    return null;
+  }
+  const separator = value.indexOf('=');
+  const key = (separator === -1 ? value : value.slice(0, separator)).trim();
+  if (!TAG_KEY_PATTERN.test(key)) {
+    return null;
+  }
+  return separator === -1 ? { key } : { key, value: value.slice(separator + 1).trim() };
+}
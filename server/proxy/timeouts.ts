@@ -0,0 +1,90 @@
+// Upstream timeouts - timeout_secs / connect_timeout_secs of a config (or its service), so a slow relay
+// can be capped without touching the others
+
+import type { ProxyConfig, ServiceConfig } from '../config/types';
+
+export interface UpstreamTimeouts {
+  totalMs?: number;   // The whole exchange, response body (and stream) included
+  connectMs?: number; // Until response headers; Bun's fetch exposes no separate connect or TLS phase
+}
+
+/**
+ * A positive number of seconds from TOML or the API; anything else means no timeout
+ */
+export function parseTimeoutSecs(value: unknown): number | undefined {
+  return typeof value === 'number' && Number.isFinite(value) && value > 0 ? value : undefined;
+}
+
+/**
+ * The config's own timeouts, each falling back to the service's
+ */
+export function upstreamTimeouts(config: ProxyConfig, serviceConfig?: ServiceConfig): UpstreamTimeouts {
+  const total = config.timeoutSecs ?? serviceConfig?.timeoutSecs;
+  const connect = config.connectTimeoutSecs ?? serviceConfig?.connectTimeoutSecs;
+  return {
+    totalMs: total !== undefined ? total * 1000 : undefined,
+    connectMs: connect !== undefined ? connect * 1000 : undefined,
+  };
+}
+
+/**
+ * Aborts one upstream attempt when a timeout passes. Without any timeout it adds nothing, leaving
+ * Bun's built-in inactivity limit (300s) in charge; with one, that limit is switched off (fetchTimeout)
+ * so timeouts longer than 300s apply as configured.
+ */
+export class UpstreamDeadline {
+  private controller = new AbortController();
+  private totalTimer?: ReturnType<typeof setTimeout>;
+  private connectTimer?: ReturnType<typeof setTimeout>;
+
+  constructor(private timeouts: UpstreamTimeouts, private configName: string) {
+    if (timeouts.totalMs !== undefined) {
+      this.totalTimer = this.arm(timeouts.totalMs, `timed out after ${timeouts.totalMs / 1000}s (timeout_secs)`);
+    }
+    if (timeouts.connectMs !== undefined) {
+      this.connectTimer = this.arm(
+        timeouts.connectMs,
+        `timed out: no response headers within ${timeouts.connectMs / 1000}s (connect_timeout_secs)`
+      );
+    }
+  }
+
+  /**
+   * The signal to fetch with: the deadline combined with the client's own signal, if any
+   */
+  signal(clientSignal?: AbortSignal): AbortSignal | undefined {
+    if (this.timeouts.totalMs === undefined && this.timeouts.connectMs === undefined) {
+      return clientSignal;
+    }
+    return clientSignal ? AbortSignal.any([clientSignal, this.controller.signal]) : this.controller.signal;
+  }
+
+  /**
+   * fetch's `timeout` option: false replaces Bun's 300s inactivity limit with the configured timeouts
+   */
+  fetchTimeout(): false | undefined {
+    return this.timeouts.totalMs === undefined && this.timeouts.connectMs === undefined ? undefined : false;
+  }
+
+  /**
+   * Response headers arrived; only timeout_secs is left running
+   */
+  responded(): void {
+    clearTimeout(this.connectTimer);
+  }
+
+  /**
+   * The attempt is over (failed over or abandoned)
+   */
+  clear(): void {
+    clearTimeout(this.connectTimer);
+    clearTimeout(this.totalTimer);
+  }
+
+  // Firing after the exchange finished is harmless: aborting a completed fetch does nothing
+  private arm(ms: number, message: string): ReturnType<typeof setTimeout> {
+    return setTimeout(() => {
+      this.controller.abort(new DOMException(`${this.configName} ${message}`, 'TimeoutError'));
+    }, ms);
+  }
+}
@@ -0,0 +1,131 @@
+// TLS proxy ports - HTTPS, optionally with required client certificates (mTLS), for proxy ports reachable
+// beyond localhost. Bun.serve can't tell which certificate a client presented, so these run on node:https.
+
+import { readFileSync } from 'node:fs';
+import { once } from 'node:events';
+import { createServer, type Server } from 'node:https';
+import type { IncomingMessage, ServerResponse } from 'node:http';
+import { Readable } from 'node:stream';
+import type { TLSSocket } from 'node:tls';
+import type { ProxyTlsConfig } from '../config/types';
+
+export interface ClientCertificate {
+  commonName: string;
+  identity: string;    // Mapped through [proxy_tls] identities; the CN itself when unmapped
+  fingerprint: string; // SHA-256, colon separated
+}
+
+export type TlsProxyHandler = (request: Request, client: ClientCertificate | undefined) => Promise<Response>;
+
+/**
+ * The verified client certificate of a connection; undefined without mTLS or when the client sent none
+ */
+function clientCertificateOf(socket: TLSSocket, tls: ProxyTlsConfig): ClientCertificate | undefined {
+  if (!tls.clientCa || !socket.authorized) {
+    return undefined;
+  }
+  const certificate = socket.getPeerCertificate();
+  const commonName = [certificate?.subject?.CN].flat()[0];
+  if (!commonName) {
+    return undefined;
+  }
+  return {
+    commonName,
+    identity: tls.identities[commonName] ?? commonName,
+    fingerprint: certificate.fingerprint256,
+  };
+}
+
+/**
+ * The request body as a stream, never buffered here: the handler enforces max_request_body_bytes and the
+ * memory budget on it exactly as on the plain ports (declared sizes first, then while reading)
+ */
+function requestBody(message: IncomingMessage): ReadableStream<Uint8Array> | undefined {
+  if (message.method === 'GET' || message.method === 'HEAD') {
+    return undefined;
+  }
+  return Readable.toWeb(message) as ReadableStream<Uint8Array>;
+}
+
+async function respond(message: IncomingMessage, res: ServerResponse, tls: ProxyTlsConfig, handler: TlsProxyHandler) {
+  // The client hanging up cancels the upstream call, as on the plain ports
+  const controller = new AbortController();
+  res.on('close', () => {
+    if (!res.writableFinished) {
+      controller.abort();
+    }
+  });
+
+  const headers = new Headers();
+  for (const [name, value] of Object.entries(message.headers)) {
+    for (const item of [value ?? []].flat()) {
+      headers.append(name, item);
+    }
+  }
+
+  let response: Response;
+  try {
+    const request = new Request(`https://${message.headers.host ?? 'localhost'}${message.url ?? '/'}`, {
+      method: message.method,
+      headers,
+      body: requestBody(message),
+      duplex: 'half',
+      signal: controller.signal,
+    } as RequestInit);
+    response = await handler(request, clientCertificateOf(message.socket as TLSSocket, tls));
+  } catch (error) {
+    console.error('[proxy:tls] Request failed:', error);
+    res.writeHead(500).end();
+    return;
+  }
+
+  const responseHeaders: Record<string, string | string[]> = {};
+  response.headers.forEach((value, name) => {
+    responseHeaders[name] = name === 'set-cookie' ? response.headers.getSetCookie() : value;
+  });
+  res.writeHead(response.status, response.statusText, responseHeaders);
+
+  if (!response.body) {
+    res.end();
+    return;
+  }
+  // Write chunks as they arrive so SSE streams reach the client without buffering
+  const reader = response.body.getReader();
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done || res.destroyed) {
+        break;
+      }
+      if (!res.write(value)) {
+        await once(res, 'drain');
+      }
+    }
+    res.end();
+  } catch {
+    await reader.cancel().catch(() => {});
+    res.destroy();
+  }
+}
+
+/**
+ * Listen on `port` with the [proxy_tls] certificate. With a client CA, connections without a
+ * certificate it signed are refused during the handshake, before any request is read.
+ */
+export function serveTlsProxy(port: number, tls: ProxyTlsConfig, handler: TlsProxyHandler): Server {
+  const server = createServer(
+    {
+      cert: readFileSync(tls.cert),
+      key: readFileSync(tls.key),
+      ...(tls.clientCa ? { ca: readFileSync(tls.clientCa), requestCert: true, rejectUnauthorized: true } : {}),
+    },
+    (message, res) => {
+      void respond(message, res, tls, handler);
+    }
+  );
+  server.on('tlsClientError', error => {
+    console.warn(`[proxy:tls] port ${port}: rejected connection: ${error.message}`);
+  });
+  server.listen(port);
+  return server;
+}
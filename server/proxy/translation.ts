@@ -0,0 +1,468 @@
+// Protocol translation - serve Claude-protocol (/v1/messages) clients from configs that only speak OpenAI
+// Chat Completions (api_format = "openai-chat"). Requests are rewritten on the way out; JSON responses,
+// SSE streams and errors come back in the Messages format so nothing downstream needs to know.
+// The opposite direction (api_format = "anthropic") lives in reverseTranslation.ts.
+
+import type { ApiFormat } from '../config/types';
+import { splitSseEvents, parseSseData } from './streamSalvage';
+import { chatCompletionsToMessages, responsesToMessages, translateMessagesResponse } from './reverseTranslation';
+
+// The service whose clients each format serves
+const API_FORMATS: Record<ApiFormat, string> = {
+  'openai-chat': 'claude',
+  anthropic: 'codex',
+};
+
+/**
+ * How one client request is rewritten for a config with an api_format
+ */
+export interface UpstreamTranslation {
+  path: string;            // Upstream path replacing the client's
+  request(body: any): any; // Client request body -> upstream request body
+  response(response: Response, requestBody: any): Promise<Response>; // Upstream response -> the client's format
+}
+
+const FINISH_REASONS: Record<string, string> = {
+  stop: 'end_turn',
+  length: 'max_tokens',
+  tool_calls: 'tool_use',
+  function_call: 'tool_use',
+  content_filter: 'refusal',
+};
+
+const ERROR_TYPES: Record<number, string> = {
+  400: 'invalid_request_error',
+  401: 'authentication_error',
+  403: 'permission_error',
+  404: 'not_found_error',
+  413: 'request_too_large',
+  429: 'rate_limit_error',
+  503: 'overloaded_error',
+  529: 'overloaded_error',
+};
+
+/**
+ * Why `value` can't be a config's api_format on `serviceName`; null when it can
+ */
+export function apiFormatError(serviceName: string, value: unknown): string | null {
+  if (typeof value !== 'string' || !Object.hasOwn(API_FORMATS, value)) {
+    return `unknown api_format ${JSON.stringify(value)}; expected one of ${Object.keys(API_FORMATS).join(', ')}`;
+  }
+  const service = API_FORMATS[value as ApiFormat];
+  if (serviceName !== service) {
+    return `api_format "${value}" only applies to ${service} configs`;
+  }
+  return null;
+}
+
+/**
+ * The translation for a request to `pathname` on a config with `apiFormat`; null when the request is
+ * forwarded as is. Only generation endpoints are translated; the rest (count_tokens, models) pass through.
+ */
+export function upstreamTranslation(apiFormat: ApiFormat | undefined, pathname: string): UpstreamTranslation | null {
+  if (apiFormat === 'openai-chat' && /\/v1\/messages$/.test(pathname)) {
+    return {
+      path: pathname.replace(/\/messages$/, '/chat/completions'),
+      request: messagesToChatCompletions,
+      response: (response, requestBody) => translateChatResponse(response, requestBody?.model),
+    };
+  }
+  if (apiFormat === 'anthropic' && /\/chat\/completions$/.test(pathname)) {
+    return {
+      path: pathname.replace(/\/chat\/completions$/, '/messages'),
+      request: chatCompletionsToMessages,
+      response: (response, requestBody) => translateMessagesResponse(response, requestBody, 'chat'),
+    };
+  }
+  if (apiFormat === 'anthropic' && /\/responses$/.test(pathname)) {
+    return {
+      path: pathname.replace(/\/responses$/, '/messages'),
+      request: responsesToMessages,
+      response: (response, requestBody) => translateMessagesResponse(response, requestBody, 'responses'),
+    };
+  }
+  return null;
+}
+
+function textOf(content: any): string {
+  if (typeof content === 'string') {
+    return content;
+  }
+  if (!Array.isArray(content)) {
+    return '';
+  }
+  return content
+    .filter((block: any) => block?.type === 'text' && typeof block.text === 'string')
+    .map((block: any) => block.text)
+    .join('\n');
+}
+
+function imagePart(source: any): any {
+  if (source?.type === 'base64') {
+    return { type: 'image_url', image_url: { url: `data:${source.media_type};base64,${source.data}` } };
+  }
+  if (source?.type === 'url') {
+    return { type: 'image_url', image_url: { url: source.url } };
+  }
+  return null;
+}
+
+function userMessages(content: any): any[] {
+  if (!Array.isArray(content)) {
+    return [{ role: 'user', content: typeof content === 'string' ? content : '' }];
+  }
+
+  // Tool results become tool messages, which must directly follow the assistant turn that called them
+  const messages: any[] = [];
+  const parts: any[] = [];
+  for (const block of content) {
+    if (block?.type === 'tool_result') {
+      const text = textOf(block.content) || (block.is_error ? 'Error' : '');
+      messages.push({ role: 'tool', tool_call_id: block.tool_use_id, content: text });
+    } else if (block?.type === 'text') {
+      parts.push({ type: 'text', text: block.text });
+    } else if (block?.type === 'image') {
+      const image = imagePart(block.source);
+      if (image) {
+        parts.push(image);
+      }
+    } else if (block?.type === 'document' && block.source?.type === 'text') {
+      parts.push({ type: 'text', text: block.source.data });
+    }
+  }
+
+  if (parts.length > 0) {
+    const textOnly = parts.every(part => part.type === 'text');
+    messages.push({ role: 'user', content: textOnly ? parts.map(part => part.text).join('\n') : parts });
+  }
+  return messages;
+}
+
+function assistantMessage(content: any): any {
+  if (!Array.isArray(content)) {
+    return { role: 'assistant', content: typeof content === 'string' ? content : '' };
+  }
+
+  // Thinking blocks carry Anthropic signatures no other provider can verify; they are dropped
+  const message: any = { role: 'assistant', content: textOf(content) || null };
+  const toolCalls = content
+    .filter((block: any) => block?.type === 'tool_use')
+    .map((block: any) => ({
+      id: block.id,
+      type: 'function',
+      function: { name: block.name, arguments: JSON.stringify(block.input ?? {}) },
+    }));
+  if (toolCalls.length > 0) {
+    message.tool_calls = toolCalls;
+  }
+  return message;
+}
+
+function toolChoice(choice: any): any {
+  switch (choice?.type) {
+    case 'auto':
+      return 'auto';
+    case 'any':
+      return 'required';
+    case 'none':
+      return 'none';
+    case 'tool':
+      return { type: 'function', function: { name: choice.name } };
+    default:
+      return undefined;
+  }
+}
+
+/**
+ * A Messages request body as a Chat Completions one. Server tools (web search, code execution) have no
+ * Chat Completions equivalent and are left out.
+ */
+export function messagesToChatCompletions(body: any): any {
+  const messages: any[] = [];
+  const system = textOf(body.system);
+  if (system) {
+    messages.push({ role: 'system', content: system });
+  }
+  for (const message of Array.isArray(body.messages) ? body.messages : []) {
+    if (message?.role === 'assistant') {
+      messages.push(assistantMessage(message.content));
+    } else {
+      messages.push(...userMessages(message?.content));
+    }
+  }
+
+  const chat: any = { model: body.model, messages };
+  if (body.max_tokens !== undefined) {
+    chat.max_tokens = body.max_tokens;
+  }
+  for (const field of ['temperature', 'top_p'] as const) {
+    if (body[field] !== undefined) {
+      chat[field] = body[field];
+    }
+  }
+  if (Array.isArray(body.stop_sequences) && body.stop_sequences.length > 0) {
+    chat.stop = body.stop_sequences;
+  }
+  if (body.stream === true) {
+    // Without it a streamed completion carries no token counts at all
+    chat.stream = true;
+    chat.stream_options = { include_usage: true };
+  }
+  if (typeof body.metadata?.user_id === 'string') {
+    chat.user = body.metadata.user_id;
+  }
+
+  const tools = (Array.isArray(body.tools) ? body.tools : []).filter(
+    (tool: any) => tool?.input_schema && (tool.type === undefined || tool.type === 'custom')
+  );
+  if (tools.length > 0) {
+    chat.tools = tools.map((tool: any) => ({
+      type: 'function',
+      function: { name: tool.name, description: tool.description, parameters: tool.input_schema },
+    }));
+    const choice = toolChoice(body.tool_choice);
+    if (choice !== undefined) {
+      chat.tool_choice = choice;
+    }
+    if (body.tool_choice?.disable_parallel_tool_use === true) {
+      chat.parallel_tool_calls = false;
+    }
+  }
+  return chat;
+}
+
+// prompt_tokens includes cached tokens; Anthropic's input_tokens counts only the uncached part
+function messagesUsage(usage: any): any {
+  const cached = usage?.prompt_tokens_details?.cached_tokens ?? 0;
+  return {
+    input_tokens: Math.max(0, (usage?.prompt_tokens ?? 0) - cached),
+    output_tokens: usage?.completion_tokens ?? 0,
+    ...(cached > 0 ? { cache_read_input_tokens: cached } : {}),
+  };
+}
+
+function parseToolInput(args: unknown): any {
+  if (typeof args !== 'string' || !args) {
+    return {};
+  }
+  try {
+    return JSON.parse(args);
+  } catch {
+    return {};
+  }
+}
+
+/**
+ * A Chat Completions response body as a Messages one
+ */
+export function chatCompletionToMessage(body: any, requestedModel?: string): any {
+  const choice = Array.isArray(body?.choices) ? body.choices[0] : undefined;
+  const content: any[] = [];
+  if (typeof choice?.message?.content === 'string' && choice.message.content) {
+    content.push({ type: 'text', text: choice.message.content });
+  }
+  for (const call of choice?.message?.tool_calls ?? []) {
+    content.push({
+      type: 'tool_use',
+      id: call.id,
+      name: call.function?.name,
+      input: parseToolInput(call.function?.arguments),
+    });
+  }
+
+  return {
+    id: `msg_${body?.id ?? crypto.randomUUID()}`,
+    type: 'message',
+    role: 'assistant',
+    model: body?.model ?? requestedModel,
+    content,
+    stop_reason: FINISH_REASONS[choice?.finish_reason] ?? 'end_turn',
+    stop_sequence: null,
+    usage: messagesUsage(body?.usage),
+  };
+}
+
+/**
+ * An OpenAI error body as an Anthropic one; the type follows the status since OpenAI's types don't map
+ */
+export function chatErrorToMessagesError(text: string, status: number): any {
+  let message = text;
+  try {
+    const parsed = JSON.parse(text);
+    message = parsed?.error?.message ?? parsed?.message ?? text;
+  } catch {
+    // Plain text error pages are passed on as the message
+  }
+  const type = ERROR_TYPES[status] ?? (status >= 500 ? 'api_error' : 'invalid_request_error');
+  return { type: 'error', error: { type, message } };
+}
+
+function sseEvent(type: string, data: Record<string, unknown>): string {
+  return `event: ${type}\ndata: ${JSON.stringify({ type, ...data })}\n\n`;
+}
+
+/**
+ * Chat Completions SSE chunks in, Messages SSE events out. Terminal events are only produced once the
+ * upstream finished (finish_reason or [DONE]), so a cut stream still looks cut to the client.
+ */
+export function createChatStreamTranslator(requestedModel?: string): TransformStream<Uint8Array, Uint8Array> {
+  const decoder = new TextDecoder();
+  const encoder = new TextEncoder();
+  let buffer = '';
+  let started = false;
+  let finished = false;
+  let blockIndex = -1;
+  let openBlock: 'text' | 'tool_use' | null = null;
+  const toolBlocks = new Map<number, number>(); // Chat tool call index -> content block index
+  let stopReason: string | null = null;
+  let usage: any = null;
+
+  const translate = (data: any): string => {
+    let out = '';
+    if (data?.error) {
+      const message = data.error.message ?? 'Upstream stream error';
+      return sseEvent('error', { error: { type: 'api_error', message } });
+    }
+    if (!started) {
+      started = true;
+      out += sseEvent('message_start', {
+        message: {
+          id: `msg_${data?.id ?? crypto.randomUUID()}`,
+          type: 'message',
+          role: 'assistant',
+          model: data?.model ?? requestedModel,
+          content: [],
+          stop_reason: null,
+          stop_sequence: null,
+          usage: { input_tokens: 0, output_tokens: 0 },
+        },
+      });
+    }
+    if (data?.usage) {
+      usage = data.usage;
+    }
+
+    const closeBlock = () => {
+      if (openBlock) {
+        out += sseEvent('content_block_stop', { index: blockIndex });
+        openBlock = null;
+      }
+    };
+
+    const choice = Array.isArray(data?.choices) ? data.choices[0] : undefined;
+    const delta = choice?.delta ?? {};
+    if (typeof delta.content === 'string' && delta.content) {
+      if (openBlock !== 'text') {
+        closeBlock();
+        blockIndex++;
+        openBlock = 'text';
+        out += sseEvent('content_block_start', { index: blockIndex, content_block: { type: 'text', text: '' } });
+      }
+      out += sseEvent('content_block_delta', { index: blockIndex, delta: { type: 'text_delta', text: delta.content } });
+    }
+    for (const call of Array.isArray(delta.tool_calls) ? delta.tool_calls : []) {
+      const callIndex = typeof call.index === 'number' ? call.index : 0;
+      if (!toolBlocks.has(callIndex)) {
+        closeBlock();
+        blockIndex++;
+        openBlock = 'tool_use';
+        toolBlocks.set(callIndex, blockIndex);
+        out += sseEvent('content_block_start', {
+          index: blockIndex,
+          content_block: { type: 'tool_use', id: call.id, name: call.function?.name, input: {} },
+        });
+      }
+      const args = call.function?.arguments;
+      if (typeof args === 'string' && args) {
+        out += sseEvent('content_block_delta', {
+          index: toolBlocks.get(callIndex),
+          delta: { type: 'input_json_delta', partial_json: args },
+        });
+      }
+    }
+    if (choice?.finish_reason) {
+      closeBlock();
+      stopReason = FINISH_REASONS[choice.finish_reason] ?? 'end_turn';
+    }
+    return out;
+  };
+
+  // include_usage sends its usage chunk after the finish_reason one, so the ending waits for [DONE]
+  const finish = (): string => {
+    if (finished || !started || stopReason === null) {
+      return '';
+    }
+    finished = true;
+    return (
+      sseEvent('message_delta', {
+        delta: { stop_reason: stopReason, stop_sequence: null },
+        usage: messagesUsage(usage),
+      }) + sseEvent('message_stop', {})
+    );
+  };
+
+  const consume = (text: string): string => {
+    const { events, remainder } = splitSseEvents(text);
+    buffer = remainder;
+    let out = '';
+    for (const event of events) {
+      if (event.data === '[DONE]') {
+        out += finish();
+        continue;
+      }
+      const data = parseSseData(event);
+      if (data) {
+        out += translate(data);
+      }
+    }
+    return out;
+  };
+
+  return new TransformStream({
+    transform(chunk, controller) {
+      const out = consume(buffer + decoder.decode(chunk, { stream: true }));
+      if (out) {
+        controller.enqueue(encoder.encode(out));
+      }
+    },
+    flush(controller) {
+      // Some relays close after the last chunk without sending [DONE]
+      const out = consume(`${buffer}${decoder.decode()}\n\n`) + finish();
+      if (out) {
+        controller.enqueue(encoder.encode(out));
+      }
+    },
+  });
+}
+
+/**
+ * An upstream Chat Completions response as the Messages response the client asked for
+ */
+export async function translateChatResponse(response: Response, requestedModel?: string): Promise<Response> {
+  const headers = new Headers(response.headers);
+  headers.delete('content-length');
+  headers.delete('content-encoding');
+
+  if (response.ok && response.body && (response.headers.get('content-type') ?? '').includes('text/event-stream')) {
+    return new Response(response.body.pipeThrough(createChatStreamTranslator(requestedModel)), {
+      status: response.status,
+      statusText: response.statusText,
+      headers,
+    });
+  }
+
+  const text = await response.text();
+  let body: any;
+  if (response.ok) {
+    try {
+      body = chatCompletionToMessage(JSON.parse(text), requestedModel);
+    } catch {
+      // Not JSON; pass it through and let compliance checking flag it
+      return new Response(text, { status: response.status, statusText: response.statusText, headers });
+    }
+  } else {
+    body = chatErrorToMessagesError(text, response.status);
+  }
+  headers.set('content-type', 'application/json');
+  return new Response(JSON.stringify(body), { status: response.status, statusText: response.statusText, headers });
+}
@@ -0,0 +1,53 @@
+// Usage tap - keeps the usage-bearing SSE events of a stream whose body is no longer captured (memory budget),
+// so token counts still reach the log for the longest, most expensive responses
+
+import { splitSseEvents } from './streamSalvage';
+
+/**
+ * Watches every chunk and keeps slimmed copies of events that carry usage, model or upstream ids.
+ * Terminal events like response.completed embed the whole output, which is exactly what must not be kept.
+ */
+export class StreamUsageTap {
+  private remainder = '';
+  private kept: string[] = [];
+
+  push(chunk: string): void {
+    const { events, remainder } = splitSseEvents(this.remainder + chunk);
+    this.remainder = remainder;
+    for (const event of events) {
+      // Cheap pre-check; only a handful of events per stream contain the key
+      if (!event.data?.includes('"usage"')) {
+        continue;
+      }
+      try {
+        const data = JSON.parse(event.data);
+        this.kept.push(`data: ${JSON.stringify(slim(data))}\n\n`);
+      } catch {
+        // Not JSON; nothing to learn from it
+      }
+    }
+  }
+
+  /**
+   * The kept events as SSE text, in stream order, for the regular usage and upstream id parsers
+   */
+  events(): string {
+    return this.kept.join('');
+  }
+
+  reset(): void {
+    this.remainder = '';
+    this.kept = [];
+  }
+}
+
+function slim(data: any): any {
+  const pick = (source: any) =>
+    source && typeof source === 'object' ? { id: source.id, model: source.model, usage: source.usage } : undefined;
+  return {
+    type: data.type,
+    ...pick(data),
+    message: pick(data.message),
+    response: pick(data.response),
+  };
+}
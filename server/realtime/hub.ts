@@ -0,0 +1,250 @@
+// Realtime hub - pushes proxy events to connected dashboard WebSocket clients
+
+import type { ServerWebSocket } from 'bun';
+import type { RequestLog } from '../logging/database';
+import { normalizeRealtimeEvent, WIRE_VERSION, type RealtimeEvent } from '../protocol';
+
+export type { RealtimeEvent, RealtimeEventType } from '../protocol';
+
+export interface RealtimeClientData {
+  services: string[];
+  id: string;
+  ip?: string;
+  userAgent?: string;
+  connectedAt: number;
+  eventsSent: number;
+  bytesSent: number;
+  backpressured: number; // Sends queued behind a client that reads too slowly
+  dropped: number;       // Sends Bun dropped outright
+  lastSentAt?: number;
+  since?: number;        // Resume point from ?since=; replay starts there instead of at the window start
+}
+
+/**
+ * Socket data for a new client; pass it to server.upgrade
+ */
+export function createRealtimeClientData(
+  services: string[],
+  info: { ip?: string; userAgent?: string; since?: number } = {}
+): RealtimeClientData {
+  return {
+    services,
+    since: info.since,
+    id: crypto.randomUUID().slice(0, 8),
+    ip: info.ip,
+    userAgent: info.userAgent,
+    connectedAt: Date.now(),
+    eventsSent: 0,
+    bytesSent: 0,
+    backpressured: 0,
+    dropped: 0,
+  };
+}
+
+export type RealtimeSocket = ServerWebSocket<RealtimeClientData>;
+
+/**
+ * Storage used to bridge recent events across daemon restarts
+ */
+export interface RealtimeEventStore {
+  saveRealtimeEvent(service: string, type: string, timestamp: number, payload: string): void;
+  getRealtimeEventsSince(service: string, since: number): string[];
+  pruneRealtimeEvents(before: number): number;
+}
+
+export interface RealtimeHubOptions {
+  service: string;
+  store?: RealtimeEventStore;
+  replayMinutes?: number;
+}
+
+export class RealtimeHub {
+  private service: string;
+  private store?: RealtimeEventStore;
+  private replayWindowMs: number;
+  private clients: Set<RealtimeSocket> = new Set();
+
+  constructor(options: RealtimeHubOptions) {
+    this.service = options.service;
+    this.store = options.store;
+    this.replayWindowMs = Math.max(0, options.replayMinutes ?? 0) * 60 * 1000;
+  }
+
+  /**
+   * Register a client for live events; use attachRealtimeClient to include replay
+   */
+  addClient(ws: RealtimeSocket): void {
+    this.clients.add(ws);
+  }
+
+  removeClient(ws: RealtimeSocket): void {
+    this.clients.delete(ws);
+  }
+
+  getClientCount(): number {
+    return this.clients.size;
+  }
+
+  /**
+   * Broadcast an event to every client; completed requests are also persisted for replay
+   */
+  publish(event: RealtimeEvent): void {
+    const payload = JSON.stringify(event);
+
+    for (const client of this.clients) {
+      sendToClient(client, payload);
+    }
+
+    if (event.type === 'request_completed') {
+      this.persist(event, payload);
+    }
+  }
+
+  /**
+   * Publish a request_completed event for a persisted request log
+   */
+  publishRequestLog(log: RequestLog): void {
+    this.publish({
+      v: WIRE_VERSION,
+      type: 'request_completed',
+      service: this.service,
+      timestamp: log.timestamp + (log.duration ?? 0),
+      data: {
+        id: log.id,
+        method: log.method,
+        path: log.path,
+        channel: log.configName,
+        status_code: log.statusCode,
+        duration_ms: log.duration,
+        model: log.model || log.requestModel,
+        prompt_tokens: log.inputTokens ?? 0,
+        completion_tokens: log.outputTokens ?? 0,
+        error_message: log.error,
+      },
+    });
+  }
+
+  private persist(event: RealtimeEvent, payload: string): void {
+    if (!this.store || this.replayWindowMs <= 0) {
+      return;
+    }
+
+    try {
+      this.store.saveRealtimeEvent(this.service, event.type, event.timestamp, payload);
+      this.store.pruneRealtimeEvents(Date.now() - this.replayWindowMs);
+    } catch (error) {
+      console.error(`[realtime:${this.service}] Failed to persist event:`, error);
+    }
+  }
+
+  /**
+   * Load the persisted replay window, oldest first; `after` narrows it for a resuming client
+   */
+  getRecentEvents(after?: number): RealtimeEvent[] {
+    if (!this.store || this.replayWindowMs <= 0) {
+      return [];
+    }
+
+    try {
+      const since = Math.max(Date.now() - this.replayWindowMs, after ?? 0);
+      return this.store
+        .getRealtimeEventsSince(this.service, since)
+        .map(payload => normalizeRealtimeEvent(JSON.parse(payload)));
+    } catch (error) {
+      console.error(`[realtime:${this.service}] Failed to load persisted events:`, error);
+      return [];
+    }
+  }
+}
+
+/**
+ * Send one event and keep the client's delivery counters; Bun returns -1 when the message was
+ * queued behind backpressure and 0 when it was dropped
+ */
+function sendToClient(ws: RealtimeSocket, payload: string): void {
+  const result = ws.send(payload);
+  const stats = ws.data;
+  if (result === 0) {
+    stats.dropped++;
+    return;
+  }
+  if (result === -1) {
+    stats.backpressured++;
+  }
+  stats.eventsSent++;
+  stats.bytesSent += payload.length;
+  stats.lastSentAt = Date.now();
+}
+
+/**
+ * Subscribe a client to one or more hubs, replaying their persisted windows merged by time. A client
+ * resuming with `since` gets events from that timestamp on, inclusive; it dedupes the boundary itself
+ */
+export function attachRealtimeClient(ws: RealtimeSocket, hubs: RealtimeHub[]): void {
+  const history = hubs
+    .flatMap(hub => hub.getRecentEvents(ws.data.since))
+    .sort((a, b) => a.timestamp - b.timestamp);
+
+  for (const event of history) {
+    sendToClient(ws, JSON.stringify({ ...event, historical: true }));
+  }
+
+  for (const hub of hubs) {
+    hub.addClient(ws);
+  }
+}
+
+export function detachRealtimeClient(ws: RealtimeSocket, hubs: RealtimeHub[]): void {
+  for (const hub of hubs) {
+    hub.removeClient(ws);
+  }
+}
+
+export interface RealtimeClientInfo {
+  id: string;
+  services: string[];
+  ip?: string;
+  userAgent?: string;
+  connectedAt: number;
+  eventsSent: number;
+  bytesSent: number;
+  backpressured: number;
+  dropped: number;
+  lastSentAt?: number;
+  bufferedBytes: number; // Sent but not yet taken by the client; grows while it lags behind
+}
+
+/**
+ * Every connected dashboard client across hubs, for the realtime clients API
+ */
+export class RealtimeClientRegistry {
+  private clients = new Map<string, RealtimeSocket>();
+
+  add(ws: RealtimeSocket): void {
+    this.clients.set(ws.data.id, ws);
+  }
+
+  remove(ws: RealtimeSocket): void {
+    this.clients.delete(ws.data.id);
+  }
+
+  list(): RealtimeClientInfo[] {
+    return [...this.clients.values()]
+      .map(ws => ({ ...ws.data, bufferedBytes: ws.getBufferedAmount() }))
+      .sort((a, b) => a.connectedAt - b.connectedAt);
+  }
+
+  /**
+   * Close a client's socket; false when no such client is connected
+   */
+  disconnect(id: string, reason = 'Disconnected by operator'): boolean {
+    const ws = this.clients.get(id);
+    if (!ws) {
+      return false;
+    }
+    // An application close code, so clients can tell this apart from a restart and not reconnect right away
+    ws.close(4000, reason);
+    this.clients.delete(id);
+    return true;
+  }
+}
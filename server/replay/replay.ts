@@ -0,0 +1,260 @@
+// Workload replay - re-send AI API requests captured as HAR or JSON lines through one config, e.g. to
+// regression-test a new provider against last week's real traffic
+
+import type { ProxyConfig } from '../config/types';
+import { TAGS_HEADER } from '../proxy/tags';
+
+export interface ReplayRequest {
+  method: string;
+  path: string;                    // Path and query; the host of the capture is not used
+  headers: Record<string, string>; // Minus credentials and transport headers; the config supplies its own
+  body: string;
+}
+
+export type ReplayJobStatus = 'running' | 'completed' | 'cancelled';
+
+export interface ReplayJob {
+  id: string;
+  service: string;
+  config: string;
+  ratePerSecond: number;
+  status: ReplayJobStatus;
+  total: number;
+  sent: number;
+  succeeded: number;
+  failed: number;
+  skipped: number;                 // Entries in the file that were not replayable AI API requests
+  statuses: Record<string, number>; // HTTP status (or "error") -> count
+  startedAt: number;
+  finishedAt?: number;
+}
+
+// Endpoints worth replaying; model listing and other GETs say nothing about a provider's behaviour
+const AI_API_PATH = /\/v1\/(messages(\/count_tokens)?|chat\/completions|completions|responses|embeddings)$/;
+
+const DROPPED_HEADERS = new Set([
+  'authorization',
+  'proxy-authorization',
+  'x-api-key',
+  'api-key',
+  'cookie',
+  'host',
+  'content-length',
+  'connection',
+  'accept-encoding',
+  'transfer-encoding',
+  TAGS_HEADER,
+]);
+
+// Finished jobs kept for GET /api/replay
+const MAX_FINISHED_JOBS = 20;
+
+function toReplayRequest(method: unknown, url: unknown, headers: Array<[string, unknown]>, body: unknown): ReplayRequest | null {
+  if (typeof url !== 'string' || typeof body !== 'string' || !body) {
+    return null;
+  }
+  let path: string;
+  try {
+    const parsed = new URL(url, 'http://replay.local');
+    path = `${parsed.pathname}${parsed.search}`;
+    if (!AI_API_PATH.test(parsed.pathname)) {
+      return null;
+    }
+    JSON.parse(body);
+  } catch {
+    return null;
+  }
+  return {
+    method: typeof method === 'string' ? method.toUpperCase() : 'POST',
+    path,
+    headers: Object.fromEntries(
+      headers
+        .filter(([name, value]) => typeof value === 'string' && !DROPPED_HEADERS.has(name.toLowerCase()))
+        .map(([name, value]) => [name.toLowerCase(), value as string])
+    ),
+    body,
+  };
+}
+
+/**
+ * Read a HAR file or JSON lines (paf's own session export, or any lines with method/url/headers/body).
+ * Entries without a complete JSON body for a known AI endpoint are counted as skipped.
+ */
+export function parseReplayFile(text: string): { requests: ReplayRequest[]; skipped: number } | { error: string } {
+  const trimmed = text.trim();
+  if (!trimmed) {
+    return { error: 'File is empty' };
+  }
+
+  const requests: ReplayRequest[] = [];
+  let skipped = 0;
+  const add = (request: ReplayRequest | null) => {
+    if (request) {
+      requests.push(request);
+    } else {
+      skipped++;
+    }
+  };
+
+  let har: any = null;
+  try {
+    har = trimmed.startsWith('{') && !trimmed.includes('\n{') ? JSON.parse(trimmed) : null;
+  } catch {
+    har = null;
+  }
+
+  if (Array.isArray(har?.log?.entries)) {
+    for (const entry of har.log.entries) {
+      const request = entry?.request;
+      const headers: Array<[string, unknown]> = Array.isArray(request?.headers)
+        ? request.headers.map((header: any) => [String(header?.name ?? ''), header?.value])
+        : [];
+      add(toReplayRequest(request?.method, request?.url, headers, request?.postData?.text));
+    }
+  } else {
+    for (const [index, line] of trimmed.split('\n').entries()) {
+      if (!line.trim()) {
+        continue;
+      }
+      let record: any;
+      try {
+        record = JSON.parse(line);
+      } catch {
+        return { error: `Line ${index + 1} is not JSON; expected a HAR file or JSON lines` };
+      }
+      if (record?.body_truncated === true) {
+        skipped++;
+        continue;
+      }
+      const body = typeof record?.body === 'string' ? record.body : record?.body ? JSON.stringify(record.body) : undefined;
+      const headers = record?.headers && typeof record.headers === 'object' ? Object.entries(record.headers) : [];
+      add(toReplayRequest(record?.method, record?.url ?? record?.path, headers, body));
+    }
+  }
+
+  if (requests.length === 0) {
+    return { error: `No replayable requests found (${skipped} skipped: not an AI API call or body missing/truncated)` };
+  }
+  return { requests, skipped };
+}
+
+type ReplayHandler = (request: Request, servers: ProxyConfig[]) => Promise<Response>;
+
+/**
+ * Replay jobs of this process; each sends its requests at a fixed rate without waiting for earlier ones
+ */
+export class ReplayRunner {
+  private jobs = new Map<string, ReplayJob>();
+
+  list(): ReplayJob[] {
+    return [...this.jobs.values()].sort((a, b) => b.startedAt - a.startedAt);
+  }
+
+  get(id: string): ReplayJob | undefined {
+    return this.jobs.get(id);
+  }
+
+  cancel(id: string): ReplayJob | undefined {
+    const job = this.jobs.get(id);
+    if (job?.status === 'running') {
+      job.status = 'cancelled';
+      job.finishedAt = Date.now();
+    }
+    return job;
+  }
+
+  /**
+   * Start replaying through `config` only. Every request carries the tag replay=<job id>, so its
+   * results can be told apart in logs and compared with GET /api/stats/tags.
+   */
+  start(
+    service: string,
+    config: ProxyConfig,
+    requests: ReplayRequest[],
+    skipped: number,
+    ratePerSecond: number,
+    handle: ReplayHandler
+  ): ReplayJob {
+    const job: ReplayJob = {
+      id: `replay-${crypto.randomUUID().slice(0, 8)}`,
+      service,
+      config: config.name,
+      ratePerSecond,
+      status: 'running',
+      total: requests.length,
+      sent: 0,
+      succeeded: 0,
+      failed: 0,
+      skipped,
+      statuses: {},
+      startedAt: Date.now(),
+    };
+    this.jobs.set(job.id, job);
+    this.prune();
+
+    void this.run(job, config, requests, handle);
+    return job;
+  }
+
+  private async run(job: ReplayJob, config: ProxyConfig, requests: ReplayRequest[], handle: ReplayHandler): Promise<void> {
+    const interval = 1000 / job.ratePerSecond;
+    const inFlight: Promise<void>[] = [];
+
+    for (const [index, item] of requests.entries()) {
+      if (job.status !== 'running') {
+        break;
+      }
+      if (index > 0) {
+        await Bun.sleep(interval);
+      }
+      if (job.status !== 'running') {
+        break;
+      }
+
+      job.sent++;
+      inFlight.push(
+        (async () => {
+          let status = 'error';
+          try {
+            const response = await handle(
+              new Request(`http://paf.replay${item.path}`, {
+                method: item.method,
+                headers: { ...item.headers, [TAGS_HEADER]: `replay=${job.id}` },
+                body: item.body,
+              }),
+              [config]
+            );
+            // Drain streams so the request finishes and is logged like a real one
+            await response.arrayBuffer().catch(() => undefined);
+            status = String(response.status);
+            if (response.ok) {
+              job.succeeded++;
+            } else {
+              job.failed++;
+            }
+          } catch (error) {
+            console.warn(`[replay] ${job.id} request ${index + 1} failed:`, error);
+            job.failed++;
+          }
+          job.statuses[status] = (job.statuses[status] ?? 0) + 1;
+        })()
+      );
+    }
+
+    await Promise.all(inFlight);
+    if (job.status === 'running') {
+      job.status = 'completed';
+      job.finishedAt = Date.now();
+    }
+    console.log(
+      `[replay] ${job.id} ${job.status}: ${job.succeeded} ok, ${job.failed} failed of ${job.sent} sent via ${job.config}`
+    );
+  }
+
+  private prune(): void {
+    const finished = this.list().filter(job => job.status !== 'running');
+    for (const job of finished.slice(MAX_FINISHED_JOBS)) {
+      this.jobs.delete(job.id);
+    }
+  }
+}
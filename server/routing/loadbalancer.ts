@@ -1,6 +1,18 @@
 // Load balancer - selects upstream servers based on configured strategy
 
 import type { ProxyConfig, LoadBalancerConfig } from '../config/types';
+import { DEFAULT_RETRY_BUDGET } from '../config/defaults';
+import { isWithinSchedule } from './schedule';
+import {
+  BUILTIN_SELECTION_STRATEGIES,
+  createSelectionStrategy,
+  type SelectionRequest,
+  type SelectionResult,
+  type SelectionStrategy,
+} from './strategies';
+
+// Built-in strategies; libraries may register more (see registerSelectionStrategy)
+export const LOAD_BALANCER_STRATEGIES: ReadonlyArray<string> = BUILTIN_SELECTION_STRATEGIES;
 
 interface ServerHealth {
   isHealthy: boolean;
@@ -9,12 +21,49 @@ interface ServerHealth {
   lastChecked: number;
 }
 
+interface RetryWindow {
+  requests: number[]; // timestamps of first attempts
+  retries: number[];  // timestamps of granted retries
+  rejected: number;   // retries denied since startup
+}
+
+/**
+ * Per-server state exchanged with cluster peers; retry counts are the sender's own traffic only
+ */
+export interface SharedServerState {
+  isHealthy: boolean;
+  consecutiveFailures: number;
+  consecutiveSuccesses: number;
+  lastChecked: number;
+  requests: number;
+  retries: number;
+}
+
+/**
+ * Operator override that sends all traffic to one config until `until` (epoch ms)
+ */
+export interface ServerPin {
+  configName: string;
+  pinnedAt: number;
+  until: number;
+  reason?: string;
+}
+
+interface PeerRetryUsage {
+  requests: number;
+  retries: number;
+  receivedAt: number;
+}
+
 export class LoadBalancer {
   private healthStatus: Map<string, ServerHealth> = new Map();
-  private roundRobinIndex = 0;
   private config: LoadBalancerConfig;
   private currentServerName: string | null = null;
-  private weightRotation: Map<string, number> = new Map();
+  private strategies: Map<string, SelectionStrategy> = new Map(); // Instantiated on first use, reset with the config
+  private latencySource?: (server: ProxyConfig) => number | undefined;
+  private retryWindows: Map<string, RetryWindow> = new Map();
+  private peerRetryUsage: Map<string, Map<string, PeerRetryUsage>> = new Map(); // peer -> server -> usage
+  private pin: ServerPin | null = null;
 
   constructor(config: LoadBalancerConfig) {
     this.config = config;
@@ -23,78 +72,139 @@ export class LoadBalancer {
   /**
    * Select an upstream server based on the configured strategy
    */
-  selectServer(servers: ProxyConfig[]): ProxyConfig | null {
+  selectServer(servers: ProxyConfig[], request: SelectionRequest = {}): ProxyConfig | null {
+    return this.select(servers, request)?.config ?? null;
+  }
+
+  /**
+   * Apply exclusions (pin, disabled, schedule, freezes), then let the strategy choose and say why
+   */
+  select(servers: ProxyConfig[], request: SelectionRequest = {}): SelectionResult | null {
     if (servers.length === 0) {
       return null;
     }
 
+    const pinned = this.getPinnedServer(servers);
+    if (pinned) {
+      return { config: pinned, reason: 'pinned' };
+    }
+
     const now = Date.now();
     const enabledServers = servers.filter(server => server.enabled !== false);
-    const basePool = enabledServers.length > 0 ? enabledServers : servers;
+    // Outside its availability windows a config is never used, not even as a last resort
+    const basePool = (enabledServers.length > 0 ? enabledServers : servers).filter(server =>
+      isWithinSchedule(server.availability, now)
+    );
+    if (basePool.length === 0) {
+      return null;
+    }
 
     const availableServers = basePool.filter(server => !this.isServerFrozen(server, now));
     const selectableServers = availableServers.length > 0 ? availableServers : basePool;
 
-    if (this.config.strategy !== 'weighted') {
-      const server = this.selectRoundRobin(selectableServers);
-      this.currentServerName = server?.name ?? null;
-      return server;
-    }
-
     if (this.currentServerName && !servers.some(s => s.name === this.currentServerName)) {
       this.currentServerName = null;
     }
 
-    if (this.currentServerName) {
-      const current = selectableServers.find(s => s.name === this.currentServerName);
-      if (current && !this.hasExceededFailureThreshold(current.name)) {
-        return current;
-      }
-      this.currentServerName = null;
+    const result = this.getStrategy().select({
+      candidates: selectableServers.map(server => {
+        const health = this.getOrCreateHealth(server.name);
+        return {
+          config: server,
+          healthy: health.isHealthy,
+          consecutiveFailures: health.consecutiveFailures,
+          overFailureThreshold: this.hasExceededFailureThreshold(server.name),
+          latencyMs: this.latencySource?.(server),
+        };
+      }),
+      current: this.currentServerName,
+      request,
+    });
+    if (!result || !selectableServers.includes(result.config)) {
+      return null;
     }
 
-    const next = this.selectByDescendingWeight(selectableServers);
-    if (next) {
-      this.currentServerName = next.name;
-      return next;
-    }
+    this.currentServerName = this.hasExceededFailureThreshold(result.config.name) ? null : result.config.name;
+    return result;
+  }
 
-    const fallback = this.selectFallback(selectableServers);
-    if (fallback && !this.hasExceededFailureThreshold(fallback.name)) {
-      this.currentServerName = fallback.name;
-    } else {
-      this.currentServerName = null;
+  /**
+   * Where candidates' latencyMs comes from; the proxy wires this to its connection stats
+   */
+  setLatencySource(source: (server: ProxyConfig) => number | undefined): void {
+    this.latencySource = source;
+  }
+
+  private getStrategy(): SelectionStrategy {
+    const name = this.config.strategy;
+    let strategy = this.strategies.get(name);
+    if (!strategy) {
+      strategy = createSelectionStrategy(name) ?? undefined;
+      if (!strategy) {
+        // A library strategy that was never registered in this process
+        console.warn(`[loadbalancer] unknown strategy "${name}"; using weighted`);
+        strategy = createSelectionStrategy('weighted')!;
+      }
+      this.strategies.set(name, strategy);
     }
-    return fallback;
+    return strategy;
   }
 
   /**
-   * Weighted random selection based on server weights
+   * True when no enabled server is usable: each is frozen or past its failure threshold
    */
-  private selectWeighted(servers: ProxyConfig[]): ProxyConfig {
-    const totalWeight = servers.reduce((sum, s) => sum + s.weight, 0);
-    if (totalWeight <= 0) {
-      return servers[0];
+  allServersDown(servers: ProxyConfig[]): boolean {
+    if (this.getPinnedServer(servers)) {
+      return false;
     }
-    let random = Math.random() * totalWeight;
+    return servers.some(server => server.enabled !== false) && this.usableServers(servers).length === 0;
+  }
 
-    for (const server of servers) {
-      random -= server.weight;
-      if (random <= 0) {
-        return server;
-      }
-    }
+  /**
+   * Enabled servers that are neither frozen, past their failure threshold nor outside their schedule
+   */
+  usableServers(servers: ProxyConfig[]): ProxyConfig[] {
+    const now = Date.now();
+    return servers.filter(
+      server =>
+        server.enabled !== false &&
+        !this.isServerFrozen(server, now) &&
+        !this.hasExceededFailureThreshold(server.name) &&
+        isWithinSchedule(server.availability, now)
+    );
+  }
+
+  /**
+   * Route everything to one config for `ttlMs`, ignoring weights, freezes and failure thresholds
+   */
+  pinServer(configName: string, ttlMs: number, reason?: string): ServerPin {
+    const now = Date.now();
+    this.pin = { configName, pinnedAt: now, until: now + ttlMs, reason };
+    return this.pin;
+  }
 
-    return servers[0];
+  unpinServer(): ServerPin | null {
+    const previous = this.getPin();
+    this.pin = null;
+    return previous;
   }
 
   /**
-   * Round-robin selection
+   * The active pin, or null once it has expired
    */
-  private selectRoundRobin(servers: ProxyConfig[]): ProxyConfig {
-    const server = servers[this.roundRobinIndex % servers.length];
-    this.roundRobinIndex = (this.roundRobinIndex + 1) % servers.length;
-    return server;
+  getPin(): ServerPin | null {
+    if (this.pin && this.pin.until <= Date.now()) {
+      this.pin = null;
+    }
+    return this.pin;
+  }
+
+  /**
+   * The pinned config when a pin is active and the config is among `servers`
+   */
+  getPinnedServer(servers: ProxyConfig[]): ProxyConfig | null {
+    const pin = this.getPin();
+    return pin ? servers.find(server => server.name === pin.configName) ?? null : null;
   }
 
   /**
@@ -273,87 +383,158 @@ export class LoadBalancer {
    */
   updateConfig(config: LoadBalancerConfig): void {
     this.config = config;
-    this.weightRotation.clear();
+    this.strategies.clear();
     if (this.currentServerName && this.hasExceededFailureThreshold(this.currentServerName)) {
       this.currentServerName = null;
     }
   }
 
-  private isServerFrozen(server: ProxyConfig, now: number): boolean {
-    return typeof server.freezeUntil === 'number' && server.freezeUntil > now;
+  /**
+   * Count a first attempt against a server for retry budget accounting
+   */
+  recordRequest(serverName: string): void {
+    const window = this.getRetryWindow(serverName, Date.now());
+    window.requests.push(Date.now());
   }
 
-  private selectFallback(servers: ProxyConfig[]): ProxyConfig | null {
-    if (servers.length === 0) {
-      return null;
+  /**
+   * Reserve a retry for a request that failed on this server; false once retries would
+   * exceed the configured share of recent volume, so outages are not amplified
+   */
+  tryConsumeRetry(serverName: string): boolean {
+    const now = Date.now();
+    const window = this.getRetryWindow(serverName, now);
+    const usage = this.getRetryUsage(serverName, now);
+
+    if (usage.retries >= usage.allowed) {
+      window.rejected++;
+      return false;
     }
 
-    switch (this.config.strategy) {
-      case 'round-robin':
-        return this.selectRoundRobin(servers);
-      case 'weighted':
-      default:
-        return this.selectWeighted(servers);
+    window.retries.push(now);
+    return true;
+  }
+
+  /**
+   * Current retry budget consumption per server
+   */
+  getRetryBudgetStats(): Record<string, {
+    requests: number;
+    retries: number;
+    allowed: number;
+    rejected: number;
+    exhausted: boolean;
+  }> {
+    const now = Date.now();
+    const stats: ReturnType<LoadBalancer['getRetryBudgetStats']> = {};
+
+    for (const serverName of this.retryWindows.keys()) {
+      const window = this.getRetryWindow(serverName, now);
+      const usage = this.getRetryUsage(serverName, now);
+      stats[serverName] = {
+        requests: usage.requests,
+        retries: usage.retries,
+        allowed: usage.allowed,
+        rejected: window.rejected,
+        exhausted: usage.retries >= usage.allowed,
+      };
     }
+
+    return stats;
   }
 
-  private selectByDescendingWeight(servers: ProxyConfig[]): ProxyConfig | null {
-    if (servers.length === 0) {
-      return null;
+  /**
+   * Local health and retry usage per server, published to cluster peers
+   */
+  exportSharedState(): Record<string, SharedServerState> {
+    const now = Date.now();
+    const names = new Set([...this.healthStatus.keys(), ...this.retryWindows.keys()]);
+    const state: Record<string, SharedServerState> = {};
+
+    for (const serverName of names) {
+      const health = this.getOrCreateHealth(serverName);
+      const window = this.getRetryWindow(serverName, now);
+      state[serverName] = {
+        isHealthy: health.isHealthy,
+        consecutiveFailures: health.consecutiveFailures,
+        consecutiveSuccesses: health.consecutiveSuccesses,
+        lastChecked: health.lastChecked,
+        requests: window.requests.length,
+        retries: window.retries.length,
+      };
     }
 
-    const groups = this.groupServersByWeight(servers);
-    for (const group of groups) {
-      const candidate = this.selectFromWeightGroup(group.weight, group.servers);
-      if (candidate) {
-        return candidate;
+    return state;
+  }
+
+  /**
+   * Merge a peer's state: the most recent health observation wins, retry usage is added to the local budget
+   */
+  mergePeerState(peerId: string, state: Record<string, SharedServerState>): void {
+    const now = Date.now();
+    const usage = new Map<string, PeerRetryUsage>();
+
+    for (const [serverName, remote] of Object.entries(state)) {
+      const local = this.getOrCreateHealth(serverName);
+      if (remote.lastChecked > local.lastChecked) {
+        local.isHealthy = remote.isHealthy;
+        local.consecutiveFailures = remote.consecutiveFailures;
+        local.consecutiveSuccesses = remote.consecutiveSuccesses;
+        local.lastChecked = remote.lastChecked;
+        if (this.currentServerName === serverName && this.hasExceededFailureThreshold(serverName)) {
+          this.currentServerName = null;
+        }
       }
+
+      usage.set(serverName, { requests: remote.requests, retries: remote.retries, receivedAt: now });
     }
 
-    return null;
+    this.peerRetryUsage.set(peerId, usage);
   }
 
-  private groupServersByWeight(servers: ProxyConfig[]): Array<{ weight: number; servers: ProxyConfig[] }> {
-    const grouped = new Map<number, ProxyConfig[]>();
-
-    for (const server of servers) {
-      const list = grouped.get(server.weight);
-      if (list) {
-        list.push(server);
-      } else {
-        grouped.set(server.weight, [server]);
+  /**
+   * Requests and retries in the window across this instance and fresh peer reports
+   */
+  private getRetryUsage(serverName: string, now: number): { requests: number; retries: number; allowed: number } {
+    const budget = this.config.retryBudget ?? DEFAULT_RETRY_BUDGET;
+    const window = this.getRetryWindow(serverName, now);
+    let requests = window.requests.length;
+    let retries = window.retries.length;
+
+    for (const peer of this.peerRetryUsage.values()) {
+      const reported = peer.get(serverName);
+      if (reported && now - reported.receivedAt <= budget.windowMs) {
+        requests += reported.requests;
+        retries += reported.retries;
       }
     }
 
-    return Array.from(grouped.entries())
-      .sort((a, b) => b[0] - a[0])
-      .map(([weight, list]) => ({
-        weight,
-        servers: list,
-      }));
+    return {
+      requests,
+      retries,
+      allowed: Math.max(budget.minRetries, Math.floor(requests * budget.ratio)),
+    };
   }
 
-  private selectFromWeightGroup(weight: number, servers: ProxyConfig[]): ProxyConfig | null {
-    const eligible = servers
-      .filter(server => !this.hasExceededFailureThreshold(server.name))
-      .sort((a, b) => a.name.localeCompare(b.name));
-
-    if (eligible.length === 0) {
-      return null;
+  private getRetryWindow(serverName: string, now: number): RetryWindow {
+    let window = this.retryWindows.get(serverName);
+    if (!window) {
+      window = { requests: [], retries: [], rejected: 0 };
+      this.retryWindows.set(serverName, window);
     }
 
-    const key = this.weightKey(weight);
-    let pointer = this.weightRotation.get(key) ?? 0;
-    if (pointer >= eligible.length) {
-      pointer = 0;
+    const cutoff = now - (this.config.retryBudget ?? DEFAULT_RETRY_BUDGET).windowMs;
+    while (window.requests.length > 0 && window.requests[0] < cutoff) {
+      window.requests.shift();
+    }
+    while (window.retries.length > 0 && window.retries[0] < cutoff) {
+      window.retries.shift();
     }
 
-    const server = eligible[pointer];
-    this.weightRotation.set(key, (pointer + 1) % eligible.length);
-    return server;
+    return window;
   }
 
-  private weightKey(weight: number): string {
-    return Number.isInteger(weight) ? weight.toString() : weight.toString();
+  private isServerFrozen(server: ProxyConfig, now: number): boolean {
+    return typeof server.freezeUntil === 'number' && server.freezeUntil > now;
   }
 }
@@ -0,0 +1,97 @@
+// Config pools - route each model family to its own group of configs, load balanced and health-tracked per pool
+
+import type { ConfigPool, ProxyConfig } from '../config/types';
+
+/**
+ * Parse `[[pools]]` entries: `name`, `models` (patterns, `*` matches anything) and `configs`,
+ * a table of config name -> weight within the pool, e.g. `configs = { relay-a = 3, relay-b = 1 }`.
+ * A plain list of names gives every member weight 1.
+ */
+export function parseConfigPools(data: any, knownConfigs: string[], where: string): ConfigPool[] | undefined {
+  if (!Array.isArray(data)) {
+    return undefined;
+  }
+
+  const pools: ConfigPool[] = [];
+  for (const entry of data) {
+    const name = typeof entry?.name === 'string' ? entry.name.trim() : '';
+    const models = Array.isArray(entry?.models)
+      ? entry.models.filter((model: unknown): model is string => typeof model === 'string' && model.trim() !== '')
+      : [];
+    if (!name || models.length === 0) {
+      console.warn(`[config] ${where}: ignoring pool without a name or models`);
+      continue;
+    }
+    if (pools.some(pool => pool.name === name)) {
+      console.warn(`[config] ${where}: ignoring duplicate pool "${name}"`);
+      continue;
+    }
+
+    const rawMembers: Array<[string, unknown]> = Array.isArray(entry.configs)
+      ? entry.configs.map((config: unknown) => [String(config), 1])
+      : entry.configs && typeof entry.configs === 'object'
+        ? Object.entries(entry.configs)
+        : [];
+    const members: ConfigPool['members'] = [];
+    for (const [config, weight] of rawMembers) {
+      if (!knownConfigs.includes(config)) {
+        console.warn(`[config] ${where}: pool "${name}" names unknown config "${config}"`);
+        continue;
+      }
+      members.push({ config, weight: typeof weight === 'number' && weight > 0 ? weight : 1 });
+    }
+    if (members.length === 0) {
+      console.warn(`[config] ${where}: ignoring pool "${name}" without configs`);
+      continue;
+    }
+
+    pools.push({ name, models: models.map((model: string) => model.trim()), members });
+  }
+
+  return pools.length > 0 ? pools : undefined;
+}
+
+/**
+ * TOML shape of the pools, the inverse of parseConfigPools; members whose config is gone are dropped
+ */
+export function serializeConfigPools(
+  pools: ConfigPool[] | undefined,
+  knownConfigs: string[]
+): Array<Record<string, unknown>> | undefined {
+  const kept = (pools ?? [])
+    .map(pool => ({ ...pool, members: pool.members.filter(member => knownConfigs.includes(member.config)) }))
+    .filter(pool => pool.members.length > 0);
+  if (kept.length === 0) {
+    return undefined;
+  }
+  return kept.map(pool => ({
+    name: pool.name,
+    models: pool.models,
+    configs: Object.fromEntries(pool.members.map(member => [member.config, member.weight])),
+  }));
+}
+
+function modelPattern(pattern: string): RegExp {
+  const escaped = pattern.replace(/[.+?^${}()|[\]\\]/g, '\\$&').replace(/\*/g, '.*');
+  return new RegExp(`^${escaped}$`, 'i');
+}
+
+/**
+ * The first pool with a pattern matching `model`; requests without a model never match
+ */
+export function findConfigPool(pools: ConfigPool[] | undefined, model: unknown): ConfigPool | null {
+  if (!pools?.length || typeof model !== 'string' || !model) {
+    return null;
+  }
+  return pools.find(pool => pool.models.some(pattern => modelPattern(pattern).test(model))) ?? null;
+}
+
+/**
+ * The pool's members among `servers`, carrying the pool's weights in place of their own
+ */
+export function poolServers(pool: ConfigPool, servers: ProxyConfig[]): ProxyConfig[] {
+  return pool.members.flatMap(member => {
+    const server = servers.find(candidate => candidate.name === member.config);
+    return server ? [{ ...server, weight: member.weight }] : [];
+  });
+}
@@ -0,0 +1,138 @@
+// Rate limiter - token buckets for a config's requests_per_minute and tokens_per_minute, so paf spreads
+// load before an upstream key gets hard-throttled
+
+import type { ProxyConfig } from '../config/types';
+
+// How long a request may wait for a limited config when every candidate is at its limit
+export const RATE_LIMIT_MAX_WAIT_MS = 2000;
+
+const MAX_PENDING = 10_000;
+
+interface Bucket {
+  level: number;      // May go negative: token usage is only known after the response
+  updatedAt: number;
+}
+
+interface Buckets {
+  requests: Bucket;
+  tokens: Bucket;
+}
+
+export interface RateLimitWait {
+  config: ProxyConfig;
+  waitMs: number;
+}
+
+// Both limits refill continuously at limit/minute, with a burst of one minute's worth
+function refill(bucket: Bucket, perMinute: number, now: number): void {
+  bucket.level = Math.min(perMinute, bucket.level + ((now - bucket.updatedAt) * perMinute) / 60_000);
+  bucket.updatedAt = now;
+}
+
+function msUntil(bucket: Bucket, perMinute: number, needed: number): number {
+  return bucket.level >= needed ? 0 : Math.ceil(((needed - bucket.level) * 60_000) / perMinute);
+}
+
+/**
+ * A positive per-minute limit from TOML or the API; anything else means no limit
+ */
+export function parsePerMinuteLimit(value: unknown): number | undefined {
+  return typeof value === 'number' && Number.isFinite(value) && value > 0 ? Math.floor(value) : undefined;
+}
+
+export function isRateLimited(config: ProxyConfig): boolean {
+  return Boolean(config.requestsPerMinute || config.tokensPerMinute);
+}
+
+export class ConfigRateLimiter {
+  private buckets = new Map<string, Buckets>();
+  private pending = new Map<string, { configName: string; tokens: number }>(); // Request id -> estimate charged
+
+  /**
+   * Milliseconds until `config` can take a request of `tokens` estimated input tokens; 0 when it can now.
+   * The token limit only needs a positive balance, so a request larger than the limit still gets through.
+   */
+  waitMs(config: ProxyConfig, tokens: number, now = Date.now()): number {
+    if (!isRateLimited(config)) {
+      return 0;
+    }
+    const buckets = this.bucketsFor(config, now);
+    return Math.max(
+      config.requestsPerMinute ? msUntil(buckets.requests, config.requestsPerMinute, 1) : 0,
+      config.tokensPerMinute ? msUntil(buckets.tokens, config.tokensPerMinute, Math.min(tokens, 1)) : 0
+    );
+  }
+
+  available(configs: ProxyConfig[], tokens: number, now = Date.now()): ProxyConfig[] {
+    return configs.filter(config => this.waitMs(config, tokens, now) === 0);
+  }
+
+  /**
+   * The limited config that frees up first; null when `configs` is empty
+   */
+  soonest(configs: ProxyConfig[], tokens: number, now = Date.now()): RateLimitWait | null {
+    let best: RateLimitWait | null = null;
+    for (const config of configs) {
+      const waitMs = this.waitMs(config, tokens, now);
+      if (!best || waitMs < best.waitMs) {
+        best = { config, waitMs };
+      }
+    }
+    return best;
+  }
+
+  /**
+   * Charge one request and its estimated input tokens to `config`; with a `requestId`, settle() corrects
+   * the estimate once the request is logged
+   */
+  take(config: ProxyConfig, tokens: number, requestId?: string, now = Date.now()): void {
+    if (!isRateLimited(config)) {
+      return;
+    }
+    const buckets = this.bucketsFor(config, now);
+    buckets.requests.level -= 1;
+    buckets.tokens.level -= tokens;
+    if (!requestId) {
+      return;
+    }
+    // Requests that never get logged would otherwise stay here forever
+    if (this.pending.size >= MAX_PENDING) {
+      this.pending.delete(this.pending.keys().next().value!);
+    }
+    this.pending.set(requestId, { configName: config.name, tokens });
+  }
+
+  /**
+   * Replace a request's estimate with the tokens the upstream reported (input and output)
+   */
+  settle(requestId: string, actualTokens: number | undefined): void {
+    const charged = this.pending.get(requestId);
+    if (!charged) {
+      return;
+    }
+    this.pending.delete(requestId);
+    const buckets = this.buckets.get(charged.configName);
+    if (buckets && actualTokens !== undefined) {
+      buckets.tokens.level -= actualTokens - charged.tokens;
+    }
+  }
+
+  private bucketsFor(config: ProxyConfig, now: number): Buckets {
+    let buckets = this.buckets.get(config.name);
+    if (!buckets) {
+      // Start full, like a key that has not been used for a minute
+      buckets = {
+        requests: { level: config.requestsPerMinute ?? 0, updatedAt: now },
+        tokens: { level: config.tokensPerMinute ?? 0, updatedAt: now },
+      };
+      this.buckets.set(config.name, buckets);
+    }
+    if (config.requestsPerMinute) {
+      refill(buckets.requests, config.requestsPerMinute, now);
+    }
+    if (config.tokensPerMinute) {
+      refill(buckets.tokens, config.tokensPerMinute, now);
+    }
+    return buckets;
+  }
+}
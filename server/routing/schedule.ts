@@ -0,0 +1,137 @@
+// Availability schedules - configs that may only be used within local time windows, e.g. a work account during office hours
+
+import type { AvailabilitySchedule, AvailabilityWindow } from '../config/types';
+
+const DAY_NAMES = ['sun', 'mon', 'tue', 'wed', 'thu', 'fri', 'sat'];
+const ALL_DAYS = [0, 1, 2, 3, 4, 5, 6];
+
+function parseTime(value: unknown): number | null {
+  const match = typeof value === 'string' ? value.trim().match(/^(\d{1,2}):(\d{2})$/) : null;
+  if (!match) {
+    return null;
+  }
+  const minutes = Number(match[1]) * 60 + Number(match[2]);
+  return Number(match[2]) < 60 && minutes <= 24 * 60 ? minutes : null;
+}
+
+/**
+ * Day names (`mon`), ranges (`mon-fri`, wrapping like `fri-mon`) or 0-6 with 0 = Sunday
+ */
+function parseDays(value: unknown): number[] | null {
+  if (value === undefined) {
+    return ALL_DAYS;
+  }
+  if (!Array.isArray(value) || value.length === 0) {
+    return null;
+  }
+
+  const days = new Set<number>();
+  for (const entry of value) {
+    if (typeof entry === 'number' && Number.isInteger(entry) && entry >= 0 && entry <= 6) {
+      days.add(entry);
+      continue;
+    }
+    const [from, to] = String(entry).toLowerCase().split('-').map(part => DAY_NAMES.indexOf(part.trim().slice(0, 3)));
+    if (from === -1 || to === -1) {
+      return null;
+    }
+    for (let day = from; ; day = (day + 1) % 7) {
+      days.add(day);
+      if (to === undefined || day === to) {
+        break;
+      }
+    }
+  }
+  return [...days].sort((a, b) => a - b);
+}
+
+function isTimeZone(value: string): boolean {
+  try {
+    new Intl.DateTimeFormat('en-US', { timeZone: value });
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Parse `{ timezone?, windows = [{ days?, start = "09:00", end = "18:00" }] }`. A window whose end is
+ * before its start runs past midnight and belongs to the day it starts on. Days default to every day.
+ */
+export function parseAvailability(data: any): AvailabilitySchedule | { error: string } {
+  if (!data || typeof data !== 'object' || !Array.isArray(data.windows) || data.windows.length === 0) {
+    return { error: 'availability needs a non-empty windows list' };
+  }
+  const timezone = typeof data.timezone === 'string' && data.timezone.trim() ? data.timezone.trim() : undefined;
+  if (timezone && !isTimeZone(timezone)) {
+    return { error: `Unknown timezone "${timezone}"` };
+  }
+
+  const windows: AvailabilityWindow[] = [];
+  for (const [index, window] of data.windows.entries()) {
+    const start = parseTime(window?.start);
+    const end = parseTime(window?.end);
+    if (start === null || end === null || start === end) {
+      return { error: `windows[${index}]: start and end must be different HH:MM times` };
+    }
+    const days = parseDays(window.days);
+    if (!days) {
+      return { error: `windows[${index}]: days must list day names (mon, tue-fri) or numbers 0-6` };
+    }
+    windows.push({ days, start: window.start.trim(), end: window.end.trim() });
+  }
+  return { timezone, windows };
+}
+
+/**
+ * TOML/API shape of a schedule, the inverse of parseAvailability
+ */
+export function serializeAvailability(schedule: AvailabilitySchedule | undefined): Record<string, unknown> | undefined {
+  if (!schedule) {
+    return undefined;
+  }
+  return {
+    timezone: schedule.timezone,
+    windows: schedule.windows.map(window => ({
+      days: window.days.length === 7 ? undefined : window.days.map(day => DAY_NAMES[day]),
+      start: window.start,
+      end: window.end,
+    })),
+  };
+}
+
+function localTime(now: number, timezone?: string): { day: number; minutes: number } {
+  const parts = new Intl.DateTimeFormat('en-US', {
+    timeZone: timezone,
+    weekday: 'short',
+    hour: '2-digit',
+    minute: '2-digit',
+    hourCycle: 'h23',
+  }).formatToParts(new Date(now));
+  const part = (type: string) => parts.find(entry => entry.type === type)?.value ?? '';
+  return {
+    day: DAY_NAMES.indexOf(part('weekday').toLowerCase().slice(0, 3)),
+    minutes: Number(part('hour')) * 60 + Number(part('minute')),
+  };
+}
+
+/**
+ * Whether `now` falls in one of the schedule's windows; configs without a schedule are always available
+ */
+export function isWithinSchedule(schedule: AvailabilitySchedule | undefined, now = Date.now()): boolean {
+  if (!schedule) {
+    return true;
+  }
+  const { day, minutes } = localTime(now, schedule.timezone);
+  return schedule.windows.some(window => {
+    const start = parseTime(window.start)!;
+    const end = parseTime(window.end)!;
+    if (start < end) {
+      return window.days.includes(day) && minutes >= start && minutes < end;
+    }
+    // Overnight: the evening part on a listed day, the morning part on the day after one
+    return (
+      (window.days.includes(day) && minutes >= start) || (window.days.includes((day + 6) % 7) && minutes < end)
+    );
+  });
+}
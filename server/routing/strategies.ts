@@ -0,0 +1,139 @@
+// Selection strategies - how the load balancer picks among the configs left after exclusions.
+// The built-in modes are registered here; library users can add their own with registerSelectionStrategy.
+
+import type { ProxyConfig } from '../config/types';
+
+export interface SelectionCandidate {
+  config: ProxyConfig;
+  healthy: boolean;
+  consecutiveFailures: number;
+  overFailureThreshold: boolean; // Failing often enough that the built-ins only use it as a last resort
+  latencyMs?: number;            // Average time to response headers from this config's host, when known
+}
+
+export interface SelectionRequest {
+  service?: string;
+  model?: string;
+  path?: string;
+  tags?: Record<string, string>;
+}
+
+export interface SelectionInput {
+  // Enabled, in schedule and not frozen; every enabled config when all are frozen. Never empty.
+  candidates: SelectionCandidate[];
+  current: string | null; // The balancer's current config: last selected or last to succeed
+  request: SelectionRequest;
+}
+
+export interface SelectionResult {
+  config: ProxyConfig;
+  reason: string; // Short explanation kept with the request's routing context
+}
+
+/**
+ * One instance per load balancer, so implementations may keep rotation state between calls
+ */
+export interface SelectionStrategy {
+  readonly name: string;
+  select(input: SelectionInput): SelectionResult | null;
+}
+
+export type SelectionStrategyFactory = () => SelectionStrategy;
+
+/**
+ * Sticky weighted: stay on the current config until it fails; otherwise take the highest weight
+ * group with a config under the failure threshold, rotating within the group. With every config
+ * failing, pick at random by weight.
+ */
+export class WeightedStrategy implements SelectionStrategy {
+  readonly name = 'weighted';
+  private rotation = new Map<number, number>(); // weight -> next index in that group
+
+  select({ candidates, current }: SelectionInput): SelectionResult | null {
+    const sticky = current ? candidates.find(candidate => candidate.config.name === current) : undefined;
+    if (sticky && !sticky.overFailureThreshold) {
+      return { config: sticky.config, reason: 'current config' };
+    }
+
+    const weights = [...new Set(candidates.map(candidate => candidate.config.weight))].sort((a, b) => b - a);
+    for (const weight of weights) {
+      const eligible = candidates
+        .filter(candidate => candidate.config.weight === weight && !candidate.overFailureThreshold)
+        .sort((a, b) => a.config.name.localeCompare(b.config.name));
+      if (eligible.length === 0) {
+        continue;
+      }
+      let pointer = this.rotation.get(weight) ?? 0;
+      if (pointer >= eligible.length) {
+        pointer = 0;
+      }
+      this.rotation.set(weight, (pointer + 1) % eligible.length);
+      return { config: eligible[pointer].config, reason: `highest healthy weight (${weight})` };
+    }
+
+    const config = pickByWeight(candidates.map(candidate => candidate.config));
+    return config ? { config, reason: 'every config failing; random by weight' } : null;
+  }
+}
+
+export class RoundRobinStrategy implements SelectionStrategy {
+  readonly name = 'round-robin';
+  private index = 0;
+
+  select({ candidates }: SelectionInput): SelectionResult | null {
+    if (candidates.length === 0) {
+      return null;
+    }
+    const candidate = candidates[this.index % candidates.length];
+    this.index = (this.index + 1) % candidates.length;
+    return { config: candidate.config, reason: 'next in rotation' };
+  }
+}
+
+function pickByWeight(configs: ProxyConfig[]): ProxyConfig | null {
+  if (configs.length === 0) {
+    return null;
+  }
+  const totalWeight = configs.reduce((sum, config) => sum + config.weight, 0);
+  if (totalWeight <= 0) {
+    return configs[0];
+  }
+  let random = Math.random() * totalWeight;
+  for (const config of configs) {
+    random -= config.weight;
+    if (random <= 0) {
+      return config;
+    }
+  }
+  return configs[0];
+}
+
+export const BUILTIN_SELECTION_STRATEGIES = ['weighted', 'round-robin'] as const;
+
+const registry = new Map<string, SelectionStrategyFactory>([
+  ['weighted', () => new WeightedStrategy()],
+  ['round-robin', () => new RoundRobinStrategy()],
+]);
+
+/**
+ * Make `name` usable as `[loadbalancer] strategy`; call before the proxy core is created.
+ * Built-in names cannot be replaced.
+ */
+export function registerSelectionStrategy(name: string, factory: SelectionStrategyFactory): void {
+  if ((BUILTIN_SELECTION_STRATEGIES as readonly string[]).includes(name)) {
+    throw new Error(`Cannot replace built-in selection strategy "${name}"`);
+  }
+  registry.set(name, factory);
+}
+
+export function isSelectionStrategy(name: unknown): name is string {
+  return typeof name === 'string' && registry.has(name);
+}
+
+export function selectionStrategyNames(): string[] {
+  return [...registry.keys()];
+}
+
+export function createSelectionStrategy(name: string): SelectionStrategy | null {
+  return registry.get(name)?.() ?? null;
+}
@@ -0,0 +1,86 @@
+// Tenant budgets - a token allowance per UTC day or month, counted from the tenant's own request log
+
+import type { TenantBudgetConfig } from '../config/types';
+import type { RequestLogger } from '../logging/logger';
+import type { RateLimitState } from '../proxy/errors';
+
+export interface TenantBudgetStatus {
+  tokens: number;
+  period: TenantBudgetConfig['period'];
+  used: number;
+  remaining: number;
+  resetsAt: number;
+  exhausted: boolean;
+}
+
+/**
+ * Start of the UTC period containing `now`
+ */
+export function budgetPeriodStart(period: TenantBudgetConfig['period'], now: number): number {
+  const date = new Date(now);
+  return period === 'day'
+    ? Date.UTC(date.getUTCFullYear(), date.getUTCMonth(), date.getUTCDate())
+    : Date.UTC(date.getUTCFullYear(), date.getUTCMonth(), 1);
+}
+
+function budgetPeriodEnd(period: TenantBudgetConfig['period'], start: number): number {
+  const date = new Date(start);
+  return period === 'day'
+    ? Date.UTC(date.getUTCFullYear(), date.getUTCMonth(), date.getUTCDate() + 1)
+    : Date.UTC(date.getUTCFullYear(), date.getUTCMonth() + 1, 1);
+}
+
+/**
+ * Tokens a tenant has used this period. Seeded from requests.db, so a restart keeps the count, then
+ * kept current as requests are logged. Admission only: requests already in flight when the budget
+ * runs out still finish, so the count may end up slightly over.
+ */
+export class TenantBudget {
+  private periodStart = -1;
+  private used = 0;
+
+  constructor(
+    private config: TenantBudgetConfig,
+    private logger: RequestLogger,
+    private now: () => number = Date.now
+  ) {
+    this.roll();
+    logger.onRequestLogged(log => {
+      this.roll();
+      if (log.timestamp >= this.periodStart) {
+        this.used += (log.inputTokens ?? 0) + (log.outputTokens ?? 0);
+      }
+    });
+  }
+
+  status(): TenantBudgetStatus {
+    this.roll();
+    const remaining = Math.max(0, this.config.tokens - this.used);
+    return {
+      tokens: this.config.tokens,
+      period: this.config.period,
+      used: this.used,
+      remaining,
+      resetsAt: budgetPeriodEnd(this.config.period, this.periodStart),
+      exhausted: remaining === 0,
+    };
+  }
+
+  /**
+   * The limiter state to refuse a request with; null while tokens remain
+   */
+  exhausted(): RateLimitState | null {
+    const status = this.status();
+    return status.exhausted ? { limit: status.tokens, remaining: 0, resetAt: status.resetsAt } : null;
+  }
+
+  private roll(): void {
+    const start = budgetPeriodStart(this.config.period, this.now());
+    if (start === this.periodStart) {
+      return;
+    }
+    this.periodStart = start;
+    const usage = this.logger.getUsageStats({ since: start });
+    this.used = usage.totalInputTokens + usage.totalOutputTokens;
+  }
+}
@@ -0,0 +1,103 @@
+// Tenant runtime - per-tenant configs, load balancers, proxies and request logs
+
+import { join } from 'path';
+import { ConfigManager } from '../config/manager';
+import type { LogScrubbingConfig, TenantConfig } from '../config/types';
+import { RequestLogger } from '../logging/logger';
+import type { ConnectionStats } from '../proxy/connectionStats';
+import type { DlpFilter } from '../proxy/dlp';
+import type { BodyMemoryBudget } from '../proxy/memoryBudget';
+import type { ConcurrencyLimiter } from '../proxy/concurrency';
+import type { ShapeCacheConfig } from '../proxy/shapeCache';
+import type { ResponseCacheConfig } from '../proxy/responseCache';
+import { createProxyCore, ensureServiceConfigs, type ProxyCore, type ServiceName } from '../core';
+import { TenantBudget } from './budget';
+
+export const DEFAULT_TENANT = 'default';
+
+export type TenantServiceName = ServiceName;
+
+export interface TenantRuntime extends ProxyCore {
+  name: string;
+  budget?: TenantBudget; // From the tenant's [[tenants]] budget; the default tenant has none
+}
+
+export function tenantConfigDir(dataDir: string, tenantName: string): string {
+  return join(dataDir, 'tenants', tenantName);
+}
+
+/**
+ * Policies every tenant inherits from the top-level system.toml
+ */
+export interface SharedTenantPolicies {
+  dlp?: DlpFilter;
+  logScrubbing?: LogScrubbingConfig;
+  modelListTtlSeconds?: number;
+  passthroughBodyBytes?: number;
+  memoryBudget?: BodyMemoryBudget;
+  shapeCache?: ShapeCacheConfig;
+  responseCache?: ResponseCacheConfig;
+  concurrency?: ConcurrencyLimiter;
+  lite?: boolean;
+}
+
+/**
+ * Build an isolated runtime for a tenant; its TOML files and requests.db live in the tenant directory
+ */
+export async function createTenantRuntime(
+  tenant: TenantConfig,
+  dataDir: string,
+  connectionStats?: ConnectionStats,
+  shared: SharedTenantPolicies = {}
+): Promise<TenantRuntime> {
+  const dir = tenantConfigDir(dataDir, tenant.name);
+  // PAF_* variables describe the default tenant only
+  const configManager = new ConfigManager(dir, false);
+  await ensureServiceConfigs(configManager);
+  const logger = new RequestLogger(dir, { scrubbing: shared.logScrubbing, jsonl: shared.lite });
+
+  return {
+    name: tenant.name,
+    budget: tenant.budget && new TenantBudget(tenant.budget, logger),
+    ...createProxyCore(
+      configManager,
+      logger,
+      connectionStats,
+      shared.dlp,
+      shared.modelListTtlSeconds ?? 0,
+      shared.passthroughBodyBytes ?? 0,
+      shared.memoryBudget,
+      shared.shapeCache ?? { threshold: 0, ttlSeconds: 0 },
+      shared.responseCache ?? { ttlSeconds: 0, maxBytes: 0 },
+      shared.concurrency
+    ),
+  };
+}
+
+/**
+ * Extract the inbound client key (x-api-key or bearer token) used for tenant selection
+ */
+export function getClientKey(request: Request): string | undefined {
+  const apiKey = request.headers.get('x-api-key');
+  if (apiKey) {
+    return apiKey.trim();
+  }
+
+  const authHeader = request.headers.get('authorization');
+  if (authHeader?.toLowerCase().startsWith('bearer ')) {
+    return authHeader.slice('bearer '.length).trim();
+  }
+
+  return undefined;
+}
+
+/**
+ * Find the tenant that owns the request's client key, if any
+ */
+export function findTenantByClientKey(request: Request, tenants: TenantConfig[]): TenantConfig | undefined {
+  const clientKey = getClientKey(request);
+  if (!clientKey) {
+    return undefined;
+  }
+  return tenants.find(tenant => tenant.clientKeys.includes(clientKey));
+}
@@ -0,0 +1,223 @@
+// Test harness - run the proxy in-process against scripted mock upstreams
+//
+//   const harness = await createTestHarness({
+//     service: 'claude',
+//     mode: 'load_balance',
+//     configs: [
+//       { name: 'primary', responses: [{ status: 500 }] },
+//       { name: 'backup', responses: [{ json: { id: 'msg_1', usage: { input_tokens: 3, output_tokens: 5 } } }] },
+//     ],
+//   });
+//   const res = await harness.request('/v1/messages', { body: { model: 'claude-sonnet-4-5', messages: [] } });
+//   const [log] = await harness.waitForLogs(1);
+//   await harness.close();
+//
+// Upstream failures freeze configs just like in production; pass freezeDuration: 0 to keep them eligible.
+
+import { mkdtempSync, rmSync } from 'fs';
+import { tmpdir } from 'os';
+import { join } from 'path';
+import { ConfigManager } from './config/manager';
+import { createDefaultServiceConfig } from './config/defaults';
+import type { LoadBalancerConfig, ProxyConfig, ServiceConfig } from './config/types';
+import type { ServiceName } from './core';
+import type { RequestLog } from './logging/database';
+import { ProxyBuilder, type EmbeddedProxy, type ProxyHooks } from './lib';
+import type { LoadBalancer } from './routing/loadbalancer';
+
+export interface MockResponse {
+  status?: number;
+  headers?: Record<string, string>;
+  json?: unknown;
+  body?: string;
+  sse?: Array<unknown>; // Each entry becomes one `data:` event; strings are sent verbatim
+  delayMs?: number;     // Wait before answering, e.g. to exercise timeouts
+}
+
+export type MockHandler = (request: Request) => MockResponse | Response | Promise<MockResponse | Response>;
+
+export interface RecordedRequest {
+  method: string;
+  path: string;
+  headers: Record<string, string>;
+  body: string;
+}
+
+export interface MockConfigDefinition {
+  name: string;
+  weight?: number;
+  enabled?: boolean;
+  apiKey?: string;
+  authToken?: string;
+  responses?: Array<MockResponse | MockHandler>; // Served in order, one per request
+  fallback?: MockResponse | MockHandler;         // Used once the queue is empty (default: 200 {})
+}
+
+export interface MockUpstream {
+  name: string;
+  url: string;
+  requests: RecordedRequest[];
+  enqueue(...responses: Array<MockResponse | MockHandler>): void;
+}
+
+export interface TestHarnessOptions {
+  service?: ServiceName;
+  configs: MockConfigDefinition[];
+  mode?: ServiceConfig['mode'];
+  loadBalancer?: Partial<LoadBalancerConfig>;
+  serviceConfig?: Partial<Omit<ServiceConfig, 'configs' | 'mode' | 'loadBalancer'>>;
+  hooks?: ProxyHooks;
+}
+
+export interface TestHarness {
+  proxy: EmbeddedProxy;
+  service: ServiceName;
+  configDir: string;
+  upstreams: Record<string, MockUpstream>;
+  loadBalancer: LoadBalancer;
+  /** Send a request through the proxy; object bodies are sent as JSON */
+  request(path: string, init?: { method?: string; headers?: Record<string, string>; body?: unknown }): Promise<Response>;
+  /** Request logs for the harness service, oldest first */
+  logs(): RequestLog[];
+  /** Resolve once at least `count` logs exist; streaming requests are logged after the body is drained */
+  waitForLogs(count: number, timeoutMs?: number): Promise<RequestLog[]>;
+  close(): Promise<void>;
+}
+
+function startMockUpstream(definition: MockConfigDefinition): MockUpstream & { stop(): void } {
+  const queue = [...(definition.responses ?? [])];
+  const requests: RecordedRequest[] = [];
+
+  const server = Bun.serve({
+    port: 0,
+    hostname: '127.0.0.1',
+    async fetch(req) {
+      const url = new URL(req.url);
+      const headers: Record<string, string> = {};
+      req.headers.forEach((value, key) => {
+        headers[key] = value;
+      });
+      requests.push({ method: req.method, path: `${url.pathname}${url.search}`, headers, body: await req.clone().text() });
+
+      const next = queue.shift() ?? definition.fallback ?? { json: {} };
+      const result = typeof next === 'function' ? await next(req) : next;
+      return result instanceof Response ? result : renderMockResponse(result);
+    },
+  });
+
+  return {
+    name: definition.name,
+    url: `http://127.0.0.1:${server.port}`,
+    requests,
+    enqueue(...responses) {
+      queue.push(...responses);
+    },
+    stop() {
+      server.stop(true);
+    },
+  };
+}
+
+async function renderMockResponse(mock: MockResponse): Promise<Response> {
+  if (mock.delayMs) {
+    await Bun.sleep(mock.delayMs);
+  }
+
+  const status = mock.status ?? 200;
+  if (mock.sse) {
+    const body = mock.sse
+      .map(event => `data: ${typeof event === 'string' ? event : JSON.stringify(event)}\n\n`)
+      .join('');
+    return new Response(body, { status, headers: { 'content-type': 'text/event-stream', ...mock.headers } });
+  }
+  if (mock.json !== undefined) {
+    return Response.json(mock.json, { status, headers: mock.headers });
+  }
+  return new Response(mock.body ?? '', { status, headers: mock.headers });
+}
+
+/**
+ * Start mock upstreams and an embedded proxy wired to them in a throwaway config directory
+ */
+export async function createTestHarness(options: TestHarnessOptions): Promise<TestHarness> {
+  const service = options.service ?? 'claude';
+  const configDir = mkdtempSync(join(tmpdir(), 'paf-test-'));
+  const mocks = options.configs.map(startMockUpstream);
+
+  const defaults = createDefaultServiceConfig();
+  const configs: ProxyConfig[] = options.configs.map((definition, index) => ({
+    name: definition.name,
+    baseUrl: mocks[index].url,
+    apiKey: definition.apiKey,
+    authToken: definition.authToken ?? (definition.apiKey ? undefined : 'test-token'),
+    weight: definition.weight ?? 1,
+    enabled: definition.enabled ?? true,
+  }));
+
+  // Seed the service TOML before the proxy loads it; health checks would hit the mocks, so they default off
+  const seed = new ConfigManager(configDir, false);
+  await seed.initialize();
+  await seed.saveServiceConfig(service, {
+    ...defaults,
+    ...options.serviceConfig,
+    configs,
+    active: configs[0]?.name ?? '',
+    mode: options.mode ?? 'manual',
+    loadBalancer: {
+      ...defaults.loadBalancer,
+      healthCheck: { ...defaults.loadBalancer.healthCheck, enabled: false },
+      ...options.loadBalancer,
+    },
+  });
+
+  const builder = new ProxyBuilder().withConfigDir(configDir).withDataDir(configDir).withEnvOverrides(false);
+  if (options.hooks) {
+    builder.withHooks(options.hooks);
+  }
+  const proxy = await builder.build();
+
+  const logs = () =>
+    proxy.logger
+      .getRecentLogs(10000)
+      .filter(log => log.service === service)
+      .reverse();
+
+  return {
+    proxy,
+    service,
+    configDir,
+    upstreams: Object.fromEntries(mocks.map(mock => [mock.name, mock])),
+    loadBalancer: proxy.loadBalancers[service],
+    request(path, init = {}) {
+      const headers = new Headers(init.headers);
+      let body: string | undefined;
+      if (init.body !== undefined) {
+        body = typeof init.body === 'string' ? init.body : JSON.stringify(init.body);
+        if (!headers.has('content-type')) {
+          headers.set('content-type', 'application/json');
+        }
+      }
+      const method = init.method ?? (body === undefined ? 'GET' : 'POST');
+      return proxy.handle(new Request(`http://paf.test${path}`, { method, headers, body }), service);
+    },
+    logs,
+    async waitForLogs(count, timeoutMs = 2000) {
+      const deadline = Date.now() + timeoutMs;
+      while (Date.now() < deadline) {
+        const current = logs();
+        if (current.length >= count) {
+          return current;
+        }
+        await Bun.sleep(10);
+      }
+      throw new Error(`Expected ${count} request logs within ${timeoutMs}ms, found ${logs().length}`);
+    },
+    async close() {
+      proxy.close();
+      for (const mock of mocks) {
+        mock.stop();
+      }
+      rmSync(configDir, { recursive: true, force: true });
+    },
+  };
+}
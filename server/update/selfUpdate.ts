@@ -0,0 +1,172 @@
+// Self-update - replaces a standalone paf binary with the latest GitHub release after verifying it
+
+import { createHash, createPublicKey, verify } from 'crypto';
+import { chmodSync, copyFileSync, linkSync, renameSync, rmSync } from 'fs';
+import pkg from '../../package.json';
+
+const DEFAULT_REPOSITORY = 'LinusChen-yf/proxy-ai-fusion';
+const CHECKSUMS_ASSET = 'SHA256SUMS';
+const SIGNATURE_ASSET = 'SHA256SUMS.sig';
+const FETCH_TIMEOUT_MS = 30_000;
+
+// Ed25519 key the release SHA256SUMS are signed with, as a base64 signature in SHA256SUMS.sig:
+//   openssl pkeyutl -sign -rawin -inkey release-signing-key.pem -in SHA256SUMS | base64 > SHA256SUMS.sig
+// Forks publishing their own releases (PAF_UPDATE_REPOSITORY) set PAF_UPDATE_PUBLIC_KEY to their key instead
+const RELEASE_PUBLIC_KEY = `-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEADe0OZuQrmSdyQHqlaApk14USXTce2VMpa0sVqKyiRvI=
+-----END PUBLIC KEY-----`;
+const DOWNLOAD_TIMEOUT_MS = 10 * 60_000;
+
+export const CURRENT_VERSION: string = pkg.version;
+
+export interface ReleaseAsset {
+  name: string;
+  url: string; // browser_download_url
+}
+
+export interface ReleaseInfo {
+  version: string; // Tag without the leading "v"
+  url: string;     // Release page
+  assets: ReleaseAsset[];
+}
+
+/**
+ * Binaries built with `bun build --compile` run from Bun's embedded filesystem
+ */
+export function isStandaloneBinary(): boolean {
+  return Bun.main.startsWith('/$bunfs/') || Bun.main.includes('~BUN');
+}
+
+/**
+ * Release asset for this machine, e.g. paf-linux-x64, paf-darwin-arm64, paf-windows-x64.exe
+ */
+export function platformAssetName(): string {
+  const platform = process.platform === 'win32' ? 'windows' : process.platform;
+  return `paf-${platform}-${process.arch}${process.platform === 'win32' ? '.exe' : ''}`;
+}
+
+/**
+ * Numeric comparison of dotted versions; pre-release suffixes are ignored
+ */
+export function compareVersions(a: string, b: string): number {
+  const parts = (version: string) => version.replace(/^v/, '').split('-')[0].split('.').map(part => Number(part) || 0);
+  const left = parts(a);
+  const right = parts(b);
+  for (let i = 0; i < Math.max(left.length, right.length); i++) {
+    const diff = (left[i] ?? 0) - (right[i] ?? 0);
+    if (diff !== 0) {
+      return diff;
+    }
+  }
+  return 0;
+}
+
+export async function fetchLatestRelease(repository = process.env.PAF_UPDATE_REPOSITORY || DEFAULT_REPOSITORY): Promise<ReleaseInfo> {
+  const response = await fetch(`https://api.github.com/repos/${repository}/releases/latest`, {
+    headers: { accept: 'application/vnd.github+json', 'user-agent': `proxy-ai-fusion/${CURRENT_VERSION}` },
+    signal: AbortSignal.timeout(FETCH_TIMEOUT_MS),
+  });
+  if (!response.ok) {
+    throw new Error(`GitHub releases returned HTTP ${response.status}`);
+  }
+  const release: any = await response.json();
+  return {
+    version: String(release.tag_name ?? '').replace(/^v/, ''),
+    url: release.html_url,
+    assets: (Array.isArray(release.assets) ? release.assets : []).map((asset: any) => ({
+      name: asset.name,
+      url: asset.browser_download_url,
+    })),
+  };
+}
+
+async function download(asset: ReleaseAsset): Promise<Uint8Array> {
+  const response = await fetch(asset.url, { signal: AbortSignal.timeout(DOWNLOAD_TIMEOUT_MS) });
+  if (!response.ok) {
+    throw new Error(`Downloading ${asset.name} failed with HTTP ${response.status}`);
+  }
+  return new Uint8Array(await response.arrayBuffer());
+}
+
+/**
+ * Check `binary` against the release's SHA256SUMS, which must carry a valid SHA256SUMS.sig from the
+ * release key (or PAF_UPDATE_PUBLIC_KEY, an Ed25519 public key in PEM). Unsigned releases are refused.
+ */
+export async function verifyReleaseBinary(release: ReleaseInfo, assetName: string, binary: Uint8Array): Promise<void> {
+  const checksumsAsset = release.assets.find(asset => asset.name === CHECKSUMS_ASSET);
+  if (!checksumsAsset) {
+    throw new Error(`Release ${release.version} has no ${CHECKSUMS_ASSET}; refusing an unverified binary`);
+  }
+  const signatureAsset = release.assets.find(asset => asset.name === SIGNATURE_ASSET);
+  if (!signatureAsset) {
+    throw new Error(`Release ${release.version} has no ${SIGNATURE_ASSET}; refusing an unsigned binary`);
+  }
+  const checksums = await download(checksumsAsset);
+
+  const publicKey = createPublicKey(process.env.PAF_UPDATE_PUBLIC_KEY || RELEASE_PUBLIC_KEY);
+  const signature = Buffer.from(new TextDecoder().decode(await download(signatureAsset)).trim(), 'base64');
+  if (!verify(null, checksums, publicKey, signature)) {
+    throw new Error(`${SIGNATURE_ASSET} is not a valid signature of ${CHECKSUMS_ASSET}; refusing the binary`);
+  }
+
+  const expected = new TextDecoder()
+    .decode(checksums)
+    .split('\n')
+    .map(line => line.trim().split(/\s+\*?/))
+    .find(([, name]) => name === assetName)?.[0];
+  if (!expected) {
+    throw new Error(`${CHECKSUMS_ASSET} has no entry for ${assetName}`);
+  }
+  const actual = createHash('sha256').update(binary).digest('hex');
+  if (actual !== expected.toLowerCase()) {
+    throw new Error(`Checksum mismatch for ${assetName} (expected ${expected}, got ${actual})`);
+  }
+}
+
+/**
+ * Download, verify and swap in the release binary. The new file is written next to the running one
+ * and renamed over it in one step, so the path always holds either the old or the new binary; the
+ * previous one is kept as `<executable>.old`.
+ */
+export async function installRelease(release: ReleaseInfo, executable = process.execPath): Promise<void> {
+  const assetName = platformAssetName();
+  const asset = release.assets.find(candidate => candidate.name === assetName);
+  if (!asset) {
+    throw new Error(`Release ${release.version} has no binary for this platform (${assetName})`);
+  }
+
+  const binary = await download(asset);
+  await verifyReleaseBinary(release, assetName, binary);
+
+  const staged = `${executable}.new`;
+  const previous = `${executable}.old`;
+  await Bun.write(staged, binary);
+  chmodSync(staged, 0o755);
+  rmSync(previous, { force: true });
+
+  // Windows cannot replace a running executable, but it can rename it out of the way
+  if (process.platform === 'win32') {
+    renameSync(executable, previous);
+    try {
+      renameSync(staged, executable);
+    } catch (error) {
+      renameSync(previous, executable);
+      throw error;
+    }
+    return;
+  }
+
+  // The old binary stays at `.old` to roll back to; a rename over an existing file is atomic
+  try {
+    linkSync(executable, previous);
+  } catch {
+    copyFileSync(executable, previous);
+  }
+  try {
+    renameSync(staged, executable);
+  } catch (error) {
+    rmSync(staged, { force: true });
+    rmSync(previous, { force: true });
+    throw error;
+  }
+}
@@ -0,0 +1,58 @@
+// Conditional GETs - ETag / Last-Modified for API responses the dashboard polls, derived from change
+// counters so an unchanged resource is answered with a 304 before any SQLite query runs
+
+/**
+ * Bumped by the owner of some state on every change (config manager, request logger, quota monitor)
+ */
+export interface ChangeMarker {
+  revision: number;
+  changedAt: number; // Unix ms of the latest change
+}
+
+export interface Validators {
+  etag: string;
+  changedAt: number;
+}
+
+/**
+ * Validators for a response built from the state behind `markers`; `variant` holds whatever else
+ * shapes the body (query string, tenant, in-memory selections)
+ */
+export function changeValidators(markers: ChangeMarker[], variant = ''): Validators {
+  const revisions = markers.map(marker => marker.revision).join('.');
+  const hash = Bun.hash(`${variant}\n${revisions}`).toString(36);
+  return {
+    etag: `W/"${revisions}-${hash}"`,
+    changedAt: Math.max(0, ...markers.map(marker => marker.changedAt)),
+  };
+}
+
+/**
+ * ETag plus `no-cache`, which makes browsers revalidate every poll instead of reusing a stale body.
+ * Last-Modified is left out while its second is still running: a later change in the same second
+ * would carry the same date and be hidden from If-Modified-Since.
+ */
+export function validatorHeaders(validators: Validators, now = Date.now()): Record<string, string> {
+  const headers: Record<string, string> = { ETag: validators.etag, 'Cache-Control': 'no-cache' };
+  if (Math.floor(validators.changedAt / 1000) < Math.floor(now / 1000)) {
+    headers['Last-Modified'] = new Date(validators.changedAt).toUTCString();
+  }
+  return headers;
+}
+
+/**
+ * A 304 when the client's copy is current, else null. If-None-Match wins over If-Modified-Since.
+ */
+export function notModified(req: Request, validators: Validators, headers: Record<string, string>): Response | null {
+  const ifNoneMatch = req.headers.get('if-none-match');
+  const ifModifiedSince = req.headers.get('if-modified-since');
+  let fresh = false;
+  if (ifNoneMatch) {
+    const etag = validators.etag.replace(/^W\//, '');
+    fresh = ifNoneMatch.split(',').some(tag => tag.trim().replace(/^W\//, '') === etag);
+  } else if (ifModifiedSince) {
+    const since = Date.parse(ifModifiedSince);
+    fresh = Number.isFinite(since) && Math.floor(validators.changedAt / 1000) * 1000 <= since;
+  }
+  return fresh ? new Response(null, { status: 304, headers: { ...headers, ...validatorHeaders(validators) } }) : null;
+}
@@ -0,0 +1,107 @@
+// Static assets - dashboard files with ETag revalidation, compressed variants and cache headers, so
+// refreshes over slow links cost a 304 instead of the whole bundle
+
+import { existsSync, statSync } from 'fs';
+import { brotliCompressSync, constants as zlibConstants, gzipSync } from 'zlib';
+
+// Content-hashed names (main-3f9a1c2b.js) never change content, so browsers may keep them for a year
+const HASHED_NAME = /[.-][0-9a-f]{8,}\.[a-z0-9]+$/i;
+const IMMUTABLE_CACHE = 'public, max-age=31536000, immutable';
+// Everything else is revalidated on each load, which the ETag turns into a cheap 304
+const REVALIDATE_CACHE = 'no-cache';
+
+// Compressing tiny files costs more than it saves
+const MIN_COMPRESS_BYTES = 1024;
+const COMPRESSIBLE = /^(text\/|application\/(javascript|json|xml)|image\/svg\+xml)/;
+
+type Encoding = 'br' | 'gzip';
+
+const ENCODING_SUFFIX: Record<Encoding, string> = { br: '.br', gzip: '.gz' };
+
+// Variants compressed on first request, per file version (the ETag)
+const compressed = new Map<string, Uint8Array>();
+
+function etagOf(size: number, mtimeMs: number): string {
+  return `"${size.toString(36)}-${Math.floor(mtimeMs).toString(36)}"`;
+}
+
+function acceptedEncodings(req: Request): Encoding[] {
+  const header = req.headers.get('accept-encoding') ?? '';
+  const accepted = (encoding: string) =>
+    header.split(',').some(part => {
+      const [name, ...params] = part.trim().split(';');
+      return name === encoding && !params.some(param => param.trim() === 'q=0');
+    });
+  return (['br', 'gzip'] as const).filter(accepted);
+}
+
+function matchesEtag(req: Request, etag: string): boolean {
+  const header = req.headers.get('if-none-match');
+  if (!header) {
+    return false;
+  }
+  return header.trim() === '*' || header.split(',').some(tag => tag.trim().replace(/^W\//, '') === etag);
+}
+
+async function compressedVariant(path: string, etag: string, encoding: Encoding, file: Blob): Promise<Uint8Array> {
+  const key = `${path}\n${etag}\n${encoding}`;
+  let body = compressed.get(key);
+  if (!body) {
+    // Drop older versions of this file, e.g. after a rebuild
+    for (const existing of compressed.keys()) {
+      if (existing.startsWith(`${path}\n`) && !existing.startsWith(`${path}\n${etag}\n`)) {
+        compressed.delete(existing);
+      }
+    }
+    const bytes = new Uint8Array(await file.arrayBuffer());
+    body =
+      encoding === 'br'
+        ? brotliCompressSync(bytes, { params: { [zlibConstants.BROTLI_PARAM_QUALITY]: 9 } })
+        : gzipSync(bytes, { level: 9 });
+    compressed.set(key, body);
+  }
+  return body;
+}
+
+/**
+ * Serve `path` from disk; null when it does not exist. Build-time `.br`/`.gz` files next to it are
+ * used when present and current, otherwise text assets are compressed once and kept in memory.
+ */
+export async function serveStaticFile(req: Request, path: string): Promise<Response | null> {
+  if (!existsSync(path)) {
+    return null;
+  }
+  const stat = statSync(path);
+  if (!stat.isFile()) {
+    return null;
+  }
+
+  const file = Bun.file(path);
+  const etag = etagOf(stat.size, stat.mtimeMs);
+  const headers = new Headers({
+    ETag: etag,
+    'Cache-Control': HASHED_NAME.test(path) ? IMMUTABLE_CACHE : REVALIDATE_CACHE,
+    'Content-Type': file.type,
+    Vary: 'Accept-Encoding',
+  });
+
+  if (matchesEtag(req, etag)) {
+    return new Response(null, { status: 304, headers });
+  }
+  if (req.method === 'HEAD') {
+    return new Response(null, { headers });
+  }
+
+  const compressible = stat.size >= MIN_COMPRESS_BYTES && COMPRESSIBLE.test(file.type);
+  const encoding = compressible ? acceptedEncodings(req)[0] : undefined;
+  if (encoding) {
+    headers.set('Content-Encoding', encoding);
+    const variantPath = `${path}${ENCODING_SUFFIX[encoding]}`;
+    if (existsSync(variantPath) && statSync(variantPath).mtimeMs >= stat.mtimeMs) {
+      return new Response(Bun.file(variantPath), { headers });
+    }
+    return new Response(await compressedVariant(path, etag, encoding, file), { headers });
+  }
+
+  return new Response(file, { headers });
+}
@@ -0,0 +1,129 @@
+// Webhook verification - checks provider signatures on async callbacks before they are trusted
+
+import { createHash, createHmac, timingSafeEqual } from 'crypto';
+import type { WebhookProviderConfig } from '../config/types';
+import { readTextWithin } from '../proxy/bodyLimit';
+
+// Callbacks are unauthenticated until their signature is checked, so nothing larger is read; provider
+// event payloads are a few KB
+export const MAX_WEBHOOK_BODY_BYTES = 256 * 1024;
+
+export type WebhookVerification =
+  | { ok: true; deliveryId: string }
+  | { ok: false; error: string };
+
+export interface WebhookPayloadInfo {
+  eventType?: string;
+  upstreamId?: string;
+}
+
+/**
+ * The callback body, or null when it is over MAX_WEBHOOK_BODY_BYTES (declared or while reading)
+ */
+export async function readWebhookBody(request: Request): Promise<string | null> {
+  if (Number(request.headers.get('content-length')) > MAX_WEBHOOK_BODY_BYTES) {
+    return null;
+  }
+  return readTextWithin(request, MAX_WEBHOOK_BODY_BYTES);
+}
+
+/**
+ * Verify a callback against the provider's configured signature scheme
+ */
+export function verifyWebhookSignature(
+  config: WebhookProviderConfig,
+  headers: Headers,
+  rawBody: string,
+  now = Date.now()
+): WebhookVerification {
+  return config.scheme === 'hmac-sha256'
+    ? verifyHexHmac(config, headers, rawBody)
+    : verifyStandardWebhook(config, headers, rawBody, now);
+}
+
+/**
+ * Standard Webhooks: base64 HMAC-SHA256 over "<id>.<timestamp>.<body>", keyed by the whsec_ secret
+ */
+function verifyStandardWebhook(
+  config: WebhookProviderConfig,
+  headers: Headers,
+  rawBody: string,
+  now: number
+): WebhookVerification {
+  const id = headers.get('webhook-id');
+  const timestamp = headers.get('webhook-timestamp');
+  const signatures = headers.get('webhook-signature');
+  if (!id || !timestamp || !signatures) {
+    return { ok: false, error: 'Missing webhook-id, webhook-timestamp or webhook-signature header' };
+  }
+
+  const seconds = Number(timestamp);
+  if (!Number.isFinite(seconds) || Math.abs(now / 1000 - seconds) > config.toleranceSeconds) {
+    return { ok: false, error: 'Webhook timestamp outside tolerance' };
+  }
+
+  const key = config.secret.startsWith('whsec_')
+    ? Buffer.from(config.secret.slice('whsec_'.length), 'base64')
+    : Buffer.from(config.secret);
+  const expected = createHmac('sha256', key).update(`${id}.${timestamp}.${rawBody}`).digest();
+
+  // The header may list several space-separated "v1,<base64>" signatures during secret rotation
+  const matched = signatures.split(' ').some(entry => {
+    const [version, signature] = entry.split(',', 2);
+    return version === 'v1' && !!signature && safeEqual(Buffer.from(signature, 'base64'), expected);
+  });
+
+  return matched ? { ok: true, deliveryId: id } : { ok: false, error: 'Invalid webhook signature' };
+}
+
+/**
+ * Plain hex HMAC-SHA256 of the body in a configurable header, optionally prefixed with "sha256="
+ */
+function verifyHexHmac(config: WebhookProviderConfig, headers: Headers, rawBody: string): WebhookVerification {
+  const header = headers.get(config.signatureHeader);
+  if (!header) {
+    return { ok: false, error: `Missing ${config.signatureHeader} header` };
+  }
+
+  const provided = Buffer.from(header.trim().replace(/^sha256=/i, ''), 'hex');
+  const expected = createHmac('sha256', config.secret).update(rawBody).digest();
+  if (!safeEqual(provided, expected)) {
+    return { ok: false, error: 'Invalid webhook signature' };
+  }
+
+  // No delivery id in this scheme; identical bodies are treated as redeliveries
+  return { ok: true, deliveryId: createHash('sha256').update(rawBody).digest('hex') };
+}
+
+function safeEqual(a: Buffer, b: Buffer): boolean {
+  return a.length === b.length && timingSafeEqual(a, b);
+}
+
+/**
+ * Pull the event type and the upstream object id the callback refers to
+ */
+export function parseWebhookPayload(config: WebhookProviderConfig, rawBody: string): WebhookPayloadInfo | null {
+  let payload: any;
+  try {
+    payload = JSON.parse(rawBody);
+  } catch {
+    return null;
+  }
+  if (!payload || typeof payload !== 'object') {
+    return null;
+  }
+
+  const upstreamId = config.idPath
+    ? readPath(payload, config.idPath)
+    : readPath(payload, 'data.id') ?? readPath(payload, 'id');
+
+  return {
+    eventType: typeof payload.type === 'string' ? payload.type : undefined,
+    upstreamId,
+  };
+}
+
+function readPath(value: any, path: string): string | undefined {
+  const result = path.split('.').reduce((current, key) => (current == null ? undefined : current[key]), value);
+  return typeof result === 'string' ? result : undefined;
+}
@@ -5,6 +5,7 @@ import { ErrorBoundary } from '@/components/ErrorBoundary';
 import { ConfigPanel } from '@/components/ConfigPanel';
 import { LoadBalancerPanel } from '@/components/LoadBalancerPanel';
 import { LogsPanel } from '@/components/LogsPanel';
+import { ComparePanel } from '@/components/ComparePanel';
 import { DocsPanel } from '@/components/DocsPanel';
 import { DashboardPanel } from '@/components/DashboardPanel';
 import { Tabs, TabsContent, TabsList, TabsTrigger } from '@/components/ui/tabs';
@@ -102,6 +103,7 @@ function App() {
             <TabsTrigger value="configs">{t('nav.configs')}</TabsTrigger>
             <TabsTrigger value="loadbalancer">{t('nav.loadbalancer')}</TabsTrigger>
             <TabsTrigger value="logs">{t('nav.logs')}</TabsTrigger>
+            <TabsTrigger value="compare">{t('nav.compare')}</TabsTrigger>
             <TabsTrigger value="docs">{t('nav.docs')}</TabsTrigger>
           </TabsList>
 
@@ -129,6 +131,12 @@ function App() {
             </ErrorBoundary>
           </TabsContent>
 
+          <TabsContent value="compare" className="space-y-4">
+            <ErrorBoundary>
+              <ComparePanel />
+            </ErrorBoundary>
+          </TabsContent>
+
           <TabsContent value="docs" className="space-y-4">
             <ErrorBoundary>
               <DocsPanel />
@@ -0,0 +1,224 @@
+import { useEffect, useState } from 'react';
+import { api } from '@/services/api';
+import type { FanoutResult, ServiceConfig, ServiceId } from '@/types/common';
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
+import { Badge } from '@/components/ui/badge';
+import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
+import { Label } from '@/components/ui/label';
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from '@/components/ui/select';
+import { Play } from 'lucide-react';
+import { useTranslation } from '@/hooks/useTranslation';
+import { useFeedback } from '@/components/FeedbackProvider';
+
+// Mirrors MAX_FANOUT_CONFIGS on the server
+const MAX_CONFIGS = 8;
+
+function normalizeConfigs(
+  configs: Record<string, ServiceConfig> | ServiceConfig[] | undefined,
+): ServiceConfig[] {
+  if (!configs) {
+    return [];
+  }
+  if (Array.isArray(configs)) {
+    return configs;
+  }
+  return Object.entries(configs).map(([name, config]) => ({ ...config, name }));
+}
+
+// A Messages body for claude, Chat Completions for codex (the fan-out's default paths); only Messages
+// requires max_tokens
+function buildBody(service: ServiceId, model: string, prompt: string): Record<string, unknown> {
+  const body: Record<string, unknown> = { messages: [{ role: 'user', content: prompt }] };
+  if (service === 'claude') {
+    body.max_tokens = 1024;
+  }
+  if (model.trim()) {
+    body.model = model.trim();
+  }
+  return body;
+}
+
+// The answer text of a Messages or Chat Completions response; anything else is shown as JSON
+function answerText(result: FanoutResult): string {
+  if (result.error) {
+    return result.error;
+  }
+  const response = result.response as any;
+  if (Array.isArray(response?.content)) {
+    return response.content
+      .filter((block: any) => block?.type === 'text' && typeof block.text === 'string')
+      .map((block: any) => block.text)
+      .join('\n');
+  }
+  const message = response?.choices?.[0]?.message?.content;
+  if (typeof message === 'string') {
+    return message;
+  }
+  if (typeof response?.error?.message === 'string') {
+    return response.error.message;
+  }
+  return typeof response === 'string' ? response : JSON.stringify(response, null, 2);
+}
+
+export function ComparePanel() {
+  const { t } = useTranslation();
+  const feedback = useFeedback();
+  const [service, setService] = useState<ServiceId>('claude');
+  const [configs, setConfigs] = useState<Record<ServiceId, ServiceConfig[]>>({ claude: [], codex: [] });
+  const [selected, setSelected] = useState<string[]>([]);
+  const [model, setModel] = useState('');
+  const [prompt, setPrompt] = useState('');
+  const [results, setResults] = useState<FanoutResult[]>([]);
+  const [running, setRunning] = useState(false);
+
+  useEffect(() => {
+    api.listSeparatedConfigs()
+      .then(data => setConfigs({
+        claude: normalizeConfigs(data.claude?.configs).filter(config => config.enabled !== false),
+        codex: normalizeConfigs(data.codex?.configs).filter(config => config.enabled !== false),
+      }))
+      .catch(error => {
+        console.error('Failed to load configs for compare:', error);
+        feedback.showError(t('compare.error.load'));
+      });
+  }, []);
+
+  const handleServiceChange = (value: ServiceId) => {
+    setService(value);
+    setSelected([]);
+    setResults([]);
+  };
+
+  const toggleConfig = (name: string) => {
+    setSelected(prev => {
+      if (prev.includes(name)) {
+        return prev.filter(entry => entry !== name);
+      }
+      return prev.length < MAX_CONFIGS ? [...prev, name] : prev;
+    });
+  };
+
+  const handleRun = async () => {
+    setRunning(true);
+    try {
+      const response = await api.fanout(service, selected, buildBody(service, model, prompt));
+      setResults(response.results);
+    } catch (error) {
+      console.error('Fan-out failed:', error);
+      feedback.showError(t('compare.error.run'));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  return (
+    <Card>
+      <CardHeader>
+        <div className="flex items-center justify-between">
+          <div>
+            <CardTitle>{t('compare.title')}</CardTitle>
+            <CardDescription>{t('compare.description')}</CardDescription>
+          </div>
+          <Button onClick={handleRun} disabled={running || selected.length === 0 || !prompt.trim()}>
+            <Play className="mr-2 h-4 w-4" />
+            {running ? t('compare.running') : t('compare.run')}
+          </Button>
+        </div>
+      </CardHeader>
+      <CardContent>
+        <div className="space-y-6">
+          <div className="grid gap-4 md:grid-cols-2">
+            <div className="grid gap-2">
+              <Label>{t('common.service')}</Label>
+              <Select value={service} onValueChange={value => handleServiceChange(value as ServiceId)}>
+                <SelectTrigger>
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="claude">{t('service.claude.name')}</SelectItem>
+                  <SelectItem value="codex">{t('service.codex.name')}</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="compare_model">{t('compare.model')}</Label>
+              <Input
+                id="compare_model"
+                value={model}
+                onChange={e => setModel(e.target.value)}
+                placeholder={t('compare.modelHint')}
+              />
+            </div>
+          </div>
+
+          <div className="grid gap-2">
+            <Label>{t('compare.configs', { max: MAX_CONFIGS })}</Label>
+            {configs[service].length === 0 ? (
+              <p className="text-sm text-muted-foreground">
+                {t('dashboard.noConfigs', { service: t(`service.${service}.name`) })}
+              </p>
+            ) : (
+              <div className="flex flex-wrap gap-2">
+                {configs[service].map(config => (
+                  <Button
+                    key={config.name}
+                    size="sm"
+                    variant={selected.includes(config.name) ? 'default' : 'outline'}
+                    onClick={() => toggleConfig(config.name)}
+                  >
+                    {config.name}
+                  </Button>
+                ))}
+              </div>
+            )}
+            <p className="text-xs text-muted-foreground">{t('compare.overrideHint')}</p>
+          </div>
+
+          <div className="grid gap-2">
+            <Label htmlFor="compare_prompt">{t('compare.prompt')}</Label>
+            <textarea
+              id="compare_prompt"
+              value={prompt}
+              onChange={e => setPrompt(e.target.value)}
+              rows={4}
+              className="flex w-full rounded-md border border-input bg-background px-3 py-2 text-sm ring-offset-background placeholder:text-muted-foreground focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-ring focus-visible:ring-offset-2"
+            />
+          </div>
+
+          {results.length > 0 && (
+            <div className="grid gap-4 md:grid-cols-2 xl:grid-cols-3">
+              {results.map(result => (
+                <div key={result.config} className="flex flex-col gap-3 rounded-lg border p-4">
+                  <div className="flex flex-wrap items-center gap-2">
+                    <Badge>{result.config}</Badge>
+                    <Badge variant={result.status >= 200 && result.status < 300 ? 'secondary' : 'destructive'}>
+                      {result.status}
+                    </Badge>
+                    <span className="text-xs text-muted-foreground">{result.duration_ms} ms</span>
+                  </div>
+                  <p className="text-xs text-muted-foreground">
+                    {t('compare.usage', {
+                      model: result.usage.model ?? '-',
+                      input: result.usage.inputTokens ?? 0,
+                      output: result.usage.outputTokens ?? 0,
+                    })}
+                  </p>
+                  <pre className="max-h-96 overflow-auto whitespace-pre-wrap break-words rounded-md bg-muted/40 p-3 text-sm">
+                    {answerText(result)}
+                  </pre>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
@@ -33,7 +33,7 @@ import {
   AlertDialogTitle,
 } from '@/components/ui/alert-dialog';
 import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from '@/components/ui/table';
-import { Plus, Edit, Trash2, Key, Shield, ShieldCheck, Eye, EyeOff, CircleOff, Power } from 'lucide-react';
+import { Plus, Edit, Trash2, Key, Shield, ShieldCheck, Eye, EyeOff, CircleOff, Power, FlaskConical, Wallet } from 'lucide-react';
 import { useTranslation } from '@/hooks/useTranslation';
 import { useFeedback } from '@/components/FeedbackProvider';
 
@@ -234,6 +234,7 @@ type ConfigFormState = {
   api_key: string;
   auth_token: string;
   weight: number;
+  anthropic_beta: string; // Comma-separated
 };
 
 export function ConfigPanel() {
@@ -254,6 +255,7 @@ export function ConfigPanel() {
     api_key: '',
     auth_token: '',
     weight: 1,
+    anthropic_beta: '',
   });
   const [showApiKey, setShowApiKey] = useState(false);
   const [showAuthToken, setShowAuthToken] = useState(false);
@@ -334,20 +336,22 @@ export function ConfigPanel() {
     setEditingConfig(null);
     setEditingService(service);
     setAuthType('auth_token');
-    setFormData({ name: '', base_url: '', api_key: '', auth_token: '', weight: 1 });
+    setFormData({ name: '', base_url: '', api_key: '', auth_token: '', weight: 1, anthropic_beta: '' });
     setDialogOpen(true);
   };
 
   const handleEdit = (service: ServiceId, config: ServiceConfig) => {
     setEditingConfig(config);
     setEditingService(service);
-    setAuthType(config.api_key ? 'api_key' : 'auth_token');
+    setAuthType(config.has_api_key ? 'api_key' : 'auth_token');
+    // Stored credentials are never sent to the browser; blank fields keep the saved value.
     setFormData({
       name: config.name,
       base_url: config.base_url,
-      api_key: config.api_key || '',
-      auth_token: config.auth_token || '',
+      api_key: '',
+      auth_token: '',
       weight: config.weight,
+      anthropic_beta: (config.anthropic_beta ?? []).join(', '),
     });
     setDialogOpen(true);
   };
@@ -363,7 +367,10 @@ export function ConfigPanel() {
     }
 
     const authValue = authType === 'api_key' ? formData.api_key : formData.auth_token;
-    if (!authValue.trim()) {
+    const keepsStoredSecret =
+      Boolean(editingConfig) &&
+      (authType === 'api_key' ? editingConfig?.has_api_key : editingConfig?.has_auth_token);
+    if (!authValue.trim() && !keepsStoredSecret) {
       feedback.showInfo(
         authType === 'api_key' ? t('config.validation.apiKey') : t('config.validation.authToken'),
       );
@@ -371,13 +378,26 @@ export function ConfigPanel() {
     }
 
     try {
+      const credentials = !authValue.trim()
+        ? {}
+        : authType === 'api_key'
+          ? { api_key: formData.api_key, auth_token: undefined }
+          : { auth_token: formData.auth_token, api_key: undefined };
+      const betas =
+        editingService === 'claude'
+          ? {
+              anthropic_beta: formData.anthropic_beta
+                .split(',')
+                .map((value) => value.trim())
+                .filter(Boolean),
+            }
+          : {};
       const configData = {
         name: formData.name,
         base_url: formData.base_url,
         weight: formData.weight,
-        ...(authType === 'api_key'
-          ? { api_key: formData.api_key, auth_token: undefined }
-          : { auth_token: formData.auth_token, api_key: undefined }),
+        ...credentials,
+        ...betas,
       };
 
       if (editingConfig) {
@@ -432,11 +452,7 @@ export function ConfigPanel() {
     enabled: boolean,
   ) => {
     try {
-      if (service === 'claude') {
-        await api.updateClaudeConfig(config.name, { enabled });
-      } else {
-        await api.updateCodexConfig(config.name, { enabled });
-      }
+      await api.patchConfig(service, config.name, { enabled });
 
       await loadConfigs();
     } catch (error) {
@@ -584,16 +600,32 @@ export function ConfigPanel() {
                 <TableCell>
                   <div className="flex items-center gap-2">
                     <span className="font-medium">{config.name}</span>
-                    {config.api_key && (
-                      <span title={t('common.apiKey')}>
+                    {config.has_api_key && (
+                      <span title={`${t('common.apiKey')} ${config.api_key_hint ?? ''}`.trim()}>
                         <Key className="h-3 w-3 text-muted-foreground" />
                       </span>
                     )}
-                    {config.auth_token && (
-                      <span title={t('common.authToken')}>
+                    {config.has_auth_token && (
+                      <span title={`${t('common.authToken')} ${config.auth_token_hint ?? ''}`.trim()}>
                         <Shield className="h-3 w-3 text-muted-foreground" />
                       </span>
                     )}
+                    {'anthropic_beta' in config && config.anthropic_beta && config.anthropic_beta.length > 0 && (
+                      <span title={`${t('config.anthropicBeta')}: ${config.anthropic_beta.join(', ')}`}>
+                        <FlaskConical className="h-3 w-3 text-muted-foreground" />
+                      </span>
+                    )}
+                    {config.quota_status && config.quota_status.remaining !== null && (
+                      <span
+                        className={`flex items-center gap-1 text-xs ${config.quota_status.low ? 'text-destructive' : 'text-muted-foreground'}`}
+                        title={config.quota_status.error
+                          ? `${t('config.quota')}: ${config.quota_status.error}`
+                          : config.quota_status.low ? t('config.quotaLow') : t('config.quota')}
+                      >
+                        <Wallet className="h-3 w-3" />
+                        {config.quota_status.remaining.toFixed(2)} {config.quota_status.unit}
+                      </span>
+                    )}
                   </div>
                 </TableCell>
                 <TableCell className="w-[10rem]">
@@ -834,7 +866,8 @@ export function ConfigPanel() {
                         type={showApiKey ? "text" : "password"}
                         value={formData.api_key}
                         onChange={(e) => setFormData({ ...formData, api_key: e.target.value })}
-                        required
+                        placeholder={editingConfig?.api_key_hint}
+                        required={!editingConfig?.has_api_key}
                         className="pr-10"
                       />
                       <Button
@@ -853,6 +886,7 @@ export function ConfigPanel() {
                     </div>
                     <p className="text-xs text-muted-foreground">
                       {t('config.form.apiKeyHint')}
+                      {editingConfig?.has_api_key && ` ${t('config.form.keepSecretHint')}`}
                     </p>
                   </div>
                 ) : (
@@ -866,7 +900,8 @@ export function ConfigPanel() {
                         type={showAuthToken ? "text" : "password"}
                         value={formData.auth_token}
                         onChange={(e) => setFormData({ ...formData, auth_token: e.target.value })}
-                        required
+                        placeholder={editingConfig?.auth_token_hint}
+                        required={!editingConfig?.has_auth_token}
                         className="pr-10"
                       />
                       <Button
@@ -885,6 +920,7 @@ export function ConfigPanel() {
                     </div>
                     <p className="text-xs text-muted-foreground">
                       {t('config.form.authTokenHint')}
+                      {editingConfig?.has_auth_token && ` ${t('config.form.keepSecretHint')}`}
                     </p>
                   </div>
                 )}
@@ -906,6 +942,21 @@ export function ConfigPanel() {
                   {t('config.form.weightHint')}
                 </p>
               </div>
+              {editingService === 'claude' && (
+                <div className="grid gap-2">
+                  <Label htmlFor="anthropic_beta">{t('config.anthropicBeta')}</Label>
+                  <Input
+                    id="anthropic_beta"
+                    value={formData.anthropic_beta}
+                    onChange={(e) => setFormData({ ...formData, anthropic_beta: e.target.value })}
+                    placeholder="prompt-caching-2024-07-31, context-1m-2025-08-07"
+                    className="font-mono text-sm"
+                  />
+                  <p className="text-xs text-muted-foreground">
+                    {t('config.form.anthropicBetaHint')}
+                  </p>
+                </div>
+              )}
             </div>
             <DialogFooter>
               <Button variant="outline" onClick={() => setDialogOpen(false)}>
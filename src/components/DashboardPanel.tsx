@@ -1,4 +1,4 @@
-import { useCallback, useEffect, useState } from 'react';
+import { useCallback, useEffect, useRef, useState } from 'react';
 import { api } from '@/services/api';
 import type { ServiceConfig, ServiceId } from '@/types/common';
 import {
@@ -21,6 +21,8 @@ import {
 import { Link, RefreshCw } from 'lucide-react';
 import { useTranslation } from '@/hooks/useTranslation';
 import { useFeedback } from '@/components/FeedbackProvider';
+import { useRealtime } from '@/hooks/useRealtime';
+import type { RealtimeEvent } from '@/types/realtime';
 
 interface DashboardService {
   id: ServiceId;
@@ -40,6 +42,9 @@ interface DashboardData {
   services: Record<ServiceId, DashboardServiceState>;
 }
 
+// Request bursts are coalesced into one refresh
+const REALTIME_REFRESH_DELAY_MS = 1000;
+
 const SERVICES: DashboardService[] = [
   {
     id: 'claude',
@@ -76,10 +81,10 @@ function getAuthLabelKey(config: ServiceConfig | undefined): string {
   if (!config) {
     return 'common.notConfigured';
   }
-  if (config.api_key) {
+  if (config.has_api_key) {
     return 'common.apiKey';
   }
-  if (config.auth_token) {
+  if (config.has_auth_token) {
     return 'common.authToken';
   }
   return 'common.notConfigured';
@@ -141,9 +146,12 @@ export function DashboardPanel() {
     codex: false,
   });
 
-  const loadDashboardData = useCallback(async () => {
+  // Quiet refreshes (realtime updates, fallback polling) keep the current view instead of showing placeholders
+  const loadDashboardData = useCallback(async (quiet = false) => {
     try {
-      setLoading(true);
+      if (!quiet) {
+        setLoading(true);
+      }
       setHasError(false);
 
       const separatedConfigs = await api.listSeparatedConfigs();
@@ -168,7 +176,9 @@ export function DashboardPanel() {
       console.error('Failed to load dashboard data:', err);
       setHasError(true);
     } finally {
-      setLoading(false);
+      if (!quiet) {
+        setLoading(false);
+      }
     }
   }, []);
 
@@ -176,28 +186,56 @@ export function DashboardPanel() {
     loadDashboardData();
   }, [loadDashboardData]);
 
-  // Auto-refresh for load balance mode
+  // Settings changes and, under load balancing, each finished request may move the forwarding config
+  const refreshTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
+  const handleRealtimeEvent = (event: RealtimeEvent) => {
+    if (event.historical) {
+      return;
+    }
+    const service = data.services[event.service as ServiceId];
+    const relevant =
+      event.type === 'settings_changed' || (event.type === 'request_completed' && service?.mode === 'load_balance');
+    if (relevant && !refreshTimer.current) {
+      refreshTimer.current = setTimeout(() => {
+        refreshTimer.current = null;
+        loadDashboardData(true);
+      }, REALTIME_REFRESH_DELAY_MS);
+    }
+  };
+  const realtimeStatus = useRealtime(['claude', 'codex'], handleRealtimeEvent);
+
+  useEffect(() => () => {
+    if (refreshTimer.current) {
+      clearTimeout(refreshTimer.current);
+    }
+  }, []);
+
+  // Catch up on whatever changed while the stream was down (e.g. across a restart)
+  const everOpened = useRef(false);
   useEffect(() => {
-    let interval: NodeJS.Timeout | null = null;
+    if (realtimeStatus !== 'open') {
+      return;
+    }
+    if (everOpened.current) {
+      loadDashboardData(true);
+    }
+    everOpened.current = true;
+  }, [realtimeStatus, loadDashboardData]);
 
-    // Check if any service is in load_balance mode
+  // Without the realtime stream, fall back to polling while load balancing
+  useEffect(() => {
     const hasLoadBalanceMode = Object.values(data.services).some(
       service => service.mode === 'load_balance'
     );
-
-    if (hasLoadBalanceMode) {
-      // Auto-refresh every 5 seconds for load balance mode
-      interval = setInterval(() => {
-        loadDashboardData();
-      }, 5000);
+    if (realtimeStatus === 'open' || !hasLoadBalanceMode) {
+      return;
     }
 
-    return () => {
-      if (interval) {
-        clearInterval(interval);
-      }
-    };
-  }, [data.services, loadDashboardData]);
+    const interval = setInterval(() => {
+      loadDashboardData(true);
+    }, 5000);
+    return () => clearInterval(interval);
+  }, [data.services, realtimeStatus, loadDashboardData]);
 
   const handleModeChange = async (service: ServiceId, newMode: 'manual' | 'load_balance') => {
     try {
@@ -240,7 +278,7 @@ export function DashboardPanel() {
           <CardTitle>{t('dashboard.title')}</CardTitle>
           <CardDescription>{t('dashboard.subtitle')}</CardDescription>
         </div>
-        <Button variant="outline" size="sm" onClick={loadDashboardData} disabled={loading}>
+        <Button variant="outline" size="sm" onClick={() => loadDashboardData()} disabled={loading}>
           <RefreshCw className={`mr-2 h-4 w-4 ${loading ? 'animate-spin' : ''}`} />
           {t('dashboard.refresh')}
         </Button>
@@ -405,7 +443,7 @@ export function DashboardPanel() {
                               <p className="mt-1 font-mono text-sm break-all">{displayConfig.base_url}</p>
                             </div>
                             <p className="mt-2 text-xs text-muted-foreground">
-                              {t('dashboard.autoRefresh')}
+                              {t(realtimeStatus === 'open' ? 'dashboard.liveUpdates' : 'dashboard.autoRefresh')}
                             </p>
                           </div>
                         </div>
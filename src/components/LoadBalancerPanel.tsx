@@ -4,11 +4,20 @@ import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/com
 import { Button } from '@/components/ui/button';
 import { Input } from '@/components/ui/input';
 import { Label } from '@/components/ui/label';
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from '@/components/ui/select';
 import { Save } from 'lucide-react';
 import { useTranslation } from '@/hooks/useTranslation';
 import { useFeedback } from '@/components/FeedbackProvider';
 import { DEFAULT_LOAD_BALANCER_CONFIG, type LoadBalancerConfig } from '@/types/loadbalancer';
 
+const STRATEGIES: LoadBalancerConfig['strategy'][] = ['weighted', 'round-robin'];
+
 export function LoadBalancerPanel() {
   const { t } = useTranslation();
   const feedback = useFeedback();
@@ -18,7 +27,7 @@ export function LoadBalancerPanel() {
     try {
       const data = await api.getLoadBalancerConfig();
       setConfig({
-        strategy: 'weighted',
+        strategy: STRATEGIES.includes(data.strategy) ? data.strategy : DEFAULT_LOAD_BALANCER_CONFIG.strategy,
         healthCheck: {
           ...DEFAULT_LOAD_BALANCER_CONFIG.healthCheck,
           ...data.healthCheck,
@@ -35,12 +44,22 @@ export function LoadBalancerPanel() {
     loadConfig();
   }, []);
 
+  // Applied at once through the focused mode endpoint; the other fields wait for Save
+  const handleStrategyChange = async (strategy: LoadBalancerConfig['strategy']) => {
+    const previous = config.strategy;
+    setConfig(prev => ({ ...prev, strategy }));
+    try {
+      await api.updateLoadBalancerMode(strategy);
+    } catch (error) {
+      console.error('Failed to switch load balancer strategy:', error);
+      setConfig(prev => ({ ...prev, strategy: previous }));
+      feedback.showError(t('loadbalancer.error.strategy'));
+    }
+  };
+
   const handleSave = async () => {
     try {
-      await api.updateLoadBalancerConfig({
-        ...config,
-        strategy: 'weighted',
-      });
+      await api.updateLoadBalancerConfig(config);
     } catch (error) {
       console.error('Failed to save load balancer config:', error);
       feedback.showError(t('loadbalancer.error.save'));
@@ -63,6 +82,28 @@ export function LoadBalancerPanel() {
       </CardHeader>
       <CardContent>
         <div className="space-y-6">
+          <div className="grid gap-2">
+            <Label>{t('lb.strategy')}</Label>
+            <Select
+              value={config.strategy}
+              onValueChange={value => handleStrategyChange(value as LoadBalancerConfig['strategy'])}
+            >
+              <SelectTrigger>
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                {STRATEGIES.map(strategy => (
+                  <SelectItem key={strategy} value={strategy}>
+                    {t(`lb.strategy.${strategy}`)}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+            <p className="text-xs text-muted-foreground">
+              {t('lb.strategyHint')}
+            </p>
+          </div>
+
           <div className="grid gap-2">
             <Label htmlFor="health_check_interval">{t('lb.healthInterval')}</Label>
             <Input
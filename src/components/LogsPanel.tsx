@@ -26,8 +26,35 @@ import { Tabs, TabsContent, TabsList, TabsTrigger } from '@/components/ui/tabs';
 import { RefreshCw, Eye, Trash2 } from 'lucide-react';
 import { useTranslation } from '@/hooks/useTranslation';
 import { useFeedback } from '@/components/FeedbackProvider';
+import { useRealtime } from '@/hooks/useRealtime';
+import type { RealtimeEvent } from '@/types/realtime';
 
-type ServiceTab = 'claude' | 'codex';
+type ServiceTab = 'all' | 'claude' | 'codex';
+
+const LOG_LIMIT = 50;
+
+/**
+ * Table row for a request_completed event; details are fetched by id like any other row
+ */
+function logFromEvent(event: RealtimeEvent): RequestLog | null {
+  const data = event.data;
+  if (event.type !== 'request_completed' || typeof data.id !== 'string') {
+    return null;
+  }
+  const durationMs = typeof data.duration_ms === 'number' ? data.duration_ms : 0;
+  return {
+    id: data.id,
+    // The event is stamped when the request finished; rows are ordered by when it started
+    timestamp: new Date(event.timestamp - durationMs).toISOString(),
+    service: String(event.service),
+    method: String(data.method ?? ''),
+    path: String(data.path ?? ''),
+    status_code: typeof data.status_code === 'number' ? data.status_code : 0,
+    duration_ms: durationMs,
+    channel: typeof data.channel === 'string' ? data.channel : undefined,
+    error_message: typeof data.error_message === 'string' ? data.error_message : undefined,
+  };
+}
 
 export function LogsPanel() {
   const { t } = useTranslation();
@@ -38,12 +65,12 @@ export function LogsPanel() {
   const [dialogOpen, setDialogOpen] = useState(false);
   const [clearDialogOpen, setClearDialogOpen] = useState(false);
   const [clearing, setClearing] = useState(false);
-  const [activeService, setActiveService] = useState<ServiceTab>('claude');
+  const [activeService, setActiveService] = useState<ServiceTab>('all');
 
   const loadLogs = async () => {
     setLoading(true);
     try {
-      const data = await api.getLogs(50, 0);
+      const data = await api.getLogs(LOG_LIMIT, 0);
       setLogs(data);
     } catch (error) {
       console.error('Failed to load logs:', error);
@@ -57,6 +84,22 @@ export function LogsPanel() {
     loadLogs();
   }, []);
 
+  // New requests, and on (re)connect the ones replayed from the server's window, join the table live
+  const realtimeStatus = useRealtime(['claude', 'codex'], event => {
+    const entry = logFromEvent(event);
+    if (!entry) {
+      return;
+    }
+    setLogs(prev => {
+      if (prev.some(log => log.id === entry.id)) {
+        return prev;
+      }
+      return [entry, ...prev]
+        .sort((a, b) => new Date(b.timestamp).getTime() - new Date(a.timestamp).getTime())
+        .slice(0, LOG_LIMIT);
+    });
+  });
+
   const handleViewDetails = async (id: string) => {
     try {
       const log = await api.getLogById(id);
@@ -92,10 +135,11 @@ export function LogsPanel() {
     }
   };
 
-  const serviceTabs: ServiceTab[] = ['claude', 'codex'];
+  const serviceTabs: ServiceTab[] = ['all', 'claude', 'codex'];
 
   const logsByService = useMemo(() => {
     const groups: Record<ServiceTab, RequestLog[]> = {
+      all: logs,
       claude: [],
       codex: [],
     };
@@ -112,7 +156,7 @@ export function LogsPanel() {
     return groups;
   }, [logs]);
 
-  const renderLogsTable = (entries: RequestLog[]) => (
+  const renderLogsTable = (entries: RequestLog[], showService: boolean) => (
     <Table>
       <TableHeader>
         <TableRow>
@@ -136,7 +180,16 @@ export function LogsPanel() {
           entries.map((log) => (
             <TableRow key={log.id}>
               <TableCell>{new Date(log.timestamp).toLocaleString()}</TableCell>
-              <TableCell>{log.channel || log.service}</TableCell>
+              <TableCell>
+                {showService ? (
+                  <div className="flex items-center gap-2">
+                    <Badge variant="outline">{log.service || 'claude'}</Badge>
+                    {log.channel}
+                  </div>
+                ) : (
+                  log.channel || log.service
+                )}
+              </TableCell>
               <TableCell className="font-mono text-sm">{log.method}</TableCell>
               <TableCell className="font-mono text-sm">
                 {log.target_url ?? log.path}
@@ -161,7 +214,9 @@ export function LogsPanel() {
         <div className="flex items-center justify-between">
           <div>
             <CardTitle>{t('logs.title')}</CardTitle>
-            <CardDescription>{t('logs.description')}</CardDescription>
+            <CardDescription>
+              {t(realtimeStatus === 'open' ? 'logs.descriptionLive' : 'logs.description')}
+            </CardDescription>
           </div>
           <div className="flex gap-2">
             <Button variant="outline" onClick={loadLogs} disabled={loading}>
@@ -188,13 +243,13 @@ export function LogsPanel() {
           <TabsList className="mb-4">
             {serviceTabs.map((service) => (
               <TabsTrigger key={service} value={service}>
-                {service === 'claude' ? t('service.claude.name') : t('service.codex.name')}
+                {service === 'all' ? t('logs.allServices') : t(`service.${service}.name`)}
               </TabsTrigger>
             ))}
           </TabsList>
           {serviceTabs.map((service) => (
             <TabsContent key={service} value={service} className="mt-0">
-              {renderLogsTable(logsByService[service])}
+              {renderLogsTable(logsByService[service], service === 'all')}
             </TabsContent>
           ))}
         </Tabs>
@@ -246,6 +301,17 @@ export function LogsPanel() {
                       <p className="text-sm font-medium">{t('common.duration')}</p>
                       <p className="text-sm text-muted-foreground">{selectedLog.duration_ms}ms</p>
                     </div>
+                    {selectedLog.timings && (
+                      <div className="col-span-2">
+                        <p className="text-sm font-medium">{t('logs.timings.title')}</p>
+                        <p className="text-sm text-muted-foreground font-mono">
+                          {(['proxy_ms', 'headers_ms', 'first_byte_ms', 'body_ms'] as const)
+                            .filter(phase => selectedLog.timings?.[phase] !== undefined)
+                            .map(phase => `${t(`logs.timings.${phase}`)} ${selectedLog.timings?.[phase]}ms`)
+                            .join(' · ')}
+                        </p>
+                      </div>
+                    )}
                     {selectedLog.target_url && (
                       <div className="col-span-2">
                         <p className="text-sm font-medium">{t('common.targetUrl')}</p>
@@ -264,6 +330,18 @@ export function LogsPanel() {
                 </TabsContent>
 
                 <TabsContent value="request" className="space-y-4">
+                  {selectedLog.tags && (
+                    <div>
+                      <p className="text-sm font-medium">{t('common.tags')}</p>
+                      <div className="mt-2 flex flex-wrap gap-2">
+                        {Object.entries(selectedLog.tags).map(([key, value]) => (
+                          <Badge key={key} variant="secondary" className="font-mono text-xs">
+                            {key}={value}
+                          </Badge>
+                        ))}
+                      </div>
+                    </div>
+                  )}
                   <div>
                     <p className="text-sm font-medium">{t('common.headers')}</p>
                     <pre className="mt-2 p-2 bg-muted rounded text-xs whitespace-pre-wrap break-words">
@@ -316,6 +394,12 @@ export function LogsPanel() {
                         <p className="text-sm font-medium">{t('common.completionTokens')}</p>
                         <p className="text-sm text-muted-foreground">{selectedLog.usage.completion_tokens}</p>
                       </div>
+                      {selectedLog.usage.thinking_tokens !== undefined && (
+                        <div>
+                          <p className="text-sm font-medium">{t('common.thinkingTokens')}</p>
+                          <p className="text-sm text-muted-foreground">{selectedLog.usage.thinking_tokens}</p>
+                        </div>
+                      )}
                     </div>
                   </TabsContent>
                 )}
@@ -0,0 +1,23 @@
+import { useEffect, useRef, useState } from 'react';
+import { subscribeRealtime } from '@/services/realtime';
+import type { ServiceId } from '@/types/common';
+import type { RealtimeEvent, RealtimeStatus } from '@/types/realtime';
+
+/**
+ * Subscribe to realtime events for the component's lifetime; returns the connection status
+ */
+export function useRealtime(services: ServiceId[], onEvent: (event: RealtimeEvent) => void): RealtimeStatus {
+  const [status, setStatus] = useState<RealtimeStatus>('connecting');
+  const handler = useRef(onEvent);
+  handler.current = onEvent;
+  const key = services.join(',');
+
+  useEffect(() => {
+    return subscribeRealtime(key.split(',') as ServiceId[], {
+      onEvent: event => handler.current(event),
+      onStatus: setStatus,
+    });
+  }, [key]);
+
+  return status;
+}
@@ -8,6 +8,7 @@ import type {
   TestConnectionResponse,
   ClaudeSetupResponse,
   ServiceId,
+  FanoutResponse,
 } from '@/types/common';
 import type { LoadBalancerConfig } from '@/types/loadbalancer';
 import { DEFAULT_LOAD_BALANCER_CONFIG } from '@/types/loadbalancer';
@@ -151,6 +152,18 @@ export const api = {
     );
   },
 
+  // Update weight/base_url/enabled without resending credentials
+  async patchConfig(
+    service: ServiceId,
+    name: string,
+    fields: Partial<Pick<ServiceConfig, 'base_url' | 'weight' | 'enabled'>>,
+  ): Promise<void> {
+    await fetchJSON(`${API_BASE}/configs/${service}/${encodeURIComponent(name)}`, {
+      method: 'PATCH',
+      body: JSON.stringify(fields),
+    });
+  },
+
   // Fetch all separated configurations
   async listSeparatedConfigs(): Promise<SeparatedConfigResponse> {
     return fetchJSON<SeparatedConfigResponse>(`${API_BASE}/configs/separated`);
@@ -180,6 +193,29 @@ export const api = {
     });
   },
 
+  async updateLoadBalancerMode(
+    mode: LoadBalancerConfig['strategy'],
+    service: ServiceId = 'claude',
+  ): Promise<void> {
+    await fetchJSON(`${API_BASE}/loadbalancer/${service}/mode`, {
+      method: 'POST',
+      body: JSON.stringify({ mode }),
+    });
+  },
+
+  // Compare: the same request body sent to several configs at once
+  async fanout(
+    service: ServiceId,
+    configs: string[],
+    body: Record<string, unknown>,
+    path?: string
+  ): Promise<FanoutResponse> {
+    return fetchJSON<FanoutResponse>(`${API_BASE}/fanout/${service}`, {
+      method: 'POST',
+      body: JSON.stringify({ configs, body, path }),
+    });
+  },
+
   // Logs
   async getLogs(limit = 50, offset = 0): Promise<RequestLog[]> {
     const response = await fetchJSON<{ logs: RequestLog[] }>(`${API_BASE}/logs?limit=${limit}&offset=${offset}`);
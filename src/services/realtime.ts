@@ -0,0 +1,98 @@
+import type { ServiceId } from '@/types/common';
+import type { RealtimeEvent, RealtimeStatus } from '@/types/realtime';
+
+export interface RealtimeHandlers {
+  onEvent: (event: RealtimeEvent) => void;
+  onStatus?: (status: RealtimeStatus) => void;
+}
+
+const INITIAL_RETRY_MS = 1_000;
+const MAX_RETRY_MS = 30_000;
+// Close code the server uses when an operator disconnects a client; don't come straight back
+const OPERATOR_CLOSE_CODE = 4000;
+// Event ids remembered for dropping replays of what was already delivered
+const MAX_SEEN_EVENTS = 1_000;
+
+function realtimeUrl(services: ServiceId[], since: number | null): string {
+  const { protocol, host } = window.location;
+  const scheme = protocol === 'https:' ? 'wss:' : 'ws:';
+  const resume = since === null ? '' : `&since=${since}`;
+  return `${scheme}//${host}/ws/realtime?services=${services.join(',')}${resume}`;
+}
+
+function eventKey(event: RealtimeEvent): string | null {
+  const id = event.data?.id;
+  return typeof id === 'string' ? `${event.service}:${event.type}:${id}` : null;
+}
+
+/**
+ * Follow /ws/realtime for the given services, reconnecting with backoff until the returned
+ * function is called. Reconnects resume from the last event seen, so the server replays only what
+ * was missed; the boundary event it sends again is dropped here.
+ */
+export function subscribeRealtime(services: ServiceId[], handlers: RealtimeHandlers): () => void {
+  let socket: WebSocket | null = null;
+  let retryTimer: ReturnType<typeof setTimeout> | null = null;
+  let retryMs = INITIAL_RETRY_MS;
+  let stopped = false;
+  let lastTimestamp: number | null = null;
+  const seen = new Set<string>();
+
+  const deliver = (event: RealtimeEvent) => {
+    const key = eventKey(event);
+    if (key) {
+      if (seen.has(key)) {
+        return;
+      }
+      seen.add(key);
+      if (seen.size > MAX_SEEN_EVENTS) {
+        seen.delete(seen.values().next().value as string);
+      }
+    }
+    if (lastTimestamp === null || event.timestamp > lastTimestamp) {
+      lastTimestamp = event.timestamp;
+    }
+    handlers.onEvent(event);
+  };
+
+  const connect = () => {
+    handlers.onStatus?.('connecting');
+    const ws = new WebSocket(realtimeUrl(services, lastTimestamp));
+    socket = ws;
+
+    ws.onopen = () => {
+      retryMs = INITIAL_RETRY_MS;
+      handlers.onStatus?.('open');
+    };
+
+    ws.onmessage = message => {
+      try {
+        deliver(JSON.parse(String(message.data)) as RealtimeEvent);
+      } catch (error) {
+        console.error('Ignoring malformed realtime event:', error);
+      }
+    };
+
+    ws.onclose = close => {
+      socket = null;
+      if (stopped) {
+        return;
+      }
+      handlers.onStatus?.('closed');
+      const delay = close.code === OPERATOR_CLOSE_CODE ? MAX_RETRY_MS : retryMs;
+      retryMs = Math.min(retryMs * 2, MAX_RETRY_MS);
+      // Jitter keeps every open dashboard from reconnecting at the same instant after a restart
+      retryTimer = setTimeout(connect, delay + Math.random() * 500);
+    };
+  };
+
+  connect();
+
+  return () => {
+    stopped = true;
+    if (retryTimer) {
+      clearTimeout(retryTimer);
+    }
+    socket?.close();
+  };
+}
@@ -15,6 +15,48 @@ export interface ServiceConfig {
   weight: number;
   enabled?: boolean;
   freeze_until?: number;
+  has_api_key?: boolean;   // Credentials are never returned; these flag presence instead
+  has_auth_token?: boolean;
+  api_key_hint?: string;   // Masked suffix, e.g. ••••abcd
+  auth_token_hint?: string;
+  fingerprint?: {
+    mode: 'passthrough' | 'override' | 'strip';
+    user_agent?: string;
+    stainless?: Record<string, string>;
+  };
+  anthropic_beta?: string[];
+  context_trim?: {
+    limit: number;
+    strategy: 'drop' | 'summarize';
+    summary_model?: string;
+  };
+  response_language?: string;
+  provider_profile?: string;
+  quirks?: {
+    strip_fields?: string[];
+    content_blocks?: string[];
+    tool_choice?: 'keep' | 'basic';
+    max_tokens_field?: 'max_tokens' | 'max_completion_tokens';
+  };
+  upstream_compression?: boolean;
+  prompt_cache?: {
+    min_tokens: number;
+    ttl?: '1h';
+  };
+  availability?: {
+    timezone?: string;
+    windows: Array<{ days?: string[]; start: string; end: string }>;
+  };
+  api_format?: 'openai-chat' | 'anthropic';
+  request_compression?: {
+    min_bytes: number;
+  };
+  requests_per_minute?: number;
+  tokens_per_minute?: number;
+  proxy_url?: string; // Password masked when returned
+  model_map?: Record<string, string>; // Client model -> upstream model id
+  timeout_secs?: number;
+  connect_timeout_secs?: number;
 }
 
 export interface TestConnectionResponse {
@@ -31,6 +73,30 @@ export interface TestConnectionResponse {
 
 export interface RequestResultPayload extends TestConnectionResponse {}
 
+// One config's answer from a fan-out (POST /api/fanout/:service)
+export interface FanoutResult {
+  config: string;
+  status: number;
+  duration_ms: number;
+  request_id?: string;
+  usage: {
+    inputTokens?: number;
+    outputTokens?: number;
+    thinkingTokens?: number;
+    cacheReadTokens?: number;
+    cacheWriteTokens?: number;
+    model?: string;
+  };
+  response: unknown;
+  error?: string;
+}
+
+export interface FanoutResponse {
+  service: ServiceId;
+  path: string;
+  results: FanoutResult[];
+}
+
 // Claude-specific configuration
 export interface ClaudeConfig {
   name: string;
@@ -40,6 +106,21 @@ export interface ClaudeConfig {
   weight: number;          // Load-balancing weight
   enabled?: boolean;
   freeze_until?: number;
+  has_api_key?: boolean;
+  has_auth_token?: boolean;
+  api_key_hint?: string;
+  auth_token_hint?: string;
+  anthropic_beta?: string[]; // Merged into the anthropic-beta header of every forwarded request
+  quota_status?: QuotaStatus;
+}
+
+// Latest balance poll for a config with a quota setting
+export interface QuotaStatus {
+  remaining: number | null; // null when the provider reports no limit
+  unit: string;
+  low: boolean;
+  checkedAt: number;
+  error?: string;
 }
 
 // Codex-specific configuration
@@ -51,6 +132,11 @@ export interface CodexConfig {
   weight: number;          // Load-balancing weight
   enabled?: boolean;
   freeze_until?: number;
+  has_api_key?: boolean;
+  has_auth_token?: boolean;
+  api_key_hint?: string;
+  auth_token_hint?: string;
+  quota_status?: QuotaStatus;
 }
 
 // Response structure for separated configs
@@ -78,9 +164,17 @@ export interface ConfigListResponse<TConfig = ServiceConfig> {
   last_results?: Record<string, RequestResultPayload>;
 }
 
+export interface ServiceHealth {
+  state: 'ok' | 'degraded';
+  configs: number;
+  usable: string[];
+}
+
 export interface StatusResponse {
   status: string;
   timestamp: string;
+  readOnly?: boolean;
+  services?: Record<string, ServiceHealth>;
 }
 
 export interface ClaudeSetupResponse {
@@ -8,6 +8,11 @@ export interface LoadBalancerConfig {
     successThreshold: number;
   };
   freezeDuration: number;
+  retryBudget?: {
+    ratio: number;
+    windowMs: number;
+    minRetries: number;
+  };
 }
 
 export const DEFAULT_LOAD_BALANCER_CONFIG: LoadBalancerConfig = {
@@ -2,10 +2,14 @@ export interface UsageMetrics {
   prompt_tokens: number;
   completion_tokens: number;
   total_tokens: number;
+  thinking_tokens?: number;
+  cache_read_tokens?: number;
+  cache_write_tokens?: number;
   model: string;
 }
 
 export interface RequestLog {
+  v?: number;
   id: string;
   timestamp: string;
   service: string;
@@ -21,5 +25,35 @@ export interface RequestLog {
   response_headers?: Record<string, string>;
   request_body?: string;
   response_body?: string;
+  outcome?: string;
+  experiment_id?: string;
+  experiment_arm?: string;
+  upstream_id?: string;
+  conversation_id?: string;
+  client_identity?: string;
+  protocol_issues?: string[];
+  content_omitted?: boolean;
+  dlp_matches?: string[];
+  scrubbed_items?: number;
+  prompt_templates?: string[];
+  tags?: Record<string, string>;
+  timings?: {
+    proxy_ms?: number;
+    headers_ms?: number;
+    first_byte_ms?: number;
+    body_ms?: number;
+  };
   usage?: UsageMetrics;
+  request_bytes?: number;
+  response_bytes?: number;
+  request_compression?: {
+    uncompressed_bytes: number;
+    compressed_bytes: number;
+  };
+  model_override?: string;
+  internal_logs?: Array<{
+    level: 'warn' | 'error';
+    message: string;
+    timestamp: number;
+  }>;
 }
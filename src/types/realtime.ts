@@ -0,0 +1,23 @@
+import type { ServiceId } from './common';
+
+// Mirrors RealtimeEvent in server/protocol.ts
+export type RealtimeEventType =
+  | 'request_completed'
+  | 'settings_changed'
+  | 'webhook_received'
+  | 'alert_fired'
+  | 'memory_pressure'
+  | 'database_recovered'
+  | 'certificate_warning'
+  | 'quota_low';
+
+export interface RealtimeEvent {
+  v: number;
+  type: RealtimeEventType | (string & {});
+  service: ServiceId | (string & {});
+  timestamp: number;
+  data: Record<string, unknown>;
+  historical?: boolean; // Replayed from before the connection was opened
+}
+
+export type RealtimeStatus = 'connecting' | 'open' | 'closed';
@@ -0,0 +1,109 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { parseBodyLimit, readTextWithin } from '../server/proxy/bodyLimit';
+import { createTestHarness, type TestHarness } from '../server/testing';
+import { MAX_WEBHOOK_BODY_BYTES, readWebhookBody } from '../server/webhooks/signature';
+
+function streamed(chunks: string[], onCancel?: () => void): Request {
+  const encoder = new TextEncoder();
+  let index = 0;
+  const body = new ReadableStream<Uint8Array>({
+    pull(controller) {
+      if (index < chunks.length) {
+        controller.enqueue(encoder.encode(chunks[index++]));
+      } else {
+        controller.close();
+      }
+    },
+    cancel: onCancel,
+  });
+  return new Request('http://paf.test/v1/messages', { method: 'POST', body, duplex: 'half' } as RequestInit);
+}
+
+describe('parseBodyLimit', () => {
+  test('accepts positive numbers only', () => {
+    expect(parseBodyLimit(1024)).toBe(1024);
+    expect(parseBodyLimit(10.7)).toBe(10);
+    expect(parseBodyLimit(0)).toBeUndefined();
+    expect(parseBodyLimit(-1)).toBeUndefined();
+    expect(parseBodyLimit('1024')).toBeUndefined();
+    expect(parseBodyLimit(Infinity)).toBeUndefined();
+  });
+});
+
+describe('readTextWithin', () => {
+  test('reads a body within the limit', async () => {
+    expect(await readTextWithin(streamed(['hello ', 'world']), 11)).toBe('hello world');
+    expect(await readTextWithin(new Request('http://paf.test/'), 1)).toBe('');
+  });
+
+  test('cancels the upload once the limit is passed', async () => {
+    let cancelled = false;
+    const request = streamed(['12345', '67890', 'never read'], () => {
+      cancelled = true;
+    });
+
+    expect(await readTextWithin(request, 8)).toBeNull();
+    expect(cancelled).toBe(true);
+  });
+});
+
+describe('readWebhookBody', () => {
+  test('refuses declared and streamed bodies over the cap', async () => {
+    const declared = new Request('http://paf.test/api/webhooks/openai', {
+      method: 'POST',
+      headers: { 'content-length': String(MAX_WEBHOOK_BODY_BYTES + 1) },
+      body: '{}',
+    });
+    expect(await readWebhookBody(declared)).toBeNull();
+
+    const half = 'x'.repeat(MAX_WEBHOOK_BODY_BYTES / 2);
+    expect(await readWebhookBody(streamed([half, half, 'x']))).toBeNull();
+    expect(await readWebhookBody(streamed([half, half]))).toHaveLength(MAX_WEBHOOK_BODY_BYTES);
+  });
+});
+
+describe('max_request_body_bytes', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  const big = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'x'.repeat(500) }] };
+
+  test('refuses larger bodies with an Anthropic 413', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { maxRequestBodyBytes: 200 },
+      configs: [{ name: 'primary' }],
+    });
+
+    const declared = await harness.request('/v1/messages', {
+      headers: { 'content-length': String(JSON.stringify(big).length) },
+      body: big,
+    });
+    const chunked = await harness.request('/v1/messages', { body: big });
+
+    for (const response of [declared, chunked]) {
+      expect(response.status).toBe(413);
+      expect(await response.json()).toMatchObject({ type: 'error', error: { type: 'request_too_large' } });
+    }
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+
+    const small = await harness.request('/v1/messages', { body: { ...big, messages: [{ role: 'user', content: 'Hi' }] } });
+    expect(small.status).toBe(200);
+  });
+
+  test('uses the OpenAI error shape for codex', async () => {
+    harness = await createTestHarness({
+      service: 'codex',
+      serviceConfig: { maxRequestBodyBytes: 200 },
+      configs: [{ name: 'primary' }],
+    });
+
+    const response = await harness.request('/v1/responses', { body: { model: 'gpt-5', input: 'x'.repeat(500) } });
+
+    expect(response.status).toBe(413);
+    expect(await response.json()).toMatchObject({ error: { type: 'invalid_request_error', code: 'request_too_large' } });
+  });
+});
@@ -0,0 +1,174 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { ConfigChangeFeed } from '../server/config/changeFeed';
+import type { ProxyConfig, ServiceConfig } from '../server/config/types';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+function proxyConfig(name: string, weight = 1): ProxyConfig {
+  return { name, baseUrl: `https://${name}.example`, weight, enabled: true };
+}
+
+function service(configs: ProxyConfig[], mode: ServiceConfig['mode'] = 'manual'): ServiceConfig {
+  return {
+    configs,
+    active: configs[0]?.name ?? '',
+    mode,
+    loadBalancer: { strategy: 'weighted', healthCheck: { enabled: false, intervalMs: 30000, failureThreshold: 3, successThreshold: 2 } },
+  } as ServiceConfig;
+}
+
+function summary(changes: Array<{ service: string; config?: string; action: string }>) {
+  return changes.map(change => `${change.service}/${change.config ?? '*'} ${change.action}`);
+}
+
+describe('ConfigChangeFeed', () => {
+  test('lists everything as added without a cursor', () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a'), proxyConfig('b')]));
+
+    const { reset, changes } = feed.since();
+    expect(reset).toBe(true);
+    expect(summary(changes)).toEqual(['claude/* added', 'claude/a added', 'claude/b added']);
+  });
+
+  test('reports only what changed after the cursor', () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a'), proxyConfig('b')]));
+    const { cursor } = feed.since();
+
+    feed.record('claude', service([proxyConfig('a', 5), proxyConfig('c')]));
+
+    const next = feed.since(cursor);
+    expect(next.reset).toBe(false);
+    expect(summary(next.changes)).toEqual(['claude/a updated', 'claude/c added', 'claude/b removed']);
+    expect(feed.since(next.cursor).changes).toEqual([]);
+  });
+
+  test('leaves the revision alone when nothing changed', () => {
+    const feed = new ConfigChangeFeed();
+    const config = service([proxyConfig('a')]);
+    feed.record('claude', config);
+    const before = feed.getRevision();
+
+    feed.record('claude', service([proxyConfig('a')]));
+    expect(feed.getRevision()).toEqual(before);
+
+    // In-place edits are caught because snapshots are serialized
+    config.configs[0].weight = 3;
+    feed.record('claude', config);
+    expect(feed.getRevision().revision).toBe(before.revision + 1);
+  });
+
+  test('collapses repeated changes into one net action', () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+    const { cursor } = feed.since();
+
+    feed.record('claude', service([proxyConfig('a'), proxyConfig('b')]));
+    feed.record('claude', service([proxyConfig('a'), proxyConfig('b', 2)]));
+    feed.record('claude', service([proxyConfig('a', 2), proxyConfig('b', 2)]));
+    feed.record('claude', service([proxyConfig('b', 2)]));
+
+    // Dropping `a` also moved the active config, a service-level change
+    expect(summary(feed.since(cursor).changes)).toEqual(['claude/b added', 'claude/a removed', 'claude/* updated']);
+  });
+
+  test('reports service-level settings without a config name', () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+    const { cursor } = feed.since();
+
+    feed.record('claude', service([proxyConfig('a')], 'load_balance'));
+    expect(summary(feed.since(cursor).changes)).toEqual(['claude/* updated']);
+  });
+
+  test('resets foreign, malformed and future cursors', () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+    const epoch = feed.cursor().split('-')[0];
+
+    for (const cursor of ['nonsense', 'other-1', `${epoch}-99`]) {
+      const result = feed.since(cursor);
+      expect(result.reset).toBe(true);
+      expect(result.cursor).toBe(feed.cursor());
+    }
+  });
+
+  test('resets cursors older than the journal', () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+    const { cursor } = feed.since();
+
+    for (let weight = 2; weight < 1100; weight++) {
+      feed.record('claude', service([proxyConfig('a', weight)]));
+    }
+    expect(feed.since(cursor).reset).toBe(true);
+  });
+
+  test('wakes long polls on the next change', async () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+    const { cursor } = feed.since();
+
+    let woken = false;
+    const waiting = feed.waitForChange(cursor, 5000).then(() => {
+      woken = true;
+    });
+    await Bun.sleep(10);
+    expect(woken).toBe(false);
+
+    feed.record('claude', service([proxyConfig('a', 2)]));
+    await waiting;
+    expect(woken).toBe(true);
+  });
+
+  test('returns at once for a stale cursor and on abort', async () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+    const { cursor } = feed.since();
+    feed.record('claude', service([proxyConfig('a', 2)]));
+
+    const started = Date.now();
+    await feed.waitForChange(cursor, 5000);
+    await feed.waitForChange(undefined, 5000);
+
+    const controller = new AbortController();
+    const waiting = feed.waitForChange(feed.cursor(), 5000, controller.signal);
+    controller.abort();
+    await waiting;
+    expect(Date.now() - started).toBeLessThan(1000);
+  });
+
+  test('gives up after the timeout', async () => {
+    const feed = new ConfigChangeFeed();
+    feed.record('claude', service([proxyConfig('a')]));
+
+    const started = Date.now();
+    await feed.waitForChange(feed.cursor(), 50);
+    expect(Date.now() - started).toBeGreaterThanOrEqual(40);
+  });
+});
+
+describe('ConfigManager change feed', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('records saves', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }, { name: 'backup' }] });
+    const { configManager } = harness.proxy;
+    const feed = configManager.getChangeFeed();
+    const { cursor } = feed.since();
+
+    const current = configManager.getServiceConfig('claude')!;
+    await configManager.saveServiceConfig('claude', {
+      ...current,
+      configs: current.configs.filter(config => config.name !== 'backup').map(config => ({ ...config, weight: 7 })),
+    });
+
+    expect(summary(feed.since(cursor).changes)).toEqual(['claude/primary updated', 'claude/backup removed']);
+    expect(configManager.getRevision()).toEqual(feed.getRevision());
+  });
+});
@@ -0,0 +1,133 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { claudeCodeHookOutput, HookEventFeed, HookSessions, type HookEvent } from '../server/hooks/claudeCode';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+function notice(feed: HookEventFeed, session?: string): HookEvent {
+  return feed.record({ type: 'budget_threshold', service: 'claude', session, timestamp: Date.now(), message: 'low', data: {} });
+}
+
+describe('HookEventFeed', () => {
+  test('lists events after a sequence number', () => {
+    const feed = new HookEventFeed();
+    const first = notice(feed);
+    const second = notice(feed, 's1');
+    notice(feed, 's2');
+
+    expect(feed.latest()).toBe(3);
+    expect(feed.since(first.seq).map(event => event.seq)).toEqual([2, 3]);
+    // Events for other sessions are hidden; events without a session reach everyone
+    expect(feed.since(0, 's1').map(event => event.seq)).toEqual([first.seq, second.seq]);
+  });
+});
+
+describe('HookSessions', () => {
+  test('tags a session from its working directory and first prompt line', () => {
+    const sessions = new HookSessions();
+    const feed = new HookEventFeed();
+    sessions.handle({ session_id: 's1', hook_event_name: 'SessionStart', cwd: '/home/dev/billing-api' }, feed);
+    sessions.handle({ session_id: 's1', hook_event_name: 'UserPromptSubmit', prompt: '  Fix the invoice totals\nmore detail' }, feed);
+    sessions.handle({ session_id: 's1', tags: { team: 'payments', 'bad key': 'x', empty: '' } }, feed);
+
+    expect(sessions.tagsFor('s1', undefined)).toEqual({ project: 'billing-api', task: 'Fix the invoice totals', team: 'payments' });
+    expect(sessions.tagsFor('s1', { team: 'core' })).toMatchObject({ team: 'core' });
+    expect(sessions.tagsFor('unknown', { team: 'core' })).toEqual({ team: 'core' });
+  });
+
+  test('tells each session about new events once', () => {
+    const sessions = new HookSessions();
+    const feed = new HookEventFeed();
+    notice(feed);
+
+    // Events from before the session started are not reported
+    expect(sessions.handle({ session_id: 's1' }, feed)).toEqual([]);
+    const event = notice(feed);
+    notice(feed, 's2');
+    expect(sessions.handle({ session_id: 's1' }, feed)).toEqual([event]);
+    expect(sessions.handle({ session_id: 's1' }, feed)).toEqual([]);
+  });
+
+  test('forgets a session on SessionEnd and ignores callbacks without one', () => {
+    const sessions = new HookSessions();
+    const feed = new HookEventFeed();
+    sessions.handle({ session_id: 's1', cwd: '/work/app' }, feed);
+    sessions.handle({ cwd: '/work/other' }, feed);
+    expect(sessions.list().map(session => session.session)).toEqual(['s1']);
+
+    sessions.handle({ session_id: 's1', hook_event_name: 'SessionEnd' }, feed);
+    expect(sessions.list()).toEqual([]);
+  });
+});
+
+describe('claudeCodeHookOutput', () => {
+  test('turns events into a systemMessage', () => {
+    const feed = new HookEventFeed();
+    expect(claudeCodeHookOutput([])).toEqual({});
+    expect(claudeCodeHookOutput([notice(feed), notice(feed)])).toEqual({ systemMessage: 'paf: low\npaf: low' });
+  });
+});
+
+describe('hooks and proxied requests', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test("tag the session's requests", async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+    harness.proxy.hookSessions.handle({ session_id: 'sess-1', cwd: '/work/app' }, harness.proxy.hookEvents);
+
+    await harness.request('/v1/messages', { headers: { 'x-paf-conversation': 'sess-1', 'x-paf-tags': 'env=ci' }, body: BODY });
+    await harness.request('/v1/messages', { headers: { 'x-paf-conversation': 'other' }, body: BODY });
+
+    const [tagged, untagged] = await harness.waitForLogs(2);
+    expect(tagged.tags).toEqual({ project: 'app', env: 'ci' });
+    expect(untagged.tags ?? undefined).toBeUndefined();
+  });
+
+  test('record a provider switch when the active config changes', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }, { name: 'backup' }] });
+    const { configManager, hookEvents, hookSessions } = harness.proxy;
+    const headers = { 'x-paf-conversation': 'sess-1' };
+    hookSessions.handle({ session_id: 'sess-1' }, hookEvents);
+
+    await harness.request('/v1/messages', { headers, body: BODY });
+    await harness.waitForLogs(1);
+    await configManager.saveServiceConfig('claude', { ...configManager.getServiceConfig('claude')!, active: 'backup' });
+    await harness.request('/v1/messages', { headers, body: BODY });
+    await harness.waitForLogs(2);
+
+    const events = hookSessions.handle({ session_id: 'sess-1' }, hookEvents);
+    expect(events).toMatchObject([
+      { type: 'provider_switch', session: 'sess-1', data: { from: 'primary', to: 'backup', reason: 'selection' } },
+    ]);
+  });
+
+  test('count only failovers when load balancing', async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      loadBalancer: { strategy: 'round-robin' },
+      configs: [{ name: 'a' }, { name: 'b' }, { name: 'c' }],
+    });
+    const headers = { 'x-paf-conversation': 'sess-1' };
+
+    // Rotation alone is not a switch
+    await harness.request('/v1/messages', { headers, body: BODY });
+    await harness.request('/v1/messages', { headers, body: BODY });
+    await harness.waitForLogs(2);
+    expect(harness.logs().map(log => log.configName)).toEqual(['a', 'b']);
+    expect(harness.proxy.hookEvents.latest()).toBe(0);
+
+    harness.upstreams.c.enqueue({ status: 500, json: { error: 'down' } });
+    await harness.request('/v1/messages', { headers, body: BODY });
+    const logs = await harness.waitForLogs(3);
+
+    expect(logs[2].configName).toBe('a');
+    expect(harness.proxy.hookEvents.since(0)).toMatchObject([
+      { type: 'provider_switch', data: { from: 'b', to: 'a', reason: 'failover' } },
+    ]);
+  });
+});
@@ -0,0 +1,107 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { ConcurrencyLimiter, releaseWhenDone } from '../server/proxy/concurrency';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+describe('ConcurrencyLimiter', () => {
+  test('refuses past the limit and counts rejections', () => {
+    const limiter = new ConcurrencyLimiter(2);
+    const first = limiter.tryAcquire();
+    const second = limiter.tryAcquire();
+
+    expect(first).not.toBeNull();
+    expect(second).not.toBeNull();
+    expect(limiter.tryAcquire()).toBeNull();
+    expect(limiter.snapshot()).toEqual({ active: 2, limit: 2, rejected: 1 });
+
+    first!();
+    first!();
+    expect(limiter.snapshot().active).toBe(1);
+    expect(limiter.tryAcquire()).not.toBeNull();
+  });
+
+  test('reads the limit on every acquire; 0 is unlimited', () => {
+    let limit = 0;
+    const limiter = new ConcurrencyLimiter(() => limit);
+    for (let i = 0; i < 5; i++) {
+      expect(limiter.tryAcquire()).not.toBeNull();
+    }
+    limit = 5;
+    expect(limiter.tryAcquire()).toBeNull();
+  });
+});
+
+describe('releaseWhenDone', () => {
+  test('releases once the body has been read', async () => {
+    let released = 0;
+    const response = releaseWhenDone(new Response('done'), () => released++);
+
+    expect(released).toBe(0);
+    expect(await response.text()).toBe('done');
+    expect(released).toBe(1);
+  });
+
+  test('releases when the client cancels', async () => {
+    let released = 0;
+    const never = new ReadableStream({ pull: () => new Promise(() => undefined) });
+    const response = releaseWhenDone(new Response(never), () => released++);
+
+    await response.body!.cancel();
+    expect(released).toBe(1);
+  });
+
+  test('releases at once without a body', () => {
+    let released = 0;
+    releaseWhenDone(new Response(null, { status: 204 }), () => released++);
+    expect(released).toBe(1);
+  });
+});
+
+describe('max_concurrent_requests', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('answers requests over the service limit with a 429', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { maxConcurrentRequests: 1 },
+      configs: [{ name: 'primary', fallback: { json: { id: 'msg_1' }, delayMs: 100 } }],
+    });
+
+    const [first, second] = await Promise.all([
+      harness.request('/v1/messages', { body: BODY }),
+      harness.request('/v1/messages', { body: BODY }),
+    ]);
+
+    expect(first.status).toBe(200);
+    expect(second.status).toBe(429);
+    expect(second.headers.get('retry-after')).not.toBeNull();
+    expect(await second.json()).toMatchObject({
+      type: 'error',
+      error: { message: 'Too many concurrent claude requests (limit 1); retry shortly' },
+    });
+    expect(harness.upstreams.primary.requests).toHaveLength(1);
+
+    await first.text();
+    expect((await harness.request('/v1/messages', { body: BODY })).status).toBe(200);
+  });
+
+  test('holds the slot until a stream is drained', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { maxConcurrentRequests: 1 },
+      configs: [{ name: 'primary', fallback: { sse: [{ type: 'message_stop' }] } }],
+    });
+    const stream = () =>
+      harness!.request('/v1/messages', { headers: { accept: 'text/event-stream' }, body: { ...BODY, stream: true } });
+
+    const first = await stream();
+    expect((await stream()).status).toBe(429);
+
+    await first.text();
+    expect((await stream()).status).toBe(200);
+  });
+});
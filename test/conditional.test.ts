@@ -0,0 +1,90 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { changeValidators, notModified, validatorHeaders } from '../server/web/conditional';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const CHANGED_AT = Date.UTC(2026, 0, 1, 12, 0, 0, 500);
+
+function get(headers: Record<string, string>): Request {
+  return new Request('http://paf.test/api/logs', { headers });
+}
+
+describe('changeValidators', () => {
+  test('changes with any revision or the variant', () => {
+    const base = changeValidators([{ revision: 3, changedAt: 1 }, { revision: 7, changedAt: CHANGED_AT }], '?limit=50');
+
+    expect(base).toEqual(changeValidators([{ revision: 3, changedAt: 1 }, { revision: 7, changedAt: CHANGED_AT }], '?limit=50'));
+    expect(base.etag).toStartWith('W/"3.7-');
+    expect(base.changedAt).toBe(CHANGED_AT);
+    expect(changeValidators([{ revision: 3, changedAt: 1 }, { revision: 8, changedAt: CHANGED_AT }], '?limit=50').etag).not.toBe(base.etag);
+    expect(changeValidators([{ revision: 3, changedAt: 1 }, { revision: 7, changedAt: CHANGED_AT }], '?limit=10').etag).not.toBe(base.etag);
+  });
+});
+
+describe('validatorHeaders', () => {
+  const validators = changeValidators([{ revision: 1, changedAt: CHANGED_AT }]);
+
+  test('always sends the ETag with no-cache', () => {
+    expect(validatorHeaders(validators, CHANGED_AT)).toEqual({ ETag: validators.etag, 'Cache-Control': 'no-cache' });
+  });
+
+  test('sends Last-Modified once its second has passed', () => {
+    expect(validatorHeaders(validators, CHANGED_AT + 1000)['Last-Modified']).toBe('Thu, 01 Jan 2026 12:00:00 GMT');
+  });
+});
+
+describe('notModified', () => {
+  const validators = changeValidators([{ revision: 1, changedAt: CHANGED_AT }]);
+  const extra = { 'Access-Control-Expose-Headers': 'ETag' };
+
+  test('answers a matching If-None-Match with a 304', () => {
+    const response = notModified(get({ 'if-none-match': `"other", ${validators.etag.replace(/^W\//, '')}` }), validators, extra);
+
+    expect(response?.status).toBe(304);
+    expect(response?.headers.get('etag')).toBe(validators.etag);
+    expect(response?.headers.get('access-control-expose-headers')).toBe('ETag');
+  });
+
+  test('returns null for a stale copy', () => {
+    expect(notModified(get({ 'if-none-match': 'W/"0-abc"' }), validators, extra)).toBeNull();
+    expect(notModified(get({}), validators, extra)).toBeNull();
+  });
+
+  test('compares If-Modified-Since at second precision', () => {
+    expect(notModified(get({ 'if-modified-since': 'Thu, 01 Jan 2026 12:00:00 GMT' }), validators, extra)?.status).toBe(304);
+    expect(notModified(get({ 'if-modified-since': 'Thu, 01 Jan 2026 11:59:59 GMT' }), validators, extra)).toBeNull();
+  });
+
+  test('prefers If-None-Match over If-Modified-Since', () => {
+    const request = get({ 'if-none-match': 'W/"0-abc"', 'if-modified-since': 'Thu, 01 Jan 2026 12:00:00 GMT' });
+    expect(notModified(request, validators, extra)).toBeNull();
+  });
+});
+
+describe('change counters', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('move with request logs and config changes', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+    const { configManager, logger } = harness.proxy;
+    const logsBefore = logger.getRevision();
+    const configsBefore = configManager.getRevision();
+
+    await harness.request('/v1/messages', { body: { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [] } });
+    await harness.waitForLogs(1);
+
+    expect(logger.getRevision().revision).toBeGreaterThan(logsBefore.revision);
+    expect(configManager.getRevision()).toEqual(configsBefore);
+
+    const service = configManager.getServiceConfig('claude')!;
+    await configManager.saveServiceConfig('claude', service);
+    expect(configManager.getRevision()).toEqual(configsBefore);
+
+    await configManager.saveServiceConfig('claude', { ...service, configs: [{ ...service.configs[0], weight: 5 }] });
+    expect(configManager.getRevision().revision).toBeGreaterThan(configsBefore.revision);
+  });
+});
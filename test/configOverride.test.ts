@@ -0,0 +1,161 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import type { ServiceConfig } from '../server/config/types';
+import {
+  CONFIG_OVERRIDE_HEADER,
+  CONFIG_OVERRIDE_TOKEN_HEADER,
+  parseConfigOverridePolicy,
+  resolveConfigOverride,
+} from '../server/proxy/configOverride';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+function forcing(name: string, token?: string): Request {
+  const headers: Record<string, string> = { [CONFIG_OVERRIDE_HEADER]: name };
+  if (token !== undefined) {
+    headers[CONFIG_OVERRIDE_TOKEN_HEADER] = token;
+  }
+  return new Request('http://paf.test/v1/messages', { method: 'POST', headers, body: '{}' });
+}
+
+describe('parseConfigOverridePolicy', () => {
+  test('needs a non-empty allow list', () => {
+    expect(parseConfigOverridePolicy({ allow: ['a', '', 3], token: 'secret' })).toEqual({ allow: ['a'], token: 'secret' });
+    expect(parseConfigOverridePolicy({ allow: ['*'], token: '' })).toEqual({ allow: ['*'], token: undefined });
+    expect(parseConfigOverridePolicy({ allow: [] })).toBeUndefined();
+    expect(parseConfigOverridePolicy({ token: 'secret' })).toBeUndefined();
+  });
+});
+
+describe('resolveConfigOverride', () => {
+  const service = {
+    configs: [
+      { name: 'primary', baseUrl: 'https://a.example', weight: 1, enabled: true },
+      { name: 'backup', baseUrl: 'https://b.example', weight: 1, enabled: true },
+      { name: 'retired', baseUrl: 'https://c.example', weight: 1, enabled: false },
+    ],
+    configOverride: { allow: ['backup', 'retired', 'ghost'], token: 'secret' },
+  } as ServiceConfig;
+
+  test('does nothing without the header', () => {
+    const request = new Request('http://paf.test/v1/messages');
+    expect(resolveConfigOverride(request, 'claude', undefined)).toEqual({ config: null });
+  });
+
+  test('returns an allowed config with the right token', () => {
+    expect(resolveConfigOverride(forcing('backup', ' secret '), 'claude', service)).toMatchObject({ config: { name: 'backup' } });
+  });
+
+  test('says why a request may not force a config', () => {
+    expect(resolveConfigOverride(forcing('backup'), 'claude', { ...service, configOverride: undefined })).toMatchObject({ kind: 'permission' });
+    expect(resolveConfigOverride(forcing('backup'), 'claude', service)).toMatchObject({ kind: 'authentication' });
+    expect(resolveConfigOverride(forcing('backup', 'secreT'), 'claude', service)).toMatchObject({ kind: 'authentication' });
+    expect(resolveConfigOverride(forcing('primary', 'secret'), 'claude', service)).toMatchObject({ kind: 'permission' });
+    expect(resolveConfigOverride(forcing('retired', 'secret'), 'claude', service)).toMatchObject({ kind: 'permission' });
+    expect(resolveConfigOverride(forcing('ghost', 'secret'), 'claude', service)).toMatchObject({ kind: 'not_found' });
+  });
+
+  test('lets "*" allow any config', () => {
+    const open = { ...service, configOverride: { allow: ['*'] } };
+    expect(resolveConfigOverride(forcing('primary'), 'claude', open)).toMatchObject({ config: { name: 'primary' } });
+  });
+});
+
+describe('x-paf-config', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('sends one request to the forced config without failing over', async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      serviceConfig: { configOverride: { allow: ['backup'], token: 'secret' } },
+      configs: [{ name: 'primary' }, { name: 'backup', responses: [{ status: 500, json: { error: 'down' } }] }],
+    });
+    const headers = { [CONFIG_OVERRIDE_HEADER]: 'backup', [CONFIG_OVERRIDE_TOKEN_HEADER]: 'secret' };
+
+    const failed = await harness.request('/v1/messages', { headers, body: BODY });
+    const served = await harness.request('/v1/messages', { headers, body: BODY });
+
+    expect(failed.status).toBe(500);
+    expect(served.status).toBe(200);
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+    const [forwarded] = harness.upstreams.backup.requests;
+    expect(forwarded.headers[CONFIG_OVERRIDE_HEADER]).toBeUndefined();
+    expect(forwarded.headers[CONFIG_OVERRIDE_TOKEN_HEADER]).toBeUndefined();
+
+    const logs = await harness.waitForLogs(2);
+    expect(logs.map(log => log.configName)).toEqual(['backup', 'backup']);
+    expect(logs[1].context?.selection).toMatchObject({ via: 'header' });
+    // The token is a secret; the config name is fine to keep
+    expect(logs.map(log => log.requestHeaders?.[CONFIG_OVERRIDE_TOKEN_HEADER])).toEqual([undefined, undefined]);
+    expect(logs[0].requestHeaders?.[CONFIG_OVERRIDE_HEADER]).toBe('backup');
+  });
+
+  test('is not held back by the forced config\'s rate limit', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { configOverride: { allow: ['backup'] } },
+      configs: [{ name: 'primary' }, { name: 'backup' }],
+    });
+    const { configManager } = harness.proxy;
+    const service = configManager.getServiceConfig('claude')!;
+    await configManager.saveServiceConfig('claude', {
+      ...service,
+      configs: service.configs.map(config => ({ ...config, requestsPerMinute: 1 })),
+    });
+    const headers = { [CONFIG_OVERRIDE_HEADER]: 'backup' };
+
+    const statuses = [];
+    for (let i = 0; i < 3; i++) {
+      statuses.push((await harness.request('/v1/messages', { headers, body: BODY })).status);
+    }
+    expect(statuses).toEqual([200, 200, 200]);
+    expect(harness.upstreams.backup.requests).toHaveLength(3);
+  });
+
+  test('refuses the header with the matching status', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { configOverride: { allow: ['backup', 'ghost'], token: 'secret' } },
+      configs: [{ name: 'primary' }, { name: 'backup' }],
+    });
+    const send = (name: string, token?: string) =>
+      harness!.request('/v1/messages', {
+        headers: token ? { [CONFIG_OVERRIDE_HEADER]: name, [CONFIG_OVERRIDE_TOKEN_HEADER]: token } : { [CONFIG_OVERRIDE_HEADER]: name },
+        body: BODY,
+      });
+
+    expect((await send('backup')).status).toBe(401);
+    expect((await send('primary', 'secret')).status).toBe(403);
+    expect((await send('ghost', 'secret')).status).toBe(404);
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+    expect(harness.upstreams.backup.requests).toHaveLength(0);
+  });
+
+  test('keeps the token out of logs of locally rejected requests', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { validateRequests: true, configOverride: { allow: ['backup'], token: 'secret' } },
+      configs: [{ name: 'primary' }, { name: 'backup' }],
+    });
+
+    const response = await harness.request('/v1/messages', {
+      headers: { [CONFIG_OVERRIDE_HEADER]: 'backup', [CONFIG_OVERRIDE_TOKEN_HEADER]: 'secret' },
+      body: { model: 'claude-sonnet-4-5', messages: 'not a list' },
+    });
+    expect(response.status).toBe(400);
+
+    const [log] = await harness.waitForLogs(1);
+    expect(log.requestHeaders?.[CONFIG_OVERRIDE_HEADER]).toBe('backup');
+    expect(log.requestHeaders?.[CONFIG_OVERRIDE_TOKEN_HEADER]).toBeUndefined();
+  });
+
+  test('is refused when the service has no [config_override]', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+
+    const response = await harness.request('/v1/messages', { headers: { [CONFIG_OVERRIDE_HEADER]: 'primary' }, body: BODY });
+    expect(response.status).toBe(403);
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+  });
+});
@@ -0,0 +1,108 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { once } from 'node:events';
+import { connect, createServer, type AddressInfo, type Server, type Socket } from 'node:net';
+import { directDialer, EgressBridge } from '../server/proxy/egressBridge';
+
+async function listen(server: Server): Promise<number> {
+  server.listen(0, '127.0.0.1');
+  await once(server, 'listening');
+  return (server.address() as AddressInfo).port;
+}
+
+/**
+ * Send raw bytes to the bridge and collect everything it answers until it closes
+ */
+async function exchange(bridgeUrl: string, send: (socket: Socket, received: () => string) => Promise<void>): Promise<string> {
+  const { port } = new URL(bridgeUrl);
+  const socket = connect({ host: '127.0.0.1', port: Number(port) });
+  let received = '';
+  socket.on('data', chunk => {
+    received += chunk.toString('latin1');
+  });
+  await once(socket, 'connect');
+  await send(socket, () => received);
+  await once(socket, 'close');
+  return received;
+}
+
+async function until(check: () => boolean): Promise<void> {
+  for (let i = 0; i < 200 && !check(); i++) {
+    await new Promise(resolve => setTimeout(resolve, 5));
+  }
+}
+
+describe('EgressBridge', () => {
+  const servers: Server[] = [];
+  let bridge: EgressBridge | undefined;
+
+  afterEach(() => {
+    bridge?.close();
+    bridge = undefined;
+    servers.splice(0).forEach(server => server.close());
+  });
+
+  test('tunnels CONNECT from the bound address', async () => {
+    let remoteAddress: string | undefined;
+    const upstream = createServer(socket => {
+      remoteAddress = socket.remoteAddress;
+      socket.on('data', chunk => socket.end(`echo:${chunk}`));
+    });
+    servers.push(upstream);
+    const port = await listen(upstream);
+    bridge = new EgressBridge(directDialer('127.0.0.1'));
+
+    const received = await exchange(await bridge.listen(), async (socket, received) => {
+      socket.write(`CONNECT 127.0.0.1:${port} HTTP/1.1\r\nHost: 127.0.0.1:${port}\r\n\r\n`);
+      await until(() => received().includes('\r\n\r\n'));
+      socket.write('ping');
+    });
+
+    expect(received).toBe('HTTP/1.1 200 Connection Established\r\n\r\necho:ping');
+    expect(remoteAddress).toContain('127.0.0.1');
+  });
+
+  test('forwards plain HTTP in origin form, one request per connection', async () => {
+    let head = '';
+    const upstream = createServer(socket => {
+      socket.on('data', chunk => {
+        head += chunk.toString('latin1');
+        if (head.includes('\r\n\r\nbody')) {
+          socket.end('HTTP/1.1 204 No Content\r\n\r\n');
+        }
+      });
+    });
+    servers.push(upstream);
+    const port = await listen(upstream);
+    bridge = new EgressBridge(directDialer());
+
+    const received = await exchange(await bridge.listen(), async socket => {
+      socket.write(
+        `POST http://127.0.0.1:${port}/v1/messages?beta=1 HTTP/1.1\r\nHost: 127.0.0.1:${port}\r\n` +
+          'Proxy-Connection: keep-alive\r\nConnection: keep-alive\r\nContent-Length: 4\r\n\r\nbody'
+      );
+    });
+
+    expect(received).toBe('HTTP/1.1 204 No Content\r\n\r\n');
+    expect(head).toBe(
+      `POST /v1/messages?beta=1 HTTP/1.1\r\nHost: 127.0.0.1:${port}\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbody`
+    );
+  });
+
+  test('answers 502 when the upstream is unreachable and 400 for other targets', async () => {
+    const closed = createServer();
+    const port = await listen(closed);
+    closed.close();
+    bridge = new EgressBridge(directDialer());
+    const url = await bridge.listen();
+
+    const unreachable = await exchange(url, async socket => {
+      socket.write(`CONNECT 127.0.0.1:${port} HTTP/1.1\r\n\r\n`);
+    });
+    const ftp = await exchange(url, async socket => {
+      socket.write('GET ftp://example.test/ HTTP/1.1\r\n\r\n');
+    });
+
+    expect(unreachable).toStartWith('HTTP/1.1 502 Bad Gateway');
+    expect(ftp).toStartWith('HTTP/1.1 400 Bad Request');
+  });
+});
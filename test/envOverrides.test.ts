@@ -0,0 +1,81 @@
+import { afterEach, beforeEach, describe, expect, test } from 'bun:test';
+import * as TOML from '@iarna/toml';
+import { mkdtempSync, readFileSync, rmSync, writeFileSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { applyServiceEnvOverrides, stripServiceEnvOverrides } from '../server/config/env';
+import { ConfigManager } from '../server/config/manager';
+
+const FILE = {
+  mode: 'manual',
+  loadbalancer: { strategy: 'weighted' },
+  configs: [{ name: 'primary', base_url: 'https://api.example.test', api_key: 'file-key' }],
+  active: { name: 'primary' },
+};
+
+const ENV = {
+  PAF_CLAUDE_MODE: 'load_balance',
+  PAF_CLAUDE_LOADBALANCER__FREEZE_DURATION: '1000',
+  PAF_CLAUDE_CONFIGS__PRIMARY__API_KEY: 'env-key',
+  PAF_CLAUDE_CONFIGS__BACKUP__BASE_URL: 'https://backup.example.test',
+  PAF_CLAUDE_CONFIGS__BACKUP__AUTH_TOKEN: 'env-token',
+};
+
+describe('stripServiceEnvOverrides', () => {
+  test('puts the file values back and drops environment-only configs', () => {
+    const loaded = applyServiceEnvOverrides('claude', structuredClone(FILE), ENV);
+    expect(loaded.configs).toHaveLength(2);
+
+    const saved = stripServiceEnvOverrides('claude', loaded, FILE, ENV);
+    expect(saved).toEqual(FILE);
+  });
+
+  test('keeps edits to keys the environment does not set', () => {
+    const loaded = applyServiceEnvOverrides('claude', structuredClone(FILE), ENV);
+    loaded.configs[0].weight = 3;
+    loaded.loadbalancer.strategy = 'round_robin';
+
+    const saved = stripServiceEnvOverrides('claude', loaded, FILE, ENV);
+    expect(saved.configs).toEqual([{ ...FILE.configs[0], weight: 3 }]);
+    expect(saved.loadbalancer).toEqual({ strategy: 'round_robin' });
+    expect(saved.mode).toBe('manual');
+  });
+});
+
+describe('saveServiceConfig', () => {
+  let dir: string;
+
+  beforeEach(() => {
+    dir = mkdtempSync(join(tmpdir(), 'paf-env-'));
+    writeFileSync(join(dir, 'claude.toml'), TOML.stringify(FILE));
+    Object.assign(process.env, ENV);
+  });
+
+  afterEach(() => {
+    for (const name of Object.keys(ENV)) {
+      delete process.env[name];
+    }
+    rmSync(dir, { recursive: true, force: true });
+  });
+
+  test('never writes environment overrides to the file', async () => {
+    const manager = new ConfigManager(dir);
+    const config = await manager.loadServiceConfig('claude');
+    expect(config.mode).toBe('load_balance');
+    expect(config.configs.map(c => c.apiKey ?? c.authToken)).toEqual(['env-key', 'env-token']);
+
+    config.configs[0].weight = 3;
+    await manager.saveServiceConfig('claude', config);
+
+    const text = readFileSync(join(dir, 'claude.toml'), 'utf8');
+    expect(text).not.toContain('env-key');
+    expect(text).not.toContain('env-token');
+    const saved: any = TOML.parse(text);
+    expect(saved.mode).toBe('manual');
+    expect(saved.configs).toHaveLength(1);
+    expect(saved.configs[0]).toMatchObject({ name: 'primary', api_key: 'file-key', weight: 3 });
+
+    // The running config keeps the overrides
+    expect(manager.getServiceConfig('claude')?.configs).toHaveLength(2);
+  });
+});
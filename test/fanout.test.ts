@@ -0,0 +1,144 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { setClientIdentity } from '../server/proxy/clientIdentity';
+import { CONFIG_OVERRIDE_HEADER } from '../server/proxy/configOverride';
+import { fanOut, FANOUT_PATH, MAX_FANOUT_CONFIGS, parseFanoutRequest, readFanoutRequest } from '../server/proxy/fanout';
+import { BodyMemoryBudget } from '../server/proxy/memoryBudget';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+function envelope(data: unknown, headers: Record<string, string> = {}): Request {
+  return new Request(`http://paf.test${FANOUT_PATH}`, {
+    method: 'POST',
+    headers: { 'content-type': 'application/json', ...headers },
+    body: typeof data === 'string' ? data : JSON.stringify(data),
+  });
+}
+
+describe('parseFanoutRequest', () => {
+  test('dedupes configs and defaults the path per service', () => {
+    expect(parseFanoutRequest({ configs: [' a', 'b', 'a'], body: BODY }, 'claude')).toEqual({
+      configs: ['a', 'b'],
+      path: '/v1/messages',
+      body: BODY,
+    });
+    expect(parseFanoutRequest({ configs: ['a'], body: {} }, 'codex')).toMatchObject({ path: '/v1/chat/completions' });
+  });
+
+  test('refuses malformed envelopes', () => {
+    const tooMany = Array.from({ length: MAX_FANOUT_CONFIGS + 1 }, (_, i) => `c${i}`);
+    for (const data of [
+      null,
+      { configs: [], body: BODY },
+      { configs: ['a', ''], body: BODY },
+      { configs: tooMany, body: BODY },
+      { configs: ['a'], body: [BODY] },
+      { configs: ['a'], body: BODY, path: 'v1/messages' },
+      { configs: ['a'], body: BODY, path: FANOUT_PATH },
+    ]) {
+      expect(parseFanoutRequest(data, 'claude')).toHaveProperty('error');
+    }
+  });
+});
+
+describe('readFanoutRequest', () => {
+  test('refuses bodies past max_request_body_bytes, declared or not', async () => {
+    const data = { configs: ['a'], body: { ...BODY, messages: [{ role: 'user', content: 'x'.repeat(500) }] } };
+
+    expect(await readFanoutRequest(envelope(data), 'claude', 200)).toMatchObject({ kind: 'too_large' });
+    expect(await readFanoutRequest(envelope(data, { 'content-length': '9999' }), 'claude', 200)).toMatchObject({ kind: 'too_large' });
+    expect(await readFanoutRequest(envelope(data), 'claude', undefined)).toMatchObject({ configs: ['a'] });
+  });
+
+  test('reports invalid JSON and envelopes as invalid_request', async () => {
+    expect(await readFanoutRequest(envelope('{nope'), 'claude', undefined)).toEqual({
+      kind: 'invalid_request',
+      error: 'Request body is not valid JSON',
+    });
+    expect(await readFanoutRequest(envelope({ configs: [] }), 'claude', undefined)).toMatchObject({ kind: 'invalid_request' });
+  });
+});
+
+describe('fanOut', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('returns every answer side by side', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { configOverride: { allow: ['*'] } },
+      configs: [
+        { name: 'primary', fallback: { json: { id: 'msg_a', usage: { input_tokens: 3, output_tokens: 5 } } } },
+        { name: 'backup', fallback: { status: 500, json: { error: 'down' } } },
+      ],
+    });
+    const { configManager, logger, proxies } = harness.proxy;
+    const budget = new BodyMemoryBudget(1 << 20);
+
+    const results = await fanOut(
+      proxies.claude,
+      envelope({}, { 'x-paf-tags': 'run=compare' }),
+      { configs: ['primary', 'backup', 'ghost'], path: '/v1/messages', body: { ...BODY, stream: true } },
+      configManager.getAllConfigs('claude'),
+      logger,
+      budget
+    );
+
+    expect(results).toMatchObject([
+      { config: 'primary', status: 200, response: { id: 'msg_a' }, usage: { inputTokens: 3, outputTokens: 5 } },
+      { config: 'backup', status: 500, usage: {} },
+      { config: 'ghost', status: 404 },
+    ]);
+    expect(typeof results[0].request_id).toBe('string');
+    expect(budget.snapshot().buffered_bytes).toBe(0);
+
+    // Each leg is an ordinary forced request: never streamed, never failed over
+    const [sent] = harness.upstreams.primary.requests;
+    expect(JSON.parse(sent.body).stream).toBe(false);
+    expect(sent.headers[CONFIG_OVERRIDE_HEADER]).toBeUndefined();
+    expect(harness.upstreams.backup.requests).toHaveLength(1);
+
+    const logs = await harness.waitForLogs(2);
+    expect(logs.every(log => log.tags?.run === 'compare')).toBe(true);
+  });
+
+  test('carries the client identity to every leg', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { configOverride: { allow: ['*'] } },
+      configs: [{ name: 'primary' }, { name: 'backup' }],
+    });
+    const { configManager, logger, proxies } = harness.proxy;
+    const request = envelope({});
+    setClientIdentity(request, 'ci-runner');
+
+    await fanOut(
+      proxies.claude,
+      request,
+      { configs: ['primary', 'backup'], path: '/v1/messages', body: BODY },
+      configManager.getAllConfigs('claude'),
+      logger
+    );
+
+    const logs = await harness.waitForLogs(2);
+    expect(logs.map(log => log.clientIdentity)).toEqual(['ci-runner', 'ci-runner']);
+  });
+
+  test('is limited by [config_override]', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+    const { configManager, logger, proxies } = harness.proxy;
+
+    const [result] = await fanOut(
+      proxies.claude,
+      envelope({}),
+      { configs: ['primary'], path: '/v1/messages', body: BODY },
+      configManager.getAllConfigs('claude'),
+      logger
+    );
+
+    expect(result.status).toBe(403);
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+  });
+});
@@ -0,0 +1,111 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const MESSAGE = {
+  id: 'msg_01',
+  type: 'message',
+  role: 'assistant',
+  model: 'claude-sonnet-4-5',
+  content: [{ type: 'text', text: 'Hello' }],
+  stop_reason: 'end_turn',
+  usage: { input_tokens: 3, output_tokens: 5 },
+};
+
+const REQUEST = {
+  model: 'claude-sonnet-4-5',
+  max_tokens: 64,
+  messages: [{ role: 'user', content: 'Hi' }],
+};
+
+let harness: TestHarness | undefined;
+
+afterEach(async () => {
+  await harness?.close();
+  harness = undefined;
+});
+
+describe('test harness', () => {
+  test('proxies to the active config and logs usage', async () => {
+    harness = await createTestHarness({
+      configs: [{ name: 'primary', responses: [{ json: MESSAGE }] }],
+    });
+
+    const response = await harness.request('/v1/messages', { body: REQUEST });
+
+    expect(response.status).toBe(200);
+    expect(await response.json()).toMatchObject({ id: 'msg_01' });
+    const [upstream] = harness.upstreams.primary.requests;
+    expect(upstream.method).toBe('POST');
+    expect(upstream.path).toBe('/v1/messages');
+    expect(JSON.parse(upstream.body)).toMatchObject({ model: 'claude-sonnet-4-5' });
+
+    const [log] = await harness.waitForLogs(1);
+    expect(log).toMatchObject({ configName: 'primary', statusCode: 200, inputTokens: 3, outputTokens: 5 });
+  });
+
+  test('load balancing moves to the next config after a failure', async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      loadBalancer: { freezeDuration: 0 },
+      configs: [
+        { name: 'primary', weight: 2, responses: [{ status: 500, json: { error: { message: 'boom' } } }] },
+        { name: 'backup', responses: [{ json: MESSAGE }] },
+      ],
+    });
+
+    const response = await harness.request('/v1/messages', { body: REQUEST });
+
+    expect(response.status).toBe(200);
+    expect(harness.upstreams.primary.requests).toHaveLength(1);
+    expect(harness.upstreams.backup.requests).toHaveLength(1);
+    expect(harness.loadBalancer.getServerHealth('primary').consecutiveFailures).toBe(1);
+  });
+
+  test('streams SSE responses and logs them once drained', async () => {
+    harness = await createTestHarness({
+      configs: [{
+        name: 'primary',
+        responses: [{
+          sse: [
+            { type: 'message_start', message: { ...MESSAGE, content: [], usage: { input_tokens: 3, output_tokens: 0 } } },
+            { type: 'content_block_start', index: 0, content_block: { type: 'text', text: '' } },
+            { type: 'content_block_delta', index: 0, delta: { type: 'text_delta', text: 'Hello' } },
+            { type: 'content_block_stop', index: 0 },
+            { type: 'message_delta', delta: { stop_reason: 'end_turn' }, usage: { output_tokens: 5 } },
+            { type: 'message_stop' },
+          ],
+        }],
+      }],
+    });
+
+    const response = await harness.request('/v1/messages', {
+      headers: { accept: 'text/event-stream' },
+      body: { ...REQUEST, stream: true },
+    });
+
+    expect(response.headers.get('content-type')).toContain('text/event-stream');
+    expect(await response.text()).toContain('text_delta');
+    const [log] = await harness.waitForLogs(1);
+    expect(log).toMatchObject({ configName: 'primary', statusCode: 200, outputTokens: 5 });
+  });
+
+  test('passes a streaming request\'s error body through without failing over', async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      loadBalancer: { freezeDuration: 0 },
+      configs: [
+        { name: 'primary', weight: 2, responses: [{ status: 400, json: { type: 'error', error: { type: 'invalid_request_error', message: 'bad' } } }] },
+        { name: 'backup', responses: [{ json: MESSAGE }] },
+      ],
+    });
+
+    const response = await harness.request('/v1/messages', {
+      headers: { accept: 'text/event-stream' },
+      body: { ...REQUEST, stream: true },
+    });
+
+    expect(response.status).toBe(400);
+    expect(await response.json()).toMatchObject({ error: { type: 'invalid_request_error', message: 'bad' } });
+    expect(harness.upstreams.backup.requests).toHaveLength(0);
+  });
+});
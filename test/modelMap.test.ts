@@ -0,0 +1,79 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { applyModelMap, parseModelMap } from '../server/proxy/modelOverride';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+describe('parseModelMap', () => {
+  test('keeps renames and drops identity and empty entries', () => {
+    expect(parseModelMap({ a: ' upstream-a ', b: 'b', c: '', d: 3 })).toEqual({ a: 'upstream-a' });
+    expect(parseModelMap({ same: 'same' })).toBeUndefined();
+    expect(parseModelMap(['a'])).toBeUndefined();
+    expect(parseModelMap(undefined)).toBeUndefined();
+  });
+});
+
+describe('applyModelMap', () => {
+  test('renames mapped models only', () => {
+    const map = { 'claude-sonnet-4-5': 'relay/sonnet' };
+
+    expect(applyModelMap(BODY, map)).toEqual({
+      body: { ...BODY, model: 'relay/sonnet' },
+      from: 'claude-sonnet-4-5',
+      to: 'relay/sonnet',
+    });
+    expect(applyModelMap({ ...BODY, model: 'claude-opus-4-1' }, map)).toBeNull();
+    expect(applyModelMap({ ...BODY, model: 'toString' }, map)).toBeNull();
+    expect(applyModelMap(BODY, undefined)).toBeNull();
+  });
+});
+
+describe('model_map', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test("uses each attempt's own map on failover", async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      loadBalancer: { strategy: 'round-robin' },
+      configs: [{ name: 'primary', responses: [{ status: 500, json: { error: 'down' } }] }, { name: 'backup' }],
+    });
+    const { configManager } = harness.proxy;
+    const service = configManager.getServiceConfig('claude')!;
+    await configManager.saveServiceConfig('claude', {
+      ...service,
+      configs: service.configs.map(config => ({ ...config, modelMap: { 'claude-sonnet-4-5': `${config.name}-sonnet` } })),
+    });
+
+    const response = await harness.request('/v1/messages', { body: BODY });
+    expect(response.status).toBe(200);
+
+    for (const [name, upstream] of Object.entries(harness.upstreams)) {
+      expect(upstream.requests.map(request => JSON.parse(request.body).model)).toEqual([`${name}-sonnet`]);
+    }
+
+    const [log] = await harness.waitForLogs(1);
+    expect(log.requestModel).toBe('backup-sonnet');
+    expect(log.context?.transforms.filter(transform => transform.name === 'model_map')).toEqual([
+      { name: 'model_map', detail: 'claude-sonnet-4-5 -> primary-sonnet' },
+      { name: 'model_map', detail: 'claude-sonnet-4-5 -> backup-sonnet' },
+    ]);
+  });
+
+  test('survives a reload', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+    const { configManager } = harness.proxy;
+    const service = configManager.getServiceConfig('claude')!;
+    await configManager.saveServiceConfig('claude', {
+      ...service,
+      configs: [{ ...service.configs[0], modelMap: { 'claude-sonnet-4-5': 'relay/sonnet' } }],
+    });
+
+    const reloaded = await configManager.loadServiceConfig('claude');
+    expect(reloaded.configs[0].modelMap).toEqual({ 'claude-sonnet-4-5': 'relay/sonnet' });
+  });
+});
@@ -0,0 +1,103 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import type { ServiceConfig } from '../server/config/types';
+import { createProxyCore } from '../server/core';
+import { isNoLogRequest, NO_LOG_HEADER, parseNoLogClientKeys } from '../server/proxy/noLog';
+import { RESPONSE_CACHE_HEADER } from '../server/proxy/responseCache';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'my secret prompt' }] };
+const REPLY = { id: 'msg_1', type: 'message', content: [{ type: 'text', text: 'secret answer' }], usage: { input_tokens: 3, output_tokens: 5 } };
+
+function request(headers: Record<string, string>): Request {
+  return new Request('http://paf.test/v1/messages', { method: 'POST', headers, body: '{}' });
+}
+
+describe('parseNoLogClientKeys', () => {
+  test('keeps non-empty string keys', () => {
+    expect(parseNoLogClientKeys({ client_keys: ['sk-a', '', 3] })).toEqual(['sk-a']);
+    expect(parseNoLogClientKeys({ client_keys: [] })).toBeUndefined();
+    expect(parseNoLogClientKeys(undefined)).toBeUndefined();
+  });
+});
+
+describe('isNoLogRequest', () => {
+  const service = { noLogClientKeys: ['sk-private'] } as ServiceConfig;
+
+  test('takes any header value except an explicit no', () => {
+    expect(isNoLogRequest(request({ [NO_LOG_HEADER]: '1' }), undefined)).toBe(true);
+    expect(isNoLogRequest(request({ [NO_LOG_HEADER]: '' }), undefined)).toBe(true);
+    for (const value of ['0', 'false', ' No ']) {
+      expect(isNoLogRequest(request({ [NO_LOG_HEADER]: value }), undefined)).toBe(false);
+    }
+    expect(isNoLogRequest(request({}), undefined)).toBe(false);
+  });
+
+  test('applies to listed client keys whatever the header says', () => {
+    expect(isNoLogRequest(request({ 'x-api-key': 'sk-private', [NO_LOG_HEADER]: '0' }), service)).toBe(true);
+    expect(isNoLogRequest(request({ authorization: 'Bearer sk-private' }), service)).toBe(true);
+    expect(isNoLogRequest(request({ 'x-api-key': 'sk-other' }), service)).toBe(false);
+  });
+});
+
+describe('x-paf-no-log', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('logs metadata only and strips the header', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary', fallback: { json: REPLY } }] });
+
+    const response = await harness.request('/v1/messages', { headers: { [NO_LOG_HEADER]: '1', 'x-paf-tags': 'team=legal' }, body: BODY });
+    expect(await response.json()).toMatchObject({ id: 'msg_1' });
+
+    const [log] = await harness.waitForLogs(1);
+    expect(log).toMatchObject({ configName: 'primary', statusCode: 200, inputTokens: 3, outputTokens: 5, tags: { team: 'legal' }, contentOmitted: true });
+    expect(JSON.stringify(log)).not.toContain('secret');
+    expect(log.requestHeaders ?? undefined).toBeUndefined();
+    expect(harness.upstreams.primary.requests[0].headers[NO_LOG_HEADER]).toBeUndefined();
+  });
+
+  test("covers every request of a service's [no_log] client keys", async () => {
+    harness = await createTestHarness({
+      serviceConfig: { noLogClientKeys: ['sk-private'] },
+      configs: [{ name: 'primary', fallback: { json: REPLY } }],
+    });
+
+    await harness.request('/v1/messages', { headers: { 'x-api-key': 'sk-private' }, body: BODY });
+    await harness.request('/v1/messages', { headers: { 'x-api-key': 'sk-public' }, body: BODY });
+
+    const [hidden, kept] = await harness.waitForLogs(2);
+    expect(hidden.contentOmitted).toBe(true);
+    expect(kept.contentOmitted ?? undefined).toBeUndefined();
+    expect(kept.requestBody).toContain('my secret prompt');
+  });
+
+  test('skips the response cache', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary', fallback: { json: REPLY } }] });
+    const { configManager, logger } = harness.proxy;
+    const core = createProxyCore(
+      configManager, logger, undefined, undefined, undefined, undefined, undefined, undefined,
+      { ttlSeconds: 60, maxBytes: 1 << 20 }
+    );
+    const send = () =>
+      core.proxies.claude.handleRequest(
+        new Request('http://paf.test/v1/messages', {
+          method: 'POST',
+          headers: { 'content-type': 'application/json', [NO_LOG_HEADER]: '1' },
+          body: JSON.stringify(BODY),
+        }),
+        configManager.getAllConfigs('claude')
+      );
+
+    const first = await send();
+    const second = await send();
+
+    expect(first.headers.get(RESPONSE_CACHE_HEADER)).toBeNull();
+    expect(second.headers.get(RESPONSE_CACHE_HEADER)).toBeNull();
+    expect(harness.upstreams.primary.requests).toHaveLength(2);
+    expect(core.responseCache.stats().entries).toBe(0);
+  });
+});
@@ -0,0 +1,170 @@
+import { afterEach, beforeEach, describe, expect, test } from 'bun:test';
+import { existsSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs';
+import { tmpdir, uptime } from 'node:os';
+import { join } from 'node:path';
+import { recoverStaleInstance, stopInstance } from '../server/monitoring/pidFile';
+
+// Process groups are created with setsid(1), which only Linux ships
+const linuxOnly = test.skipIf(process.platform !== 'linux');
+
+let dir: string;
+let pidPath: string;
+const spawned: number[] = [];
+
+beforeEach(() => {
+  dir = mkdtempSync(join(tmpdir(), 'paf-pid-'));
+  pidPath = join(dir, 'paf.pid');
+});
+
+afterEach(() => {
+  for (const pid of spawned.splice(0)) {
+    try {
+      process.kill(-pid, 'SIGKILL');
+    } catch {
+      // Already gone
+    }
+  }
+  rmSync(dir, { recursive: true, force: true });
+});
+
+function writeRecord(record: { pid: number; pgid?: number; command?: string; startedAt?: number }): void {
+  writeFileSync(pidPath, JSON.stringify({ startedAt: Date.now(), ...record }));
+}
+
+function alive(pid: number): boolean {
+  try {
+    process.kill(pid, 0);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+function groupMembers(pgid: number): Array<{ pid: number; args: string }> {
+  const output = Bun.spawnSync(['ps', '-A', '-o', 'pid=,pgid=,args=']).stdout.toString();
+  return output
+    .split('\n')
+    .map(line => line.trim().match(/^(\d+)\s+(\d+)\s+(.*)$/))
+    .filter((match): match is RegExpMatchArray => match !== null && Number(match[2]) === pgid)
+    .map(match => ({ pid: Number(match[1]), args: match[3] }));
+}
+
+/**
+ * Start `script` as the leader of a new process group; returns the group id (the leader's pid)
+ */
+function startGroup(script: string): { pgid: number; exited: Promise<number> } {
+  const child = Bun.spawn(['setsid', 'sh', '-c', script]);
+  spawned.push(child.pid);
+  return { pgid: child.pid, exited: child.exited };
+}
+
+async function waitFor(condition: () => boolean): Promise<void> {
+  const deadline = Date.now() + 2000;
+  while (!condition() && Date.now() < deadline) {
+    await Bun.sleep(20);
+  }
+}
+
+describe('stopInstance', () => {
+  test('reports not_running without a PID file', async () => {
+    expect(await stopInstance(pidPath)).toEqual({ state: 'not_running' });
+  });
+
+  test('ignores and removes a record from before the last boot', async () => {
+    writeRecord({ pid: 1, pgid: 1, startedAt: Date.now() - uptime() * 1000 - 60_000 });
+
+    expect(await stopInstance(pidPath)).toEqual({ state: 'not_running' });
+    expect(existsSync(pidPath)).toBe(false);
+  });
+
+  test('never signals the caller', async () => {
+    writeRecord({ pid: process.pid });
+
+    expect(await stopInstance(pidPath)).toEqual({ state: 'not_running' });
+    expect(existsSync(pidPath)).toBe(true);
+  });
+
+  linuxOnly('terminates the whole group of a live server', async () => {
+    const { pgid } = startGroup('sleep 61 & exec sleep 62');
+    await waitFor(() => groupMembers(pgid).length === 2);
+    const members = groupMembers(pgid);
+    writeRecord({ pid: pgid, pgid, command: 'sleep 62' });
+
+    expect(await stopInstance(pidPath)).toEqual({ state: 'stopped', pid: pgid, group: true });
+    await waitFor(() => members.every(member => !alive(member.pid)));
+    expect(members.filter(member => alive(member.pid))).toEqual([]);
+    expect(existsSync(pidPath)).toBe(false);
+  });
+
+  linuxOnly('terminates orphans that still run the recorded command', async () => {
+    const { pgid, exited } = startGroup('sleep 63 & exit 0');
+    await exited;
+    await waitFor(() => groupMembers(pgid).length === 1);
+    const [orphan] = groupMembers(pgid);
+    writeRecord({ pid: pgid, pgid, command: orphan.args });
+
+    expect(await stopInstance(pidPath)).toEqual({ state: 'stopped', pid: pgid, group: true });
+    await waitFor(() => !alive(orphan.pid));
+    expect(alive(orphan.pid)).toBe(false);
+  });
+
+  linuxOnly('leaves a group it cannot vouch for alone', async () => {
+    const { pgid, exited } = startGroup('sleep 64 & exit 0');
+    await exited;
+    await waitFor(() => groupMembers(pgid).length === 1);
+    const [stranger] = groupMembers(pgid);
+
+    // The leader is gone and what is left of its group runs something else: a reused group id
+    writeRecord({ pid: pgid, pgid, command: 'bun run server/index.ts' });
+    expect(await stopInstance(pidPath)).toEqual({ state: 'not_running' });
+    expect(alive(stranger.pid)).toBe(true);
+
+    // Records from before commands were stored can't be vouched for either
+    writeRecord({ pid: pgid, pgid });
+    expect(await stopInstance(pidPath)).toEqual({ state: 'not_running' });
+    expect(alive(stranger.pid)).toBe(true);
+    expect(existsSync(pidPath)).toBe(false);
+  });
+
+  linuxOnly('signals only the server when it does not lead its group', async () => {
+    // The shell leads the group and reaps the server, then stays around
+    const { pgid } = startGroup('sleep 65 & wait; sleep 66');
+    await waitFor(() => groupMembers(pgid).length === 2);
+    const server = groupMembers(pgid).find(member => member.pid !== pgid)!;
+
+    // Started from a shell: the group belongs to the shell, not to the server
+    writeRecord({ pid: server.pid, pgid, command: server.args });
+    expect(await stopInstance(pidPath)).toEqual({ state: 'stopped', pid: server.pid, group: false });
+    await waitFor(() => !alive(server.pid));
+    expect(alive(server.pid)).toBe(false);
+    expect(alive(pgid)).toBe(true);
+  });
+});
+
+describe('recoverStaleInstance', () => {
+  // Nothing listens on port 1, so the recorded server never counts as answering
+  const statusUrl = 'http://127.0.0.1:1/api/status';
+
+  linuxOnly('clears a server that does not lead its group without touching the group', async () => {
+    const { pgid } = startGroup('sleep 67 & wait; sleep 68');
+    await waitFor(() => groupMembers(pgid).length === 2);
+    const server = groupMembers(pgid).find(member => member.pid !== pgid)!;
+    writeRecord({ pid: server.pid, pgid, command: server.args });
+
+    expect(await recoverStaleInstance(pidPath, statusUrl)).toEqual({ state: 'cleaned', pid: server.pid, killed: true });
+    await waitFor(() => !alive(server.pid));
+    expect(alive(server.pid)).toBe(false);
+    expect(alive(pgid)).toBe(true);
+    expect(existsSync(pidPath)).toBe(false);
+  });
+
+  linuxOnly('leaves the group of a dead server that did not lead it', async () => {
+    const { pgid } = startGroup('exec sleep 69');
+    await waitFor(() => groupMembers(pgid).length === 1);
+
+    // The recorded server is gone; its former group still runs the same command but is not ours to end
+    writeRecord({ pid: 999_999, pgid, command: 'sleep 69' });
+    expect(await recoverStaleInstance(pidPath, statusUrl)).toEqual({ state: 'cleaned', pid: 999_999, killed: false });
+    expect(alive(pgid)).toBe(true);
+  });
+});
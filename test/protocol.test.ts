@@ -0,0 +1,142 @@
+// Wire format compatibility - the v1 fixtures are what existing consumers parse. Fields may be added to
+// these payloads, so outputs are matched as supersets; a renamed, removed or retyped field fails here.
+
+import { describe, expect, test } from 'bun:test';
+import type { RequestLog } from '../server/logging/database';
+import { normalizeRealtimeEvent, toWireRequestLog, WIRE_VERSION } from '../server/protocol';
+import {
+  attachRealtimeClient,
+  createRealtimeClientData,
+  RealtimeHub,
+  type RealtimeEventStore,
+  type RealtimeSocket,
+} from '../server/realtime/hub';
+import requestLogFixture from './fixtures/wire/request-log.v1.json';
+import requestCompletedFixture from './fixtures/wire/request-completed.v1.json';
+import legacyEvent from './fixtures/wire/request-completed.legacy.json';
+
+function fakeSocket(sent: string[], since?: number): RealtimeSocket {
+  return {
+    data: createRealtimeClientData(['claude', 'codex'], { since }),
+    send: (payload: string) => {
+      sent.push(payload);
+      return payload.length;
+    },
+  } as unknown as RealtimeSocket;
+}
+
+function memoryStore(): RealtimeEventStore & { payloads: string[] } {
+  const payloads: string[] = [];
+  return {
+    payloads,
+    saveRealtimeEvent: (_service, _type, _timestamp, payload) => {
+      payloads.push(payload);
+    },
+    getRealtimeEventsSince: () => payloads,
+    pruneRealtimeEvents: () => 0,
+  };
+}
+
+describe('request log wire format v1', () => {
+  test('keeps every v1 field', () => {
+    const wire = toWireRequestLog(requestLogFixture.log as RequestLog);
+    expect(wire).toMatchObject(requestLogFixture.wire);
+  });
+
+  test('version matches the fixtures', () => {
+    expect(WIRE_VERSION).toBe(requestLogFixture.wire.v as typeof WIRE_VERSION);
+  });
+
+  test('rows predating the service column are claude', () => {
+    const log = { ...requestLogFixture.log, service: undefined } as RequestLog;
+    expect(toWireRequestLog(log).service).toBe('claude');
+  });
+
+  test('omits usage when no tokens or model were recorded', () => {
+    const log = { ...requestLogFixture.log, inputTokens: undefined, outputTokens: undefined, model: undefined };
+    expect(toWireRequestLog(log as RequestLog).usage).toBeUndefined();
+  });
+});
+
+describe('realtime event wire format v1', () => {
+  test('request_completed keeps every v1 field', () => {
+    const hub = new RealtimeHub({ service: 'codex' });
+    const sent: string[] = [];
+    hub.addClient(fakeSocket(sent));
+
+    hub.publishRequestLog(requestCompletedFixture.log as RequestLog);
+
+    expect(sent).toHaveLength(1);
+    expect(JSON.parse(sent[0])).toMatchObject(requestCompletedFixture.event);
+  });
+
+  test('events persisted before versioning replay as v1', () => {
+    const store = memoryStore();
+    store.payloads.push(JSON.stringify(legacyEvent));
+    const hub = new RealtimeHub({ service: 'claude', store, replayMinutes: 60 });
+
+    const [event] = hub.getRecentEvents();
+    expect(event).toEqual({ ...legacyEvent, v: WIRE_VERSION });
+  });
+
+  test('normalizes a legacy payload without touching its data', () => {
+    expect(normalizeRealtimeEvent(legacyEvent)).toMatchObject({ ...legacyEvent, v: 1 });
+  });
+
+  test('published events round-trip through the replay store', () => {
+    const store = memoryStore();
+    const hub = new RealtimeHub({ service: 'codex', store, replayMinutes: 60 });
+
+    hub.publishRequestLog(requestCompletedFixture.log as RequestLog);
+
+    expect(hub.getRecentEvents()).toEqual([requestCompletedFixture.event]);
+  });
+});
+
+describe('realtime replay', () => {
+  // Filters like the SQLite store does, so the hub's resume point is what gets tested
+  function timedStore(events: Array<{ service: string; timestamp: number }>): RealtimeEventStore {
+    return {
+      saveRealtimeEvent: () => {},
+      getRealtimeEventsSince: (service, since) =>
+        events
+          .filter(event => event.service === service && event.timestamp >= since)
+          .map(event => JSON.stringify({ v: WIRE_VERSION, type: 'request_completed', data: { id: `${event.service}-${event.timestamp}` }, ...event })),
+      pruneRealtimeEvents: () => 0,
+    };
+  }
+
+  test('merges services by time and resumes from since', () => {
+    const now = Date.now();
+    const store = timedStore([
+      { service: 'claude', timestamp: now - 3000 },
+      { service: 'claude', timestamp: now - 1000 },
+      { service: 'codex', timestamp: now - 2000 },
+    ]);
+    const hubs = [
+      new RealtimeHub({ service: 'claude', store, replayMinutes: 60 }),
+      new RealtimeHub({ service: 'codex', store, replayMinutes: 60 }),
+    ];
+
+    const fresh: string[] = [];
+    attachRealtimeClient(fakeSocket(fresh), hubs);
+    expect(fresh.map(payload => JSON.parse(payload).data.id)).toEqual([
+      `claude-${now - 3000}`,
+      `codex-${now - 2000}`,
+      `claude-${now - 1000}`,
+    ]);
+    expect(fresh.every(payload => JSON.parse(payload).historical)).toBe(true);
+
+    // The boundary event is sent again; the client drops it by id
+    const resumed: string[] = [];
+    attachRealtimeClient(fakeSocket(resumed, now - 2000), hubs);
+    expect(resumed.map(payload => JSON.parse(payload).data.id)).toEqual([`codex-${now - 2000}`, `claude-${now - 1000}`]);
+  });
+
+  test('never replays past the window', () => {
+    const now = Date.now();
+    const hub = new RealtimeHub({ service: 'claude', store: timedStore([{ service: 'claude', timestamp: now - 120_000 }]), replayMinutes: 1 });
+
+    expect(hub.getRecentEvents(now - 180_000)).toEqual([]);
+  });
+});
@@ -0,0 +1,131 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { parseReplayFile, type ReplayJob, type ReplayRequest } from '../server/replay/replay';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = JSON.stringify({ model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] });
+
+const HAR = JSON.stringify({
+  log: {
+    entries: [
+      {
+        request: {
+          method: 'post',
+          url: 'https://api.anthropic.com/v1/messages?beta=true',
+          headers: [
+            { name: 'X-Api-Key', value: 'sk-captured' },
+            { name: 'Content-Type', value: 'application/json' },
+            { name: 'anthropic-version', value: '2023-06-01' },
+          ],
+          postData: { text: BODY },
+        },
+      },
+      { request: { method: 'GET', url: 'https://api.anthropic.com/v1/models', headers: [] } },
+    ],
+  },
+});
+
+function requests(text: string): { requests: ReplayRequest[]; skipped: number } {
+  const parsed = parseReplayFile(text);
+  if ('error' in parsed) {
+    throw new Error(parsed.error);
+  }
+  return parsed;
+}
+
+describe('parseReplayFile', () => {
+  test('reads HAR entries without the capture credentials', () => {
+    const parsed = requests(HAR);
+
+    expect(parsed.skipped).toBe(1);
+    expect(parsed.requests).toEqual([{
+      method: 'POST',
+      path: '/v1/messages?beta=true',
+      headers: { 'content-type': 'application/json', 'anthropic-version': '2023-06-01' },
+      body: BODY,
+    }]);
+  });
+
+  test('reads JSON lines and skips truncated bodies', () => {
+    const lines = [
+      JSON.stringify({ method: 'POST', path: '/v1/chat/completions', headers: { authorization: 'Bearer x' }, body: { model: 'gpt-5', messages: [] } }),
+      JSON.stringify({ method: 'POST', path: '/v1/messages', body: '{"model":', body_truncated: true }),
+      '',
+      JSON.stringify({ method: 'POST', url: 'https://example.com/v1/responses', body: '{"model":"gpt-5","input":"Hi"}' }),
+    ].join('\n');
+
+    const parsed = requests(lines);
+
+    expect(parsed.skipped).toBe(1);
+    expect(parsed.requests.map(request => request.path)).toEqual(['/v1/chat/completions', '/v1/responses']);
+    expect(parsed.requests[0].headers).toEqual({});
+    expect(JSON.parse(parsed.requests[0].body)).toEqual({ model: 'gpt-5', messages: [] });
+  });
+
+  test('rejects files with nothing to replay', () => {
+    expect(parseReplayFile('  ')).toEqual({ error: 'File is empty' });
+    expect(parseReplayFile('{"method":"POST"}\nnot json')).toEqual({
+      error: 'Line 2 is not JSON; expected a HAR file or JSON lines',
+    });
+    const onlyModels = parseReplayFile(JSON.stringify({ method: 'GET', path: '/v1/models', body: '{}' }));
+    expect('error' in onlyModels && onlyModels.error).toContain('No replayable requests found (1 skipped');
+  });
+});
+
+describe('ReplayRunner', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  async function finished(job: ReplayJob): Promise<ReplayJob> {
+    const deadline = Date.now() + 2000;
+    while (job.status === 'running' && Date.now() < deadline) {
+      await Bun.sleep(10);
+    }
+    return job;
+  }
+
+  test('sends every request to the chosen config only, tagged with the job', async () => {
+    harness = await createTestHarness({
+      configs: [
+        { name: 'primary' },
+        { name: 'candidate', fallback: { json: { id: 'msg_1', usage: { input_tokens: 1, output_tokens: 1 } } } },
+      ],
+    });
+    const proxy = harness.proxy;
+    const candidate = proxy.configManager.getServiceConfig('claude')!.configs.find(config => config.name === 'candidate')!;
+    const { requests: captured } = requests(HAR);
+
+    const job = await finished(proxy.replays.start('claude', candidate, [...captured, ...captured], 1, 100, (request, servers) =>
+      proxy.proxies.claude.handleRequest(request, servers)
+    ));
+
+    expect(job).toMatchObject({ status: 'completed', total: 2, sent: 2, succeeded: 2, failed: 0, skipped: 1, statuses: { '200': 2 } });
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+    const sent = harness.upstreams.candidate.requests;
+    expect(sent.map(request => request.path)).toEqual(['/v1/messages?beta=true', '/v1/messages?beta=true']);
+    expect(Object.values(sent[0].headers)).not.toContain('sk-captured');
+
+    const logs = await harness.waitForLogs(2);
+    expect(logs.map(log => log.tags)).toEqual([{ replay: job.id }, { replay: job.id }]);
+  });
+
+  test('cancel stops the remaining sends', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+    const proxy = harness.proxy;
+    const config = proxy.configManager.getServiceConfig('claude')!.configs[0];
+    const { requests: captured } = requests(HAR);
+
+    const job = proxy.replays.start('claude', config, [captured[0], captured[0], captured[0]], 0, 1, (request, servers) =>
+      proxy.proxies.claude.handleRequest(request, servers)
+    );
+    proxy.replays.cancel(job.id);
+    await Bun.sleep(50);
+
+    expect(job.status).toBe('cancelled');
+    expect(job.sent).toBe(1);
+    expect(proxy.replays.get(job.id)).toBe(job);
+  });
+});
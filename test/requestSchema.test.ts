@@ -0,0 +1,107 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { describeViolations, hasRequestSchema, validateRequestBody } from '../server/proxy/requestSchema';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const MESSAGES_BODY = {
+  model: 'claude-sonnet-4-5',
+  max_tokens: 64,
+  messages: [{ role: 'user', content: 'Hi' }],
+};
+
+describe('validateRequestBody', () => {
+  test('accepts valid bodies and fields the schemas do not know', () => {
+    expect(validateRequestBody('/v1/messages', { ...MESSAGES_BODY, some_new_feature: { on: true } })).toEqual([]);
+    expect(validateRequestBody('/v1/messages/count_tokens', { model: 'm', messages: MESSAGES_BODY.messages })).toEqual([]);
+    expect(validateRequestBody('/v1/responses', { model: 'gpt-5', input: 'Hi', instructions: null })).toEqual([]);
+  });
+
+  test('only checks endpoints with a schema', () => {
+    expect(hasRequestSchema('/v1/messages')).toBe(true);
+    expect(hasRequestSchema('/v1/chat/completions')).toBe(false);
+    expect(validateRequestBody('/v1/chat/completions', { nonsense: 1 })).toEqual([]);
+  });
+
+  test('names every offending path', () => {
+    const violations = validateRequestBody('/v1/messages', {
+      model: 'claude-sonnet-4-5',
+      temperature: 1.5,
+      messages: [
+        { role: 'user', content: 'Hi' },
+        { role: 'system', content: [{ text: 'no type' }] },
+      ],
+    });
+
+    expect(violations).toEqual([
+      { path: 'max_tokens', message: 'is required' },
+      { path: 'messages[1].role', message: 'must be one of "user", "assistant"' },
+      { path: 'messages[1].content[0].type', message: 'is required' },
+      { path: 'temperature', message: 'must be at most 1' },
+    ]);
+    expect(describeViolations(violations.slice(0, 1))).toBe('Invalid request: max_tokens: is required');
+  });
+
+  test('reports types, ranges and empty arrays', () => {
+    expect(validateRequestBody('/v1/messages', { ...MESSAGES_BODY, max_tokens: 0, messages: [] })).toEqual([
+      { path: 'messages', message: 'must have at least 1 item(s)' },
+      { path: 'max_tokens', message: 'must be at least 1' },
+    ]);
+    expect(validateRequestBody('/v1/responses', { model: 'gpt-5', input: 42 })).toEqual([
+      { path: 'input', message: 'must be string or array, got integer' },
+    ]);
+    expect(validateRequestBody('/v1/messages', [])).toEqual([{ path: 'body', message: 'must be object, got array' }]);
+  });
+
+  test('stops after five violations', () => {
+    const messages = Array.from({ length: 10 }, () => ({ role: 'robot', content: 'x' }));
+    expect(validateRequestBody('/v1/messages', { ...MESSAGES_BODY, messages })).toHaveLength(5);
+  });
+});
+
+describe('validate_requests', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('answers invalid bodies locally with a 400', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { validateRequests: true },
+      configs: [{ name: 'primary' }],
+    });
+
+    const response = await harness.request('/v1/messages', { body: { ...MESSAGES_BODY, max_tokens: 'lots' } });
+
+    expect(response.status).toBe(400);
+    expect(await response.json()).toMatchObject({
+      type: 'error',
+      error: { type: 'invalid_request_error', message: 'Invalid request: max_tokens: must be integer, got string' },
+    });
+    expect(harness.upstreams.primary.requests).toHaveLength(0);
+    const [log] = await harness.waitForLogs(1);
+    expect(log).toMatchObject({ statusCode: 400, outcome: 'invalid' });
+    expect(harness.loadBalancer.getServerHealth('primary').consecutiveFailures).toBe(0);
+  });
+
+  test('forwards valid bodies', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { validateRequests: true },
+      configs: [{ name: 'primary' }],
+    });
+
+    const response = await harness.request('/v1/messages', { body: MESSAGES_BODY });
+
+    expect(response.status).toBe(200);
+    expect(harness.upstreams.primary.requests).toHaveLength(1);
+  });
+
+  test('is off by default', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }] });
+
+    const response = await harness.request('/v1/messages', { body: { model: 'claude-sonnet-4-5' } });
+
+    expect(response.status).toBe(200);
+    expect(harness.upstreams.primary.requests).toHaveLength(1);
+  });
+});
@@ -0,0 +1,151 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { createProxyCore } from '../server/core';
+import {
+  isCacheableRequest,
+  RESPONSE_CACHE_HEADER,
+  ResponseCache,
+  responseCacheKey,
+  type CachedResponse,
+  type ResponseCacheStore,
+} from '../server/proxy/responseCache';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+function entry(key: string, body: string, service = 'claude') {
+  return { key, service, path: '/v1/messages', configName: 'primary', status: 200, headers: [] as [string, string][], body };
+}
+
+function memoryStore(): ResponseCacheStore & { saved: Map<string, CachedResponse> } {
+  const saved = new Map<string, CachedResponse>();
+  return {
+    saved,
+    loadCachedResponses: now => [...saved.values()].filter(cached => cached.expiresAt > now),
+    saveCachedResponse: cached => {
+      saved.set(cached.key, cached);
+    },
+    deleteCachedResponses: keys => keys.forEach(key => saved.delete(key)),
+  };
+}
+
+describe('responseCacheKey', () => {
+  test('ignores field order and per-caller fields', () => {
+    const key = responseCacheKey('claude', '/v1/messages', BODY);
+    const reordered = { messages: BODY.messages, max_tokens: 16, model: 'claude-sonnet-4-5', metadata: { user_id: 'u1' }, user: 'x', stream: false };
+
+    expect(responseCacheKey('claude', '/v1/messages', reordered)).toBe(key);
+    expect(responseCacheKey('codex', '/v1/messages', BODY)).not.toBe(key);
+    expect(responseCacheKey('claude', '/v1/messages', { ...BODY, max_tokens: 17 })).not.toBe(key);
+  });
+});
+
+describe('isCacheableRequest', () => {
+  const post = (headers: Record<string, string> = {}) =>
+    new Request('http://paf.test/v1/messages', { method: 'POST', headers, body: '{}' });
+
+  test('takes buffered JSON POSTs only', () => {
+    expect(isCacheableRequest(post(), BODY)).toBe(true);
+    expect(isCacheableRequest(post(), { ...BODY, stream: true })).toBe(false);
+    expect(isCacheableRequest(post({ accept: 'text/event-stream' }), BODY)).toBe(false);
+    expect(isCacheableRequest(post(), [BODY])).toBe(false);
+    expect(isCacheableRequest(new Request('http://paf.test/v1/models'), BODY)).toBe(false);
+  });
+
+  test('honours cache-control opt-outs', () => {
+    expect(isCacheableRequest(post({ 'cache-control': 'no-cache' }), BODY)).toBe(false);
+    expect(isCacheableRequest(post({ 'cache-control': 'No-Store' }), BODY)).toBe(false);
+  });
+});
+
+describe('ResponseCache', () => {
+  test('is off without a ttl or size', () => {
+    expect(new ResponseCache({ ttlSeconds: 0, maxBytes: 1024 }).isEnabled()).toBe(false);
+    expect(new ResponseCache({ ttlSeconds: 60, maxBytes: 0 }).isEnabled()).toBe(false);
+  });
+
+  test('counts hits and misses', () => {
+    const cache = new ResponseCache({ ttlSeconds: 60, maxBytes: 1024 });
+    cache.set(entry('a', 'answer'));
+
+    expect(cache.get('a')?.body).toBe('answer');
+    expect(cache.get('b')).toBeNull();
+    expect(cache.stats()).toMatchObject({ enabled: true, entries: 1, bytes: 6, hits: 1, misses: 1 });
+    expect(cache.list()).toMatchObject([{ key: 'a', hits: 1 }]);
+  });
+
+  test('evicts the least recently used entries past max_bytes', () => {
+    const cache = new ResponseCache({ ttlSeconds: 60, maxBytes: 10 });
+    cache.set(entry('a', '1234'));
+    cache.set(entry('b', '1234'));
+    cache.get('a');
+    cache.set(entry('c', '1234'));
+    cache.set(entry('huge', 'x'.repeat(11)));
+
+    expect(cache.list().map(cached => cached.key)).toEqual(['a', 'c']);
+  });
+
+  test('drops expired entries', () => {
+    const cache = new ResponseCache({ ttlSeconds: 60, maxBytes: 1024 });
+    cache.set(entry('a', 'answer'));
+    cache.get('a')!.expiresAt = Date.now() - 1;
+
+    expect(cache.get('a')).toBeNull();
+    expect(cache.stats().entries).toBe(0);
+  });
+
+  test('writes through to its store and reloads from it', () => {
+    const store = memoryStore();
+    const cache = new ResponseCache({ ttlSeconds: 60, maxBytes: 1024 }, store);
+    cache.set(entry('a', 'claude answer'));
+    cache.set(entry('b', 'codex answer', 'codex'));
+
+    expect(cache.clear('codex')).toBe(1);
+    expect([...store.saved.keys()]).toEqual(['a']);
+    const reloaded = new ResponseCache({ ttlSeconds: 60, maxBytes: 1024 }, store);
+    expect(reloaded.get('a')?.body).toBe('claude answer');
+  });
+});
+
+describe('response_cache', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('answers a repeated request without going upstream', async () => {
+    harness = await createTestHarness({
+      configs: [{ name: 'primary', fallback: { json: { id: 'msg_1', type: 'message', content: [], usage: { input_tokens: 3, output_tokens: 5 } } } }],
+    });
+    const { configManager, logger } = harness.proxy;
+    const core = createProxyCore(
+      configManager, logger, undefined, undefined, undefined, undefined, undefined, undefined,
+      { ttlSeconds: 60, maxBytes: 1 << 20 }
+    );
+    const send = (headers: Record<string, string> = {}) =>
+      core.proxies.claude.handleRequest(
+        new Request('http://paf.test/v1/messages', {
+          method: 'POST',
+          headers: { 'content-type': 'application/json', ...headers },
+          body: JSON.stringify(BODY),
+        }),
+        configManager.getAllConfigs('claude')
+      );
+
+    const first = await send();
+    const second = await send();
+    const optedOut = await send({ 'cache-control': 'no-cache' });
+
+    expect(first.headers.get(RESPONSE_CACHE_HEADER)).toBe('miss');
+    expect(second.headers.get(RESPONSE_CACHE_HEADER)).toBe('hit');
+    expect(await second.json()).toMatchObject({ id: 'msg_1' });
+    expect(optedOut.headers.get(RESPONSE_CACHE_HEADER)).toBeNull();
+    expect(harness.upstreams.primary.requests).toHaveLength(2);
+
+    const logs = await harness.waitForLogs(3);
+    expect(logs[1]).toMatchObject({ configName: 'primary', statusCode: 200, outcome: 'cached' });
+    expect(logs[1].inputTokens ?? undefined).toBeUndefined();
+    expect(core.responseCache.stats()).toMatchObject({ entries: 1, hits: 1, misses: 1 });
+  });
+});
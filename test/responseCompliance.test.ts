@@ -0,0 +1,136 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { checkJsonResponse, checkSseResponse, hasResponseProtocol } from '../server/proxy/responseCompliance';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const MESSAGE = {
+  id: 'msg_01',
+  type: 'message',
+  role: 'assistant',
+  content: [{ type: 'text', text: 'Hi' }],
+  usage: { input_tokens: 3, output_tokens: 5 },
+};
+
+const REQUEST = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+function sse(events: unknown[]): string {
+  return events.map(event => `data: ${typeof event === 'string' ? event : JSON.stringify(event)}\n\n`).join('');
+}
+
+const ANTHROPIC_STREAM = [
+  { type: 'message_start', message: { ...MESSAGE, content: [], usage: { input_tokens: 3, output_tokens: 0 } } },
+  { type: 'content_block_start', index: 0, content_block: { type: 'text', text: '' } },
+  { type: 'content_block_delta', index: 0, delta: { type: 'text_delta', text: 'Hi' } },
+  { type: 'content_block_stop', index: 0 },
+  { type: 'message_delta', delta: { stop_reason: 'end_turn' }, usage: { output_tokens: 5 } },
+  { type: 'message_stop' },
+];
+
+describe('checkJsonResponse', () => {
+  test('accepts compliant bodies', () => {
+    expect(checkJsonResponse('/v1/messages', 'application/json', JSON.stringify(MESSAGE))).toEqual([]);
+    expect(checkJsonResponse('/v1/messages/count_tokens', 'application/json', '{"input_tokens":3}')).toEqual([]);
+    expect(checkJsonResponse('/v1/chat/completions', 'application/json; charset=utf-8',
+      JSON.stringify({ choices: [], usage: { prompt_tokens: 1, completion_tokens: 1 } }))).toEqual([]);
+  });
+
+  test('flags content type, shape and usage problems', () => {
+    expect(checkJsonResponse('/v1/messages', 'text/plain', JSON.stringify({ ...MESSAGE, usage: undefined }))).toEqual([
+      'wrong_content_type: got "text/plain"',
+      'missing_usage: usage.input_tokens/output_tokens missing',
+    ]);
+    expect(checkJsonResponse('/v1/responses', 'application/json', '{"object":"list"}')).toEqual([
+      'unexpected_shape: expected object "response" with an output array',
+      'missing_usage: usage.input_tokens/output_tokens missing',
+    ]);
+    expect(checkJsonResponse('/v1/messages', 'application/json', 'oops')).toEqual(['invalid_json: response body is not JSON']);
+  });
+
+  test('ignores endpoints without a known protocol', () => {
+    expect(hasResponseProtocol('/v1/models')).toBe(false);
+    expect(checkJsonResponse('/v1/models', 'text/html', '<html>')).toEqual([]);
+  });
+});
+
+describe('checkSseResponse', () => {
+  test('accepts a well-formed Messages stream', () => {
+    expect(checkSseResponse('/v1/messages', sse(ANTHROPIC_STREAM), {})).toEqual([]);
+  });
+
+  test('flags events out of order and a missing message_stop', () => {
+    const stream = [ANTHROPIC_STREAM[0], ANTHROPIC_STREAM[2], ANTHROPIC_STREAM[4]];
+    expect(checkSseResponse('/v1/messages', sse(stream), {})).toEqual([
+      'bad_event_order: content_block_delta for block 0 that was never started',
+      'missing_terminal_event: no message_stop',
+    ]);
+  });
+
+  test('judges a stream only up to its error event', () => {
+    const stream = [ANTHROPIC_STREAM[0], { type: 'error', error: { type: 'overloaded_error', message: 'busy' } }];
+    expect(checkSseResponse('/v1/messages', sse(stream), {})).toEqual([]);
+  });
+
+  test('checks Responses sequence numbers and the terminal event', () => {
+    const stream = [
+      { type: 'response.created', sequence_number: 0 },
+      { type: 'response.output_text.delta', sequence_number: 2 },
+      { type: 'response.output_text.delta', sequence_number: 1 },
+    ];
+    expect(checkSseResponse('/v1/responses', sse(stream), {})).toEqual([
+      'bad_event_order: sequence_number 1 after 2',
+      'missing_terminal_event: no response.completed, response.incomplete or response.failed',
+    ]);
+  });
+
+  test('expects chat completion usage only when it was requested', () => {
+    const stream = sse([{ choices: [{ delta: { content: 'Hi' } }] }, '[DONE]']);
+    expect(checkSseResponse('/v1/chat/completions', stream, {})).toEqual([]);
+    expect(checkSseResponse('/v1/chat/completions', stream, { stream_options: { include_usage: true } })).toEqual([
+      'missing_usage: include_usage was requested but no chunk carried usage',
+    ]);
+  });
+});
+
+describe('validate_responses', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('stores issues on the log and scores each config', async () => {
+    harness = await createTestHarness({
+      serviceConfig: { validateResponses: true },
+      configs: [{
+        name: 'relay',
+        responses: [{ json: MESSAGE }, { json: { ...MESSAGE, usage: {} } }],
+      }],
+    });
+
+    await harness.request('/v1/messages', { body: REQUEST });
+    await harness.request('/v1/messages', { body: REQUEST });
+
+    const logs = await harness.waitForLogs(2);
+    expect(logs.map(log => log.protocolIssues)).toEqual([
+      [],
+      ['missing_usage: usage.input_tokens/output_tokens missing'],
+    ]);
+    expect(harness.proxy.logger.getComplianceStats(60, 'claude')).toEqual([{
+      service: 'claude',
+      configName: 'relay',
+      checked: 2,
+      violating: 1,
+      score: 0.5,
+      issues: { missing_usage: 1 },
+    }]);
+  });
+
+  test('leaves responses unchecked by default', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'relay', responses: [{ json: {} }] }] });
+
+    await harness.request('/v1/messages', { body: REQUEST });
+
+    const [log] = await harness.waitForLogs(1);
+    expect(log.protocolIssues).toBeUndefined();
+  });
+});
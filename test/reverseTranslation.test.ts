@@ -0,0 +1,257 @@
+import { describe, expect, test } from 'bun:test';
+import {
+  chatCompletionsToMessages,
+  createMessagesToChatStream,
+  createMessagesToResponsesStream,
+  responsesToMessages,
+} from '../server/proxy/reverseTranslation';
+
+/**
+ * Push `chunks` through `transform` as separate writes and return the events that come out
+ */
+async function translateStream(transform: TransformStream<Uint8Array, Uint8Array>, chunks: string[]): Promise<any[]> {
+  const encoder = new TextEncoder();
+  const source = new ReadableStream<Uint8Array>({
+    start(controller) {
+      chunks.forEach(chunk => controller.enqueue(encoder.encode(chunk)));
+      controller.close();
+    },
+  });
+  const text = await new Response(source.pipeThrough(transform)).text();
+  return text
+    .split('\n\n')
+    .filter(Boolean)
+    .map(event => {
+      const data = event.split('\n').find(line => line.startsWith('data: '))!.slice(6);
+      return data === '[DONE]' ? data : JSON.parse(data);
+    });
+}
+
+function messagesEvent(data: { type: string } & Record<string, unknown>): string {
+  return `event: ${data.type}\ndata: ${JSON.stringify(data)}\n\n`;
+}
+
+// Text, then a tool call whose arguments arrive in two pieces; 90 of the 100 prompt tokens were cached
+const MESSAGES_STREAM = [
+  messagesEvent({
+    type: 'message_start',
+    message: {
+      id: 'msg_1',
+      model: 'claude-sonnet-4-5',
+      content: [],
+      usage: { input_tokens: 10, cache_read_input_tokens: 90, output_tokens: 1 },
+    },
+  }),
+  messagesEvent({ type: 'content_block_start', index: 0, content_block: { type: 'text', text: '' } }),
+  messagesEvent({ type: 'content_block_delta', index: 0, delta: { type: 'text_delta', text: 'Checking.' } }),
+  messagesEvent({ type: 'content_block_stop', index: 0 }),
+  messagesEvent({
+    type: 'content_block_start',
+    index: 1,
+    content_block: { type: 'tool_use', id: 'toolu_1', name: 'get_weather', input: {} },
+  }),
+  messagesEvent({ type: 'content_block_delta', index: 1, delta: { type: 'input_json_delta', partial_json: '{"city":' } }),
+  messagesEvent({ type: 'content_block_delta', index: 1, delta: { type: 'input_json_delta', partial_json: '"Paris"}' } }),
+  messagesEvent({ type: 'content_block_stop', index: 1 }),
+  messagesEvent({ type: 'message_delta', delta: { stop_reason: 'tool_use' }, usage: { output_tokens: 15 } }),
+  messagesEvent({ type: 'message_stop' }),
+].join('');
+
+// Split mid-event, as network reads do
+const CHUNKS = [MESSAGES_STREAM.slice(0, 100), MESSAGES_STREAM.slice(100, 700), MESSAGES_STREAM.slice(700)];
+
+describe('chatCompletionsToMessages', () => {
+  test('turns tool calls into tool_use and groups parallel tool results in one user turn', () => {
+    const messages = chatCompletionsToMessages({
+      model: 'claude-sonnet-4-5',
+      max_completion_tokens: 512,
+      temperature: 1.5,
+      stop: 'END',
+      messages: [
+        { role: 'system', content: 'Be brief.' },
+        { role: 'developer', content: [{ type: 'text', text: 'Use metric units.' }] },
+        {
+          role: 'user',
+          content: [
+            { type: 'text', text: 'Weather here and there?' },
+            { type: 'image_url', image_url: { url: 'data:image/png;base64,iVBORw0KGgo=' } },
+          ],
+        },
+        {
+          role: 'assistant',
+          content: null,
+          tool_calls: [
+            { id: 'call_1', type: 'function', function: { name: 'get_weather', arguments: '{"city":"Paris"}' } },
+            { id: 'call_2', type: 'function', function: { name: 'get_weather', arguments: '{"city":"Oslo"}' } },
+          ],
+        },
+        { role: 'tool', tool_call_id: 'call_1', content: '18°C' },
+        { role: 'tool', tool_call_id: 'call_2', content: '4°C' },
+      ],
+      tools: [{ type: 'function', function: { name: 'get_weather', parameters: { type: 'object' } } }],
+      tool_choice: 'required',
+      parallel_tool_calls: false,
+    });
+
+    expect(messages).toEqual({
+      model: 'claude-sonnet-4-5',
+      max_tokens: 512,
+      system: 'Be brief.\n\nUse metric units.',
+      messages: [
+        {
+          role: 'user',
+          content: [
+            { type: 'text', text: 'Weather here and there?' },
+            { type: 'image', source: { type: 'base64', media_type: 'image/png', data: 'iVBORw0KGgo=' } },
+          ],
+        },
+        {
+          role: 'assistant',
+          content: [
+            { type: 'tool_use', id: 'call_1', name: 'get_weather', input: { city: 'Paris' } },
+            { type: 'tool_use', id: 'call_2', name: 'get_weather', input: { city: 'Oslo' } },
+          ],
+        },
+        {
+          role: 'user',
+          content: [
+            { type: 'tool_result', tool_use_id: 'call_1', content: '18°C' },
+            { type: 'tool_result', tool_use_id: 'call_2', content: '4°C' },
+          ],
+        },
+      ],
+      temperature: 1,
+      tools: [{ name: 'get_weather', description: undefined, input_schema: { type: 'object' } }],
+      tool_choice: { type: 'any', disable_parallel_tool_use: true },
+      stop_sequences: ['END'],
+    });
+  });
+});
+
+describe('responsesToMessages', () => {
+  test('maps instructions, function calls and their outputs', () => {
+    const messages = responsesToMessages({
+      model: 'claude-sonnet-4-5',
+      instructions: 'Be brief.',
+      stream: true,
+      input: [
+        {
+          role: 'user',
+          content: [
+            { type: 'input_text', text: 'Weather in Paris?' },
+            { type: 'input_image', image_url: 'https://example.test/map.png' },
+          ],
+        },
+        { type: 'reasoning', encrypted_content: 'opaque' },
+        { type: 'function_call', call_id: 'call_1', name: 'get_weather', arguments: '{"city":"Paris"}' },
+        { type: 'function_call_output', call_id: 'call_1', output: { celsius: 18 } },
+      ],
+      tools: [
+        { type: 'function', name: 'get_weather', description: 'Current weather', parameters: { type: 'object' } },
+        { type: 'web_search_preview' },
+      ],
+    });
+
+    expect(messages).toEqual({
+      model: 'claude-sonnet-4-5',
+      max_tokens: 4096,
+      system: 'Be brief.',
+      messages: [
+        {
+          role: 'user',
+          content: [
+            { type: 'text', text: 'Weather in Paris?' },
+            { type: 'image', source: { type: 'url', url: 'https://example.test/map.png' } },
+          ],
+        },
+        { role: 'assistant', content: [{ type: 'tool_use', id: 'call_1', name: 'get_weather', input: { city: 'Paris' } }] },
+        { role: 'user', content: [{ type: 'tool_result', tool_use_id: 'call_1', content: '{"celsius":18}' }] },
+      ],
+      stream: true,
+      tools: [{ name: 'get_weather', description: 'Current weather', input_schema: { type: 'object' } }],
+    });
+  });
+});
+
+describe('createMessagesToChatStream', () => {
+  test('streams text and tool calls as chunks, with usage when asked for', async () => {
+    const events = await translateStream(
+      createMessagesToChatStream({ model: 'claude-sonnet-4-5', stream_options: { include_usage: true } }),
+      CHUNKS
+    );
+
+    expect(events.at(-1)).toBe('[DONE]');
+    const chunks = events.slice(0, -1);
+    expect(new Set(chunks.map(chunk => chunk.id))).toEqual(new Set(['chatcmpl-msg_1']));
+    expect(chunks.map(chunk => chunk.choices[0]?.delta)).toEqual([
+      { role: 'assistant', content: '' },
+      { content: 'Checking.' },
+      { tool_calls: [{ index: 0, id: 'toolu_1', type: 'function', function: { name: 'get_weather', arguments: '' } }] },
+      { tool_calls: [{ index: 0, function: { arguments: '{"city":' } }] },
+      { tool_calls: [{ index: 0, function: { arguments: '"Paris"}' } }] },
+      {},
+      undefined,
+    ]);
+    expect(chunks.at(-2).choices[0].finish_reason).toBe('tool_calls');
+    expect(chunks.at(-1)).toMatchObject({
+      choices: [],
+      usage: { prompt_tokens: 100, completion_tokens: 15, total_tokens: 115, prompt_tokens_details: { cached_tokens: 90 } },
+    });
+  });
+
+  test('leaves usage out unless include_usage is set, and never ends a cut stream', async () => {
+    const events = await translateStream(createMessagesToChatStream({ model: 'claude-sonnet-4-5' }), CHUNKS);
+    expect(events.some(event => event !== '[DONE]' && event.usage)).toBe(false);
+
+    const cut = MESSAGES_STREAM.slice(0, MESSAGES_STREAM.indexOf('event: message_delta'));
+    const partial = await translateStream(createMessagesToChatStream({}), [cut]);
+    expect(partial).not.toContain('[DONE]');
+    expect(partial.some(event => event.choices?.[0]?.finish_reason)).toBe(false);
+  });
+});
+
+describe('createMessagesToResponsesStream', () => {
+  test('streams output items in order and completes with the usage', async () => {
+    const events = await translateStream(createMessagesToResponsesStream({ model: 'claude-sonnet-4-5' }), CHUNKS);
+
+    expect(events.map(event => event.type)).toEqual([
+      'response.created',
+      'response.in_progress',
+      'response.output_item.added',
+      'response.content_part.added',
+      'response.output_text.delta',
+      'response.output_text.done',
+      'response.content_part.done',
+      'response.output_item.done',
+      'response.output_item.added',
+      'response.function_call_arguments.delta',
+      'response.function_call_arguments.delta',
+      'response.function_call_arguments.done',
+      'response.output_item.done',
+      'response.completed',
+    ]);
+    expect(events.map(event => event.sequence_number)).toEqual(events.map((_, index) => index));
+    expect(events[11]).toMatchObject({ output_index: 1, item_id: 'fc_toolu_1', arguments: '{"city":"Paris"}' });
+
+    const { response } = events.at(-1);
+    expect(response).toMatchObject({
+      id: 'resp_msg_1',
+      status: 'completed',
+      output: [
+        { type: 'message', status: 'completed', content: [{ type: 'output_text', text: 'Checking.' }] },
+        { type: 'function_call', call_id: 'toolu_1', name: 'get_weather', arguments: '{"city":"Paris"}', status: 'completed' },
+      ],
+      usage: { input_tokens: 100, input_tokens_details: { cached_tokens: 90 }, output_tokens: 15, total_tokens: 115 },
+    });
+  });
+
+  test('reports max_tokens as an incomplete response', async () => {
+    const stream = MESSAGES_STREAM.replace('"stop_reason":"tool_use"', '"stop_reason":"max_tokens"');
+    const events = await translateStream(createMessagesToResponsesStream({}), [stream]);
+
+    expect(events.at(-1)).toMatchObject({
+      type: 'response.incomplete',
+      response: { status: 'incomplete', incomplete_details: { reason: 'max_output_tokens' } },
+    });
+  });
+});
@@ -0,0 +1,118 @@
+import { afterEach, beforeEach, describe, expect, test } from 'bun:test';
+import { createHash, generateKeyPairSync, sign, type KeyObject } from 'node:crypto';
+import { existsSync, mkdtempSync, readFileSync, rmSync, writeFileSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { installRelease, platformAssetName, type ReleaseInfo, verifyReleaseBinary } from '../server/update/selfUpdate';
+
+const ASSET = platformAssetName();
+const BINARY = new TextEncoder().encode('new paf binary');
+const CHECKSUMS = `${createHash('sha256').update(BINARY).digest('hex')}  ${ASSET}\n`;
+
+function keyPair(): { publicKey: string; privateKey: KeyObject } {
+  const { publicKey, privateKey } = generateKeyPairSync('ed25519');
+  return { publicKey: publicKey.export({ type: 'spki', format: 'pem' }).toString(), privateKey };
+}
+
+function signature(privateKey: KeyObject, checksums = CHECKSUMS): string {
+  return sign(null, Buffer.from(checksums), privateKey).toString('base64');
+}
+
+describe('verifyReleaseBinary', () => {
+  let server: ReturnType<typeof Bun.serve>;
+  let assets: Record<string, string>;
+
+  beforeEach(() => {
+    assets = {};
+    server = Bun.serve({
+      port: 0,
+      hostname: '127.0.0.1',
+      fetch: request => {
+        const body = assets[new URL(request.url).pathname.slice(1)];
+        return body === undefined ? new Response('not found', { status: 404 }) : new Response(body);
+      },
+    });
+  });
+
+  afterEach(() => {
+    server.stop(true);
+    delete process.env.PAF_UPDATE_PUBLIC_KEY;
+  });
+
+  function release(): ReleaseInfo {
+    return {
+      version: '9.9.9',
+      url: 'https://example.test/releases/v9.9.9',
+      assets: Object.keys(assets).map(name => ({ name, url: `http://127.0.0.1:${server.port}/${name}` })),
+    };
+  }
+
+  test('accepts a binary whose checksums carry a valid signature', async () => {
+    const { publicKey, privateKey } = keyPair();
+    process.env.PAF_UPDATE_PUBLIC_KEY = publicKey;
+    assets = { SHA256SUMS: CHECKSUMS, 'SHA256SUMS.sig': signature(privateKey) };
+
+    await verifyReleaseBinary(release(), ASSET, BINARY);
+    await expect(verifyReleaseBinary(release(), ASSET, new TextEncoder().encode('tampered'))).rejects.toThrow(
+      'Checksum mismatch'
+    );
+  });
+
+  test('refuses unsigned releases', async () => {
+    assets = { SHA256SUMS: CHECKSUMS };
+
+    await expect(verifyReleaseBinary(release(), ASSET, BINARY)).rejects.toThrow('refusing an unsigned binary');
+  });
+
+  test('refuses checksums signed by another key', async () => {
+    const { publicKey } = keyPair();
+    const other = keyPair();
+    assets = { SHA256SUMS: CHECKSUMS, 'SHA256SUMS.sig': signature(other.privateKey) };
+
+    // Without PAF_UPDATE_PUBLIC_KEY the embedded release key decides
+    await expect(verifyReleaseBinary(release(), ASSET, BINARY)).rejects.toThrow('not a valid signature');
+    process.env.PAF_UPDATE_PUBLIC_KEY = publicKey;
+    await expect(verifyReleaseBinary(release(), ASSET, BINARY)).rejects.toThrow('not a valid signature');
+  });
+});
+
+describe('installRelease', () => {
+  const posixOnly = test.skipIf(process.platform === 'win32');
+
+  posixOnly('replaces the binary in place and keeps the previous one', async () => {
+    const dir = mkdtempSync(join(tmpdir(), 'paf-update-'));
+    const executable = join(dir, 'paf');
+    writeFileSync(executable, 'old paf binary');
+    const { publicKey, privateKey } = keyPair();
+    process.env.PAF_UPDATE_PUBLIC_KEY = publicKey;
+    const assets: Record<string, string | Uint8Array> = {
+      [ASSET]: BINARY,
+      SHA256SUMS: CHECKSUMS,
+      'SHA256SUMS.sig': signature(privateKey),
+    };
+    const server = Bun.serve({
+      port: 0,
+      hostname: '127.0.0.1',
+      fetch: request => new Response(assets[new URL(request.url).pathname.slice(1)]),
+    });
+
+    try {
+      await installRelease(
+        {
+          version: '9.9.9',
+          url: 'https://example.test/releases/v9.9.9',
+          assets: Object.keys(assets).map(name => ({ name, url: `http://127.0.0.1:${server.port}/${name}` })),
+        },
+        executable
+      );
+
+      expect(readFileSync(executable, 'utf8')).toBe('new paf binary');
+      expect(readFileSync(`${executable}.old`, 'utf8')).toBe('old paf binary');
+      expect(existsSync(`${executable}.new`)).toBe(false);
+    } finally {
+      server.stop(true);
+      delete process.env.PAF_UPDATE_PUBLIC_KEY;
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+});
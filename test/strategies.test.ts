@@ -0,0 +1,177 @@
+import { describe, expect, test } from 'bun:test';
+import type { LoadBalancerConfig, ProxyConfig } from '../server/config/types';
+import { LoadBalancer } from '../server/routing/loadbalancer';
+import {
+  createSelectionStrategy,
+  registerSelectionStrategy,
+  selectionStrategyNames,
+  type SelectionInput,
+  type SelectionResult,
+  type SelectionStrategy,
+} from '../server/routing/strategies';
+
+function server(name: string, overrides: Partial<ProxyConfig> = {}): ProxyConfig {
+  return { name, baseUrl: `https://${name}.example.com`, weight: 1, enabled: true, ...overrides };
+}
+
+function balancerConfig(strategy: string): LoadBalancerConfig {
+  return {
+    strategy,
+    healthCheck: { enabled: false, interval: 30000, timeout: 5000, failureThreshold: 2, successThreshold: 1 },
+    freezeDuration: 300000,
+  };
+}
+
+/**
+ * Picks the candidate with the lowest known latency and records every input it was given
+ */
+class FastestStrategy implements SelectionStrategy {
+  readonly name = 'test-fastest';
+  inputs: SelectionInput[] = [];
+
+  select(input: SelectionInput): SelectionResult | null {
+    this.inputs.push(input);
+    const [fastest] = [...input.candidates].sort(
+      (a, b) => (a.latencyMs ?? Infinity) - (b.latencyMs ?? Infinity)
+    );
+    return fastest ? { config: fastest.config, reason: `fastest (${fastest.latencyMs} ms)` } : null;
+  }
+}
+
+const instances: FastestStrategy[] = [];
+registerSelectionStrategy('test-fastest', () => {
+  const strategy = new FastestStrategy();
+  instances.push(strategy);
+  return strategy;
+});
+
+const LATENCIES: Record<string, number> = { a: 300, b: 120, c: 80 };
+
+function fastestBalancer(): LoadBalancer {
+  const balancer = new LoadBalancer(balancerConfig('test-fastest'));
+  balancer.setLatencySource(config => LATENCIES[config.name]);
+  return balancer;
+}
+
+// Strategies are created on a balancer's first selection
+function latestStrategy(): FastestStrategy {
+  return instances[instances.length - 1];
+}
+
+describe('selection strategy registry', () => {
+  test('lists registered strategies next to the built-ins', () => {
+    expect(selectionStrategyNames()).toEqual(expect.arrayContaining(['weighted', 'round-robin', 'test-fastest']));
+    expect(createSelectionStrategy('test-fastest')?.name).toBe('test-fastest');
+    expect(createSelectionStrategy('missing')).toBeNull();
+  });
+
+  test('refuses to replace a built-in strategy', () => {
+    expect(() => registerSelectionStrategy('weighted', () => new FastestStrategy())).toThrow(
+      'Cannot replace built-in selection strategy'
+    );
+  });
+});
+
+describe('custom selection strategy', () => {
+  test('chooses among candidates and reports its reason', () => {
+    const balancer = fastestBalancer();
+    const result = balancer.select([server('a'), server('b'), server('c')]);
+
+    expect(result?.config.name).toBe('c');
+    expect(result?.reason).toBe('fastest (80 ms)');
+    expect(balancer.getCurrentServerName()).toBe('c');
+  });
+
+  test('only sees configs left after exclusions', () => {
+    const balancer = fastestBalancer();
+    balancer.select([
+      server('a'),
+      server('b', { freezeUntil: Date.now() + 60000 }),
+      server('c', { enabled: false }),
+    ]);
+
+    const [input] = latestStrategy().inputs;
+    expect(input.candidates.map(candidate => candidate.config.name)).toEqual(['a']);
+  });
+
+  test('receives health, the current config and the request', () => {
+    const balancer = fastestBalancer();
+    const servers = [server('a'), server('b')];
+    balancer.markFailure('b');
+    balancer.markFailure('b');
+    balancer.markSuccess('a');
+
+    balancer.select(servers, { service: 'claude', model: 'claude-sonnet-4', path: '/v1/messages', tags: { team: 'x' } });
+
+    const [input] = latestStrategy().inputs;
+    expect(input.current).toBe('a');
+    expect(input.request).toEqual({ service: 'claude', model: 'claude-sonnet-4', path: '/v1/messages', tags: { team: 'x' } });
+    expect(input.candidates).toMatchObject([
+      { config: { name: 'a' }, healthy: true, consecutiveFailures: 0, overFailureThreshold: false, latencyMs: 300 },
+      { config: { name: 'b' }, healthy: false, consecutiveFailures: 2, overFailureThreshold: true, latencyMs: 120 },
+    ]);
+  });
+
+  test('is bypassed while a config is pinned', () => {
+    const balancer = fastestBalancer();
+    balancer.pinServer('a', 60000);
+    const created = instances.length;
+
+    const result = balancer.select([server('a'), server('c')]);
+
+    expect(result).toEqual({ config: server('a'), reason: 'pinned' });
+    expect(instances).toHaveLength(created);
+  });
+
+  test('a config that was not a candidate is ignored', () => {
+    registerSelectionStrategy('test-outsider', () => ({
+      name: 'test-outsider',
+      select: () => ({ config: server('elsewhere'), reason: 'not offered' }),
+    }));
+    const balancer = new LoadBalancer(balancerConfig('test-outsider'));
+
+    expect(balancer.select([server('a')])).toBeNull();
+  });
+
+  test('each load balancer gets its own instance, kept between calls', () => {
+    const created = instances.length;
+    const first = fastestBalancer();
+    const second = fastestBalancer();
+    first.select([server('a')]);
+    second.select([server('a')]);
+    first.select([server('a')]);
+
+    expect(instances).toHaveLength(created + 2);
+    expect(instances[created].inputs).toHaveLength(2);
+    expect(instances[created + 1].inputs).toHaveLength(1);
+  });
+
+  test('an unregistered name falls back to weighted', () => {
+    const balancer = new LoadBalancer(balancerConfig('never-registered'));
+    const result = balancer.select([server('low', { weight: 1 }), server('high', { weight: 5 })]);
+
+    expect(result?.config.name).toBe('high');
+    expect(result?.reason).toBe('highest healthy weight (5)');
+  });
+});
+
+describe('built-in strategies', () => {
+  test('weighted stays on the current config until it fails', () => {
+    const balancer = new LoadBalancer(balancerConfig('weighted'));
+    const servers = [server('a', { weight: 2 }), server('b', { weight: 1 })];
+
+    expect(balancer.selectServer(servers)?.name).toBe('a');
+    balancer.markFailure('a');
+    expect(balancer.selectServer(servers)?.name).toBe('a');
+    balancer.markFailure('a');
+    expect(balancer.selectServer(servers)?.name).toBe('b');
+  });
+
+  test('round-robin rotates through the candidates', () => {
+    const balancer = new LoadBalancer(balancerConfig('round-robin'));
+    const servers = [server('a'), server('b'), server('c')];
+
+    const picks = [1, 2, 3, 4].map(() => balancer.selectServer(servers)?.name);
+    expect(picks).toEqual(['a', 'b', 'c', 'a']);
+  });
+});
@@ -0,0 +1,53 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { budgetPeriodStart, TenantBudget } from '../server/tenancy/budget';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+const REPLY = { id: 'msg_1', type: 'message', content: [], usage: { input_tokens: 3, output_tokens: 5 } };
+
+describe('budgetPeriodStart', () => {
+  test('starts UTC days and months', () => {
+    const now = Date.UTC(2026, 9, 17, 23, 30);
+    expect(budgetPeriodStart('day', now)).toBe(Date.UTC(2026, 9, 17));
+    expect(budgetPeriodStart('month', now)).toBe(Date.UTC(2026, 9, 1));
+  });
+});
+
+describe('TenantBudget', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('counts logged tokens until the allowance is spent', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary', fallback: { json: REPLY } }] });
+    const budget = new TenantBudget({ tokens: 10, period: 'day' }, harness.proxy.logger);
+
+    await harness.request('/v1/messages', { body: BODY });
+    await harness.waitForLogs(1);
+    expect(budget.status()).toMatchObject({ used: 8, remaining: 2, exhausted: false });
+    expect(budget.exhausted()).toBeNull();
+
+    await harness.request('/v1/messages', { body: BODY });
+    await harness.waitForLogs(2);
+    expect(budget.exhausted()).toMatchObject({ limit: 10, remaining: 0 });
+
+    // A restart picks the count back up from the request log
+    expect(new TenantBudget({ tokens: 100, period: 'day' }, harness.proxy.logger).status().used).toBe(16);
+  });
+
+  test('starts over when the period turns', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary', fallback: { json: REPLY } }] });
+    let now = Date.now();
+    const budget = new TenantBudget({ tokens: 8, period: 'day' }, harness.proxy.logger, () => now);
+
+    await harness.request('/v1/messages', { body: BODY });
+    await harness.waitForLogs(1);
+    expect(budget.status().exhausted).toBe(true);
+
+    now = budgetPeriodStart('day', now) + 24 * 60 * 60 * 1000;
+    expect(budget.status()).toMatchObject({ used: 0, remaining: 8, resetsAt: now + 24 * 60 * 60 * 1000 });
+  });
+});
@@ -0,0 +1,125 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import type { ProxyConfig, ServiceConfig } from '../server/config/types';
+import { parseTimeoutSecs, UpstreamDeadline, upstreamTimeouts } from '../server/proxy/timeouts';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+describe('parseTimeoutSecs', () => {
+  test('accepts positive finite numbers only', () => {
+    expect(parseTimeoutSecs(30)).toBe(30);
+    expect(parseTimeoutSecs(0.5)).toBe(0.5);
+    expect(parseTimeoutSecs(0)).toBeUndefined();
+    expect(parseTimeoutSecs(-5)).toBeUndefined();
+    expect(parseTimeoutSecs('30')).toBeUndefined();
+    expect(parseTimeoutSecs(Infinity)).toBeUndefined();
+  });
+});
+
+describe('upstreamTimeouts', () => {
+  const config = { name: 'primary', baseUrl: 'https://a.example', weight: 1, enabled: true } as ProxyConfig;
+
+  test("falls back to the service's values one by one", () => {
+    const service = { timeoutSecs: 600, connectTimeoutSecs: 10 } as ServiceConfig;
+
+    expect(upstreamTimeouts(config)).toEqual({ totalMs: undefined, connectMs: undefined });
+    expect(upstreamTimeouts(config, service)).toEqual({ totalMs: 600_000, connectMs: 10_000 });
+    expect(upstreamTimeouts({ ...config, timeoutSecs: 30 }, service)).toEqual({ totalMs: 30_000, connectMs: 10_000 });
+  });
+});
+
+describe('UpstreamDeadline', () => {
+  test("leaves the client's signal and Bun's limit alone without timeouts", () => {
+    const client = new AbortController().signal;
+    const deadline = new UpstreamDeadline({}, 'primary');
+
+    expect(deadline.signal(client)).toBe(client);
+    expect(deadline.signal()).toBeUndefined();
+    expect(deadline.fetchTimeout()).toBeUndefined();
+  });
+
+  test('aborts when no response headers arrive in time', async () => {
+    const deadline = new UpstreamDeadline({ connectMs: 20 }, 'primary');
+    const signal = deadline.signal()!;
+
+    expect(deadline.fetchTimeout()).toBe(false);
+    await Bun.sleep(50);
+    expect(signal.aborted).toBe(true);
+    expect(signal.reason).toMatchObject({ name: 'TimeoutError' });
+    expect(String(signal.reason.message)).toContain('primary timed out: no response headers within 0.02s (connect_timeout_secs)');
+  });
+
+  test('keeps only timeout_secs running once headers arrived', async () => {
+    const deadline = new UpstreamDeadline({ connectMs: 20, totalMs: 60 }, 'primary');
+    const signal = deadline.signal()!;
+    deadline.responded();
+
+    await Bun.sleep(40);
+    expect(signal.aborted).toBe(false);
+    await Bun.sleep(40);
+    expect(String(signal.reason?.message)).toContain('timeout_secs');
+  });
+
+  test('follows the client abort and stops on clear', async () => {
+    const client = new AbortController();
+    const deadline = new UpstreamDeadline({ totalMs: 20 }, 'primary');
+    const signal = deadline.signal(client.signal)!;
+    deadline.clear();
+
+    await Bun.sleep(40);
+    expect(signal.aborted).toBe(false);
+    client.abort();
+    expect(signal.aborted).toBe(true);
+  });
+});
+
+describe('timeout_secs', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('gives up on a config that answers too slowly and freezes it', async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      loadBalancer: { strategy: 'round-robin' },
+      configs: [{ name: 'slow', fallback: { json: { id: 'late' }, delayMs: 500 } }, { name: 'fast' }],
+    });
+    const { configManager } = harness.proxy;
+    const service = configManager.getServiceConfig('claude')!;
+    await configManager.saveServiceConfig('claude', {
+      ...service,
+      configs: service.configs.map(config => (config.name === 'slow' ? { ...config, connectTimeoutSecs: 0.05 } : config)),
+    });
+
+    const started = Date.now();
+    const timedOut = await harness.request('/v1/messages', { body: BODY });
+
+    expect(timedOut.status).toBe(502);
+    expect(Date.now() - started).toBeLessThan(450);
+    expect(await timedOut.json()).toMatchObject({
+      error: { message: 'slow timed out: no response headers within 0.05s (connect_timeout_secs)' },
+    });
+
+    // Frozen after the failure, so the next requests skip it
+    await harness.request('/v1/messages', { body: BODY });
+    await harness.request('/v1/messages', { body: BODY });
+    const logs = await harness.waitForLogs(3);
+    expect(logs.map(log => log.configName)).toEqual(['slow', 'fast', 'fast']);
+  });
+
+  test("applies the service's value to a single config", async () => {
+    harness = await createTestHarness({
+      serviceConfig: { timeoutSecs: 0.05 },
+      configs: [{ name: 'slow', fallback: { json: { id: 'late' }, delayMs: 500 } }],
+    });
+
+    const response = await harness.request('/v1/messages', { body: BODY });
+
+    expect(response.status).toBeGreaterThanOrEqual(500);
+    const [log] = await harness.waitForLogs(1);
+    expect(log.error).toContain('slow timed out after 0.05s (timeout_secs)');
+  });
+});
@@ -0,0 +1,200 @@
+import { describe, expect, test } from 'bun:test';
+import {
+  chatCompletionToMessage,
+  createChatStreamTranslator,
+  messagesToChatCompletions,
+} from '../server/proxy/translation';
+
+/**
+ * Push `chunks` through `transform` as separate writes and return the events that come out
+ */
+async function translateStream(transform: TransformStream<Uint8Array, Uint8Array>, chunks: string[]): Promise<any[]> {
+  const encoder = new TextEncoder();
+  const source = new ReadableStream<Uint8Array>({
+    start(controller) {
+      chunks.forEach(chunk => controller.enqueue(encoder.encode(chunk)));
+      controller.close();
+    },
+  });
+  const text = await new Response(source.pipeThrough(transform)).text();
+  return text
+    .split('\n\n')
+    .filter(Boolean)
+    .map(event => {
+      const data = event.split('\n').find(line => line.startsWith('data: '))!.slice(6);
+      return data === '[DONE]' ? data : JSON.parse(data);
+    });
+}
+
+function chatChunk(delta: unknown, extra: Record<string, unknown> = {}): string {
+  return `data: ${JSON.stringify({ id: 'chatcmpl-1', model: 'gpt-4o', choices: [{ index: 0, delta, finish_reason: null }], ...extra })}\n\n`;
+}
+
+describe('messagesToChatCompletions', () => {
+  test('turns tool use and tool results into tool calls and tool messages', () => {
+    const chat = messagesToChatCompletions({
+      model: 'claude-sonnet-4-5',
+      max_tokens: 256,
+      system: [{ type: 'text', text: 'Be brief.' }],
+      messages: [
+        { role: 'user', content: 'Weather in Paris?' },
+        {
+          role: 'assistant',
+          content: [
+            { type: 'thinking', thinking: 'Look it up', signature: 'sig' },
+            { type: 'text', text: 'Checking.' },
+            { type: 'tool_use', id: 'toolu_1', name: 'get_weather', input: { city: 'Paris' } },
+          ],
+        },
+        {
+          role: 'user',
+          content: [
+            { type: 'tool_result', tool_use_id: 'toolu_1', content: [{ type: 'text', text: '18°C' }] },
+            { type: 'text', text: 'And tomorrow?' },
+          ],
+        },
+      ],
+      tools: [
+        { name: 'get_weather', description: 'Current weather', input_schema: { type: 'object' } },
+        { type: 'web_search_20250305', name: 'web_search' },
+      ],
+      tool_choice: { type: 'any', disable_parallel_tool_use: true },
+    });
+
+    expect(chat.messages).toEqual([
+      { role: 'system', content: 'Be brief.' },
+      { role: 'user', content: 'Weather in Paris?' },
+      {
+        role: 'assistant',
+        content: 'Checking.',
+        tool_calls: [{ id: 'toolu_1', type: 'function', function: { name: 'get_weather', arguments: '{"city":"Paris"}' } }],
+      },
+      { role: 'tool', tool_call_id: 'toolu_1', content: '18°C' },
+      { role: 'user', content: 'And tomorrow?' },
+    ]);
+    // Server tools have no Chat Completions equivalent
+    expect(chat.tools).toEqual([
+      { type: 'function', function: { name: 'get_weather', description: 'Current weather', parameters: { type: 'object' } } },
+    ]);
+    expect(chat.tool_choice).toBe('required');
+    expect(chat.parallel_tool_calls).toBe(false);
+    expect(chat.max_tokens).toBe(256);
+  });
+
+  test('sends images as image_url parts', () => {
+    const chat = messagesToChatCompletions({
+      model: 'claude-sonnet-4-5',
+      stream: true,
+      messages: [
+        {
+          role: 'user',
+          content: [
+            { type: 'text', text: 'Compare these' },
+            { type: 'image', source: { type: 'base64', media_type: 'image/png', data: 'iVBORw0KGgo=' } },
+            { type: 'image', source: { type: 'url', url: 'https://example.test/cat.jpg' } },
+          ],
+        },
+      ],
+    });
+
+    expect(chat.messages).toEqual([
+      {
+        role: 'user',
+        content: [
+          { type: 'text', text: 'Compare these' },
+          { type: 'image_url', image_url: { url: 'data:image/png;base64,iVBORw0KGgo=' } },
+          { type: 'image_url', image_url: { url: 'https://example.test/cat.jpg' } },
+        ],
+      },
+    ]);
+    expect(chat.stream_options).toEqual({ include_usage: true });
+  });
+});
+
+describe('chatCompletionToMessage', () => {
+  test('maps tool calls, finish_reason and cached usage', () => {
+    const message = chatCompletionToMessage({
+      id: 'chatcmpl-1',
+      model: 'gpt-4o',
+      choices: [{
+        message: {
+          content: 'Checking.',
+          tool_calls: [{ id: 'call_1', function: { name: 'get_weather', arguments: '{"city":"Paris"}' } }],
+        },
+        finish_reason: 'tool_calls',
+      }],
+      usage: { prompt_tokens: 120, completion_tokens: 9, prompt_tokens_details: { cached_tokens: 100 } },
+    });
+
+    expect(message).toMatchObject({
+      id: 'msg_chatcmpl-1',
+      model: 'gpt-4o',
+      content: [
+        { type: 'text', text: 'Checking.' },
+        { type: 'tool_use', id: 'call_1', name: 'get_weather', input: { city: 'Paris' } },
+      ],
+      stop_reason: 'tool_use',
+      usage: { input_tokens: 20, output_tokens: 9, cache_read_input_tokens: 100 },
+    });
+  });
+});
+
+describe('createChatStreamTranslator', () => {
+  test('streams text and tool calls as Messages events, ending with the usage that follows finish_reason', async () => {
+    const stream = [
+      chatChunk({ role: 'assistant', content: 'Check' }),
+      chatChunk({ content: 'ing.' }),
+      chatChunk({ tool_calls: [{ index: 0, id: 'call_1', function: { name: 'get_weather', arguments: '{"ci' } }] }),
+      chatChunk({ tool_calls: [{ index: 0, function: { arguments: 'ty":"Paris"}' } }] }),
+      `data: ${JSON.stringify({ id: 'chatcmpl-1', choices: [{ index: 0, delta: {}, finish_reason: 'tool_calls' }] })}\n\n`,
+      `data: ${JSON.stringify({ id: 'chatcmpl-1', choices: [], usage: { prompt_tokens: 50, completion_tokens: 12 } })}\n\n`,
+      'data: [DONE]\n\n',
+    ].join('');
+    // Split mid-event, as network reads do
+    const events = await translateStream(createChatStreamTranslator('claude-sonnet-4-5'), [
+      stream.slice(0, 70),
+      stream.slice(70, 400),
+      stream.slice(400),
+    ]);
+
+    expect(events.map(event => event.type)).toEqual([
+      'message_start',
+      'content_block_start',
+      'content_block_delta',
+      'content_block_delta',
+      'content_block_stop',
+      'content_block_start',
+      'content_block_delta',
+      'content_block_delta',
+      'content_block_stop',
+      'message_delta',
+      'message_stop',
+    ]);
+    expect(events[0].message).toMatchObject({ id: 'msg_chatcmpl-1', model: 'gpt-4o', role: 'assistant' });
+    expect(events[5]).toMatchObject({
+      index: 1,
+      content_block: { type: 'tool_use', id: 'call_1', name: 'get_weather', input: {} },
+    });
+    expect(events.slice(6, 8).map(event => event.delta.partial_json).join('')).toBe('{"city":"Paris"}');
+    expect(events[9]).toMatchObject({
+      delta: { stop_reason: 'tool_use' },
+      usage: { input_tokens: 50, output_tokens: 12 },
+    });
+  });
+
+  test('maps length to max_tokens and ends a stream closed without [DONE]', async () => {
+    const events = await translateStream(createChatStreamTranslator(), [
+      chatChunk({ content: 'Once upon' }),
+      `data: ${JSON.stringify({ id: 'chatcmpl-2', choices: [{ index: 0, delta: {}, finish_reason: 'length' }] })}`,
+    ]);
+
+    expect(events.at(-2)).toMatchObject({ type: 'message_delta', delta: { stop_reason: 'max_tokens' } });
+    expect(events.at(-1)).toEqual({ type: 'message_stop' });
+  });
+
+  test('never ends a stream that was cut before finish_reason', async () => {
+    const events = await translateStream(createChatStreamTranslator(), [chatChunk({ content: 'Once upon' })]);
+
+    expect(events.map(event => event.type)).toEqual(['message_start', 'content_block_start', 'content_block_delta']);
+  });
+});
@@ -0,0 +1,91 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { createProxyCore } from '../server/core';
+import { BodyMemoryBudget } from '../server/proxy/memoryBudget';
+import { StreamUsageTap } from '../server/proxy/usageTap';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const LONG_TEXT = 'x'.repeat(4096);
+
+const STREAM = [
+  { type: 'message_start', message: { id: 'msg_big', type: 'message', role: 'assistant', model: 'claude-sonnet-4-5', content: [], usage: { input_tokens: 11, output_tokens: 1 } } },
+  { type: 'content_block_start', index: 0, content_block: { type: 'text', text: '' } },
+  { type: 'content_block_delta', index: 0, delta: { type: 'text_delta', text: LONG_TEXT } },
+  { type: 'content_block_stop', index: 0 },
+  { type: 'message_delta', delta: { stop_reason: 'end_turn' }, usage: { output_tokens: 1024 } },
+  { type: 'message_stop' },
+];
+
+function sse(events: unknown[]): string {
+  return events.map(event => `data: ${JSON.stringify(event)}\n\n`).join('');
+}
+
+describe('StreamUsageTap', () => {
+  test('keeps slimmed usage events across chunk boundaries', () => {
+    const tap = new StreamUsageTap();
+    const text = sse(STREAM);
+    for (let offset = 0; offset < text.length; offset += 7) {
+      tap.push(text.slice(offset, offset + 7));
+    }
+
+    expect(tap.events()).toBe(sse([
+      { type: 'message_start', message: { id: 'msg_big', model: 'claude-sonnet-4-5', usage: { input_tokens: 11, output_tokens: 1 } } },
+      { type: 'message_delta', usage: { output_tokens: 1024 } },
+    ]));
+  });
+
+  test('drops the output embedded in Responses terminal events', () => {
+    const tap = new StreamUsageTap();
+    tap.push(sse([{
+      type: 'response.completed',
+      response: { id: 'resp_1', model: 'gpt-5', output: [{ content: LONG_TEXT }], usage: { input_tokens: 2, output_tokens: 3 } },
+    }]));
+
+    expect(tap.events()).not.toContain(LONG_TEXT);
+    expect(tap.events()).toContain('"response":{"id":"resp_1","model":"gpt-5","usage":{"input_tokens":2,"output_tokens":3}}');
+  });
+
+  test('reset forgets a failed attempt', () => {
+    const tap = new StreamUsageTap();
+    tap.push(sse(STREAM.slice(0, 1)));
+    tap.push('data: {"type":"message_delta","usage"');
+    tap.reset();
+    tap.push(':ok\n\n');
+
+    expect(tap.events()).toBe('');
+  });
+});
+
+describe('streams past the memory budget', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('are logged with their token usage and upstream id', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary', responses: [{ sse: STREAM }] }] });
+    const { configManager, logger } = harness.proxy;
+    // A budget too small for any capture, so the tap is the only source of usage
+    const core = createProxyCore(configManager, logger, undefined, undefined, undefined, undefined, new BodyMemoryBudget(256));
+
+    const response = await core.proxies.claude.handleRequest(
+      new Request('http://paf.test/v1/messages', {
+        method: 'POST',
+        headers: { 'content-type': 'application/json', accept: 'text/event-stream' },
+        body: JSON.stringify({ model: 'claude-sonnet-4-5', max_tokens: 2048, stream: true, messages: [{ role: 'user', content: 'Hi' }] }),
+      }),
+      configManager.getAllConfigs('claude')
+    );
+    expect(await response.text()).toContain(LONG_TEXT);
+
+    const [log] = await harness.waitForLogs(1);
+    expect(log).toMatchObject({
+      statusCode: 200,
+      inputTokens: 11,
+      outputTokens: 1024,
+      model: 'claude-sonnet-4-5',
+      upstreamId: 'msg_big',
+    });
+  });
+});
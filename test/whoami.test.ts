@@ -0,0 +1,56 @@
+import { afterEach, describe, expect, test } from 'bun:test';
+import { selectedConfig } from '../server/core';
+import { createTestHarness, type TestHarness } from '../server/testing';
+
+const BODY = { model: 'claude-sonnet-4-5', max_tokens: 16, messages: [{ role: 'user', content: 'Hi' }] };
+
+describe('selectedConfig', () => {
+  let harness: TestHarness | undefined;
+
+  afterEach(async () => {
+    await harness?.close();
+    harness = undefined;
+  });
+
+  test('reports the active config in manual mode', async () => {
+    harness = await createTestHarness({ configs: [{ name: 'primary' }, { name: 'backup' }] });
+    const { configManager } = harness.proxy;
+    await configManager.saveServiceConfig('claude', { ...configManager.getServiceConfig('claude')!, active: 'backup' });
+
+    expect(selectedConfig(harness.proxy, 'claude')).toMatchObject({ config: { name: 'backup' }, via: 'active' });
+
+    // Manual mode only routes to the active config, so a pin elsewhere changes nothing
+    harness.loadBalancer.pinServer('primary', 60_000);
+    expect(selectedConfig(harness.proxy, 'claude')).toMatchObject({ config: { name: 'backup' }, via: 'active' });
+  });
+
+  test('prefers a pin when load balancing', async () => {
+    harness = await createTestHarness({ mode: 'load_balance', configs: [{ name: 'primary' }, { name: 'backup' }] });
+    harness.loadBalancer.pinServer('backup', 60_000);
+
+    expect(selectedConfig(harness.proxy, 'claude')).toMatchObject({ config: { name: 'backup' }, via: 'pin' });
+  });
+
+  test("follows the balancer's choice without moving it", async () => {
+    harness = await createTestHarness({
+      mode: 'load_balance',
+      loadBalancer: { strategy: 'round-robin' },
+      configs: [{ name: 'first' }, { name: 'second' }],
+    });
+
+    expect(selectedConfig(harness.proxy, 'claude')).toBeNull();
+
+    await harness.request('/v1/messages', { body: BODY });
+    expect(selectedConfig(harness.proxy, 'claude')).toMatchObject({ config: { name: 'first' }, via: 'load_balancer' });
+    expect(selectedConfig(harness.proxy, 'claude')).toMatchObject({ config: { name: 'first' } });
+
+    await harness.request('/v1/messages', { body: BODY });
+    expect(selectedConfig(harness.proxy, 'claude')).toMatchObject({ config: { name: 'second' } });
+    expect(harness.upstreams.second.requests).toHaveLength(1);
+  });
+
+  test('is null without configs', async () => {
+    harness = await createTestHarness({ configs: [] });
+    expect(selectedConfig(harness.proxy, 'claude')).toBeNull();
+  });
+});